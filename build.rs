@@ -2,7 +2,9 @@ use std::path::Path;
 use std::process::Command;
 
 fn main() {
-    // Feature off → complete no-op so publishers / docs.rs / downstream need no Node.
+    emit_build_metadata();
+
+    // Feature off → skip the web-ui build, so publishers / docs.rs / downstream need no Node.
     if std::env::var_os("CARGO_FEATURE_WEB_UI").is_none() {
         return;
     }
@@ -25,3 +27,61 @@ fn main() {
         Err(e) => panic!("failed to run bun (is bun installed?): {e}"),
     }
 }
+
+/// Bake commit, build date and target into the binary for `cc-switch version`.
+///
+/// Outside a git checkout (e.g. crates.io installs) the commit is "unknown".
+fn emit_build_metadata() {
+    println!("cargo:rerun-if-changed=build.rs");
+    // Only trust git when this package is the checkout itself, not a crate
+    // unpacked inside some other repository
+    let in_checkout = Path::new(".git").exists();
+    if in_checkout {
+        println!("cargo:rerun-if-changed=.git/HEAD");
+        if let Some(head_ref) = git(&["symbolic-ref", "-q", "HEAD"]) {
+            println!("cargo:rerun-if-changed=.git/{head_ref}");
+        }
+    }
+
+    let commit = in_checkout
+        .then(|| git(&["rev-parse", "--short=12", "HEAD"]))
+        .flatten()
+        .unwrap_or_else(|| "unknown".into());
+    println!("cargo:rustc-env=CC_SWITCH_GIT_COMMIT={commit}");
+    println!("cargo:rustc-env=CC_SWITCH_BUILD_DATE={}", build_date());
+    let target = std::env::var("TARGET").unwrap_or_else(|_| "unknown".into());
+    println!("cargo:rustc-env=CC_SWITCH_TARGET={target}");
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// UTC build date as YYYY-MM-DD, honouring SOURCE_DATE_EPOCH for reproducible builds
+fn build_date() -> String {
+    let secs = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+        });
+    // Civil-from-days conversion (Howard Hinnant's algorithm)
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
@@ -2,8 +2,8 @@ use anyhow::{Context, Result};
 use std::collections::BTreeMap;
 use std::fs;
 
-use crate::config::types::{ClaudeSettings, Configuration, StorageMode};
-use crate::utils::get_claude_settings_path;
+use crate::config::types::{ANTHROPIC_ENV_VARS, ClaudeSettings, Configuration, StorageMode};
+use crate::utils::{get_claude_settings_path, write_verified};
 
 const PER_PID_ALIAS_PREFIX: &str = "cc_auto_switch_alias_";
 
@@ -130,11 +130,34 @@ impl ClaudeSettings {
         let json = serde_json::to_string_pretty(&settings_to_save)
             .with_context(|| "Failed to serialize Claude settings")?;
 
-        fs::write(&path, json).with_context(|| format!("Failed to write to {}", path.display()))?;
+        if self.skip_write_verification {
+            fs::write(&path, json)
+                .with_context(|| format!("Failed to write to {}", path.display()))?;
+        } else {
+            write_verified(&path, &json, |written| self.check_written(written))?;
+        }
 
         Ok(())
     }
 
+    /// Check that `written` (settings.json read back after a save) holds the
+    /// same `env` entries as `self`
+    ///
+    /// # Errors
+    /// Returns error if `written` doesn't parse or its `env` differs
+    pub fn check_written(&self, written: &str) -> Result<()> {
+        let stored: ClaudeSettings =
+            serde_json::from_str(written).with_context(|| "Saved Claude settings do not parse")?;
+        if stored.env != self.env {
+            anyhow::bail!(
+                "Saved Claude settings have {} env entries, expected {}",
+                stored.env.len(),
+                self.env.len()
+            );
+        }
+        Ok(())
+    }
+
     /// Switch to a specific API configuration
     ///
     /// Updates the environment variables with the provided configuration
@@ -269,9 +292,8 @@ impl ClaudeSettings {
         }
 
         // Remove all environment variables that can be set by configurations
-        let env_fields = Configuration::get_env_field_names();
-        for field in &env_fields {
-            self.env.remove(*field);
+        for field in ANTHROPIC_ENV_VARS {
+            self.env.remove(field);
         }
     }
 
@@ -447,13 +469,43 @@ impl ClaudeSettings {
     ///
     /// Removes `~/.claude/cc_auto_switch_alias_<PID>` if it exists.
     pub fn clear_current_alias_for_pid() -> Result<()> {
-        let pid = std::process::id();
+        Self::clear_alias_file_for_pid(std::process::id())?;
+        Ok(())
+    }
+
+    /// Remove the alias file of session `pid`, returning its path if one existed
+    ///
+    /// # Errors
+    /// Returns error if the file exists but cannot be removed
+    pub fn clear_alias_file_for_pid(pid: u32) -> Result<Option<std::path::PathBuf>> {
         let path = Self::get_current_alias_for_pid(pid)?;
-        if path.exists() {
-            fs::remove_file(&path)
-                .with_context(|| format!("Failed to remove {}", path.display()))?;
+        if !path.exists() {
+            return Ok(None);
         }
-        Ok(())
+        fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+        Ok(Some(path))
+    }
+
+    /// Copy settings.json to `settings.json.bak`, returning the backup path
+    ///
+    /// Returns `None` when there is no settings file to back up.
+    ///
+    /// # Errors
+    /// Returns error if the copy fails
+    pub fn backup(custom_dir: Option<&str>) -> Result<Option<std::path::PathBuf>> {
+        let path = get_claude_settings_path(custom_dir)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let backup = path.with_extension("json.bak");
+        fs::copy(&path, &backup).with_context(|| {
+            format!(
+                "Failed to back up {} to {}",
+                path.display(),
+                backup.display()
+            )
+        })?;
+        Ok(Some(backup))
     }
 
     /// Get the path to the per-PID alias file
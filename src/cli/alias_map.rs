@@ -0,0 +1,146 @@
+//! `cc-switch alias-map`: alias → base URL (and model) for scripts and other
+//! tools, never containing credentials.
+//!
+//! Output is built from [`PublicConfigView`] rather than by filtering the
+//! serialized configuration, so a field added to `Configuration` later
+//! can't show up here unless it is added to the view on purpose.
+
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::cli::display_utils::FieldMask;
+use crate::config::types::Configuration;
+
+/// Replacement for credential values found inside other fields
+pub const REDACTED: &str = "[redacted]";
+
+/// The credential-free part of a configuration
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PublicConfigView {
+    /// ANTHROPIC_BASE_URL
+    pub url: String,
+    /// ANTHROPIC_MODEL, if set
+    pub model: Option<String>,
+    /// Host of the base URL, naming who serves the configuration
+    pub provider: Option<String>,
+}
+
+impl PublicConfigView {
+    /// View of `config` with hidden fields masked and any token or API key
+    /// value scrubbed from the fields it carries (e.g. a key in a URL query)
+    pub fn new(config: &Configuration, mask: &FieldMask) -> Self {
+        let secrets: Vec<&str> = [Some(config.token.as_str()), config.api_key.as_deref()]
+            .into_iter()
+            .flatten()
+            .filter(|secret| !secret.is_empty())
+            .collect();
+        let scrub = |value: &str| {
+            secrets.iter().fold(value.to_string(), |value, secret| {
+                value.replace(secret, REDACTED)
+            })
+        };
+
+        let provider = url::Url::parse(&config.url)
+            .ok()
+            .and_then(|url| url.host_str().map(&scrub));
+        PublicConfigView {
+            url: mask.apply("url", scrub(&config.url)),
+            model: config
+                .model
+                .as_deref()
+                .map(|model| mask.apply("model", scrub(model))),
+            provider: provider.map(|provider| mask.apply("url", provider)),
+        }
+    }
+}
+
+/// Public views of `configs` keyed by alias
+pub fn public_views(
+    configs: &BTreeMap<String, Configuration>,
+    mask: &FieldMask,
+) -> BTreeMap<String, PublicConfigView> {
+    configs
+        .iter()
+        .map(|(alias, config)| (alias.clone(), PublicConfigView::new(config, mask)))
+        .collect()
+}
+
+/// `alias<TAB>url<TAB>model` lines; the model column is empty when unset
+pub fn format_alias_map_lines(views: &BTreeMap<String, PublicConfigView>) -> Vec<String> {
+    views
+        .iter()
+        .map(|(alias, view)| {
+            format!(
+                "{}\t{}\t{}",
+                alias,
+                view.url,
+                view.model.as_deref().unwrap_or("")
+            )
+        })
+        .collect()
+}
+
+/// The views as a JSON object keyed by alias (`--json`)
+///
+/// # Errors
+/// Returns error if serialization fails
+pub fn format_alias_map_json(views: &BTreeMap<String, PublicConfigView>) -> Result<String> {
+    serde_json::to_string_pretty(views).map_err(|e| anyhow!("Failed to serialize alias map: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::types::ConfigStorage;
+
+    const TOKEN: &str = "sk-ant-secret-0123456789";
+    const API_KEY: &str = "sk-key-9876543210";
+
+    fn configs() -> BTreeMap<String, Configuration> {
+        let mut storage = ConfigStorage::default();
+        storage.add_configuration(Configuration {
+            alias_name: "relay".to_string(),
+            token: TOKEN.to_string(),
+            url: format!("https://relay.example.com/v1?key={TOKEN}"),
+            model: Some(format!("opus-{TOKEN}")),
+            tags: vec![TOKEN.to_string()],
+            ..Default::default()
+        });
+        storage.add_configuration(Configuration {
+            alias_name: "work".to_string(),
+            api_key: Some(API_KEY.to_string()),
+            url: "https://api.work.example".to_string(),
+            ..Default::default()
+        });
+        storage.configurations
+    }
+
+    #[test]
+    fn output_never_contains_credentials() {
+        let views = public_views(&configs(), &FieldMask::default());
+        let json = format_alias_map_json(&views).unwrap();
+        let lines = format_alias_map_lines(&views).join("\n");
+        for output in [&json, &lines] {
+            assert!(!output.contains(TOKEN), "token leaked: {output}");
+            assert!(!output.contains(API_KEY), "api key leaked: {output}");
+        }
+        assert!(
+            lines.contains("relay\thttps://relay.example.com/v1?key=[redacted]\topus-[redacted]")
+        );
+        assert!(lines.contains("work\thttps://api.work.example\t"));
+    }
+
+    #[test]
+    fn json_has_only_public_fields() {
+        let views = public_views(&configs(), &FieldMask::default());
+        let value: serde_json::Value =
+            serde_json::from_str(&format_alias_map_json(&views).unwrap()).unwrap();
+        let work = value["work"].as_object().unwrap();
+        let mut keys: Vec<&str> = work.keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, vec!["model", "provider", "url"]);
+        assert_eq!(work["provider"], "api.work.example");
+        assert!(work["model"].is_null());
+    }
+}
@@ -0,0 +1,250 @@
+//! `cc-switch backup` / `cc-switch restore`: the whole configuration store
+//! in one file, for moving to another machine.
+//!
+//! The file wraps the storage as saved on disk with a format marker, so a
+//! settings file or an `export` of a single configuration is rejected
+//! instead of being restored as an empty store.
+
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::config::types::ConfigStorage;
+use crate::config::validate_alias_name;
+
+/// Format version written to backup files
+const BACKUP_FORMAT: u32 = 1;
+
+/// Top level of a backup file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BackupFile {
+    cc_switch_backup: u32,
+    /// The storage file, migrated once the format is checked
+    storage: serde_json::Value,
+}
+
+/// How `restore` combines the backup with the current store
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreMode {
+    /// Add the backup's configurations, keeping everything else
+    Merge {
+        /// Overwrite configurations whose alias already exists
+        force: bool,
+    },
+    /// Replace the store, preferences included, with the backup
+    Replace,
+}
+
+/// Backup file contents for `storage`
+///
+/// # Errors
+/// Returns error if the storage cannot be serialized
+pub fn backup_json(storage: &ConfigStorage) -> Result<String> {
+    let backup = serde_json::json!({
+        "cc_switch_backup": BACKUP_FORMAT,
+        "storage": storage,
+    });
+    serde_json::to_string_pretty(&backup).context("Failed to serialize configuration storage")
+}
+
+/// Parse a backup file and check every alias in it
+///
+/// # Errors
+/// Returns error if `content` is not a cc-switch backup of a known format,
+/// or holds an invalid alias
+pub fn parse_backup(content: &str, path: &Path) -> Result<ConfigStorage> {
+    let backup: BackupFile = serde_json::from_str(content)
+        .map_err(|e| anyhow!("'{}' is not a cc-switch backup: {}", path.display(), e))?;
+    if backup.cc_switch_backup != BACKUP_FORMAT {
+        anyhow::bail!(
+            "'{}' has backup format {}, expected {}",
+            path.display(),
+            backup.cc_switch_backup,
+            BACKUP_FORMAT
+        );
+    }
+    let storage = crate::config::migrations::migrate(backup.storage)
+        .with_context(|| format!("'{}' holds no readable storage", path.display()))?;
+    for (alias, config) in &storage.configurations {
+        validate_alias_name(alias)
+            .with_context(|| format!("Backup '{}' holds an invalid alias", path.display()))?;
+        if config.alias_name != *alias {
+            anyhow::bail!(
+                "Backup '{}' stores configuration '{}' under alias '{}'",
+                path.display(),
+                config.alias_name,
+                alias
+            );
+        }
+    }
+    Ok(storage)
+}
+
+/// Apply `backup` to `storage` according to `mode`
+///
+/// Merging also takes the backup's default alias when none is set.
+/// Returns one summary line per alias.
+pub fn restore_into(
+    storage: &mut ConfigStorage,
+    backup: ConfigStorage,
+    mode: RestoreMode,
+) -> Vec<String> {
+    let mut summary = Vec::new();
+    match mode {
+        RestoreMode::Replace => {
+            for alias in storage.configurations.keys() {
+                if !backup.configurations.contains_key(alias) {
+                    summary.push(format!("  - {alias} (removed)"));
+                }
+            }
+            for alias in backup.configurations.keys() {
+                summary.push(format!("  + {alias} (restored)"));
+            }
+            *storage = backup;
+        }
+        RestoreMode::Merge { force } => {
+            let default_alias = backup.default_alias;
+            for (alias, config) in backup.configurations {
+                let line = match storage.get_configuration(&alias) {
+                    Some(_) if !force => {
+                        summary.push(format!("  = {alias} (skipped, already exists)"));
+                        continue;
+                    }
+                    Some(_) => format!("  ~ {alias} (overwritten)"),
+                    None => format!("  + {alias} (added)"),
+                };
+                summary.push(line);
+                storage.add_configuration(config);
+            }
+            if storage.default_alias.is_none()
+                && let Some(alias) = default_alias
+                && storage.get_configuration(&alias).is_some()
+            {
+                storage.default_alias = Some(alias);
+            }
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::types::Configuration;
+
+    fn config(alias: &str, url: &str) -> Configuration {
+        Configuration {
+            alias_name: alias.to_string(),
+            token: "sk-test".to_string(),
+            url: url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn storage_with(configs: impl IntoIterator<Item = Configuration>) -> ConfigStorage {
+        let mut storage = ConfigStorage::default();
+        for config in configs {
+            storage.add_configuration(config);
+        }
+        storage
+    }
+
+    fn url_of<'a>(storage: &'a ConfigStorage, alias: &str) -> &'a str {
+        &storage.get_configuration(alias).unwrap().url
+    }
+
+    #[test]
+    fn backup_round_trips_optional_fields_and_settings() {
+        let mut storage = storage_with([config("work", "https://work.example.com")]);
+        let mut relay = config("relay", "https://relay.example.com");
+        relay.api_key = Some("sk-key".to_string());
+        relay.token = String::new();
+        relay.model = Some("opus".to_string());
+        relay.max_thinking_tokens = Some(8192);
+        relay.tags = vec!["prod".to_string()];
+        relay.color = Some("red".to_string());
+        relay
+            .url_variants
+            .insert("eu".to_string(), "https://eu.example.com".to_string());
+        storage.add_configuration(relay);
+        storage.default_alias = Some("relay".to_string());
+        storage.preferences.hidden_fields = vec!["token".to_string()];
+
+        let json = backup_json(&storage).unwrap();
+        let restored = parse_backup(&json, Path::new("backup.json")).unwrap();
+        assert_eq!(
+            serde_json::to_value(&restored.configurations).unwrap(),
+            serde_json::to_value(&storage.configurations).unwrap()
+        );
+        assert_eq!(restored.default_alias.as_deref(), Some("relay"));
+        assert_eq!(restored.preferences, storage.preferences);
+    }
+
+    #[test]
+    fn foreign_or_invalid_files_are_rejected() {
+        let path = Path::new("backup.json");
+        let err = |content: &str| parse_backup(content, path).err().unwrap().to_string();
+
+        assert!(err(r#"{"env": {"ANTHROPIC_BASE_URL": "x"}}"#).contains("not a cc-switch backup"));
+        assert!(err(r#"{"configurations": {}}"#).contains("not a cc-switch backup"));
+        assert!(
+            err(r#"{"cc_switch_backup": 2, "storage": {"configurations": {}}}"#)
+                .contains("backup format 2")
+        );
+
+        let mut storage = storage_with([config("work", "https://work.example.com")]);
+        let bad = config("has space", "https://x.example.com");
+        storage.configurations.insert("has space".to_string(), bad);
+        let error = parse_backup(&backup_json(&storage).unwrap(), path)
+            .err()
+            .unwrap();
+        assert!(format!("{error:#}").contains("whitespace"));
+    }
+
+    #[test]
+    fn merge_keeps_existing_unless_forced_and_replace_takes_everything() {
+        let backup = || {
+            let mut backup = storage_with([
+                config("work", "https://new.example.com"),
+                config("relay", "https://relay.example.com"),
+            ]);
+            backup.default_alias = Some("relay".to_string());
+            backup
+        };
+        let current = || {
+            storage_with([
+                config("work", "https://old.example.com"),
+                config("home", "https://home.example.com"),
+            ])
+        };
+
+        let mut storage = current();
+        let summary = restore_into(&mut storage, backup(), RestoreMode::Merge { force: false });
+        assert_eq!(
+            summary,
+            vec!["  + relay (added)", "  = work (skipped, already exists)"]
+        );
+        assert_eq!(url_of(&storage, "work"), "https://old.example.com");
+        assert!(storage.get_configuration("home").is_some());
+        assert_eq!(storage.default_alias.as_deref(), Some("relay"));
+
+        let mut storage = current();
+        restore_into(&mut storage, backup(), RestoreMode::Merge { force: true });
+        assert_eq!(url_of(&storage, "work"), "https://new.example.com");
+        assert!(storage.get_configuration("home").is_some());
+
+        let mut storage = current();
+        let summary = restore_into(&mut storage, backup(), RestoreMode::Replace);
+        assert_eq!(
+            summary,
+            vec![
+                "  - home (removed)",
+                "  + relay (restored)",
+                "  + work (restored)"
+            ]
+        );
+        assert!(storage.get_configuration("home").is_none());
+        assert_eq!(url_of(&storage, "work"), "https://new.example.com");
+    }
+}
@@ -1,5 +1,7 @@
 use clap::{Parser, Subcommand};
 
+use crate::cli::output::OutputFormat;
+
 /// Command-line interface for managing Claude API configurations
 #[derive(Parser)]
 #[command(name = "cc-switch")]
@@ -19,7 +21,11 @@ EXAMPLES:
     cc-switch add my-config --from-file              # Import from ~/.claude/settings.json
     cc-switch add my-config --from-file ./other.json # Import from an explicit JSON file
     cc-switch add my-config --force  # Overwrite existing config
+    cc-switch add prod-relay -t sk-xxx -u https://relay.example --tag prod
     cc-switch list
+    cc-switch list --plain --tag prod
+    cc-switch --tag prod  # Interactive mode listing only configurations tagged prod
+    cc-switch config set hidden-fields token,url  # Hide values in all output
     cc-switch remove config1 config2 config3
     cc-switch current  # Interactive mode to view and switch configurations
     cc-switch  # Enter interactive mode (same as 'current' without arguments)
@@ -33,7 +39,8 @@ CODEX CONFIGURATIONS:
     cc-switch codex remove work
 
 SHELL COMPLETION AND ALIASES:
-    cc-switch completion fish  # Generates shell completions
+    cc-switch completion fish  # Prints shell completions
+    cc-switch completion fish --install  # Writes them where fish loads them
 
     Recommended aliases:
     - cs='cc-switch'           # Quick access to cc-switch
@@ -56,17 +63,34 @@ pub struct Cli {
     #[arg(long = "list-aliases", hide = true)]
     pub list_aliases: bool,
 
+    /// List stored configuration aliases only, without `cc`/`official` (for shell completion)
+    #[arg(long = "list-stored-aliases", hide = true)]
+    pub list_stored_aliases: bool,
+
     /// List available Codex configuration aliases (for shell completion)
     #[arg(long = "list-codex-aliases", hide = true)]
     pub list_codex_aliases: bool,
 
-    /// Migrate old config path (~/.cc_auto_switch/configurations.json) to new path
+    /// Same as `cc-switch migrate`: copy the configurations of
+    /// ~/.cc_auto_switch/configurations.json into the current store
     #[arg(
         long = "migrate",
-        help = "Migrate old config path to new path and exit"
+        help = "Copy configurations from the old config path and exit"
     )]
     pub migrate: bool,
 
+    /// Configuration storage file to use instead of the default
+    ///
+    /// Takes precedence over CC_SWITCH_CONFIG, CC_SWITCH_CONFIG_DIR and
+    /// XDG_CONFIG_HOME.
+    #[arg(
+        long = "config",
+        alias = "config-path",
+        value_name = "PATH",
+        global = true
+    )]
+    pub config: Option<std::path::PathBuf>,
+
     /// Storage mode for writing configuration (env or config)
     #[arg(
         long = "store",
@@ -74,6 +98,73 @@ pub struct Cli {
         global = true
     )]
     pub store: Option<String>,
+
+    /// Storage mode for this launch only, overriding the stored default
+    ///
+    /// `env` passes the configuration to Claude as environment variables;
+    /// `settings` (or `config`) also writes it into settings.json. Applies
+    /// to `use`, `current` and the interactive menu.
+    #[arg(long = "mode", value_name = "MODE", global = true)]
+    pub mode: Option<crate::config::types::StorageMode>,
+
+    /// Show fields hidden by the `hidden-fields` preference
+    #[arg(
+        long = "show-hidden",
+        help = "Show values of fields hidden by the hidden-fields preference",
+        global = true
+    )]
+    pub show_hidden: bool,
+
+    /// Emit ANSI colors even when stdout is not a terminal
+    ///
+    /// Color precedence: --force-color, then NO_COLOR (disables), then
+    /// CLICOLOR_FORCE (enables), then CLICOLOR=0 (disables), then whether
+    /// stdout is a terminal.
+    #[arg(long = "force-color", global = true)]
+    pub force_color: bool,
+
+    /// Never emit ANSI colors, including in the interactive menus
+    ///
+    /// Same as setting NO_COLOR; the menu markers (●/○) are kept.
+    #[arg(long = "no-color", global = true, conflicts_with = "force_color")]
+    pub no_color: bool,
+
+    /// Print a single JSON document on stdout instead of text
+    ///
+    /// Applies to `list`, `current`, `add` and `remove`; their messages go
+    /// to stderr. Other commands print text as usual.
+    #[arg(
+        long = "format",
+        value_enum,
+        value_name = "FORMAT",
+        default_value_t = OutputFormat::Text,
+        global = true
+    )]
+    pub format: OutputFormat,
+
+    /// Launch Claude without --dangerously-skip-permissions
+    ///
+    /// Overrides the `skip-permissions` preference for this run.
+    #[arg(long = "no-skip-permissions", global = true)]
+    pub no_skip_permissions: bool,
+
+    /// Unset inherited ANTHROPIC_*, CLAUDE_CODE_* and API_TIMEOUT_MS
+    /// variables the selected configuration doesn't set
+    ///
+    /// Without it a launch only warns about them. Applies to `use` and the
+    /// interactive menu.
+    #[arg(long = "clear-env", global = true)]
+    pub clear_env: bool,
+
+    /// Only list configurations with this tag in the interactive menu
+    /// (`cc-switch --tag work`); the default configuration is not launched
+    #[arg(long = "tag", value_name = "TAG")]
+    pub tag: Option<String>,
+
+    /// Arguments after `--`, passed through to Claude when launching from
+    /// the interactive menu (`cc-switch -- --model opus`)
+    #[arg(last = true, value_name = "CLAUDE_ARGS")]
+    pub claude_args: Vec<String>,
 }
 
 /// Available subcommands for configuration management
@@ -85,8 +176,14 @@ pub enum Commands {
     /// Stores a new configuration with alias, API token, base URL, and optional model settings
     Add {
         /// Configuration alias name (used to identify this config)
-        #[arg(help = "Configuration alias name (cannot be 'cc')")]
-        alias_name: String,
+        ///
+        /// Not given with `--from-file`, which names the configuration after
+        /// the file (`work.json` adds `work`).
+        #[arg(
+            required_unless_present = "from_file",
+            help = "Configuration alias name (cannot be 'cc'; omitted with --from-file)"
+        )]
+        alias_name: Option<String>,
 
         /// ANTHROPIC_AUTH_TOKEN value (your Claude API token)
         #[arg(
@@ -104,6 +201,16 @@ pub enum Commands {
         )]
         api_key: Option<String>,
 
+        /// Command printing the ANTHROPIC_AUTH_TOKEN value, run through the
+        /// shell at each switch so the token is never stored
+        #[arg(
+            long = "token-command",
+            value_name = "COMMAND",
+            conflicts_with_all = ["token", "api_key", "token_arg"],
+            help = "Command printing the API token at each switch (e.g. \"pass show claude/work\"), instead of storing it"
+        )]
+        token_command: Option<String>,
+
         /// ANTHROPIC_BASE_URL value (API endpoint URL)
         #[arg(
             long = "url",
@@ -239,18 +346,192 @@ pub enum Commands {
             help = "Import configuration from JSON file (defaults to ~/.claude/settings.json if no path)"
         )]
         from_file: Option<Option<String>>,
+
+        /// Reject comments and trailing commas in the --from-file JSON
+        ///
+        /// By default `.json`, `.jsonc` and `.json5` imports may contain
+        /// `//` and `/* */` comments and trailing commas.
+        #[arg(long = "strict-json", requires = "from_file")]
+        strict_json: bool,
+
+        /// Store the URL as given instead of checking it is an http(s) URL
+        /// with a host (for unusual endpoints)
+        #[arg(long = "no-validate")]
+        no_validate: bool,
+
+        /// Tag to attach to the configuration (repeatable, e.g. --tag prod)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+
+        /// Label color shown next to the alias (e.g. red for production)
+        #[arg(long = "color", value_name = "COLOR")]
+        color: Option<String>,
+
+        /// Provider whose token format is checked (detected from the URL
+        /// by default)
+        #[arg(long = "provider", value_name = "PROVIDER")]
+        provider: Option<crate::config::Provider>,
+
+        /// Fill in the URL and recommended models of a known endpoint
+        /// (anthropic, openrouter, deepseek, moonshot, zhipu); flags given
+        /// alongside win
+        #[arg(long = "preset", value_name = "NAME", conflicts_with = "from_file")]
+        preset: Option<String>,
+
+        /// Additional environment variable to export at launch (repeatable,
+        /// e.g. --env ANTHROPIC_CUSTOM_HEADERS="X-Org: 1")
+        #[arg(long = "env", value_name = "KEY=VALUE")]
+        env: Vec<String>,
+
+        /// Argument always passed to Claude with this configuration
+        /// (repeatable, e.g. --launch-arg --add-dir --launch-arg ~/work)
+        #[arg(long = "launch-arg", value_name = "ARG", allow_hyphen_values = true)]
+        launch_args: Vec<String>,
+
+        /// With --format json, show the full token instead of the masked form
+        #[arg(long = "reveal")]
+        reveal: bool,
     },
     /// Remove one or more configurations by alias name
     ///
-    /// Deletes stored configurations by their alias names
+    /// Deletes stored configurations by their alias names, asking for each
+    /// one when run in a terminal. --all removes every configuration after
+    /// typing "all". Exits with code 2 when none of them exist
     Remove {
         /// Configuration alias name(s) to remove (one or more)
-        #[arg(required = true)]
+        #[arg(
+            required_unless_present = "all",
+            conflicts_with = "all",
+            value_name = "STORED_ALIAS"
+        )]
         alias_names: Vec<String>,
+
+        /// Remove every stored configuration
+        #[arg(long = "all")]
+        all: bool,
+
+        /// Remove without asking
+        #[arg(long = "yes", short = 'y')]
+        yes: bool,
+
+        /// Exit with code 2 if any alias is missing, even when others were removed
+        #[arg(long = "strict")]
+        strict: bool,
+    },
+    /// Export a configuration as a Claude settings-style JSON file
+    ///
+    /// Writes `{"env": {...}}` with the token, URL and every optional field
+    /// that is set, the format `add --from-file` imports. Prints to stdout
+    /// unless --output is given; a directory gets `<alias>.json` inside it.
+    Export {
+        /// Configuration alias name to export
+        #[arg(value_name = "STORED_ALIAS")]
+        alias_name: String,
+        /// File or directory to write to
+        #[arg(long = "output", short = 'o', value_name = "PATH")]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Check that a configuration's endpoint and token actually work
+    ///
+    /// Sends one authenticated request (listing models, or with --live a
+    /// one-token message) and reports the HTTP status and latency. Exits
+    /// with an error if any tested configuration fails.
+    Test {
+        /// Configuration alias name to test; may carry a URL variant
+        #[arg(value_name = "ALIAS", required_unless_present = "all")]
+        alias_name: Option<String>,
+        /// Test every stored configuration
+        #[arg(long = "all", conflicts_with = "alias_name")]
+        all: bool,
+        /// Send a minimal messages request instead of listing models
+        #[arg(long = "live")]
+        live: bool,
+    },
+    /// Rename a stored configuration
+    ///
+    /// Keeps the token, URL and every other field; only the alias changes.
+    Rename {
+        /// Current configuration alias name
+        #[arg(value_name = "STORED_ALIAS")]
+        old_alias: String,
+        /// New alias name
+        #[arg(value_name = "NEW_ALIAS")]
+        new_alias: String,
+        /// Replace an existing configuration named NEW_ALIAS
+        #[arg(long = "force")]
+        force: bool,
+    },
+    /// Import several configurations from JSON files in one go
+    ///
+    /// Each PATH is a settings-style JSON file (as read by `add --from-file`)
+    /// or a directory whose `.json`, `.jsonc` and `.json5` files are
+    /// imported. Aliases are the file names without extension. A file that
+    /// fails to parse is reported and the others are still imported.
+    ///
+    /// Example:
+    ///   cc-switch import ~/providers
+    ///   cc-switch import ~/providers/*.json --dry-run
+    Import {
+        /// Files or directories to import
+        #[arg(required = true, value_name = "PATH")]
+        paths: Vec<String>,
+        /// Replace configurations whose alias already exists
+        #[arg(long = "force")]
+        force: bool,
+        /// Print what would be imported without saving
+        #[arg(long = "dry-run")]
+        dry_run: bool,
+        /// Reject comments and trailing commas in the JSON files
+        #[arg(long = "strict-json")]
+        strict_json: bool,
+    },
+    /// Create a configuration as a copy of an existing one
+    ///
+    /// Copies every field of SOURCE (an `alias@variant` copies that
+    /// variant's URL), then applies the field flags on top before saving.
+    ///
+    /// Example:
+    ///   cc-switch clone work work-haiku -m claude-3-5-haiku
+    Clone {
+        /// Configuration alias name to copy
+        #[arg(value_name = "ALIAS")]
+        alias_name: String,
+        /// Alias name of the new configuration
+        #[arg(value_name = "NEW_ALIAS")]
+        new_alias: String,
+        /// Replace an existing configuration named NEW_ALIAS
+        #[arg(long = "force")]
+        force: bool,
+        #[command(flatten)]
+        fields: FieldFlags,
+    },
+    /// Edit a stored configuration
+    ///
+    /// With field flags, sets only those fields and saves without prompting;
+    /// an empty value (e.g. `--model ""`) or --unset clears an optional field.
+    /// Otherwise opens the interactive field editor, or with --editor the
+    /// configuration as JSON in $VISUAL / $EDITOR. Changing the alias there
+    /// renames the configuration.
+    ///
+    /// Example:
+    ///   cc-switch edit work -u https://new.relay.example -m claude-sonnet-4
+    ///   cc-switch edit work --unset model,small_fast_model
+    Edit {
+        /// Configuration alias name to edit
+        #[arg(value_name = "STORED_ALIAS")]
+        alias_name: String,
+
+        /// Edit the configuration as JSON in $VISUAL / $EDITOR
+        #[arg(long = "editor", conflicts_with = "fields")]
+        editor: bool,
+
+        #[command(flatten)]
+        fields: FieldFlags,
     },
     /// List all stored configurations
     ///
-    /// Displays all saved configurations with their aliases, tokens, and URLs
+    /// Displays all saved configurations with their aliases, tokens, and URLs.
+    /// Tokens are masked unless --reveal is given.
     List {
         /// Output in plain text format (default is JSON)
         #[arg(long = "plain", short = 'p')]
@@ -258,25 +539,67 @@ pub enum Commands {
         /// Show only name and URL
         #[arg(long = "name", short = 'n')]
         name: bool,
+        /// Report configurations referencing missing external resources
+        /// (e.g. launch profile commands); exits non-zero if any are found
+        #[arg(long = "orphaned", conflicts_with_all = ["plain", "name", "tag"])]
+        orphaned: bool,
+        /// Show full tokens and API keys instead of the masked form
+        #[arg(long = "reveal")]
+        reveal: bool,
+        /// Only list configurations with this tag
+        #[arg(long = "tag", value_name = "TAG")]
+        tag: Option<String>,
+        /// With --plain, list configurations in sections per tag
+        #[arg(long = "group-by-tag", requires = "plain")]
+        group_by_tag: bool,
+        /// Order of the listed configurations
+        #[arg(long = "sort", value_name = "KEY", default_value = "name")]
+        sort: crate::cli::display_utils::ListSort,
+        /// Reverse the order
+        #[arg(long = "reverse")]
+        reverse: bool,
     },
     /// Generate shell completion scripts
     ///
-    /// Generates completion scripts for supported shells
+    /// Prints the completion script for a shell; with `--install`, writes it
+    /// to the shell's per-user completion directory instead.
+    /// Use `completion doctor <shell>` to check an installed completion setup.
     #[command(alias = "C")]
     Completion {
-        /// Shell type (fish, zsh, bash, elvish, powershell)
+        /// Shell type (fish, zsh, bash, elvish, powershell), or `doctor`
         #[arg(default_value = "fish")]
         shell: String,
+
+        /// Shell to check with `completion doctor` (fish, zsh, bash)
+        #[arg(value_name = "SHELL")]
+        doctor_shell: Option<String>,
+
+        /// Print the `completion doctor` report as JSON
+        #[arg(long = "json", conflicts_with = "install")]
+        json: bool,
+
+        /// Write the script to the per-user completion path (fish, zsh, bash)
+        #[arg(long = "install")]
+        install: bool,
+
+        /// With --install, overwrite an existing completion file
+        #[arg(long = "force", requires = "install")]
+        force: bool,
     },
     /// Switch to a configuration and optionally send a prompt to Claude
     ///
     /// Quickly switches to the specified configuration and launches Claude.
-    /// Any additional arguments after the alias name are joined and sent as a prompt.
+    /// Further arguments naming configurations form a failover chain
+    /// (`use relay-a relay-b`); with --preflight the first reachable one is
+    /// used. Remaining arguments are joined and sent as a prompt; arguments
+    /// after `--` are passed to Claude unchanged
+    /// (`use work -- --model claude-3-5-haiku`).
+    /// `use -` launches the configuration used last.
     /// Use --resume to resume a previous Claude session by ID.
     /// Use --continue to continue the most recent Claude session.
-    #[command(trailing_var_arg = true)]
     Use {
         /// Configuration alias name to switch to
+        #[arg(value_name = "ALIAS")]
         alias_name: String,
 
         /// Resume a previous Claude session by ID
@@ -287,9 +610,215 @@ pub enum Commands {
         #[arg(long, short = 'c')]
         r#continue: bool,
 
-        /// Prompt to send to Claude (all remaining arguments)
-        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        /// Skip the confirmation for configurations with a confirm tag (and,
+        /// like --clean-settings, the settings.json question for `cc`)
+        #[arg(long = "yes", short = 'y')]
+        yes: bool,
+
+        /// Show the configuration's details and ask before launching it
+        #[arg(long = "confirm", conflicts_with = "yes")]
+        confirm: bool,
+
+        /// With the official alias (`cc`), remove Anthropic variables left in
+        /// settings.json without asking
+        #[arg(long = "clean-settings")]
+        clean_settings: bool,
+
+        /// Persist the switch (settings.json in config mode, last used) and
+        /// print the summary without starting Claude
+        #[arg(long = "no-launch", conflicts_with_all = ["with", "resume", "continue"])]
+        no_launch: bool,
+
+        /// Check that the endpoint answers before launching, falling through
+        /// the alias chain on failure
+        #[arg(long = "preflight")]
+        preflight: bool,
+
+        /// Extra argument passed through to Claude (repeatable)
+        #[arg(long = "claude-arg", value_name = "ARG", allow_hyphen_values = true)]
+        claude_args: Vec<String>,
+
+        /// Launch the configuration's named launch profile instead of Claude
+        #[arg(long = "with", value_name = "PROFILE", conflicts_with_all = ["resume", "continue"])]
+        with: Option<String>,
+
+        /// Prompt to send to Claude (all remaining arguments before `--`)
         prompt: Vec<String>,
+
+        /// Arguments after `--`, passed through to Claude unchanged
+        #[arg(last = true, value_name = "CLAUDE_ARGS")]
+        passthrough: Vec<String>,
+    },
+    /// Print a configuration's environment as shell statements
+    ///
+    /// Nothing is launched: the statements set the variables Claude would
+    /// receive and unset the other variables cc-switch manages. With the
+    /// official alias (`cc`) only unset statements are printed.
+    ///
+    /// Example:
+    ///   eval "$(cc-switch env work)"
+    ///   eval (cc-switch env work --shell fish)
+    Env {
+        /// Configuration alias name to print the environment of
+        #[arg(value_name = "ALIAS")]
+        alias_name: String,
+
+        /// Shell syntax to emit: bash, zsh, sh, fish or powershell (defaults to $SHELL)
+        #[arg(long = "shell", value_name = "SHELL")]
+        shell: Option<String>,
+    },
+    /// Print what launching a configuration would run, without launching it
+    ///
+    /// Shows every environment variable Claude would receive, the command
+    /// line that would be exec'd (Claude binary, permission flag and the
+    /// configuration's launch arguments) and the storage mode. A token
+    /// command is run to resolve the token, which is masked unless --reveal
+    /// is given.
+    ///
+    /// Example:
+    ///   cc-switch resolve work
+    ///   cc-switch which work --json
+    #[command(visible_alias = "which")]
+    Resolve {
+        /// Configuration alias name to resolve
+        #[arg(value_name = "ALIAS")]
+        alias_name: String,
+
+        /// Print the plan as JSON
+        #[arg(long = "json")]
+        json: bool,
+
+        /// Show the full token or API key instead of the masked form
+        #[arg(long = "reveal")]
+        reveal: bool,
+    },
+    /// Clear Anthropic variables and cc-switch state without launching Claude
+    ///
+    /// Prints unset statements for the shell on stdout (use with eval) and
+    /// reports what was cleared on stderr. The session marker of the calling
+    /// shell is removed; with --persistent, Anthropic variables are also
+    /// removed from settings.json after backing it up.
+    ///
+    /// Example:
+    ///   eval "$(cc-switch unuse)"
+    ///   cc-switch unuse --shell fish | source
+    Unuse {
+        /// Shell to print unset statements for (defaults to $SHELL)
+        #[arg(long = "shell", value_name = "SHELL")]
+        shell: Option<String>,
+
+        /// Also remove Anthropic variables from settings.json (backed up first)
+        #[arg(long = "persistent")]
+        persistent: bool,
+    },
+    /// Print a shell script defining one function per configuration
+    ///
+    /// The script needs no cc-switch: `claude_<alias>` runs Claude with that
+    /// configuration's variables and `claude_reset` unsets them. Tokens are
+    /// replaced with placeholders unless --show-secrets is given.
+    ///
+    /// Example:
+    ///   cc-switch export-shell > claude-envs.sh
+    ///   cc-switch export-shell --shell fish > claude-envs.fish
+    ExportShell {
+        /// Shell syntax to emit (sh, bash, zsh or fish)
+        #[arg(long = "shell", value_name = "SHELL", default_value = "sh")]
+        shell: String,
+
+        /// Include tokens and API keys in the script
+        #[arg(long = "show-secrets")]
+        show_secrets: bool,
+    },
+    /// Print shell aliases for eval: `cs` and `cx`, and optionally one
+    /// function per configuration
+    ///
+    /// With --per-config, `<prefix><alias>` runs `cc-switch use <alias>`.
+    /// Characters not allowed in function names become `_`; aliases whose
+    /// name is then already taken are skipped with a warning.
+    ///
+    /// Example:
+    ///   eval "$(cc-switch alias zsh --per-config)"
+    ///   cc-switch alias fish --per-config --prefix cs_ | source
+    Alias {
+        /// Shell syntax to emit (fish, zsh or bash)
+        #[arg(value_name = "SHELL")]
+        shell: String,
+
+        /// Also define one function per stored configuration
+        #[arg(long = "per-config")]
+        per_config: bool,
+
+        /// Prefix of the per-configuration function names
+        #[arg(
+            long = "prefix",
+            value_name = "PREFIX",
+            default_value = crate::cli::completion::DEFAULT_FUNCTION_PREFIX,
+            requires = "per_config"
+        )]
+        prefix: String,
+    },
+    /// Set the configuration launched by bare `cc-switch`
+    ///
+    /// With a default set, running `cc-switch` without a subcommand launches
+    /// it directly instead of opening the interactive menu. It is marked
+    /// with ★ in `list` and in the menu.
+    SetDefault {
+        /// Configuration alias name to launch by default
+        #[arg(
+            value_name = "STORED_ALIAS",
+            required_unless_present = "clear",
+            conflicts_with = "clear"
+        )]
+        alias_name: Option<String>,
+
+        /// Remove the default so bare `cc-switch` opens the menu again
+        #[arg(long = "clear")]
+        clear: bool,
+    },
+    /// Print alias, base URL and model of every configuration for scripts
+    ///
+    /// One `alias<TAB>url<TAB>model` line per configuration, or with --json
+    /// an object keyed by alias with url, model and provider. Tokens and API
+    /// keys are never included, whatever the flags.
+    AliasMap {
+        /// Print a JSON object keyed by alias
+        #[arg(long = "json")]
+        json: bool,
+    },
+    /// Show which configuration is active, then offer the interactive menu
+    ///
+    /// Reads the Anthropic variables of this shell, falling back to the env
+    /// block of settings.json, and matches them against the stored
+    /// configurations. Tokens are masked.
+    Current {
+        /// Print the report as JSON and skip the menu
+        #[arg(long = "json")]
+        json: bool,
+    },
+    /// Print version and build metadata for bug reports
+    ///
+    /// Shows the git commit, build date, target triple, enabled features and
+    /// the configuration storage path. --short prints the same single line
+    /// as --version.
+    Version {
+        /// Print only the version line
+        #[arg(long = "short", conflicts_with = "json")]
+        short: bool,
+
+        /// Print the metadata as JSON
+        #[arg(long = "json")]
+        json: bool,
+    },
+    /// Diagnose common setup problems
+    ///
+    /// Checks the Claude binary, the configuration storage file and its
+    /// permissions, the stored configurations, settings.json and the
+    /// terminal. Each check prints a hint when it warns or fails; the exit
+    /// code is non-zero when any check fails.
+    Doctor {
+        /// Print the report as JSON
+        #[arg(long = "json")]
+        json: bool,
     },
     /// Manage Codex (OpenAI CLI) configurations
     Codex {
@@ -304,6 +833,138 @@ pub enum Commands {
         #[command(subcommand)]
         command: DaemonCommands,
     },
+    /// Set one field on every configuration matching a filter
+    ///
+    /// Previews the affected aliases and asks for confirmation before saving.
+    /// Filters are key=value (exact) or key~=value (substring) on url,
+    /// provider (URL host), alias or tag. An empty value clears optional fields.
+    ///
+    /// Example:
+    ///   cc-switch set --where url=https://old.relay.example url https://new.relay.example
+    Set {
+        /// Filter selecting the configurations to change
+        #[arg(long = "where", value_name = "KEY=VALUE")]
+        filter: String,
+
+        /// Field to set (e.g. url, token, model)
+        field: String,
+
+        /// New value for the field
+        #[arg(allow_hyphen_values = true)]
+        value: String,
+
+        /// Apply without asking for confirmation
+        #[arg(long = "yes", short = 'y')]
+        yes: bool,
+
+        /// Show the affected configurations without saving
+        #[arg(long = "dry-run")]
+        dry_run: bool,
+    },
+    /// Manage cc-switch preferences
+    ///
+    /// Preferences are stored alongside the configurations.
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+    /// Save the whole configuration store to a file
+    ///
+    /// Writes every configuration together with the default alias and
+    /// preferences, for `cc-switch restore` on another machine. The file
+    /// holds tokens and is readable by the current user only.
+    Backup {
+        /// File to write
+        #[arg(value_name = "FILE")]
+        file: std::path::PathBuf,
+    },
+    /// Load a configuration store saved by `cc-switch backup`
+    ///
+    /// By default (--merge) adds the configurations that don't exist yet,
+    /// overwriting existing ones only with --force. --replace makes the
+    /// store exactly the backup, preferences included.
+    Restore {
+        /// Backup file to read
+        #[arg(value_name = "FILE")]
+        file: std::path::PathBuf,
+        /// Add the backup's configurations to the current ones (default)
+        #[arg(long = "merge", conflicts_with = "replace")]
+        merge: bool,
+        /// Replace the whole store with the backup
+        #[arg(long = "replace")]
+        replace: bool,
+        /// Overwrite existing configurations when merging
+        #[arg(long = "force", conflicts_with = "replace")]
+        force: bool,
+    },
+    /// Copy configurations from a store written by an older release
+    ///
+    /// Reads ~/.cc_auto_switch/configurations.json, or the file given with
+    /// --from, and adds its configurations to the current store. The old
+    /// file is left in place. This also happens automatically the first time
+    /// cc-switch runs without a current store.
+    ///
+    /// Example:
+    ///   cc-switch migrate
+    ///   cc-switch migrate --from /mnt/old-home/.cc_auto_switch/configurations.json
+    Migrate {
+        /// Legacy storage file to read
+        #[arg(long = "from", value_name = "PATH")]
+        from: Option<String>,
+        /// Overwrite configurations whose alias already exists
+        #[arg(long = "force")]
+        force: bool,
+    },
+    /// Find duplicate and broken configurations, and optionally remove them
+    ///
+    /// Reports configurations with the same token, URL and model as another
+    /// one (the most recently used is kept) and ones missing a token or URL.
+    /// --check-url also probes every URL. Nothing is removed without --apply,
+    /// which asks before each removal unless --yes is given.
+    ///
+    /// Example:
+    ///   cc-switch prune --check-url
+    ///   cc-switch prune --apply --yes
+    Prune {
+        /// Remove the configurations found, asking for each
+        #[arg(long = "apply")]
+        apply: bool,
+        /// Remove without asking (with --apply)
+        #[arg(long = "yes", short = 'y', requires = "apply")]
+        yes: bool,
+        /// Also report configurations whose URL doesn't answer
+        #[arg(long = "check-url")]
+        check_url: bool,
+    },
+    /// Restore the configurations from their backup
+    ///
+    /// Every save keeps the previous version of the storage file as
+    /// `<storage file>.bak`; this puts it back in place.
+    #[command(name = "restore-backup")]
+    RestoreBackup,
+    /// Inspect or clean Claude's settings.json
+    ///
+    /// Example:
+    ///   cc-switch settings show
+    ///   cc-switch settings clean --dir ~/.claude-work
+    Settings {
+        #[command(subcommand)]
+        command: SettingsCommands,
+    },
+    /// Keep tokens in the OS keyring instead of the storage file
+    ///
+    /// While secure mode is on, each token is stored in the keyring under
+    /// its alias (service "cc-switch") and the storage file holds
+    /// `keyring:<alias>` instead; `add` and `edit` store new tokens there
+    /// too. Needs a build with the `keyring` feature.
+    ///
+    /// Example:
+    ///   cc-switch secure enable
+    ///   cc-switch secure disable
+    Secure {
+        #[command(subcommand)]
+        command: SecureCommands,
+    },
     /// Manage statusLine integration with Claude Code
     ///
     /// Installs a wrapper script that displays the current cc-switch alias name
@@ -320,6 +981,310 @@ pub enum Commands {
     },
 }
 
+/// Field flags shared by `edit` and `clone`
+///
+/// Each given flag sets one configuration field; `--unset` clears fields.
+#[derive(clap::Args, Debug, Default)]
+#[group(id = "fields", multiple = true)]
+pub struct FieldFlags {
+    /// New API token (replaces the API key instead if one is stored)
+    #[arg(long = "token", short = 't')]
+    pub token: Option<String>,
+
+    /// Command printing the API token at each switch; replaces the stored
+    /// token or API key
+    #[arg(
+        long = "token-command",
+        value_name = "COMMAND",
+        conflicts_with = "token"
+    )]
+    pub token_command: Option<String>,
+
+    /// New API endpoint URL
+    #[arg(long = "url", short = 'u')]
+    pub url: Option<String>,
+
+    /// New custom model name
+    #[arg(long = "model", short = 'm')]
+    pub model: Option<String>,
+
+    /// New Haiku-class model for background tasks
+    #[arg(long = "small-fast-model")]
+    pub small_fast_model: Option<String>,
+
+    /// New maximum thinking tokens limit
+    #[arg(long = "max-thinking-tokens")]
+    pub max_thinking_tokens: Option<String>,
+
+    /// New API timeout in milliseconds
+    #[arg(long = "api-timeout-ms")]
+    pub api_timeout_ms: Option<String>,
+
+    /// New disable non-essential traffic flag
+    #[arg(long = "disable-nonessential-traffic")]
+    pub claude_code_disable_nonessential_traffic: Option<String>,
+
+    /// New default Sonnet model name
+    #[arg(long = "default-sonnet-model")]
+    pub anthropic_default_sonnet_model: Option<String>,
+
+    /// New default Opus model name
+    #[arg(long = "default-opus-model")]
+    pub anthropic_default_opus_model: Option<String>,
+
+    /// New default Haiku model name
+    #[arg(long = "default-haiku-model")]
+    pub anthropic_default_haiku_model: Option<String>,
+
+    /// New subagent model name
+    #[arg(long = "subagent-model")]
+    pub claude_code_subagent_model: Option<String>,
+
+    /// New disable non-streaming fallback flag
+    #[arg(long = "disable-nonstreaming-fallback")]
+    pub claude_code_disable_nonstreaming_fallback: Option<String>,
+
+    /// New effort level for Claude Code
+    #[arg(long = "effort-level")]
+    pub claude_code_effort_level: Option<String>,
+
+    /// New disable prompt caching flag
+    #[arg(long = "disable-prompt-caching")]
+    pub disable_prompt_caching: Option<String>,
+
+    /// New disable experimental betas flag
+    #[arg(long = "disable-experimental-betas")]
+    pub claude_code_disable_experimental_betas: Option<String>,
+
+    /// New disable auto-updater flag
+    #[arg(long = "disable-autoupdater")]
+    pub disable_autoupdater: Option<String>,
+
+    /// Replace the tags (repeatable, e.g. --tag prod --tag eu)
+    #[arg(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+
+    /// New label color
+    #[arg(long = "color", value_name = "COLOR")]
+    pub color: Option<String>,
+
+    /// Provider whose token format is checked (`--unset provider` detects it
+    /// from the URL again)
+    #[arg(long = "provider", value_name = "PROVIDER")]
+    pub provider: Option<crate::config::Provider>,
+
+    /// Set an additional environment variable (repeatable); KEY= removes it
+    #[arg(long = "env", value_name = "KEY=VALUE")]
+    pub env: Vec<String>,
+
+    /// Replace the arguments always passed to Claude (repeatable, e.g.
+    /// --launch-arg --continue; `--unset launch_args` clears them)
+    #[arg(long = "launch-arg", value_name = "ARG", allow_hyphen_values = true)]
+    pub launch_args: Vec<String>,
+
+    /// Comma-separated optional fields to clear (e.g. model,small_fast_model)
+    #[arg(long = "unset", value_name = "FIELDS", value_delimiter = ',')]
+    pub unset: Vec<String>,
+}
+
+impl FieldFlags {
+    /// `(field, value)` pairs for [`Configuration::set_field`], an empty
+    /// value clearing the field
+    ///
+    /// [`Configuration::set_field`]: crate::config::Configuration::set_field
+    pub fn into_updates(self) -> Vec<crate::cli::main::FieldUpdate> {
+        let tags = (!self.tags.is_empty()).then(|| self.tags.join(","));
+        let launch_args = (!self.launch_args.is_empty())
+            .then(|| crate::config::types::format_launch_args(&self.launch_args));
+        [
+            ("token", self.token),
+            ("token_command", self.token_command),
+            ("url", self.url),
+            ("model", self.model),
+            ("small_fast_model", self.small_fast_model),
+            ("max_thinking_tokens", self.max_thinking_tokens),
+            ("api_timeout_ms", self.api_timeout_ms),
+            (
+                "claude_code_disable_nonessential_traffic",
+                self.claude_code_disable_nonessential_traffic,
+            ),
+            (
+                "anthropic_default_sonnet_model",
+                self.anthropic_default_sonnet_model,
+            ),
+            (
+                "anthropic_default_opus_model",
+                self.anthropic_default_opus_model,
+            ),
+            (
+                "anthropic_default_haiku_model",
+                self.anthropic_default_haiku_model,
+            ),
+            (
+                "claude_code_subagent_model",
+                self.claude_code_subagent_model,
+            ),
+            (
+                "claude_code_disable_nonstreaming_fallback",
+                self.claude_code_disable_nonstreaming_fallback,
+            ),
+            ("claude_code_effort_level", self.claude_code_effort_level),
+            ("disable_prompt_caching", self.disable_prompt_caching),
+            (
+                "claude_code_disable_experimental_betas",
+                self.claude_code_disable_experimental_betas,
+            ),
+            ("disable_autoupdater", self.disable_autoupdater),
+            ("tags", tags),
+            ("color", self.color),
+            (
+                "provider",
+                self.provider.map(|provider| provider.as_str().to_string()),
+            ),
+            ("launch_args", launch_args),
+        ]
+        .into_iter()
+        .filter_map(|(field, value)| value.map(|v| (field.to_string(), v)))
+        .chain(
+            self.env
+                .into_iter()
+                .map(|assignment| ("extra_env".to_string(), assignment)),
+        )
+        .chain(self.unset.into_iter().map(|field| (field, String::new())))
+        .collect()
+    }
+}
+
+/// How a command treats the official pseudo-aliases (`cc` / `official`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OfficialAliasPolicy {
+    /// The command takes no configuration alias
+    NotApplicable,
+    /// The official alias resets to the default Claude configuration
+    Reset,
+    /// The official alias is rejected as a reserved name
+    Reject,
+}
+
+impl Commands {
+    /// The decision this command makes for the official pseudo-aliases
+    ///
+    /// Deliberately exhaustive: a new command does not compile until it
+    /// picks a policy.
+    pub fn official_alias_policy(&self) -> OfficialAliasPolicy {
+        match self {
+            Commands::Use { .. } | Commands::Env { .. } => OfficialAliasPolicy::Reset,
+            Commands::Add { .. }
+            | Commands::Remove { .. }
+            | Commands::Rename { .. }
+            | Commands::Clone { .. }
+            | Commands::Export { .. }
+            | Commands::Test { .. }
+            | Commands::SetDefault { .. }
+            | Commands::Resolve { .. }
+            | Commands::Edit { .. } => OfficialAliasPolicy::Reject,
+            Commands::List { .. }
+            | Commands::Set { .. }
+            | Commands::Unuse { .. }
+            | Commands::Current { .. }
+            | Commands::Version { .. }
+            | Commands::Doctor { .. }
+            | Commands::ExportShell { .. }
+            | Commands::Alias { .. }
+            | Commands::AliasMap { .. }
+            | Commands::Completion { .. }
+            | Commands::Codex { .. }
+            | Commands::Daemon { .. }
+            | Commands::Config { .. }
+            | Commands::RestoreBackup
+            | Commands::Migrate { .. }
+            | Commands::Prune { .. }
+            | Commands::Backup { .. }
+            | Commands::Restore { .. }
+            | Commands::Import { .. }
+            | Commands::Settings { .. }
+            | Commands::Secure { .. }
+            | Commands::Statusline { .. } => OfficialAliasPolicy::NotApplicable,
+        }
+    }
+}
+
+/// Subcommands for `cc-switch settings`
+///
+/// `--dir` picks the settings directory, overriding the one set with
+/// `config set settings-dir` (default `~/.claude`).
+#[derive(Subcommand)]
+pub enum SettingsCommands {
+    /// Print the env block (secrets masked) and the other keys kept
+    Show {
+        /// Settings directory to use instead of the configured one
+        #[arg(long = "dir", value_name = "PATH")]
+        dir: Option<String>,
+    },
+    /// Remove the Anthropic variables from settings.json, keeping the rest
+    ///
+    /// The previous file is saved as settings.json.bak.
+    Clean {
+        /// Settings directory to use instead of the configured one
+        #[arg(long = "dir", value_name = "PATH")]
+        dir: Option<String>,
+    },
+    /// Print the path of settings.json
+    Path {
+        /// Settings directory to use instead of the configured one
+        #[arg(long = "dir", value_name = "PATH")]
+        dir: Option<String>,
+    },
+}
+
+/// Subcommands for `cc-switch secure`
+#[derive(Subcommand)]
+pub enum SecureCommands {
+    /// Move every stored token into the OS keyring
+    Enable,
+    /// Move the tokens back from the keyring into the storage file
+    Disable,
+}
+
+/// Subcommands for `cc-switch config`
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// Set a preference value
+    ///
+    /// Supported keys:
+    ///   hidden-fields  Comma-separated field names displayed as [hidden]
+    ///                  (e.g. token,url); an empty value clears the list
+    ///   confirm-tags   Comma-separated tags (e.g. prod) whose configurations
+    ///                  ask for confirmation before launching
+    ///   menu-mode      `grouped` shows the interactive menu in sections per
+    ///                  tag, `flat` (default) as one list
+    ///   fast-select    `on` launches immediately, printing only `→ alias`,
+    ///                  when the first key in the menu is a digit, R or Q
+    ///   confirm-launch `on` shows the picked configuration in the menu and
+    ///                  launches only after Enter; Esc goes back
+    ///   settings-dir   Directory holding the Claude settings.json that launches
+    ///                  update (relative to home unless absolute); an empty
+    ///                  value restores ~/.claude
+    ///   claude-path    Claude binary to launch instead of `claude` from PATH;
+    ///                  CC_SWITCH_CLAUDE_BIN overrides it and an empty value
+    ///                  clears it
+    ///   storage-mode   `env` (default) passes the configuration to Claude as
+    ///                  environment variables, `config` (or `settings`) writes
+    ///                  it into settings.json; `--mode` overrides it per launch
+    ///   verify-writes  `on` (default) reads storage and settings files back
+    ///                  after saving and restores them on a mismatch; `off`
+    ///                  skips the check
+    ///   skip-permissions  `on` (default) launches Claude with
+    ///                  --dangerously-skip-permissions; `off` leaves it out
+    Set {
+        /// Preference name
+        key: String,
+        /// Preference value
+        #[arg(allow_hyphen_values = true)]
+        value: String,
+    },
+}
+
 /// Actions for the statusline subcommand
 #[derive(Clone, Copy, clap::ValueEnum)]
 pub enum StatuslineAction {
@@ -417,6 +1382,8 @@ pub enum CodexCommands {
     },
     #[command(trailing_var_arg = true)]
     Use {
+        /// Codex configuration alias name to switch to
+        #[arg(value_name = "CODEX_ALIAS")]
         alias_name: String,
         #[arg(long = "continue", short = 'c')]
         r#continue: bool,
@@ -426,7 +1393,8 @@ pub enum CodexCommands {
         prompt: Vec<String>,
     },
     Remove {
-        #[arg(required = true)]
+        /// Codex configuration alias name(s) to remove
+        #[arg(required = true, value_name = "CODEX_ALIAS")]
         alias_names: Vec<String>,
     },
 }
@@ -0,0 +1,111 @@
+//! Color policy shared by every command.
+//!
+//! Whether ANSI colors are emitted is decided once at startup, in this order:
+//! 1. `--force-color` / `--no-color` flags: always / never color, whatever
+//!    the environment says
+//! 2. `NO_COLOR` (non-empty): never color
+//! 3. `CLICOLOR_FORCE` (non-empty, not `0`): always color
+//! 4. `CLICOLOR=0`: never color
+//! 5. Otherwise color only when stdout is a terminal
+
+use std::io::IsTerminal;
+
+/// Decide whether to emit colors
+///
+/// # Arguments
+/// * `flag` - `Some(true)` for `--force-color`, `Some(false)` for
+///   `--no-color`, `None` when neither was passed
+/// * `env` - Environment lookup (e.g. `std::env::var(..).ok()`)
+/// * `stdout_is_tty` - Whether stdout is a terminal
+pub fn should_colorize(
+    flag: Option<bool>,
+    env: impl Fn(&str) -> Option<String>,
+    stdout_is_tty: bool,
+) -> bool {
+    let set = |name: &str| env(name).filter(|value| !value.is_empty());
+
+    if let Some(enabled) = flag {
+        return enabled;
+    }
+    if set("NO_COLOR").is_some() {
+        return false;
+    }
+    if set("CLICOLOR_FORCE").is_some_and(|value| value != "0") {
+        return true;
+    }
+    if set("CLICOLOR").as_deref() == Some("0") {
+        return false;
+    }
+    stdout_is_tty
+}
+
+/// Apply the color policy for this process
+///
+/// The interactive menus draw with the same `colored` styles, so this also
+/// decides whether they are colored.
+pub fn apply_color_policy(flag: Option<bool>) {
+    let enabled = should_colorize(
+        flag,
+        |name| std::env::var(name).ok(),
+        std::io::stdout().is_terminal(),
+    );
+    colored::control::set_override(enabled);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type EnvVars<'a> = [(&'a str, &'a str)];
+
+    fn env_of<'a>(vars: &'a EnvVars<'a>) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn tty_decides_without_overrides() {
+        assert!(should_colorize(None, env_of(&[]), true));
+        assert!(!should_colorize(None, env_of(&[]), false));
+    }
+
+    #[test]
+    fn flag_beats_no_color() {
+        assert!(should_colorize(
+            Some(true),
+            env_of(&[("NO_COLOR", "1")]),
+            false
+        ));
+    }
+
+    #[test]
+    fn no_color_flag_beats_clicolor_force_and_tty() {
+        let env = [("CLICOLOR_FORCE", "1")];
+        assert!(!should_colorize(Some(false), env_of(&env), true));
+    }
+
+    #[test]
+    fn no_color_beats_clicolor_force() {
+        let env = [("NO_COLOR", "1"), ("CLICOLOR_FORCE", "1")];
+        assert!(!should_colorize(None, env_of(&env), true));
+    }
+
+    #[test]
+    fn clicolor_force_and_clicolor() {
+        assert!(should_colorize(
+            None,
+            env_of(&[("CLICOLOR_FORCE", "1")]),
+            false
+        ));
+        assert!(!should_colorize(
+            None,
+            env_of(&[("CLICOLOR_FORCE", "0")]),
+            false
+        ));
+        assert!(!should_colorize(None, env_of(&[("CLICOLOR", "0")]), true));
+        assert!(should_colorize(None, env_of(&[("NO_COLOR", "")]), true));
+    }
+}
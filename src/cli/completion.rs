@@ -1,9 +1,13 @@
-use crate::config::ConfigStorage;
-use anyhow::Result;
+use crate::config::{ConfigStorage, OFFICIAL_ALIASES};
+use anyhow::{Context, Result};
 use clap::CommandFactory;
+use std::ffi::OsString;
 use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// Type alias for a rendered completion script
+type CompletionScript = Vec<u8>;
 
 /// Generate shell aliases for eval
 ///
@@ -29,25 +33,188 @@ pub fn generate_aliases(shell: &str) -> Result<()> {
     Ok(())
 }
 
-/// Return the install path for a shell's completion file, if it has a standard location.
-fn completion_install_path(shell: &str) -> Option<PathBuf> {
-    let home = dirs::home_dir()?;
+/// Aliases printed by [`generate_aliases`], which per-configuration
+/// functions must not shadow
+const FIXED_ALIASES: [&str; 2] = ["cs", "cx"];
+
+/// Prefix of per-configuration functions unless `--prefix` is given
+pub const DEFAULT_FUNCTION_PREFIX: &str = "cc";
+
+/// Per-configuration shell functions and what could not be generated
+pub struct ConfigFunctions {
+    /// One function definition per line
+    pub script: String,
+    /// Aliases skipped because their function name was taken
+    pub warnings: Vec<String>,
+}
+
+/// One shell function per stored configuration, running `cc-switch use`
+///
+/// The function for alias `work` is `<prefix>work`. Characters that are not
+/// valid in a shell identifier (dots, dashes, non-ASCII letters) become
+/// `_`; an alias whose name is then taken by an earlier alias or one of the
+/// fixed aliases is skipped with a warning.
+///
+/// # Arguments
+/// * `shell` - Shell type (fish, zsh, bash)
+/// * `storage` - Storage whose configurations get a function
+/// * `prefix` - Prefix of every function name, made of `[A-Za-z0-9_]`
+///
+/// # Errors
+/// Returns error if shell is not supported or the prefix is not a valid
+/// identifier part
+pub fn render_config_functions(
+    shell: &str,
+    storage: &ConfigStorage,
+    prefix: &str,
+) -> Result<ConfigFunctions> {
+    if !matches!(shell, "fish" | "zsh" | "bash") {
+        anyhow::bail!(
+            "Unsupported shell: {}. Supported shells: fish, zsh, bash",
+            shell
+        );
+    }
+    if crate::cli::export_shell::shell_identifier(prefix) != prefix {
+        anyhow::bail!(
+            "Invalid prefix '{}': use letters, digits and '_' only",
+            prefix
+        );
+    }
+
+    let mut taken: std::collections::BTreeMap<String, String> = FIXED_ALIASES
+        .iter()
+        .map(|name| (name.to_string(), format!("the `{name}` alias")))
+        .collect();
+    let mut script = String::new();
+    let mut warnings = Vec::new();
+    for alias in storage.configurations.keys() {
+        let mut name = format!(
+            "{prefix}{}",
+            crate::cli::export_shell::shell_identifier(alias)
+        );
+        if name.starts_with(|c: char| c.is_ascii_digit()) {
+            name.insert(0, '_');
+        }
+        if let Some(owner) = taken.get(&name) {
+            warnings.push(format!(
+                "Skipped '{alias}': its function name '{name}' is already used by {owner}"
+            ));
+            continue;
+        }
+        let line = if shell == "fish" {
+            format!(
+                "function {name}; cc-switch use {} $argv; end\n",
+                crate::cli::export_shell::fish_quote(alias)
+            )
+        } else {
+            format!(
+                "{name}() {{ cc-switch use {} \"$@\"; }}\n",
+                crate::interactive::interactive::shell_quote(alias)
+            )
+        };
+        script.push_str(&line);
+        taken.insert(name, format!("'{alias}'"));
+    }
+    Ok(ConfigFunctions { script, warnings })
+}
+
+/// Print the fixed aliases and, with `per_config`, one function per stored
+/// configuration for eval; skipped aliases are reported on stderr
+///
+/// # Errors
+/// Returns error if shell is not supported or the prefix is invalid
+pub fn print_aliases(
+    shell: &str,
+    storage: &ConfigStorage,
+    per_config: bool,
+    prefix: &str,
+) -> Result<()> {
+    generate_aliases(shell)?;
+    if per_config {
+        let functions = render_config_functions(shell, storage, prefix)?;
+        print!("{}", functions.script);
+        for warning in &functions.warnings {
+            eprintln!("Warning: {warning}");
+        }
+    }
+    Ok(())
+}
+
+/// Per-user completion files cc-switch installs next to the main script,
+/// as `(file name, content)`
+type CompanionFile = (&'static str, String);
+
+/// Absolute directory named by the environment variable `var`
+///
+/// Relative values are ignored, as the XDG base directory spec requires.
+fn xdg_dir(env: &impl Fn(&str) -> Option<OsString>, var: &str) -> Option<PathBuf> {
+    env(var).map(PathBuf::from).filter(|dir| dir.is_absolute())
+}
+
+/// Whether a file can be created in `dir`, checked by creating one
+fn dir_is_writable(dir: &Path) -> bool {
+    let probe = dir.join(format!(".cc-switch-write-test-{}", std::process::id()));
+    let writable = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+        .is_ok();
+    if writable {
+        let _ = fs::remove_file(&probe);
+    }
+    writable
+}
+
+/// Conventional per-user path of the completion file for `shell`
+///
+/// * fish: `$XDG_CONFIG_HOME/fish/completions/cc-switch.fish` (default `~/.config`)
+/// * zsh: `_cc-switch` in the first writable `$FPATH` directory under
+///   `home`, else `~/.zsh/completions/_cc-switch`
+/// * bash: `$XDG_DATA_HOME/bash-completion/completions/cc-switch`
+///   (default `~/.local/share`), which bash-completion loads on demand
+///
+/// Returns `None` for shells without a standard location (elvish,
+/// powershell). `env` looks up environment variables, so the resolution
+/// can be tested without touching the process environment.
+pub fn resolve_completion_install_path(
+    shell: &str,
+    env: impl Fn(&str) -> Option<OsString>,
+    home: &Path,
+) -> Option<PathBuf> {
     match shell {
-        "fish" => Some(home.join(".config/fish/completions/cc-switch.fish")),
-        "zsh" => Some(home.join(".zsh/completions/_cc-switch")),
-        "bash" => Some(home.join(".bash_completion.d/cc-switch")),
+        "fish" => {
+            let config = xdg_dir(&env, "XDG_CONFIG_HOME").unwrap_or_else(|| home.join(".config"));
+            Some(config.join("fish/completions/cc-switch.fish"))
+        }
+        "zsh" => {
+            let fpath = env("FPATH").unwrap_or_default();
+            let dir = std::env::split_paths(&fpath)
+                .find(|dir| dir.starts_with(home) && dir.is_dir() && dir_is_writable(dir))
+                .unwrap_or_else(|| home.join(".zsh/completions"));
+            Some(dir.join("_cc-switch"))
+        }
+        "bash" => {
+            let data = xdg_dir(&env, "XDG_DATA_HOME").unwrap_or_else(|| home.join(".local/share"));
+            Some(data.join("bash-completion/completions/cc-switch"))
+        }
         _ => None,
     }
 }
 
-/// Generate shell completion script and install it to the standard path.
+/// Return the install path for a shell's completion file, if it has a standard location.
+fn completion_install_path(shell: &str) -> Option<PathBuf> {
+    let home = dirs::home_dir()?;
+    resolve_completion_install_path(shell, |var| std::env::var_os(var), &home)
+}
+
+/// Render the completion script cc-switch installs for `shell`
 ///
-/// For fish/zsh/bash the output is written directly to the shell's
-/// completion directory. For other shells the script is printed to stdout.
+/// Returns `None` for shells without a standard install path (elvish,
+/// powershell), whose scripts are printed instead.
 ///
 /// # Errors
-/// Returns error if shell is not supported or generation fails
-pub fn generate_completion(shell: &str) -> Result<()> {
+/// Returns error if shell is not supported
+pub fn render_completion(shell: &str) -> Result<Option<CompletionScript>> {
     use crate::cli::Cli;
 
     let mut app = Cli::command();
@@ -59,80 +226,175 @@ pub fn generate_completion(shell: &str) -> Result<()> {
         }
         "zsh" => {
             clap_complete::generate(clap_complete::shells::Zsh, &mut app, "cc-switch", &mut buf);
+            buf = add_zsh_dynamic_aliases(&buf, &alias_args(&app)).into_bytes();
         }
         "bash" => {
             clap_complete::generate(clap_complete::shells::Bash, &mut app, "cc-switch", &mut buf);
+            buf.extend_from_slice(bash_dynamic_aliases(&alias_args(&app)).as_bytes());
         }
-        "elvish" => {
+        "elvish" | "powershell" => return Ok(None),
+        _ => {
+            anyhow::bail!(
+                "Unsupported shell: {}. Supported shells: fish, zsh, bash, elvish, powershell",
+                shell
+            );
+        }
+    }
+
+    Ok(Some(buf))
+}
+
+/// Print the shell completion script to stdout
+///
+/// # Errors
+/// Returns error if shell is not supported or generation fails
+pub fn generate_completion(shell: &str) -> Result<()> {
+    use crate::cli::Cli;
+
+    let Some(buf) = render_completion(shell)? else {
+        let mut app = Cli::command();
+        if shell == "elvish" {
             clap_complete::generate(
                 clap_complete::shells::Elvish,
                 &mut app,
                 "cc-switch",
                 &mut std::io::stdout(),
             );
-            return Ok(());
-        }
-        "powershell" => {
+        } else {
             clap_complete::generate(
                 clap_complete::shells::PowerShell,
                 &mut app,
                 "cc-switch",
                 &mut std::io::stdout(),
             );
-            return Ok(());
         }
-        _ => {
-            anyhow::bail!(
-                "Unsupported shell: {}. Supported shells: fish, zsh, bash, elvish, powershell",
-                shell
+        return Ok(());
+    };
+
+    std::io::stdout().write_all(&buf)?;
+    Ok(())
+}
+
+/// Handle `cc-switch completion <shell> --install`: write the completion
+/// script to the shell's per-user completion path
+///
+/// Parent directories are created. For fish, the `cs.fish` and `cx.fish`
+/// files completing the aliases are written next to it. Existing files are
+/// only replaced with `force`.
+///
+/// # Errors
+/// Returns error if the shell has no standard location, a file exists
+/// without `force`, or a directory or file can't be written
+pub fn install_completion(shell: &str, force: bool) -> Result<()> {
+    let (Some(path), Some(script)) = (completion_install_path(shell), render_completion(shell)?)
+    else {
+        anyhow::bail!(
+            "--install supports fish, zsh and bash, not '{}'; run 'cc-switch completion {}' to print the script",
+            shell,
+            shell
+        );
+    };
+    let dir = path.parent().unwrap_or(Path::new("."));
+
+    let mut files = vec![(path.clone(), script)];
+    if shell == "fish" {
+        files.extend(
+            fish_companion_files()
+                .into_iter()
+                .map(|(name, content)| (dir.join(name), content.into_bytes())),
+        );
+    }
+    if !force && let Some((existing, _)) = files.iter().find(|(path, _)| path.exists()) {
+        anyhow::bail!(
+            "{} already exists; pass --force to overwrite it",
+            existing.display()
+        );
+    }
+
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Could not create completion directory {}", dir.display()))?;
+    for (path, content) in &files {
+        fs::write(path, content).with_context(|| format!("Could not write {}", path.display()))?;
+        println!("Wrote {shell} completion to {}", path.display());
+    }
+
+    if shell == "zsh" {
+        let in_fpath = std::env::var_os("FPATH")
+            .is_some_and(|fpath| std::env::split_paths(&fpath).any(|entry| entry == dir));
+        if !in_fpath {
+            println!(
+                "Add 'fpath=({} $fpath)' before 'compinit' in ~/.zshrc",
+                dir.display()
             );
         }
     }
+    Ok(())
+}
+
+/// Build the alias completion candidates for `storage`
+///
+/// The official pseudo-aliases (`cc`, `official`) come first when
+/// `include_official` is set, then 'current' if stored, so that when user
+/// types 'cc-switch use c' the 'current' configuration appears first,
+/// followed by all other stored aliases in alphabetical order.
+///
+/// Commands accepting the official aliases also accept URL variants, so
+/// `alias@variant` follows each alias that defines some in that case.
+pub fn alias_completion_candidates(storage: &ConfigStorage, include_official: bool) -> Vec<String> {
+    let mut candidates: Vec<String> = Vec::new();
+    if include_official {
+        candidates.extend(OFFICIAL_ALIASES.iter().map(|name| name.to_string()));
+    }
 
-    if let Some(path) = completion_install_path(shell) {
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
+    // BTreeMap keys are already sorted
+    let current = storage.configurations.get_key_value("current");
+    let others = storage
+        .configurations
+        .iter()
+        .filter(|(alias_name, _)| alias_name.as_str() != "current");
+    for (alias_name, config) in current.into_iter().chain(others) {
+        candidates.push(alias_name.clone());
+        if include_official {
+            candidates.extend(
+                config
+                    .url_variants
+                    .keys()
+                    .map(|variant| format!("{alias_name}@{variant}")),
+            );
         }
-        fs::write(&path, &buf)?;
-        eprintln!("Installed {shell} completion to {}", path.display());
-    } else {
-        std::io::stdout().write_all(&buf)?;
     }
 
-    Ok(())
+    candidates
 }
 
 /// List available configuration aliases for shell completion
 ///
 /// Outputs all stored configuration aliases, one per line
 /// Also includes 'cc' and 'official' as special aliases for resetting to default Claude
-/// For contexts where user types 'cc-switch use c' or similar, 'current' is prioritized first
+/// Used by commands whose official-alias policy is reset (`use`)
 ///
 /// # Errors
 /// Returns error if loading configurations fails
 pub fn list_aliases_for_completion() -> Result<()> {
     let storage = ConfigStorage::load()?;
-
-    // Always include 'cc' and 'official' for reset functionality
-    println!("cc");
-    println!("official");
-
-    // Prioritize 'current' first if it exists - this ensures when user types 'cc-switch use c'
-    // or 'cs use c', the 'current' configuration appears first in completion
-    if storage.configurations.contains_key("current") {
-        println!("current");
+    for alias_name in alias_completion_candidates(&storage, true) {
+        println!("{alias_name}");
     }
+    Ok(())
+}
 
-    // Output all other stored aliases in alphabetical order
-    let mut aliases: Vec<String> = storage.configurations.keys().cloned().collect();
-    aliases.sort();
-
-    for alias_name in aliases {
-        if alias_name != "current" {
-            println!("{alias_name}");
-        }
+/// List stored configuration aliases for shell completion
+///
+/// Like [`list_aliases_for_completion`] but without 'cc' and 'official', for
+/// commands that reject the reserved names (e.g. `remove`)
+///
+/// # Errors
+/// Returns error if loading configurations fails
+pub fn list_stored_aliases_for_completion() -> Result<()> {
+    let storage = ConfigStorage::load()?;
+    for alias_name in alias_completion_candidates(&storage, false) {
+        println!("{alias_name}");
     }
-
     Ok(())
 }
 
@@ -160,30 +422,39 @@ pub fn list_codex_aliases_for_completion() -> Result<()> {
 
 /// Generate custom fish completion with dynamic alias completion, writing to `out`.
 fn generate_fish_completion(app: &mut clap::Command, out: &mut Vec<u8>) {
+    let alias_args = alias_args(app);
+    let subcommands = subcommand_names(app);
     clap_complete::generate(clap_complete::shells::Fish, app, "cc-switch", out);
 
-    let extra = r#"
-# Custom completion for use subcommand with dynamic aliases
-complete -c cc-switch -n '__fish_cc_switch_using_subcommand use' -f -a '(cc-switch --list-aliases)' -d 'Configuration alias name'
-# Custom completion for switch subcommand (alias for use)
-complete -c cc-switch -n '__fish_cc_switch_using_subcommand switch' -f -a '(cc-switch --list-aliases)' -d 'Configuration alias name'
-# Custom completion for remove subcommand with dynamic aliases
-complete -c cc-switch -n '__fish_cc_switch_using_subcommand remove' -f -a '(cc-switch --list-aliases)' -d 'Configuration alias name'
+    // Dynamic alias arguments and subcommand lists, derived from the CLI
+    let mut generated =
+        String::from("\n# Dynamic alias completion (one line per alias argument)\n");
+    for arg in &alias_args {
+        let conditions = match arg.path.as_slice() {
+            [name] => format!("-n '__fish_cc_switch_using_subcommand {name}'"),
+            path => path
+                .iter()
+                .map(|name| format!("-n '__fish_seen_subcommand_from {name}'"))
+                .collect::<Vec<_>>()
+                .join(" "),
+        };
+        generated.push_str(&format!(
+            "complete -c cc-switch {conditions} -f -a '(cc-switch {})' -d '{}'\n",
+            arg.source.list_flag(),
+            arg.source.fish_description()
+        ));
+    }
+    generated.push_str(&format!(
+        "\n# Completion for the 'cs' alias\ncomplete -c cs -w cc-switch\ncomplete -c cs -n '__fish_use_subcommand' -f -a '{}' -d 'Subcommand'\n",
+        subcommands.join(" ")
+    ));
+    out.extend_from_slice(generated.as_bytes());
 
+    let extra = r#"
 # Completion for 'completion' subcommand with shell types
 complete -c cc-switch -n '__fish_cc_switch_using_subcommand completion' -f -a 'fish zsh bash elvish powershell' -d 'Shell type'
 complete -c cs -n '__fish_seen_subcommand_from completion' -f -a 'fish zsh bash elvish powershell' -d 'Shell type'
 
-# Custom completion for codex subcommand with dynamic aliases
-complete -c cc-switch -n '__fish_seen_subcommand_from codex' -n '__fish_seen_subcommand_from use' -f -a '(cc-switch --list-codex-aliases)' -d 'Codex configuration alias name'
-complete -c cc-switch -n '__fish_seen_subcommand_from codex' -n '__fish_seen_subcommand_from remove' -f -a '(cc-switch --list-codex-aliases)' -d 'Codex configuration alias name'
-
-# Completion for the 'cs' alias
-complete -c cs -w cc-switch
-
-# Completion for 'cs' alias subcommands
-complete -c cs -n '__fish_use_subcommand' -f -a 'add remove list set-default-dir completion alias use switch current codex daemon statusline' -d 'Subcommand'
-
 # Completion for 'daemon' subcommand
 complete -c cc-switch -n '__fish_cc_switch_using_subcommand daemon; and not __fish_seen_subcommand_from start stop status restart' -f -a 'start stop status restart' -d 'Daemon action'
 complete -c cc-switch -n '__fish_cc_switch_using_subcommand daemon; and __fish_seen_subcommand_from start' -l foreground -d 'Run in the foreground'
@@ -197,6 +468,8 @@ complete -c cc-switch -n '__fish_cc_switch_using_subcommand daemon; and __fish_s
 # Completion for 'cs list' subcommand
 complete -c cs -n '__fish_seen_subcommand_from list' -l plain -s p -d 'Plain text output'
 complete -c cs -n '__fish_seen_subcommand_from list' -l name -s n -d 'Show only name and URL'
+complete -c cs -n '__fish_seen_subcommand_from list' -l sort -d 'Sort order' -r -f -a 'name url model last-used'
+complete -c cs -n '__fish_seen_subcommand_from list' -l reverse -d 'Reverse the order'
 
 # Completion for 'cs daemon' subcommand
 complete -c cs -n '__fish_seen_subcommand_from daemon; and not __fish_seen_subcommand_from start stop status restart' -f -a 'start stop status restart' -d 'Daemon action'
@@ -212,82 +485,509 @@ complete -c cs -n '__fish_seen_subcommand_from daemon; and __fish_seen_subcomman
 complete -c cs -n '__fish_seen_subcommand_from statusline' -f -a 'install uninstall' -d 'Statusline action'
 
 # Completion for the 'cx' alias (cc-switch codex)
-complete -c cx -f
-complete -c cx -n '__fish_use_subcommand' -f -a 'add use remove list' -d 'Codex subcommand'
-complete -c cx -n '__fish_seen_subcommand_from use' -f -a '(cc-switch --list-codex-aliases)' -d 'Codex configuration alias name'
-complete -c cx -n '__fish_seen_subcommand_from remove' -f -a '(cc-switch --list-codex-aliases)' -d 'Codex configuration alias name'
-complete -c cx -n '__fish_seen_subcommand_from list' -f -l plain -s p -d 'Plain text output'
+"#;
+    out.extend_from_slice(extra.as_bytes());
+    out.extend_from_slice(cx_fish_completions(app).as_bytes());
+}
+
+/// fish completion lines for `cx` (`cc-switch codex`), with the codex
+/// subcommands and alias arguments taken from the CLI definition
+fn cx_fish_completions(app: &clap::Command) -> String {
+    let mut lines = String::from("complete -c cx -f\n");
+    if let Some(codex) = app.find_subcommand("codex") {
+        lines.push_str(&format!(
+            "complete -c cx -n '__fish_use_subcommand' -f -a '{}' -d 'Codex subcommand'\n",
+            subcommand_names(codex).join(" ")
+        ));
+    }
+    for arg in alias_args(app) {
+        if let [parent, name] = arg.path.as_slice()
+            && parent == "codex"
+        {
+            lines.push_str(&format!(
+                "complete -c cx -n '__fish_seen_subcommand_from {name}' -f -a '(cc-switch {})' -d '{}'\n",
+                arg.source.list_flag(),
+                arg.source.fish_description()
+            ));
+        }
+    }
+    lines.push_str(CX_FISH_FLAGS);
+    lines
+}
+
+/// Flag completions for `cx` subcommands
+const CX_FISH_FLAGS: &str = r#"complete -c cx -n '__fish_seen_subcommand_from list' -f -l plain -s p -d 'Plain text output'
 complete -c cx -n '__fish_seen_subcommand_from list' -f -l name -s n -d 'Show only name and auth mode'
 complete -c cx -n '__fish_seen_subcommand_from add' -f -l interactive -s i -d 'Interactive mode'
 complete -c cx -n '__fish_seen_subcommand_from add' -f -l from-file -d 'Import from auth.json (defaults to ~/.codex/auth.json if no path)' -r
 "#;
-    out.extend_from_slice(extra.as_bytes());
 
-    generate_cs_completion_file();
-    generate_cx_completion_file();
+/// Where the dynamic candidates of an alias argument come from
+///
+/// Chosen by the argument's `value_name` in the CLI definition, so a new
+/// subcommand gets dynamic alias completion in every shell just by naming
+/// its alias argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AliasSource {
+    /// Stored aliases, `cc`/`official` and `alias@variant` (`ALIAS`)
+    All,
+    /// Stored aliases only (`STORED_ALIAS`)
+    Stored,
+    /// Codex aliases (`CODEX_ALIAS`)
+    Codex,
 }
 
-/// Generate separate completion file for cs fish alias.
-///
-/// Fish only auto-loads completions from files named after the command,
-/// so `cs` needs its own `cs.fish`.
-fn generate_cs_completion_file() {
-    let home = dirs::home_dir().unwrap_or_else(|| std::path::PathBuf::from("~"));
-    let completions_dir = home.join(".config").join("fish").join("completions");
+impl AliasSource {
+    /// The source selected by an argument's value name, if any
+    pub fn from_value_name(name: &str) -> Option<Self> {
+        match name {
+            "ALIAS" => Some(Self::All),
+            "STORED_ALIAS" => Some(Self::Stored),
+            "CODEX_ALIAS" => Some(Self::Codex),
+            _ => None,
+        }
+    }
 
-    if !completions_dir.exists()
-        && let Err(e) = fs::create_dir_all(&completions_dir)
-    {
-        eprintln!("Warning: Could not create completions directory: {e}");
-        return;
+    /// Hidden cc-switch flag printing the candidates
+    pub fn list_flag(self) -> &'static str {
+        match self {
+            Self::All => "--list-aliases",
+            Self::Stored => "--list-stored-aliases",
+            Self::Codex => "--list-codex-aliases",
+        }
+    }
+
+    /// zsh helper function completing the candidates
+    pub fn zsh_function(self) -> &'static str {
+        match self {
+            Self::All => "_cc_switch_aliases",
+            Self::Stored => "_cc_switch_stored_aliases",
+            Self::Codex => "_cc_switch_codex_aliases",
+        }
+    }
+
+    /// Description shown next to the fish candidates
+    fn fish_description(self) -> &'static str {
+        match self {
+            Self::All | Self::Stored => "Configuration alias name",
+            Self::Codex => "Codex configuration alias name",
+        }
+    }
+}
+
+/// A positional alias argument found in the CLI definition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AliasArg {
+    /// Subcommand path below `cc-switch`, e.g. `["codex", "use"]`
+    pub path: Vec<String>,
+    /// Argument id, e.g. `alias_name`
+    pub id: String,
+    /// Argument help, which clap's zsh script shows as the description
+    pub help: String,
+    pub source: AliasSource,
+    /// Whether the argument takes several aliases
+    pub multiple: bool,
+}
+
+/// Collect every positional alias argument of `command`'s subcommands
+pub fn alias_args(command: &clap::Command) -> Vec<AliasArg> {
+    fn walk(command: &clap::Command, path: &mut Vec<String>, out: &mut Vec<AliasArg>) {
+        for sub in command.get_subcommands() {
+            path.push(sub.get_name().to_string());
+            for arg in sub.get_positionals() {
+                let source = arg
+                    .get_value_names()
+                    .and_then(|names| names.first())
+                    .and_then(|name| AliasSource::from_value_name(name.as_str()));
+                if let Some(source) = source {
+                    out.push(AliasArg {
+                        path: path.clone(),
+                        id: arg.get_id().to_string(),
+                        help: arg
+                            .get_help()
+                            .map(|help| help.to_string())
+                            .unwrap_or_default(),
+                        source,
+                        multiple: matches!(arg.get_action(), clap::ArgAction::Append),
+                    });
+                }
+            }
+            walk(sub, path, out);
+            path.pop();
+        }
+    }
+
+    let mut out = Vec::new();
+    walk(command, &mut Vec::new(), &mut out);
+    out
+}
+
+/// Names of the visible subcommands of `command`
+fn subcommand_names(command: &clap::Command) -> Vec<String> {
+    command
+        .get_subcommands()
+        .filter(|sub| !sub.is_hide_set())
+        .map(|sub| sub.get_name().to_string())
+        .collect()
+}
+
+/// Escape help text the way clap_complete's zsh generator does
+fn zsh_escape_help(help: &str) -> String {
+    help.replace('\\', "\\\\")
+        .replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:")
+        .replace('$', "\\$")
+        .replace('`', "\\`")
+        .replace('\n', " ")
+}
+
+/// zsh helper listing the candidates of `source`; it runs cc-switch on every
+/// completion (no completion cache) so aliases added or removed in this shell
+/// appear at once
+fn zsh_alias_function(source: AliasSource) -> String {
+    let function = source.zsh_function();
+    format!(
+        "(( $+functions[{function}] )) ||\n{function}() {{\n    local -a aliases\n    aliases=(${{(f)\"$(cc-switch {} 2>/dev/null)\"}})\n    compadd -a aliases\n}}\n",
+        source.list_flag()
+    )
+}
+
+/// Point the alias arguments of clap's zsh script at the dynamic alias helpers
+fn add_zsh_dynamic_aliases(script: &[u8], args: &[AliasArg]) -> String {
+    let mut script = String::from_utf8_lossy(script).into_owned();
+    let mut functions = String::from("\n");
+    for source in [AliasSource::All, AliasSource::Stored, AliasSource::Codex] {
+        if args.iter().any(|arg| arg.source == source) {
+            functions.push_str(&zsh_alias_function(source));
+        }
+    }
+    functions.push('\n');
+
+    for arg in args {
+        let spec = format!("{} -- {}:", arg.id, zsh_escape_help(&arg.help));
+        script = script.replace(
+            &format!("{spec}_default'"),
+            &format!("{spec}{}'", arg.source.zsh_function()),
+        );
+    }
+    let tail = "if [ \"$funcstack[1]\" = \"_cc-switch\" ]; then";
+    match script.rfind(tail) {
+        Some(at) => format!("{}{}{}", &script[..at], functions, &script[at..]),
+        None => script + &functions,
     }
+}
+
+/// bash wrapper completing alias arguments by running cc-switch on every
+/// completion (no caching) and deferring to clap's generated function
+/// otherwise
+fn bash_dynamic_aliases(args: &[AliasArg]) -> String {
+    let mut checks = String::new();
+    for arg in args {
+        let position = arg.path.len() + 1;
+        let mut conditions: Vec<String> = arg
+            .path
+            .iter()
+            .enumerate()
+            .map(|(i, name)| format!("\"${{COMP_WORDS[{}]}}\" == {name}", i + 1))
+            .collect();
+        conditions.push(if arg.multiple {
+            format!("${{COMP_CWORD}} -ge {position}")
+        } else {
+            format!("${{COMP_CWORD}} -eq {position}")
+        });
+        checks.push_str(&format!(
+            "        if [[ {} ]]; then\n            COMPREPLY=( $(compgen -W \"$(cc-switch {} 2>/dev/null)\" -- \"${{cur}}\") )\n            return 0\n        fi\n",
+            conditions.join(" && "),
+            arg.source.list_flag()
+        ));
+    }
+    format!(
+        r#"
+_cc_switch_dynamic() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}"
+    if [[ ${{cur}} != -* ]]; then
+{checks}    fi
+    _cc-switch "$@"
+}}
+
+if [[ "${{BASH_VERSINFO[0]}}" -eq 4 && "${{BASH_VERSINFO[1]}}" -ge 4 || "${{BASH_VERSINFO[0]}}" -gt 4 ]]; then
+    complete -F _cc_switch_dynamic -o nosort -o bashdefault -o default cc-switch
+else
+    complete -F _cc_switch_dynamic -o bashdefault -o default cc-switch
+fi
+"#
+    )
+}
 
+/// Completion files for the `cs` and `cx` fish aliases
+///
+/// Fish only auto-loads completions from files named after the command,
+/// and not for functions, so both aliases need their own file.
+fn fish_companion_files() -> Vec<CompanionFile> {
     let cs_content = r#"# Completion for 'cs' alias (cc-switch)
 complete -c cs -w cc-switch
 "#;
+    let cx_content = format!(
+        "# Completion for 'cx' alias (cc-switch codex)\n# cx is a fish function; disable file completion by default\n{}",
+        cx_fish_completions(&crate::cli::Cli::command())
+    );
+    vec![("cs.fish", cs_content.to_string()), ("cx.fish", cx_content)]
+}
 
-    let cs_path = completions_dir.join("cs.fish");
+/// `--list-aliases` must answer faster than this to keep completion responsive
+pub const LIST_ALIASES_THRESHOLD: std::time::Duration = std::time::Duration::from_millis(500);
 
-    if let Err(e) = fs::write(&cs_path, cs_content) {
-        eprintln!("Warning: Could not write cs.fish: {e}");
-    }
+pub use crate::reporting::DoctorCheck;
+use crate::reporting::DoctorReport;
 
-    eprintln!("Created completion file: {}", cs_path.display());
+/// Check that the completion file exists and matches what this binary generates
+///
+/// `installed` is the file content, or `None` when the file is missing.
+pub fn check_completion_file(
+    shell: &str,
+    path: &std::path::Path,
+    installed: Option<&[u8]>,
+    expected: &[u8],
+) -> Vec<DoctorCheck> {
+    let fix = format!("cc-switch completion {shell} --install --force");
+    let Some(installed) = installed else {
+        return vec![DoctorCheck::fail(
+            "Completion file",
+            format!("{} not found", path.display()),
+            fix,
+        )];
+    };
+
+    let exists = DoctorCheck::pass("Completion file", path.display().to_string());
+    let current = if installed == expected {
+        DoctorCheck::pass("Up to date", "matches the current cc-switch binary")
+    } else {
+        DoctorCheck::fail(
+            "Up to date",
+            "stale: generated by a different cc-switch version",
+            fix,
+        )
+    };
+    vec![exists, current]
 }
 
-/// Generate separate completion file for cx fish function
+/// Hint for add/remove when the installed completion file is stale
 ///
-/// Fish doesn't automatically load completion files for functions, only for commands.
-/// This creates ~/.config/fish/completions/cx.fish
-fn generate_cx_completion_file() {
-    // Fish uses ~/.config/fish/completions on all platforms (including macOS)
-    let home = dirs::home_dir().unwrap_or_else(|| std::path::PathBuf::from("~"));
-    let completions_dir = home.join(".config").join("fish").join("completions");
-
-    if !completions_dir.exists()
-        && let Err(e) = fs::create_dir_all(&completions_dir)
-    {
-        eprintln!("Warning: Could not create completions directory: {e}");
+/// Uses the same comparison as `completion doctor`. Returns `None` when the
+/// file is missing (completion not installed) or up to date, and also when
+/// the hint was already shown for this shell and cc-switch version according
+/// to `marker`; otherwise the marker is updated and the hint returned.
+pub fn take_stale_completion_hint(
+    shell: &str,
+    path: &std::path::Path,
+    expected: &[u8],
+    marker: &std::path::Path,
+) -> Option<String> {
+    let installed = fs::read(path).ok()?;
+    let stale = check_completion_file(shell, path, Some(&installed), expected)
+        .iter()
+        .any(|check| !check.passed);
+    if !stale {
+        return None;
+    }
+
+    let key = format!("{shell} {}", env!("CARGO_PKG_VERSION"));
+    if fs::read_to_string(marker).is_ok_and(|shown| shown.trim() == key) {
+        return None;
+    }
+    if let Some(parent) = marker.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let _ = fs::write(marker, &key);
+
+    Some(format!(
+        "Hint: {} was generated by another cc-switch version; run 'cc-switch completion {shell} --install --force' to refresh it",
+        path.display()
+    ))
+}
+
+/// Print the stale-completion hint for the user's `$SHELL`, at most once per version
+pub fn print_stale_completion_hint() {
+    let Some(shell) = std::env::var("SHELL").ok().and_then(|shell| {
+        std::path::Path::new(&shell)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+    }) else {
+        return;
+    };
+    let (Some(path), Ok(Some(expected)), Some(home)) = (
+        completion_install_path(&shell),
+        render_completion(&shell),
+        dirs::home_dir(),
+    ) else {
         return;
+    };
+    let marker = home.join(".claude").join("cc_switch_completion_hint");
+    if let Some(hint) = take_stale_completion_hint(&shell, &path, &expected, &marker) {
+        eprintln!("{hint}");
     }
+}
 
-    let cx_content = r#"# Completion for 'cx' alias (cc-switch codex)
-# cx is a fish function; disable file completion by default
-complete -c cx -f
-complete -c cx -n '__fish_use_subcommand' -f -a 'add use remove list' -d 'Codex subcommand'
-complete -c cx -n '__fish_seen_subcommand_from use' -f -a '(cc-switch --list-codex-aliases)' -d 'Codex configuration alias name'
-complete -c cx -n '__fish_seen_subcommand_from remove' -f -a '(cc-switch --list-codex-aliases)' -d 'Codex configuration alias name'
-complete -c cx -n '__fish_seen_subcommand_from list' -f -l plain -s p -d 'Plain text output'
-complete -c cx -n '__fish_seen_subcommand_from list' -f -l name -s n -d 'Show only name and auth mode'
-complete -c cx -n '__fish_seen_subcommand_from add' -f -l interactive -s i -d 'Interactive mode'
-complete -c cx -n '__fish_seen_subcommand_from add' -f -l from-file -d 'Import from auth.json (defaults to ~/.codex/auth.json if no path)' -r
-"#;
+/// Check that zsh's `$fpath` (as printed by `echo $fpath`) contains `dir`
+pub fn check_zsh_fpath(fpath_output: &str, dir: &std::path::Path) -> DoctorCheck {
+    let dir_str = dir.to_string_lossy();
+    if fpath_output
+        .split_whitespace()
+        .any(|entry| entry == dir_str)
+    {
+        DoctorCheck::pass("Shell loads completions", format!("{dir_str} is in $fpath"))
+    } else {
+        DoctorCheck::fail(
+            "Shell loads completions",
+            format!("{dir_str} is not in $fpath"),
+            format!("add 'fpath=({dir_str} $fpath)' before 'compinit' in ~/.zshrc"),
+        )
+    }
+}
 
-    let cx_path = completions_dir.join("cx.fish");
+/// Check that `cc-switch --list-aliases` answered within [`LIST_ALIASES_THRESHOLD`]
+pub fn check_list_aliases_duration(elapsed: std::time::Duration) -> DoctorCheck {
+    let detail = format!("cc-switch --list-aliases took {} ms", elapsed.as_millis());
+    if elapsed <= LIST_ALIASES_THRESHOLD {
+        DoctorCheck::pass("Alias listing speed", detail)
+    } else {
+        DoctorCheck::fail(
+            "Alias listing speed",
+            detail,
+            "check that the storage file (see `cc-switch version`) is on a local, responsive disk",
+        )
+    }
+}
+
+/// Check whether the shell picks up files from the completion directory
+fn check_shell_loads_completions(shell: &str, dir: &std::path::Path) -> DoctorCheck {
+    match shell {
+        "zsh" => {
+            let output = std::process::Command::new("zsh")
+                .args(["-ic", "echo $fpath"])
+                .stdin(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .output();
+            match output {
+                Ok(output) => check_zsh_fpath(&String::from_utf8_lossy(&output.stdout), dir),
+                Err(e) => DoctorCheck::fail(
+                    "Shell loads completions",
+                    format!("could not run zsh: {e}"),
+                    "install zsh or make sure it is in PATH",
+                ),
+            }
+        }
+        "fish" => {
+            // fish autoloads every file in ~/.config/fish/completions
+            if dir.is_dir() {
+                DoctorCheck::pass(
+                    "Shell loads completions",
+                    format!("{} is autoloaded by fish", dir.display()),
+                )
+            } else {
+                DoctorCheck::fail(
+                    "Shell loads completions",
+                    format!("{} does not exist", dir.display()),
+                    "cc-switch completion fish --install",
+                )
+            }
+        }
+        _ => {
+            const BASH_COMPLETION_PATHS: &[&str] = &[
+                "/usr/share/bash-completion/bash_completion",
+                "/etc/bash_completion",
+                "/usr/local/etc/profile.d/bash_completion.sh",
+                "/opt/homebrew/etc/profile.d/bash_completion.sh",
+            ];
+            match BASH_COMPLETION_PATHS
+                .iter()
+                .find(|p| std::path::Path::new(p).exists())
+            {
+                Some(found) => DoctorCheck::pass(
+                    "Shell loads completions",
+                    format!("bash-completion found at {found}"),
+                ),
+                None => DoctorCheck::fail(
+                    "Shell loads completions",
+                    "bash-completion package not found",
+                    format!(
+                        "install bash-completion, or add 'source {}' to ~/.bashrc",
+                        dir.join("cc-switch").display()
+                    ),
+                ),
+            }
+        }
+    }
+}
 
-    if let Err(e) = fs::write(&cx_path, cx_content) {
-        eprintln!("Warning: Could not write cx.fish: {e}");
+/// Time `cc-switch --list-aliases` using the current binary
+fn check_list_aliases_speed() -> DoctorCheck {
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            return DoctorCheck::fail(
+                "Alias listing speed",
+                format!("could not locate cc-switch binary: {e}"),
+                "reinstall cc-switch",
+            );
+        }
+    };
+    let start = std::time::Instant::now();
+    let status = std::process::Command::new(exe)
+        .arg("--list-aliases")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status();
+    match status {
+        Ok(status) if status.success() => check_list_aliases_duration(start.elapsed()),
+        Ok(status) => DoctorCheck::fail(
+            "Alias listing speed",
+            format!("cc-switch --list-aliases exited with {status}"),
+            "run 'cc-switch --list-aliases' to see the error",
+        ),
+        Err(e) => DoctorCheck::fail(
+            "Alias listing speed",
+            format!("could not run cc-switch --list-aliases: {e}"),
+            "reinstall cc-switch",
+        ),
     }
+}
 
-    eprintln!("\nCreated completion file: {}", cx_path.display());
+/// Check the installed completion setup for `shell` and print ✓/✗ per check
+///
+/// With `json`, the report is printed as JSON instead.
+///
+/// # Errors
+/// Returns error if the shell is not fish, zsh or bash
+pub fn run_completion_doctor(shell: &str, json: bool) -> Result<()> {
+    let (Some(path), Some(expected)) = (completion_install_path(shell), render_completion(shell)?)
+    else {
+        anyhow::bail!(
+            "completion doctor supports fish, zsh and bash, not '{}'",
+            shell
+        );
+    };
+
+    let installed = fs::read(&path).ok();
+    let mut checks = check_completion_file(shell, &path, installed.as_deref(), &expected);
+    if let Some(dir) = path.parent() {
+        checks.push(check_shell_loads_completions(shell, dir));
+    }
+    checks.push(check_list_aliases_speed());
+
+    let report = DoctorReport {
+        subject: format!("{shell} completion setup"),
+        checks,
+        paths: None,
+    };
+    if json {
+        println!("{}", crate::reporting::to_json(&report)?);
+    } else {
+        for line in report.render_human() {
+            println!("{line}");
+        }
+    }
+    Ok(())
 }
@@ -0,0 +1,277 @@
+//! `cc-switch current`: which configuration the environment actually uses.
+//!
+//! The Anthropic variables of this process win; without them the `env`
+//! block of Claude's settings.json is checked. The values found are matched
+//! against the stored configurations, strongest evidence first.
+
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+use crate::cli::display_utils::format_token_for_display;
+use crate::config::types::{ClaudeSettings, ConfigStorage, Configuration};
+use crate::reporting::StatusReport;
+
+/// Type alias for environment variables by name
+pub type EnvVars = BTreeMap<String, String>;
+
+/// Variables whose presence means a configuration is in effect
+const SELECTING_VARS: [&str; 3] = [
+    "ANTHROPIC_BASE_URL",
+    "ANTHROPIC_AUTH_TOKEN",
+    "ANTHROPIC_API_KEY",
+];
+
+/// Variables set by `cc-switch use` and `env`
+fn relevant_vars(vars: impl IntoIterator<Item = (String, String)>) -> EnvVars {
+    let names = crate::config::managed_env_var_names();
+    vars.into_iter()
+        .filter(|(name, value)| names.contains(&name.as_str()) && !value.is_empty())
+        .collect()
+}
+
+fn selects_configuration(env: &EnvVars) -> bool {
+    SELECTING_VARS.iter().any(|name| env.contains_key(*name))
+}
+
+fn same_url(a: &str, b: &str) -> bool {
+    a.trim_end_matches('/') == b.trim_end_matches('/')
+}
+
+/// A stored configuration and the evidence that identified it
+struct Match<'a> {
+    config: &'a Configuration,
+    by: &'static str,
+}
+
+/// The stored configuration `env` selects and what identified it
+///
+/// Token and URL together identify a configuration best, then the token
+/// alone, then a URL no other configuration shares. Ties are broken by the
+/// model, then by the alias marker `cc-switch` sets.
+fn find_match<'a>(env: &EnvVars, storage: &'a ConfigStorage) -> Option<Match<'a>> {
+    let token = env
+        .get("ANTHROPIC_AUTH_TOKEN")
+        .or_else(|| env.get("ANTHROPIC_API_KEY"));
+    let url = env.get("ANTHROPIC_BASE_URL");
+    let token_matches =
+        |config: &Configuration| token.is_some_and(|token| config.auth_env_pair().1 == token);
+    let url_matches = |config: &Configuration| {
+        url.is_some_and(|url| {
+            same_url(&config.url, url)
+                || config
+                    .url_variants
+                    .values()
+                    .any(|variant| same_url(variant, url))
+        })
+    };
+
+    let by_token_and_url: Vec<&Configuration> = storage
+        .configurations
+        .values()
+        .filter(|config| token_matches(config) && url_matches(config))
+        .collect();
+    let by_token: Vec<&Configuration> = storage
+        .configurations
+        .values()
+        .filter(|config| token_matches(config))
+        .collect();
+    let by_url: Vec<&Configuration> = storage
+        .configurations
+        .values()
+        .filter(|config| url_matches(config))
+        .collect();
+
+    if !by_token_and_url.is_empty() {
+        return Some(Match {
+            config: best_of(by_token_and_url, env),
+            by: "token+url",
+        });
+    }
+    if !by_token.is_empty() {
+        return Some(Match {
+            config: best_of(by_token, env),
+            by: "token",
+        });
+    }
+    match by_url.as_slice() {
+        [config] if token.is_none() => Some(Match { config, by: "url" }),
+        _ => None,
+    }
+}
+
+/// The candidate whose model matches, else the marked alias, else the first
+fn best_of<'a>(candidates: Vec<&'a Configuration>, env: &EnvVars) -> &'a Configuration {
+    let model = env.get("ANTHROPIC_MODEL");
+    let marker = env.get("CC_SWITCH_CURRENT_ALIAS");
+    candidates
+        .iter()
+        .find(|config| model.is_some() && config.model.as_ref() == model)
+        .or_else(|| {
+            candidates
+                .iter()
+                .find(|config| Some(&config.alias_name) == marker)
+        })
+        .unwrap_or(&candidates[0])
+}
+
+/// What is active given this process's variables and settings.json's `env`
+pub fn detect_active(
+    process_env: &EnvVars,
+    settings_env: &EnvVars,
+    storage: &ConfigStorage,
+) -> StatusReport {
+    let (source, env) = if selects_configuration(process_env) {
+        ("environment", process_env)
+    } else if selects_configuration(settings_env) {
+        ("settings.json", settings_env)
+    } else {
+        return StatusReport {
+            state: "official".to_string(),
+            active_alias: None,
+            matched_by: None,
+            source: None,
+            url: None,
+            token: None,
+            model: None,
+        };
+    };
+
+    let matched = find_match(env, storage);
+    StatusReport {
+        state: if matched.is_some() { "alias" } else { "custom" }.to_string(),
+        active_alias: matched.as_ref().map(|m| m.config.alias_name.clone()),
+        matched_by: matched.as_ref().map(|m| m.by.to_string()),
+        source: Some(source.to_string()),
+        url: env.get("ANTHROPIC_BASE_URL").cloned(),
+        token: env
+            .get("ANTHROPIC_AUTH_TOKEN")
+            .or_else(|| env.get("ANTHROPIC_API_KEY"))
+            .map(|token| format_token_for_display(token)),
+        model: env.get("ANTHROPIC_MODEL").cloned(),
+    }
+}
+
+/// [`detect_active`] for this process and the configured settings.json
+///
+/// # Errors
+/// Returns error if settings.json exists but cannot be read
+pub fn current_status(storage: &ConfigStorage) -> Result<StatusReport> {
+    let custom_dir = storage.get_claude_settings_dir().map(|s| s.as_str());
+    let settings = ClaudeSettings::load(custom_dir)?;
+    Ok(detect_active(
+        &relevant_vars(std::env::vars()),
+        &relevant_vars(settings.env),
+        storage,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(vars: &[[&str; 2]]) -> EnvVars {
+        vars.iter()
+            .map(|[name, value]| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn config(alias: &str, token: &str, url: &str) -> Configuration {
+        Configuration {
+            alias_name: alias.to_string(),
+            token: token.to_string(),
+            url: url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn storage() -> ConfigStorage {
+        let mut storage = ConfigStorage::default();
+        storage.add_configuration(config("work", "sk-work", "https://work.example.com"));
+        storage.add_configuration(config("relay", "sk-relay", "https://relay.example.com"));
+        let mut relay_opus = config("relay-opus", "sk-relay", "https://relay.example.com");
+        relay_opus.model = Some("opus".to_string());
+        storage.add_configuration(relay_opus);
+        storage
+    }
+
+    /// `alias by evidence`, or `None` when nothing matched
+    fn matched(report: &StatusReport) -> Option<String> {
+        let alias = report.active_alias.as_ref()?;
+        Some(format!("{alias} by {}", report.matched_by.as_ref()?))
+    }
+
+    #[test]
+    fn process_environment_matches_by_token_and_url() {
+        let process = env(&[
+            ["ANTHROPIC_AUTH_TOKEN", "sk-work"],
+            ["ANTHROPIC_BASE_URL", "https://work.example.com/"],
+        ]);
+        let report = detect_active(&process, &EnvVars::new(), &storage());
+        assert_eq!(report.state, "alias");
+        assert_eq!(matched(&report).as_deref(), Some("work by token+url"));
+        assert_eq!(report.source.as_deref(), Some("environment"));
+        assert_eq!(report.token.as_deref(), Some("sk-***"));
+    }
+
+    #[test]
+    fn weaker_evidence_and_tie_breaks() {
+        let storage = storage();
+
+        // Token alone, URL changed since
+        let process = env(&[
+            ["ANTHROPIC_AUTH_TOKEN", "sk-work"],
+            ["ANTHROPIC_BASE_URL", "https://moved.example.com"],
+        ]);
+        let report = detect_active(&process, &EnvVars::new(), &storage);
+        assert_eq!(matched(&report).as_deref(), Some("work by token"));
+
+        // URL alone, only when no token contradicts it
+        let settings = env(&[["ANTHROPIC_BASE_URL", "https://work.example.com"]]);
+        let report = detect_active(&EnvVars::new(), &settings, &storage);
+        assert_eq!(matched(&report).as_deref(), Some("work by url"));
+        assert_eq!(report.source.as_deref(), Some("settings.json"));
+
+        // Two configurations share token and URL: the model decides
+        let relay = [
+            ["ANTHROPIC_AUTH_TOKEN", "sk-relay"],
+            ["ANTHROPIC_BASE_URL", "https://relay.example.com"],
+        ];
+        let mut process = env(&relay);
+        process.insert("ANTHROPIC_MODEL".to_string(), "opus".to_string());
+        let report = detect_active(&process, &EnvVars::new(), &storage);
+        assert_eq!(report.active_alias.as_deref(), Some("relay-opus"));
+
+        // ... or the alias marker
+        let mut process = env(&relay);
+        process.insert("CC_SWITCH_CURRENT_ALIAS".to_string(), "relay".to_string());
+        let report = detect_active(&process, &EnvVars::new(), &storage);
+        assert_eq!(report.active_alias.as_deref(), Some("relay"));
+    }
+
+    #[test]
+    fn unmatched_values_are_custom_and_nothing_is_official() {
+        let storage = storage();
+        let process = env(&[
+            ["ANTHROPIC_AUTH_TOKEN", "sk-unknown"],
+            ["ANTHROPIC_BASE_URL", "https://work.example.com"],
+        ]);
+        let report = detect_active(&process, &EnvVars::new(), &storage);
+        assert_eq!(report.state, "custom");
+        assert_eq!(matched(&report), None);
+
+        // A model alone selects no configuration
+        let process = env(&[["ANTHROPIC_MODEL", "opus"]]);
+        let report = detect_active(&process, &EnvVars::new(), &storage);
+        assert_eq!(report.state, "official");
+        assert_eq!(report.source, None);
+    }
+
+    #[test]
+    fn process_environment_wins_over_settings() {
+        let process = env(&[["ANTHROPIC_AUTH_TOKEN", "sk-relay"]]);
+        let settings = env(&[["ANTHROPIC_AUTH_TOKEN", "sk-work"]]);
+        let report = detect_active(&process, &settings, &storage());
+        assert_eq!(report.source.as_deref(), Some("environment"));
+        assert_eq!(report.active_alias.as_deref(), Some("relay"));
+    }
+}
@@ -0,0 +1,538 @@
+//! `cc-switch doctor`: diagnose common setup problems
+//!
+//! Every [`Check`] is a named function of a [`DoctorContext`] holding the
+//! paths and environment it inspects, so each check can be tested against
+//! temporary files. [`run_doctor`] runs them all against the real setup and
+//! fails when any check fails; warnings don't change the exit code.
+
+use anyhow::{Result, anyhow};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::config::types::{ANTHROPIC_ENV_VARS, ConfigStorage, StorageMode};
+use crate::config::validate_base_url;
+use crate::reporting::{DoctorCheck, DoctorReport, PathsReport};
+use crate::utils::StoragePathSource;
+
+/// Environment variables the terminal check looks at
+const TERMINAL_ENV_VARS: [&str; 2] = ["NO_COLOR", "TERM"];
+
+/// What the checks inspect
+pub struct DoctorContext {
+    /// Configuration storage file
+    pub storage_path: PathBuf,
+    /// What chose `storage_path`
+    pub storage_source: StoragePathSource,
+    /// Claude settings.json launches update
+    pub settings_path: PathBuf,
+    /// Claude binary launches run (see `choose_claude_binary`)
+    pub claude_binary: PathBuf,
+    /// Values of [`TERMINAL_ENV_VARS`] that are set
+    pub env: BTreeMap<String, String>,
+}
+
+impl DoctorContext {
+    /// Context for the current user and process
+    ///
+    /// # Errors
+    /// Returns error if the home directory cannot be found
+    pub fn detect() -> Result<Self> {
+        let (storage_path, storage_source) = crate::utils::config_storage_location()?;
+        // A broken storage file is reported by its own check; fall back to
+        // the default settings location meanwhile
+        let storage = std::fs::read_to_string(&storage_path)
+            .ok()
+            .and_then(|content| crate::config::migrations::parse(&content).ok())
+            .unwrap_or_default();
+        let env_override = std::env::var(crate::platform::CLAUDE_BIN_ENV).ok();
+        let (claude_binary, _) = crate::platform::choose_claude_binary(
+            env_override.as_deref(),
+            storage.claude_binary.as_deref(),
+        );
+        Ok(DoctorContext {
+            storage_path,
+            storage_source,
+            settings_path: crate::utils::get_claude_settings_path(
+                storage.claude.settings_dir.as_deref(),
+            )?,
+            claude_binary,
+            env: TERMINAL_ENV_VARS
+                .iter()
+                .filter_map(|name| Some((name.to_string(), std::env::var(name).ok()?)))
+                .collect(),
+        })
+    }
+
+    /// The storage, backup, lock and settings files the checks look at
+    pub fn paths(&self) -> PathsReport {
+        PathsReport::for_storage(&self.storage_path, &self.settings_path)
+    }
+
+    /// The parsed storage file; `None` when it doesn't exist yet
+    fn storage(&self) -> Option<Result<ConfigStorage>> {
+        if !self.storage_path.exists() {
+            return None;
+        }
+        Some(
+            std::fs::read_to_string(&self.storage_path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| crate::config::migrations::parse(&content)),
+        )
+    }
+}
+
+/// Outcome of a check, before it is given its name
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    Pass(String),
+    /// Detail and fix of something worth attention
+    Warn(String, String),
+    /// Detail and fix of a broken setup
+    Fail(String, String),
+}
+
+/// Body of a check
+pub type CheckFn = fn(&DoctorContext) -> Verdict;
+
+/// A named diagnostic
+pub struct Check {
+    pub name: &'static str,
+    pub run: CheckFn,
+}
+
+impl Check {
+    /// Run the check against `context`
+    pub fn evaluate(&self, context: &DoctorContext) -> DoctorCheck {
+        match (self.run)(context) {
+            Verdict::Pass(detail) => DoctorCheck::pass(self.name, detail),
+            Verdict::Warn(detail, fix) => DoctorCheck::warn(self.name, detail, fix),
+            Verdict::Fail(detail, fix) => DoctorCheck::fail(self.name, detail, fix),
+        }
+    }
+}
+
+/// All checks, in the order they are reported
+pub fn checks() -> Vec<Check> {
+    vec![
+        Check {
+            name: "Claude binary",
+            run: check_claude_binary,
+        },
+        Check {
+            name: "Configuration storage",
+            run: check_storage,
+        },
+        Check {
+            name: "Storage permissions",
+            run: check_storage_permissions,
+        },
+        Check {
+            name: "Stored configurations",
+            run: check_configurations,
+        },
+        Check {
+            name: "Claude settings",
+            run: check_settings,
+        },
+        Check {
+            name: "Terminal",
+            run: check_terminal,
+        },
+    ]
+}
+
+/// Run every check against `context`
+pub fn diagnose(context: &DoctorContext) -> DoctorReport {
+    DoctorReport {
+        subject: "cc-switch setup".to_string(),
+        checks: checks()
+            .iter()
+            .map(|check| check.evaluate(context))
+            .collect(),
+        paths: Some(context.paths()),
+    }
+}
+
+/// Handle `cc-switch doctor`
+///
+/// # Errors
+/// Returns error when at least one check fails
+pub fn run_doctor(json: bool) -> Result<()> {
+    let report = diagnose(&DoctorContext::detect()?);
+    if json {
+        println!("{}", crate::reporting::to_json(&report)?);
+    } else {
+        for line in report.render_human() {
+            println!("{line}");
+        }
+    }
+    match report.failures() {
+        0 => Ok(()),
+        failed => Err(anyhow!("{failed} doctor check(s) failed")),
+    }
+}
+
+fn check_claude_binary(context: &DoctorContext) -> Verdict {
+    let binary = context.claude_binary.display().to_string();
+    let Ok(path) = which::which(&context.claude_binary) else {
+        return Verdict::Fail(
+            format!("'{binary}' not found on PATH"),
+            "install Claude (npm install -g @anthropic-ai/claude-code) or run \
+             `cc-switch config set claude-path <PATH>`"
+                .to_string(),
+        );
+    };
+    match std::process::Command::new(&path).arg("--version").output() {
+        Ok(output) if output.status.success() => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let version = stdout.lines().next().unwrap_or("").trim();
+            Verdict::Pass(format!("{} ({version})", path.display()))
+        }
+        Ok(output) => Verdict::Warn(
+            format!("{} --version exited with {}", path.display(), output.status),
+            "reinstall Claude or point CLAUDE_BINARY at a working binary".to_string(),
+        ),
+        Err(e) => Verdict::Fail(
+            format!("{} cannot be run: {e}", path.display()),
+            "reinstall Claude or point CLAUDE_BINARY at a working binary".to_string(),
+        ),
+    }
+}
+
+fn check_storage(context: &DoctorContext) -> Verdict {
+    let mut path = context.storage_path.display().to_string();
+    if let Some(origin) = context.storage_source.origin() {
+        path.push_str(&format!(" (from {origin})"));
+    }
+    match context.storage() {
+        None => Verdict::Warn(
+            format!("{path} does not exist yet"),
+            "cc-switch add <alias> <token> <url>".to_string(),
+        ),
+        Some(Ok(storage)) => Verdict::Pass(format!(
+            "{path} ({} configuration(s))",
+            storage.configurations.len()
+        )),
+        Some(Err(e)) => Verdict::Fail(
+            format!("{path} cannot be read: {e}"),
+            "cc-switch restore-backup, or fix the JSON by hand".to_string(),
+        ),
+    }
+}
+
+#[cfg(unix)]
+fn check_storage_permissions(context: &DoctorContext) -> Verdict {
+    use std::os::unix::fs::PermissionsExt;
+
+    let Ok(metadata) = std::fs::metadata(&context.storage_path) else {
+        return Verdict::Pass("no storage file yet".to_string());
+    };
+    let mode = metadata.permissions().mode() & 0o777;
+    let fix = format!("chmod 600 {}", context.storage_path.display());
+    if mode & 0o600 != 0o600 {
+        Verdict::Fail(
+            format!("mode {mode:o}: cc-switch cannot read and write it"),
+            fix,
+        )
+    } else if mode & 0o077 != 0 {
+        Verdict::Warn(
+            format!("mode {mode:o}: other users can read the stored tokens"),
+            fix,
+        )
+    } else {
+        Verdict::Pass(format!("mode {mode:o}"))
+    }
+}
+
+#[cfg(not(unix))]
+fn check_storage_permissions(_context: &DoctorContext) -> Verdict {
+    Verdict::Pass("not checked on this platform".to_string())
+}
+
+fn check_configurations(context: &DoctorContext) -> Verdict {
+    let Some(Ok(storage)) = context.storage() else {
+        return Verdict::Pass("none stored".to_string());
+    };
+    let problems: Vec<String> = storage
+        .configurations
+        .values()
+        .flat_map(|config| {
+            let mut problems = Vec::new();
+            if !config.has_credential() {
+                problems.push(format!("{}: empty token", config.alias_name));
+            }
+            if let Err(e) = validate_base_url(&config.url) {
+                problems.push(format!("{}: {e}", config.alias_name));
+            }
+            problems
+        })
+        .collect();
+    if problems.is_empty() {
+        Verdict::Pass(format!(
+            "{} configuration(s) have a token and a valid URL",
+            storage.configurations.len()
+        ))
+    } else {
+        Verdict::Fail(problems.join("; "), "cc-switch edit <alias>".to_string())
+    }
+}
+
+fn check_settings(context: &DoctorContext) -> Verdict {
+    let path = context.settings_path.display();
+    let content = match std::fs::read_to_string(&context.settings_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Verdict::Pass(format!("{path} not present"));
+        }
+        Err(e) => {
+            return Verdict::Fail(
+                format!("{path} cannot be read: {e}"),
+                format!("check the permissions of {path}"),
+            );
+        }
+    };
+    let settings: serde_json::Value = match serde_json::from_str(&content) {
+        Ok(settings) => settings,
+        Err(e) => {
+            return Verdict::Fail(
+                format!("{path} is not valid JSON: {e}"),
+                format!("fix the JSON syntax in {path}"),
+            );
+        }
+    };
+
+    let stale: Vec<&str> = ANTHROPIC_ENV_VARS
+        .iter()
+        .copied()
+        .filter(|name| settings["env"].get(name).is_some())
+        .collect();
+    let config_mode = matches!(
+        context.storage(),
+        Some(Ok(ConfigStorage {
+            default_storage_mode: Some(StorageMode::Config),
+            ..
+        }))
+    );
+    if stale.is_empty() || config_mode {
+        Verdict::Pass(format!("{path} parses"))
+    } else {
+        Verdict::Warn(
+            format!(
+                "{path} sets {}, which override launches in env mode",
+                stale.join(", ")
+            ),
+            "cc-switch unuse --persistent".to_string(),
+        )
+    }
+}
+
+fn check_terminal(context: &DoctorContext) -> Verdict {
+    let term = context.env.get("TERM").map(String::as_str);
+    if matches!(term, None | Some("" | "dumb")) {
+        return Verdict::Warn(
+            format!("TERM is {}", term.unwrap_or("unset")),
+            "run the interactive menu in a full terminal, or use `cc-switch use <alias>`"
+                .to_string(),
+        );
+    }
+    if context.env.contains_key("NO_COLOR") {
+        return Verdict::Warn(
+            "NO_COLOR is set, so output is not colored".to_string(),
+            "unset NO_COLOR to get colors back".to_string(),
+        );
+    }
+    Verdict::Pass(format!("TERM={}", term.unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn context(dir: &TempDir) -> DoctorContext {
+        DoctorContext {
+            storage_path: dir.path().join("storage.json"),
+            storage_source: StoragePathSource::Default,
+            settings_path: dir.path().join("settings.json"),
+            claude_binary: dir.path().join("missing-claude"),
+            env: BTreeMap::from([("TERM".to_string(), "xterm-256color".to_string())]),
+        }
+    }
+
+    fn write_storage(context: &DoctorContext, storage: serde_json::Value) {
+        std::fs::write(&context.storage_path, storage.to_string()).unwrap();
+    }
+
+    fn is_fail(verdict: &Verdict) -> bool {
+        matches!(verdict, Verdict::Fail(..))
+    }
+
+    fn is_warn(verdict: &Verdict) -> bool {
+        matches!(verdict, Verdict::Warn(..))
+    }
+
+    #[test]
+    fn missing_claude_binary_fails() {
+        let dir = TempDir::new().unwrap();
+        let verdict = check_claude_binary(&context(&dir));
+        assert!(is_fail(&verdict), "got {verdict:?}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn claude_binary_reports_its_version() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        let mut context = context(&dir);
+        context.claude_binary = dir.path().join("claude");
+        std::fs::write(&context.claude_binary, "#!/bin/sh\necho '2.0.1 (Claude)'\n").unwrap();
+        std::fs::set_permissions(
+            &context.claude_binary,
+            std::fs::Permissions::from_mode(0o755),
+        )
+        .unwrap();
+        match check_claude_binary(&context) {
+            Verdict::Pass(detail) => assert!(detail.ends_with("(2.0.1 (Claude))"), "{detail}"),
+            other => panic!("expected a pass, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn storage_check_counts_or_rejects() {
+        let dir = TempDir::new().unwrap();
+        let mut context = context(&dir);
+        assert!(is_warn(&check_storage(&context)));
+
+        write_storage(
+            &context,
+            serde_json::json!({
+                "configurations": {
+                    "work": { "alias_name": "work", "token": "sk-w", "url": "https://w.example" }
+                }
+            }),
+        );
+        match check_storage(&context) {
+            Verdict::Pass(detail) => assert!(detail.ends_with("(1 configuration(s))")),
+            other => panic!("expected a pass, got {other:?}"),
+        }
+        context.storage_source = StoragePathSource::FileEnv;
+        match check_storage(&context) {
+            Verdict::Pass(detail) => assert!(
+                detail.ends_with("storage.json (from CC_SWITCH_CONFIG) (1 configuration(s))"),
+                "{detail}"
+            ),
+            other => panic!("expected a pass, got {other:?}"),
+        }
+
+        std::fs::write(&context.storage_path, "{ not json").unwrap();
+        assert!(is_fail(&check_storage(&context)));
+        // The configuration check leaves a broken file to the storage check
+        assert!(!is_fail(&check_configurations(&context)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn shared_storage_file_warns() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        let context = context(&dir);
+        write_storage(&context, serde_json::json!({ "configurations": {} }));
+        let set_mode = |mode| {
+            std::fs::set_permissions(&context.storage_path, std::fs::Permissions::from_mode(mode))
+                .unwrap()
+        };
+
+        set_mode(0o600);
+        assert_eq!(
+            check_storage_permissions(&context),
+            Verdict::Pass("mode 600".to_string())
+        );
+        set_mode(0o644);
+        assert!(is_warn(&check_storage_permissions(&context)));
+        set_mode(0o200);
+        assert!(is_fail(&check_storage_permissions(&context)));
+    }
+
+    #[test]
+    fn malformed_configurations_are_named() {
+        let dir = TempDir::new().unwrap();
+        let context = context(&dir);
+        write_storage(
+            &context,
+            serde_json::json!({
+                "configurations": {
+                    "good": { "alias_name": "good", "token": "sk-g", "url": "https://g.example" },
+                    "blank": { "alias_name": "blank", "token": "", "url": "https://b.example" },
+                    "typo": { "alias_name": "typo", "token": "sk-t", "url": "htps//t.example" }
+                }
+            }),
+        );
+        match check_configurations(&context) {
+            Verdict::Fail(detail, _) => {
+                assert!(detail.contains("blank: empty token"), "{detail}");
+                assert!(detail.contains("typo: "), "{detail}");
+                assert!(!detail.contains("good"), "{detail}");
+            }
+            other => panic!("expected a failure, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn settings_check_parses_and_flags_anthropic_keys() {
+        let dir = TempDir::new().unwrap();
+        let context = context(&dir);
+        assert!(matches!(check_settings(&context), Verdict::Pass(_)));
+
+        std::fs::write(&context.settings_path, "{ broken").unwrap();
+        assert!(is_fail(&check_settings(&context)));
+
+        std::fs::write(
+            &context.settings_path,
+            serde_json::json!({
+                "env": { "ANTHROPIC_BASE_URL": "https://stale.example", "EDITOR": "vim" }
+            })
+            .to_string(),
+        )
+        .unwrap();
+        match check_settings(&context) {
+            Verdict::Warn(detail, _) => assert!(detail.contains("sets ANTHROPIC_BASE_URL,")),
+            other => panic!("expected a warning, got {other:?}"),
+        }
+
+        // Expected when launches write settings.json
+        write_storage(
+            &context,
+            serde_json::json!({ "configurations": {}, "default_storage_mode": "config" }),
+        );
+        assert!(matches!(check_settings(&context), Verdict::Pass(_)));
+    }
+
+    #[test]
+    fn limited_terminals_warn() {
+        let dir = TempDir::new().unwrap();
+        let mut context = context(&dir);
+        assert_eq!(
+            check_terminal(&context),
+            Verdict::Pass("TERM=xterm-256color".to_string())
+        );
+
+        context.env.insert("NO_COLOR".to_string(), "1".to_string());
+        assert!(is_warn(&check_terminal(&context)));
+
+        context.env.insert("TERM".to_string(), "dumb".to_string());
+        assert!(is_warn(&check_terminal(&context)));
+        context.env.clear();
+        assert!(is_warn(&check_terminal(&context)));
+    }
+
+    #[test]
+    fn failed_checks_fail_the_report() {
+        let dir = TempDir::new().unwrap();
+        let report = diagnose(&context(&dir));
+        assert_eq!(report.checks.len(), checks().len());
+        assert_eq!(report.failures(), 1, "{:?}", report.checks);
+        assert_eq!(report.checks[0].name, "Claude binary");
+    }
+}
@@ -0,0 +1,301 @@
+//! `cc-switch edit <alias> --editor`: edit one configuration as JSON in
+//! `$VISUAL` / `$EDITOR`.
+//!
+//! The configuration is written to a private temp file (0600, removed
+//! afterwards since it holds the token), re-parsed and validated when the
+//! editor exits, and saved after printing a field diff. A changed alias is
+//! a rename.
+
+use anyhow::{Context, Result, anyhow};
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+use tempfile::NamedTempFile;
+
+use crate::cli::display_utils::format_token_for_display;
+use crate::config::types::{ConfigStorage, Configuration};
+use crate::config::{parse_label_color, validate_alias_name};
+use crate::interactive::Prompter;
+
+/// Editor command from `$VISUAL`, then `$EDITOR`, else a platform default
+fn editor_command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| {
+            if cfg!(windows) {
+                "notepad".to_string()
+            } else {
+                "vi".to_string()
+            }
+        })
+}
+
+/// Run `editor` on `path` and wait for it; the editor value may carry
+/// arguments (e.g. `code --wait`)
+fn run_editor(editor: &str, path: &Path) -> Result<()> {
+    let status = if cfg!(windows) {
+        let mut parts = editor.split_whitespace();
+        let program = parts.next().unwrap_or("notepad");
+        Command::new(program).args(parts).arg(path).status()
+    } else {
+        Command::new("sh")
+            .arg("-c")
+            .arg(format!("{editor} \"$1\""))
+            .arg("sh")
+            .arg(path)
+            .status()
+    }
+    .with_context(|| format!("Failed to start editor '{}'", editor))?;
+
+    if !status.success() {
+        anyhow::bail!("Editor '{}' exited with {}", editor, status);
+    }
+    Ok(())
+}
+
+/// Temp file readable only by the current user (tempfile creates it 0600
+/// on Unix), removed when dropped
+fn secret_temp_file(contents: &str) -> Result<NamedTempFile> {
+    let mut file = tempfile::Builder::new()
+        .prefix("cc-switch-edit-")
+        .suffix(".json")
+        .tempfile()
+        .context("Failed to create temporary file for editing")?;
+    file.write_all(contents.as_bytes())
+        .context("Failed to write temporary file for editing")?;
+    file.flush()?;
+    Ok(file)
+}
+
+/// Parse and validate an edited configuration
+///
+/// # Errors
+/// Returns error if the JSON is invalid or a field breaks the usual rules
+/// (alias name, URL, credentials, color)
+pub fn parse_edited_config(content: &str) -> Result<Configuration> {
+    let config: Configuration =
+        serde_json::from_str(content).map_err(|e| anyhow!("Invalid configuration JSON: {}", e))?;
+
+    validate_alias_name(&config.alias_name)?;
+    url::Url::parse(&config.url)
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+        .ok_or_else(|| anyhow!("Invalid url '{}': expected an http(s) URL", config.url))?;
+    for (name, url) in &config.url_variants {
+        url::Url::parse(url).map_err(|e| anyhow!("Invalid URL variant '{}': {}", name, e))?;
+    }
+    match (config.token.is_empty(), config.api_key.as_deref()) {
+        (true, None | Some("")) if config.token_command.is_none() => {
+            anyhow::bail!("Either token, api_key or token_command must be set")
+        }
+        (false, Some(key)) if !key.is_empty() => {
+            anyhow::bail!("Only one of token and api_key may be set")
+        }
+        _ => {}
+    }
+    if config.token_command.is_some() && !config.auth_env_pair().1.is_empty() {
+        anyhow::bail!("token_command can't be set together with token or api_key")
+    }
+    if let Some(color) = &config.color {
+        parse_label_color(color)?;
+    }
+    if let Some(probe) = &config.quota_probe {
+        url::Url::parse(&probe.url)
+            .map_err(|e| anyhow!("Invalid quota_probe url '{}': {}", probe.url, e))?;
+    }
+    Ok(config)
+}
+
+/// `field: old -> new` lines for every field that differs, tokens masked
+pub fn config_diff(old: &Configuration, new: &Configuration) -> Vec<String> {
+    let show = |field: &str, value: Option<String>| match value {
+        Some(value) if field == "token" => format_token_for_display(&value),
+        Some(value) => value,
+        None => "[unset]".to_string(),
+    };
+
+    let mut lines = Vec::new();
+    if old.alias_name != new.alias_name {
+        lines.push(format!("alias: {} -> {}", old.alias_name, new.alias_name));
+    }
+    for field in Configuration::FIELD_NAMES {
+        let (before, after) = (old.field_value(field), new.field_value(field));
+        if before != after {
+            lines.push(format!(
+                "{field}: {} -> {}",
+                show(field, before),
+                show(field, after)
+            ));
+        }
+    }
+    if old.url_variants != new.url_variants {
+        lines.push("url_variants: changed".to_string());
+    }
+    if old.launch_profiles != new.launch_profiles {
+        lines.push("launch_profiles: changed".to_string());
+    }
+    if old.quota_probe != new.quota_probe {
+        lines.push("quota_probe: changed".to_string());
+    }
+    lines
+}
+
+/// Edit `alias_name` in the user's editor and save the result
+///
+/// # Errors
+/// Returns error if the alias is missing, the editor fails, the edit is
+/// abandoned after a parse error, or saving fails
+pub fn edit_in_editor(
+    storage: &mut ConfigStorage,
+    alias_name: &str,
+    prompter: &mut dyn Prompter,
+) -> Result<()> {
+    let original = crate::config::resolve_stored_alias(storage, alias_name)?.clone();
+    let initial = serde_json::to_string_pretty(&original)?;
+    let file = secret_temp_file(&initial)?;
+    let editor = editor_command();
+
+    let edited = loop {
+        run_editor(&editor, file.path())?;
+        let content = std::fs::read_to_string(file.path())
+            .context("Failed to read the edited configuration")?;
+        match parse_edited_config(&content) {
+            Ok(config) => break config,
+            Err(e) => {
+                // The erroneous content stays in the file for the next round
+                eprintln!("Error: {e}");
+                if !prompter.is_interactive()
+                    || !prompter.confirm("Re-open the editor to fix it? [y/N] ")?
+                {
+                    anyhow::bail!("Edit of '{}' discarded", alias_name);
+                }
+            }
+        }
+    };
+    drop(file);
+
+    let diff = config_diff(&original, &edited);
+    if diff.is_empty() {
+        println!("No changes to '{alias_name}'");
+        return Ok(());
+    }
+
+    let renamed_over = |storage: &ConfigStorage| {
+        edited.alias_name != alias_name && storage.get_configuration(&edited.alias_name).is_some()
+    };
+    let overwrite = renamed_over(storage);
+    if overwrite {
+        if !prompter.is_interactive() {
+            anyhow::bail!(
+                "Configuration '{}' already exists; rename aborted",
+                edited.alias_name
+            );
+        }
+        if !prompter.confirm(&format!(
+            "Configuration '{}' already exists. Overwrite it? [y/N] ",
+            edited.alias_name
+        ))? {
+            println!("Edit cancelled");
+            return Ok(());
+        }
+    }
+
+    for line in &diff {
+        println!("  {line}");
+    }
+    let new_alias = edited.alias_name.clone();
+    storage.with_lock(|storage| {
+        // Another run may have added the new alias since the user was asked
+        if renamed_over(storage) {
+            if !overwrite {
+                anyhow::bail!(
+                    "Configuration '{}' already exists; rename aborted",
+                    edited.alias_name
+                );
+            }
+            storage.remove_configuration(&edited.alias_name);
+        }
+        storage.update_configuration(alias_name, edited.clone())
+    })?;
+    println!("Configuration '{new_alias}' saved");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Configuration {
+        Configuration {
+            alias_name: "work".to_string(),
+            token: "sk-ant-REDACTED".to_string(),
+            url: "https://api.example.com".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn round_trip_parses() {
+        let json = serde_json::to_string_pretty(&sample()).unwrap();
+        let parsed = parse_edited_config(&json).unwrap();
+        assert!(config_diff(&sample(), &parsed).is_empty());
+    }
+
+    #[test]
+    fn invalid_edits_are_rejected() {
+        let with = |edit: fn(&mut Configuration)| {
+            let mut config = sample();
+            edit(&mut config);
+            parse_edited_config(&serde_json::to_string(&config).unwrap())
+        };
+        assert!(with(|c| c.alias_name = "has space".to_string()).is_err());
+        assert!(with(|c| c.alias_name = "cc".to_string()).is_err());
+        assert!(with(|c| c.url = "not a url".to_string()).is_err());
+        assert!(with(|c| c.token = String::new()).is_err());
+        assert!(with(|c| c.api_key = Some("sk-key".to_string())).is_err());
+        assert!(with(|c| c.color = Some("mauve".to_string())).is_err());
+        assert!(
+            with(|c| {
+                c.quota_probe = Some(crate::config::QuotaProbe {
+                    url: "not a url".to_string(),
+                    used: "x-quota-used".to_string(),
+                    limit: None,
+                })
+            })
+            .is_err()
+        );
+        assert!(parse_edited_config(r#"{"alias_name": "work", "url": 5}"#).is_err());
+        assert!(parse_edited_config(r#"{"max_thinking_tokens": -1}"#).is_err());
+    }
+
+    #[test]
+    fn diff_lists_changed_fields_with_masked_token() {
+        let mut edited = sample();
+        edited.alias_name = "work2".to_string();
+        edited.model = Some("opus".to_string());
+        edited.token = "sk-ant-REDACTED".to_string();
+        assert_eq!(
+            config_diff(&sample(), &edited),
+            vec![
+                "alias: work -> work2".to_string(),
+                "token: sk-ant-01234...ijklmnop -> sk-ant-zyxwv...hgfedcba".to_string(),
+                "model: [unset] -> opus".to_string(),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn temp_file_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let file = secret_temp_file("{}").unwrap();
+        let mode = std::fs::metadata(file.path()).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        let path = file.path().to_path_buf();
+        drop(file);
+        assert!(!path.exists());
+    }
+}
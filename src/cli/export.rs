@@ -0,0 +1,167 @@
+//! `cc-switch export`: one configuration as a Claude settings-style JSON
+//! file, the shape `cc-switch add --from-file` imports.
+//!
+//! Only the fields that live in the `env` section round-trip; tags, colors,
+//! launch profiles, URL variants and the quota probe are cc-switch's own and
+//! are not part of the file.
+
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::config::types::Configuration;
+
+/// `{"env": {...}}` for `config`, with every optional field that is set
+///
+/// Numeric settings are written as JSON numbers, as the importer expects.
+pub fn settings_json(config: &Configuration) -> Value {
+    let mut env = Map::new();
+    let (auth_var, auth_value) = config.auth_env_pair();
+    env.insert(auth_var.to_string(), auth_value.into());
+    env.insert("ANTHROPIC_BASE_URL".to_string(), config.url.as_str().into());
+
+    let strings = [
+        ("ANTHROPIC_MODEL", &config.model),
+        ("ANTHROPIC_SMALL_FAST_MODEL", &config.small_fast_model),
+        (
+            "ANTHROPIC_DEFAULT_SONNET_MODEL",
+            &config.anthropic_default_sonnet_model,
+        ),
+        (
+            "ANTHROPIC_DEFAULT_OPUS_MODEL",
+            &config.anthropic_default_opus_model,
+        ),
+        (
+            "ANTHROPIC_DEFAULT_HAIKU_MODEL",
+            &config.anthropic_default_haiku_model,
+        ),
+        (
+            "CLAUDE_CODE_SUBAGENT_MODEL",
+            &config.claude_code_subagent_model,
+        ),
+        ("CLAUDE_CODE_EFFORT_LEVEL", &config.claude_code_effort_level),
+    ];
+    for (name, value) in strings {
+        if let Some(value) = value {
+            env.insert(name.to_string(), value.as_str().into());
+        }
+    }
+
+    let numbers = [
+        ("ANTHROPIC_MAX_THINKING_TOKENS", config.max_thinking_tokens),
+        ("API_TIMEOUT_MS", config.api_timeout_ms),
+        (
+            "CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC",
+            config.claude_code_disable_nonessential_traffic,
+        ),
+        (
+            "CLAUDE_CODE_EXPERIMENTAL_AGENT_TEAMS",
+            config.claude_code_experimental_agent_teams,
+        ),
+        (
+            "CLAUDE_CODE_DISABLE_1M_CONTEXT",
+            config.claude_code_disable_1m_context,
+        ),
+        (
+            "CLAUDE_CODE_DISABLE_NONSTREAMING_FALLBACK",
+            config.claude_code_disable_nonstreaming_fallback,
+        ),
+        ("DISABLE_PROMPT_CACHING", config.disable_prompt_caching),
+        (
+            "CLAUDE_CODE_DISABLE_EXPERIMENTAL_BETAS",
+            config.claude_code_disable_experimental_betas,
+        ),
+        ("DISABLE_AUTOUPDATER", config.disable_autoupdater),
+    ];
+    for (name, value) in numbers {
+        if let Some(value) = value {
+            env.insert(name.to_string(), value.into());
+        }
+    }
+
+    for (name, value) in &config.extra_env {
+        env.entry(name.clone())
+            .or_insert_with(|| value.as_str().into());
+    }
+
+    let mut settings = Map::new();
+    settings.insert("env".to_string(), Value::Object(env));
+    Value::Object(settings)
+}
+
+/// File to write for `--output`: `<dir>/<alias>.json` when `output` is a
+/// directory, otherwise `output` itself
+pub fn export_path(output: &Path, alias: &str) -> PathBuf {
+    if output.is_dir() {
+        output.join(format!("{alias}.json"))
+    } else {
+        output.to_path_buf()
+    }
+}
+
+/// Write the exported JSON to `path`, readable only by the current user
+///
+/// # Errors
+/// Returns error if the file cannot be written
+pub fn write_export(path: &Path, json: &str) -> Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    writeln!(file, "{json}").with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_stay_numbers_and_unset_fields_are_left_out() {
+        let config = Configuration {
+            alias_name: "work".to_string(),
+            token: "sk-work".to_string(),
+            url: "https://api.work.example".to_string(),
+            model: Some("opus".to_string()),
+            api_timeout_ms: Some(600000),
+            ..Default::default()
+        };
+        let json = settings_json(&config);
+        let env = json["env"].as_object().unwrap();
+        assert_eq!(env["ANTHROPIC_AUTH_TOKEN"], "sk-work");
+        assert_eq!(env["API_TIMEOUT_MS"], 600000);
+        assert_eq!(env.len(), 4);
+    }
+
+    #[test]
+    fn api_key_is_exported_instead_of_token() {
+        let config = Configuration {
+            alias_name: "work".to_string(),
+            api_key: Some("sk-key".to_string()),
+            url: "https://api.work.example".to_string(),
+            ..Default::default()
+        };
+        let env = settings_json(&config)["env"].clone();
+        assert_eq!(env["ANTHROPIC_API_KEY"], "sk-key");
+        assert!(env.get("ANTHROPIC_AUTH_TOKEN").is_none());
+    }
+
+    #[test]
+    fn directory_output_gets_alias_file_name() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert_eq!(
+            export_path(dir.path(), "work"),
+            dir.path().join("work.json")
+        );
+        let file = dir.path().join("custom.json");
+        assert_eq!(export_path(&file, "work"), file);
+    }
+}
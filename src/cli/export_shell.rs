@@ -0,0 +1,267 @@
+//! `cc-switch export-shell`: a self-contained shell script with one function
+//! per configuration, for machines where cc-switch is not installed.
+//!
+//! Each `claude_<alias>` function runs Claude with that configuration's
+//! variables and clears the other variables cc-switch manages;
+//! `claude_reset` unsets all of them in the current shell.
+//!
+//! [`env_statements`] backs `cc-switch env`, which prints the same variables
+//! for a single configuration as statements to evaluate in the current shell.
+
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+use crate::config::config::{EnvironmentConfig, managed_env_var_names, unset_statements};
+use crate::config::types::{ANTHROPIC_ENV_VARS, Configuration};
+use crate::interactive::interactive::shell_quote;
+
+/// Value written instead of a token when secrets are not requested
+pub const SECRET_PLACEHOLDER: &str = "REPLACE_WITH_TOKEN";
+
+/// Variables holding credentials, replaced unless `--show-secrets` is given
+const SECRET_VARS: [&str; 2] = ["ANTHROPIC_AUTH_TOKEN", "ANTHROPIC_API_KEY"];
+
+/// `alias` with every character outside `[A-Za-z0-9_]` replaced by `_`
+pub fn shell_identifier(alias: &str) -> String {
+    alias
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Shell function name for an alias: `claude_` plus the alias as a
+/// [`shell_identifier`]
+pub fn function_name(alias: &str) -> String {
+    format!("claude_{}", shell_identifier(alias))
+}
+
+/// Quote a value for fish, where `\` and `'` are the only escapes inside
+/// single quotes
+pub(crate) fn fish_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Quote a value as a PowerShell double-quoted string, escaping the
+/// characters that would otherwise expand or terminate it
+fn powershell_quote(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        if matches!(c, '`' | '"' | '$') {
+            quoted.push('`');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Statements that give the current shell the environment of `env`
+///
+/// Managed variables that `env` does not set are unset first, so evaluating
+/// the output after another configuration leaves nothing of it behind.
+///
+/// # Arguments
+/// * `shell` - Shell type (bash, zsh, sh, fish, powershell)
+///
+/// # Errors
+/// Returns error if the shell is not supported
+pub fn env_statements(env: &EnvironmentConfig, shell: &str) -> Result<Vec<String>> {
+    let unset: Vec<&str> = managed_env_var_names()
+        .into_iter()
+        .filter(|name| !env.env_vars.contains_key(*name))
+        .collect();
+    let mut statements = if unset.is_empty() {
+        Vec::new()
+    } else {
+        unset_statements(shell, &unset)?
+    };
+    for (name, value) in env.as_env_tuples() {
+        statements.push(match shell {
+            "fish" => format!("set -gx {name} {}", fish_quote(&value)),
+            "powershell" | "pwsh" => format!("$env:{name} = {}", powershell_quote(&value)),
+            _ => format!("export {name}={}", shell_quote(&value)),
+        });
+    }
+    Ok(statements)
+}
+
+/// Render the export script for `shell` (`sh`, `bash`, `zsh` or `fish`)
+///
+/// # Errors
+/// Returns error if the shell is not supported
+pub fn render_export_shell(
+    configs: &BTreeMap<String, Configuration>,
+    shell: &str,
+    show_secrets: bool,
+) -> Result<String> {
+    let fish = match shell {
+        "sh" | "bash" | "zsh" => false,
+        "fish" => true,
+        _ => anyhow::bail!(
+            "Unsupported shell: {}. Supported shells: sh, bash, zsh, fish",
+            shell
+        ),
+    };
+
+    let mut out = String::new();
+    if fish {
+        out.push_str("# Generated by cc-switch export-shell; load with: source claude-envs.fish\n");
+    } else {
+        out.push_str("# Generated by cc-switch export-shell; load with: . ./claude-envs.sh\n");
+    }
+
+    for (alias, config) in configs {
+        let mut env = if show_secrets {
+            EnvironmentConfig::from_config_resolved(config)?.env_vars
+        } else {
+            EnvironmentConfig::from_config(config).env_vars
+        };
+        if !show_secrets {
+            for name in SECRET_VARS {
+                if let Some(value) = env.get_mut(name) {
+                    *value = SECRET_PLACEHOLDER.to_string();
+                }
+            }
+        }
+        let unset: Vec<&str> = ANTHROPIC_ENV_VARS
+            .iter()
+            .copied()
+            .filter(|name| !env.contains_key(*name))
+            .collect();
+
+        out.push('\n');
+        out.push_str(&format!("# {alias}\n"));
+        if fish {
+            out.push_str(&format!("function {}\n", function_name(alias)));
+            for (name, value) in &env {
+                out.push_str(&format!("    set -lx {name} {}\n", fish_quote(value)));
+            }
+            let removals: String = unset.iter().map(|name| format!("-u {name} ")).collect();
+            out.push_str(&format!("    env {removals}claude $argv\nend\n"));
+        } else {
+            // The subshell body keeps the variables out of the calling shell
+            out.push_str(&format!("{}() (\n", function_name(alias)));
+            if !unset.is_empty() {
+                out.push_str(&format!("    unset {}\n", unset.join(" ")));
+            }
+            for (name, value) in &env {
+                out.push_str(&format!("    export {name}={}\n", shell_quote(value)));
+            }
+            out.push_str("    exec claude \"$@\"\n)\n");
+        }
+    }
+
+    out.push('\n');
+    out.push_str("# Remove every variable set by the functions above\n");
+    if fish {
+        out.push_str("function claude_reset\n");
+        out.push_str(&format!(
+            "    set -e {}\nend\n",
+            ANTHROPIC_ENV_VARS.join(" ")
+        ));
+    } else {
+        out.push_str("claude_reset() {\n");
+        out.push_str(&format!("    unset {}\n}}\n", ANTHROPIC_ENV_VARS.join(" ")));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tricky_configs() -> BTreeMap<String, Configuration> {
+        let config = Configuration {
+            alias_name: "my-relay".to_string(),
+            token: "sk-it's $HOME".to_string(),
+            url: "https://relay.example/a b".to_string(),
+            model: Some("claude \"opus\" $(rm -rf)".to_string()),
+            ..Default::default()
+        };
+        BTreeMap::from([(config.alias_name.clone(), config)])
+    }
+
+    #[test]
+    fn function_names_are_valid_identifiers() {
+        assert_eq!(function_name("work"), "claude_work");
+        assert_eq!(function_name("my-relay.eu"), "claude_my_relay_eu");
+    }
+
+    #[test]
+    fn posix_snapshot_quotes_values() {
+        let script = render_export_shell(&tricky_configs(), "sh", true).unwrap();
+        let function: Vec<&str> = script
+            .lines()
+            .skip_while(|line| !line.starts_with("claude_my_relay() ("))
+            .take_while(|line| *line != ")")
+            .collect();
+        assert_eq!(
+            function[2..],
+            [
+                "    export ANTHROPIC_AUTH_TOKEN='sk-it'\\''s $HOME'",
+                "    export ANTHROPIC_BASE_URL='https://relay.example/a b'",
+                "    export ANTHROPIC_MODEL='claude \"opus\" $(rm -rf)'",
+                "    exec claude \"$@\"",
+            ]
+        );
+        assert!(function[1].starts_with("    unset ANTHROPIC_API_KEY ANTHROPIC_SMALL_FAST_MODEL"));
+        assert!(!function[1].contains("ANTHROPIC_MODEL "));
+        assert!(script.contains("claude_reset() {\n    unset ANTHROPIC_AUTH_TOKEN "));
+    }
+
+    #[test]
+    fn fish_snapshot_quotes_values() {
+        let script = render_export_shell(&tricky_configs(), "fish", true).unwrap();
+        assert!(script.contains("function claude_my_relay\n"));
+        assert!(script.contains("    set -lx ANTHROPIC_AUTH_TOKEN 'sk-it\\'s $HOME'\n"));
+        assert!(script.contains("    set -lx ANTHROPIC_MODEL 'claude \"opus\" $(rm -rf)'\n"));
+        assert!(script.contains("    env -u ANTHROPIC_API_KEY "));
+        assert!(script.contains(" claude $argv\nend\n"));
+        assert!(script.contains("function claude_reset\n    set -e ANTHROPIC_AUTH_TOKEN "));
+        assert_eq!(fish_quote(r"a\b"), r"'a\\b'");
+    }
+
+    #[test]
+    fn tokens_are_placeholders_without_show_secrets() {
+        let script = render_export_shell(&tricky_configs(), "bash", false).unwrap();
+        assert!(!script.contains("sk-it"));
+        assert!(script.contains(&format!(
+            "export ANTHROPIC_AUTH_TOKEN={SECRET_PLACEHOLDER}\n"
+        )));
+    }
+
+    #[test]
+    fn unsupported_shell_errors() {
+        assert!(render_export_shell(&BTreeMap::new(), "tcsh", false).is_err());
+        assert!(env_statements(&EnvironmentConfig::empty(), "tcsh").is_err());
+    }
+
+    #[test]
+    fn env_statements_quote_values_per_shell() {
+        let configs = tricky_configs();
+        let env = EnvironmentConfig::from_config(&configs["my-relay"]).with_alias("my-relay");
+
+        let posix = env_statements(&env, "bash").unwrap();
+        assert!(posix[0].starts_with("unset ANTHROPIC_API_KEY "));
+        assert!(posix.contains(&"export ANTHROPIC_AUTH_TOKEN='sk-it'\\''s $HOME'".to_string()));
+        assert!(posix.contains(&"export CC_SWITCH_CURRENT_ALIAS=my-relay".to_string()));
+
+        let fish = env_statements(&env, "fish").unwrap();
+        assert!(fish.contains(&"set -e ANTHROPIC_API_KEY".to_string()));
+        assert!(fish.contains(&"set -gx ANTHROPIC_AUTH_TOKEN 'sk-it\\'s $HOME'".to_string()));
+
+        let powershell = env_statements(&env, "powershell").unwrap();
+        assert!(
+            powershell
+                .contains(&"$env:ANTHROPIC_MODEL = \"claude `\"opus`\" `$(rm -rf)\"".to_string())
+        );
+    }
+
+    #[test]
+    fn env_statements_for_empty_config_only_unset() {
+        let statements = env_statements(&EnvironmentConfig::empty(), "zsh").unwrap();
+        assert_eq!(statements.len(), 1);
+        assert!(statements[0].starts_with("unset ANTHROPIC_AUTH_TOKEN "));
+        assert!(statements[0].ends_with(" CC_SWITCH_CURRENT_ALIAS"));
+    }
+}
@@ -1,19 +1,34 @@
 use crate::cli::completion::{
-    generate_completion, list_aliases_for_completion, list_codex_aliases_for_completion,
+    generate_completion, install_completion, list_aliases_for_completion,
+    list_codex_aliases_for_completion, list_stored_aliases_for_completion,
+    print_stale_completion_hint, run_completion_doctor,
 };
 use crate::cli::{Cli, Commands};
 use crate::codex::{
     handle_codex_add, handle_codex_interactive, handle_codex_list, handle_codex_remove,
     handle_codex_use,
 };
-use crate::config::types::{AddCommandParams, ClaudeSettings, StorageMode};
-use crate::config::{ConfigStorage, Configuration, EnvironmentConfig, validate_alias_name};
+use crate::config::types::{AddCommandParams, ClaudeSettings, ParsedConfigFile, StorageMode};
+use crate::config::{
+    AliasTarget, ConfigStorage, Configuration, EnvironmentConfig, ModelNotice, ModelNoticeLevel,
+    TokenWarning, resolve_alias_target, resolve_stored_alias, validate_alias_name,
+    validate_base_url,
+};
+use crate::exit_code::{ExitCode, config_not_found, conflict, invalid, not_found};
 use crate::interactive::{
-    handle_interactive_selection, launch_claude_with_env, read_input, read_sensitive_input,
+    ExecLauncher, LaunchOptions, LaunchPlan, Selection, StdinPrompter,
+    handle_interactive_selection, launch_selection, read_input, read_sensitive_input,
 };
-use anyhow::{Result, anyhow};
+use anyhow::{Context, Result, anyhow};
 use clap::Parser;
+use std::collections::BTreeMap;
 use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A `(field, value)` pair for [`handle_edit_fields`]
+pub type FieldUpdate = (String, String);
+/// Additional environment variables of a configuration, by name
+type ExtraEnv = BTreeMap<String, String>;
 
 /// Parse storage mode string to StorageMode enum
 ///
@@ -25,68 +40,193 @@ use std::fs;
 fn parse_storage_mode(store_str: &str) -> Result<StorageMode> {
     match store_str.to_lowercase().as_str() {
         "env" => Ok(StorageMode::Env),
-        "config" => Ok(StorageMode::Config),
-        _ => Err(anyhow!(
-            "Invalid storage mode '{}'. Use 'env' or 'config'",
+        "config" | "settings" => Ok(StorageMode::Config),
+        _ => Err(invalid(format!(
+            "Invalid storage mode '{}'. Use 'env' or 'config' (alias 'settings')",
             store_str
+        ))),
+    }
+}
+
+/// JSON object, as found in a configuration file
+type JsonObject = serde_json::Map<String, serde_json::Value>;
+
+/// What a JSON value is, for error messages
+fn json_kind(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "a boolean",
+        serde_json::Value::Number(_) => "a number",
+        serde_json::Value::String(_) => "a string",
+        serde_json::Value::Array(_) => "an array",
+        serde_json::Value::Object(_) => "an object",
+    }
+}
+
+/// String variable `key` of an `env` section
+///
+/// # Errors
+/// Returns error if the variable is present but not a string
+fn env_string(env: &JsonObject, key: &str, file_path: &str) -> Result<Option<String>> {
+    match env.get(key) {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(serde_json::Value::String(value)) => Ok(Some(value.clone())),
+        Some(other) => Err(anyhow!(
+            "{} in file '{}' must be a string, found {}",
+            key,
+            file_path,
+            json_kind(other)
         )),
     }
 }
 
+/// Numeric variable `key` of an `env` section
+///
+/// Numbers written as strings, as Claude Code's settings.json does, are
+/// accepted too.
+///
+/// # Errors
+/// Returns error if the variable is present but not a non-negative integer
+/// that fits in 32 bits
+fn env_number(env: &JsonObject, key: &str, file_path: &str) -> Result<Option<u32>> {
+    let value = match env.get(key) {
+        None | Some(serde_json::Value::Null) => return Ok(None),
+        Some(value) => value,
+    };
+    let number = match value {
+        serde_json::Value::Number(number) => number.as_u64(),
+        serde_json::Value::String(text) => text.trim().parse::<u64>().ok(),
+        other => anyhow::bail!(
+            "{} in file '{}' must be a number, found {}",
+            key,
+            file_path,
+            json_kind(other)
+        ),
+    };
+    number
+        .and_then(|number| u32::try_from(number).ok())
+        .map(Some)
+        .ok_or_else(|| {
+            anyhow!(
+                "{} in file '{}' must be a whole number from 0 to {}, found {}",
+                key,
+                file_path,
+                u32::MAX,
+                value
+            )
+        })
+}
+
+/// Whether `value` is a credential or field as `list` masks it
+fn is_masked(value: &str) -> bool {
+    value == crate::cli::display_utils::HIDDEN_PLACEHOLDER
+        || value.ends_with("***")
+        || value.contains("...")
+}
+
+/// Whether `value` is shaped like a serialized [`Configuration`]
+fn is_serialized_configuration(value: &serde_json::Value) -> bool {
+    value.get("url").is_some() && value.get("token").is_some()
+}
+
 /// Parse a configuration from a JSON file
 ///
+/// Two shapes are accepted and told apart by their keys:
+/// - a settings file with an `env` section, as written by `export` or used
+///   by Claude Code. Keys without a dedicated field are kept in
+///   `extra_env`; keys whose value could not be kept are listed in
+///   `ignored_keys`.
+/// - a serialized configuration, as shown by `list --reveal`: either the
+///   configuration itself or a `list` output (array, or the map by alias of
+///   older versions) holding exactly one.
+///
 /// # Arguments
 /// * `file_path` - Path to the JSON configuration file
 ///
-/// # Returns
-/// Result containing a tuple of configuration values (token, url, and optional fields)
-///
 /// # Errors
-/// Returns error if file cannot be read or parsed
-#[allow(clippy::type_complexity)]
+/// Returns error if file cannot be read or parsed, or a field has the
+/// wrong type
 fn parse_config_from_file(
     file_path: &str,
-) -> Result<(
-    String,
-    Option<String>,
-    String,
-    Option<String>,
-    Option<String>,
-    Option<u32>,
-    Option<u32>,
-    Option<u32>,
-    Option<String>,
-    Option<String>,
-    Option<String>,
-    Option<String>,
-    Option<u32>,
-    Option<String>,
-    Option<u32>,
-    Option<u32>,
-    Option<u32>,
-)> {
+    strict_json: bool,
+    validate_url: bool,
+) -> Result<ParsedConfigFile> {
     let file_content = fs::read_to_string(file_path)
         .map_err(|e| anyhow!("Failed to read file '{}': {}", file_path, e))?;
 
+    // Comments and trailing commas are blanked in place, so error positions
+    // still point into the original file
+    let file_content =
+        if !strict_json && crate::utils::is_lenient_json_path(std::path::Path::new(file_path)) {
+            crate::utils::strip_json_comments(&file_content)
+        } else {
+            file_content
+        };
     let json: serde_json::Value = serde_json::from_str(&file_content)
         .map_err(|e| anyhow!("Failed to parse JSON from file '{}': {}", file_path, e))?;
 
-    let env = json.get("env").and_then(|v| v.as_object()).ok_or_else(|| {
-        anyhow!(
-            "File '{}' does not contain a valid 'env' section",
-            file_path
-        )
-    })?;
-
-    let auth_token = env
-        .get("ANTHROPIC_AUTH_TOKEN")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
+    if let Some(env) = json.get("env") {
+        let env = env.as_object().ok_or_else(|| {
+            anyhow!(
+                "The 'env' section of file '{}' must be an object, found {}",
+                file_path,
+                json_kind(env)
+            )
+        })?;
+        return parse_settings_env(env, file_path, validate_url);
+    }
+    if is_serialized_configuration(&json) {
+        let config: Configuration = serde_json::from_str(&file_content)
+            .map_err(|e| anyhow!("Invalid configuration in file '{}': {}", file_path, e))?;
+        return check_serialized_configuration(config, file_path, validate_url);
+    }
+    // `list` output: an array, or a map by alias from older versions
+    let entries: Vec<&serde_json::Value> = match &json {
+        serde_json::Value::Array(items) => items.iter().collect(),
+        serde_json::Value::Object(map) => map.values().collect(),
+        _ => Vec::new(),
+    };
+    if !entries.is_empty()
+        && entries
+            .iter()
+            .all(|entry| is_serialized_configuration(entry))
+    {
+        let mut configs = entries
+            .into_iter()
+            .map(|entry| serde_json::from_value::<Configuration>(entry.clone()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| anyhow!("Invalid configuration in file '{}': {}", file_path, e))?;
+        if configs.len() > 1 {
+            let mut aliases: Vec<&str> = configs.iter().map(|c| c.alias_name.as_str()).collect();
+            aliases.sort_unstable();
+            anyhow::bail!(
+                "File '{}' holds {} configurations ({}); keep only the one to import",
+                file_path,
+                configs.len(),
+                aliases.join(", ")
+            );
+        }
+        let config = configs.pop().expect("one configuration");
+        return check_serialized_configuration(config, file_path, validate_url);
+    }
+    anyhow::bail!(
+        "File '{}' does not contain a valid 'env' section or a cc-switch configuration",
+        file_path
+    )
+}
 
-    let api_key = env
-        .get("ANTHROPIC_API_KEY")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
+/// Read the `env` section of a settings file
+///
+/// # Errors
+/// Returns error if credentials or the URL are missing, or a variable has
+/// the wrong type
+fn parse_settings_env(
+    env: &JsonObject,
+    file_path: &str,
+    validate_url: bool,
+) -> Result<ParsedConfigFile> {
+    let auth_token = env_string(env, "ANTHROPIC_AUTH_TOKEN", file_path)?;
+    let api_key = env_string(env, "ANTHROPIC_API_KEY", file_path)?;
 
     if auth_token.is_some() && api_key.is_some() {
         anyhow::bail!(
@@ -107,101 +247,149 @@ fn parse_config_from_file(
         _ => unreachable!(),
     };
 
-    let url = env
-        .get("ANTHROPIC_BASE_URL")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| anyhow!("Missing ANTHROPIC_BASE_URL in file '{}'", file_path))?
-        .to_string();
-
-    let model = env
-        .get("ANTHROPIC_MODEL")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-
-    let small_fast_model = env
-        .get("ANTHROPIC_SMALL_FAST_MODEL")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-
-    let max_thinking_tokens = env
-        .get("ANTHROPIC_MAX_THINKING_TOKENS")
-        .and_then(|v| v.as_u64())
-        .map(|u| u as u32);
-
-    let api_timeout_ms = env
-        .get("API_TIMEOUT_MS")
-        .and_then(|v| v.as_u64())
-        .map(|u| u as u32);
-
-    let claude_code_disable_nonessential_traffic = env
-        .get("CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC")
-        .and_then(|v| v.as_u64())
-        .map(|u| u as u32);
-
-    let anthropic_default_sonnet_model = env
-        .get("ANTHROPIC_DEFAULT_SONNET_MODEL")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-
-    let anthropic_default_opus_model = env
-        .get("ANTHROPIC_DEFAULT_OPUS_MODEL")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-
-    let anthropic_default_haiku_model = env
-        .get("ANTHROPIC_DEFAULT_HAIKU_MODEL")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-
-    let claude_code_subagent_model = env
-        .get("CLAUDE_CODE_SUBAGENT_MODEL")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-
-    let claude_code_disable_nonstreaming_fallback = env
-        .get("CLAUDE_CODE_DISABLE_NONSTREAMING_FALLBACK")
-        .and_then(|v| v.as_u64())
-        .map(|u| u as u32);
-
-    let claude_code_effort_level = env
-        .get("CLAUDE_CODE_EFFORT_LEVEL")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-
-    let disable_prompt_caching = env
-        .get("DISABLE_PROMPT_CACHING")
-        .and_then(|v| v.as_u64())
-        .map(|u| u as u32);
-
-    let claude_code_disable_experimental_betas = env
-        .get("CLAUDE_CODE_DISABLE_EXPERIMENTAL_BETAS")
-        .and_then(|v| v.as_u64())
-        .map(|u| u as u32);
-
-    let disable_autoupdater = env
-        .get("DISABLE_AUTOUPDATER")
-        .and_then(|v| v.as_u64())
-        .map(|u| u as u32);
-
-    Ok((
+    let url = env_string(env, "ANTHROPIC_BASE_URL", file_path)?
+        .ok_or_else(|| anyhow!("Missing ANTHROPIC_BASE_URL in file '{}'", file_path))?;
+    let url = if validate_url {
+        validate_base_url(&url)
+            .with_context(|| format!("Invalid ANTHROPIC_BASE_URL in file '{}'", file_path))?
+    } else {
+        url
+    };
+
+    // Variables cc-switch has no field for are kept as they are
+    let mut extra_env = ExtraEnv::new();
+    for (key, value) in env {
+        if crate::config::types::ANTHROPIC_ENV_VARS.contains(&key.as_str())
+            || crate::config::types::validate_extra_env_key(key).is_err()
+        {
+            continue;
+        }
+        let value = match value {
+            serde_json::Value::String(value) => value.clone(),
+            serde_json::Value::Number(_) | serde_json::Value::Bool(_) => value.to_string(),
+            _ => continue,
+        };
+        extra_env.insert(key.clone(), value);
+    }
+
+    let config = Configuration {
         token,
         api_key,
         url,
-        model,
-        small_fast_model,
-        max_thinking_tokens,
-        api_timeout_ms,
-        claude_code_disable_nonessential_traffic,
-        anthropic_default_sonnet_model,
-        anthropic_default_opus_model,
-        anthropic_default_haiku_model,
-        claude_code_subagent_model,
-        claude_code_disable_nonstreaming_fallback,
-        claude_code_effort_level,
-        disable_prompt_caching,
-        claude_code_disable_experimental_betas,
-        disable_autoupdater,
-    ))
+        model: env_string(env, "ANTHROPIC_MODEL", file_path)?,
+        small_fast_model: env_string(env, "ANTHROPIC_SMALL_FAST_MODEL", file_path)?,
+        max_thinking_tokens: env_number(env, "ANTHROPIC_MAX_THINKING_TOKENS", file_path)?,
+        api_timeout_ms: env_number(env, "API_TIMEOUT_MS", file_path)?,
+        claude_code_disable_nonessential_traffic: env_number(
+            env,
+            "CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC",
+            file_path,
+        )?,
+        anthropic_default_sonnet_model: env_string(
+            env,
+            "ANTHROPIC_DEFAULT_SONNET_MODEL",
+            file_path,
+        )?,
+        anthropic_default_opus_model: env_string(env, "ANTHROPIC_DEFAULT_OPUS_MODEL", file_path)?,
+        anthropic_default_haiku_model: env_string(env, "ANTHROPIC_DEFAULT_HAIKU_MODEL", file_path)?,
+        claude_code_subagent_model: env_string(env, "CLAUDE_CODE_SUBAGENT_MODEL", file_path)?,
+        claude_code_disable_nonstreaming_fallback: env_number(
+            env,
+            "CLAUDE_CODE_DISABLE_NONSTREAMING_FALLBACK",
+            file_path,
+        )?,
+        claude_code_effort_level: env_string(env, "CLAUDE_CODE_EFFORT_LEVEL", file_path)?,
+        disable_prompt_caching: env_number(env, "DISABLE_PROMPT_CACHING", file_path)?,
+        claude_code_disable_experimental_betas: env_number(
+            env,
+            "CLAUDE_CODE_DISABLE_EXPERIMENTAL_BETAS",
+            file_path,
+        )?,
+        disable_autoupdater: env_number(env, "DISABLE_AUTOUPDATER", file_path)?,
+        claude_code_experimental_agent_teams: env_number(
+            env,
+            "CLAUDE_CODE_EXPERIMENTAL_AGENT_TEAMS",
+            file_path,
+        )?,
+        claude_code_disable_1m_context: env_number(
+            env,
+            "CLAUDE_CODE_DISABLE_1M_CONTEXT",
+            file_path,
+        )?,
+        extra_env,
+        ..Default::default()
+    };
+    // Whatever an export of the result would not write back was dropped,
+    // e.g. a variable cc-switch manages or a nested object
+    let kept = crate::cli::export::settings_json(&config);
+    let ignored_keys = env
+        .keys()
+        .filter(|key| kept["env"].get(key.as_str()).is_none())
+        .cloned()
+        .collect();
+
+    Ok(ParsedConfigFile {
+        config,
+        ignored_keys,
+    })
+}
+
+/// Check a configuration deserialized from a file before it is stored
+///
+/// # Errors
+/// Returns error if credentials are masked, missing or doubled, or the URL
+/// or label color is invalid
+fn check_serialized_configuration(
+    mut config: Configuration,
+    file_path: &str,
+    validate_url: bool,
+) -> Result<ParsedConfigFile> {
+    let api_key = config.api_key.as_deref().filter(|key| !key.is_empty());
+    match (config.token.is_empty(), api_key) {
+        (false, Some(_)) => anyhow::bail!(
+            "File '{}' sets both token and api_key — only one is allowed",
+            file_path
+        ),
+        (false, None) | (true, Some(_)) if config.token_command.is_some() => anyhow::bail!(
+            "File '{}' sets token_command together with a token or api_key — only one is allowed",
+            file_path
+        ),
+        (true, None) if config.token_command.is_none() => {
+            anyhow::bail!("Missing token or api_key in file '{}'", file_path)
+        }
+        _ => {}
+    }
+    if config.token_command.is_none() && is_masked(config.auth_env_pair().1) {
+        anyhow::bail!(
+            "The credential in file '{}' is masked; write the file with `cc-switch list --reveal`",
+            file_path
+        );
+    }
+    if validate_url {
+        config.url = validate_base_url(&config.url)
+            .with_context(|| format!("Invalid url in file '{}'", file_path))?;
+    }
+    if let Some(color) = &config.color {
+        let color = crate::config::parse_label_color(color)
+            .with_context(|| format!("Invalid color in file '{}'", file_path))?;
+        config.color = Some(color);
+    }
+
+    // The same rules as `--env`: managed variables have dedicated fields
+    let mut ignored_keys = Vec::new();
+    config.extra_env.retain(|key, _| {
+        let valid = crate::config::types::validate_extra_env_key(key).is_ok();
+        if !valid {
+            ignored_keys.push(key.clone());
+        }
+        valid
+    });
+    config.alias_name = String::new();
+
+    Ok(ParsedConfigFile {
+        config,
+        ignored_keys,
+    })
 }
 
 /// Handle adding a configuration with all the new features
@@ -213,62 +401,90 @@ fn parse_config_from_file(
 /// # Errors
 /// Returns error if validation fails or user cancels interactive input
 fn handle_add_command(mut params: AddCommandParams, storage: &mut ConfigStorage) -> Result<()> {
+    let output = params.output;
+    if params.interactive && output.is_json() {
+        anyhow::bail!("Cannot use --interactive with --format json");
+    }
+    if params.from_file.is_some() && params.alias_name.is_some() {
+        return Err(invalid(
+            "--from-file cannot be combined with an alias argument; the configuration is named after the file (work.json adds 'work')",
+        ));
+    }
+    // Fields without an `add` flag, set only by a serialized configuration
+    let mut imported = Configuration::default();
     // If from-file is provided, parse the file and use those values
     if let Some(file_path) = &params.from_file {
-        println!("Importing configuration from file: {}", file_path);
-
-        let (
-            file_token,
-            file_api_key,
-            file_url,
-            file_model,
-            file_small_fast_model,
-            file_max_thinking_tokens,
-            file_api_timeout_ms,
-            file_claude_disable_nonessential_traffic,
-            file_sonnet_model,
-            file_opus_model,
-            file_haiku_model,
-            file_subagent_model,
-            file_disable_nonstreaming_fallback,
-            file_effort_level,
-            file_disable_prompt_caching,
-            file_disable_experimental_betas,
-            file_disable_autoupdater,
-        ) = parse_config_from_file(file_path)?;
-
-        params.token = Some(file_token);
-        params.api_key = file_api_key;
-        params.url = Some(file_url);
-        params.model = file_model;
-        params.small_fast_model = file_small_fast_model;
-        params.max_thinking_tokens = file_max_thinking_tokens;
-        params.api_timeout_ms = file_api_timeout_ms;
-        params.claude_code_disable_nonessential_traffic = file_claude_disable_nonessential_traffic;
-        params.anthropic_default_sonnet_model = file_sonnet_model;
-        params.anthropic_default_opus_model = file_opus_model;
-        params.anthropic_default_haiku_model = file_haiku_model;
-        params.claude_code_subagent_model = file_subagent_model;
-        params.claude_code_disable_nonstreaming_fallback = file_disable_nonstreaming_fallback;
-        params.claude_code_effort_level = file_effort_level;
-        params.disable_prompt_caching = file_disable_prompt_caching;
-        params.claude_code_disable_experimental_betas = file_disable_experimental_betas;
-        params.disable_autoupdater = file_disable_autoupdater;
-
-        println!(
-            "Configuration '{}' will be imported from file",
-            params.alias_name
-        );
+        output.note(format!("Importing configuration from file: {}", file_path));
+
+        let file = parse_config_from_file(file_path, params.strict_json, !params.no_validate)?;
+        file.warn_on_ignored_keys();
+        let file = file.config;
+        imported.launch_profiles = file.launch_profiles;
+        imported.url_variants = file.url_variants;
+        imported.quota_probe = file.quota_probe;
+        // --tag, --color and --provider override the file's
+        if params.tags.is_empty() {
+            params.tags = file.tags;
+        }
+        params.color = params.color.or(file.color);
+        params.provider = params.provider.or(file.provider);
+        if params.launch_args.is_empty() {
+            params.launch_args = file.launch_args;
+        }
+
+        params.token = Some(file.token);
+        params.api_key = file.api_key;
+        params.token_command = file.token_command;
+        params.url = Some(file.url);
+        params.model = file.model;
+        params.small_fast_model = file.small_fast_model;
+        params.max_thinking_tokens = file.max_thinking_tokens;
+        params.api_timeout_ms = file.api_timeout_ms;
+        params.claude_code_disable_nonessential_traffic =
+            file.claude_code_disable_nonessential_traffic;
+        params.anthropic_default_sonnet_model = file.anthropic_default_sonnet_model;
+        params.anthropic_default_opus_model = file.anthropic_default_opus_model;
+        params.anthropic_default_haiku_model = file.anthropic_default_haiku_model;
+        params.claude_code_subagent_model = file.claude_code_subagent_model;
+        params.claude_code_disable_nonstreaming_fallback =
+            file.claude_code_disable_nonstreaming_fallback;
+        params.claude_code_effort_level = file.claude_code_effort_level;
+        params.disable_prompt_caching = file.disable_prompt_caching;
+        params.claude_code_disable_experimental_betas = file.claude_code_disable_experimental_betas;
+        params.disable_autoupdater = file.disable_autoupdater;
+        params.claude_code_experimental_agent_teams = file.claude_code_experimental_agent_teams;
+        params.claude_code_disable_1m_context = file.claude_code_disable_1m_context;
+        // --env flags override variables from the file
+        let flag_env = std::mem::replace(&mut params.extra_env, file.extra_env);
+        params.extra_env.extend(flag_env);
+        params.alias_name = Some(alias_from_file_name(Path::new(file_path)));
     }
 
-    // Validate alias name
-    validate_alias_name(&params.alias_name)?;
+    // Resolve the alias only once any file import has succeeded, so a failed
+    // parse can never leave a half-named configuration behind
+    let alias_name = params
+        .alias_name
+        .take()
+        .ok_or_else(|| anyhow!("Configuration alias name is required"))?;
+    validate_alias_name(&alias_name)?;
+    let color = params
+        .color
+        .as_deref()
+        .map(crate::config::parse_label_color)
+        .transpose()?;
+
+    if params.from_file.is_some() {
+        output.note(format!(
+            "Configuration '{}' will be imported from file",
+            alias_name
+        ));
+    }
 
     // Check if alias already exists
-    if storage.get_configuration(&params.alias_name).is_some() && !params.force {
-        eprintln!("Configuration '{}' already exists.", params.alias_name);
-        eprintln!("Use --force to overwrite or choose a different alias name.");
-        return Ok(());
+    if storage.get_configuration(&alias_name).is_some() && !params.force {
+        return Err(conflict(format!(
+            "Configuration '{alias_name}' already exists; use --force to overwrite it"
+        )));
     }
 
     // Cannot use interactive mode with --from-file
@@ -285,9 +501,25 @@ fn handle_add_command(mut params: AddCommandParams, storage: &mut ConfigStorage)
         );
     }
 
+    // Interactive add starts by offering the presets, unless one was given
+    let preset = match params.preset.as_deref() {
+        Some(name) => Some(crate::config::presets::find(name)?),
+        None if params.interactive => read_preset_choice()?,
+        None => None,
+    };
+    if let Some(preset) = preset
+        && !params.interactive
+    {
+        preset.apply(&mut params);
+    }
+
     // Determine authentication value (token or api_key)
     let (final_token, final_api_key): (String, Option<String>) = if params.interactive {
-        if params.token.is_some() || params.token_arg.is_some() || params.api_key.is_some() {
+        if params.token.is_some()
+            || params.token_arg.is_some()
+            || params.api_key.is_some()
+            || params.token_command.is_some()
+        {
             eprintln!(
                 "Warning: Token/API key provided via flags/arguments will be ignored in interactive mode"
             );
@@ -295,15 +527,18 @@ fn handle_add_command(mut params: AddCommandParams, storage: &mut ConfigStorage)
         let auth_type = read_input(
             "Auth type — (1) ANTHROPIC_AUTH_TOKEN  (2) ANTHROPIC_API_KEY [default: 1]: ",
         )?;
+        let token_hint = preset.map_or("sk-ant-xxx", |preset| preset.token_hint);
         if auth_type == "2" {
             let key = read_sensitive_input("Enter API key (ANTHROPIC_API_KEY): ")?;
             (String::new(), Some(key))
         } else {
-            let token = read_sensitive_input("Enter API token (sk-ant-xxx): ")?;
+            let token = read_sensitive_input(&format!("Enter API token ({token_hint}): "))?;
             (token, None)
         }
     } else if let Some(key) = params.api_key {
         (String::new(), Some(key))
+    } else if params.token_command.is_some() {
+        (String::new(), None)
     } else {
         match (&params.token, &params.token_arg) {
             (Some(t), _) => (t.clone(), None),
@@ -313,12 +548,21 @@ fn handle_add_command(mut params: AddCommandParams, storage: &mut ConfigStorage)
                     "Authentication is required. Use one of:\n\
                      --token / -t  → sets ANTHROPIC_AUTH_TOKEN\n\
                      --api-key / -k → sets ANTHROPIC_API_KEY\n\
+                     --token-command → runs a command printing ANTHROPIC_AUTH_TOKEN\n\
                      -i            → interactive mode"
                 );
             }
         }
     };
 
+    if params.interactive
+        && final_token.is_empty()
+        && final_api_key.as_deref().unwrap_or("").is_empty()
+    {
+        println!("No credential entered; configuration '{alias_name}' was not added");
+        return Ok(());
+    }
+
     // Determine URL value
     let final_url = if params.interactive {
         if params.url.is_some() || params.url_arg.is_some() {
@@ -326,7 +570,21 @@ fn handle_add_command(mut params: AddCommandParams, storage: &mut ConfigStorage)
                 "Warning: URL provided via flags/arguments will be ignored in interactive mode"
             );
         }
-        read_input("Enter API URL (default: https://api.anthropic.com): ")?
+        // Ask again until the URL is valid; an empty answer takes the default
+        let default_url = preset.map_or("https://api.anthropic.com", |preset| preset.url);
+        loop {
+            let url = read_input(&format!("Enter API URL (default: {default_url}): "))?;
+            if url.is_empty() {
+                break default_url.to_string();
+            }
+            if params.no_validate {
+                break url;
+            }
+            match validate_base_url(&url) {
+                Ok(url) => break url,
+                Err(e) => eprintln!("Error: {e}"),
+            }
+        }
     } else {
         match (&params.url, &params.url_arg) {
             (Some(u), _) => u.clone(),
@@ -338,8 +596,10 @@ fn handle_add_command(mut params: AddCommandParams, storage: &mut ConfigStorage)
     // Use default URL if empty
     let final_url = if final_url.is_empty() {
         "https://api.anthropic.com".to_string()
-    } else {
+    } else if params.no_validate {
         final_url
+    } else {
+        validate_base_url(&final_url)?
     };
 
     // Determine model value
@@ -347,12 +607,7 @@ fn handle_add_command(mut params: AddCommandParams, storage: &mut ConfigStorage)
         if params.model.is_some() {
             eprintln!("Warning: Model provided via flags will be ignored in interactive mode");
         }
-        let model_input = read_input("Enter model name (optional, press enter to skip): ")?;
-        if model_input.is_empty() {
-            None
-        } else {
-            Some(model_input)
-        }
+        read_optional_input("model name", preset.and_then(|preset| preset.model))?
     } else {
         params.model
     };
@@ -364,13 +619,10 @@ fn handle_add_command(mut params: AddCommandParams, storage: &mut ConfigStorage)
                 "Warning: Small fast model provided via flags will be ignored in interactive mode"
             );
         }
-        let small_model_input =
-            read_input("Enter small fast model name (optional, press enter to skip): ")?;
-        if small_model_input.is_empty() {
-            None
-        } else {
-            Some(small_model_input)
-        }
+        read_optional_input(
+            "small fast model name",
+            preset.and_then(|preset| preset.small_fast_model),
+        )?
     } else {
         params.small_fast_model
     };
@@ -448,13 +700,10 @@ fn handle_add_command(mut params: AddCommandParams, storage: &mut ConfigStorage)
                 "Warning: Default Sonnet model provided via flags will be ignored in interactive mode"
             );
         }
-        let model_input =
-            read_input("Enter default Sonnet model name (optional, press enter to skip): ")?;
-        if model_input.is_empty() {
-            None
-        } else {
-            Some(model_input)
-        }
+        read_optional_input(
+            "default Sonnet model name",
+            preset.and_then(|preset| preset.default_sonnet_model),
+        )?
     } else {
         params.anthropic_default_sonnet_model
     };
@@ -466,13 +715,10 @@ fn handle_add_command(mut params: AddCommandParams, storage: &mut ConfigStorage)
                 "Warning: Default Opus model provided via flags will be ignored in interactive mode"
             );
         }
-        let model_input =
-            read_input("Enter default Opus model name (optional, press enter to skip): ")?;
-        if model_input.is_empty() {
-            None
-        } else {
-            Some(model_input)
-        }
+        read_optional_input(
+            "default Opus model name",
+            preset.and_then(|preset| preset.default_opus_model),
+        )?
     } else {
         params.anthropic_default_opus_model
     };
@@ -484,13 +730,10 @@ fn handle_add_command(mut params: AddCommandParams, storage: &mut ConfigStorage)
                 "Warning: Default Haiku model provided via flags will be ignored in interactive mode"
             );
         }
-        let model_input =
-            read_input("Enter default Haiku model name (optional, press enter to skip): ")?;
-        if model_input.is_empty() {
-            None
-        } else {
-            Some(model_input)
-        }
+        read_optional_input(
+            "default Haiku model name",
+            preset.and_then(|preset| preset.default_haiku_model),
+        )?
     } else {
         params.anthropic_default_haiku_model
     };
@@ -618,30 +861,12 @@ fn handle_add_command(mut params: AddCommandParams, storage: &mut ConfigStorage)
         params.disable_autoupdater
     };
 
-    // Validate token format with flexible API provider support (only for AUTH_TOKEN)
-    if final_api_key.is_none() {
-        let is_anthropic_official = final_url.contains("api.anthropic.com");
-        if is_anthropic_official {
-            if !final_token.starts_with("sk-ant-api03-") {
-                eprintln!(
-                    "Warning: For official Anthropic API (api.anthropic.com), token should start with 'sk-ant-api03-'"
-                );
-            }
-        } else {
-            // For non-official APIs, provide general guidance
-            if final_token.starts_with("sk-ant-api03-") {
-                eprintln!(
-                    "Warning: Using official Claude token format with non-official API endpoint"
-                );
-            }
-        }
-    }
-
     // Create and add configuration
     let config = Configuration {
-        alias_name: params.alias_name.clone(),
+        alias_name: alias_name.clone(),
         token: final_token,
         api_key: final_api_key,
+        token_command: params.token_command.filter(|_| !params.interactive),
         url: final_url,
         model: final_model,
         small_fast_model: final_small_fast_model,
@@ -657,110 +882,1170 @@ fn handle_add_command(mut params: AddCommandParams, storage: &mut ConfigStorage)
         disable_prompt_caching: final_disable_prompt_caching,
         claude_code_disable_experimental_betas: final_claude_code_disable_experimental_betas,
         disable_autoupdater: final_disable_autoupdater,
-        claude_code_experimental_agent_teams: None,
-        claude_code_disable_1m_context: None,
+        claude_code_experimental_agent_teams: params.claude_code_experimental_agent_teams,
+        claude_code_disable_1m_context: params.claude_code_disable_1m_context,
+        tags: params.tags.clone(),
+        launch_profiles: imported.launch_profiles,
+        url_variants: imported.url_variants,
+        quota_probe: imported.quota_probe,
+        color,
+        extra_env: params.extra_env,
+        provider: params.provider,
+        launch_args: params.launch_args,
     };
+    warn_on_token_format(&config);
+    warn_on_model_names(&config, &[]);
+
+    // Another run may have added the alias meanwhile, so check again under the lock
+    storage.with_lock(|storage| {
+        if storage.get_configuration(&alias_name).is_some() && !params.force {
+            return Err(conflict(format!(
+                "Configuration '{alias_name}' already exists; use --force to overwrite it"
+            )));
+        }
+        storage.add_configuration(config);
+        Ok(())
+    })?;
 
-    storage.add_configuration(config);
-    storage.save()?;
-
-    println!("Configuration '{}' added successfully", params.alias_name);
+    output.note(format!("Configuration '{}' added successfully", alias_name));
     if params.force {
-        println!("(Overwrote existing configuration)");
+        output.note("(Overwrote existing configuration)");
+    }
+    if output.is_json()
+        && let Some(config) = storage.get_configuration(&alias_name)
+    {
+        use crate::cli::display_utils::{FieldMask, ListedConfiguration};
+        let mut value = serde_json::to_value(ListedConfiguration::new(config, params.reveal))?;
+        FieldMask::from_storage(storage).apply_to_json(&mut value);
+        println!("{}", serde_json::to_string_pretty(&value)?);
     }
 
     Ok(())
 }
 
-/// Main entry point for the CLI application
+/// Show the presets and read the choice, asking again until it is valid
 ///
-/// Parses command-line arguments and executes the appropriate action:
-/// - Switch configuration with `-c` flag
-/// - Execute subcommands (add, remove, list)
-/// - Show help if no arguments provided
+/// Returns `None` for Custom, which is also the default.
 ///
 /// # Errors
-/// Returns error if any operation fails (file I/O, parsing, etc.)
-pub fn run() -> Result<()> {
-    let cli = Cli::parse();
+/// Returns error if reading input fails
+fn read_preset_choice() -> Result<Option<&'static crate::config::presets::Preset>> {
+    use crate::config::presets;
 
-    // Handle --migrate flag: migrate old path to new path and exit
-    if cli.migrate {
-        ConfigStorage::migrate_from_old_path()?;
-        return Ok(());
+    println!("Start from a preset:");
+    for line in presets::menu_lines() {
+        println!("{line}");
     }
-
-    // Handle --list-aliases flag for completion
-    if cli.list_aliases {
-        list_aliases_for_completion()?;
-        return Ok(());
+    loop {
+        let input = read_input(&format!(
+            "Choose a preset [1-{}, default: Custom]: ",
+            presets::PRESETS.len() + 1
+        ))?;
+        match presets::menu_choice(&input) {
+            Some(choice) => return Ok(choice),
+            None => eprintln!("Error: '{input}' is not one of the presets"),
+        }
     }
+}
 
-    // Handle --list-codex-aliases flag for completion
-    if cli.list_codex_aliases {
-        list_codex_aliases_for_completion()?;
-        return Ok(());
+/// Ask for an optional text value; an empty answer keeps `default`
+///
+/// # Errors
+/// Returns error if reading input fails
+fn read_optional_input(label: &str, default: Option<&str>) -> Result<Option<String>> {
+    let input = match default {
+        Some(default) => read_input(&format!("Enter {label} (press enter for {default}): "))?,
+        None => read_input(&format!("Enter {label} (optional, press enter to skip): "))?,
+    };
+    Ok(if input.is_empty() {
+        default.map(str::to_string)
+    } else {
+        Some(input)
+    })
+}
+
+/// Parse `--env KEY=VALUE` flags, warning about unconventional names
+///
+/// # Errors
+/// Returns error if a flag is not `KEY=VALUE` or names a variable
+/// cc-switch manages
+fn parse_env_assignments(assignments: &[String]) -> Result<ExtraEnv> {
+    let mut config = Configuration::default();
+    for assignment in assignments {
+        config.set_field("extra_env", assignment)?;
+        if let Some((key, _)) = assignment.split_once('=') {
+            warn_on_env_key(key);
+        }
     }
+    Ok(config.extra_env)
+}
 
-    // Reap per-PID alias files for terminated sessions on every invocation.
-    // Skipped for completion-only paths above to keep shell completion fast.
-    let _ = ClaudeSettings::cleanup_orphan_alias_files();
+/// Warn when an environment variable name is not UPPER_SNAKE_CASE
+fn warn_on_env_key(key: &str) {
+    if !crate::config::types::is_conventional_env_key(key) {
+        eprintln!(
+            "Warning: '{key}' is not an UPPER_CASE_WITH_UNDERSCORES environment variable name"
+        );
+    }
+}
 
-    // Handle --store flag: set default storage mode and exit
-    if let Some(ref store_str) = cli.store
-        && cli.command.is_none()
+/// Warn when the active credential does not look right for the
+/// configuration's provider
+///
+/// A detected provider can be wrong (e.g. a gateway on an unusual host), so
+/// the prefix warning then also says how to set it.
+fn warn_on_token_format(config: &Configuration) {
+    let warnings = config.token_warnings();
+    for warning in &warnings {
+        eprintln!("Warning: {warning}");
+    }
+    if config.provider.is_none()
+        && warnings
+            .iter()
+            .any(|warning| matches!(warning, TokenWarning::MissingPrefix { .. }))
     {
-        // No command provided, so --store is a setter
-        let mode = match parse_storage_mode(store_str) {
-            Ok(mode) => mode,
-            Err(e) => {
-                eprintln!("Error: {}", e);
-                std::process::exit(1);
-            }
-        };
+        eprintln!(
+            "  Provider '{}' was detected from the URL; set it with --provider",
+            config.provider().as_str()
+        );
+    }
+}
 
-        let mut storage = ConfigStorage::load()?;
-        storage.default_storage_mode = Some(mode.clone());
-        storage.save()?;
+/// Warn about model names that aren't known Anthropic models
+///
+/// Notices in `already` (those of the configuration before editing) are
+/// left out, so changing one field doesn't repeat notes about the others.
+/// Gateways use their own names, so off the official API these are only
+/// notes.
+fn warn_on_model_names(config: &Configuration, already: &[ModelNotice]) {
+    for notice in config.model_notices() {
+        if already.contains(&notice) {
+            continue;
+        }
+        match notice.level {
+            ModelNoticeLevel::Warning => eprintln!("Warning: {notice}"),
+            ModelNoticeLevel::Note => eprintln!("Note: {notice}"),
+        }
+    }
+}
 
-        let mode_str = match mode {
-            StorageMode::Env => "env",
-            StorageMode::Config => "config",
-        };
+/// Handle `cc-switch edit <alias>` with field flags
+///
+/// Applies each `(field, value)` update to the configuration as reloaded
+/// under the storage lock, an empty value clearing the field, and saves only
+/// when all of them are valid.
+///
+/// # Errors
+/// Returns error if the alias is missing, a field or value is invalid, or
+/// saving fails
+pub fn handle_edit_fields(
+    storage: &mut ConfigStorage,
+    alias_name: &str,
+    updates: &[FieldUpdate],
+) -> Result<()> {
+    use crate::cli::display_utils::normalize_field_name;
+
+    resolve_stored_alias(storage, alias_name)?;
+    let (config, flagged) = storage.with_lock(|storage| {
+        let mut config = resolve_stored_alias(storage, alias_name)?.clone();
+        let flagged = config.model_notices();
+        for (field, value) in updates {
+            config.set_field(&normalize_field_name(field), value)?;
+        }
+        storage.update_configuration(alias_name, config.clone())?;
+        Ok((config, flagged))
+    })?;
+    for (field, value) in updates {
+        if normalize_field_name(field) == "extra_env"
+            && let Some((key, _)) = value.split_once('=')
+        {
+            warn_on_env_key(key);
+        }
+    }
+    warn_on_token_format(&config);
+    warn_on_model_names(&config, &flagged);
 
-        println!("Default storage mode set to: {}", mode_str);
+    println!("Configuration '{}' updated successfully", alias_name);
+    Ok(())
+}
+
+/// Alias of a configuration imported from `file`: the file name without
+/// its extension
+fn alias_from_file_name(file: &Path) -> String {
+    file.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Build a configuration named `alias_name` from a JSON file
+///
+/// # Errors
+/// Returns error if the file cannot be read or parsed, or its URL is invalid
+fn config_from_file(alias_name: &str, file_path: &str, strict_json: bool) -> Result<Configuration> {
+    let file = parse_config_from_file(file_path, strict_json, true)?;
+    file.warn_on_ignored_keys();
+    Ok(Configuration {
+        alias_name: alias_name.to_string(),
+        ..file.config
+    })
+}
+
+/// `(file, error)` of each file `import` could not import
+type ImportFailures = Vec<(String, String)>;
+
+/// Files named by the `import` arguments: files as given, and the JSON
+/// files directly inside directories, sorted by name
+///
+/// Arguments that can't be read are added to `failures` with their error.
+fn import_files(paths: &[String], failures: &mut ImportFailures) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for path in paths {
+        let path = Path::new(path);
+        if path.is_dir() {
+            match fs::read_dir(path) {
+                Ok(entries) => {
+                    let mut found: Vec<PathBuf> = entries
+                        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                        .filter(|file| file.is_file() && crate::utils::is_lenient_json_path(file))
+                        .collect();
+                    found.sort();
+                    files.extend(found);
+                }
+                Err(e) => failures.push((path.display().to_string(), e.to_string())),
+            }
+        } else if path.is_file() {
+            files.push(path.to_path_buf());
+        } else {
+            failures.push((
+                path.display().to_string(),
+                "No such file or directory".to_string(),
+            ));
+        }
+    }
+    files
+}
+
+/// Handle `cc-switch import`: add a configuration for every file in
+/// `paths`, saving once at the end
+///
+/// Existing aliases are skipped unless `force`; files that fail to parse
+/// are listed after the summary.
+///
+/// # Errors
+/// Returns error if saving fails or any file failed to import
+pub fn handle_import(
+    storage: &mut ConfigStorage,
+    paths: &[String],
+    force: bool,
+    dry_run: bool,
+    strict_json: bool,
+) -> Result<()> {
+    let mut failures = ImportFailures::new();
+    let mut parsed = Vec::new();
+    for file in import_files(paths, &mut failures) {
+        let shown = file.display().to_string();
+        let alias_name = alias_from_file_name(&file);
+        let config = validate_alias_name(&alias_name)
+            .and_then(|()| config_from_file(&alias_name, &shown, strict_json));
+        match config {
+            Ok(config) => parsed.push((shown, config)),
+            Err(e) => failures.push((shown, format!("{e:#}"))),
+        }
+    }
+
+    let nothing_parsed = parsed.is_empty();
+    // Whether an alias exists is decided against the store as it is under
+    // the lock, so an alias another run added meanwhile is still skipped
+    let add_all = |storage: &mut ConfigStorage| {
+        let (mut imported, mut skipped) = (0, 0);
+        for (shown, config) in parsed {
+            let alias_name = config.alias_name.clone();
+            let exists = storage.get_configuration(&alias_name).is_some();
+            if exists && !force {
+                println!("Skipped '{alias_name}' from {shown}: already exists");
+                skipped += 1;
+                continue;
+            }
+            let note = if exists { " (overwriting)" } else { "" };
+            if dry_run {
+                println!("Would import '{alias_name}' from {shown}{note}");
+            } else {
+                println!("Imported '{alias_name}' from {shown}{note}");
+            }
+            storage.add_configuration(config);
+            imported += 1;
+        }
+        Ok((imported, skipped))
+    };
+    let (imported, skipped) = if dry_run || nothing_parsed {
+        add_all(storage)?
+    } else {
+        storage.with_lock(add_all)?
+    };
+    println!(
+        "{} {imported}, skipped {skipped} (already exist), failed {}",
+        if dry_run { "Would import" } else { "Imported" },
+        failures.len()
+    );
+    if failures.is_empty() {
         return Ok(());
     }
+    eprintln!("Failed to import:");
+    for (file, error) in &failures {
+        eprintln!("  {file}: {error}");
+    }
+    anyhow::bail!("{} file(s) could not be imported", failures.len())
+}
 
-    // Handle subcommands
-    if let Some(command) = cli.command {
-        let mut storage = ConfigStorage::load()?;
+/// Handle `cc-switch restore`: apply the backup in `file` to `storage`
+/// and save, printing a line per alias
+///
+/// # Errors
+/// Returns error if the file cannot be read, is not a valid backup, or
+/// saving fails
+pub fn handle_restore(
+    storage: &mut ConfigStorage,
+    file: &Path,
+    mode: crate::cli::backup::RestoreMode,
+) -> Result<()> {
+    let content = fs::read_to_string(file)
+        .with_context(|| format!("Failed to read backup {}", file.display()))?;
+    let backup = crate::cli::backup::parse_backup(&content, file)?;
+    let summary =
+        storage.with_lock(|storage| Ok(crate::cli::backup::restore_into(storage, backup, mode)))?;
+    println!("Restored from {}:", file.display());
+    for line in summary {
+        println!("{line}");
+    }
+    Ok(())
+}
 
-        match command {
-            Commands::Add {
-                alias_name,
-                token,
-                api_key,
-                url,
-                model,
-                small_fast_model,
-                max_thinking_tokens,
-                api_timeout_ms,
-                claude_code_disable_nonessential_traffic,
-                anthropic_default_sonnet_model,
-                anthropic_default_opus_model,
-                anthropic_default_haiku_model,
-                claude_code_subagent_model,
-                claude_code_disable_nonstreaming_fallback,
-                claude_code_effort_level,
-                disable_prompt_caching,
-                claude_code_disable_experimental_betas,
-                disable_autoupdater,
-                force,
-                interactive,
-                token_arg,
-                url_arg,
+/// Handle `cc-switch migrate`: add the configurations of a legacy store
+/// (`from`, else `~/.cc_auto_switch/configurations.json`) to `storage`
+///
+/// Existing aliases are kept unless `force` is set. The legacy file is
+/// never modified.
+///
+/// # Errors
+/// Returns a not-found error when there is no legacy file, or an error if
+/// it doesn't parse or saving fails
+pub fn handle_migrate(storage: &mut ConfigStorage, from: Option<&Path>, force: bool) -> Result<()> {
+    let path = match from {
+        Some(path) => path.to_path_buf(),
+        None => crate::config::config_storage::legacy_storage_path()?,
+    };
+    if !path.exists() {
+        return Err(not_found(format!(
+            "No legacy configuration found at {}",
+            path.display()
+        )));
+    }
+    let mut legacy = ConfigStorage::read_legacy(&path)?;
+    let summary = storage.with_lock(|storage| {
+        if storage.claude.settings_dir.is_none() {
+            storage.claude.settings_dir = legacy.claude.settings_dir.take();
+        }
+        Ok(crate::cli::backup::restore_into(
+            storage,
+            legacy,
+            crate::cli::backup::RestoreMode::Merge { force },
+        ))
+    })?;
+    println!("Migrated from {}:", path.display());
+    for line in summary {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+/// Handle `cc-switch test`: probe `alias_name`, or every stored
+/// configuration with `all`, printing a pass/fail line for each and the
+/// quota line of a passing one that has a quota probe
+///
+/// # Errors
+/// Returns error if the alias doesn't resolve or any probe fails
+pub fn handle_test(
+    storage: &ConfigStorage,
+    alias_name: Option<&str>,
+    all: bool,
+    live: bool,
+    transport: &mut impl crate::config::probe::Transport,
+) -> Result<()> {
+    use colored::Colorize;
+
+    let configs: Vec<std::borrow::Cow<Configuration>> = if all {
+        storage
+            .configurations
+            .values()
+            .map(std::borrow::Cow::Borrowed)
+            .collect()
+    } else {
+        let alias_name = alias_name.context("Specify an alias or --all")?;
+        let AliasTarget::Config(config) = resolve_alias_target(storage, alias_name)? else {
+            anyhow::bail!(
+                "'{}' is the official Claude API; there is nothing to test",
+                alias_name
+            );
+        };
+        vec![*config]
+    };
+    if configs.is_empty() {
+        println!("No configurations to test");
+        return Ok(());
+    }
+
+    let width = configs
+        .iter()
+        .map(|config| config.alias_name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut failed = 0;
+    for config in &configs {
+        let report = crate::config::probe::probe(config, live, transport);
+        let status = report
+            .status
+            .map_or_else(|| "no response".to_string(), |code| format!("HTTP {code}"));
+        let timing = format!("{} ms", report.latency.as_millis());
+        match &report.failure {
+            None => {
+                println!(
+                    "{} {:<width$}  {}  {}",
+                    "✓".green(),
+                    config.alias_name,
+                    status.green(),
+                    timing
+                );
+                if config.quota_probe.is_some()
+                    && let Ok(resolved) = config.with_resolved_token()
+                    && let Some(quota) = crate::config::quota::probe_quota(&resolved)
+                {
+                    println!("  {quota}");
+                }
+            }
+            Some(failure) => {
+                failed += 1;
+                println!(
+                    "{} {:<width$}  {}  {}  {}",
+                    "✗".red(),
+                    config.alias_name,
+                    status.red(),
+                    timing,
+                    failure
+                );
+            }
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("{} of {} configuration(s) failed", failed, configs.len());
+    }
+    Ok(())
+}
+
+/// Handle `cc-switch clone`: save a copy of `source_alias` as `new_alias`
+/// with `updates` applied, then list overridden and inherited fields
+///
+/// # Errors
+/// Returns error if the source is missing or official, the new alias is
+/// invalid or taken without `force`, a field is invalid, or saving fails
+pub fn handle_clone(
+    storage: &mut ConfigStorage,
+    source_alias: &str,
+    new_alias: &str,
+    updates: &[FieldUpdate],
+    force: bool,
+) -> Result<()> {
+    use crate::cli::display_utils::normalize_field_name;
+
+    validate_alias_name(new_alias)?;
+    let AliasTarget::Config(source) = resolve_alias_target(storage, source_alias)? else {
+        return Err(invalid(format!("'{source_alias}' is a reserved name")));
+    };
+    if storage.get_configuration(new_alias).is_some() && !force {
+        return Err(conflict(format!(
+            "Configuration '{new_alias}' already exists; use --force to replace it"
+        )));
+    }
+
+    let flagged = source.model_notices();
+    let mut config = source.into_owned();
+    config.alias_name = new_alias.to_string();
+    let mut overridden = Vec::new();
+    for (field, value) in updates {
+        let field = normalize_field_name(field);
+        config.set_field(&field, value)?;
+        if !overridden.contains(&field) {
+            overridden.push(field);
+        }
+    }
+    warn_on_token_format(&config);
+    warn_on_model_names(&config, &flagged);
+    let inherited: Vec<&str> = Configuration::FIELD_NAMES
+        .iter()
+        .copied()
+        .filter(|field| !overridden.iter().any(|o| o == field))
+        .filter(|field| config.field_value(field).is_some())
+        .collect();
+
+    // Another run may have added the alias meanwhile, so check again under the lock
+    storage.with_lock(|storage| {
+        if storage.get_configuration(new_alias).is_some() && !force {
+            return Err(conflict(format!(
+                "Configuration '{new_alias}' already exists; use --force to replace it"
+            )));
+        }
+        storage.add_configuration(config);
+        Ok(())
+    })?;
+    println!("Configuration '{new_alias}' cloned from '{source_alias}'");
+    if !overridden.is_empty() {
+        println!("  Overridden: {}", overridden.join(", "));
+    }
+    if !inherited.is_empty() {
+        println!("  Inherited: {}", inherited.join(", "));
+    }
+    Ok(())
+}
+
+/// Run the interactive add prompts for `alias_name`
+///
+/// # Errors
+/// Returns error if the alias is invalid, input fails or saving fails
+pub fn handle_interactive_add(alias_name: &str, storage: &mut ConfigStorage) -> Result<()> {
+    let params = AddCommandParams {
+        alias_name: Some(alias_name.to_string()),
+        interactive: true,
+        ..Default::default()
+    };
+    handle_add_command(params, storage)
+}
+
+/// Make sure `alias_name` can be used, offering to create it when missing
+///
+/// In a terminal the user is asked whether to create the missing
+/// configuration, and `create` runs on consent. Without a terminal, or when
+/// the offer is declined, the usual not-found error is returned.
+///
+/// # Errors
+/// Returns error if the alias is missing and not created
+pub fn ensure_alias_for_use<F>(
+    storage: &mut ConfigStorage,
+    alias_name: &str,
+    prompter: &mut dyn crate::interactive::Prompter,
+    create: F,
+) -> Result<()>
+where
+    F: FnOnce(&str, &mut ConfigStorage) -> Result<()>,
+{
+    let Err(not_found) = resolve_alias_target(storage, alias_name).map(|_| ()) else {
+        return Ok(());
+    };
+    // Names that could never be stored (e.g. an unknown `alias@variant`) get
+    // the error as is
+    if validate_alias_name(alias_name).is_err()
+        || !prompter.is_interactive()
+        || !prompter.confirm(&format!(
+            "Configuration '{alias_name}' doesn't exist \u{2014} create it now? [y/N] "
+        ))?
+    {
+        return Err(not_found);
+    }
+
+    create(alias_name, storage)?;
+    resolve_alias_target(storage, alias_name).map(|_| ())
+}
+
+/// Handle `cc-switch version`
+///
+/// # Errors
+/// Returns error if JSON serialization fails
+pub fn handle_version(short: bool, json: bool) -> Result<()> {
+    let storage_path = crate::config::get_config_storage_path()
+        .ok()
+        .map(|path| path.display().to_string());
+    let paths = crate::cli::doctor::DoctorContext::detect()
+        .ok()
+        .map(|context| context.paths());
+    let report = crate::reporting::VersionReport::for_this_build(storage_path, paths);
+    if short {
+        println!("{}", report.render_short());
+    } else if json {
+        println!("{}", crate::reporting::to_json(&report)?);
+    } else {
+        for line in report.render_human() {
+            println!("{line}");
+        }
+    }
+    Ok(())
+}
+
+/// Handle `cc-switch config set <key> <value>`
+///
+/// # Arguments
+/// * `key` - Preference name (e.g. `hidden-fields`)
+/// * `value` - New preference value
+/// * `storage` - Mutable reference to config storage
+///
+/// # Errors
+/// Returns error if the key or value is invalid, or saving fails
+pub fn handle_config_set(key: &str, value: &str, storage: &mut ConfigStorage) -> Result<()> {
+    use crate::cli::display_utils::{HIDEABLE_FIELDS, normalize_field_name};
+
+    match key {
+        "hidden-fields" | "hidden_fields" => {
+            let mut fields: Vec<String> = Vec::new();
+            for field in value.split(',').filter(|f| !f.trim().is_empty()) {
+                let field = normalize_field_name(field);
+                if !HIDEABLE_FIELDS.contains(&field.as_str()) {
+                    anyhow::bail!(
+                        "Unknown field '{}'. Valid fields: {}",
+                        field,
+                        HIDEABLE_FIELDS.join(", ")
+                    );
+                }
+                if !fields.contains(&field) {
+                    fields.push(field);
+                }
+            }
+            storage.with_lock(|storage| {
+                storage.preferences.hidden_fields = fields;
+                Ok(())
+            })?;
+
+            if storage.preferences.hidden_fields.is_empty() {
+                println!("Hidden fields cleared");
+            } else {
+                println!(
+                    "Hidden fields set to: {}",
+                    storage.preferences.hidden_fields.join(", ")
+                );
+            }
+            Ok(())
+        }
+        "confirm-tags" | "confirm_tags" => {
+            let tags = crate::config::types::parse_tags(value);
+            storage.with_lock(|storage| {
+                storage.preferences.confirm_tags = tags;
+                Ok(())
+            })?;
+
+            if storage.preferences.confirm_tags.is_empty() {
+                println!("Confirm tags cleared");
+            } else {
+                println!(
+                    "Confirm tags set to: {}",
+                    storage.preferences.confirm_tags.join(", ")
+                );
+            }
+            Ok(())
+        }
+        "menu-mode" | "menu_mode" => {
+            let grouped = match value.trim() {
+                "grouped" => true,
+                "flat" => false,
+                other => anyhow::bail!("Invalid menu mode '{}'. Use grouped or flat", other),
+            };
+            storage.with_lock(|storage| {
+                storage.preferences.grouped_menu = grouped;
+                Ok(())
+            })?;
+
+            println!("Menu mode set to: {}", value.trim());
+            Ok(())
+        }
+        "fast-select" | "fast_select" => {
+            let fast_select = match value.trim() {
+                "on" => true,
+                "off" => false,
+                other => anyhow::bail!("Invalid fast-select value '{}'. Use on or off", other),
+            };
+            storage.with_lock(|storage| {
+                storage.preferences.fast_select = fast_select;
+                Ok(())
+            })?;
+
+            println!("Fast select: {}", value.trim());
+            Ok(())
+        }
+        "confirm-launch" | "confirm_launch" => {
+            let confirm = match value.trim() {
+                "on" => true,
+                "off" => false,
+                other => anyhow::bail!("Invalid confirm-launch value '{}'. Use on or off", other),
+            };
+            storage.with_lock(|storage| {
+                storage.preferences.confirm_before_launch = confirm;
+                Ok(())
+            })?;
+
+            println!("Confirm before launch: {}", value.trim());
+            Ok(())
+        }
+        "settings-dir" | "settings_dir" => {
+            let directory = value.trim();
+            storage.with_lock(|storage| {
+                if directory.is_empty() {
+                    storage.claude.settings_dir = None;
+                } else {
+                    storage.set_claude_settings_dir(directory.to_string());
+                }
+                Ok(())
+            })?;
+
+            let path = crate::utils::get_claude_settings_path(
+                storage.get_claude_settings_dir().map(String::as_str),
+            )?;
+            println!("Claude settings file: {}", path.display());
+            Ok(())
+        }
+        "claude-path" | "claude_path" => {
+            let path = value.trim();
+            if !path.is_empty() {
+                crate::platform::check_executable(Path::new(path), "claude-path")
+                    .map_err(|e| invalid(format!("{e:#}")))?;
+            }
+            storage.with_lock(|storage| {
+                storage.claude_binary = (!path.is_empty()).then(|| path.to_string());
+                Ok(())
+            })?;
+
+            let (binary, source) =
+                crate::platform::choose_claude_binary(None, storage.claude_binary.as_deref());
+            println!("Claude binary: {} ({source})", binary.display());
+            Ok(())
+        }
+        "storage-mode" | "storage_mode" => {
+            let mode = parse_storage_mode(value.trim())?;
+            storage.with_lock(|storage| {
+                storage.default_storage_mode = Some(mode.clone());
+                Ok(())
+            })?;
+
+            println!("Default storage mode: {}", mode.as_str());
+            Ok(())
+        }
+        "verify-writes" | "verify_writes" => {
+            let skip = match value.trim() {
+                "on" => false,
+                "off" => true,
+                other => anyhow::bail!("Invalid verify-writes value '{}'. Use on or off", other),
+            };
+            storage.with_lock(|storage| {
+                storage.preferences.skip_write_verification = skip;
+                Ok(())
+            })?;
+
+            println!("Write verification: {}", value.trim());
+            Ok(())
+        }
+        "skip-permissions" | "skip_permissions" => {
+            let no_skip = match value.trim() {
+                "on" => false,
+                "off" => true,
+                other => {
+                    anyhow::bail!("Invalid skip-permissions value '{}'. Use on or off", other)
+                }
+            };
+            storage.with_lock(|storage| {
+                storage.preferences.no_skip_permissions = no_skip;
+                Ok(())
+            })?;
+
+            println!("Skip permissions: {}", value.trim());
+            Ok(())
+        }
+        _ => Err(anyhow!(
+            "Unknown preference '{}'. Valid keys: hidden-fields, confirm-tags, menu-mode, fast-select, confirm-launch, settings-dir, claude-path, storage-mode, verify-writes, skip-permissions",
+            key
+        )),
+    }
+}
+
+/// Apply one field change to each of `aliases` in memory
+///
+/// Every change is validated before any configuration is touched, and the
+/// caller saves once afterwards.
+///
+/// # Errors
+/// Returns error if an alias is missing or the value is invalid for the field
+pub fn apply_field_to_aliases(
+    storage: &mut ConfigStorage,
+    aliases: &[String],
+    field: &str,
+    value: &str,
+) -> Result<()> {
+    let mut updated = Vec::with_capacity(aliases.len());
+    for alias in aliases {
+        let mut config = storage
+            .get_configuration(alias)
+            .ok_or_else(|| config_not_found(alias))?
+            .clone();
+        config.set_field(field, value)?;
+        updated.push((alias, config));
+    }
+
+    for (alias, config) in updated {
+        storage.update_configuration(alias, config)?;
+    }
+    Ok(())
+}
+
+/// Handle `cc-switch set --where <filter> <field> <value>`
+///
+/// # Errors
+/// Returns error if the filter or field is invalid, or saving fails
+pub fn handle_batch_set(
+    filter: &str,
+    field: &str,
+    value: &str,
+    yes: bool,
+    dry_run: bool,
+    storage: &mut ConfigStorage,
+) -> Result<()> {
+    use crate::cli::display_utils::{FieldMask, format_batch_set_preview, normalize_field_name};
+    use crate::config::ConfigFilter;
+
+    let filter_expr = filter;
+    let filter = ConfigFilter::parse(filter_expr)?;
+    let field = normalize_field_name(field);
+    if !Configuration::FIELD_NAMES.contains(&field.as_str()) {
+        anyhow::bail!(
+            "Unknown field '{}'. Valid fields: {}",
+            field,
+            Configuration::FIELD_NAMES.join(", ")
+        );
+    }
+
+    let aliases = filter.matching_aliases(storage);
+    if aliases.is_empty() {
+        println!("No configurations match '{}'", filter_expr);
+        return Ok(());
+    }
+    // An invalid value (e.g. a malformed URL) fails before it is previewed
+    if let Some(config) = storage.configurations.get(&aliases[0]) {
+        config.clone().set_field(&field, value)?;
+    }
+
+    let mask = FieldMask::from_storage(storage);
+    println!(
+        "{} configuration(s) match '{}':",
+        aliases.len(),
+        filter_expr
+    );
+    for line in format_batch_set_preview(&storage.configurations, &aliases, &field, value, &mask) {
+        println!("{line}");
+    }
+
+    if dry_run {
+        println!("Dry run: no changes saved");
+        return Ok(());
+    }
+
+    if !yes {
+        let answer = read_input("Apply these changes? [y/N]: ")?;
+        if !matches!(answer.to_lowercase().as_str(), "y" | "yes") {
+            println!("Aborted: no changes saved");
+            return Ok(());
+        }
+    }
+
+    // The aliases confirmed above are updated in the store as it is under
+    // the lock; one removed meanwhile fails the whole batch
+    storage.with_lock(|storage| apply_field_to_aliases(storage, &aliases, &field, value))?;
+    println!("Updated {} configuration(s)", aliases.len());
+    Ok(())
+}
+
+/// Choose the alias to launch from a `use` failover chain, reporting on stderr
+///
+/// # Errors
+/// Returns error if no alias in the chain is usable
+fn select_alias_from_chain(
+    storage: &ConfigStorage,
+    chain: &[String],
+    preflight: bool,
+) -> Result<String> {
+    use crate::config::chain::{ChainEntry, HttpProber, Prober, select_from_chain};
+
+    let entries = ChainEntry::resolve_all(storage, chain);
+    let mut http = HttpProber::new(std::time::Duration::from_secs(5));
+    let prober = preflight.then_some(&mut http as &mut dyn Prober);
+    let selection = select_from_chain(&entries, prober)?;
+
+    for (alias, reason) in &selection.skipped {
+        eprintln!("Skipping '{alias}': {reason}");
+    }
+    let chosen = chain[selection.index].clone();
+    if chain.len() > 1 {
+        eprintln!("Using '{chosen}' from failover chain");
+    }
+    if !preflight && !selection.ignored.is_empty() {
+        eprintln!(
+            "Warning: preflight is disabled, ignoring {} (pass --preflight to fail over)",
+            selection.ignored.join(", ")
+        );
+    }
+    Ok(chosen)
+}
+
+/// The `--shell` argument, or the name of `$SHELL` when it was not given
+fn shell_or_detected(shell: Option<String>) -> Result<String> {
+    match shell {
+        Some(shell) => Ok(shell),
+        None => std::env::var("SHELL")
+            .ok()
+            .and_then(|path| {
+                std::path::Path::new(&path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+            })
+            .ok_or_else(|| anyhow!("Could not detect the shell; pass --shell <SHELL>")),
+    }
+}
+
+/// Handle `cc-switch env`: print the statements that give the calling shell
+/// the environment `use` would launch Claude with
+///
+/// # Errors
+/// Returns error if the alias is not found or the shell is not supported
+pub fn handle_env(alias_name: &str, shell: Option<String>, storage: &ConfigStorage) -> Result<()> {
+    let shell = shell_or_detected(shell)?;
+    let env = match resolve_alias_target(storage, alias_name)? {
+        AliasTarget::Official => EnvironmentConfig::empty(),
+        AliasTarget::Config(config) => {
+            EnvironmentConfig::from_config_resolved(&config)?.with_alias(alias_name)
+        }
+    };
+    for statement in crate::cli::export_shell::env_statements(&env, &shell)? {
+        println!("{statement}");
+    }
+    Ok(())
+}
+
+/// Handle `cc-switch resolve`: print the plan `use` would launch with
+///
+/// The plan is built like a launch's, with the daemon proxy substituted and
+/// the token command run, but nothing is written or started.
+///
+/// # Errors
+/// Returns error if the alias is reserved or not found, the token command
+/// fails or the Claude binary can't be found
+pub fn handle_resolve(
+    alias_name: &str,
+    json: bool,
+    reveal: bool,
+    storage: &ConfigStorage,
+) -> Result<()> {
+    let AliasTarget::Config(config) = resolve_alias_target(storage, alias_name)? else {
+        return Err(invalid(format!(
+            "'{alias_name}' is a reserved name for the official configuration; there is nothing to resolve"
+        )));
+    };
+    let mut config = config.into_owned();
+    if let crate::daemon::ProxyResolution::Proxied { proxy_url } =
+        crate::daemon::try_resolve_proxy(&config.url)
+    {
+        config.url = proxy_url;
+    }
+    let resolved = config.with_resolved_token()?;
+    let mut plan = LaunchPlan::build(alias_name, &resolved, storage, &LaunchOptions::default())?;
+    if !reveal {
+        plan = plan.masked();
+    }
+    if json {
+        println!("{}", crate::reporting::to_json(&plan)?);
+    } else {
+        print!("{plan}");
+    }
+    Ok(())
+}
+
+/// Handle `cc-switch unuse`
+///
+/// Statements for the shell go to stdout so the output can be eval'd; the
+/// report of what was cleared in each layer goes to stderr.
+///
+/// # Errors
+/// Returns error if the shell is unsupported or settings.json cannot be updated
+pub fn handle_unuse(
+    shell: Option<String>,
+    persistent: bool,
+    storage: &ConfigStorage,
+) -> Result<()> {
+    let shell = shell_or_detected(shell)?;
+
+    let names = crate::config::managed_env_var_names();
+    for statement in crate::config::unset_statements(&shell, &names)? {
+        println!("{statement}");
+    }
+    eprintln!("Shell ({shell}): unset {}", names.join(", "));
+
+    #[cfg(unix)]
+    let marker = ClaudeSettings::clear_alias_file_for_pid(std::os::unix::process::parent_id())?;
+    #[cfg(not(unix))]
+    let marker: Option<std::path::PathBuf> = None;
+    match marker {
+        Some(path) => eprintln!("Session marker: removed {}", path.display()),
+        None => eprintln!("Session marker: none for this session"),
+    }
+
+    if persistent {
+        let custom_dir = storage.get_claude_settings_dir().map(|s| s.as_str());
+        let mut settings = ClaudeSettings::load(custom_dir)?;
+        settings.skip_write_verification = storage.preferences.skip_write_verification;
+        let removed: Vec<&str> = Configuration::get_env_field_names()
+            .into_iter()
+            .filter(|name| settings.env.contains_key(*name))
+            .collect();
+        if removed.is_empty() {
+            eprintln!("settings.json: no Anthropic variables to remove");
+        } else {
+            let backup = ClaudeSettings::backup(custom_dir)?;
+            settings.remove_anthropic_env();
+            settings.save(custom_dir)?;
+            eprintln!("settings.json: removed {}", removed.join(", "));
+            if let Some(backup) = backup {
+                eprintln!("  (backup saved to {})", backup.display());
+            }
+        }
+    } else {
+        eprintln!("settings.json: unchanged (use --persistent to clean it)");
+    }
+
+    Ok(())
+}
+
+/// Main entry point for the CLI application
+///
+/// Parses command-line arguments and executes the appropriate action:
+/// - Switch configuration with `-c` flag
+/// - Execute subcommands (add, remove, list)
+/// - Show help if no arguments provided
+///
+/// # Errors
+/// Returns error if any operation fails (file I/O, parsing, etc.)
+pub fn run() -> Result<()> {
+    // Usage errors exit with the validation code; clap's own 2 would read
+    // as "not found". --help and --version still exit 0.
+    let cli = Cli::try_parse().unwrap_or_else(|err| {
+        if !err.use_stderr() {
+            err.exit();
+        }
+        let _ = err.print();
+        std::process::exit(ExitCode::Invalid.code().into());
+    });
+    let color_flag = match (cli.force_color, cli.no_color) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    };
+    crate::cli::color::apply_color_policy(color_flag);
+    if let Some(path) = &cli.config {
+        crate::utils::set_config_path_override(path.clone());
+    }
+
+    // Handle --migrate flag: same as `cc-switch migrate`
+    if cli.migrate {
+        handle_migrate(&mut ConfigStorage::load()?, None, false)?;
+        return Ok(());
+    }
+
+    // Handle --list-aliases flag for completion
+    if cli.list_aliases {
+        list_aliases_for_completion()?;
+        return Ok(());
+    }
+
+    // Handle --list-stored-aliases flag for completion
+    if cli.list_stored_aliases {
+        list_stored_aliases_for_completion()?;
+        return Ok(());
+    }
+
+    // Handle --list-codex-aliases flag for completion
+    if cli.list_codex_aliases {
+        list_codex_aliases_for_completion()?;
+        return Ok(());
+    }
+
+    crate::cli::display_utils::set_show_hidden(cli.show_hidden);
+
+    // Reap per-PID alias files for terminated sessions on every invocation.
+    // Skipped for completion-only paths above to keep shell completion fast.
+    let _ = ClaudeSettings::cleanup_orphan_alias_files();
+
+    // Handle --store flag: set default storage mode and exit
+    if let Some(ref store_str) = cli.store
+        && cli.command.is_none()
+    {
+        // No command provided, so --store is a setter
+        let mode = parse_storage_mode(store_str)?;
+
+        let mut storage = ConfigStorage::default();
+        storage.with_lock(|storage| {
+            storage.default_storage_mode = Some(mode.clone());
+            Ok(())
+        })?;
+
+        println!("Default storage mode set to: {}", mode.as_str());
+        return Ok(());
+    }
+
+    if cli.mode.is_some() && !matches!(cli.command, None | Some(Commands::Use { .. })) {
+        return Err(invalid(
+            "--mode only applies to `use` and the interactive menu; use `--store` to change the default",
+        ));
+    }
+
+    if cli.tag.is_some() && cli.command.is_some() {
+        anyhow::bail!(
+            "--tag before a subcommand only filters the interactive menu; use `list --tag` to filter the list"
+        );
+    }
+
+    // The doctor diagnoses a broken storage file instead of failing to load it
+    if let Some(Commands::Doctor { json }) = cli.command {
+        return crate::cli::doctor::run_doctor(json);
+    }
+
+    // Handle subcommands
+    if let Some(command) = cli.command {
+        let mut storage = ConfigStorage::load()?;
+
+        match command {
+            Commands::Add {
+                alias_name,
+                token,
+                api_key,
+                token_command,
+                url,
+                model,
+                small_fast_model,
+                max_thinking_tokens,
+                api_timeout_ms,
+                claude_code_disable_nonessential_traffic,
+                anthropic_default_sonnet_model,
+                anthropic_default_opus_model,
+                anthropic_default_haiku_model,
+                claude_code_subagent_model,
+                claude_code_disable_nonstreaming_fallback,
+                claude_code_effort_level,
+                disable_prompt_caching,
+                claude_code_disable_experimental_betas,
+                disable_autoupdater,
+                force,
+                interactive,
+                token_arg,
+                url_arg,
                 from_file,
+                strict_json,
+                no_validate,
+                tags,
+                color,
+                provider,
+                preset,
+                env,
+                launch_args,
+                reveal,
             } => {
                 let resolved_from_file: Option<String> = match from_file {
                     Some(Some(path)) => {
@@ -792,6 +2077,7 @@ pub fn run() -> Result<()> {
                     alias_name,
                     token,
                     api_key,
+                    token_command,
                     url,
                     model,
                     small_fast_model,
@@ -807,197 +2093,308 @@ pub fn run() -> Result<()> {
                     disable_prompt_caching,
                     claude_code_disable_experimental_betas,
                     disable_autoupdater,
+                    claude_code_experimental_agent_teams: None,
+                    claude_code_disable_1m_context: None,
                     force,
                     interactive,
                     token_arg,
                     url_arg,
                     from_file: resolved_from_file,
+                    strict_json,
+                    no_validate,
+                    tags,
+                    color,
+                    provider,
+                    preset,
+                    extra_env: parse_env_assignments(&env)?,
+                    launch_args,
+                    output: cli.format,
+                    reveal,
                 };
                 handle_add_command(params, &mut storage)?;
+                print_stale_completion_hint();
             }
-            Commands::Remove { alias_names } => {
-                let mut removed_count = 0;
-                let mut not_found_aliases = Vec::new();
-
-                for alias_name in &alias_names {
-                    if storage.remove_configuration(alias_name) {
-                        removed_count += 1;
-                        println!("Configuration '{alias_name}' removed successfully");
-                    } else {
-                        not_found_aliases.push(alias_name.clone());
-                        println!("Configuration '{alias_name}' not found");
-                    }
-                }
+            Commands::Edit {
+                alias_name,
+                editor,
+                fields,
+            } => {
+                resolve_stored_alias(&storage, &alias_name)?;
+                let updates = fields.into_updates();
 
-                if removed_count > 0 {
-                    storage.save()?;
+                if !updates.is_empty() {
+                    handle_edit_fields(&mut storage, &alias_name, &updates)?;
+                } else if editor {
+                    crate::cli::editor::edit_in_editor(
+                        &mut storage,
+                        &alias_name,
+                        &mut crate::interactive::StdinPrompter,
+                    )?;
+                } else {
+                    crate::interactive::edit_configuration(&storage, &alias_name)?;
                 }
-
-                if !not_found_aliases.is_empty() {
-                    eprintln!(
-                        "Warning: The following configurations were not found: {}",
-                        not_found_aliases.join(", ")
-                    );
+                print_stale_completion_hint();
+            }
+            Commands::Import {
+                paths,
+                force,
+                dry_run,
+                strict_json,
+            } => {
+                handle_import(&mut storage, &paths, force, dry_run, strict_json)?;
+                if !dry_run {
+                    print_stale_completion_hint();
                 }
-
-                if removed_count > 0 {
-                    println!("Successfully removed {removed_count} configuration(s)");
+            }
+            Commands::Clone {
+                alias_name,
+                new_alias,
+                force,
+                fields,
+            } => {
+                handle_clone(
+                    &mut storage,
+                    &alias_name,
+                    &new_alias,
+                    &fields.into_updates(),
+                    force,
+                )?;
+                print_stale_completion_hint();
+            }
+            Commands::Export { alias_name, output } => {
+                let config = resolve_stored_alias(&storage, &alias_name)?.resolve_token()?;
+                let json =
+                    serde_json::to_string_pretty(&crate::cli::export::settings_json(&config))?;
+                match output {
+                    Some(output) => {
+                        let path = crate::cli::export::export_path(&output, &alias_name);
+                        crate::cli::export::write_export(&path, &json)?;
+                        println!(
+                            "Configuration '{}' exported to {}",
+                            alias_name,
+                            path.display()
+                        );
+                    }
+                    None => println!("{json}"),
                 }
             }
-            Commands::List { plain, name } => {
-                if name {
-                    if storage.configurations.is_empty() {
-                        println!("No configurations stored");
-                    } else {
-                        for (alias_name, config) in &storage.configurations {
-                            println!("{}: {}", alias_name, config.url);
-                        }
+            Commands::Test {
+                alias_name,
+                all,
+                live,
+            } => {
+                handle_test(
+                    &storage,
+                    alias_name.as_deref(),
+                    all,
+                    live,
+                    &mut crate::config::probe::HttpTransport,
+                )?;
+            }
+            Commands::Rename {
+                old_alias,
+                new_alias,
+                force,
+            } => {
+                validate_alias_name(&new_alias)?;
+                storage.with_lock(|storage| {
+                    resolve_stored_alias(storage, &old_alias)?;
+                    if old_alias != new_alias
+                        && storage.get_configuration(&new_alias).is_some()
+                        && !force
+                    {
+                        return Err(conflict(format!(
+                            "Configuration '{new_alias}' already exists; use --force to replace it"
+                        )));
+                    }
+                    storage.rename_configuration(&old_alias, &new_alias)
+                })?;
+                println!("Configuration '{old_alias}' renamed to '{new_alias}'");
+            }
+            Commands::Remove {
+                alias_names,
+                all,
+                yes,
+                strict,
+            } => {
+                crate::cli::remove::handle_remove(
+                    &mut storage,
+                    &alias_names,
+                    all,
+                    yes,
+                    strict,
+                    cli.format,
+                    &mut StdinPrompter,
+                )?;
+            }
+            Commands::List {
+                plain,
+                name,
+                orphaned,
+                reveal,
+                tag,
+                group_by_tag,
+                sort,
+                reverse,
+            } => {
+                use crate::cli::display_utils::{
+                    FieldMask, ListLayout, format_config_list_json, format_config_list_names,
+                    format_config_list_plain, format_config_list_plain_by_tag, get_terminal_width,
+                };
+                use std::io::IsTerminal;
+                if cli.format.is_json() && (plain || name || orphaned) {
+                    anyhow::bail!(
+                        "--plain, --name and --orphaned cannot be used with --format json"
+                    );
+                }
+                let mask = FieldMask::from_storage(&storage);
+                let configs = storage.configurations_tagged(tag.as_deref())?;
+                let layout = ListLayout {
+                    sort,
+                    reverse,
+                    max_width: std::io::stdout().is_terminal().then(get_terminal_width),
+                };
+                if orphaned {
+                    use crate::config::orphans::{SystemProbe, find_orphans};
+                    let orphans = find_orphans(&storage, &SystemProbe);
+                    if orphans.is_empty() {
+                        println!("No orphaned configurations");
+                        return Ok(());
+                    }
+                    for orphan in &orphans {
+                        println!("{}: {}", orphan.alias, orphan.reference);
+                    }
+                    anyhow::bail!("{} broken reference(s) found", orphans.len());
+                } else if name {
+                    for line in format_config_list_names(
+                        &configs,
+                        &mask,
+                        storage.default_alias.as_deref(),
+                        &storage.last_used_at,
+                        &layout,
+                    ) {
+                        println!("{line}");
                     }
                 } else if plain {
                     // Text output when -p flag is used
-                    if storage.configurations.is_empty() {
-                        println!("No configurations stored");
+                    let format = if group_by_tag {
+                        format_config_list_plain_by_tag
                     } else {
-                        println!("Stored configurations:");
-                        for (alias_name, config) in &storage.configurations {
-                            let (auth_label, auth_value) = config.auth_env_pair();
-                            let mut info =
-                                format!("{}={}, url={}", auth_label, auth_value, config.url);
-                            if let Some(model) = &config.model {
-                                info.push_str(&format!(", model={model}"));
-                            }
-                            if let Some(small_fast_model) = &config.small_fast_model {
-                                info.push_str(&format!(", small_fast_model={small_fast_model}"));
-                            }
-                            if let Some(max_thinking_tokens) = config.max_thinking_tokens {
-                                info.push_str(&format!(
-                                    ", max_thinking_tokens={max_thinking_tokens}"
-                                ));
-                            }
-                            if let Some(subagent_model) = &config.claude_code_subagent_model {
-                                info.push_str(&format!(", subagent_model={subagent_model}"));
-                            }
-                            if let Some(flag) = config.claude_code_disable_nonstreaming_fallback {
-                                info.push_str(&format!(", disable_nonstreaming_fallback={flag}"));
-                            }
-                            if let Some(effort_level) = &config.claude_code_effort_level {
-                                info.push_str(&format!(", effort_level={effort_level}"));
-                            }
-                            if let Some(flag) = config.disable_prompt_caching {
-                                info.push_str(&format!(", disable_prompt_caching={flag}"));
-                            }
-                            if let Some(flag) = config.claude_code_disable_experimental_betas {
-                                info.push_str(&format!(", disable_experimental_betas={flag}"));
-                            }
-                            if let Some(flag) = config.disable_autoupdater {
-                                info.push_str(&format!(", disable_autoupdater={flag}"));
-                            }
-                            println!("  {alias_name}: {info}");
-                        }
+                        format_config_list_plain
+                    };
+                    for line in format(
+                        &configs,
+                        &mask,
+                        reveal,
+                        storage.default_alias.as_deref(),
+                        &storage.last_used_at,
+                        &storage.use_counts,
+                        &layout,
+                    ) {
+                        println!("{line}");
                     }
                 } else {
                     // JSON output (default)
                     println!(
                         "{}",
-                        serde_json::to_string_pretty(&storage.configurations)
-                            .map_err(|e| anyhow!("Failed to serialize configurations: {}", e))?
+                        format_config_list_json(
+                            &configs,
+                            &mask,
+                            reveal,
+                            &storage.last_used_at,
+                            &storage.use_counts,
+                            &layout
+                        )?
                     );
                 }
             }
-            Commands::Completion { shell } => {
-                generate_completion(&shell)?;
+            Commands::Completion {
+                shell,
+                doctor_shell,
+                json,
+                install,
+                force,
+            } => {
+                if shell == "doctor" {
+                    if install {
+                        anyhow::bail!("--install is not supported by `completion doctor`");
+                    }
+                    let doctor_shell = doctor_shell.ok_or_else(|| {
+                        anyhow!("Usage: cc-switch completion doctor <fish|zsh|bash>")
+                    })?;
+                    run_completion_doctor(&doctor_shell, json)?;
+                } else {
+                    if doctor_shell.is_some() {
+                        anyhow::bail!("Unexpected argument after shell '{}'", shell);
+                    }
+                    if json {
+                        anyhow::bail!("--json is only supported by `completion doctor`");
+                    }
+                    if install {
+                        install_completion(&shell, force)?;
+                    } else {
+                        generate_completion(&shell)?;
+                    }
+                }
             }
             Commands::Use {
                 alias_name,
                 resume,
                 r#continue,
+                yes,
+                confirm,
+                clean_settings,
+                no_launch,
+                preflight,
+                mut claude_args,
+                with,
                 prompt,
+                passthrough,
             } => {
-                // Handle special reset aliases (route through daemon proxy if running)
-                if alias_name == "cc" || alias_name == "official" {
-                    use colored::Colorize;
-                    println!("{}", "Using official Claude configuration".blue());
-
-                    let mut settings = ClaudeSettings::load(
-                        storage.get_claude_settings_dir().map(|s| s.as_str()),
+                // `use -` goes back to the last configuration launched
+                let alias_name = if alias_name == "-" {
+                    storage.last_used_alias()?.to_string()
+                } else {
+                    alias_name
+                };
+                let (chain_rest, prompt) =
+                    crate::config::chain::split_chain_and_prompt(&storage, &prompt);
+                let alias_name = if chain_rest.is_empty() && !preflight {
+                    ensure_alias_for_use(
+                        &mut storage,
+                        &alias_name,
+                        &mut StdinPrompter,
+                        handle_interactive_add,
                     )?;
-                    settings.remove_anthropic_env();
-                    settings.save(storage.get_claude_settings_dir().map(|s| s.as_str()))?;
-
-                    crate::daemon::print_version_mismatch_warning();
-                    let env = crate::daemon::build_official_env();
-
-                    launch_claude_with_env(env, None, None, r#continue)?;
-                    return Ok(());
-                }
-
-                let mut config = storage
-                    .configurations
-                    .get(&alias_name)
-                    .ok_or_else(|| anyhow!("Configuration '{}' not found", alias_name))?
-                    .clone();
-
-                // Consult daemon state: substitute proxy URL if daemon is alive.
-                let original_url = config.url.clone();
-                crate::daemon::print_version_mismatch_warning();
-                match crate::daemon::try_resolve_proxy(&config.url) {
-                    crate::daemon::ProxyResolution::Proxied { proxy_url } => {
-                        config.url = proxy_url;
-                    }
-                    crate::daemon::ProxyResolution::Direct => {
-                        if !original_url.is_empty() {
-                            use colored::Colorize;
-                            eprintln!(
-                                "{}",
-                                format!(
-                                    "\u{2139} cc daemon is not running — traffic for '{alias_name}' will NOT be captured."
-                                )
-                                .blue()
-                            );
-                            eprintln!(
-                                "{}",
-                                "  Run `cc-switch daemon start` and re-run to enable capture."
-                                    .blue()
-                            );
-                        }
-                    }
-                }
-
-                let env_config = EnvironmentConfig::from_config(&config).with_alias(&alias_name);
-                let storage_mode = storage.default_storage_mode.clone().unwrap_or_default();
-
-                // Update settings.json with the configuration
-                let mut settings =
-                    ClaudeSettings::load(storage.get_claude_settings_dir().map(|s| s.as_str()))?;
-                settings.switch_to_config_with_mode(
-                    &config,
-                    storage_mode,
-                    storage.get_claude_settings_dir().map(|s| s.as_str()),
-                )?;
-
-                println!("Switched to configuration '{}'", alias_name);
-                println!("  URL:   {}", config.url);
-                if config.url != original_url {
-                    println!("  (proxied from: {})", original_url);
-                }
-                let (auth_label, auth_value) = config.auth_env_pair();
-                println!(
-                    "  {}: {}",
-                    auth_label,
-                    crate::cli::display_utils::format_token_for_display(auth_value)
-                );
-
-                let prompt_str = if prompt.is_empty() {
-                    None
+                    alias_name
                 } else {
-                    Some(prompt.join(" "))
+                    let chain: Vec<String> =
+                        std::iter::once(alias_name).chain(chain_rest).collect();
+                    select_alias_from_chain(&storage, &chain, preflight)?
                 };
 
-                launch_claude_with_env(
-                    env_config,
-                    prompt_str.as_deref(),
-                    resume.as_deref(),
-                    r#continue,
+                claude_args.extend(passthrough);
+                let options = LaunchOptions {
+                    claude_args,
+                    prompt: (!prompt.is_empty()).then(|| prompt.join(" ")),
+                    resume,
+                    continue_session: r#continue,
+                    with,
+                    yes,
+                    confirm,
+                    quiet: false,
+                    no_skip_permissions: cli.no_skip_permissions,
+                    clear_env: cli.clear_env,
+                    storage_mode: cli.mode,
+                    clean_settings,
+                    no_launch,
+                };
+                launch_selection(
+                    &storage,
+                    &Selection::from_alias(&alias_name),
+                    &options,
+                    &mut StdinPrompter,
+                    &mut ExecLauncher,
                 )?;
             }
             Commands::Codex { command } => match command {
@@ -1092,6 +2489,165 @@ pub fn run() -> Result<()> {
                 };
                 handle_daemon_command(action, &storage)?;
             }
+            Commands::Set {
+                filter,
+                field,
+                value,
+                yes,
+                dry_run,
+            } => {
+                handle_batch_set(&filter, &field, &value, yes, dry_run, &mut storage)?;
+            }
+            Commands::Env { alias_name, shell } => {
+                handle_env(&alias_name, shell, &storage)?;
+            }
+            Commands::Resolve {
+                alias_name,
+                json,
+                reveal,
+            } => {
+                handle_resolve(&alias_name, json || cli.format.is_json(), reveal, &storage)?;
+            }
+            Commands::Unuse { shell, persistent } => {
+                handle_unuse(shell, persistent, &storage)?;
+            }
+            Commands::Alias {
+                shell,
+                per_config,
+                prefix,
+            } => {
+                crate::cli::completion::print_aliases(&shell, &storage, per_config, &prefix)?;
+            }
+            Commands::ExportShell {
+                shell,
+                show_secrets,
+            } => {
+                print!(
+                    "{}",
+                    crate::cli::export_shell::render_export_shell(
+                        &storage.configurations,
+                        &shell,
+                        show_secrets
+                    )?
+                );
+                if !show_secrets && !storage.configurations.is_empty() {
+                    eprintln!(
+                        "Warning: tokens were replaced with '{}'; pass --show-secrets to include them",
+                        crate::cli::export_shell::SECRET_PLACEHOLDER
+                    );
+                }
+            }
+            Commands::SetDefault { alias_name, clear } => {
+                let alias_name = alias_name.filter(|_| !clear);
+                storage.with_lock(|storage| {
+                    if let Some(alias) = &alias_name {
+                        resolve_stored_alias(storage, alias)?;
+                    }
+                    storage.set_default_alias(alias_name.as_deref())
+                })?;
+                match alias_name {
+                    Some(alias) => println!("Default configuration set to '{alias}'"),
+                    None => println!("Default configuration cleared"),
+                }
+            }
+            Commands::AliasMap { json } => {
+                use crate::cli::alias_map::{
+                    format_alias_map_json, format_alias_map_lines, public_views,
+                };
+                let mask = crate::cli::display_utils::FieldMask::from_storage(&storage);
+                let views = public_views(&storage.configurations, &mask);
+                if json {
+                    println!("{}", format_alias_map_json(&views)?);
+                } else {
+                    for line in format_alias_map_lines(&views) {
+                        println!("{line}");
+                    }
+                }
+            }
+            Commands::Current { json } => {
+                if json || cli.format.is_json() {
+                    let report = crate::cli::current::current_status(&storage)?;
+                    println!("{}", crate::reporting::to_json(&report)?);
+                } else {
+                    crate::interactive::handle_current_command()?;
+                }
+            }
+            Commands::Version { short, json } => {
+                handle_version(short, json)?;
+            }
+            Commands::Doctor { .. } => unreachable!("handled before loading storage"),
+            Commands::Config { command } => match command {
+                crate::cli::ConfigCommands::Set { key, value } => {
+                    handle_config_set(&key, &value, &mut storage)?;
+                }
+            },
+            Commands::Backup { file } => {
+                let json = crate::cli::backup::backup_json(&storage)?;
+                crate::cli::export::write_export(&file, &json)?;
+                println!(
+                    "Backed up {} configuration(s) to {}",
+                    storage.configurations.len(),
+                    file.display()
+                );
+            }
+            Commands::Restore {
+                file,
+                merge: _,
+                replace,
+                force,
+            } => {
+                let mode = if replace {
+                    crate::cli::backup::RestoreMode::Replace
+                } else {
+                    crate::cli::backup::RestoreMode::Merge { force }
+                };
+                handle_restore(&mut storage, &file, mode)?;
+                print_stale_completion_hint();
+            }
+            Commands::Prune {
+                apply,
+                yes,
+                check_url,
+            } => {
+                let mut http =
+                    crate::config::chain::HttpProber::new(std::time::Duration::from_secs(5));
+                let prober =
+                    check_url.then_some(&mut http as &mut dyn crate::config::chain::Prober);
+                crate::cli::prune::handle_prune(
+                    &mut storage,
+                    prober,
+                    apply,
+                    yes,
+                    &mut StdinPrompter,
+                )?;
+            }
+            Commands::Migrate { from, force } => {
+                handle_migrate(&mut storage, from.as_deref().map(Path::new), force)?;
+                print_stale_completion_hint();
+            }
+            Commands::RestoreBackup => {
+                let backup_path = ConfigStorage::restore_backup()?;
+                println!("Configurations restored from {}", backup_path.display());
+            }
+            Commands::Settings { command } => {
+                use crate::cli::settings;
+                match command {
+                    crate::cli::SettingsCommands::Show { dir } => {
+                        settings::handle_show(settings::settings_dir(dir.as_deref(), &storage))?;
+                    }
+                    crate::cli::SettingsCommands::Clean { dir } => {
+                        settings::handle_clean(settings::settings_dir(dir.as_deref(), &storage))?;
+                    }
+                    crate::cli::SettingsCommands::Path { dir } => {
+                        let custom_dir = settings::settings_dir(dir.as_deref(), &storage);
+                        println!("{}", settings::settings_path(custom_dir)?.display());
+                    }
+                }
+            }
+            Commands::Secure { command } => match command {
+                crate::cli::SecureCommands::Enable => crate::cli::secure::handle_enable()?,
+                crate::cli::SecureCommands::Disable => crate::cli::secure::handle_disable()?,
+            },
             Commands::Statusline { action } => {
                 let custom_dir = storage.get_claude_settings_dir().map(|s| s.as_str());
                 match action {
@@ -1105,9 +2661,33 @@ pub fn run() -> Result<()> {
             }
         }
     } else {
-        // No command provided, show interactive configuration selection
+        // No command provided: launch the default configuration if one is
+        // set, otherwise show interactive configuration selection
         let storage = ConfigStorage::load()?;
-        handle_interactive_selection(&storage)?;
+        let options = LaunchOptions {
+            claude_args: cli.claude_args,
+            no_skip_permissions: cli.no_skip_permissions,
+            clear_env: cli.clear_env,
+            storage_mode: cli.mode,
+            ..Default::default()
+        };
+        match storage.default_alias.as_deref() {
+            Some(alias) if cli.tag.is_none() && storage.get_configuration(alias).is_some() => {
+                launch_selection(
+                    &storage,
+                    &Selection::from_alias(alias),
+                    &options,
+                    &mut StdinPrompter,
+                    &mut ExecLauncher,
+                )?;
+            }
+            default => {
+                if let Some(alias) = default {
+                    eprintln!("Warning: default configuration '{alias}' not found");
+                }
+                handle_interactive_selection(&storage, cli.tag.as_deref(), options)?;
+            }
+        }
     }
 
     Ok(())
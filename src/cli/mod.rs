@@ -1,8 +1,24 @@
+pub mod alias_map;
+pub mod backup;
 #[allow(clippy::module_inception)]
 pub mod cli;
+pub mod color;
 pub mod completion;
+pub mod current;
 pub mod display_utils;
+pub mod doctor;
+pub mod editor;
+pub mod export;
+pub mod export_shell;
 pub mod main;
+pub mod output;
+pub mod prune;
+pub mod remove;
+pub mod secure;
+pub mod settings;
 
 // Re-export types for convenience
-pub use crate::cli::cli::{Cli, CodexCommands, Commands, DaemonCommands, StatuslineAction};
+pub use crate::cli::cli::{
+    Cli, CodexCommands, Commands, ConfigCommands, DaemonCommands, OfficialAliasPolicy,
+    SecureCommands, SettingsCommands, StatuslineAction,
+};
@@ -0,0 +1,79 @@
+//! `--format`: human-readable text, or one JSON document on stdout for
+//! scripts.
+//!
+//! In JSON mode `list`, `current`, `add` and `remove` print exactly one JSON
+//! value on stdout. Their human messages go to stderr through
+//! [`OutputFormat::note`], so stdout can be piped straight into a parser.
+
+use clap::ValueEnum;
+use serde::Serialize;
+use std::fmt::Display;
+
+/// What commands print on stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable messages
+    #[default]
+    Text,
+    /// A single JSON document; messages go to stderr
+    Json,
+}
+
+impl OutputFormat {
+    pub fn is_json(self) -> bool {
+        self == Self::Json
+    }
+
+    /// Print a human message: on stdout as text, on stderr in JSON mode
+    pub fn note(self, message: impl Display) {
+        if self.is_json() {
+            eprintln!("{message}");
+        } else {
+            println!("{message}");
+        }
+    }
+}
+
+/// Outcome of `remove` in JSON mode
+#[derive(Debug, Default, Serialize)]
+pub struct RemoveSummary {
+    /// Aliases that were removed, in argument order
+    pub removed: Vec<String>,
+    /// Aliases the user chose to keep when asked, in argument order
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<String>,
+    /// Aliases that did not exist, in argument order
+    pub not_found: Vec<String>,
+}
+
+impl RemoveSummary {
+    /// One line counting the removed, skipped and missing aliases
+    pub fn line(&self) -> String {
+        let mut line = format!("Removed {} configuration(s)", self.removed.len());
+        if !self.skipped.is_empty() {
+            line.push_str(&format!(", skipped {}", self.skipped.len()));
+        }
+        if !self.not_found.is_empty() {
+            line.push_str(&format!(", not found {}", self.not_found.len()));
+        }
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remove_summary_keeps_both_lists() {
+        let summary = RemoveSummary {
+            removed: vec!["a".to_string()],
+            not_found: vec!["b".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&summary).unwrap(),
+            serde_json::json!({"removed": ["a"], "not_found": ["b"]})
+        );
+    }
+}
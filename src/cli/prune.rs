@@ -0,0 +1,322 @@
+//! `cc-switch prune`: find duplicate and broken configurations.
+//!
+//! [`analyze`] only reads the storage, so the findings can be tested on a
+//! constructed store; [`handle_prune`] prints them and, with `--apply`,
+//! removes the configurations the user agrees to.
+
+use anyhow::Result;
+use colored::Colorize;
+use std::collections::BTreeMap;
+
+use crate::config::chain::Prober;
+use crate::config::types::ConfigStorage;
+use crate::interactive::Prompter;
+
+/// `(heading, findings)` of a report category
+type Category<'a> = (&'static str, &'a [Finding]);
+
+/// Aliases by the credential, URL and model they share
+type DuplicateGroups<'a> = BTreeMap<(&'a str, &'a str, Option<&'a str>), Vec<&'a str>>;
+
+/// Outcome of probing a URL: `Err(reason)` when it didn't answer
+type ProbeOutcome = std::result::Result<(), String>;
+
+/// A configuration `prune` suggests removing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub alias: String,
+    /// Why it can go, e.g. "duplicate of 'work'"
+    pub reason: String,
+}
+
+/// Findings of [`analyze`], by category
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct PruneReport {
+    /// Same credential, URL and model as a configuration that is kept
+    pub duplicates: Vec<Finding>,
+    /// No credential or no URL
+    pub incomplete: Vec<Finding>,
+    /// URL that didn't answer (only with `--check-url`)
+    pub unreachable: Vec<Finding>,
+}
+
+impl PruneReport {
+    /// `(heading, findings)` of each category
+    fn categories(&self) -> [Category<'_>; 3] {
+        [
+            ("Duplicates", &self.duplicates),
+            ("Missing token or URL", &self.incomplete),
+            ("Unreachable", &self.unreachable),
+        ]
+    }
+
+    /// Every finding, category by category
+    pub fn findings(&self) -> impl Iterator<Item = &Finding> {
+        self.duplicates
+            .iter()
+            .chain(&self.incomplete)
+            .chain(&self.unreachable)
+    }
+
+    /// Whether nothing was found
+    pub fn is_empty(&self) -> bool {
+        self.findings().next().is_none()
+    }
+
+    /// The findings grouped under a heading per non-empty category
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for (heading, findings) in self.categories() {
+            if findings.is_empty() {
+                continue;
+            }
+            lines.push(format!("{heading} ({}):", findings.len()));
+            for finding in findings {
+                lines.push(format!("  {}: {}", finding.alias, finding.reason));
+            }
+        }
+        lines
+    }
+}
+
+/// Find the configurations of `storage` worth removing
+///
+/// A configuration is listed in one category only: incomplete ones are not
+/// also reported as duplicates or probed. Of a group of duplicates the most
+/// recently used one is kept (the first by name when none was used). With
+/// a `prober`, every distinct URL left is probed once.
+pub fn analyze(storage: &ConfigStorage, prober: Option<&mut dyn Prober>) -> PruneReport {
+    let mut report = PruneReport::default();
+    let mut groups = DuplicateGroups::new();
+    for (alias, config) in &storage.configurations {
+        // A token command stands for the token it prints
+        let credential = match &config.token_command {
+            Some(command) => command.as_str(),
+            None => config.auth_env_pair().1,
+        };
+        let missing = match (!config.has_credential(), config.url.trim().is_empty()) {
+            (true, true) => Some("no token and no URL"),
+            (true, false) => Some("no token"),
+            (false, true) => Some("no URL"),
+            (false, false) => None,
+        };
+        if let Some(missing) = missing {
+            report.incomplete.push(Finding {
+                alias: alias.clone(),
+                reason: missing.to_string(),
+            });
+            continue;
+        }
+        groups
+            .entry((credential, config.url.as_str(), config.model.as_deref()))
+            .or_default()
+            .push(alias);
+    }
+
+    let mut kept = Vec::new();
+    for mut aliases in groups.into_values() {
+        aliases.sort_by_key(|alias| (std::cmp::Reverse(storage.last_used_at.get(*alias)), *alias));
+        let keep = aliases[0];
+        kept.push(keep);
+        report
+            .duplicates
+            .extend(aliases[1..].iter().map(|alias| Finding {
+                alias: alias.to_string(),
+                reason: format!("duplicate of '{keep}'"),
+            }));
+    }
+    report.duplicates.sort_by(|a, b| a.alias.cmp(&b.alias));
+
+    if let Some(prober) = prober {
+        kept.sort();
+        let mut probed: BTreeMap<&str, ProbeOutcome> = BTreeMap::new();
+        for alias in kept {
+            let url = storage.configurations[alias].url.as_str();
+            let outcome = probed.entry(url).or_insert_with(|| prober.probe(url));
+            if let Err(reason) = outcome {
+                report.unreachable.push(Finding {
+                    alias: alias.to_string(),
+                    reason: format!("{url}: {reason}"),
+                });
+            }
+        }
+    }
+    report
+}
+
+/// Handle `cc-switch prune`: print the findings and, with `apply`, remove
+/// the ones confirmed (all of them with `yes`), saving once
+///
+/// # Errors
+/// Returns error if `apply` is asked for without a terminal or `yes`, or
+/// saving fails
+pub fn handle_prune(
+    storage: &mut ConfigStorage,
+    prober: Option<&mut dyn Prober>,
+    apply: bool,
+    yes: bool,
+    prompter: &mut dyn Prompter,
+) -> Result<()> {
+    let report = analyze(storage, prober);
+    if report.is_empty() {
+        println!("Nothing to prune");
+        return Ok(());
+    }
+    for line in report.lines() {
+        println!("{line}");
+    }
+    if !apply {
+        println!("Run with --apply to remove them");
+        return Ok(());
+    }
+    if !yes && !prompter.is_interactive() {
+        anyhow::bail!("--apply needs a terminal to confirm each removal; pass --yes to skip it");
+    }
+
+    let mut removing = Vec::new();
+    for finding in report.findings() {
+        if yes
+            || prompter.confirm(&format!(
+                "Remove '{}' ({})? [y/N]: ",
+                finding.alias, finding.reason
+            ))?
+        {
+            removing.push(finding.alias.as_str());
+        }
+    }
+    if removing.is_empty() {
+        println!("No configurations removed");
+        return Ok(());
+    }
+    storage.with_lock(|storage| {
+        for alias in &removing {
+            storage.remove_configuration(alias);
+        }
+        Ok(())
+    })?;
+    for alias in &removing {
+        println!("{} {alias}", "Removed".red());
+    }
+    println!("Removed {} configuration(s)", removing.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::types::Configuration;
+
+    fn config(alias: &str, token: &str, url: &str) -> Configuration {
+        Configuration {
+            alias_name: alias.to_string(),
+            token: token.to_string(),
+            url: url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn storage_with(configs: impl IntoIterator<Item = Configuration>) -> ConfigStorage {
+        let mut storage = ConfigStorage::default();
+        for config in configs {
+            storage.add_configuration(config);
+        }
+        storage
+    }
+
+    fn aliases(findings: &[Finding]) -> Vec<&str> {
+        findings.iter().map(|f| f.alias.as_str()).collect()
+    }
+
+    /// Prober failing the URLs containing "down", counting its probes
+    struct FakeProber(usize);
+
+    impl Prober for FakeProber {
+        fn probe(&mut self, url: &str) -> std::result::Result<(), String> {
+            self.0 += 1;
+            if url.contains("down") {
+                Err("connection refused".to_string())
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    #[test]
+    fn duplicates_keep_the_most_recently_used_alias() {
+        let mut storage = storage_with([
+            config("a-old", "sk-1", "https://relay.example"),
+            config("b-new", "sk-1", "https://relay.example"),
+            config("c-copy", "sk-1", "https://relay.example"),
+            config("other", "sk-2", "https://relay.example"),
+        ]);
+        let mut with_model = config("modelled", "sk-1", "https://relay.example");
+        with_model.model = Some("claude-opus".to_string());
+        storage.add_configuration(with_model);
+        storage.record_use("a-old", "2026-01-01T00:00:00Z".parse().unwrap());
+        storage.record_use("b-new", "2026-02-01T00:00:00Z".parse().unwrap());
+
+        let report = analyze(&storage, None);
+        assert_eq!(aliases(&report.duplicates), ["a-old", "c-copy"]);
+        assert_eq!(report.duplicates[0].reason, "duplicate of 'b-new'");
+        assert!(report.incomplete.is_empty() && report.unreachable.is_empty());
+
+        // Without any use the first alias by name is kept
+        storage.last_used_at.clear();
+        let report = analyze(&storage, None);
+        assert_eq!(aliases(&report.duplicates), ["b-new", "c-copy"]);
+        assert_eq!(report.duplicates[0].reason, "duplicate of 'a-old'");
+    }
+
+    #[test]
+    fn credentials_count_as_token_or_api_key() {
+        let mut keyed = config("keyed", "", "https://relay.example");
+        keyed.api_key = Some("sk-key".to_string());
+        let storage = storage_with([
+            keyed,
+            config("blank", " ", "https://relay.example"),
+            config("nowhere", "sk-1", ""),
+            config("empty", "", ""),
+        ]);
+
+        let report = analyze(&storage, None);
+        assert_eq!(aliases(&report.incomplete), ["blank", "empty", "nowhere"]);
+        assert_eq!(report.incomplete[1].reason, "no token and no URL");
+        assert!(report.duplicates.is_empty());
+    }
+
+    #[test]
+    fn unreachable_urls_are_probed_once_and_only_with_a_prober() {
+        let storage = storage_with([
+            config("up", "sk-1", "https://up.example"),
+            config("down-1", "sk-1", "https://down.example"),
+            config("down-2", "sk-2", "https://down.example"),
+            config("down-copy", "sk-1", "https://down.example"),
+            config("incomplete", "", "https://down.example"),
+        ]);
+
+        let mut prober = FakeProber(0);
+        let report = analyze(&storage, Some(&mut prober));
+        assert_eq!(prober.0, 2);
+        assert_eq!(aliases(&report.duplicates), ["down-copy"]);
+        assert_eq!(aliases(&report.unreachable), ["down-1", "down-2"]);
+        assert_eq!(
+            report.unreachable[0].reason,
+            "https://down.example: connection refused"
+        );
+        assert_eq!(
+            report.lines(),
+            [
+                "Duplicates (1):",
+                "  down-copy: duplicate of 'down-1'",
+                "Missing token or URL (1):",
+                "  incomplete: no token",
+                "Unreachable (2):",
+                "  down-1: https://down.example: connection refused",
+                "  down-2: https://down.example: connection refused",
+            ]
+        );
+
+        assert!(analyze(&storage, None).unreachable.is_empty());
+        assert!(analyze(&ConfigStorage::default(), None).is_empty());
+    }
+}
@@ -0,0 +1,300 @@
+//! `cc-switch remove`: delete configurations, asking first on a terminal.
+//!
+//! [`choose_removals`] and [`confirm_remove_all`] only ask, through a
+//! [`Prompter`], so the decisions can be tested without a terminal;
+//! [`handle_remove`] applies them in one save and prints the outcome.
+
+use anyhow::Result;
+
+use crate::cli::display_utils::credential_for_display;
+use crate::cli::output::{OutputFormat, RemoveSummary};
+use crate::config::resolve_stored_alias;
+use crate::config::types::{ConfigStorage, Configuration};
+use crate::exit_code::{ExitCode, invalid, not_found};
+use crate::interactive::Prompter;
+
+/// Question asked before removing `config`, showing its URL and masked token
+pub fn removal_prompt(config: &Configuration) -> String {
+    format!(
+        "Remove configuration '{}' (url {}, token {})? [y/N]: ",
+        config.alias_name,
+        config.url,
+        credential_for_display(config)
+    )
+}
+
+/// Decide which of `alias_names` to remove
+///
+/// Each stored alias is asked about once when a user is present to answer;
+/// with `yes`, or without a terminal, all of them are removed as before.
+/// Nothing is removed yet: the summary lists what the user chose.
+///
+/// # Errors
+/// Returns error if an alias is reserved or selects a URL variant (before
+/// anything is asked), or reading an answer fails
+pub fn choose_removals(
+    storage: &ConfigStorage,
+    alias_names: &[String],
+    yes: bool,
+    prompter: &mut dyn Prompter,
+) -> Result<RemoveSummary> {
+    let mut stored = Vec::with_capacity(alias_names.len());
+    for alias_name in alias_names {
+        match resolve_stored_alias(storage, alias_name) {
+            Ok(config) => stored.push((alias_name, Some(config))),
+            Err(e) if ExitCode::of(&e) == ExitCode::NotFound => stored.push((alias_name, None)),
+            Err(e) => return Err(e),
+        }
+    }
+
+    let ask = !yes && prompter.is_interactive();
+    let mut summary = RemoveSummary::default();
+    for (alias_name, config) in stored {
+        let decided = summary.removed.iter().chain(&summary.skipped);
+        if decided.chain(&summary.not_found).any(|a| a == alias_name) {
+            continue;
+        }
+        match config {
+            None => summary.not_found.push(alias_name.clone()),
+            Some(config) if ask && !prompter.confirm(&removal_prompt(config))? => {
+                summary.skipped.push(alias_name.clone())
+            }
+            Some(_) => summary.removed.push(alias_name.clone()),
+        }
+    }
+    Ok(summary)
+}
+
+/// Confirm removing all `count` configurations: `yes`, or the word "all"
+/// typed at the prompt
+///
+/// # Errors
+/// Returns error without `yes` when no terminal is available to type it
+pub fn confirm_remove_all(count: usize, yes: bool, prompter: &mut dyn Prompter) -> Result<bool> {
+    if yes {
+        return Ok(true);
+    }
+    if !prompter.is_interactive() {
+        return Err(invalid(
+            "--all needs a terminal to type 'all'; pass --yes to skip the confirmation",
+        ));
+    }
+    let answer = prompter.ask(&format!(
+        "Type 'all' to remove all {count} configuration(s): "
+    ))?;
+    Ok(answer == "all")
+}
+
+/// Handle `cc-switch remove`: ask, remove the chosen configurations in one
+/// save and report each alias
+///
+/// # Errors
+/// Returns error if `--all` can't be confirmed, saving fails, or aliases
+/// are missing (any of them with `strict`, all of them otherwise)
+pub fn handle_remove(
+    storage: &mut ConfigStorage,
+    alias_names: &[String],
+    all: bool,
+    yes: bool,
+    strict: bool,
+    output: OutputFormat,
+    prompter: &mut dyn Prompter,
+) -> Result<()> {
+    let mut summary = if all {
+        let count = storage.configurations.len();
+        if count == 0 {
+            output.note("No configurations to remove");
+            RemoveSummary::default()
+        } else if confirm_remove_all(count, yes, prompter)? {
+            // The confirmation covers the whole store, so remove whatever it
+            // holds once reloaded under the lock
+            let mut summary = RemoveSummary::default();
+            storage.with_lock(|storage| {
+                let aliases: Vec<String> = storage.configurations.keys().cloned().collect();
+                for alias_name in aliases {
+                    storage.remove_configuration(&alias_name);
+                    summary.removed.push(alias_name);
+                }
+                Ok(())
+            })?;
+            summary
+        } else {
+            output.note("Aborted; nothing removed");
+            return Ok(());
+        }
+    } else {
+        choose_removals(storage, alias_names, yes, prompter)?
+    };
+
+    // Only take the lock and save when something is there to remove
+    if !all && !summary.removed.is_empty() {
+        let chosen = std::mem::take(&mut summary.removed);
+        storage.with_lock(|storage| {
+            for alias_name in chosen {
+                // Another process may have removed it since it was asked about
+                if storage.remove_configuration(&alias_name) {
+                    summary.removed.push(alias_name);
+                } else {
+                    summary.not_found.push(alias_name);
+                }
+            }
+            Ok(())
+        })?;
+    }
+
+    let reported = if all {
+        summary.removed.clone()
+    } else {
+        alias_names.to_vec()
+    };
+    for alias_name in &reported {
+        if summary.removed.contains(alias_name) {
+            output.note(format!("Configuration '{alias_name}' removed successfully"));
+        } else if summary.skipped.contains(alias_name) {
+            output.note(format!("Configuration '{alias_name}' kept"));
+        } else {
+            output.note(format!("Configuration '{alias_name}' not found"));
+        }
+    }
+
+    let missed = !summary.not_found.is_empty() && (strict || summary.removed.is_empty());
+    if !summary.not_found.is_empty() && !missed {
+        eprintln!(
+            "Warning: The following configurations were not found: {}",
+            summary.not_found.join(", ")
+        );
+    }
+    if !summary.removed.is_empty() || !summary.skipped.is_empty() {
+        output.note(summary.line());
+    }
+    if !summary.removed.is_empty() {
+        crate::cli::completion::print_stale_completion_hint();
+    }
+    if output.is_json() {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+    }
+    if missed {
+        return Err(not_found(format!(
+            "Configuration(s) not found: {}",
+            summary.not_found.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Prompter answering from a script, recording the questions
+    struct ScriptedPrompter {
+        interactive: bool,
+        answers: Vec<&'static str>,
+        asked: Vec<String>,
+    }
+
+    impl Prompter for ScriptedPrompter {
+        fn is_interactive(&self) -> bool {
+            self.interactive
+        }
+
+        fn confirm(&mut self, prompt: &str) -> Result<bool> {
+            let answer = self.ask(prompt)?;
+            Ok(matches!(answer.as_str(), "y" | "yes"))
+        }
+
+        fn ask(&mut self, prompt: &str) -> Result<String> {
+            self.asked.push(prompt.to_string());
+            Ok(self.answers.remove(0).to_string())
+        }
+    }
+
+    fn prompter(interactive: bool, answers: Vec<&'static str>) -> ScriptedPrompter {
+        ScriptedPrompter {
+            interactive,
+            answers,
+            asked: Vec::new(),
+        }
+    }
+
+    fn storage() -> ConfigStorage {
+        let mut storage = ConfigStorage::default();
+        for alias in ["foo", "bar"] {
+            storage.add_configuration(Configuration {
+                alias_name: alias.to_string(),
+                token: "sk-ant-REDACTED".to_string(),
+                url: format!("https://{alias}.example"),
+                ..Default::default()
+            });
+        }
+        storage
+    }
+
+    fn names(aliases: &[&str]) -> Vec<String> {
+        aliases.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn each_stored_alias_is_asked_about_with_its_url_and_masked_token() {
+        let mut answers = prompter(true, vec!["y", "n"]);
+        let summary = choose_removals(
+            &storage(),
+            &names(&["foo", "gone", "bar", "foo"]),
+            false,
+            &mut answers,
+        )
+        .unwrap();
+        assert_eq!(summary.removed, ["foo"]);
+        assert_eq!(summary.skipped, ["bar"]);
+        assert_eq!(summary.not_found, ["gone"]);
+        assert_eq!(
+            answers.asked,
+            [
+                "Remove configuration 'foo' (url https://foo.example, token sk-ant-api03...stuvwxyz)? [y/N]: ",
+                "Remove configuration 'bar' (url https://bar.example, token sk-ant-api03...stuvwxyz)? [y/N]: ",
+            ]
+        );
+        assert_eq!(
+            summary.line(),
+            "Removed 1 configuration(s), skipped 1, not found 1"
+        );
+    }
+
+    #[test]
+    fn yes_or_no_terminal_removes_without_asking() {
+        for (interactive, yes) in [(true, true), (false, false)] {
+            let mut answers = prompter(interactive, Vec::new());
+            let summary =
+                choose_removals(&storage(), &names(&["foo", "bar"]), yes, &mut answers).unwrap();
+            assert_eq!(summary.removed, ["foo", "bar"]);
+            assert!(answers.asked.is_empty());
+        }
+    }
+
+    #[test]
+    fn reserved_names_and_url_variants_fail_before_anything_is_asked() {
+        for name in ["cc", "foo@eu"] {
+            let mut answers = prompter(true, vec!["y"]);
+            let err = choose_removals(&storage(), &names(&["foo", name]), false, &mut answers)
+                .err()
+                .unwrap();
+            assert_eq!(ExitCode::of(&err), ExitCode::Invalid, "{name}");
+            assert!(answers.asked.is_empty());
+        }
+    }
+
+    #[test]
+    fn remove_all_needs_the_word_all_or_yes() {
+        assert!(confirm_remove_all(2, false, &mut prompter(true, vec!["all"])).unwrap());
+        assert!(!confirm_remove_all(2, false, &mut prompter(true, vec!["y"])).unwrap());
+        assert!(confirm_remove_all(2, true, &mut prompter(false, Vec::new())).unwrap());
+        assert!(confirm_remove_all(2, false, &mut prompter(false, Vec::new())).is_err());
+
+        let mut answers = prompter(true, vec!["ALL"]);
+        assert!(!confirm_remove_all(2, false, &mut answers).unwrap());
+        assert_eq!(
+            answers.asked,
+            ["Type 'all' to remove all 2 configuration(s): "]
+        );
+    }
+}
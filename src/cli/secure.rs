@@ -0,0 +1,175 @@
+//! `cc-switch secure`: keep tokens in the OS keyring instead of the
+//! storage file.
+//!
+//! Both directions rewrite the storage file under its lock and then replace
+//! its backup, so no plaintext token (or reference to a deleted keyring
+//! entry) is left behind in the `.bak` of the storage file.
+
+use anyhow::Result;
+use std::path::Path;
+
+use crate::config::get_config_storage_path;
+use crate::config::secure::{self, KEYRING_SERVICE, SecretStore, SystemStore};
+use crate::config::types::ConfigStorage;
+
+/// Turn secure mode on for the storage file at `path`, moving every stored
+/// token into `store`; returns the aliases moved
+///
+/// # Errors
+/// Returns error if a token can't be stored or the file can't be saved
+pub fn enable_at(path: &Path, store: &dyn SecretStore) -> Result<Vec<String>> {
+    let mut storage = ConfigStorage::default();
+    let moved = storage.with_lock_in(path, store, |storage| {
+        storage.preferences.secure_tokens = true;
+        Ok(storage
+            .configurations
+            .iter()
+            .filter(|(_, config)| {
+                config.token_command.is_none()
+                    && config.keyring_key().is_none()
+                    && !config.auth_env_pair().1.is_empty()
+            })
+            .map(|(alias, _)| alias.clone())
+            .collect())
+    })?;
+    ConfigStorage::replace_backup_at(path)?;
+    Ok(moved)
+}
+
+/// Turn secure mode off for the storage file at `path`, writing every
+/// token kept in `store` back into the file; returns the aliases moved
+///
+/// Nothing changes when an entry is missing; the entries are deleted once
+/// the file is saved.
+///
+/// # Errors
+/// Returns error if an entry can't be read or the file can't be saved
+pub fn disable_at(path: &Path, store: &dyn SecretStore) -> Result<Vec<String>> {
+    let mut storage = ConfigStorage::default();
+    let moved = storage.with_lock_in(path, store, |storage| {
+        let moved = secure::unseal(storage, store)?;
+        storage.preferences.secure_tokens = false;
+        Ok(moved)
+    })?;
+    ConfigStorage::replace_backup_at(path)?;
+    Ok(moved)
+}
+
+/// Handle `cc-switch secure enable`
+///
+/// # Errors
+/// Returns error if the keyring is unavailable or saving fails
+pub fn handle_enable() -> Result<()> {
+    let path = get_config_storage_path()?;
+    let moved = enable_at(&path, &SystemStore)?;
+    println!("Secure mode on: tokens are kept in the keyring (service '{KEYRING_SERVICE}')");
+    if !moved.is_empty() {
+        println!(
+            "Moved {} token(s) into the keyring: {}",
+            moved.len(),
+            moved.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Handle `cc-switch secure disable`
+///
+/// # Errors
+/// Returns error if a keyring entry can't be read or saving fails
+pub fn handle_disable() -> Result<()> {
+    let path = get_config_storage_path()?;
+    let moved = disable_at(&path, &SystemStore)?;
+    println!("Secure mode off: tokens are stored in {}", path.display());
+    if !moved.is_empty() {
+        println!(
+            "Moved {} token(s) out of the keyring: {}",
+            moved.len(),
+            moved.join(", ")
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::secure::MemoryStore;
+    use crate::config::types::Configuration;
+
+    fn save_storage(path: &Path) {
+        let mut storage = ConfigStorage::default();
+        for (alias, token) in [("work", "sk-ant-work"), ("relay", "sk-relay")] {
+            storage.add_configuration(Configuration {
+                alias_name: alias.to_string(),
+                token: token.to_string(),
+                url: "https://api.example.com".to_string(),
+                ..Default::default()
+            });
+        }
+        storage.save_to_with(path, &MemoryStore::default()).unwrap();
+    }
+
+    #[test]
+    fn enable_then_disable_round_trips_every_token() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("cc_auto_switch_setting.json");
+        save_storage(&path);
+        let store = MemoryStore::default();
+
+        assert_eq!(enable_at(&path, &store).unwrap(), ["relay", "work"]);
+        let file = std::fs::read_to_string(&path).unwrap();
+        assert!(
+            !file.contains("sk-") && file.contains("keyring:work"),
+            "{file}"
+        );
+        let backup =
+            std::fs::read_to_string(tmp.path().join("cc_auto_switch_setting.json.bak")).unwrap();
+        assert_eq!(backup, file);
+        assert_eq!(store.get("work").unwrap(), "sk-ant-work");
+
+        // A token added while secure mode is on goes to the keyring as well
+        let mut storage = ConfigStorage::load_from(&path).unwrap();
+        assert!(storage.preferences.secure_tokens);
+        storage
+            .with_lock_in(&path, &store, |storage| {
+                storage.add_configuration(Configuration {
+                    alias_name: "new".to_string(),
+                    token: "sk-new".to_string(),
+                    url: "https://api.example.com".to_string(),
+                    ..Default::default()
+                });
+                storage.remove_configuration("relay");
+                Ok(())
+            })
+            .unwrap();
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("sk-new"));
+        assert_eq!(
+            store.secrets.borrow().keys().collect::<Vec<_>>(),
+            ["new", "work"]
+        );
+
+        assert_eq!(disable_at(&path, &store).unwrap(), ["new", "work"]);
+        let storage = ConfigStorage::load_from(&path).unwrap();
+        assert!(!storage.preferences.secure_tokens);
+        assert_eq!(storage.configurations["work"].token, "sk-ant-work");
+        assert_eq!(storage.configurations["new"].token, "sk-new");
+        assert!(store.secrets.borrow().is_empty());
+    }
+
+    #[test]
+    fn disable_changes_nothing_when_an_entry_is_missing() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("cc_auto_switch_setting.json");
+        save_storage(&path);
+        let store = MemoryStore::default();
+        enable_at(&path, &store).unwrap();
+        let before = std::fs::read_to_string(&path).unwrap();
+
+        store.delete("relay").unwrap();
+        let error = format!("{:#}", disable_at(&path, &store).err().unwrap());
+        assert!(error.contains("'relay'"), "{error}");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), before);
+        assert_eq!(store.get("work").unwrap(), "sk-ant-work");
+    }
+}
@@ -0,0 +1,202 @@
+//! `cc-switch settings`: inspect and clean Claude's settings.json.
+//!
+//! Every subcommand works on the settings directory given with `--dir`,
+//! else the one set with `config set settings-dir`, else `~/.claude`.
+
+use anyhow::Result;
+use std::path::PathBuf;
+
+use crate::cli::display_utils::format_token_for_display;
+use crate::config::types::{ANTHROPIC_ENV_VARS, ClaudeSettings, ConfigStorage};
+use crate::utils::get_claude_settings_path;
+
+/// Settings directory to use: `dir` if given, else the stored one
+pub fn settings_dir<'a>(dir: Option<&'a str>, storage: &'a ConfigStorage) -> Option<&'a str> {
+    dir.or_else(|| storage.get_claude_settings_dir().map(|s| s.as_str()))
+}
+
+/// Path of settings.json in `custom_dir`
+///
+/// # Errors
+/// Returns error if the home directory can't be determined
+pub fn settings_path(custom_dir: Option<&str>) -> Result<PathBuf> {
+    get_claude_settings_path(custom_dir)
+}
+
+/// The env block of `settings` with secrets masked, then how many other
+/// keys the file holds
+pub fn show_lines(settings: &ClaudeSettings) -> Vec<String> {
+    let mut lines = Vec::new();
+    if settings.env.is_empty() {
+        lines.push("env: (empty)".to_string());
+    } else {
+        lines.push("env:".to_string());
+        for (key, value) in &settings.env {
+            let value = if key.contains("TOKEN") || key.contains("KEY") {
+                format_token_for_display(value)
+            } else {
+                value.clone()
+            };
+            lines.push(format!("  {key}={value}"));
+        }
+    }
+    let other: Vec<&str> = settings.other.keys().map(String::as_str).collect();
+    if other.is_empty() {
+        lines.push("0 other key(s) preserved".to_string());
+    } else {
+        lines.push(format!(
+            "{} other key(s) preserved: {}",
+            other.len(),
+            other.join(", ")
+        ));
+    }
+    lines
+}
+
+/// Remove the Anthropic variables from settings.json in `custom_dir`,
+/// returning their names
+///
+/// The file is backed up to `settings.json.bak` and saved only when there
+/// is something to remove; every other key is left as it was.
+///
+/// # Errors
+/// Returns error if the file can't be read, backed up or saved
+pub fn clean(custom_dir: Option<&str>) -> Result<Vec<&'static str>> {
+    let mut settings = ClaudeSettings::load(custom_dir)?;
+    let removed: Vec<&'static str> = ANTHROPIC_ENV_VARS
+        .iter()
+        .copied()
+        .filter(|name| settings.env.contains_key(*name))
+        .collect();
+    if !removed.is_empty() {
+        ClaudeSettings::backup(custom_dir)?;
+        settings.remove_anthropic_env();
+        settings.save(custom_dir)?;
+    }
+    Ok(removed)
+}
+
+/// Handle `cc-switch settings show`
+///
+/// # Errors
+/// Returns error if settings.json exists but can't be parsed
+pub fn handle_show(custom_dir: Option<&str>) -> Result<()> {
+    let path = settings_path(custom_dir)?;
+    println!("{}", path.display());
+    // Loading would create a missing file; showing it shouldn't
+    if !path.exists() {
+        println!("(no settings.json yet)");
+        return Ok(());
+    }
+    for line in show_lines(&ClaudeSettings::load(custom_dir)?) {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+/// Handle `cc-switch settings clean`
+///
+/// # Errors
+/// Returns error if settings.json can't be read, backed up or saved
+pub fn handle_clean(custom_dir: Option<&str>) -> Result<()> {
+    let path = settings_path(custom_dir)?;
+    if !path.exists() {
+        println!("{}: no settings.json, nothing to remove", path.display());
+        return Ok(());
+    }
+    let removed = clean(custom_dir)?;
+    if removed.is_empty() {
+        println!("{}: no Anthropic variables to remove", path.display());
+    } else {
+        println!("{}: removed {}", path.display(), removed.join(", "));
+        println!(
+            "  (backup saved to {})",
+            path.with_extension("json.bak").display()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn settings() -> ClaudeSettings {
+        let mut settings = ClaudeSettings::default();
+        for (key, value) in [
+            (
+                "ANTHROPIC_AUTH_TOKEN",
+                "sk-ant-REDACTED",
+            ),
+            ("ANTHROPIC_BASE_URL", "https://relay.example"),
+            ("ANTHROPIC_MODEL", "claude-sonnet-4-5"),
+            ("EDITOR", "vim"),
+        ] {
+            settings.env.insert(key.to_string(), value.to_string());
+        }
+        settings.other.insert(
+            "permissions".to_string(),
+            serde_json::json!({"allow": ["Bash(ls:*)"], "deny": []}),
+        );
+        settings
+            .other
+            .insert("alwaysThinkingEnabled".to_string(), serde_json::json!(true));
+        settings
+    }
+
+    #[test]
+    fn show_masks_secrets_and_counts_other_keys() {
+        assert_eq!(
+            show_lines(&settings()),
+            [
+                "env:",
+                "  ANTHROPIC_AUTH_TOKEN=sk-ant-api03...stuvwxyz",
+                "  ANTHROPIC_BASE_URL=https://relay.example",
+                "  ANTHROPIC_MODEL=claude-sonnet-4-5",
+                "  EDITOR=vim",
+                "2 other key(s) preserved: alwaysThinkingEnabled, permissions",
+            ]
+        );
+        assert_eq!(
+            show_lines(&ClaudeSettings::default()),
+            ["env: (empty)", "0 other key(s) preserved"]
+        );
+    }
+
+    #[test]
+    fn clean_removes_only_the_anthropic_variables() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path().to_str().unwrap();
+        settings().save(Some(dir)).unwrap();
+        let path = settings_path(Some(dir)).unwrap();
+        let original = std::fs::read_to_string(&path).unwrap();
+
+        assert_eq!(
+            clean(Some(dir)).unwrap(),
+            [
+                "ANTHROPIC_AUTH_TOKEN",
+                "ANTHROPIC_BASE_URL",
+                "ANTHROPIC_MODEL"
+            ]
+        );
+        let cleaned = std::fs::read_to_string(&path).unwrap();
+        let expected = original
+            .lines()
+            .filter(|line| !line.trim_start().starts_with("\"ANTHROPIC_"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(cleaned, expected);
+        assert_eq!(
+            std::fs::read_to_string(path.with_extension("json.bak")).unwrap(),
+            original
+        );
+
+        let reloaded = ClaudeSettings::load(Some(dir)).unwrap();
+        assert_eq!(
+            reloaded.env,
+            BTreeMap::from([("EDITOR".to_string(), "vim".to_string())])
+        );
+        assert!(clean(Some(dir)).unwrap().is_empty());
+    }
+}
@@ -243,12 +243,11 @@ fn launch_codex(continue_flag: bool, resume: Option<String>, prompt: Vec<String>
         cmd.args(prompt);
     }
 
-    let status = cmd
-        .status()
+    let code = crate::platform::spawn_and_wait(&mut cmd)
         .map_err(|e| anyhow!("Failed to launch Codex: {}", e))?;
 
-    if !status.success() {
-        std::process::exit(status.code().unwrap_or(1));
+    if code != 0 {
+        std::process::exit(code);
     }
 
     Ok(())
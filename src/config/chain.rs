@@ -0,0 +1,267 @@
+//! Failover chains for `cc-switch use <alias> <alias>...`.
+//!
+//! A chain is tried in order. With preflight enabled each entry's endpoint is
+//! probed and the first reachable one wins; without preflight the first
+//! existing entry is used and the rest are ignored.
+
+use anyhow::Result;
+
+use crate::config::config::{AliasTarget, resolve_alias_target};
+use crate::config::types::ConfigStorage;
+
+/// Endpoint probed for the official Claude configuration
+pub const OFFICIAL_PROBE_URL: &str = "https://api.anthropic.com";
+
+/// Checks whether an endpoint answers before launching against it
+pub trait Prober {
+    /// Return `Err(reason)` when `url` cannot be reached
+    fn probe(&mut self, url: &str) -> std::result::Result<(), String>;
+}
+
+/// Prober issuing a real HTTP request; any HTTP response counts as reachable
+pub struct HttpProber {
+    timeout: std::time::Duration,
+}
+
+impl HttpProber {
+    pub fn new(timeout: std::time::Duration) -> Self {
+        HttpProber { timeout }
+    }
+}
+
+impl Prober for HttpProber {
+    fn probe(&mut self, url: &str) -> std::result::Result<(), String> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(self.timeout)
+            .build()
+            .map_err(|e| e.to_string())?;
+        client
+            .get(url)
+            .send()
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// One alias of a chain with what it resolved to (`None` if not found)
+pub struct ChainEntry<'a> {
+    pub alias: String,
+    pub target: Option<AliasTarget<'a>>,
+}
+
+impl<'a> ChainEntry<'a> {
+    /// Resolve every alias of a chain against storage
+    pub fn resolve_all(storage: &'a ConfigStorage, aliases: &[String]) -> Vec<ChainEntry<'a>> {
+        aliases
+            .iter()
+            .map(|alias| ChainEntry {
+                alias: alias.clone(),
+                target: resolve_alias_target(storage, alias).ok(),
+            })
+            .collect()
+    }
+
+    fn probe_url(&self) -> Option<&str> {
+        match self.target.as_ref()? {
+            AliasTarget::Official => Some(OFFICIAL_PROBE_URL),
+            AliasTarget::Config(config) => Some(config.url.as_str()),
+        }
+    }
+}
+
+/// Alias of a skipped chain entry and the reason it was skipped
+pub type SkippedEntry = (String, String);
+
+/// Remaining chain aliases and prompt words of a `use` invocation
+pub type ChainAndPrompt = (Vec<String>, Vec<String>);
+
+/// Outcome of [`select_from_chain`]
+#[derive(Debug, PartialEq)]
+pub struct ChainSelection {
+    /// Index of the chosen entry
+    pub index: usize,
+    /// Entries tried before the chosen one, with the reason they were skipped
+    pub skipped: Vec<SkippedEntry>,
+    /// Entries after the chosen one that were never tried
+    pub ignored: Vec<String>,
+}
+
+/// Pick the entry to launch from a failover chain
+///
+/// With a prober, the first entry whose endpoint answers is chosen; without
+/// one, the first entry that exists.
+///
+/// # Errors
+/// Returns error listing each entry's failure if none can be used
+pub fn select_from_chain(
+    configs: &[ChainEntry],
+    mut prober: Option<&mut dyn Prober>,
+) -> Result<ChainSelection> {
+    let mut skipped = Vec::new();
+    for (index, entry) in configs.iter().enumerate() {
+        let Some(url) = entry.probe_url() else {
+            skipped.push((entry.alias.clone(), "not found".to_string()));
+            continue;
+        };
+        if let Some(prober) = prober.as_deref_mut()
+            && let Err(reason) = prober.probe(url)
+        {
+            skipped.push((entry.alias.clone(), format!("unreachable: {reason}")));
+            continue;
+        }
+        return Ok(ChainSelection {
+            index,
+            skipped,
+            ignored: configs[index + 1..]
+                .iter()
+                .map(|entry| entry.alias.clone())
+                .collect(),
+        });
+    }
+
+    let reasons: Vec<String> = skipped
+        .iter()
+        .map(|(alias, reason)| format!("{alias} ({reason})"))
+        .collect();
+    anyhow::bail!(
+        "No usable configuration in the chain: {}",
+        reasons.join(", ")
+    )
+}
+
+/// Split `use` arguments into the rest of an alias chain and the prompt
+///
+/// Leading arguments that name a stored configuration or a reserved alias
+/// extend the chain; everything from the first other word on is the prompt.
+pub fn split_chain_and_prompt(storage: &ConfigStorage, args: &[String]) -> ChainAndPrompt {
+    let chain_len = args
+        .iter()
+        .take_while(|arg| resolve_alias_target(storage, arg).is_ok())
+        .count();
+    (args[..chain_len].to_vec(), args[chain_len..].to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::types::Configuration;
+
+    /// Prober answering from a fixed list of reachable URLs
+    struct MockProber {
+        reachable: Vec<&'static str>,
+        probed: Vec<String>,
+    }
+
+    impl Prober for MockProber {
+        fn probe(&mut self, url: &str) -> std::result::Result<(), String> {
+            self.probed.push(url.to_string());
+            if self.reachable.contains(&url) {
+                Ok(())
+            } else {
+                Err("connection refused".to_string())
+            }
+        }
+    }
+
+    fn storage() -> ConfigStorage {
+        let mut storage = ConfigStorage::default();
+        for (alias, url) in [
+            ("relay-a", "https://a.relay.example"),
+            ("relay-b", "https://b.relay.example"),
+            ("backup", "https://backup.example"),
+        ] {
+            storage.add_configuration(Configuration {
+                alias_name: alias.to_string(),
+                token: "sk-test".to_string(),
+                url: url.to_string(),
+                ..Default::default()
+            });
+        }
+        storage
+    }
+
+    fn chain(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn falls_through_to_first_reachable() {
+        let storage = storage();
+        let entries = ChainEntry::resolve_all(&storage, &chain(&["relay-a", "relay-b", "backup"]));
+        let mut prober = MockProber {
+            reachable: vec!["https://b.relay.example", "https://backup.example"],
+            probed: Vec::new(),
+        };
+
+        let selection = select_from_chain(&entries, Some(&mut prober)).unwrap();
+        assert_eq!(selection.index, 1);
+        assert_eq!(
+            selection.skipped,
+            vec![(
+                "relay-a".to_string(),
+                "unreachable: connection refused".to_string()
+            )]
+        );
+        assert_eq!(selection.ignored, vec!["backup"]);
+        assert_eq!(
+            prober.probed,
+            vec!["https://a.relay.example", "https://b.relay.example"],
+            "entries after the chosen one are not probed"
+        );
+    }
+
+    #[test]
+    fn without_prober_uses_first_existing() {
+        let storage = storage();
+        let entries = ChainEntry::resolve_all(&storage, &chain(&["gone", "relay-b", "backup"]));
+
+        let selection = select_from_chain(&entries, None).unwrap();
+        assert_eq!(selection.index, 1);
+        assert_eq!(
+            selection.skipped,
+            vec![("gone".to_string(), "not found".to_string())]
+        );
+        assert_eq!(selection.ignored, vec!["backup"]);
+    }
+
+    #[test]
+    fn official_entry_probes_official_endpoint() {
+        let storage = storage();
+        let entries = ChainEntry::resolve_all(&storage, &chain(&["relay-a", "official"]));
+        let mut prober = MockProber {
+            reachable: vec![OFFICIAL_PROBE_URL],
+            probed: Vec::new(),
+        };
+
+        let selection = select_from_chain(&entries, Some(&mut prober)).unwrap();
+        assert_eq!(selection.index, 1);
+    }
+
+    #[test]
+    fn errors_when_nothing_is_usable() {
+        let storage = storage();
+        let entries = ChainEntry::resolve_all(&storage, &chain(&["relay-a", "gone"]));
+        let mut prober = MockProber {
+            reachable: Vec::new(),
+            probed: Vec::new(),
+        };
+
+        let err = select_from_chain(&entries, Some(&mut prober))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("relay-a (unreachable: connection refused)"),
+            "got: {err}"
+        );
+        assert!(err.contains("gone (not found)"), "got: {err}");
+    }
+
+    #[test]
+    fn split_stops_at_first_non_alias() {
+        let storage = storage();
+        let args = chain(&["relay-b", "cc", "fix", "backup"]);
+        let (rest, prompt) = split_chain_and_prompt(&storage, &args);
+        assert_eq!(rest, vec!["relay-b", "cc"]);
+        assert_eq!(prompt, vec!["fix", "backup"]);
+    }
+}
@@ -1,8 +1,10 @@
-use anyhow::{Context, Result};
+use anyhow::Result;
+use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::path::PathBuf;
 
-use crate::config::types::Configuration;
+use crate::config::types::{ConfigStorage, Configuration};
+use crate::exit_code::{config_not_found, invalid};
 
 /// Type alias for environment variable map
 type EnvVarMap = BTreeMap<String, String>;
@@ -18,6 +20,8 @@ type EnvVarTuples = Vec<EnvVarTuple>;
 pub struct EnvironmentConfig {
     /// Environment variables to be set
     pub env_vars: EnvVarMap,
+    /// Inherited variables removed from the child before `env_vars` are set
+    pub unset_keys: Vec<String>,
 }
 
 impl EnvironmentConfig {
@@ -134,13 +138,39 @@ impl EnvironmentConfig {
             env_vars.insert("DISABLE_AUTOUPDATER".to_string(), flag.to_string());
         }
 
-        EnvironmentConfig { env_vars }
+        // Additional variables never replace the ones set above
+        for (key, value) in &config.extra_env {
+            env_vars.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+
+        EnvironmentConfig {
+            env_vars,
+            unset_keys: Vec::new(),
+        }
+    }
+
+    /// Like [`EnvironmentConfig::from_config`], but with the token printed by
+    /// the configuration's `token_command`, if it has one
+    ///
+    /// # Errors
+    /// Returns error if the token command fails or prints nothing
+    pub fn from_config_resolved(config: &Configuration) -> Result<Self> {
+        let resolved = config.with_resolved_token()?;
+        Ok(Self::from_config(&resolved))
     }
 
     /// Create an empty environment configuration (for reset)
+    ///
+    /// Every variable cc-switch manages is listed in `unset_keys`, so values
+    /// exported by the shell or an earlier switch don't leak into the
+    /// official session.
     pub fn empty() -> Self {
         EnvironmentConfig {
             env_vars: EnvVarMap::new(),
+            unset_keys: managed_env_var_names()
+                .into_iter()
+                .map(String::from)
+                .collect(),
         }
     }
 
@@ -161,6 +191,36 @@ impl EnvironmentConfig {
         self
     }
 
+    /// Inherited Claude variables this environment neither sets nor unsets
+    ///
+    /// These reach Claude next to the selected configuration and can mix
+    /// with it, e.g. an exported `ANTHROPIC_MODEL` meant for another endpoint.
+    pub fn unmanaged_conflicts(&self) -> EnvVarTuples {
+        self.unmanaged_conflicts_in(
+            std::env::vars_os().filter_map(|(key, value)| {
+                Some((key.into_string().ok()?, value.into_string().ok()?))
+            }),
+        )
+    }
+
+    /// [`Self::unmanaged_conflicts`] against the given inherited variables,
+    /// sorted by name
+    pub fn unmanaged_conflicts_in(
+        &self,
+        inherited: impl IntoIterator<Item = EnvVarTuple>,
+    ) -> EnvVarTuples {
+        let mut conflicts: EnvVarTuples = inherited
+            .into_iter()
+            .filter(|(key, _)| {
+                is_claude_env_var(key)
+                    && !self.env_vars.contains_key(key)
+                    && !self.unset_keys.contains(key)
+            })
+            .collect();
+        conflicts.sort();
+        conflicts
+    }
+
     /// Get environment variables as a Vec of (key, value) tuples
     /// for use with Command::envs()
     pub fn as_env_tuples(&self) -> EnvVarTuples {
@@ -171,15 +231,69 @@ impl EnvironmentConfig {
     }
 }
 
+/// Environment variables cc-switch may set for a Claude session
+pub fn managed_env_var_names() -> Vec<&'static str> {
+    let mut names = Configuration::get_env_field_names();
+    names.push("CC_SWITCH_CURRENT_ALIAS");
+    names
+}
+
+/// Whether `key` configures Claude: `ANTHROPIC_*`, `CLAUDE_CODE_*`,
+/// `API_TIMEOUT_MS` or another variable a configuration can set
+fn is_claude_env_var(key: &str) -> bool {
+    key.starts_with("ANTHROPIC_")
+        || key.starts_with("CLAUDE_CODE_")
+        || Configuration::get_env_field_names().contains(&key)
+}
+
+/// Shell statements that unset every variable in `names`
+///
+/// # Arguments
+/// * `shell` - Shell type (bash, zsh, sh, fish, powershell)
+///
+/// # Errors
+/// Returns error if the shell is not supported
+pub fn unset_statements(shell: &str, names: &[&str]) -> Result<Vec<String>> {
+    let statements = match shell {
+        "bash" | "zsh" | "sh" => vec![format!("unset {}", names.join(" "))],
+        "fish" => names.iter().map(|name| format!("set -e {name}")).collect(),
+        "powershell" | "pwsh" => names
+            .iter()
+            .map(|name| format!("Remove-Item Env:{name} -ErrorAction SilentlyContinue"))
+            .collect(),
+        _ => anyhow::bail!(
+            "Unsupported shell: {}. Supported shells: bash, zsh, sh, fish, powershell",
+            shell
+        ),
+    };
+    Ok(statements)
+}
+
 /// Get the path to the configuration storage file
 ///
-/// Returns `~/.claude/cc_auto_switch_setting.json`
+/// Returns `~/.config/cc-switch/configurations.json` unless `--config`,
+/// `$CC_SWITCH_CONFIG`, `$CC_SWITCH_CONFIG_DIR`, `$XDG_CONFIG_HOME` or an
+/// existing store in `~/.claude` name another place (see
+/// [`crate::utils::resolve_config_storage_path`])
 ///
 /// # Errors
 /// Returns error if home directory cannot be found
 pub fn get_config_storage_path() -> Result<PathBuf> {
-    let home_dir = dirs::home_dir().context("Could not find home directory")?;
-    Ok(home_dir.join(".claude").join("cc_auto_switch_setting.json"))
+    crate::utils::get_config_storage_path()
+}
+
+/// Get the path of the backup of the configuration storage file
+///
+/// Returns the storage file with a `.bak` suffix (e.g.
+/// `~/.config/cc-switch/configurations.json.bak`), the previous version kept
+/// by every save
+///
+/// # Errors
+/// Returns error if home directory cannot be found
+pub fn get_config_backup_path() -> Result<PathBuf> {
+    let mut path = get_config_storage_path()?.into_os_string();
+    path.push(".bak");
+    Ok(PathBuf::from(path))
 }
 
 /// Validate alias name
@@ -191,21 +305,411 @@ pub fn get_config_storage_path() -> Result<PathBuf> {
 /// Ok(()) if valid, Err with message if invalid
 pub fn validate_alias_name(alias_name: &str) -> Result<()> {
     if alias_name.is_empty() {
-        anyhow::bail!("Alias name cannot be empty");
+        return Err(invalid("Alias name cannot be empty"));
     }
-    if alias_name == "cc" {
-        anyhow::bail!("Alias name 'cc' is reserved and cannot be used");
+    if is_official_alias(alias_name) {
+        return Err(invalid(format!(
+            "Alias name '{}' is reserved and cannot be used",
+            alias_name
+        )));
     }
     if alias_name.chars().any(|c| c.is_whitespace()) {
-        anyhow::bail!("Alias name cannot contain whitespace");
+        return Err(invalid("Alias name cannot contain whitespace"));
+    }
+    if alias_name.contains('@') {
+        return Err(invalid(
+            "Alias name cannot contain '@' (reserved for URL variants)",
+        ));
     }
     Ok(())
 }
 
+/// Validate and normalize an API base URL
+///
+/// Surrounding whitespace and trailing slashes are removed, so
+/// `https://api.example.com/` and `https://api.example.com` are stored alike.
+///
+/// # Errors
+/// Returns error unless the URL is http(s) with a host and has no embedded
+/// whitespace
+pub fn validate_base_url(url: &str) -> Result<String> {
+    let trimmed = url.trim();
+    if trimmed.is_empty() {
+        return Err(invalid("URL cannot be empty"));
+    }
+    if trimmed.chars().any(char::is_whitespace) {
+        return Err(invalid(format!(
+            "Invalid URL '{}': contains whitespace",
+            trimmed
+        )));
+    }
+    let parsed =
+        url::Url::parse(trimmed).map_err(|e| invalid(format!("Invalid URL '{trimmed}': {e}")))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(invalid(format!(
+            "Invalid URL '{}': scheme must be http or https, not '{}'",
+            trimmed,
+            parsed.scheme()
+        )));
+    }
+    if parsed.host_str().is_none_or(str::is_empty) {
+        return Err(invalid(format!("Invalid URL '{}': missing host", trimmed)));
+    }
+    Ok(trimmed.trim_end_matches('/').to_string())
+}
+
+/// Pseudo-aliases that select the official Claude configuration
+pub const OFFICIAL_ALIASES: [&str; 2] = ["cc", "official"];
+
+/// Check whether a name is one of the official pseudo-aliases
+pub fn is_official_alias(name: &str) -> bool {
+    OFFICIAL_ALIASES.contains(&name)
+}
+
+/// What an alias argument given on the command line refers to
+pub enum AliasTarget<'a> {
+    /// The official Claude configuration (`cc` / `official`)
+    Official,
+    /// A stored configuration, owned when a URL variant was substituted
+    Config(Box<Cow<'a, Configuration>>),
+}
+
+/// Resolve an alias argument to the official configuration or a stored one
+///
+/// Every alias-taking command goes through this so the reserved names and
+/// the `<alias>@<variant>` URL suffix are handled in one place.
+///
+/// # Errors
+/// Returns error if the alias is neither reserved nor stored, or names an
+/// unknown URL variant
+pub fn resolve_alias_target<'a>(storage: &'a ConfigStorage, name: &str) -> Result<AliasTarget<'a>> {
+    if is_official_alias(name) {
+        return Ok(AliasTarget::Official);
+    }
+    // An exact match wins so aliases stored before '@' was reserved still work
+    if let Some(config) = storage.get_configuration(name) {
+        return Ok(AliasTarget::Config(Box::new(Cow::Borrowed(config))));
+    }
+    let Some((alias, variant)) = name.rsplit_once('@') else {
+        return Err(config_not_found(name));
+    };
+    let config = storage
+        .get_configuration(alias)
+        .ok_or_else(|| config_not_found(alias))?;
+    Ok(AliasTarget::Config(Box::new(Cow::Owned(
+        config.with_url_variant(variant)?,
+    ))))
+}
+
+/// Resolve an alias argument that must name a stored configuration
+///
+/// Used by commands that modify or export a stored entry, for which the
+/// official pseudo-alias and URL variants have nothing to act on.
+///
+/// # Errors
+/// Returns error if the alias is reserved, carries a URL variant or is not stored
+pub fn resolve_stored_alias<'a>(
+    storage: &'a ConfigStorage,
+    name: &str,
+) -> Result<&'a Configuration> {
+    if is_official_alias(name) {
+        return Err(invalid(format!("'{name}' is a reserved name")));
+    }
+    if let Some(config) = storage.get_configuration(name) {
+        return Ok(config);
+    }
+    if let Some((alias, _)) = name.rsplit_once('@')
+        && storage.get_configuration(alias).is_some()
+    {
+        return Err(invalid(format!(
+            "'{}' selects a URL variant; use the stored alias '{}' instead",
+            name, alias
+        )));
+    }
+    Err(config_not_found(name))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn storage_with(alias: &str) -> ConfigStorage {
+        let mut storage = ConfigStorage::default();
+        storage.add_configuration(Configuration {
+            alias_name: alias.to_string(),
+            token: "sk-test".to_string(),
+            url: "https://api.example.com".to_string(),
+            ..Default::default()
+        });
+        storage
+    }
+
+    #[test]
+    fn from_config_resolved_exports_the_command_token() {
+        let mut config = storage_with("relay")
+            .get_configuration("relay")
+            .unwrap()
+            .clone();
+        config.set_field("token_command", "echo sk-cmd").unwrap();
+
+        let env = EnvironmentConfig::from_config_resolved(&config)
+            .unwrap()
+            .env_vars;
+        assert_eq!(
+            env.get("ANTHROPIC_AUTH_TOKEN").map(String::as_str),
+            Some("sk-cmd")
+        );
+
+        config.token_command = Some("exit 1".to_string());
+        assert!(EnvironmentConfig::from_config_resolved(&config).is_err());
+    }
+
+    #[test]
+    fn from_config_exports_extra_env_without_overriding_managed_vars() {
+        let mut config = storage_with("relay")
+            .get_configuration("relay")
+            .unwrap()
+            .clone();
+        config.extra_env.insert(
+            "ANTHROPIC_CUSTOM_HEADERS".to_string(),
+            "X-Org: 1".to_string(),
+        );
+        // Only reachable by editing the storage file by hand
+        config.extra_env.insert(
+            "ANTHROPIC_BASE_URL".to_string(),
+            "https://evil.example".to_string(),
+        );
+
+        let env = EnvironmentConfig::from_config(&config).env_vars;
+        assert_eq!(
+            env.get("ANTHROPIC_CUSTOM_HEADERS").map(String::as_str),
+            Some("X-Org: 1")
+        );
+        assert_eq!(
+            env.get("ANTHROPIC_BASE_URL").map(String::as_str),
+            Some("https://api.example.com")
+        );
+    }
+
+    #[test]
+    fn validate_base_url_accepts_and_normalizes_http_urls() {
+        let ok = |url| validate_base_url(url).unwrap();
+        assert_eq!(ok("https://api.example.com"), "https://api.example.com");
+        assert_eq!(ok("http://localhost:8080/v1"), "http://localhost:8080/v1");
+        // Trailing slashes and surrounding whitespace are dropped
+        assert_eq!(ok("https://api.example.com/"), "https://api.example.com");
+        assert_eq!(
+            ok("  https://relay.example.com/api//\n"),
+            "https://relay.example.com/api"
+        );
+    }
+
+    #[test]
+    fn validate_base_url_rejects_malformed_urls() {
+        let err = |url| validate_base_url(url).unwrap_err().to_string();
+        assert!(err("htps://api.example.com").contains("scheme must be http or https"));
+        assert!(err("ftp://api.example.com").contains("scheme must be http or https"));
+        assert!(err("https://").contains("empty host"));
+        assert!(err("https://:8443/v1").contains("empty host"));
+        assert!(err("api.example.com").contains("Invalid URL"));
+        assert!(err("https://api.example .com").contains("contains whitespace"));
+        assert!(err("   ").contains("cannot be empty"));
+    }
+
+    #[test]
+    fn resolve_alias_target_maps_reserved_names_to_official() {
+        let storage = storage_with("work");
+        for name in OFFICIAL_ALIASES {
+            assert!(matches!(
+                resolve_alias_target(&storage, name),
+                Ok(AliasTarget::Official)
+            ));
+        }
+    }
+
+    #[test]
+    fn resolve_alias_target_finds_stored_config() {
+        let storage = storage_with("work");
+        match resolve_alias_target(&storage, "work") {
+            Ok(AliasTarget::Config(config)) => assert_eq!(config.alias_name, "work"),
+            _ => panic!("expected stored config"),
+        }
+        let err = resolve_alias_target(&storage, "missing")
+            .err()
+            .expect("missing alias should not resolve");
+        assert_eq!(err.to_string(), "Configuration 'missing' not found");
+    }
+
+    #[test]
+    fn resolve_alias_target_substitutes_url_variant() {
+        let mut storage = storage_with("relay");
+        let relay = storage.configurations.get_mut("relay").unwrap();
+        relay
+            .url_variants
+            .insert("eu".to_string(), "https://eu.example.com".to_string());
+        relay
+            .url_variants
+            .insert("us".to_string(), "https://us.example.com".to_string());
+
+        match resolve_alias_target(&storage, "relay@eu") {
+            Ok(AliasTarget::Config(config)) => {
+                assert_eq!(config.alias_name, "relay");
+                assert_eq!(config.url, "https://eu.example.com");
+                assert_eq!(config.token, "sk-test");
+            }
+            _ => panic!("expected variant config"),
+        }
+        match resolve_alias_target(&storage, "relay") {
+            Ok(AliasTarget::Config(config)) => assert_eq!(config.url, "https://api.example.com"),
+            _ => panic!("expected base config"),
+        }
+
+        let err = resolve_alias_target(&storage, "relay@asia")
+            .err()
+            .expect("unknown variant should not resolve")
+            .to_string();
+        assert!(err.contains("Available variants: eu, us"), "got: {err}");
+        let err = resolve_alias_target(&storage, "gone@eu")
+            .err()
+            .expect("unknown alias should not resolve");
+        assert_eq!(err.to_string(), "Configuration 'gone' not found");
+    }
+
+    #[test]
+    fn resolve_alias_target_prefers_exact_alias_with_at_sign() {
+        let storage = storage_with("me@work");
+        match resolve_alias_target(&storage, "me@work") {
+            Ok(AliasTarget::Config(config)) => assert_eq!(config.alias_name, "me@work"),
+            _ => panic!("expected stored config"),
+        }
+        assert!(resolve_stored_alias(&storage, "me@work").is_ok());
+    }
+
+    #[test]
+    fn resolve_stored_alias_rejects_url_variants() {
+        let storage = storage_with("relay");
+        let err = resolve_stored_alias(&storage, "relay@eu")
+            .err()
+            .expect("variant should be rejected");
+        assert!(err.to_string().contains("use the stored alias 'relay'"));
+    }
+
+    #[test]
+    fn validate_alias_name_rejects_at_sign() {
+        assert!(validate_alias_name("relay@eu").is_err());
+    }
+
+    #[test]
+    fn resolve_stored_alias_rejects_reserved_names() {
+        let storage = storage_with("work");
+        let err = resolve_stored_alias(&storage, "cc")
+            .err()
+            .expect("reserved alias should be rejected");
+        assert_eq!(err.to_string(), "'cc' is a reserved name");
+        assert!(resolve_stored_alias(&storage, "work").is_ok());
+    }
+
+    #[test]
+    fn unset_statements_per_shell() {
+        let names = ["ANTHROPIC_BASE_URL", "ANTHROPIC_AUTH_TOKEN"];
+        assert_eq!(
+            unset_statements("zsh", &names).unwrap(),
+            vec!["unset ANTHROPIC_BASE_URL ANTHROPIC_AUTH_TOKEN"]
+        );
+        assert_eq!(
+            unset_statements("fish", &names).unwrap(),
+            vec!["set -e ANTHROPIC_BASE_URL", "set -e ANTHROPIC_AUTH_TOKEN"]
+        );
+        assert_eq!(
+            unset_statements("powershell", &names[..1]).unwrap(),
+            vec!["Remove-Item Env:ANTHROPIC_BASE_URL -ErrorAction SilentlyContinue"]
+        );
+        assert!(unset_statements("tcsh", &names).is_err());
+    }
+
+    #[test]
+    fn managed_env_var_names_include_alias_marker() {
+        let names = managed_env_var_names();
+        assert!(names.contains(&"ANTHROPIC_API_KEY"));
+        assert!(names.contains(&"CC_SWITCH_CURRENT_ALIAS"));
+    }
+
+    #[test]
+    fn validate_alias_name_rejects_official_aliases() {
+        for name in OFFICIAL_ALIASES {
+            assert!(validate_alias_name(name).is_err());
+        }
+    }
+
+    fn inherited(pairs: &[[&str; 2]]) -> EnvVarTuples {
+        pairs
+            .iter()
+            .map(|[key, value]| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn unmanaged_conflicts_skip_variables_the_config_sets() {
+        let env = EnvironmentConfig::from_config(&Configuration {
+            alias_name: "work".to_string(),
+            token: "sk-work".to_string(),
+            url: "https://work.example".to_string(),
+            ..Default::default()
+        });
+        let conflicts = env.unmanaged_conflicts_in(inherited(&[
+            ["ANTHROPIC_BASE_URL", "https://shell.example"],
+            ["ANTHROPIC_MODEL", "shell-model"],
+            ["API_TIMEOUT_MS", "1000"],
+            ["PATH", "/usr/bin"],
+        ]));
+        assert_eq!(
+            conflicts,
+            inherited(&[
+                ["ANTHROPIC_MODEL", "shell-model"],
+                ["API_TIMEOUT_MS", "1000"]
+            ])
+        );
+    }
+
+    #[test]
+    fn unmanaged_conflicts_ignore_unrelated_and_unset_variables() {
+        let disjoint = inherited(&[["PATH", "/usr/bin"], ["EDITOR", "vim"]]);
+        assert!(
+            EnvironmentConfig::default()
+                .unmanaged_conflicts_in(disjoint)
+                .is_empty()
+        );
+
+        let exported = inherited(&[["ANTHROPIC_API_KEY", "sk-shell"]]);
+        assert!(
+            EnvironmentConfig::empty()
+                .unmanaged_conflicts_in(exported.clone())
+                .is_empty()
+        );
+        assert_eq!(
+            EnvironmentConfig::default().unmanaged_conflicts_in(exported.clone()),
+            exported
+        );
+        assert!(
+            EnvironmentConfig::default()
+                .unmanaged_conflicts_in(Vec::new())
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn empty_unsets_every_managed_variable() {
+        let env = EnvironmentConfig::empty();
+        assert!(env.env_vars.is_empty());
+        for name in managed_env_var_names() {
+            assert!(
+                env.unset_keys.iter().any(|key| key == name),
+                "{name} should be unset"
+            );
+        }
+        assert!(EnvironmentConfig::default().unset_keys.is_empty());
+    }
+
     #[test]
     fn with_base_url_sets_anthropic_base_url() {
         let env = EnvironmentConfig::empty()
@@ -222,7 +726,7 @@ mod tests {
             Some("official"),
         );
         assert!(
-            env.env_vars.get("ANTHROPIC_AUTH_TOKEN").is_none(),
+            !env.env_vars.contains_key("ANTHROPIC_AUTH_TOKEN"),
             "with_base_url must NOT set a token (OAuth must flow through unchanged)"
         );
     }
@@ -1,33 +1,138 @@
 use anyhow::{Context, Result};
+use std::borrow::Cow;
 use std::fs;
+use std::path::{Path, PathBuf};
 
-use crate::config::config::get_config_storage_path;
-use crate::config::types::{ConfigStorage, Configuration};
+use crate::config::config::{get_config_backup_path, get_config_storage_path};
+use crate::config::migrations::{self, NewerVersion};
+use crate::config::secure::{self, SecretStore, SystemStore};
+use crate::config::types::{ConfigMap, ConfigStorage, Configuration};
+use crate::utils::{write_atomic, write_verified};
+
+/// Set the mode of `path` to `mode` (e.g. 0o600); a no-op off Unix
+#[cfg(unix)]
+fn restrict_permissions(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        .with_context(|| format!("Failed to set permissions of {}", path.display()))
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &Path, _mode: u32) -> Result<()> {
+    Ok(())
+}
+
+/// Warn, once per run, when the storage file is readable by other users
+#[cfg(unix)]
+fn warn_if_shared(path: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+    static WARNED: std::sync::Once = std::sync::Once::new();
+
+    let Ok(metadata) = fs::metadata(path) else {
+        return;
+    };
+    let mode = metadata.permissions().mode() & 0o777;
+    if mode & 0o077 != 0 {
+        WARNED.call_once(|| {
+            eprintln!(
+                "Warning: {} holds API tokens but is readable by other users (mode {:o}); the next save restricts it to 0600",
+                path.display(),
+                mode
+            );
+        });
+    }
+}
+
+#[cfg(not(unix))]
+fn warn_if_shared(_path: &Path) {}
+
+/// Storage file of releases before the move to `~/.claude`
+///
+/// # Errors
+/// Returns error if home directory cannot be found
+pub fn legacy_storage_path() -> Result<PathBuf> {
+    let home_dir = dirs::home_dir().context("Could not find home directory")?;
+    Ok(home_dir.join(".cc_auto_switch").join("configurations.json"))
+}
+
+/// Backup kept next to the storage file at `path`
+pub fn backup_path_of(path: &Path) -> PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".bak");
+    PathBuf::from(backup)
+}
+
+/// Lock file guarding the storage file at `path` (see [`lock_storage`])
+pub fn lock_path_of(path: &Path) -> PathBuf {
+    let mut lock_path = path.as_os_str().to_owned();
+    lock_path.push(".lock");
+    PathBuf::from(lock_path)
+}
+
+/// Take the exclusive lock guarding the storage file at `path`, waiting
+/// while another run holds it
+///
+/// The lock is taken on a separate `<path>.lock` file, since saving
+/// replaces the storage file itself. It is released when the returned
+/// file is dropped.
+fn lock_storage(path: &Path) -> Result<fs::File> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
+    let lock_path = lock_path_of(path);
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .with_context(|| format!("Failed to open lock file {}", lock_path.display()))?;
+    file.lock()
+        .with_context(|| format!("Failed to lock {}", lock_path.display()))?;
+    Ok(file)
+}
 
 impl ConfigStorage {
     /// Load configurations from disk
     ///
-    /// Reads the JSON file from `~/.claude/cc_auto_switch_setting.json`
-    /// Auto-migrates from old location `~/.cc-switch/configurations.json` if it exists
-    /// Returns default empty storage if file doesn't exist
+    /// Reads the storage file (`~/.config/cc-switch/configurations.json` by
+    /// default, see [`crate::utils::resolve_config_storage_path`])
+    /// When it doesn't exist but the store of an older release does
+    /// (`~/.cc_auto_switch/configurations.json`), that one is copied over
+    /// once, leaving the old file in place
+    /// Returns default empty storage if neither file exists
+    ///
+    /// A file that doesn't parse (e.g. truncated by a crash) is replaced by
+    /// its backup for this run, with a warning.
     ///
     /// # Errors
-    /// Returns error if file exists but cannot be read or parsed
+    /// Returns error if file exists but cannot be read, or neither it nor
+    /// the backup parses
     pub fn load() -> Result<Self> {
         let new_path = get_config_storage_path()?;
 
         // Check if the new file already exists
         if new_path.exists() {
-            let content = fs::read_to_string(&new_path).with_context(|| {
-                format!(
-                    "Failed to read configuration storage from {}",
-                    new_path.display()
-                )
-            })?;
-
-            let storage: ConfigStorage = serde_json::from_str(&content)
-                .with_context(|| "Failed to parse configuration storage JSON")?;
+            return Self::load_from(&new_path);
+        }
 
+        let legacy_path = legacy_storage_path()?;
+        if legacy_path.exists() {
+            let storage = Self::read_legacy(&legacy_path)?;
+            match storage.save() {
+                Ok(()) => eprintln!(
+                    "Migrated {} configurations from {} to {}",
+                    storage.configurations.len(),
+                    legacy_path.display(),
+                    new_path.display()
+                ),
+                Err(e) => eprintln!(
+                    "Warning: could not save the configurations of {} to {}: {e}",
+                    legacy_path.display(),
+                    new_path.display()
+                ),
+            }
             return Ok(storage);
         }
 
@@ -35,79 +140,276 @@ impl ConfigStorage {
         Ok(ConfigStorage::default())
     }
 
+    /// Load the storage file at `path`, falling back to its `.bak` backup
+    /// when it doesn't parse
+    ///
+    /// A missing file is read as an empty storage.
+    ///
+    /// # Errors
+    /// Returns error if the file exists but cannot be read, or neither it
+    /// nor the backup parses
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(ConfigStorage::default());
+        }
+        let content = fs::read_to_string(path).with_context(|| {
+            format!(
+                "Failed to read configuration storage from {}",
+                path.display()
+            )
+        })?;
+
+        warn_if_shared(path);
+        let error = match migrations::parse(&content) {
+            Ok(storage) => return Ok(storage),
+            // Falling back to an older backup would lose the newer data
+            Err(e) if e.is::<NewerVersion>() => {
+                return Err(e.context(format!("Can't load {}", path.display())));
+            }
+            Err(e) => e,
+        };
+        let backup_path = backup_path_of(path);
+        if let Ok(storage) = Self::read_backup(&backup_path) {
+            eprintln!(
+                "Warning: {} is corrupt ({}); using the backup {}. Run `cc-switch restore-backup` to restore it.",
+                path.display(),
+                error,
+                backup_path.display()
+            );
+            return Ok(storage);
+        }
+        Err(error.context("Failed to parse configuration storage JSON"))
+    }
+
+    /// Read a storage file written by an older release
+    ///
+    /// Optional fields those releases didn't know are read as unset, and a
+    /// configuration stored without `alias_name` takes the alias it is
+    /// stored under.
+    ///
+    /// # Errors
+    /// Returns error if the file cannot be read or is not a storage file
+    pub fn read_legacy(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).with_context(|| {
+            format!(
+                "Failed to read legacy configuration storage {}",
+                path.display()
+            )
+        })?;
+        let mut json: serde_json::Value = serde_json::from_str(&content).with_context(|| {
+            format!(
+                "Failed to parse legacy configuration storage {}",
+                path.display()
+            )
+        })?;
+        if let Some(configs) = json
+            .get_mut("configurations")
+            .and_then(serde_json::Value::as_object_mut)
+        {
+            for (alias, config) in configs.iter_mut() {
+                if let Some(config) = config.as_object_mut() {
+                    config
+                        .entry("alias_name")
+                        .or_insert_with(|| alias.clone().into());
+                }
+            }
+        }
+        migrations::migrate(json).with_context(|| {
+            format!(
+                "{} is not a cc-switch configuration storage file",
+                path.display()
+            )
+        })
+    }
+
+    /// Read and parse the backup at `path`
+    fn read_backup(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("No backup found at {}", path.display()))?;
+        migrations::parse(&content)
+            .with_context(|| format!("Backup {} does not parse", path.display()))
+    }
+
     /// Save configurations to disk
     ///
-    /// Writes the current state to `~/.claude/cc_auto_switch_setting.json`
+    /// Writes the current state to the storage file
     /// Creates the directory structure if it doesn't exist
     ///
+    /// The file is replaced atomically, and its previous version is kept as
+    /// `<file>.bak` unless it is itself corrupt. On Unix
+    /// both are made readable by the owner only (0600) and the directory
+    /// 0700, since they hold API tokens.
+    ///
     /// # Errors
     /// Returns error if directory cannot be created or file cannot be written
     pub fn save(&self) -> Result<()> {
-        let path = get_config_storage_path()?;
+        self.save_to(&get_config_storage_path()?)
+    }
 
+    /// Save configurations to the storage file at `path`, keeping its
+    /// previous version as `<path>.bak`
+    ///
+    /// # Errors
+    /// Returns error if directory cannot be created or file cannot be written
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        self.save_to_with(path, &SystemStore)
+    }
+
+    /// [`Self::save_to`] keeping tokens in `store` when secure mode is on
+    ///
+    /// The file then holds `keyring:<alias>` in place of each token, and
+    /// the entries of configurations removed or renamed since the previous
+    /// version are deleted from `store`.
+    ///
+    /// # Errors
+    /// Returns error if directory cannot be created, file cannot be written
+    /// or a token cannot be stored
+    pub fn save_to_with(&self, path: &Path, store: &dyn SecretStore) -> Result<()> {
         // Create directory if it doesn't exist
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)
                 .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+            restrict_permissions(parent, 0o700)?;
         }
 
-        let json = serde_json::to_string_pretty(self)
+        let stored = if self.preferences.secure_tokens {
+            secure::seal(self, store)?
+        } else {
+            Cow::Borrowed(self)
+        };
+        let json = serde_json::to_string_pretty(&*stored)
             .with_context(|| "Failed to serialize configuration storage")?;
 
-        fs::write(&path, json).with_context(|| format!("Failed to write to {}", path.display()))?;
+        // A corrupt file must not replace the backup it may be recovered from
+        let previous = fs::read_to_string(path)
+            .ok()
+            .and_then(|text| migrations::parse(&text).ok().map(|storage| (text, storage)));
+        if let Some((text, _)) = &previous {
+            let backup_path = backup_path_of(path);
+            write_atomic(&backup_path, text)?;
+            restrict_permissions(&backup_path, 0o600)?;
+        }
+
+        if self.preferences.skip_write_verification {
+            write_atomic(path, &json)?;
+        } else {
+            write_verified(path, &json, |written| self.check_written(written))?;
+        }
+        restrict_permissions(path, 0o600)?;
+
+        if let Some((_, previous)) = previous {
+            for key in secure::stale_keys(&previous, &stored) {
+                if let Err(e) = store.delete(&key) {
+                    eprintln!("Warning: {e:#}");
+                }
+            }
+        }
 
         Ok(())
     }
 
-    /// Migrate configurations from old path to new path
-    ///
-    /// Old path: `~/.cc_auto_switch/configurations.json`
-    /// New path: `~/.claude/cc_auto_switch_setting.json`
+    /// Replace the backup of the storage file at `path` with a copy of the
+    /// file as it is now
     ///
-    /// Safe to run multiple times. If old path does not exist, returns Ok(()) and prints a note.
-    pub fn migrate_from_old_path() -> Result<()> {
-        let new_path = get_config_storage_path()?;
-        let old_path = dirs::home_dir()
-            .map(|home| home.join(".cc_auto_switch").join("configurations.json"))
-            .ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
-
-        if !old_path.exists() {
-            println!("ℹ️ No old configuration found at {}", old_path.display());
-            return Ok(());
-        }
+    /// # Errors
+    /// Returns error if the file can't be read or the backup written
+    pub fn replace_backup_at(path: &Path) -> Result<()> {
+        let current = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let backup_path = backup_path_of(path);
+        write_atomic(&backup_path, &current)?;
+        restrict_permissions(&backup_path, 0o600)
+    }
 
-        println!("🔄 Migrating configuration from old location...");
+    /// Apply `f` to the latest stored configurations and save the result,
+    /// holding an exclusive lock so concurrent cc-switch runs don't lose
+    /// each other's changes
+    ///
+    /// `self` is replaced by the configurations as stored on disk before
+    /// `f` runs, so `f` sees the changes of other runs; nothing is saved
+    /// when `f` fails.
+    ///
+    /// # Errors
+    /// Returns error if the lock cannot be taken, the storage cannot be
+    /// loaded or saved, or `f` fails
+    pub fn with_lock<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        self.with_lock_at(&get_config_storage_path()?, f)
+    }
 
-        let content = fs::read_to_string(&old_path).with_context(|| {
-            format!(
-                "Failed to read old configuration from {}",
-                old_path.display()
-            )
-        })?;
+    /// [`Self::with_lock`] on the storage file at `path`
+    ///
+    /// # Errors
+    /// Returns error if the lock cannot be taken, the storage cannot be
+    /// loaded or saved, or `f` fails
+    pub fn with_lock_at<T>(
+        &mut self,
+        path: &Path,
+        f: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        self.with_lock_in(path, &SystemStore, f)
+    }
 
-        let storage: ConfigStorage = serde_json::from_str(&content)
-            .with_context(|| "Failed to parse old configuration storage JSON")?;
+    /// [`Self::with_lock_at`] keeping tokens in `store` (see
+    /// [`Self::save_to_with`])
+    ///
+    /// # Errors
+    /// Returns error if the lock cannot be taken, the storage cannot be
+    /// loaded or saved, or `f` fails
+    pub fn with_lock_in<T>(
+        &mut self,
+        path: &Path,
+        store: &dyn SecretStore,
+        f: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        let _lock = lock_storage(path)?;
+        *self = Self::load_from(path)?;
+        let value = f(self)?;
+        self.save_to_with(path, store)?;
+        Ok(value)
+    }
 
-        // Save to new location
-        storage
-            .save()
-            .with_context(|| "Failed to save migrated configuration to new location")?;
+    /// Replace the configuration storage file with its backup
+    ///
+    /// Returns the path of the backup restored from.
+    ///
+    /// # Errors
+    /// Returns error if there is no backup, it doesn't parse, or writing fails
+    pub fn restore_backup() -> Result<PathBuf> {
+        let path = get_config_storage_path()?;
+        let backup_path = get_config_backup_path()?;
+        let json = serde_json::to_string_pretty(&Self::read_backup(&backup_path)?)
+            .with_context(|| "Failed to serialize configuration storage")?;
+        write_atomic(&path, json)?;
+        restrict_permissions(&path, 0o600)?;
+        Ok(backup_path)
+    }
 
-        // Remove old directory
-        if let Some(parent) = old_path.parent() {
-            fs::remove_dir_all(parent).with_context(|| {
-                format!(
-                    "Failed to remove old configuration directory {}",
-                    parent.display()
-                )
-            })?;
+    /// Check that `written` (the file read back after a save) holds the
+    /// same configurations as `self`
+    ///
+    /// # Errors
+    /// Returns error if `written` doesn't parse or its aliases differ
+    pub fn check_written(&self, written: &str) -> Result<()> {
+        let stored = migrations::parse(written)
+            .with_context(|| "Saved configuration storage does not parse")?;
+        if !stored.configurations.keys().eq(self.configurations.keys()) {
+            anyhow::bail!(
+                "Saved configuration storage has {} configurations ({}), expected {} ({})",
+                stored.configurations.len(),
+                stored
+                    .configurations
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                self.configurations.len(),
+                self.configurations
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
         }
-
-        println!(
-            "✅ Configuration migrated successfully to {}",
-            new_path.display()
-        );
-
         Ok(())
     }
 
@@ -130,9 +432,91 @@ impl ConfigStorage {
     /// # Returns
     /// `true` if configuration was found and removed, `false` if not found
     pub fn remove_configuration(&mut self, alias_name: &str) -> bool {
+        if self.default_alias.as_deref() == Some(alias_name) {
+            self.default_alias = None;
+        }
+        if self.last_used.as_deref() == Some(alias_name) {
+            self.last_used = None;
+        }
+        self.last_used_at.remove(alias_name);
+        self.use_counts.remove(alias_name);
         self.configurations.remove(alias_name).is_some()
     }
 
+    /// Rename a configuration, keeping its token, URL and other fields
+    ///
+    /// # Arguments
+    /// * `old_alias` - Current alias name of the configuration
+    /// * `new_alias` - Alias name to move it to
+    ///
+    /// Overwrites an existing configuration named `new_alias`; callers that
+    /// don't want that check for it first.
+    ///
+    /// # Errors
+    /// Returns error if `old_alias` doesn't exist or `new_alias` is not a
+    /// valid alias name
+    pub fn rename_configuration(&mut self, old_alias: &str, new_alias: &str) -> Result<()> {
+        crate::config::validate_alias_name(new_alias)?;
+        let mut config = self
+            .configurations
+            .remove(old_alias)
+            .ok_or_else(|| crate::exit_code::config_not_found(old_alias))?;
+        config.alias_name = new_alias.to_string();
+        self.configurations.insert(new_alias.to_string(), config);
+        self.move_alias_references(old_alias, new_alias);
+        Ok(())
+    }
+
+    /// Point the default and last-used references at `new_alias` after a
+    /// rename
+    fn move_alias_references(&mut self, old_alias: &str, new_alias: &str) {
+        if self.default_alias.as_deref() == Some(old_alias) {
+            self.default_alias = Some(new_alias.to_string());
+        }
+        if self.last_used.as_deref() == Some(old_alias) {
+            self.last_used = Some(new_alias.to_string());
+        }
+        if let Some(at) = self.last_used_at.remove(old_alias) {
+            self.last_used_at.insert(new_alias.to_string(), at);
+        }
+        if let Some(count) = self.use_counts.remove(old_alias) {
+            self.use_counts.insert(new_alias.to_string(), count);
+        }
+    }
+
+    /// Record that `alias_name` was launched at `at`, counting the launch
+    pub fn record_use(&mut self, alias_name: &str, at: chrono::DateTime<chrono::Utc>) {
+        self.last_used = Some(alias_name.to_string());
+        self.last_used_at.insert(alias_name.to_string(), at);
+        *self.use_counts.entry(alias_name.to_string()).or_default() += 1;
+    }
+
+    /// The configuration `cc-switch use -` switches back to
+    ///
+    /// # Errors
+    /// Returns error if no configuration has been launched yet
+    pub fn last_used_alias(&self) -> Result<&str> {
+        self.last_used.as_deref().ok_or_else(|| {
+            anyhow::anyhow!(
+                "No configuration has been used yet; there is nothing to switch back to"
+            )
+        })
+    }
+
+    /// Set or clear the configuration launched by bare `cc-switch`
+    ///
+    /// # Errors
+    /// Returns error if `alias_name` is not a stored configuration
+    pub fn set_default_alias(&mut self, alias_name: Option<&str>) -> Result<()> {
+        if let Some(alias) = alias_name
+            && !self.configurations.contains_key(alias)
+        {
+            return Err(crate::exit_code::config_not_found(alias));
+        }
+        self.default_alias = alias_name.map(str::to_string);
+        Ok(())
+    }
+
     /// Get a configuration by alias name
     ///
     /// # Arguments
@@ -144,13 +528,33 @@ impl ConfigStorage {
         self.configurations.get(alias_name)
     }
 
+    /// Configurations carrying `tag` (case-insensitive), or all of them
+    /// when `tag` is `None`
+    ///
+    /// # Errors
+    /// Returns error if no configuration carries `tag`
+    pub fn configurations_tagged(&self, tag: Option<&str>) -> Result<ConfigMap> {
+        let Some(tag) = tag else {
+            return Ok(self.configurations.clone());
+        };
+        let tagged: ConfigMap = self
+            .configurations
+            .iter()
+            .filter(|(_, config)| config.has_tag(tag))
+            .map(|(alias, config)| (alias.clone(), config.clone()))
+            .collect();
+        if tagged.is_empty() {
+            anyhow::bail!("no configurations match tag '{}'", tag);
+        }
+        Ok(tagged)
+    }
+
     /// Set the default directory for Claude settings
     ///
     /// # Arguments
     /// * `directory` - Directory path for Claude settings
-    #[allow(dead_code)]
     pub fn set_claude_settings_dir(&mut self, directory: String) {
-        self.claude_settings_dir = Some(directory);
+        self.claude.settings_dir = Some(directory);
     }
 
     /// Get the current Claude settings directory
@@ -159,7 +563,7 @@ impl ConfigStorage {
     /// `Some(&String)` if custom directory is set, `None` if using default
     #[allow(dead_code)]
     pub fn get_claude_settings_dir(&self) -> Option<&String> {
-        self.claude_settings_dir.as_ref()
+        self.claude.settings_dir.as_ref()
     }
 
     /// Update an existing configuration
@@ -184,12 +588,13 @@ impl ConfigStorage {
     ) -> Result<()> {
         // Check if the old configuration exists
         if !self.configurations.contains_key(old_alias) {
-            return Err(anyhow::anyhow!("Configuration '{}' not found", old_alias));
+            return Err(crate::exit_code::config_not_found(old_alias));
         }
 
         // If alias changed, remove the old entry
         if old_alias != new_config.alias_name {
             self.configurations.remove(old_alias);
+            self.move_alias_references(old_alias, &new_config.alias_name);
         }
 
         // Insert the updated configuration (this will overwrite if alias hasn't changed)
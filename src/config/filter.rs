@@ -0,0 +1,185 @@
+//! `--where` filters for commands that act on several configurations at once.
+//!
+//! A filter is `key=value` (exact match) or `key~=value` (substring match).
+//! Supported keys:
+//! - `url`: the configuration's ANTHROPIC_BASE_URL
+//! - `provider`: the host part of the URL (e.g. `relay.example.com`)
+//! - `alias`: the configuration's alias name
+//! - `tag`: any of the configuration's tags
+
+use anyhow::Result;
+
+use crate::config::types::{ConfigStorage, Configuration};
+
+/// Keys accepted on the left-hand side of a `--where` filter
+pub const FILTER_KEYS: &[&str] = &["url", "provider", "alias", "tag"];
+
+/// A parsed `--where` filter
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigFilter {
+    /// Field the filter applies to (one of [`FILTER_KEYS`])
+    pub key: String,
+    /// Value to compare against
+    pub value: String,
+    /// `true` for `~=` (substring), `false` for `=` (exact)
+    pub substring: bool,
+}
+
+impl ConfigFilter {
+    /// Parse a `key=value` or `key~=value` filter expression
+    ///
+    /// # Errors
+    /// Returns error if the expression has no operator or an unknown key
+    pub fn parse(expr: &str) -> Result<Self> {
+        let (key, value, substring) = if let Some((key, value)) = expr.split_once("~=") {
+            (key, value, true)
+        } else if let Some((key, value)) = expr.split_once('=') {
+            (key, value, false)
+        } else {
+            anyhow::bail!(
+                "Invalid filter '{}'. Expected key=value or key~=value",
+                expr
+            );
+        };
+
+        let key = key.trim().to_lowercase();
+        if !FILTER_KEYS.contains(&key.as_str()) {
+            anyhow::bail!(
+                "Unknown filter key '{}'. Valid keys: {}",
+                key,
+                FILTER_KEYS.join(", ")
+            );
+        }
+
+        Ok(ConfigFilter {
+            key,
+            value: value.trim().to_string(),
+            substring,
+        })
+    }
+
+    /// Check whether a configuration matches this filter
+    pub fn matches(&self, config: &Configuration) -> bool {
+        if self.key == "tag" {
+            return config.tags.iter().any(|tag| self.compare(tag));
+        }
+
+        let candidate = match self.key.as_str() {
+            "url" => config.url.as_str(),
+            "provider" => provider_of(&config.url),
+            "alias" => config.alias_name.as_str(),
+            _ => return false,
+        };
+        self.compare(candidate)
+    }
+
+    fn compare(&self, candidate: &str) -> bool {
+        if self.substring {
+            candidate.contains(&self.value)
+        } else {
+            candidate == self.value
+        }
+    }
+
+    /// Aliases of all stored configurations matching this filter, in order
+    pub fn matching_aliases(&self, storage: &ConfigStorage) -> Vec<String> {
+        storage
+            .configurations
+            .values()
+            .filter(|config| self.matches(config))
+            .map(|config| config.alias_name.clone())
+            .collect()
+    }
+}
+
+/// Host part of a URL: scheme, port, path and credentials are stripped
+fn provider_of(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    host.split(':').next().unwrap_or(host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(alias: &str, url: &str) -> Configuration {
+        Configuration {
+            alias_name: alias.to_string(),
+            token: "sk-test".to_string(),
+            url: url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parse_exact_and_substring() {
+        let exact = ConfigFilter::parse("url=https://old.relay.example").unwrap();
+        assert_eq!(exact.key, "url");
+        assert_eq!(exact.value, "https://old.relay.example");
+        assert!(!exact.substring);
+
+        let sub = ConfigFilter::parse("provider~=relay").unwrap();
+        assert_eq!(sub.key, "provider");
+        assert_eq!(sub.value, "relay");
+        assert!(sub.substring);
+    }
+
+    #[test]
+    fn parse_rejects_bad_expressions() {
+        assert!(ConfigFilter::parse("url").is_err());
+        let err = ConfigFilter::parse("color=red").unwrap_err().to_string();
+        assert!(err.contains("Unknown filter key 'color'"), "got: {err}");
+    }
+
+    #[test]
+    fn matches_by_url_provider_and_alias() {
+        let relay = config("a", "https://old.relay.example:8443/v1");
+        let other = config("b", "https://api.anthropic.com");
+
+        let url = ConfigFilter::parse("url=https://old.relay.example:8443/v1").unwrap();
+        assert!(url.matches(&relay));
+        assert!(!url.matches(&other));
+
+        let url_prefix = ConfigFilter::parse("url=https://old.relay.example").unwrap();
+        assert!(
+            !url_prefix.matches(&relay),
+            "exact match must not be a prefix match"
+        );
+
+        let provider = ConfigFilter::parse("provider=old.relay.example").unwrap();
+        assert!(provider.matches(&relay));
+        assert!(!provider.matches(&other));
+
+        let alias = ConfigFilter::parse("alias~=a").unwrap();
+        assert!(alias.matches(&relay));
+        assert!(!alias.matches(&other));
+    }
+
+    #[test]
+    fn matches_any_tag() {
+        let mut tagged = config("a", "https://relay.example");
+        tagged.tags = vec!["prod".to_string(), "eu".to_string()];
+        let untagged = config("b", "https://relay.example");
+
+        let filter = ConfigFilter::parse("tag=eu").unwrap();
+        assert!(filter.matches(&tagged));
+        assert!(!filter.matches(&untagged));
+        assert!(!ConfigFilter::parse("tag=pro").unwrap().matches(&tagged));
+        assert!(ConfigFilter::parse("tag~=pro").unwrap().matches(&tagged));
+    }
+
+    #[test]
+    fn matching_aliases_collects_in_alias_order() {
+        let mut storage = ConfigStorage::default();
+        storage.add_configuration(config("zeta", "https://old.relay.example"));
+        storage.add_configuration(config("alpha", "https://old.relay.example"));
+        storage.add_configuration(config("beta", "https://api.anthropic.com"));
+
+        let filter = ConfigFilter::parse("url~=old.relay").unwrap();
+        assert_eq!(filter.matching_aliases(&storage), vec!["alpha", "zeta"]);
+    }
+}
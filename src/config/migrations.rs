@@ -0,0 +1,174 @@
+//! Versions of the storage file format and the steps between them.
+//!
+//! Every storage file is read through [`migrate`]: one of an older version
+//! is brought up to [`CURRENT_VERSION`] a step at a time before it is
+//! parsed, and one written by a newer cc-switch is refused, since parsing
+//! it would drop whatever this release doesn't know on the next save.
+//! Files without a `version` predate it and are version 1.
+
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+use std::fmt;
+
+use crate::config::types::ConfigStorage;
+
+/// Version of the storage format this release reads and writes
+pub const CURRENT_VERSION: u32 = 2;
+
+/// Version of files written before the format was versioned
+pub fn unversioned() -> u32 {
+    1
+}
+
+/// A step from one version to the next, on the file's top-level object
+type Migration = fn(&mut Map<String, Value>);
+
+/// `MIGRATIONS[n]` turns version `n + 1` into version `n + 2`
+const MIGRATIONS: [Migration; CURRENT_VERSION as usize - 1] = [fold_claude_settings_dir];
+
+/// The storage file was written by a newer cc-switch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NewerVersion {
+    pub found: u32,
+}
+
+impl fmt::Display for NewerVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "file written by a newer cc-switch, found version {}, supported up to {CURRENT_VERSION}; \
+             upgrade cc-switch to use it (the file is left as it is)",
+            self.found
+        )
+    }
+}
+
+impl std::error::Error for NewerVersion {}
+
+/// Format version of the storage file `storage`
+///
+/// # Errors
+/// Returns error if `version` is not a positive integer
+pub fn version_of(storage: &Value) -> Result<u32> {
+    match storage.get("version") {
+        None | Some(Value::Null) => Ok(unversioned()),
+        Some(version) => version
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .filter(|v| *v >= 1)
+            .with_context(|| format!("Invalid storage format version {version}")),
+    }
+}
+
+/// Parse the storage file `storage`, migrating it from an older version
+///
+/// # Errors
+/// Returns [`NewerVersion`] for a file of a newer version, or error if it
+/// is not a storage file
+pub fn migrate(mut storage: Value) -> Result<ConfigStorage> {
+    let version = version_of(&storage)?;
+    if version > CURRENT_VERSION {
+        return Err(NewerVersion { found: version }.into());
+    }
+    if let Some(object) = storage.as_object_mut() {
+        for step in &MIGRATIONS[version as usize - 1..] {
+            step(object);
+        }
+        object.insert("version".to_string(), CURRENT_VERSION.into());
+    }
+    Ok(serde_json::from_value(storage)?)
+}
+
+/// Parse the contents of a storage file (see [`migrate`])
+///
+/// # Errors
+/// Returns error if `content` is not JSON or [`migrate`] fails
+pub fn parse(content: &str) -> Result<ConfigStorage> {
+    migrate(serde_json::from_str(content)?)
+}
+
+/// Version 1 → 2: the top-level `claude_settings_dir` becomes
+/// `claude.settings_dir`
+fn fold_claude_settings_dir(storage: &mut Map<String, Value>) {
+    let Some(dir) = storage.remove("claude_settings_dir") else {
+        return;
+    };
+    if dir.is_null() {
+        return;
+    }
+    let claude = storage
+        .entry("claude")
+        .or_insert_with(|| Value::Object(Map::new()));
+    if let Some(claude) = claude.as_object_mut() {
+        claude.entry("settings_dir").or_insert(dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn an_unversioned_file_is_migrated_to_the_current_version() {
+        let storage = migrate(json!({
+            "configurations": {
+                "work": {"alias_name": "work", "token": "sk-work", "url": "https://work.example"}
+            },
+            "claude_settings_dir": "/opt/claude"
+        }))
+        .unwrap();
+        assert_eq!(storage.version, CURRENT_VERSION);
+        assert_eq!(storage.claude.settings_dir.as_deref(), Some("/opt/claude"));
+        assert_eq!(storage.configurations["work"].url, "https://work.example");
+
+        let written = serde_json::to_value(&storage).unwrap();
+        assert_eq!(written["version"], CURRENT_VERSION);
+        assert_eq!(written["claude"], json!({"settings_dir": "/opt/claude"}));
+        assert!(written.get("claude_settings_dir").is_none());
+
+        let unset = migrate(json!({"configurations": {}, "claude_settings_dir": null})).unwrap();
+        assert_eq!(unset.claude.settings_dir, None);
+        assert!(
+            serde_json::to_value(&unset)
+                .unwrap()
+                .get("claude")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn a_current_file_reads_back_unchanged() {
+        let mut storage = ConfigStorage::default();
+        storage.set_claude_settings_dir("/opt/claude".to_string());
+        let json = serde_json::to_string_pretty(&storage).unwrap();
+        assert!(json.contains("\"version\": 2"), "{json}");
+
+        let read = parse(&json).unwrap();
+        assert_eq!(serde_json::to_string_pretty(&read).unwrap(), json);
+    }
+
+    #[test]
+    fn a_newer_file_is_refused() {
+        let error = parse(r#"{"version": 3, "configurations": {}, "profiles": {}}"#)
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.downcast_ref::<NewerVersion>(),
+            Some(&NewerVersion { found: 3 })
+        );
+        let message = error.to_string();
+        assert!(
+            message.contains("found version 3, supported up to 2"),
+            "{message}"
+        );
+
+        for version in [json!(0), json!("2"), json!(-1)] {
+            let error = migrate(json!({"version": version, "configurations": {}}))
+                .err()
+                .unwrap()
+                .to_string();
+            assert!(error.contains("Invalid storage format version"), "{error}");
+        }
+    }
+}
@@ -1,8 +1,28 @@
+pub mod chain;
 #[allow(clippy::module_inception)]
 pub mod config;
 pub mod config_storage;
+pub mod filter;
+pub mod migrations;
+pub mod models;
+pub mod orphans;
+pub mod presets;
+pub mod probe;
+pub mod provider;
+pub mod quota;
+pub mod secure;
 pub mod types;
 
 // Re-export types for convenience
-pub use crate::config::config::{EnvironmentConfig, get_config_storage_path, validate_alias_name};
-pub use crate::config::types::{AddCommandParams, ClaudeSettings, ConfigStorage, Configuration};
+pub use crate::config::config::{
+    AliasTarget, EnvironmentConfig, OFFICIAL_ALIASES, get_config_backup_path,
+    get_config_storage_path, is_official_alias, managed_env_var_names, resolve_alias_target,
+    resolve_stored_alias, unset_statements, validate_alias_name, validate_base_url,
+};
+pub use crate::config::filter::ConfigFilter;
+pub use crate::config::models::{ModelNotice, ModelNoticeLevel};
+pub use crate::config::provider::{Provider, TokenWarning};
+pub use crate::config::types::{
+    ANTHROPIC_ENV_VARS, AddCommandParams, ClaudeSettings, ConfigStorage, Configuration,
+    LABEL_COLORS, LaunchProfile, QuotaProbe, parse_label_color,
+};
@@ -0,0 +1,266 @@
+//! Model name checks: whether a configured model looks like an Anthropic
+//! model identifier, and the closest known one when it doesn't.
+//!
+//! A name is accepted when it is in [`KNOWN_MODELS`] or follows the naming
+//! pattern of Anthropic models, so models released after this list still
+//! pass. Like the token checks, the result is only ever a warning, and just
+//! a note for gateways, which are free to use their own names.
+
+use std::fmt;
+
+use crate::config::provider::Provider;
+
+/// Anthropic model identifiers and the aliases Claude accepts, newest first
+/// within each family
+pub const KNOWN_MODELS: &[&str] = &[
+    "default",
+    "sonnet",
+    "opus",
+    "haiku",
+    "opusplan",
+    "sonnet[1m]",
+    "claude-opus-4-5-20251101",
+    "claude-opus-4-5",
+    "claude-opus-4-1-20250805",
+    "claude-opus-4-1",
+    "claude-opus-4-20250514",
+    "claude-opus-4-0",
+    "claude-sonnet-4-5-20250929",
+    "claude-sonnet-4-5",
+    "claude-sonnet-4-20250514",
+    "claude-sonnet-4-0",
+    "claude-haiku-4-5-20251001",
+    "claude-haiku-4-5",
+    "claude-3-7-sonnet-20250219",
+    "claude-3-7-sonnet-latest",
+    "claude-3-5-sonnet-20241022",
+    "claude-3-5-sonnet-20240620",
+    "claude-3-5-sonnet-latest",
+    "claude-3-5-haiku-20241022",
+    "claude-3-5-haiku-latest",
+    "claude-3-opus-20240229",
+    "claude-3-opus-latest",
+    "claude-3-sonnet-20240229",
+    "claude-3-haiku-20240307",
+];
+
+/// Model families appearing in Anthropic model identifiers
+const FAMILIES: [&str; 3] = ["opus", "sonnet", "haiku"];
+
+/// How much a model name that isn't recognized matters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelNoticeLevel {
+    /// The official API rejects unknown models
+    Warning,
+    /// Gateways use their own model names, so this is likely fine
+    Note,
+}
+
+/// A configured model name that isn't a known Anthropic model
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelNotice {
+    /// Configuration field holding the model, e.g. "small_fast_model"
+    pub field: &'static str,
+    pub model: String,
+    /// Closest known model, if one is close enough to be a typo
+    pub suggestion: Option<&'static str>,
+    pub level: ModelNoticeLevel,
+}
+
+impl fmt::Display for ModelNotice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} '{}' is not a known Anthropic model",
+            self.field, self.model
+        )?;
+        if let Some(suggestion) = self.suggestion {
+            write!(f, " (did you mean {suggestion}?)")?;
+        }
+        if self.level == ModelNoticeLevel::Note {
+            write!(f, "; fine if the gateway uses its own model names")?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `part` is a `YYYYMMDD` release date
+fn is_date_suffix(part: &str) -> bool {
+    part.len() == 8
+        && part.starts_with("20")
+        && part.bytes().all(|b| b.is_ascii_digit())
+        && matches!(part[4..6].parse::<u8>(), Ok(1..=12))
+        && matches!(part[6..].parse::<u8>(), Ok(1..=31))
+}
+
+/// Whether `model` follows the Anthropic naming pattern
+///
+/// That is `claude-`, version numbers of one or two digits and exactly one
+/// family in any order, then optionally a `YYYYMMDD` date or `latest`, and
+/// optionally `[1m]` for the long context variant.
+pub fn matches_model_pattern(model: &str) -> bool {
+    let model = model.strip_suffix("[1m]").unwrap_or(model);
+    let Some(rest) = model.strip_prefix("claude-") else {
+        return false;
+    };
+    let parts: Vec<&str> = rest.split('-').collect();
+    let body = match parts.split_last() {
+        Some((last, body)) if *last == "latest" || is_date_suffix(last) => body,
+        _ => &parts[..],
+    };
+    let version =
+        |part: &&str| (1..=2).contains(&part.len()) && part.bytes().all(|b| b.is_ascii_digit());
+    body.iter().filter(|part| FAMILIES.contains(part)).count() == 1
+        && body
+            .iter()
+            .all(|part| FAMILIES.contains(part) || version(part))
+}
+
+/// Whether `model` is a known model or follows the naming pattern
+pub fn is_known_model(model: &str) -> bool {
+    KNOWN_MODELS.contains(&model) || matches_model_pattern(model)
+}
+
+/// Number of single character insertions, deletions and substitutions
+/// turning `a` into `b`
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(diagonal + 1).min(row[j] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// The known model closest to `model`, if it is close enough to be a typo
+///
+/// Known models are also compared without their date or `-latest` suffix,
+/// so `claude-3.5-sonnet` finds `claude-3-5-sonnet-20241022`. On a tie the
+/// earlier entry of [`KNOWN_MODELS`], the newer release, wins.
+pub fn suggest_model(model: &str) -> Option<&'static str> {
+    let model = model.trim().to_ascii_lowercase();
+    let max_distance = (model.chars().count() / 4).max(2);
+    KNOWN_MODELS
+        .iter()
+        .map(|known| {
+            let short = known
+                .rsplit_once('-')
+                .filter(|(_, suffix)| *suffix == "latest" || is_date_suffix(suffix))
+                .map_or(*known, |(short, _)| short);
+            let distance = edit_distance(&model, known).min(edit_distance(&model, short));
+            (distance, *known)
+        })
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, known)| known)
+}
+
+/// Check the model in `field`: `None` when it is known, otherwise a notice
+/// whose level depends on `provider`
+pub fn check_model(field: &'static str, model: &str, provider: Provider) -> Option<ModelNotice> {
+    if is_known_model(model.trim()) {
+        return None;
+    }
+    Some(ModelNotice {
+        field,
+        model: model.to_string(),
+        suggestion: suggest_model(model),
+        level: match provider {
+            Provider::Anthropic => ModelNoticeLevel::Warning,
+            _ => ModelNoticeLevel::Note,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pattern_accepts_future_models_and_rejects_malformed_ones() {
+        for model in [
+            "claude-sonnet-4-5",
+            "claude-opus-5-20270101",
+            "claude-3-5-haiku-latest",
+            "claude-sonnet-4-5[1m]",
+            "sonnet",
+        ] {
+            assert!(is_known_model(model), "{model} should be accepted");
+        }
+        for model in [
+            "claude-3.5-sonnet",
+            "claude-3-5-sonnet-2024102",
+            "claude-3-5-sonnet-20241322",
+            "claude-sonnet-opus-4",
+            "claude-4",
+            "gpt-4o",
+            "Claude-sonnet-4",
+        ] {
+            assert!(!is_known_model(model), "{model} should be rejected");
+        }
+    }
+
+    #[test]
+    fn edit_distance_counts_single_character_edits() {
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("opus", "opus"), 0);
+    }
+
+    #[test]
+    fn suggestions_rank_the_closest_and_newest_model_first() {
+        assert_eq!(
+            suggest_model("claude-3.5-sonnet"),
+            Some("claude-3-5-sonnet-20241022")
+        );
+        assert_eq!(
+            suggest_model("claude-3-5-sonnet-2024102"),
+            Some("claude-3-5-sonnet-20241022")
+        );
+        assert_eq!(
+            suggest_model("claude-3-5-sonnet-20240602"),
+            Some("claude-3-5-sonnet-20240620")
+        );
+        assert_eq!(
+            suggest_model("claude-sonet-4-5"),
+            Some("claude-sonnet-4-5-20250929")
+        );
+        assert_eq!(suggest_model("Sonet"), Some("sonnet"));
+        assert_eq!(suggest_model("glm-4.6"), None);
+        assert_eq!(suggest_model("gpt-4o"), None);
+    }
+
+    #[test]
+    fn severity_depends_on_the_provider() {
+        assert_eq!(
+            check_model("model", "claude-sonnet-4-5", Provider::Anthropic),
+            None
+        );
+
+        let warning = check_model("model", "claude-3.5-sonnet", Provider::Anthropic).unwrap();
+        assert_eq!(warning.level, ModelNoticeLevel::Warning);
+        assert_eq!(
+            warning.to_string(),
+            "model 'claude-3.5-sonnet' is not a known Anthropic model (did you mean claude-3-5-sonnet-20241022?)"
+        );
+
+        let note = check_model("small_fast_model", "glm-4.6", Provider::Custom).unwrap();
+        assert_eq!(note.level, ModelNoticeLevel::Note);
+        assert_eq!(
+            note.to_string(),
+            "small_fast_model 'glm-4.6' is not a known Anthropic model; fine if the gateway uses its own model names"
+        );
+        assert_eq!(
+            check_model("model", "anthropic/claude-sonnet-4", Provider::OpenRouter)
+                .unwrap()
+                .level,
+            ModelNoticeLevel::Note
+        );
+    }
+}
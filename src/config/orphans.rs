@@ -0,0 +1,130 @@
+//! Configurations whose external references no longer resolve
+//! (`cc-switch list --orphaned`).
+//!
+//! Each detector looks at one configuration through a [`ResourceProbe`], so
+//! they can be tested without touching the system.
+
+use crate::config::types::{ConfigStorage, Configuration};
+
+/// Lookups of resources outside the storage file
+pub trait ResourceProbe {
+    /// Whether `program` is found on PATH (or exists, when it is a path)
+    fn command_exists(&self, program: &str) -> bool;
+}
+
+/// Probe looking at the real system
+pub struct SystemProbe;
+
+impl ResourceProbe for SystemProbe {
+    fn command_exists(&self, program: &str) -> bool {
+        which::which(program).is_ok()
+    }
+}
+
+/// A configuration's broken reference
+#[derive(Debug, Clone, PartialEq)]
+pub struct Orphan {
+    pub alias: String,
+    /// What is referenced and why it is broken
+    pub reference: String,
+}
+
+/// Finds the broken references of one configuration
+type Detector = fn(&Configuration, &dyn ResourceProbe) -> Vec<String>;
+
+/// Every detector run by [`find_orphans`]
+const DETECTORS: &[Detector] = &[missing_launch_commands];
+
+/// Launch profiles whose command cannot be found
+pub fn missing_launch_commands(config: &Configuration, probe: &dyn ResourceProbe) -> Vec<String> {
+    config
+        .launch_profiles
+        .iter()
+        .filter(|(_, profile)| !probe.command_exists(&profile.command))
+        .map(|(name, profile)| {
+            format!(
+                "launch profile '{}' runs '{}', which is not found",
+                name, profile.command
+            )
+        })
+        .collect()
+}
+
+/// Broken references of every stored configuration, by alias
+pub fn find_orphans(storage: &ConfigStorage, probe: &dyn ResourceProbe) -> Vec<Orphan> {
+    storage
+        .configurations
+        .values()
+        .flat_map(|config| {
+            DETECTORS
+                .iter()
+                .flat_map(|detect| detect(config, probe))
+                .map(|reference| Orphan {
+                    alias: config.alias_name.clone(),
+                    reference,
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::types::LaunchProfile;
+
+    /// Probe where only the listed commands exist
+    struct Installed(&'static [&'static str]);
+
+    impl ResourceProbe for Installed {
+        fn command_exists(&self, program: &str) -> bool {
+            self.0.contains(&program)
+        }
+    }
+
+    /// Launch profile name and its command
+    type ProfileCommand<'a> = (&'a str, &'a str);
+
+    fn with_profiles(alias: &str, commands: &[ProfileCommand]) -> Configuration {
+        Configuration {
+            alias_name: alias.to_string(),
+            launch_profiles: commands
+                .iter()
+                .map(|(name, command)| {
+                    (
+                        name.to_string(),
+                        LaunchProfile {
+                            command: command.to_string(),
+                            ..Default::default()
+                        },
+                    )
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn missing_launch_commands_names_the_profile_and_command() {
+        let config = with_profiles("work", &[("aider", "aider"), ("goose", "goose")]);
+        assert_eq!(
+            missing_launch_commands(&config, &Installed(&["aider"])),
+            vec!["launch profile 'goose' runs 'goose', which is not found".to_string()]
+        );
+        assert!(missing_launch_commands(&config, &Installed(&["aider", "goose"])).is_empty());
+        assert!(missing_launch_commands(&with_profiles("home", &[]), &Installed(&[])).is_empty());
+    }
+
+    #[test]
+    fn find_orphans_reports_each_broken_reference() {
+        let mut storage = ConfigStorage::default();
+        storage.add_configuration(with_profiles("home", &[("aider", "aider")]));
+        storage.add_configuration(with_profiles("work", &[("goose", "goose")]));
+        assert_eq!(
+            find_orphans(&storage, &Installed(&["aider"])),
+            vec![Orphan {
+                alias: "work".to_string(),
+                reference: "launch profile 'goose' runs 'goose', which is not found".to_string(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,240 @@
+//! Provider presets for `cc-switch add`: the base URL and recommended
+//! models of well-known Anthropic-compatible endpoints.
+//!
+//! `add --preset <name>` fills in whatever the flags leave unset, and
+//! interactive add offers the same table as a numbered list before asking
+//! for the token.
+
+use crate::config::types::AddCommandParams;
+use crate::exit_code::invalid;
+
+/// An endpoint's base URL, recommended models and token format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Preset {
+    /// Name used with `--preset`
+    pub name: &'static str,
+    /// Name shown in the interactive list
+    pub label: &'static str,
+    pub url: &'static str,
+    pub model: Option<&'static str>,
+    pub small_fast_model: Option<&'static str>,
+    pub default_sonnet_model: Option<&'static str>,
+    pub default_opus_model: Option<&'static str>,
+    pub default_haiku_model: Option<&'static str>,
+    /// What the provider's tokens look like, shown before asking for one
+    pub token_hint: &'static str,
+}
+
+/// Every preset, in the order they are listed
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "anthropic",
+        label: "Anthropic official",
+        url: "https://api.anthropic.com",
+        model: None,
+        small_fast_model: None,
+        default_sonnet_model: None,
+        default_opus_model: None,
+        default_haiku_model: None,
+        token_hint: "sk-ant-...",
+    },
+    Preset {
+        name: "openrouter",
+        label: "OpenRouter",
+        url: "https://openrouter.ai/api",
+        model: None,
+        small_fast_model: None,
+        default_sonnet_model: None,
+        default_opus_model: None,
+        default_haiku_model: None,
+        token_hint: "sk-or-v1-...",
+    },
+    Preset {
+        name: "deepseek",
+        label: "DeepSeek",
+        url: "https://api.deepseek.com/anthropic",
+        model: Some("deepseek-v4-pro"),
+        small_fast_model: Some("deepseek-v4-flash"),
+        default_sonnet_model: Some("deepseek-v4-pro"),
+        default_opus_model: Some("deepseek-v4-pro"),
+        default_haiku_model: Some("deepseek-v4-flash"),
+        token_hint: "sk-...",
+    },
+    Preset {
+        name: "moonshot",
+        label: "Moonshot (Kimi)",
+        url: "https://api.moonshot.ai/anthropic",
+        model: Some("kimi-k2-turbo-preview"),
+        small_fast_model: Some("kimi-k2-turbo-preview"),
+        default_sonnet_model: None,
+        default_opus_model: None,
+        default_haiku_model: None,
+        token_hint: "sk-...",
+    },
+    Preset {
+        name: "zhipu",
+        label: "Zhipu GLM",
+        url: "https://open.bigmodel.cn/api/anthropic",
+        model: None,
+        small_fast_model: None,
+        default_sonnet_model: Some("glm-4.6"),
+        default_opus_model: Some("glm-4.6"),
+        default_haiku_model: Some("glm-4.5-air"),
+        token_hint: "<id>.<secret>",
+    },
+];
+
+/// The preset called `name` (case-insensitive)
+///
+/// # Errors
+/// Returns error naming the valid presets for anything else
+pub fn find(name: &str) -> anyhow::Result<&'static Preset> {
+    PRESETS
+        .iter()
+        .find(|preset| preset.name.eq_ignore_ascii_case(name.trim()))
+        .ok_or_else(|| {
+            invalid(format!(
+                "Unknown preset '{name}'. Valid presets: {}",
+                PRESETS
+                    .iter()
+                    .map(|preset| preset.name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+        })
+}
+
+/// The numbered list shown at the start of interactive add, ending with
+/// "Custom"
+pub fn menu_lines() -> Vec<String> {
+    PRESETS
+        .iter()
+        .enumerate()
+        .map(|(i, preset)| format!("  {}. {} ({})", i + 1, preset.label, preset.url))
+        .chain(std::iter::once(format!(
+            "  {}. Custom...",
+            PRESETS.len() + 1
+        )))
+        .collect()
+}
+
+/// The choice typed at the preset list: `Some(Some(preset))`, `Some(None)`
+/// for Custom (also an empty answer), `None` for anything else
+pub fn menu_choice(input: &str) -> Option<Option<&'static Preset>> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Some(None);
+    }
+    match input.parse::<usize>() {
+        Ok(n) if (1..=PRESETS.len()).contains(&n) => Some(Some(&PRESETS[n - 1])),
+        Ok(n) if n == PRESETS.len() + 1 => Some(None),
+        Ok(_) => None,
+        Err(_) => find(input).ok().map(Some),
+    }
+}
+
+impl Preset {
+    /// Fill the URL and models of `params` that aren't given
+    pub fn apply(&self, params: &mut AddCommandParams) {
+        if params.url.is_none() && params.url_arg.is_none() {
+            params.url = Some(self.url.to_string());
+        }
+        let fields = [
+            (&mut params.model, self.model),
+            (&mut params.small_fast_model, self.small_fast_model),
+            (
+                &mut params.anthropic_default_sonnet_model,
+                self.default_sonnet_model,
+            ),
+            (
+                &mut params.anthropic_default_opus_model,
+                self.default_opus_model,
+            ),
+            (
+                &mut params.anthropic_default_haiku_model,
+                self.default_haiku_model,
+            ),
+        ];
+        for (field, value) in fields {
+            if field.is_none() {
+                *field = value.map(str::to_string);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::validate_base_url;
+
+    #[test]
+    fn every_preset_url_passes_the_validator() {
+        for preset in PRESETS {
+            assert_eq!(
+                validate_base_url(preset.url).unwrap(),
+                preset.url,
+                "{}",
+                preset.name
+            );
+        }
+    }
+
+    #[test]
+    fn names_are_unique_and_found_case_insensitively() {
+        for preset in PRESETS {
+            assert_eq!(find(&preset.name.to_uppercase()).unwrap(), preset);
+        }
+        let error = find("nope").err().unwrap().to_string();
+        assert!(error.contains("anthropic, openrouter"), "{error}");
+    }
+
+    #[test]
+    fn the_menu_ends_with_custom_and_accepts_numbers_or_names() {
+        let lines = menu_lines();
+        assert_eq!(
+            lines[0],
+            "  1. Anthropic official (https://api.anthropic.com)"
+        );
+        assert_eq!(
+            lines.last().unwrap(),
+            &format!("  {}. Custom...", PRESETS.len() + 1)
+        );
+
+        assert_eq!(menu_choice("2"), Some(Some(&PRESETS[1])));
+        assert_eq!(menu_choice("deepseek").unwrap().unwrap().name, "deepseek");
+        assert_eq!(menu_choice(""), Some(None));
+        assert_eq!(menu_choice(&(PRESETS.len() + 1).to_string()), Some(None));
+        assert_eq!(menu_choice("0"), None);
+        assert_eq!(menu_choice("99"), None);
+    }
+
+    #[test]
+    fn apply_fills_only_what_the_flags_leave_unset() {
+        let mut params = AddCommandParams {
+            model: Some("my-model".to_string()),
+            ..Default::default()
+        };
+        find("deepseek").unwrap().apply(&mut params);
+        assert_eq!(
+            params.url.as_deref(),
+            Some("https://api.deepseek.com/anthropic")
+        );
+        assert_eq!(params.model.as_deref(), Some("my-model"));
+        assert_eq!(
+            params.small_fast_model.as_deref(),
+            Some("deepseek-v4-flash")
+        );
+
+        let mut params = AddCommandParams {
+            url_arg: Some("https://relay.example".to_string()),
+            ..Default::default()
+        };
+        find("zhipu").unwrap().apply(&mut params);
+        assert!(params.url.is_none());
+        assert_eq!(
+            params.anthropic_default_haiku_model.as_deref(),
+            Some("glm-4.5-air")
+        );
+    }
+}
@@ -0,0 +1,303 @@
+//! Connectivity checks for `cc-switch test`: one authenticated request to a
+//! configuration's endpoint, timed, with common failures explained.
+//!
+//! By default the check lists models, which costs nothing; `--live` sends a
+//! one-token message instead, for relays that only proxy `/v1/messages`.
+
+use anyhow::Result;
+use std::time::{Duration, Instant};
+
+use crate::config::types::Configuration;
+use crate::utils::http::{self, CapturedResponse};
+
+/// Timeout used when the configuration doesn't set `api_timeout_ms`
+pub const DEFAULT_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// API version sent with every probe
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Model used by live probes when the configuration names none
+const FALLBACK_LIVE_MODEL: &str = "claude-3-5-haiku-latest";
+
+/// Type alias for a request header (name, value)
+type Header = (String, String);
+
+/// One request a probe sends
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbeRequest {
+    pub url: String,
+    /// Header names are lowercase
+    pub headers: Vec<Header>,
+    /// JSON body; `None` sends a GET, `Some` a POST
+    pub body: Option<String>,
+    pub timeout: Duration,
+}
+
+/// Sends probe requests; replaced by a fake in tests
+pub trait Transport {
+    /// Send `request` and capture the response
+    ///
+    /// # Errors
+    /// Returns error if no response arrives
+    fn send(&mut self, request: &ProbeRequest) -> Result<CapturedResponse>;
+}
+
+/// Transport over real HTTP
+pub struct HttpTransport;
+
+impl Transport for HttpTransport {
+    fn send(&mut self, request: &ProbeRequest) -> Result<CapturedResponse> {
+        let headers: Vec<http::RequestHeader> = request
+            .headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        match &request.body {
+            Some(body) => http::post_json(&request.url, &headers, body, request.timeout),
+            None => http::get(&request.url, &headers, request.timeout),
+        }
+    }
+}
+
+/// Outcome of probing one configuration
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbeReport {
+    /// Time until the response (or the failure) arrived
+    pub latency: Duration,
+    /// HTTP status, when a response arrived
+    pub status: Option<u16>,
+    /// Why the probe failed; `None` when it passed
+    pub failure: Option<String>,
+}
+
+impl ProbeReport {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// Request that probes `config`: `GET /v1/models`, or with `live` a
+/// one-token `POST /v1/messages`
+pub fn probe_request(config: &Configuration, live: bool) -> ProbeRequest {
+    let base = config.url.trim_end_matches('/');
+    let auth = match config.auth_env_pair() {
+        ("ANTHROPIC_API_KEY", key) => ("x-api-key", key.to_string()),
+        (_, token) => ("authorization", format!("Bearer {token}")),
+    };
+    let headers = vec![
+        (auth.0.to_string(), auth.1),
+        (
+            "anthropic-version".to_string(),
+            ANTHROPIC_VERSION.to_string(),
+        ),
+    ];
+    let timeout = config
+        .api_timeout_ms
+        .filter(|ms| *ms > 0)
+        .map_or(DEFAULT_PROBE_TIMEOUT, |ms| {
+            Duration::from_millis(u64::from(ms))
+        });
+
+    if !live {
+        return ProbeRequest {
+            url: format!("{base}/v1/models"),
+            headers,
+            body: None,
+            timeout,
+        };
+    }
+    let model = config
+        .small_fast_model
+        .as_deref()
+        .or(config.anthropic_default_haiku_model.as_deref())
+        .or(config.model.as_deref())
+        .unwrap_or(FALLBACK_LIVE_MODEL);
+    let body = serde_json::json!({
+        "model": model,
+        "max_tokens": 1,
+        "messages": [{"role": "user", "content": "ping"}],
+    });
+    ProbeRequest {
+        url: format!("{base}/v1/messages"),
+        headers,
+        body: Some(body.to_string()),
+        timeout,
+    }
+}
+
+/// Friendly explanation of a non-success HTTP status
+fn status_failure(status: u16) -> Option<String> {
+    match status {
+        200..=299 => None,
+        401 | 403 => Some(format!("unauthorized (HTTP {status}): token rejected")),
+        404 => Some("not found (HTTP 404): wrong base path?".to_string()),
+        429 => Some("rate limited (HTTP 429)".to_string()),
+        500..=599 => Some(format!("server error (HTTP {status})")),
+        _ => Some(format!("unexpected status HTTP {status}")),
+    }
+}
+
+/// Friendly explanation of a request that got no response
+fn transport_failure(error: &anyhow::Error, timeout: Duration) -> String {
+    let timed_out = error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<reqwest::Error>())
+        .any(reqwest::Error::is_timeout);
+    let text = format!("{error:#}").to_ascii_lowercase();
+    if timed_out || text.contains("timed out") {
+        format!("timeout after {} ms", timeout.as_millis())
+    } else if text.contains("dns") || text.contains("failed to lookup") {
+        "DNS lookup failed: check the host name".to_string()
+    } else if text.contains("certificate") || text.contains("tls") || text.contains("handshake") {
+        "TLS error: certificate or handshake failed".to_string()
+    } else if text.contains("connection refused") || text.contains("connect") {
+        "connection failed: is the server reachable?".to_string()
+    } else {
+        format!("request failed: {error:#}")
+    }
+}
+
+/// Probe `config` once through `transport`
+pub fn probe(config: &Configuration, live: bool, transport: &mut impl Transport) -> ProbeReport {
+    let config = match config.with_resolved_token() {
+        Ok(config) => config,
+        Err(error) => {
+            return ProbeReport {
+                latency: Duration::ZERO,
+                status: None,
+                failure: Some(error.to_string()),
+            };
+        }
+    };
+    let request = probe_request(&config, live);
+    let started = Instant::now();
+    let result = transport.send(&request);
+    let latency = started.elapsed();
+    match result {
+        Ok(response) => ProbeReport {
+            latency,
+            status: Some(response.status),
+            failure: status_failure(response.status),
+        },
+        Err(error) => ProbeReport {
+            latency,
+            status: None,
+            failure: Some(transport_failure(&error, request.timeout)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    /// Canned reply of a fake transport
+    type Reply = fn() -> Result<CapturedResponse>;
+
+    /// Answers every request with `reply`, recording what was sent
+    struct FakeTransport {
+        reply: Reply,
+        sent: Vec<ProbeRequest>,
+    }
+
+    impl Transport for FakeTransport {
+        fn send(&mut self, request: &ProbeRequest) -> Result<CapturedResponse> {
+            self.sent.push(request.clone());
+            (self.reply)()
+        }
+    }
+
+    fn fake(reply: Reply) -> FakeTransport {
+        FakeTransport {
+            reply,
+            sent: Vec::new(),
+        }
+    }
+
+    fn status(status: u16) -> Result<CapturedResponse> {
+        Ok(CapturedResponse {
+            status,
+            ..Default::default()
+        })
+    }
+
+    fn config() -> Configuration {
+        Configuration {
+            alias_name: "work".to_string(),
+            token: "sk-test".to_string(),
+            url: "https://api.example.com/".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn header<'a>(request: &'a ProbeRequest, name: &str) -> Option<&'a str> {
+        request
+            .headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    #[test]
+    fn requests_list_models_or_send_one_token() {
+        let mut config = config();
+        let request = probe_request(&config, false);
+        assert_eq!(request.url, "https://api.example.com/v1/models");
+        assert_eq!(request.body, None);
+        assert_eq!(request.timeout, DEFAULT_PROBE_TIMEOUT);
+        assert_eq!(header(&request, "authorization"), Some("Bearer sk-test"));
+        assert_eq!(header(&request, "anthropic-version"), Some("2023-06-01"));
+
+        config.api_key = Some("sk-key".to_string());
+        config.api_timeout_ms = Some(2500);
+        config.model = Some("opus".to_string());
+        let request = probe_request(&config, true);
+        assert_eq!(request.url, "https://api.example.com/v1/messages");
+        assert_eq!(request.timeout, Duration::from_millis(2500));
+        assert_eq!(header(&request, "x-api-key"), Some("sk-key"));
+        let body: serde_json::Value = serde_json::from_str(&request.body.unwrap()).unwrap();
+        assert_eq!(body["model"], "opus");
+        assert_eq!(body["max_tokens"], 1);
+    }
+
+    #[test]
+    fn statuses_map_to_friendly_failures() {
+        let mut transport = fake(|| status(200));
+        let report = probe(&config(), false, &mut transport);
+        assert!(report.passed());
+        assert_eq!(report.status, Some(200));
+        assert_eq!(transport.sent.len(), 1);
+
+        let failure = |reply: Reply| probe(&config(), false, &mut fake(reply)).failure.unwrap();
+        assert!(failure(|| status(401)).contains("token rejected"));
+        assert!(failure(|| status(404)).contains("wrong base path"));
+        assert!(failure(|| status(503)).contains("server error"));
+        assert!(failure(|| status(302)).contains("unexpected status"));
+    }
+
+    #[test]
+    fn transport_errors_map_to_friendly_failures() {
+        let failure = |reply: Reply| {
+            let report = probe(&config(), false, &mut fake(reply));
+            assert_eq!(report.status, None);
+            report.failure.unwrap()
+        };
+        assert!(
+            failure(|| Err(anyhow!("dns error: failed to lookup address information")))
+                .starts_with("DNS lookup failed")
+        );
+        assert!(
+            failure(|| Err(anyhow!("invalid peer certificate: UnknownIssuer")))
+                .starts_with("TLS error")
+        );
+        assert_eq!(
+            failure(|| Err(anyhow!("operation timed out"))),
+            "timeout after 10000 ms"
+        );
+        assert!(
+            failure(|| Err(anyhow!("tcp connect error: Connection refused")))
+                .starts_with("connection failed")
+        );
+    }
+}
@@ -0,0 +1,316 @@
+//! Provider hints: which kind of endpoint a configuration talks to, and the
+//! token checks that follow from it.
+//!
+//! A configuration may store its provider; otherwise it is detected from the
+//! base URL. Checks only ever produce warnings, since gateways are free to use
+//! whatever token format they like.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Kind of endpoint a configuration talks to
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Provider {
+    /// The official Anthropic API
+    #[serde(rename = "anthropic")]
+    Anthropic,
+    /// OpenRouter's Anthropic-compatible endpoint
+    #[serde(rename = "openrouter")]
+    #[value(name = "openrouter")]
+    OpenRouter,
+    /// A gateway in front of Amazon Bedrock
+    #[serde(rename = "bedrock-proxy")]
+    BedrockProxy,
+    /// Any other Anthropic-compatible gateway
+    #[serde(rename = "custom")]
+    Custom,
+}
+
+/// Token format expected by a provider
+struct TokenRules {
+    prefix: Option<&'static str>,
+    min_len: usize,
+}
+
+/// Something that looks wrong with a token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenWarning {
+    /// A newline or carriage return, usually from copying across lines
+    LineBreak,
+    /// Spaces or tabs inside or around the token
+    Whitespace,
+    /// The token lacks the provider's usual prefix
+    MissingPrefix {
+        provider: Provider,
+        prefix: &'static str,
+    },
+    /// The token is shorter than the provider's tokens ever are
+    TooShort {
+        provider: Provider,
+        len: usize,
+        min: usize,
+    },
+}
+
+impl fmt::Display for TokenWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenWarning::LineBreak => {
+                write!(f, "Token contains a line break (copied across lines?)")
+            }
+            TokenWarning::Whitespace => write!(f, "Token contains spaces or tabs"),
+            TokenWarning::MissingPrefix { provider, prefix } => write!(
+                f,
+                "{} tokens start with '{prefix}'",
+                provider.display_name()
+            ),
+            TokenWarning::TooShort { provider, len, min } => write!(
+                f,
+                "Token is only {len} characters, {} tokens have at least {min} (truncated?)",
+                provider.display_name()
+            ),
+        }
+    }
+}
+
+impl Provider {
+    /// Name used on the command line and in storage
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Provider::Anthropic => "anthropic",
+            Provider::OpenRouter => "openrouter",
+            Provider::BedrockProxy => "bedrock-proxy",
+            Provider::Custom => "custom",
+        }
+    }
+
+    /// Name shown in warnings
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Provider::Anthropic => "Anthropic",
+            Provider::OpenRouter => "OpenRouter",
+            Provider::BedrockProxy => "Bedrock proxy",
+            Provider::Custom => "Custom gateway",
+        }
+    }
+
+    /// Parse a stored or command-line name (case-insensitive)
+    ///
+    /// # Errors
+    /// Returns error naming the valid providers for anything else
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        <Self as clap::ValueEnum>::from_str(value, true).map_err(|_| {
+            anyhow::anyhow!(
+                "Unknown provider '{value}'. Valid providers: anthropic, openrouter, bedrock-proxy, custom"
+            )
+        })
+    }
+
+    /// Guess the provider from a base URL's host
+    ///
+    /// Anything unrecognized, including URLs that don't parse, is `Custom`.
+    pub fn detect(url: &str) -> Self {
+        let Some(host) = url::Url::parse(url.trim())
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_ascii_lowercase))
+        else {
+            return Provider::Custom;
+        };
+        let under = |domain: &str| host == domain || host.ends_with(&format!(".{domain}"));
+        if under("anthropic.com") {
+            Provider::Anthropic
+        } else if under("openrouter.ai") {
+            Provider::OpenRouter
+        } else if host.contains("bedrock") {
+            Provider::BedrockProxy
+        } else {
+            Provider::Custom
+        }
+    }
+
+    fn rules(self) -> TokenRules {
+        match self {
+            Provider::Anthropic => TokenRules {
+                prefix: Some("sk-ant-"),
+                min_len: 40,
+            },
+            Provider::OpenRouter => TokenRules {
+                prefix: Some("sk-or-"),
+                min_len: 40,
+            },
+            Provider::BedrockProxy => TokenRules {
+                prefix: None,
+                min_len: 20,
+            },
+            Provider::Custom => TokenRules {
+                prefix: None,
+                min_len: 16,
+            },
+        }
+    }
+
+    /// Problems with `token` for this provider; empty when it looks fine
+    pub fn validate_token(self, token: &str) -> Vec<TokenWarning> {
+        let mut warnings = Vec::new();
+        if token.contains(['\n', '\r']) {
+            warnings.push(TokenWarning::LineBreak);
+        }
+        if token.contains([' ', '\t']) {
+            warnings.push(TokenWarning::Whitespace);
+        }
+
+        let rules = self.rules();
+        let trimmed = token.trim();
+        if let Some(prefix) = rules.prefix
+            && !trimmed.starts_with(prefix)
+        {
+            warnings.push(TokenWarning::MissingPrefix {
+                provider: self,
+                prefix,
+            });
+        }
+        let len = trimmed.chars().count();
+        if len < rules.min_len {
+            warnings.push(TokenWarning::TooShort {
+                provider: self,
+                len,
+                min: rules.min_len,
+            });
+        }
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANTHROPIC_TOKEN: &str =
+        "sk-ant-REDACTED";
+    const OPENROUTER_TOKEN: &str =
+        "sk-or-v1-0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn detects_provider_from_host() {
+        assert_eq!(
+            Provider::detect("https://api.anthropic.com"),
+            Provider::Anthropic
+        );
+        assert_eq!(
+            Provider::detect("https://openrouter.ai/api"),
+            Provider::OpenRouter
+        );
+        assert_eq!(
+            Provider::detect("https://bedrock.corp.example/v1"),
+            Provider::BedrockProxy
+        );
+        // The path doesn't count: a gateway proxying the official API
+        assert_eq!(
+            Provider::detect("https://relay.example/api.anthropic.com"),
+            Provider::Custom
+        );
+        assert_eq!(Provider::detect("not a url"), Provider::Custom);
+    }
+
+    #[test]
+    fn anthropic_tokens_need_prefix_and_length() {
+        assert!(
+            Provider::Anthropic
+                .validate_token(ANTHROPIC_TOKEN)
+                .is_empty()
+        );
+        // OAuth tokens share the sk-ant- prefix
+        assert!(
+            Provider::Anthropic
+                .validate_token("sk-ant-REDACTED")
+                .is_empty()
+        );
+        assert_eq!(
+            Provider::Anthropic.validate_token(OPENROUTER_TOKEN),
+            [TokenWarning::MissingPrefix {
+                provider: Provider::Anthropic,
+                prefix: "sk-ant-"
+            }]
+        );
+        assert_eq!(
+            Provider::Anthropic.validate_token("sk-ant-api03-abc"),
+            [TokenWarning::TooShort {
+                provider: Provider::Anthropic,
+                len: 16,
+                min: 40
+            }]
+        );
+    }
+
+    #[test]
+    fn openrouter_tokens_need_prefix_and_length() {
+        assert!(
+            Provider::OpenRouter
+                .validate_token(OPENROUTER_TOKEN)
+                .is_empty()
+        );
+        let warnings = Provider::OpenRouter.validate_token(ANTHROPIC_TOKEN);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].to_string().contains("'sk-or-'"));
+        assert_eq!(Provider::OpenRouter.validate_token("sk-or-v1-abc").len(), 1);
+    }
+
+    #[test]
+    fn bedrock_proxy_and_custom_tokens_only_need_length() {
+        assert!(
+            Provider::BedrockProxy
+                .validate_token("AKIAIOSFODNN7EXAMPLEKEY")
+                .is_empty()
+        );
+        assert_eq!(Provider::BedrockProxy.validate_token("AKIA123").len(), 1);
+        // Any format goes for a gateway, including Anthropic's
+        assert!(Provider::Custom.validate_token(ANTHROPIC_TOKEN).is_empty());
+        assert!(
+            Provider::Custom
+                .validate_token("gw-0123456789abcdef")
+                .is_empty()
+        );
+        assert!(matches!(
+            Provider::Custom.validate_token("gw-123")[..],
+            [TokenWarning::TooShort { len: 6, .. }]
+        ));
+    }
+
+    #[test]
+    fn pasted_whitespace_is_flagged_for_every_provider() {
+        for provider in [
+            Provider::Anthropic,
+            Provider::OpenRouter,
+            Provider::BedrockProxy,
+            Provider::Custom,
+        ] {
+            let good = match provider {
+                Provider::Anthropic => ANTHROPIC_TOKEN,
+                Provider::OpenRouter => OPENROUTER_TOKEN,
+                _ => "gw-0123456789abcdefghij",
+            };
+            assert!(provider.validate_token(good).is_empty());
+            assert_eq!(
+                provider.validate_token(&format!("{good}\n")),
+                [TokenWarning::LineBreak]
+            );
+            assert_eq!(
+                provider.validate_token(&format!(" {good}")),
+                [TokenWarning::Whitespace]
+            );
+        }
+    }
+
+    #[test]
+    fn parses_names_case_insensitively() {
+        assert_eq!(Provider::parse("OpenRouter").unwrap(), Provider::OpenRouter);
+        assert_eq!(
+            Provider::parse("bedrock-proxy").unwrap(),
+            Provider::BedrockProxy
+        );
+        assert!(Provider::parse("azure").is_err());
+        for provider in [Provider::Anthropic, Provider::Custom] {
+            assert_eq!(Provider::parse(provider.as_str()).unwrap(), provider);
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! Opt-in quota probes: after a switch, one request to a provider endpoint
+//! whose header or JSON body reports usage, shown as a one-line summary.
+//!
+//! Probing never gets in the way of a launch: it has a fixed budget and any
+//! failure just means no summary.
+
+use std::time::Duration;
+
+use crate::config::types::{Configuration, QuotaProbe};
+use crate::utils::http::{self, CapturedResponse};
+
+/// Time budget of a quota probe, connection included
+pub const QUOTA_PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Read `selector` from `response`: a JSON pointer into the body when it
+/// starts with `/`, a header name otherwise
+fn read_selector(selector: &str, response: &CapturedResponse) -> Option<String> {
+    if selector.starts_with('/') {
+        let body: serde_json::Value = serde_json::from_str(&response.body).ok()?;
+        match body.pointer(selector)? {
+            serde_json::Value::String(value) => Some(value.clone()),
+            serde_json::Value::Number(value) => Some(value.to_string()),
+            _ => None,
+        }
+    } else {
+        response.header(selector).map(str::to_string)
+    }
+}
+
+/// One-line quota summary of a captured probe response
+///
+/// `None` when the response doesn't carry the configured readings, or the
+/// limit isn't a positive number.
+pub fn quota_summary(probe: &QuotaProbe, response: &CapturedResponse) -> Option<String> {
+    let used = read_selector(&probe.used, response)?;
+    let Some(limit_selector) = &probe.limit else {
+        return Some(format!("quota: {used} used"));
+    };
+    let limit = read_selector(limit_selector, response)?;
+    let (used_value, limit_value) = (
+        used.trim().parse::<f64>().ok()?,
+        limit.trim().parse::<f64>().ok()?,
+    );
+    if limit_value <= 0.0 {
+        return None;
+    }
+    Some(format!(
+        "quota: {:.0}% used ({used} / {limit})",
+        used_value / limit_value * 100.0
+    ))
+}
+
+/// Run the quota probe of `config`, if it has one
+///
+/// Sends the configuration's credentials like Claude Code would. Errors,
+/// timeouts and unreadable responses all yield `None`.
+pub fn probe_quota(config: &Configuration) -> Option<String> {
+    let probe = config.quota_probe.as_ref()?;
+    let auth = match config.auth_env_pair() {
+        ("ANTHROPIC_API_KEY", key) => ("x-api-key", key.to_string()),
+        (_, token) => ("authorization", format!("Bearer {token}")),
+    };
+    let response = http::get(
+        &probe.url,
+        &[(auth.0, auth.1.as_str())],
+        QUOTA_PROBE_TIMEOUT,
+    )
+    .ok()?;
+    quota_summary(probe, &response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(headers: &[http::RequestHeader], body: &str) -> CapturedResponse {
+        CapturedResponse {
+            status: 200,
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            body: body.to_string(),
+        }
+    }
+
+    fn probe(used: &str, limit: Option<&str>) -> QuotaProbe {
+        QuotaProbe {
+            url: "https://relay.example.com/quota".to_string(),
+            used: used.to_string(),
+            limit: limit.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn header_readings_give_a_percentage() {
+        let captured = response(&[("x-quota-used", "3700"), ("x-quota-limit", "10000")], "");
+        assert_eq!(
+            quota_summary(&probe("X-Quota-Used", Some("x-quota-limit")), &captured),
+            Some("quota: 37% used (3700 / 10000)".to_string())
+        );
+    }
+
+    #[test]
+    fn json_pointer_readings() {
+        let captured = response(&[], r#"{"usage": {"today": 12.5, "cap": "50"}}"#);
+        assert_eq!(
+            quota_summary(&probe("/usage/today", Some("/usage/cap")), &captured),
+            Some("quota: 25% used (12.5 / 50)".to_string())
+        );
+        assert_eq!(
+            quota_summary(&probe("/usage/today", None), &captured),
+            Some("quota: 12.5 used".to_string())
+        );
+    }
+
+    #[test]
+    fn missing_or_unusable_readings_give_nothing() {
+        let captured = response(&[("x-quota-used", "many")], "not json");
+        assert_eq!(quota_summary(&probe("x-other", None), &captured), None);
+        assert_eq!(quota_summary(&probe("/usage", None), &captured), None);
+        assert_eq!(
+            quota_summary(&probe("x-quota-used", Some("x-quota-used")), &captured),
+            None
+        );
+        let zero_limit = response(&[("used", "1"), ("limit", "0")], "");
+        assert_eq!(
+            quota_summary(&probe("used", Some("limit")), &zero_limit),
+            None
+        );
+    }
+}
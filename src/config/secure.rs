@@ -0,0 +1,353 @@
+//! Tokens kept in the OS keyring instead of the storage file.
+//!
+//! With secure mode on (`cc-switch secure enable`) every save moves the
+//! credential of each configuration into a [`SecretStore`] under its alias
+//! and writes `keyring:<alias>` in its place; launches read it back through
+//! [`Configuration::resolve_token`]. The OS keyring is only available in
+//! builds with the `keyring` feature; [`MemoryStore`] stands in for it in
+//! tests.
+
+use anyhow::Result;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::config::types::{ConfigStorage, Configuration};
+
+/// Keyring service the tokens are stored under
+pub const KEYRING_SERVICE: &str = "cc-switch";
+
+/// Prefix of a credential field kept in the keyring: `keyring:<key>`
+pub const KEYRING_PREFIX: &str = "keyring:";
+
+/// The credential field value standing for keyring entry `key`
+pub fn sentinel(key: &str) -> String {
+    format!("{KEYRING_PREFIX}{key}")
+}
+
+/// Where secured tokens are kept, by key
+pub trait SecretStore {
+    /// The secret stored under `key`
+    ///
+    /// # Errors
+    /// Returns error if there is no entry for `key` or the store fails
+    fn get(&self, key: &str) -> Result<String>;
+
+    /// Store `secret` under `key`, replacing any previous one
+    ///
+    /// # Errors
+    /// Returns error if the store fails
+    fn set(&self, key: &str, secret: &str) -> Result<()>;
+
+    /// Remove the entry for `key`, if there is one
+    ///
+    /// # Errors
+    /// Returns error if the store fails
+    fn delete(&self, key: &str) -> Result<()>;
+}
+
+/// The OS keyring (Keychain, Credential Manager or Secret Service)
+pub struct SystemStore;
+
+#[cfg(feature = "keyring")]
+impl SystemStore {
+    fn entry(key: &str) -> Result<keyring::Entry> {
+        keyring::Entry::new(KEYRING_SERVICE, key)
+            .map_err(|e| anyhow::anyhow!("Failed to open keyring entry '{key}': {e}"))
+    }
+}
+
+#[cfg(feature = "keyring")]
+impl SecretStore for SystemStore {
+    fn get(&self, key: &str) -> Result<String> {
+        match Self::entry(key)?.get_password() {
+            Ok(secret) => Ok(secret),
+            Err(keyring::Error::NoEntry) => {
+                anyhow::bail!("No keyring entry '{key}' (service '{KEYRING_SERVICE}')")
+            }
+            Err(e) => anyhow::bail!("Failed to read keyring entry '{key}': {e}"),
+        }
+    }
+
+    fn set(&self, key: &str, secret: &str) -> Result<()> {
+        Self::entry(key)?
+            .set_password(secret)
+            .map_err(|e| anyhow::anyhow!("Failed to write keyring entry '{key}': {e}"))
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        match Self::entry(key)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => anyhow::bail!("Failed to delete keyring entry '{key}': {e}"),
+        }
+    }
+}
+
+#[cfg(not(feature = "keyring"))]
+impl SystemStore {
+    fn unavailable() -> anyhow::Error {
+        anyhow::anyhow!(
+            "This cc-switch was built without keyring support; reinstall it with `--features keyring`"
+        )
+    }
+}
+
+#[cfg(not(feature = "keyring"))]
+impl SecretStore for SystemStore {
+    fn get(&self, _key: &str) -> Result<String> {
+        Err(Self::unavailable())
+    }
+
+    fn set(&self, _key: &str, _secret: &str) -> Result<()> {
+        Err(Self::unavailable())
+    }
+
+    fn delete(&self, _key: &str) -> Result<()> {
+        Err(Self::unavailable())
+    }
+}
+
+/// Secrets by key
+pub type SecretMap = BTreeMap<String, String>;
+
+/// A store held in memory, for tests
+#[derive(Default)]
+pub struct MemoryStore {
+    pub secrets: RefCell<SecretMap>,
+}
+
+impl SecretStore for MemoryStore {
+    fn get(&self, key: &str) -> Result<String> {
+        self.secrets
+            .borrow()
+            .get(key)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("No keyring entry '{key}'"))
+    }
+
+    fn set(&self, key: &str, secret: &str) -> Result<()> {
+        self.secrets
+            .borrow_mut()
+            .insert(key.to_string(), secret.to_string());
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        self.secrets.borrow_mut().remove(key);
+        Ok(())
+    }
+}
+
+/// The credential field of `config` that is sent: `api_key` when set,
+/// else `token`
+fn credential_mut(config: &mut Configuration) -> &mut String {
+    match &mut config.api_key {
+        Some(key) => key,
+        None => &mut config.token,
+    }
+}
+
+/// `storage` as written in secure mode: every credential in `store` under
+/// its alias, with `keyring:<alias>` in its place
+///
+/// Configurations whose token comes from `token_command`, or that have no
+/// credential, are left alone. A credential stored under another key (a
+/// renamed or cloned configuration) is copied to its own alias, so each
+/// entry belongs to one configuration.
+///
+/// # Errors
+/// Returns error if the store can't be read or written
+pub fn seal<'a>(
+    storage: &'a ConfigStorage,
+    store: &dyn SecretStore,
+) -> Result<Cow<'a, ConfigStorage>> {
+    let mut sealed = Cow::Borrowed(storage);
+    for (alias, config) in &storage.configurations {
+        if config.token_command.is_some() {
+            continue;
+        }
+        let secret = match config.keyring_key() {
+            Some(key) if key == alias => continue,
+            Some(_) => config
+                .resolve_token_in(store)?
+                .auth_env_pair()
+                .1
+                .to_string(),
+            None if config.auth_env_pair().1.is_empty() => continue,
+            None => config.auth_env_pair().1.to_string(),
+        };
+        store.set(alias, &secret)?;
+        if let Some(config) = sealed.to_mut().configurations.get_mut(alias) {
+            *credential_mut(config) = sentinel(alias);
+        }
+    }
+    Ok(sealed)
+}
+
+/// Put the credential of every configuration kept in `store` back into
+/// `storage`, returning the aliases changed
+///
+/// Nothing is changed unless every entry can be read.
+///
+/// # Errors
+/// Returns error naming the configuration whose entry is missing
+pub fn unseal(storage: &mut ConfigStorage, store: &dyn SecretStore) -> Result<Vec<String>> {
+    let mut secrets = Vec::new();
+    for (alias, config) in &storage.configurations {
+        if config.keyring_key().is_some() {
+            let resolved = config.resolve_token_in(store)?;
+            secrets.push((alias.clone(), resolved.auth_env_pair().1.to_string()));
+        }
+    }
+    for (alias, secret) in &secrets {
+        if let Some(config) = storage.configurations.get_mut(alias) {
+            *credential_mut(config) = secret.clone();
+        }
+    }
+    Ok(secrets.into_iter().map(|(alias, _)| alias).collect())
+}
+
+/// Keyring keys referenced by the configurations of `storage`
+pub fn keyring_keys(storage: &ConfigStorage) -> BTreeSet<&str> {
+    storage
+        .configurations
+        .values()
+        .filter_map(Configuration::keyring_key)
+        .collect()
+}
+
+/// Keys `previous` referenced that `current` no longer does, whose entries
+/// can be deleted once `current` is saved
+pub fn stale_keys(previous: &ConfigStorage, current: &ConfigStorage) -> Vec<String> {
+    let kept = keyring_keys(current);
+    keyring_keys(previous)
+        .into_iter()
+        .filter(|key| !kept.contains(key))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn storage() -> ConfigStorage {
+        let mut storage = ConfigStorage::default();
+        for (alias, token, api_key) in [
+            ("work", "sk-ant-work", None),
+            ("relay", "", Some("sk-relay-key")),
+            ("empty", "", None),
+        ] {
+            storage.add_configuration(Configuration {
+                alias_name: alias.to_string(),
+                token: token.to_string(),
+                api_key: api_key.map(str::to_string),
+                url: "https://api.example.com".to_string(),
+                ..Default::default()
+            });
+        }
+        storage.add_configuration(Configuration {
+            alias_name: "vault".to_string(),
+            token_command: Some("pass show anthropic".to_string()),
+            url: "https://api.example.com".to_string(),
+            ..Default::default()
+        });
+        storage
+    }
+
+    #[test]
+    fn seal_moves_each_credential_under_its_alias() {
+        let store = MemoryStore::default();
+        let sealed = seal(&storage(), &store).unwrap().into_owned();
+
+        assert_eq!(sealed.configurations["work"].token, "keyring:work");
+        assert_eq!(
+            sealed.configurations["relay"].api_key.as_deref(),
+            Some("keyring:relay")
+        );
+        assert_eq!(sealed.configurations["empty"].token, "");
+        assert_eq!(sealed.configurations["vault"].token, "");
+        assert_eq!(
+            *store.secrets.borrow(),
+            BTreeMap::from([
+                ("relay".to_string(), "sk-relay-key".to_string()),
+                ("work".to_string(), "sk-ant-work".to_string()),
+            ])
+        );
+
+        // Sealing again touches nothing
+        assert!(matches!(seal(&sealed, &store).unwrap(), Cow::Borrowed(_)));
+        assert_eq!(
+            keyring_keys(&sealed).into_iter().collect::<Vec<_>>(),
+            ["relay", "work"]
+        );
+    }
+
+    #[test]
+    fn seal_copies_a_renamed_configuration_to_its_own_entry() {
+        let store = MemoryStore::default();
+        let previous = seal(&storage(), &store).unwrap().into_owned();
+        let mut renamed = previous.clone();
+        renamed.rename_configuration("work", "job").unwrap();
+        assert_eq!(renamed.configurations["job"].token, "keyring:work");
+
+        let sealed = seal(&renamed, &store).unwrap().into_owned();
+        assert_eq!(sealed.configurations["job"].token, "keyring:job");
+        assert_eq!(store.get("job").unwrap(), "sk-ant-work");
+        assert_eq!(stale_keys(&previous, &sealed), ["work"]);
+    }
+
+    #[test]
+    fn unseal_restores_plaintext_or_changes_nothing() {
+        let store = MemoryStore::default();
+        let mut sealed = seal(&storage(), &store).unwrap().into_owned();
+
+        let mut broken = sealed.clone();
+        store.delete("work").unwrap();
+        let error = unseal(&mut broken, &store).err().unwrap().to_string();
+        assert!(error.contains("'work'"), "{error}");
+        assert_eq!(
+            broken.configurations["relay"].api_key.as_deref(),
+            Some("keyring:relay")
+        );
+
+        store.set("work", "sk-ant-work").unwrap();
+        assert_eq!(unseal(&mut sealed, &store).unwrap(), ["relay", "work"]);
+        assert_eq!(sealed.configurations["work"].token, "sk-ant-work");
+        assert_eq!(
+            sealed.configurations["relay"].api_key.as_deref(),
+            Some("sk-relay-key")
+        );
+        assert!(keyring_keys(&sealed).is_empty());
+    }
+
+    #[test]
+    fn resolve_token_reads_the_entry_or_names_the_missing_one() {
+        let store = MemoryStore::default();
+        let config = Configuration {
+            alias_name: "work".to_string(),
+            token: sentinel("work"),
+            url: "https://api.example.com".to_string(),
+            ..Default::default()
+        };
+        let error = config.resolve_token_in(&store).err().unwrap().to_string();
+        assert!(
+            error.contains("'work'") && error.contains("cc-switch edit work --token"),
+            "{error}"
+        );
+
+        store.set("work", "sk-ant-work").unwrap();
+        assert_eq!(
+            config.resolve_token_in(&store).unwrap().token,
+            "sk-ant-work"
+        );
+        let plain = Configuration {
+            token: "sk-plain".to_string(),
+            ..config
+        };
+        assert!(matches!(
+            plain.resolve_token_in(&store).unwrap(),
+            Cow::Borrowed(_)
+        ));
+    }
+}
@@ -1,27 +1,78 @@
+use anyhow::Context;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
 use std::collections::BTreeMap;
 
+use crate::config::models::{ModelNotice, check_model};
+use crate::config::provider::{Provider, TokenWarning};
+use crate::config::validate_base_url;
+
 /// Type alias for configuration map
-type ConfigMap = BTreeMap<String, Configuration>;
+pub type ConfigMap = BTreeMap<String, Configuration>;
 /// Type alias for environment variable map
 type EnvMap = BTreeMap<String, String>;
 /// Type alias for JSON value map
 type JsonMap = BTreeMap<String, serde_json::Value>;
 /// Type alias for Codex configuration map
 type CodexConfigMap = BTreeMap<String, crate::codex::CodexConfiguration>;
+/// Launch time of each configuration, keyed by alias
+pub type LastUsedMap = BTreeMap<String, chrono::DateTime<chrono::Utc>>;
+/// Number of launches of each configuration, keyed by alias
+pub type UseCountMap = BTreeMap<String, u64>;
 
 /// Storage mode for how configuration should be written to settings.json
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default, clap::ValueEnum)]
 pub enum StorageMode {
-    /// Write to env field with uppercase environment variable names (default)
+    /// Pass the configuration to Claude as environment variables (default)
     #[serde(rename = "env")]
     #[default]
     Env,
-    /// Write to root level with camelCase field names
+    /// Persist the configuration in the env block of settings.json
     #[serde(rename = "config")]
+    #[value(alias = "settings")]
     Config,
 }
 
+impl StorageMode {
+    /// Name used on the command line and in storage
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StorageMode::Env => "env",
+            StorageMode::Config => "config",
+        }
+    }
+}
+
+/// Environment variables a configuration can set
+///
+/// Shared by the launcher (unset for the official configuration) and
+/// [`ClaudeSettings::remove_anthropic_env`] so both clear the same keys.
+pub const ANTHROPIC_ENV_VARS: [&str; 19] = [
+    "ANTHROPIC_AUTH_TOKEN",
+    "ANTHROPIC_API_KEY",
+    "ANTHROPIC_BASE_URL",
+    "ANTHROPIC_MODEL",
+    "ANTHROPIC_SMALL_FAST_MODEL",
+    "ANTHROPIC_MAX_THINKING_TOKENS",
+    "API_TIMEOUT_MS",
+    "CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC",
+    "CLAUDE_CODE_EXPERIMENTAL_AGENT_TEAMS",
+    "CLAUDE_CODE_DISABLE_1M_CONTEXT",
+    "CLAUDE_CODE_SUBAGENT_MODEL",
+    "CLAUDE_CODE_DISABLE_NONSTREAMING_FALLBACK",
+    "CLAUDE_CODE_EFFORT_LEVEL",
+    "DISABLE_PROMPT_CACHING",
+    "CLAUDE_CODE_DISABLE_EXPERIMENTAL_BETAS",
+    "DISABLE_AUTOUPDATER",
+    "ANTHROPIC_DEFAULT_SONNET_MODEL",
+    "ANTHROPIC_DEFAULT_OPUS_MODEL",
+    "ANTHROPIC_DEFAULT_HAIKU_MODEL",
+];
+
+/// Group of configurations without tags, in the grouped menu and
+/// `list --group-by-tag`
+pub const UNTAGGED_GROUP: &str = "untagged";
+
 /// Represents a Claude API configuration
 ///
 /// Contains the components needed to configure Claude API access:
@@ -39,6 +90,10 @@ pub struct Configuration {
     /// ANTHROPIC_API_KEY value (alternative to AUTH_TOKEN, mutually exclusive)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub api_key: Option<String>,
+    /// Shell command printing the ANTHROPIC_AUTH_TOKEN value, run at launch
+    /// instead of storing the token (e.g. `pass show claude/work`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_command: Option<String>,
     /// ANTHROPIC_BASE_URL value (API endpoint URL)
     pub url: String,
     /// ANTHROPIC_MODEL value (custom model name)
@@ -89,36 +144,473 @@ pub struct Configuration {
     /// Disable auto-updater flag
     #[serde(skip_serializing_if = "Option::is_none")]
     pub disable_autoupdater: Option<u32>,
+    /// Free-form labels (e.g. `prod`) used by filters and launch confirmation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Label color shown next to the alias (one of [`LABEL_COLORS`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Other Anthropic-compatible CLIs launchable with this configuration's
+    /// environment via `cc-switch use <alias> --with <profile>`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub launch_profiles: BTreeMap<String, LaunchProfile>,
+    /// Alternative base URLs selected with `cc-switch use <alias>@<variant>`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub url_variants: BTreeMap<String, String>,
+    /// Endpoint read after a switch to show quota usage (opt-in)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quota_probe: Option<QuotaProbe>,
+    /// Additional environment variables exported at launch (e.g.
+    /// `ANTHROPIC_CUSTOM_HEADERS`); never one of [`ANTHROPIC_ENV_VARS`]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra_env: BTreeMap<String, String>,
+    /// Provider whose token rules apply; detected from the URL when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<Provider>,
+    /// Arguments always passed to Claude for this configuration (e.g.
+    /// `--continue`), after `--dangerously-skip-permissions`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub launch_args: Vec<String>,
+}
+
+/// Where to read a provider's quota usage from
+///
+/// `used` and `limit` name a response header, or a JSON pointer into the
+/// response body when they start with `/` (e.g. `/usage/today`).
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct QuotaProbe {
+    /// URL requested (GET) with the configuration's credentials
+    pub url: String,
+    /// Usage reading
+    pub used: String,
+    /// Limit reading; with it usage is shown as a percentage
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<String>,
+}
+
+/// A binary launched instead of `claude` with a configuration's environment
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct LaunchProfile {
+    /// Program to run (looked up on PATH)
+    pub command: String,
+    /// Arguments always passed before any passthrough arguments
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
 }
 
 impl Configuration {
+    /// Field names accepted by [`Configuration::field_value`] and
+    /// [`Configuration::set_field`]
+    ///
+    /// `token` covers both ANTHROPIC_AUTH_TOKEN and ANTHROPIC_API_KEY.
+    pub const FIELD_NAMES: &'static [&'static str] = &[
+        "token",
+        "token_command",
+        "url",
+        "model",
+        "small_fast_model",
+        "max_thinking_tokens",
+        "api_timeout_ms",
+        "claude_code_disable_nonessential_traffic",
+        "anthropic_default_sonnet_model",
+        "anthropic_default_opus_model",
+        "anthropic_default_haiku_model",
+        "claude_code_subagent_model",
+        "claude_code_disable_nonstreaming_fallback",
+        "claude_code_effort_level",
+        "disable_prompt_caching",
+        "claude_code_disable_experimental_betas",
+        "disable_autoupdater",
+        "tags",
+        "color",
+        "extra_env",
+        "provider",
+        "launch_args",
+    ];
+
+    /// Get the value of a field by name, `None` when unset
+    ///
+    /// `token` returns the active credential (API key if set, else auth token).
+    pub fn field_value(&self, field: &str) -> Option<String> {
+        let value = match field {
+            "token" => Some(self.auth_env_pair().1.to_string()),
+            "token_command" => self.token_command.clone(),
+            "url" => Some(self.url.clone()),
+            "model" => self.model.clone(),
+            "small_fast_model" => self.small_fast_model.clone(),
+            "max_thinking_tokens" => self.max_thinking_tokens.map(|v| v.to_string()),
+            "api_timeout_ms" => self.api_timeout_ms.map(|v| v.to_string()),
+            "claude_code_disable_nonessential_traffic" => self
+                .claude_code_disable_nonessential_traffic
+                .map(|v| v.to_string()),
+            "anthropic_default_sonnet_model" => self.anthropic_default_sonnet_model.clone(),
+            "anthropic_default_opus_model" => self.anthropic_default_opus_model.clone(),
+            "anthropic_default_haiku_model" => self.anthropic_default_haiku_model.clone(),
+            "claude_code_subagent_model" => self.claude_code_subagent_model.clone(),
+            "claude_code_disable_nonstreaming_fallback" => self
+                .claude_code_disable_nonstreaming_fallback
+                .map(|v| v.to_string()),
+            "claude_code_effort_level" => self.claude_code_effort_level.clone(),
+            "disable_prompt_caching" => self.disable_prompt_caching.map(|v| v.to_string()),
+            "claude_code_disable_experimental_betas" => self
+                .claude_code_disable_experimental_betas
+                .map(|v| v.to_string()),
+            "disable_autoupdater" => self.disable_autoupdater.map(|v| v.to_string()),
+            "tags" => Some(self.tags.join(",")),
+            "color" => self.color.clone(),
+            "extra_env" => Some(
+                self.extra_env
+                    .iter()
+                    .map(|(key, value)| format!("{key}={value}"))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            "provider" => self.provider.map(|provider| provider.as_str().to_string()),
+            "launch_args" => Some(format_launch_args(&self.launch_args)),
+            _ => None,
+        };
+        value.filter(|v| !v.is_empty())
+    }
+
+    /// Set a field by name from its string form
+    ///
+    /// An empty value clears optional fields. `token` updates the active
+    /// credential (API key if set, else auth token) and drops any
+    /// `token_command`; setting `token_command` drops the stored token and
+    /// API key instead, so only one of them is ever kept. `tags` takes a
+    /// comma-separated list. `extra_env` takes one `KEY=VALUE` assignment,
+    /// `KEY=` removing that variable and an empty value removing them all.
+    /// An empty `provider` goes back to detecting it from the URL, and
+    /// `url` is checked and normalised by [`validate_base_url`].
+    /// `launch_args` takes a whole command line, split by
+    /// [`parse_launch_args`].
+    ///
+    /// # Errors
+    /// Returns error for unknown fields, an empty token, an invalid url, clearing a
+    /// `token_command` that is the only credential, a non-numeric
+    /// value for a numeric field, an unknown provider or an unterminated
+    /// quote in `launch_args`
+    pub fn set_field(&mut self, field: &str, value: &str) -> anyhow::Result<()> {
+        fn text(value: &str) -> Option<String> {
+            (!value.is_empty()).then(|| value.to_string())
+        }
+        fn number(field: &str, value: &str) -> anyhow::Result<Option<u32>> {
+            if value.is_empty() {
+                return Ok(None);
+            }
+            value
+                .parse::<u32>()
+                .map(Some)
+                .map_err(|_| anyhow::anyhow!("'{}' must be a number, got '{}'", field, value))
+        }
+
+        match field {
+            "token" | "url" if value.is_empty() => {
+                anyhow::bail!("'{}' cannot be empty", field)
+            }
+            "token" => {
+                match self.api_key {
+                    Some(_) => self.api_key = Some(value.to_string()),
+                    None => self.token = value.to_string(),
+                }
+                self.token_command = None;
+            }
+            "token_command" if value.is_empty() => {
+                if self.token_command.is_some() && self.auth_env_pair().1.is_empty() {
+                    anyhow::bail!(
+                        "'token_command' is the only credential; set a token instead of clearing it"
+                    );
+                }
+                self.token_command = None;
+            }
+            "token_command" => {
+                self.token_command = Some(value.to_string());
+                self.token.clear();
+                self.api_key = None;
+            }
+            "url" => self.url = validate_base_url(value)?,
+            "model" => self.model = text(value),
+            "small_fast_model" => self.small_fast_model = text(value),
+            "max_thinking_tokens" => self.max_thinking_tokens = number(field, value)?,
+            "api_timeout_ms" => self.api_timeout_ms = number(field, value)?,
+            "claude_code_disable_nonessential_traffic" => {
+                self.claude_code_disable_nonessential_traffic = number(field, value)?
+            }
+            "anthropic_default_sonnet_model" => self.anthropic_default_sonnet_model = text(value),
+            "anthropic_default_opus_model" => self.anthropic_default_opus_model = text(value),
+            "anthropic_default_haiku_model" => self.anthropic_default_haiku_model = text(value),
+            "claude_code_subagent_model" => self.claude_code_subagent_model = text(value),
+            "claude_code_disable_nonstreaming_fallback" => {
+                self.claude_code_disable_nonstreaming_fallback = number(field, value)?
+            }
+            "claude_code_effort_level" => self.claude_code_effort_level = text(value),
+            "disable_prompt_caching" => self.disable_prompt_caching = number(field, value)?,
+            "claude_code_disable_experimental_betas" => {
+                self.claude_code_disable_experimental_betas = number(field, value)?
+            }
+            "disable_autoupdater" => self.disable_autoupdater = number(field, value)?,
+            "tags" => self.tags = parse_tags(value),
+            "color" if value.is_empty() => self.color = None,
+            "color" => self.color = Some(parse_label_color(value)?),
+            "provider" if value.is_empty() => self.provider = None,
+            "provider" => self.provider = Some(Provider::parse(value)?),
+            "launch_args" => self.launch_args = parse_launch_args(value)?,
+            "extra_env" if value.is_empty() => self.extra_env.clear(),
+            "extra_env" => {
+                let Some((key, value)) = value.split_once('=') else {
+                    anyhow::bail!("Expected KEY=VALUE for extra_env, got '{}'", value);
+                };
+                validate_extra_env_key(key)?;
+                if value.is_empty() {
+                    self.extra_env.remove(key);
+                } else {
+                    self.extra_env.insert(key.to_string(), value.to_string());
+                }
+            }
+            _ => anyhow::bail!(
+                "Unknown field '{}'. Valid fields: {}",
+                field,
+                Self::FIELD_NAMES.join(", ")
+            ),
+        }
+        Ok(())
+    }
+
+    /// Arguments for a Claude launch: the stored `launch_args`, then `extra`
+    /// from the command line
+    pub fn claude_args(&self, extra: &[String]) -> Vec<String> {
+        self.launch_args.iter().chain(extra).cloned().collect()
+    }
+
+    /// The stored provider, or the one detected from the URL
+    pub fn provider(&self) -> Provider {
+        self.provider.unwrap_or_else(|| Provider::detect(&self.url))
+    }
+
+    /// Problems with the active credential under [`Configuration::provider`]
+    ///
+    /// A token read from `token_command` or the keyring is only known at
+    /// launch, so it is not checked.
+    pub fn token_warnings(&self) -> Vec<TokenWarning> {
+        if self.token_command.is_some() || self.keyring_key().is_some() {
+            return Vec::new();
+        }
+        self.provider().validate_token(self.auth_env_pair().1)
+    }
+
+    /// Whether a credential is stored or comes from `token_command`
+    pub fn has_credential(&self) -> bool {
+        self.token_command.is_some() || !self.auth_env_pair().1.trim().is_empty()
+    }
+
+    /// This configuration with the token printed by `token_command`, or
+    /// read from the keyring, or itself when neither is used
+    ///
+    /// The command runs through the shell with the terminal's stdin and
+    /// stderr, so password managers can prompt; its trimmed stdout becomes
+    /// the auth token.
+    ///
+    /// # Errors
+    /// Returns error if the command can't be started, fails or prints nothing
+    pub fn with_resolved_token(&self) -> anyhow::Result<Cow<'_, Configuration>> {
+        let Some(command) = &self.token_command else {
+            return self.resolve_token();
+        };
+        let mut shell = if cfg!(windows) {
+            let mut shell = std::process::Command::new("cmd");
+            shell.arg("/C");
+            shell
+        } else {
+            let mut shell = std::process::Command::new("sh");
+            shell.arg("-c");
+            shell
+        };
+        let output = shell
+            .arg(command)
+            .stderr(std::process::Stdio::inherit())
+            .output()
+            .with_context(|| format!("Failed to run token command '{command}'"))?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "Token command '{}' for '{}' exited with {}",
+                command,
+                self.alias_name,
+                output.status
+            );
+        }
+        let token = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if token.is_empty() {
+            anyhow::bail!(
+                "Token command '{}' for '{}' printed no token",
+                command,
+                self.alias_name
+            );
+        }
+        Ok(Cow::Owned(Configuration {
+            token,
+            api_key: None,
+            token_command: None,
+            ..self.clone()
+        }))
+    }
+
+    /// Keyring entry holding the credential, when it is stored as
+    /// `keyring:<key>` (see `cc-switch secure`)
+    pub fn keyring_key(&self) -> Option<&str> {
+        self.auth_env_pair()
+            .1
+            .strip_prefix(crate::config::secure::KEYRING_PREFIX)
+    }
+
+    /// This configuration with its credential read from the OS keyring, or
+    /// itself when the credential is stored in the file
+    ///
+    /// # Errors
+    /// Returns error if the keyring entry is missing or can't be read
+    pub fn resolve_token(&self) -> anyhow::Result<Cow<'_, Configuration>> {
+        self.resolve_token_in(&crate::config::secure::SystemStore)
+    }
+
+    /// [`Configuration::resolve_token`] reading from `store`
+    ///
+    /// # Errors
+    /// Returns error if the entry is missing or can't be read
+    pub fn resolve_token_in(
+        &self,
+        store: &dyn crate::config::secure::SecretStore,
+    ) -> anyhow::Result<Cow<'_, Configuration>> {
+        let Some(key) = self.keyring_key() else {
+            return Ok(Cow::Borrowed(self));
+        };
+        let secret = store.get(key).with_context(|| {
+            format!(
+                "The token of '{}' is kept in the keyring but can't be read; set it again with `cc-switch edit {} --token <TOKEN>`",
+                self.alias_name, self.alias_name
+            )
+        })?;
+        let mut config = self.clone();
+        match &mut config.api_key {
+            Some(api_key) => *api_key = secret,
+            None => config.token = secret,
+        }
+        Ok(Cow::Owned(config))
+    }
+
+    /// `(field, model)` of each model field that is set
+    pub fn models(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        [
+            ("model", &self.model),
+            ("small_fast_model", &self.small_fast_model),
+            (
+                "anthropic_default_sonnet_model",
+                &self.anthropic_default_sonnet_model,
+            ),
+            (
+                "anthropic_default_opus_model",
+                &self.anthropic_default_opus_model,
+            ),
+            (
+                "anthropic_default_haiku_model",
+                &self.anthropic_default_haiku_model,
+            ),
+            (
+                "claude_code_subagent_model",
+                &self.claude_code_subagent_model,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(field, model)| Some((field, model.as_deref()?)))
+    }
+
+    /// Model names that aren't known Anthropic models, as warnings under the
+    /// Anthropic provider and notes under any other
+    pub fn model_notices(&self) -> Vec<ModelNotice> {
+        let provider = self.provider();
+        self.models()
+            .filter_map(|(field, model)| check_model(field, model, provider))
+            .collect()
+    }
+
+    /// The label color as a terminal color, if one is set
+    pub fn label_color(&self) -> Option<colored::Color> {
+        self.color.as_deref()?.replace('_', " ").parse().ok()
+    }
+
+    /// Check whether the configuration carries `tag` (case-insensitive)
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Group the configuration is listed under: its first tag, or
+    /// [`UNTAGGED_GROUP`]
+    pub fn group(&self) -> &str {
+        self.tags.first().map_or(UNTAGGED_GROUP, String::as_str)
+    }
+
+    /// Look up a launch profile by name
+    ///
+    /// # Errors
+    /// Returns error listing the defined profiles if `name` is not one of them
+    pub fn launch_profile(&self, name: &str) -> anyhow::Result<&LaunchProfile> {
+        if let Some(profile) = self.launch_profiles.get(name) {
+            return Ok(profile);
+        }
+        if self.launch_profiles.is_empty() {
+            anyhow::bail!(
+                "Launch profile '{}' not found: configuration '{}' defines no launch profiles",
+                name,
+                self.alias_name
+            );
+        }
+        anyhow::bail!(
+            "Launch profile '{}' not found for configuration '{}'. Defined profiles: {}",
+            name,
+            self.alias_name,
+            self.launch_profile_names().join(", ")
+        )
+    }
+
+    /// Names of the defined launch profiles, in order
+    pub fn launch_profile_names(&self) -> Vec<&str> {
+        self.launch_profiles.keys().map(String::as_str).collect()
+    }
+
+    /// Copy of this configuration with the base URL of a named variant
+    ///
+    /// # Errors
+    /// Returns error listing the available variants if `name` is not one of them
+    pub fn with_url_variant(&self, name: &str) -> anyhow::Result<Configuration> {
+        let Some(url) = self.url_variants.get(name) else {
+            if self.url_variants.is_empty() {
+                return Err(crate::exit_code::not_found(format!(
+                    "Unknown URL variant '{}': configuration '{}' defines no URL variants",
+                    name, self.alias_name
+                )));
+            }
+            return Err(crate::exit_code::not_found(format!(
+                "Unknown URL variant '{}' for configuration '{}'. Available variants: {}",
+                name,
+                self.alias_name,
+                self.url_variants
+                    .keys()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+        };
+        Ok(Configuration {
+            url: url.clone(),
+            ..self.clone()
+        })
+    }
+
     /// Get all environment variable names that this configuration can set
     ///
     /// Returns a vector of all UPPERCASE environment variable names
     /// that can be set by this configuration, used for conflict detection
     /// in config mode.
     pub fn get_env_field_names() -> Vec<&'static str> {
-        vec![
-            "ANTHROPIC_AUTH_TOKEN",
-            "ANTHROPIC_API_KEY",
-            "ANTHROPIC_BASE_URL",
-            "ANTHROPIC_MODEL",
-            "ANTHROPIC_SMALL_FAST_MODEL",
-            "ANTHROPIC_MAX_THINKING_TOKENS",
-            "API_TIMEOUT_MS",
-            "CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC",
-            "CLAUDE_CODE_EXPERIMENTAL_AGENT_TEAMS",
-            "CLAUDE_CODE_DISABLE_1M_CONTEXT",
-            "CLAUDE_CODE_SUBAGENT_MODEL",
-            "CLAUDE_CODE_DISABLE_NONSTREAMING_FALLBACK",
-            "CLAUDE_CODE_EFFORT_LEVEL",
-            "DISABLE_PROMPT_CACHING",
-            "CLAUDE_CODE_DISABLE_EXPERIMENTAL_BETAS",
-            "DISABLE_AUTOUPDATER",
-            "ANTHROPIC_DEFAULT_SONNET_MODEL",
-            "ANTHROPIC_DEFAULT_OPUS_MODEL",
-            "ANTHROPIC_DEFAULT_HAIKU_MODEL",
-        ]
+        ANTHROPIC_ENV_VARS.to_vec()
     }
 
     /// Get the authentication credential and its environment variable name.
@@ -168,6 +660,353 @@ impl Configuration {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_set_field_round_trips_through_field_value() {
+        let mut config = Configuration {
+            alias_name: "work".to_string(),
+            token: "sk-old".to_string(),
+            url: "https://old.example".to_string(),
+            ..Default::default()
+        };
+
+        config.set_field("url", "https://new.example").unwrap();
+        config.set_field("max_thinking_tokens", "8192").unwrap();
+        config.set_field("model", "claude-sonnet-4").unwrap();
+        assert_eq!(
+            config.field_value("url").as_deref(),
+            Some("https://new.example")
+        );
+        assert_eq!(config.max_thinking_tokens, Some(8192));
+        assert_eq!(
+            config.field_value("model").as_deref(),
+            Some("claude-sonnet-4")
+        );
+
+        config.set_field("model", "").unwrap();
+        assert_eq!(config.model, None);
+    }
+
+    #[test]
+    fn test_token_command_and_stored_token_replace_each_other() {
+        let mut config = Configuration {
+            alias_name: "work".to_string(),
+            api_key: Some("sk-key".to_string()),
+            url: "https://api.anthropic.com".to_string(),
+            ..Default::default()
+        };
+
+        config
+            .set_field("token_command", "pass show claude/work")
+            .unwrap();
+        assert_eq!(config.api_key, None);
+        assert_eq!(config.token, "");
+        assert!(config.has_credential());
+        assert!(
+            config.token_warnings().is_empty(),
+            "the token isn't known yet"
+        );
+        assert!(config.set_field("token_command", "").is_err());
+
+        config.set_field("token", "sk-ant-plain").unwrap();
+        assert_eq!(config.token_command, None);
+        config.set_field("token_command", "").unwrap();
+        assert_eq!(config.token, "sk-ant-plain");
+    }
+
+    #[test]
+    fn test_resolved_token_comes_from_the_command_output() {
+        let mut config = Configuration {
+            alias_name: "work".to_string(),
+            token: "sk-stored".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(config.with_resolved_token().unwrap().token, "sk-stored");
+
+        config
+            .set_field("token_command", "echo sk-from-command")
+            .unwrap();
+        let resolved = config.with_resolved_token().unwrap();
+        assert_eq!(resolved.token, "sk-from-command");
+        assert_eq!(resolved.token_command, None);
+
+        config.token_command = Some("exit 3".to_string());
+        let error = config.with_resolved_token().err().unwrap().to_string();
+        assert!(error.contains("'exit 3' for 'work' exited"), "got: {error}");
+
+        #[cfg(unix)]
+        {
+            config.token_command = Some("true".to_string());
+            let error = config.with_resolved_token().err().unwrap().to_string();
+            assert!(error.contains("printed no token"), "got: {error}");
+        }
+    }
+
+    #[test]
+    fn test_stored_provider_overrides_detection() {
+        let mut config = Configuration {
+            alias_name: "relay".to_string(),
+            token: "sk-ant-REDACTED".to_string(),
+            url: "https://relay.example".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(config.provider(), Provider::Custom);
+        assert!(config.token_warnings().is_empty());
+
+        config.set_field("provider", "OpenRouter").unwrap();
+        assert_eq!(
+            config.field_value("provider").as_deref(),
+            Some("openrouter")
+        );
+        assert!(matches!(
+            config.token_warnings()[..],
+            [TokenWarning::MissingPrefix {
+                prefix: "sk-or-",
+                ..
+            }]
+        ));
+        assert!(config.set_field("provider", "azure").is_err());
+
+        config.set_field("provider", "").unwrap();
+        assert_eq!(config.provider, None);
+    }
+
+    #[test]
+    fn test_model_notices_soften_off_the_official_api() {
+        let mut config = Configuration {
+            alias_name: "work".to_string(),
+            url: "https://api.anthropic.com".to_string(),
+            model: Some("claude-3.5-sonnet".to_string()),
+            small_fast_model: Some("claude-3-5-haiku-latest".to_string()),
+            claude_code_subagent_model: Some("glm-4.6".to_string()),
+            ..Default::default()
+        };
+
+        let notices = config.model_notices();
+        let fields: Vec<&str> = notices.iter().map(|notice| notice.field).collect();
+        assert_eq!(fields, ["model", "claude_code_subagent_model"]);
+        assert!(
+            notices
+                .iter()
+                .all(|notice| notice.level == crate::config::ModelNoticeLevel::Warning)
+        );
+
+        config.url = "https://relay.example".to_string();
+        assert!(
+            config
+                .model_notices()
+                .iter()
+                .all(|notice| notice.level == crate::config::ModelNoticeLevel::Note)
+        );
+    }
+
+    #[test]
+    fn test_set_field_rejects_invalid_values() {
+        let mut config = Configuration {
+            token: "sk-old".to_string(),
+            url: "https://old.example".to_string(),
+            ..Default::default()
+        };
+        assert!(config.set_field("url", "").is_err());
+        assert!(config.set_field("url", "htps://bad url").is_err());
+        assert!(config.set_field("url", "ftp://x").is_err());
+        assert!(config.set_field("api_timeout_ms", "soon").is_err());
+        assert!(config.set_field("colour", "red").is_err());
+        assert_eq!(config.url, "https://old.example");
+
+        config.set_field("url", " https://new.example/ ").unwrap();
+        assert_eq!(config.url, "https://new.example");
+    }
+
+    #[test]
+    fn test_set_field_tags_parses_list() {
+        let mut config = Configuration::default();
+        config.set_field("tags", " prod, eu ,,PROD").unwrap();
+        assert_eq!(config.tags, vec!["prod", "eu"]);
+        assert!(config.has_tag("Prod"));
+        assert_eq!(config.field_value("tags").as_deref(), Some("prod,eu"));
+
+        config.set_field("tags", "").unwrap();
+        assert!(config.tags.is_empty());
+        assert_eq!(config.field_value("tags"), None);
+    }
+
+    #[test]
+    fn test_set_field_extra_env_assigns_removes_and_rejects_managed_keys() {
+        let mut config = Configuration::default();
+        config
+            .set_field("extra_env", "ANTHROPIC_CUSTOM_HEADERS=X-Org: a=b")
+            .unwrap();
+        config
+            .set_field("extra_env", "HTTPS_PROXY=http://proxy:3128")
+            .unwrap();
+        assert_eq!(
+            config
+                .extra_env
+                .get("ANTHROPIC_CUSTOM_HEADERS")
+                .map(String::as_str),
+            Some("X-Org: a=b")
+        );
+        assert_eq!(
+            config.field_value("extra_env").as_deref(),
+            Some("ANTHROPIC_CUSTOM_HEADERS=X-Org: a=b, HTTPS_PROXY=http://proxy:3128")
+        );
+
+        config.set_field("extra_env", "HTTPS_PROXY=").unwrap();
+        assert!(!config.extra_env.contains_key("HTTPS_PROXY"));
+
+        for bad in ["NO_EQUALS", "=value", "MY VAR=1", "ANTHROPIC_MODEL=opus"] {
+            assert!(config.set_field("extra_env", bad).is_err(), "{bad}");
+        }
+        let err = validate_extra_env_key("ANTHROPIC_BASE_URL").unwrap_err();
+        assert!(err.to_string().contains("dedicated option"));
+
+        config.set_field("extra_env", "").unwrap();
+        assert!(config.extra_env.is_empty());
+        assert_eq!(config.field_value("extra_env"), None);
+    }
+
+    #[test]
+    fn test_parse_launch_args_quoting() {
+        let parse = |value: &str| parse_launch_args(value).unwrap();
+        assert_eq!(parse("  --continue  "), ["--continue"]);
+        assert_eq!(parse(""), Vec::<String>::new());
+        assert_eq!(
+            parse("--add-dir '/src/mono repo' --model\topus"),
+            ["--add-dir", "/src/mono repo", "--model", "opus"]
+        );
+        assert_eq!(
+            parse(r#"--append-system-prompt "say \"hi\" to $USER" it\'s"#),
+            ["--append-system-prompt", "say \"hi\" to $USER", "it's"]
+        );
+        // Quotes join with adjacent text; empty quotes are an empty argument
+        assert_eq!(parse(r#"--x='a b'"c" '' ~/dir"#), ["--x=a bc", "", "~/dir"]);
+        // Inside double quotes other backslashes are kept
+        assert_eq!(parse(r#""C:\Users\me""#), [r"C:\Users\me"]);
+        assert_eq!(parse(r"a\ b"), ["a b"]);
+
+        for bad in ["'open", "\"open", "trailing\\", "\"escaped\\\""] {
+            assert!(parse_launch_args(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_launch_args_field_round_trips() {
+        let mut config = Configuration::default();
+        assert_eq!(config.field_value("launch_args"), None);
+        config
+            .set_field("launch_args", "--add-dir '/src/mono repo' --continue")
+            .unwrap();
+        assert_eq!(
+            config.launch_args,
+            ["--add-dir", "/src/mono repo", "--continue"]
+        );
+        let shown = config.field_value("launch_args").unwrap();
+        assert_eq!(shown, "--add-dir '/src/mono repo' --continue");
+        assert_eq!(parse_launch_args(&shown).unwrap(), config.launch_args);
+
+        assert!(config.set_field("launch_args", "'unclosed").is_err());
+        assert_eq!(config.launch_args.len(), 3, "kept on error");
+        config.set_field("launch_args", "").unwrap();
+        assert!(config.launch_args.is_empty());
+    }
+
+    #[test]
+    fn test_conventional_env_keys() {
+        assert!(is_conventional_env_key("HTTPS_PROXY"));
+        assert!(is_conventional_env_key("NODE_OPTIONS_2"));
+        assert!(!is_conventional_env_key("https_proxy"));
+        assert!(!is_conventional_env_key("2FA_CODE"));
+        assert!(!is_conventional_env_key("MY-VAR"));
+    }
+
+    #[test]
+    fn test_label_color_validation() {
+        assert_eq!(parse_label_color("Red").unwrap(), "red");
+        assert_eq!(parse_label_color("bright-blue").unwrap(), "bright_blue");
+        let err = parse_label_color("mauve").unwrap_err().to_string();
+        assert!(err.contains("Valid colors: black, red"), "got: {err}");
+
+        let mut config = Configuration::default();
+        assert!(config.label_color().is_none());
+        config.set_field("color", "bright green").unwrap();
+        assert_eq!(config.color.as_deref(), Some("bright_green"));
+        assert_eq!(config.label_color(), Some(colored::Color::BrightGreen));
+        assert!(config.set_field("color", "mauve").is_err());
+        config.set_field("color", "").unwrap();
+        assert_eq!(config.color, None);
+    }
+
+    #[test]
+    fn test_grouped_menu_preference_serialization() {
+        let mut preferences = Preferences::default();
+        assert!(preferences.is_empty());
+        assert_eq!(serde_json::to_string(&preferences).unwrap(), "{}");
+
+        preferences.grouped_menu = true;
+        assert!(!preferences.is_empty());
+        let json = serde_json::to_string(&preferences).unwrap();
+        assert_eq!(json, r#"{"grouped_menu":true}"#);
+        assert_eq!(
+            serde_json::from_str::<Preferences>(&json).unwrap(),
+            preferences
+        );
+    }
+
+    #[test]
+    fn test_launch_profile_lookup() {
+        let mut config = Configuration {
+            alias_name: "relay".to_string(),
+            ..Default::default()
+        };
+        let err = config.launch_profile("aider").unwrap_err().to_string();
+        assert!(err.contains("defines no launch profiles"), "got: {err}");
+
+        config.launch_profiles.insert(
+            "aider".to_string(),
+            LaunchProfile {
+                command: "aider".to_string(),
+                args: vec!["--model".to_string(), "claude".to_string()],
+            },
+        );
+        config.launch_profiles.insert(
+            "goose".to_string(),
+            LaunchProfile {
+                command: "goose".to_string(),
+                args: Vec::new(),
+            },
+        );
+        assert_eq!(config.launch_profile("aider").unwrap().command, "aider");
+        let err = config.launch_profile("cursor").unwrap_err().to_string();
+        assert!(err.contains("Defined profiles: aider, goose"), "got: {err}");
+    }
+
+    #[test]
+    fn test_launch_profiles_round_trip_json() {
+        let json = r#"{"alias_name":"relay","token":"sk","url":"https://r",
+            "launch_profiles":{"aider":{"command":"aider","args":["--model","claude"]}}}"#;
+        let config: Configuration = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            config.launch_profiles["aider"].args,
+            vec!["--model", "claude"]
+        );
+
+        let plain = Configuration::default();
+        let serialized = serde_json::to_string(&plain).unwrap();
+        assert!(!serialized.contains("launch_profiles"));
+    }
+
+    #[test]
+    fn test_set_field_token_updates_active_credential() {
+        let mut config = Configuration {
+            token: String::new(),
+            api_key: Some("sk-key-old".to_string()),
+            ..Default::default()
+        };
+        config.set_field("token", "sk-key-new").unwrap();
+        assert_eq!(config.api_key.as_deref(), Some("sk-key-new"));
+        assert_eq!(config.token, "");
+    }
+
     #[test]
     fn test_get_env_field_names() {
         let fields = Configuration::get_env_field_names();
@@ -340,6 +1179,7 @@ mod tests {
             alias_name: "test".to_string(),
             token: "new_token".to_string(),
             api_key: None,
+            token_command: None,
             url: "https://api.new.com".to_string(),
             model: Some("new_model".to_string()),
             small_fast_model: Some("new_fast_model".to_string()),
@@ -357,6 +1197,14 @@ mod tests {
             disable_prompt_caching: None,
             claude_code_disable_experimental_betas: None,
             disable_autoupdater: None,
+            tags: Vec::new(),
+            launch_profiles: Default::default(),
+            url_variants: Default::default(),
+            quota_probe: None,
+            extra_env: Default::default(),
+            color: None,
+            provider: None,
+            launch_args: Vec::new(),
         };
 
         // Switch to new configuration
@@ -424,6 +1272,7 @@ mod tests {
             alias_name: "test".to_string(),
             token: "new_token".to_string(),
             api_key: None,
+            token_command: None,
             url: "https://api.new.com".to_string(),
             model: Some("new_model".to_string()),
             small_fast_model: Some("new_fast_model".to_string()),
@@ -441,6 +1290,14 @@ mod tests {
             disable_prompt_caching: None,
             claude_code_disable_experimental_betas: None,
             disable_autoupdater: None,
+            tags: Vec::new(),
+            launch_profiles: Default::default(),
+            url_variants: Default::default(),
+            quota_probe: None,
+            extra_env: Default::default(),
+            color: None,
+            provider: None,
+            launch_args: Vec::new(),
         };
 
         // Switch to new configuration
@@ -496,18 +1353,135 @@ mod tests {
 ///
 /// Handles persistence and retrieval of multiple API configurations
 /// stored in `~/.cc_auto_switch/configurations.json`
-#[derive(Serialize, Deserialize, Default)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ConfigStorage {
+    /// Format version of the file (see [`crate::config::migrations`])
+    #[serde(default = "crate::config::migrations::unversioned")]
+    pub version: u32,
     /// Map of alias names to configuration objects
     pub configurations: ConfigMap,
-    /// Custom directory for Claude settings (optional)
-    pub claude_settings_dir: Option<String>,
+    /// Where Claude's own files are, when not the defaults
+    #[serde(default, skip_serializing_if = "ClaudePaths::is_empty")]
+    pub claude: ClaudePaths,
+    /// Claude binary to launch instead of `claude` from PATH (`claude-path`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claude_binary: Option<String>,
     /// Default storage mode for writing configurations (None = use env mode)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub default_storage_mode: Option<StorageMode>,
     /// Codex (OpenAI) configurations, stored separately from Claude configurations
     #[serde(skip_serializing_if = "Option::is_none")]
     pub codex_configurations: Option<CodexConfigMap>,
+    /// User preferences set via `cc-switch config set`
+    #[serde(default, skip_serializing_if = "Preferences::is_empty")]
+    pub preferences: Preferences,
+    /// Configuration launched by bare `cc-switch` instead of the menu
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_alias: Option<String>,
+    /// Configuration most recently launched by name (`cc-switch use -`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used: Option<String>,
+    /// When each configuration was last launched
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub last_used_at: LastUsedMap,
+    /// How many times each configuration was launched
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub use_counts: UseCountMap,
+}
+
+impl Default for ConfigStorage {
+    fn default() -> Self {
+        ConfigStorage {
+            version: crate::config::migrations::CURRENT_VERSION,
+            configurations: ConfigMap::default(),
+            claude: ClaudePaths::default(),
+            claude_binary: None,
+            default_storage_mode: None,
+            codex_configurations: None,
+            preferences: Preferences::default(),
+            default_alias: None,
+            last_used: None,
+            last_used_at: LastUsedMap::default(),
+            use_counts: UseCountMap::default(),
+        }
+    }
+}
+
+/// Locations of Claude's own files, when not the defaults
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct ClaudePaths {
+    /// Custom directory for Claude settings (`config set settings-dir`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings_dir: Option<String>,
+}
+
+impl ClaudePaths {
+    /// Whether every location is the default (used to skip serialization)
+    pub fn is_empty(&self) -> bool {
+        self.settings_dir.is_none()
+    }
+}
+
+/// User preferences persisted alongside the configurations
+///
+/// Every field is optional so that older storage files without a
+/// `preferences` section keep loading unchanged.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct Preferences {
+    /// Configuration field names whose values are displayed as `[hidden]`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hidden_fields: Vec<String>,
+    /// Tags that require confirmation before launching a configuration
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub confirm_tags: Vec<String>,
+    /// Show the interactive menu grouped by tag instead of as a flat list
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub grouped_menu: bool,
+    /// Launch straight away, printing only `→ alias`, when the first key
+    /// pressed in the menu is a digit, R or Q
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub fast_select: bool,
+    /// Show the picked configuration and wait for Enter before launching
+    /// from the interactive menu
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub confirm_before_launch: bool,
+    /// Don't read storage and settings files back after saving them
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub skip_write_verification: bool,
+    /// Launch Claude without `--dangerously-skip-permissions`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub no_skip_permissions: bool,
+    /// Keep tokens in the OS keyring, writing `keyring:<alias>` to the
+    /// file instead (`cc-switch secure enable`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub secure_tokens: bool,
+}
+
+impl Preferences {
+    /// Whether no preference has been set (used to skip serialization)
+    pub fn is_empty(&self) -> bool {
+        self.hidden_fields.is_empty()
+            && self.confirm_tags.is_empty()
+            && !self.grouped_menu
+            && !self.fast_select
+            && !self.confirm_before_launch
+            && !self.skip_write_verification
+            && !self.no_skip_permissions
+            && !self.secure_tokens
+    }
+
+    /// Whether launches pass `--dangerously-skip-permissions` to Claude
+    pub fn skip_permissions(&self) -> bool {
+        !self.no_skip_permissions
+    }
+
+    /// The first confirm tag carried by `config`, if any
+    pub fn confirm_tag_for<'a>(&'a self, config: &Configuration) -> Option<&'a str> {
+        self.confirm_tags
+            .iter()
+            .find(|tag| config.has_tag(tag))
+            .map(String::as_str)
+    }
 }
 
 /// Claude settings manager for API configuration
@@ -521,6 +1495,9 @@ pub struct ClaudeSettings {
     pub env: EnvMap,
     /// Other settings to preserve when modifying API configuration
     pub other: JsonMap,
+    /// Don't read settings.json back after saving it; copied from the
+    /// storage preference by callers that hold the storage
+    pub skip_write_verification: bool,
 }
 
 impl Serialize for ClaudeSettings {
@@ -565,16 +1542,194 @@ impl<'de> Deserialize<'de> for ClaudeSettings {
         Ok(ClaudeSettings {
             env: helper.env,
             other: helper.other,
+            skip_write_verification: false,
         })
     }
 }
 
+/// Split a comma-separated tag list, trimming and dropping empty or repeated tags
+pub fn parse_tags(value: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in value.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+/// Split a command line into launch arguments, the way a POSIX shell would
+///
+/// Words are separated by whitespace. Single quotes keep everything up to
+/// the closing quote; in double quotes a backslash only escapes `"`, `\`,
+/// `$` and `` ` ``; elsewhere it escapes any character. Nothing is expanded:
+/// `~` and `$HOME` reach Claude as written.
+///
+/// # Errors
+/// Returns error for an unterminated quote or a trailing backslash
+pub fn parse_launch_args(value: &str) -> anyhow::Result<Vec<String>> {
+    let mut args = Vec::new();
+    // `None` between words; quotes start a word even when it stays empty
+    let mut word: Option<String> = None;
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => args.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => anyhow::bail!("Unterminated ' in launch arguments"),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => anyhow::bail!("Unterminated \" in launch arguments"),
+                        },
+                        Some(c) => word.push(c),
+                        None => anyhow::bail!("Unterminated \" in launch arguments"),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some(c) => word.get_or_insert_with(String::new).push(c),
+                None => anyhow::bail!("Launch arguments end with a lone backslash"),
+            },
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    args.extend(word);
+    Ok(args)
+}
+
+/// Join launch arguments into a command line [`parse_launch_args`] reads back
+pub fn format_launch_args(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| crate::interactive::shell_quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Label colors a configuration can use, from the `colored` palette
+pub const LABEL_COLORS: [&str; 16] = [
+    "black",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "magenta",
+    "cyan",
+    "white",
+    "bright_black",
+    "bright_red",
+    "bright_green",
+    "bright_yellow",
+    "bright_blue",
+    "bright_magenta",
+    "bright_cyan",
+    "bright_white",
+];
+
+/// Validate a label color name, returning its canonical form
+///
+/// Case is ignored and `bright-red` / `bright red` are accepted for
+/// `bright_red`.
+///
+/// # Errors
+/// Returns error listing [`LABEL_COLORS`] if the name is not one of them
+pub fn parse_label_color(value: &str) -> anyhow::Result<String> {
+    let name = value.trim().to_lowercase().replace(['-', ' '], "_");
+    if LABEL_COLORS.contains(&name.as_str()) {
+        return Ok(name);
+    }
+    Err(crate::exit_code::invalid(format!(
+        "Invalid color '{}'. Valid colors: {}",
+        value,
+        LABEL_COLORS.join(", ")
+    )))
+}
+
+/// Check a variable name for [`Configuration::extra_env`]
+///
+/// Variables cc-switch sets itself are rejected rather than overridden, so
+/// a configuration's dedicated fields always mean what they say.
+///
+/// # Errors
+/// Returns error if `key` is empty, contains `=` or whitespace, or is one of
+/// the variables cc-switch manages
+pub fn validate_extra_env_key(key: &str) -> anyhow::Result<()> {
+    if key.is_empty() {
+        return Err(crate::exit_code::invalid(
+            "Environment variable name cannot be empty",
+        ));
+    }
+    if key.contains('=') || key.chars().any(char::is_whitespace) {
+        return Err(crate::exit_code::invalid(format!(
+            "Environment variable name '{}' cannot contain '=' or whitespace",
+            key
+        )));
+    }
+    if ANTHROPIC_ENV_VARS.contains(&key) || key == "CC_SWITCH_CURRENT_ALIAS" {
+        return Err(crate::exit_code::invalid(format!(
+            "'{}' is set by cc-switch itself; use its dedicated option instead",
+            key
+        )));
+    }
+    Ok(())
+}
+
+/// Whether `key` follows the UPPER_SNAKE_CASE convention of environment
+/// variable names
+pub fn is_conventional_env_key(key: &str) -> bool {
+    !key.starts_with(|c: char| c.is_ascii_digit())
+        && key
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// A configuration read by `add --from-file` or `import`
+pub struct ParsedConfigFile {
+    /// The parsed fields; `alias_name` is left empty
+    pub config: Configuration,
+    /// Keys of the file that could not be kept, in file order
+    pub ignored_keys: Vec<String>,
+}
+
+impl ParsedConfigFile {
+    /// Warn on stderr about the keys the import dropped, if any
+    pub fn warn_on_ignored_keys(&self) {
+        if !self.ignored_keys.is_empty() {
+            eprintln!(
+                "Warning: ignored keys from env section: {}",
+                self.ignored_keys.join(", ")
+            );
+        }
+    }
+}
+
 /// Parameters for adding a new configuration
 #[allow(dead_code)]
+#[derive(Default)]
 pub struct AddCommandParams {
-    pub alias_name: String,
+    /// Alias to store the configuration under; resolved and validated inside
+    /// `handle_add_command` after any `--from-file` import has been parsed
+    pub alias_name: Option<String>,
     pub token: Option<String>,
     pub api_key: Option<String>,
+    /// `--token-command`, run at launch instead of storing a token
+    pub token_command: Option<String>,
     pub url: Option<String>,
     pub model: Option<String>,
     pub small_fast_model: Option<String>,
@@ -590,9 +1745,30 @@ pub struct AddCommandParams {
     pub disable_prompt_caching: Option<u32>,
     pub claude_code_disable_experimental_betas: Option<u32>,
     pub disable_autoupdater: Option<u32>,
+    /// Only set by `--from-file`; there are no flags for these
+    pub claude_code_experimental_agent_teams: Option<u32>,
+    pub claude_code_disable_1m_context: Option<u32>,
     pub force: bool,
     pub interactive: bool,
     pub token_arg: Option<String>,
     pub url_arg: Option<String>,
     pub from_file: Option<String>,
+    /// Parse `from_file` as strict JSON, without comments or trailing commas
+    pub strict_json: bool,
+    /// Store the URL as given, skipping `validate_base_url`
+    pub no_validate: bool,
+    pub tags: Vec<String>,
+    pub color: Option<String>,
+    /// `--provider`, overriding the one detected from the URL
+    pub provider: Option<Provider>,
+    /// `--preset`, filling the URL and models the flags leave unset
+    pub preset: Option<String>,
+    /// Additional variables from `--env KEY=VALUE` or the imported file
+    pub extra_env: BTreeMap<String, String>,
+    /// Arguments from `--launch-arg` or the imported file
+    pub launch_args: Vec<String>,
+    /// Print the stored configuration as JSON instead of messages
+    pub output: crate::cli::output::OutputFormat,
+    /// Show the full token in the JSON output
+    pub reveal: bool,
 }
@@ -56,7 +56,10 @@ mod tests {
     use crate::config::types::{ConfigStorage, Configuration};
     use std::collections::BTreeMap;
 
-    fn make_storage(entries: &[(&str, &str)]) -> ConfigStorage {
+    /// Alias and URL of a stored configuration
+    type AliasUrl<'a> = (&'a str, &'a str);
+
+    fn make_storage(entries: &[AliasUrl]) -> ConfigStorage {
         let mut configurations = BTreeMap::new();
         for (alias, url) in entries {
             configurations.insert(
@@ -71,9 +74,7 @@ mod tests {
         }
         ConfigStorage {
             configurations,
-            claude_settings_dir: None,
-            default_storage_mode: None,
-            codex_configurations: None,
+            ..Default::default()
         }
     }
 
@@ -339,6 +339,7 @@ mod tests {
                     alias_name: alias,
                     token: "sk-test".to_string(),
                     api_key: None,
+                    token_command: None,
                     url: url.to_string(),
                     model: None,
                     small_fast_model: None,
@@ -356,14 +357,20 @@ mod tests {
                     disable_prompt_caching: None,
                     claude_code_disable_experimental_betas: None,
                     disable_autoupdater: None,
+                    tags: Vec::new(),
+                    launch_profiles: Default::default(),
+                    url_variants: Default::default(),
+                    quota_probe: None,
+                    extra_env: Default::default(),
+                    color: None,
+                    provider: None,
+                    launch_args: Vec::new(),
                 },
             );
         }
         ConfigStorage {
             configurations,
-            claude_settings_dir: None,
-            default_storage_mode: None,
-            codex_configurations: None,
+            ..Default::default()
         }
     }
 
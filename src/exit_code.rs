@@ -0,0 +1,128 @@
+//! Process exit codes
+//!
+//! Scripts can tell failures apart by `$?` alone:
+//!
+//! | Code | Meaning |
+//! |------|---------|
+//! | 0 | Success |
+//! | 1 | Any other error |
+//! | 2 | A named configuration does not exist |
+//! | 3 | The target already exists (use `--force`) |
+//! | 4 | Invalid input: a bad argument, alias name, URL or color |
+//!
+//! Handlers return a [`CodedError`] (built with [`not_found`], [`conflict`]
+//! or [`invalid`]) anywhere in the `anyhow` chain; [`ExitCode::of`] finds it
+//! and `main` exits with its code. Untyped errors exit with 1.
+
+use std::fmt;
+
+/// Exit status of a `cc-switch` invocation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitCode {
+    Success,
+    Failure,
+    NotFound,
+    Conflict,
+    Invalid,
+}
+
+impl ExitCode {
+    /// Numeric status passed to the OS
+    pub const fn code(self) -> u8 {
+        match self {
+            Self::Success => 0,
+            Self::Failure => 1,
+            Self::NotFound => 2,
+            Self::Conflict => 3,
+            Self::Invalid => 4,
+        }
+    }
+
+    /// Exit code for an error: the first [`CodedError`] in its chain, or
+    /// [`ExitCode::Failure`]
+    pub fn of(err: &anyhow::Error) -> Self {
+        err.chain()
+            .find_map(|cause| cause.downcast_ref::<CodedError>())
+            .map_or(Self::Failure, |coded| coded.code)
+    }
+}
+
+impl From<ExitCode> for std::process::ExitCode {
+    fn from(code: ExitCode) -> Self {
+        Self::from(code.code())
+    }
+}
+
+/// An error message tagged with the exit code it should produce
+#[derive(Debug)]
+pub struct CodedError {
+    code: ExitCode,
+    message: String,
+}
+
+impl CodedError {
+    pub fn new(code: ExitCode, message: impl fmt::Display) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn code(&self) -> ExitCode {
+        self.code
+    }
+}
+
+impl fmt::Display for CodedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CodedError {}
+
+/// Error for a configuration (or other named item) that does not exist
+pub fn not_found(message: impl fmt::Display) -> anyhow::Error {
+    CodedError::new(ExitCode::NotFound, message).into()
+}
+
+/// Error for a name that is already taken
+pub fn conflict(message: impl fmt::Display) -> anyhow::Error {
+    CodedError::new(ExitCode::Conflict, message).into()
+}
+
+/// Error for input that failed validation
+pub fn invalid(message: impl fmt::Display) -> anyhow::Error {
+    CodedError::new(ExitCode::Invalid, message).into()
+}
+
+/// Shorthand for the common "Configuration 'x' not found" error
+pub fn config_not_found(alias: &str) -> anyhow::Error {
+    not_found(format!("Configuration '{alias}' not found"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn code_survives_added_context() {
+        let err = Err::<(), _>(config_not_found("work"))
+            .context("while exporting")
+            .unwrap_err();
+        assert_eq!(ExitCode::of(&err), ExitCode::NotFound);
+        assert_eq!(
+            format!("{err:#}"),
+            "while exporting: Configuration 'work' not found"
+        );
+    }
+
+    #[test]
+    fn untyped_errors_are_generic_failures() {
+        let err = anyhow::anyhow!("disk full");
+        assert_eq!(ExitCode::of(&err), ExitCode::Failure);
+        assert_eq!(ExitCode::of(&invalid("bad")).code(), 4);
+        assert_eq!(ExitCode::of(&conflict("taken")).code(), 3);
+    }
+}
@@ -1,10 +1,10 @@
 use crate::cli::display_utils::{
-    TextAlignment, get_terminal_width, pad_text_to_width, text_display_width,
+    BorderDrawing, TextAlignment, get_terminal_width, pad_text_to_width, text_display_width,
 };
 use crate::codex::{CodexConfiguration, write_auth_json};
 use crate::config::types::ConfigStorage;
 use crate::interactive::interactive::{
-    BorderDrawing, EditModeError, cleanup_terminal, edit_optional_string_field, edit_string_field,
+    EditModeError, cleanup_terminal, edit_optional_string_field, edit_string_field,
 };
 use crate::platform::resolve_npm_cli;
 use anyhow::Result;
@@ -158,6 +158,92 @@ fn format_codex_config_details(config: &CodexConfiguration, indent: &str) -> Vec
     lines
 }
 
+/// Page and selection of the Codex menu, kept consistent with each other
+///
+/// Indices 0 .. configs_len-1 are configurations and `configs_len` is Exit,
+/// which is shown on the last page. `current_page` always holds the
+/// selected row, so digits pick from the page that is on screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CodexMenuState {
+    configs_len: usize,
+    page_size: usize,
+    current_page: usize,
+    selected_index: usize,
+}
+
+impl CodexMenuState {
+    fn new(configs_len: usize, page_size: usize, selected_index: usize) -> Self {
+        let mut state = Self {
+            configs_len,
+            page_size,
+            current_page: 0,
+            selected_index: selected_index.min(configs_len),
+        };
+        state.follow_selection();
+        state
+    }
+
+    fn total_pages(&self) -> usize {
+        self.configs_len.div_ceil(self.page_size).max(1)
+    }
+
+    /// Page showing row `index`; Exit belongs to the last page
+    fn page_of(&self, index: usize) -> usize {
+        (index / self.page_size).min(self.total_pages() - 1)
+    }
+
+    /// Configurations shown on the current page
+    fn page_range(&self) -> std::ops::Range<usize> {
+        let start = self.current_page * self.page_size;
+        start..(start + self.page_size).min(self.configs_len)
+    }
+
+    fn follow_selection(&mut self) {
+        self.current_page = self.page_of(self.selected_index);
+    }
+
+    fn move_up(&mut self) {
+        self.selected_index = self.selected_index.saturating_sub(1);
+        self.follow_selection();
+    }
+
+    fn move_down(&mut self) {
+        self.selected_index = (self.selected_index + 1).min(self.configs_len);
+        self.follow_selection();
+    }
+
+    /// Next page, selecting its first row
+    fn page_next(&mut self) {
+        if self.current_page + 1 < self.total_pages() {
+            self.current_page += 1;
+            self.selected_index = self.current_page * self.page_size;
+        }
+    }
+
+    /// Previous page, selecting its first row
+    fn page_prev(&mut self) {
+        if self.current_page > 0 {
+            self.current_page -= 1;
+            self.selected_index = self.current_page * self.page_size;
+        }
+    }
+
+    /// Global index of the row numbered `digit` on the current page
+    fn select_digit(&self, digit: usize) -> Option<usize> {
+        let range = self.page_range();
+        let index = range.start + digit.checked_sub(1)?;
+        range.contains(&index).then_some(index)
+    }
+
+    /// Adjust to a reloaded list of `configs_len` configurations, keeping
+    /// the selection in range
+    fn set_configs_len(&mut self, configs_len: usize) {
+        self.configs_len = configs_len;
+        self.selected_index = self.selected_index.min(configs_len);
+        self.follow_selection();
+    }
+}
+
 /// Handle full interactive menu with arrow key navigation and pagination for Codex
 #[allow(clippy::ptr_arg)]
 fn handle_codex_full_interactive_menu(
@@ -178,17 +264,15 @@ fn handle_codex_full_interactive_menu(
 
     const PAGE_SIZE: usize = 9;
 
-    let total_pages = if configs.len() <= PAGE_SIZE {
-        1
-    } else {
-        configs.len().div_ceil(PAGE_SIZE)
-    };
-    let mut current_page = 0;
+    let mut state = CodexMenuState::new(configs.len(), PAGE_SIZE, *selected_index);
 
     loop {
-        let start_idx = current_page * PAGE_SIZE;
-        let end_idx = std::cmp::min(start_idx + PAGE_SIZE, configs.len());
-        let page_configs = &configs[start_idx..end_idx];
+        *selected_index = state.selected_index;
+        let current_page = state.current_page;
+        let total_pages = state.total_pages();
+        let page_range = state.page_range();
+        let start_idx = page_range.start;
+        let page_configs = &configs[page_range];
 
         // Clear screen and redraw
         execute!(stdout, terminal::Clear(terminal::ClearType::All))?;
@@ -318,34 +402,13 @@ fn handle_codex_full_interactive_menu(
                 kind: KeyEventKind::Press,
                 ..
             }) => match code {
-                KeyCode::Up | KeyCode::Char('k') | KeyCode::Char('K') => {
-                    *selected_index = selected_index.saturating_sub(1);
-                }
-                KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('J')
-                    if *selected_index < configs.len() =>
-                {
-                    *selected_index += 1;
-                }
-                KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('J') => {}
-                KeyCode::PageDown | KeyCode::Char('n') | KeyCode::Char('N')
-                    if total_pages > 1 && current_page < total_pages - 1 =>
-                {
-                    current_page += 1;
-                    let new_page_start_idx = current_page * PAGE_SIZE;
-                    *selected_index = new_page_start_idx;
-                }
-                KeyCode::PageDown | KeyCode::Char('n') | KeyCode::Char('N') => {}
-                KeyCode::PageUp | KeyCode::Char('p') | KeyCode::Char('P')
-                    if total_pages > 1 && current_page > 0 =>
-                {
-                    current_page -= 1;
-                    let new_page_start_idx = current_page * PAGE_SIZE;
-                    *selected_index = new_page_start_idx;
-                }
-                KeyCode::PageUp | KeyCode::Char('p') | KeyCode::Char('P') => {}
+                KeyCode::Up | KeyCode::Char('k') | KeyCode::Char('K') => state.move_up(),
+                KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('J') => state.move_down(),
+                KeyCode::PageDown | KeyCode::Char('n') | KeyCode::Char('N') => state.page_next(),
+                KeyCode::PageUp | KeyCode::Char('p') | KeyCode::Char('P') => state.page_prev(),
                 KeyCode::Enter => {
                     cleanup_terminal(stdout);
-                    return handle_codex_selection_action(configs, *selected_index);
+                    return handle_codex_selection_action(configs, state.selected_index);
                 }
                 KeyCode::Esc => {
                     cleanup_terminal(stdout);
@@ -354,15 +417,14 @@ fn handle_codex_full_interactive_menu(
                 }
                 KeyCode::Char(c) if c.is_ascii_digit() => {
                     let digit = c.to_digit(10).unwrap() as usize;
-                    if digit >= 1 && digit <= page_configs.len() {
-                        let actual_config_index = start_idx + (digit - 1);
+                    if let Some(actual_config_index) = state.select_digit(digit) {
                         cleanup_terminal(stdout);
                         return handle_codex_selection_action(configs, actual_config_index);
                     }
                 }
-                KeyCode::Char('e') | KeyCode::Char('E') if *selected_index < configs.len() => {
+                KeyCode::Char('e') | KeyCode::Char('E') if state.selected_index < configs.len() => {
                     cleanup_terminal(stdout);
-                    let edit_result = handle_codex_config_edit(&configs[*selected_index]);
+                    let edit_result = handle_codex_config_edit(&configs[state.selected_index]);
                     if execute!(
                         stdout,
                         terminal::EnterAlternateScreen,
@@ -378,9 +440,7 @@ fn handle_codex_full_interactive_menu(
                                         *configs = map.values().cloned().collect();
                                         configs.sort_by(|a, b| a.alias_name.cmp(&b.alias_name));
                                     }
-                                    if *selected_index > configs.len() {
-                                        *selected_index = configs.len().saturating_sub(1);
-                                    }
+                                    state.set_configs_len(configs.len());
                                 }
                                 continue;
                             }
@@ -572,21 +632,12 @@ fn launch_codex_from_interactive() -> Result<()> {
     #[cfg(not(unix))]
     {
         use anyhow::Context;
-        use std::process::Stdio;
         let mut command = Command::new(resolve_npm_cli("codex"));
-        command
-            .stdin(Stdio::inherit())
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit());
-
-        let mut child = command.spawn().context(
+        let code = crate::platform::spawn_and_wait(&mut command).context(
             "Failed to launch Codex CLI. Make sure 'codex' command is available in PATH",
         )?;
-
-        let status = child.wait()?;
-
-        if !status.success() {
-            anyhow::bail!("Codex CLI exited with error status: {}", status);
+        if code != 0 {
+            std::process::exit(code);
         }
         Ok(())
     }
@@ -926,4 +977,107 @@ mod codex_interactive_tests {
         let actual_config_index = start_idx + (digit - 1);
         assert_eq!(actual_config_index, 4);
     }
+
+    #[test]
+    fn test_menu_state_arrows_flip_pages_at_boundaries() {
+        // 20 configs: pages 0..9, 9..18, 18..20 plus Exit (index 20)
+        let mut state = CodexMenuState::new(20, 9, 0);
+        assert_eq!(state.total_pages(), 3);
+
+        for expected in 1..=8 {
+            state.move_down();
+            assert_eq!((state.selected_index, state.current_page), (expected, 0));
+        }
+        state.move_down();
+        assert_eq!((state.selected_index, state.current_page), (9, 1));
+        assert_eq!(state.page_range(), 9..18);
+
+        state.move_up();
+        assert_eq!((state.selected_index, state.current_page), (8, 0));
+
+        for _ in 0..30 {
+            state.move_down();
+        }
+        assert_eq!((state.selected_index, state.current_page), (20, 2));
+        assert_eq!(state.page_range(), 18..20);
+
+        for _ in 0..30 {
+            state.move_up();
+        }
+        assert_eq!((state.selected_index, state.current_page), (0, 0));
+    }
+
+    #[test]
+    fn test_menu_state_selection_always_on_current_page() {
+        for configs_len in 1..=28 {
+            let mut state = CodexMenuState::new(configs_len, 9, 0);
+            let keys = [0, 0, 2, 0, 0, 1, 3, 0, 0, 0, 0, 0, 1, 1, 2, 2, 2, 3, 3, 0];
+            for key in keys.iter().cycle().take(200) {
+                match key {
+                    0 => state.move_down(),
+                    1 => state.move_up(),
+                    2 => state.page_next(),
+                    _ => state.page_prev(),
+                }
+                let range = state.page_range();
+                let on_page = range.contains(&state.selected_index)
+                    || (state.selected_index == configs_len
+                        && state.current_page == state.total_pages() - 1);
+                assert!(on_page, "{configs_len}: {state:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_menu_state_paging_selects_first_row() {
+        let mut state = CodexMenuState::new(20, 9, 0);
+        state.page_prev();
+        assert_eq!((state.selected_index, state.current_page), (0, 0));
+        state.page_next();
+        assert_eq!((state.selected_index, state.current_page), (9, 1));
+        state.page_next();
+        assert_eq!((state.selected_index, state.current_page), (18, 2));
+        state.page_next();
+        assert_eq!((state.selected_index, state.current_page), (18, 2));
+        state.page_prev();
+        assert_eq!((state.selected_index, state.current_page), (9, 1));
+    }
+
+    #[test]
+    fn test_menu_state_digits_pick_from_the_page_on_screen() {
+        let mut state = CodexMenuState::new(20, 9, 0);
+        assert_eq!(state.select_digit(0), None);
+        assert_eq!(state.select_digit(1), Some(0));
+        assert_eq!(state.select_digit(9), Some(8));
+
+        // Reaching page 2 with the arrows, not N, must move the digits too
+        for _ in 0..9 {
+            state.move_down();
+        }
+        assert_eq!(state.select_digit(1), Some(9));
+        assert_eq!(state.select_digit(9), Some(17));
+
+        state.page_next();
+        assert_eq!(state.select_digit(2), Some(19));
+        assert_eq!(state.select_digit(3), None);
+    }
+
+    #[test]
+    fn test_menu_state_exit_on_a_full_last_page() {
+        // 18 configs fill two pages; Exit (18) stays on the second
+        let mut state = CodexMenuState::new(18, 9, 17);
+        assert_eq!(state.current_page, 1);
+        state.move_down();
+        assert_eq!((state.selected_index, state.current_page), (18, 1));
+        assert_eq!(state.total_pages(), 2);
+    }
+
+    #[test]
+    fn test_menu_state_reload_keeps_selection_in_range() {
+        let mut state = CodexMenuState::new(20, 9, 19);
+        assert_eq!(state.current_page, 2);
+        state.set_configs_len(5);
+        assert_eq!((state.selected_index, state.current_page), (5, 0));
+        assert_eq!(state.page_range(), 0..5);
+    }
 }
@@ -1,9 +1,19 @@
 use crate::cli::display_utils::{
-    TextAlignment, format_token_for_display, get_terminal_width, pad_text_to_width,
-    text_display_width,
+    BorderDrawing, FieldMask, HIDDEN_PLACEHOLDER, TextAlignment, color_label,
+    credential_for_display, default_marker, format_token_for_display, get_terminal_width,
+    last_used_note, pad_text_to_width, tags_note, text_display_width,
 };
-use crate::config::EnvironmentConfig;
-use crate::config::types::{ClaudeSettings, ConfigStorage, Configuration};
+use crate::config::types::{
+    ClaudeSettings, ConfigStorage, Configuration, LaunchProfile, Preferences, StorageMode,
+    UNTAGGED_GROUP,
+};
+use crate::config::{EnvironmentConfig, ModelNoticeLevel, validate_base_url};
+use crate::interactive::launch_plan::LaunchPlan;
+use crate::interactive::prompter::{Prompter, StdinPrompter};
+use crate::interactive::screen::{
+    MenuCommand, MenuScreen, map_key, read_event_burst, ui_mode_from_env,
+};
+use crate::interactive::secret::read_sensitive_input;
 use crate::platform::resolve_npm_cli;
 use anyhow::{Context, Result};
 use colored::*;
@@ -11,45 +21,10 @@ use crossterm::{
     event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
     execute, terminal,
 };
+use std::collections::{BTreeMap, BTreeSet};
 use std::io::{self, Write};
 use std::process::Command;
-
-/// Calculate display width of a character
-/// Returns 2 for wide characters (CJK), 1 for others
-pub(crate) fn char_display_width(c: char) -> usize {
-    match c as u32 {
-        0x00..=0x7F => 1,
-        0x80..=0x2FF => 1,
-        0x2190..=0x21FF => 2,
-        0x3000..=0x303F => 2,
-        0x3040..=0x309F => 2,
-        0x30A0..=0x30FF => 2,
-        0x4E00..=0x9FFF => 2,
-        0xAC00..=0xD7AF => 2,
-        0x3400..=0x4DBF => 2,
-        0xFF01..=0xFF60 => 2,
-        _ => 1,
-    }
-}
-
-/// Truncate text to fit within available width, considering character display width
-pub(crate) fn truncate_text_to_width(text: &str, available_width: usize) -> (String, usize) {
-    let mut current_width = 0;
-    let truncated: String = text
-        .chars()
-        .take_while(|&c| {
-            let char_width = char_display_width(c);
-            if current_width + char_width <= available_width {
-                current_width += char_width;
-                true
-            } else {
-                false
-            }
-        })
-        .collect();
-    let truncated_width = text_display_width(&truncated);
-    (truncated, truncated_width)
-}
+use std::time::{Duration, Instant};
 
 /// Clean up terminal state by leaving alternate screen and disabling raw mode
 pub(crate) fn cleanup_terminal(stdout: &mut io::Stdout) {
@@ -57,203 +32,198 @@ pub(crate) fn cleanup_terminal(stdout: &mut io::Stdout) {
     let _ = terminal::disable_raw_mode();
 }
 
-/// Border drawing utilities for terminal compatibility
-pub(crate) struct BorderDrawing {
-    /// Check if terminal supports Unicode box drawing characters
-    pub unicode_supported: bool,
-}
-
-impl BorderDrawing {
-    /// Create new border drawing utility
-    pub(crate) fn new() -> Self {
-        let unicode_supported = Self::detect_unicode_support();
-        Self { unicode_supported }
-    }
-
-    /// Detect if terminal supports Unicode characters
-    fn detect_unicode_support() -> bool {
-        crate::platform::unicode_support_enabled()
-    }
-
-    /// Draw top border with title
-    pub(crate) fn draw_top_border(&self, title: &str, width: usize) -> String {
-        if self.unicode_supported {
-            let title_padded = format!(" {title} ");
-            let title_len = text_display_width(&title_padded);
-
-            if title_len >= width.saturating_sub(2) {
-                // Title too long, use simple border
-                format!("╔{}╗", "═".repeat(width.saturating_sub(2)))
-            } else {
-                let inner_width = width.saturating_sub(2); // Total width minus borders
-                let padding_total = inner_width.saturating_sub(title_len);
-                let padding_left = padding_total / 2;
-                let padding_right = padding_total - padding_left;
-                format!(
-                    "╔{}{}{}╗",
-                    "═".repeat(padding_left),
-                    title_padded,
-                    "═".repeat(padding_right)
-                )
-            }
-        } else {
-            // ASCII fallback
-            let title_padded = format!(" {title} ");
-            let title_len = title_padded.len();
-
-            if title_len >= width.saturating_sub(2) {
-                format!("+{}+", "-".repeat(width.saturating_sub(2)))
-            } else {
-                let inner_width = width.saturating_sub(2);
-                let padding_total = inner_width.saturating_sub(title_len);
-                let padding_left = padding_total / 2;
-                let padding_right = padding_total - padding_left;
-                format!(
-                    "+{}{}{}+",
-                    "-".repeat(padding_left),
-                    title_padded,
-                    "-".repeat(padding_right)
-                )
-            }
-        }
-    }
+/// Narrowest menu border; narrower terminals wrap the border rather than
+/// squeezing the help text out of it
+const MIN_MENU_WIDTH: usize = 40;
 
-    /// Draw middle border line with text
-    pub(crate) fn draw_middle_line(&self, text: &str, width: usize) -> String {
-        let text_len = text_display_width(text);
-        // Account for borders: "║ " (1+1) + " ║" (1+1) = 4 characters
-        let available_width = width.saturating_sub(4);
-
-        let (left_border, right_border) = if self.unicode_supported {
-            ("║", "║")
-        } else {
-            ("|", "|")
-        };
+/// Border width and page size of a menu, fitted to the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct MenuLayout {
+    width: usize,
+    /// Configurations per page, at most [`MenuState::PAGE_SIZE`]
+    page_size: usize,
+}
 
-        if text_len > available_width {
-            // Truncate text to fit within available width, considering display width
-            let (truncated, truncated_width) = truncate_text_to_width(text, available_width);
-            let padding_spaces = available_width.saturating_sub(truncated_width);
-            format!(
-                "{left_border} {}{} {right_border}",
-                truncated,
-                " ".repeat(padding_spaces)
-            )
-        } else {
-            let padded_text = pad_text_to_width(text, available_width, TextAlignment::Left, ' ');
-            format!("{left_border} {padded_text} {right_border}")
+impl MenuLayout {
+    /// Rows of the configuration menu besides the unselected configuration
+    /// rows: header, official, selected details, add, Exit and help lines
+    const CHROME_ROWS: usize = 16;
+
+    /// Layout for a `columns` x `rows` terminal, no wider than `max_width`
+    fn for_terminal(columns: usize, rows: usize, max_width: usize) -> Self {
+        MenuLayout {
+            width: columns.clamp(MIN_MENU_WIDTH.min(max_width), max_width),
+            page_size: rows
+                .saturating_sub(Self::CHROME_ROWS)
+                .clamp(1, MenuState::PAGE_SIZE),
         }
     }
 
-    /// Draw bottom border
-    pub(crate) fn draw_bottom_border(&self, width: usize) -> String {
-        if self.unicode_supported {
-            format!("╚{}╝", "═".repeat(width - 2))
-        } else {
-            format!("+{}+", "-".repeat(width - 2))
-        }
+    /// Layout for the current terminal size
+    fn current(max_width: usize) -> Self {
+        let rows = terminal::size().map_or(24, |(_, rows)| rows as usize);
+        Self::for_terminal(get_terminal_width(), rows, max_width)
     }
 }
 
 /// Handle interactive current command
 ///
-/// Provides interactive menu for:
-/// 1. Execute claude --dangerously-skip-permissions
-/// 2. Switch configuration (lists available aliases)
-/// 3. Exit
+/// Reports the configuration active in the environment, then provides an
+/// interactive menu for:
+/// 1. Relaunch the active configuration, or the last used one (only when
+///    one is known)
+/// 2. Execute claude (with --dangerously-skip-permissions unless disabled)
+/// 3. Switch configuration (lists available aliases)
+/// 4. Exit
 ///
 /// # Errors
 /// Returns error if file operations fail or user input fails
 pub fn handle_current_command() -> Result<()> {
     let storage = ConfigStorage::load()?;
 
+    let report = crate::cli::current::current_status(&storage)?;
+    let status = report.render_human();
     println!("\n{}", "Current Configuration:".green().bold());
-    println!("Environment variable mode: configurations are set per-command execution");
+    for line in &status {
+        println!("{line}");
+    }
+    println!();
     println!("Select a configuration from the menu below to launch Claude");
     println!("Select 'cc' to launch Claude with default settings");
 
-    // Try to enable interactive menu with keyboard navigation
-    let raw_mode_enabled = terminal::enable_raw_mode().is_ok();
-
-    if raw_mode_enabled {
-        let mut stdout = io::stdout();
-        if execute!(
-            stdout,
-            terminal::EnterAlternateScreen,
-            terminal::Clear(terminal::ClearType::All)
-        )
-        .is_ok()
-        {
-            // Full interactive mode with arrow keys for main menu
-            let result = handle_main_menu_interactive(&mut stdout, &storage);
-
-            // Always restore terminal
-            let _ = execute!(stdout, terminal::LeaveAlternateScreen);
-            let _ = terminal::disable_raw_mode();
+    let items = main_menu_items(&storage, report.active_alias.as_deref());
 
-            return result;
-        } else {
-            // Fallback to simple mode
-            let _ = terminal::disable_raw_mode();
-        }
+    // Keyboard navigation on the alternate screen or inline, whichever the
+    // terminal supports
+    if let Some(mut screen) = MenuScreen::enter(ui_mode_from_env()) {
+        let result = handle_main_menu_interactive(&mut screen, &storage, &items, &status[0]);
+        screen.leave();
+        return result;
     }
 
     // Fallback to simple numbered menu
-    handle_main_menu_simple(&storage)
+    handle_main_menu_simple(&storage, &items)
+}
+
+/// What choosing a main menu item does
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum MainMenuAction {
+    /// Launch the stored configuration with this alias
+    Relaunch(String),
+    /// Launch Claude without switching configuration
+    ExecuteClaude,
+    /// Open the configuration selection
+    SwitchConfiguration,
+    Exit,
+}
+
+/// An entry of the main menu
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MenuItem {
+    label: String,
+    action: MainMenuAction,
+}
+
+/// Items of the main menu
+///
+/// Led by relaunching `active_alias`, the configuration detected in the
+/// environment, or failing that the last used one; the item is left out
+/// when neither names a stored configuration.
+fn main_menu_items(storage: &ConfigStorage, active_alias: Option<&str>) -> Vec<MenuItem> {
+    let stored = |alias: &&str| storage.configurations.contains_key(*alias);
+    let relaunch = match (
+        active_alias.filter(stored),
+        storage.last_used.as_deref().filter(stored),
+    ) {
+        (Some(alias), _) => Some(("current", alias)),
+        (None, Some(alias)) => Some(("last", alias)),
+        (None, None) => None,
+    };
+
+    let mut items: Vec<MenuItem> = relaunch
+        .map(|(which, alias)| MenuItem {
+            label: format!("Relaunch with {which} configuration ('{alias}')"),
+            action: MainMenuAction::Relaunch(alias.to_string()),
+        })
+        .into_iter()
+        .collect();
+    items.extend([
+        MenuItem {
+            label: execute_claude_label(storage.preferences.skip_permissions()),
+            action: MainMenuAction::ExecuteClaude,
+        },
+        MenuItem {
+            label: "Switch configuration".to_string(),
+            action: MainMenuAction::SwitchConfiguration,
+        },
+        MenuItem {
+            label: "Exit".to_string(),
+            action: MainMenuAction::Exit,
+        },
+    ]);
+    items
 }
 
 /// Handle main menu with keyboard navigation
-fn handle_main_menu_interactive(stdout: &mut io::Stdout, storage: &ConfigStorage) -> Result<()> {
-    let menu_items = [
-        "Execute claude --dangerously-skip-permissions",
-        "Switch configuration",
-        "Exit",
-    ];
+///
+/// `status` is the active configuration line shown under the header.
+fn handle_main_menu_interactive(
+    screen: &mut MenuScreen,
+    storage: &ConfigStorage,
+    items: &[MenuItem],
+    status: &str,
+) -> Result<()> {
     let mut selected_index = 0;
+    const MAIN_MENU_WIDTH: usize = 68;
+    let mut menu_width = MenuLayout::current(MAIN_MENU_WIDTH).width;
 
     loop {
-        // Clear screen and redraw
-        execute!(stdout, terminal::Clear(terminal::ClearType::All))?;
-        execute!(stdout, crossterm::cursor::MoveTo(0, 0))?;
+        let mut frame = Vec::new();
 
         // Header - use BorderDrawing for compatibility
         let border = BorderDrawing::new();
-        const MAIN_MENU_WIDTH: usize = 68;
 
-        println!(
+        frame.push(format!(
             "\r{}",
-            border.draw_top_border("Main Menu", MAIN_MENU_WIDTH).green()
-        );
-        println!(
+            border.draw_top_border("Main Menu", menu_width).green()
+        ));
+        frame.push(format!(
             "\r{}",
             border
                 .draw_middle_line(
                     "↑↓/jk导航，1-9快选，E-编辑，R-官方，Q-退出，Enter确认，Esc取消",
-                    MAIN_MENU_WIDTH
+                    menu_width
                 )
                 .green()
-        );
-        println!("\r{}", border.draw_bottom_border(MAIN_MENU_WIDTH).green());
-        println!();
+        ));
+        frame.push(format!(
+            "\r{}",
+            border.draw_bottom_border(menu_width).green()
+        ));
+        frame.push(format!("\r{status}"));
+        frame.push(String::new());
 
         // Draw menu items
-        for (index, item) in menu_items.iter().enumerate() {
+        for (index, item) in items.iter().enumerate() {
             if index == selected_index {
-                println!("\r> {} {}", "●".blue().bold(), item.blue().bold());
+                frame.push(format!(
+                    "\r> {} {}",
+                    "●".blue().bold(),
+                    item.label.blue().bold()
+                ));
             } else {
-                println!("\r  {} {}", "○".dimmed(), item.dimmed());
+                frame.push(format!("\r  {} {}", "○".dimmed(), item.label.dimmed()));
             }
         }
 
-        // Ensure output is flushed
-        stdout.flush()?;
+        screen.draw(&frame)?;
 
         // Handle input with error recovery
         let event = match event::read() {
             Ok(event) => event,
             Err(e) => {
                 // Clean up terminal state on input error
-                cleanup_terminal(stdout);
+                screen.leave();
                 return Err(e.into());
             }
         };
@@ -261,26 +231,26 @@ fn handle_main_menu_interactive(stdout: &mut io::Stdout, storage: &ConfigStorage
         match event {
             Event::Key(KeyEvent {
                 code,
+                modifiers,
                 kind: KeyEventKind::Press,
                 ..
             }) => {
-                match code {
-                    KeyCode::Up => {
+                match map_key(code, modifiers) {
+                    MenuCommand::Up => {
                         selected_index = selected_index.saturating_sub(1);
                     }
-                    KeyCode::Down if selected_index < menu_items.len() - 1 => {
+                    MenuCommand::Down if selected_index < items.len() - 1 => {
                         selected_index += 1;
                     }
-                    KeyCode::Down => {}
-                    KeyCode::Enter => {
+                    MenuCommand::Confirm => {
                         // Execute terminal cleanup here
-                        cleanup_terminal(stdout);
+                        screen.leave();
 
-                        return handle_main_menu_action(selected_index, storage);
+                        return handle_main_menu_action(&items[selected_index].action, storage);
                     }
-                    KeyCode::Esc => {
+                    MenuCommand::Back | MenuCommand::Interrupt => {
                         // Clean up terminal before exit
-                        cleanup_terminal(stdout);
+                        screen.leave();
 
                         println!("\nExiting...");
                         return Ok(());
@@ -289,20 +259,26 @@ fn handle_main_menu_interactive(stdout: &mut io::Stdout, storage: &ConfigStorage
                 }
             }
             Event::Key(_) => {} // Ignore key release events
+            Event::Resize(columns, rows) => {
+                menu_width =
+                    MenuLayout::for_terminal(columns as usize, rows as usize, MAIN_MENU_WIDTH)
+                        .width;
+                screen.force_redraw(columns as usize);
+            }
             _ => {}
         }
     }
 }
 
 /// Handle main menu simple fallback
-fn handle_main_menu_simple(storage: &ConfigStorage) -> Result<()> {
+fn handle_main_menu_simple(storage: &ConfigStorage, items: &[MenuItem]) -> Result<()> {
     loop {
         println!("\n{}", "Available Actions:".blue().bold());
-        println!("1. Execute claude --dangerously-skip-permissions");
-        println!("2. Switch configuration");
-        println!("3. Exit");
+        for (index, item) in items.iter().enumerate() {
+            println!("{}. {}", index + 1, item.label);
+        }
 
-        print!("\nPlease select an option (1-3): ");
+        print!("\nPlease select an option (1-{}): ", items.len());
         io::stdout().flush().context("Failed to flush stdout")?;
 
         let mut input = String::new();
@@ -310,405 +286,1432 @@ fn handle_main_menu_simple(storage: &ConfigStorage) -> Result<()> {
             .read_line(&mut input)
             .context("Failed to read input")?;
 
-        let choice = input.trim();
-
-        match choice {
-            "1" => return handle_main_menu_action(0, storage),
-            "2" => return handle_main_menu_action(1, storage),
-            "3" => return handle_main_menu_action(2, storage),
-            _ => {
-                println!("Invalid option. Please select 1-3.");
-            }
+        match menu_choice(input.trim(), items) {
+            Some(item) => return handle_main_menu_action(&item.action, storage),
+            None => println!("Invalid option. Please select 1-{}.", items.len()),
         }
     }
 }
 
-/// Handle main menu action based on selected index
-fn handle_main_menu_action(selected_index: usize, storage: &ConfigStorage) -> Result<()> {
-    match selected_index {
-        0 => {
-            println!("\nExecuting: claude --dangerously-skip-permissions");
-            execute_claude_command(true)?;
-        }
-        1 => {
+/// The item numbered `input` (from 1) in the simple menu
+fn menu_choice<'a>(input: &str, items: &'a [MenuItem]) -> Option<&'a MenuItem> {
+    let number: usize = input.parse().ok()?;
+    items.get(number.checked_sub(1)?)
+}
+
+/// Label of the main menu item launching Claude, showing the actual flags
+fn execute_claude_label(skip_permissions: bool) -> String {
+    if skip_permissions {
+        "Execute claude --dangerously-skip-permissions".to_string()
+    } else {
+        "Execute claude".to_string()
+    }
+}
+
+/// Carry out the main menu item chosen
+fn handle_main_menu_action(action: &MainMenuAction, storage: &ConfigStorage) -> Result<()> {
+    match action {
+        MainMenuAction::Relaunch(alias) => launch_selection(
+            storage,
+            &Selection::Config(alias.clone()),
+            &LaunchOptions::default(),
+            &mut StdinPrompter,
+            &mut ExecLauncher,
+        ),
+        MainMenuAction::ExecuteClaude => execute_claude_command(storage, &[]),
+        MainMenuAction::SwitchConfiguration => {
             // Use the interactive selection instead of simple menu
-            handle_interactive_selection(storage)?;
+            handle_interactive_selection(storage, None, LaunchOptions::default())
         }
-        2 => {
+        MainMenuAction::Exit => {
             println!("Exiting...");
-        }
-        _ => {
-            println!("Invalid selection");
+            Ok(())
         }
     }
-    Ok(())
 }
 
 /// Handle interactive configuration selection with real-time preview
 ///
 /// # Arguments
 /// * `storage` - Reference to configuration storage
+/// * `tag` - Only list configurations carrying this tag (`cc-switch --tag`)
+/// * `options` - Command-line launch settings (passthrough arguments,
+///   `--no-skip-permissions`) applied to whatever is selected
 ///
 /// # Errors
-/// Returns error if terminal operations fail or user selection fails
-pub fn handle_interactive_selection(storage: &ConfigStorage) -> Result<()> {
+/// Returns error if terminal operations fail, user selection fails or no
+/// configuration carries `tag`
+pub fn handle_interactive_selection(
+    storage: &ConfigStorage,
+    tag: Option<&str>,
+    options: LaunchOptions,
+) -> Result<()> {
     if storage.configurations.is_empty() {
         println!("No configurations available. Use 'add' command to create configurations first.");
         return Ok(());
     }
 
-    let mut configs: Vec<Configuration> = storage.configurations.values().cloned().collect();
-    configs.sort_by(|a, b| a.alias_name.cmp(&b.alias_name));
+    let mut state = MenuState::new(
+        storage.configurations_tagged(tag)?.into_values().collect(),
+        storage.preferences.grouped_menu,
+    )
+    .with_tag(tag);
+    state.storage_mode = options.effective_storage_mode(storage);
+
+    // Keyboard navigation on the alternate screen or inline, whichever the
+    // terminal supports; the simple numbered menu otherwise
+    let mut reloaded_storage = None;
+    let pick = if let Some(mut screen) = MenuScreen::enter(ui_mode_from_env()) {
+        let result =
+            handle_full_interactive_menu(&mut screen, &mut state, storage, &mut reloaded_storage);
+        screen.leave();
+        result?
+    } else {
+        MenuPick {
+            selection: handle_simple_interactive_menu(
+                &state.configs.iter().collect::<Vec<_>>(),
+                storage,
+            )?,
+            quiet: false,
+        }
+    };
 
-    let mut selected_index = 0;
+    // Selections act on the latest snapshot when the menu edited storage
+    let options = LaunchOptions {
+        quiet: pick.quiet,
+        ..options
+    };
+    launch_selection(
+        reloaded_storage.as_ref().unwrap_or(storage),
+        &pick.selection,
+        &options,
+        &mut StdinPrompter,
+        &mut ExecLauncher,
+    )
+}
 
-    // Try to enable raw mode, fallback to simple menu if it fails
-    let raw_mode_enabled = terminal::enable_raw_mode().is_ok();
+/// Indices into `MenuState::configs` per group name
+type GroupMembers<'a> = BTreeMap<&'a str, Vec<usize>>;
+
+/// One line of the configuration list between the official and Exit entries
+#[derive(Debug, Clone, PartialEq)]
+enum MenuRow {
+    /// Group header in grouped mode; only selectable while collapsed, when
+    /// it stands in for the hidden configurations so Tab can expand it
+    Header {
+        group: String,
+        count: usize,
+        collapsed: bool,
+    },
+    /// Index into `MenuState::configs`
+    Config(usize),
+}
 
-    if raw_mode_enabled {
-        let mut stdout = io::stdout();
-        if execute!(
-            stdout,
-            terminal::EnterAlternateScreen,
-            terminal::Clear(terminal::ClearType::All)
-        )
-        .is_ok()
-        {
-            // Full interactive mode with arrow keys
-            let storage_mode = storage.default_storage_mode.clone().unwrap_or_default();
-            let result = handle_full_interactive_menu(
-                &mut stdout,
-                &mut configs,
-                &mut selected_index,
-                storage,
-                storage_mode,
-            );
+/// Number being typed to pick a configuration in the full menu
+///
+/// Digits are buffered while a longer number could still follow, so that
+/// `12` reaches the twelfth configuration from any page; a number that no
+/// further digit could extend is picked at once.
+#[derive(Debug, Default)]
+struct NumberInput {
+    digits: String,
+    /// When the last digit was typed
+    typed_at: Option<Instant>,
+}
 
-            // Always restore terminal
-            let _ = execute!(stdout, terminal::LeaveAlternateScreen);
-            let _ = terminal::disable_raw_mode();
+/// What a digit typed into [`NumberInput`] does
+#[derive(Debug, PartialEq)]
+enum NumberStep {
+    /// Pick configuration `n` (1-based)
+    Pick(usize),
+    /// Wait for another digit, Enter or the timeout
+    Pending,
+    /// The number typed has no configuration
+    OutOfRange(usize),
+    /// A single digit with no configuration, ignored as before buffering
+    Ignored,
+}
 
-            return result;
-        } else {
-            // Fallback to simple mode
-            let _ = terminal::disable_raw_mode();
+impl NumberInput {
+    /// How long to wait for the next digit of a number
+    const TIMEOUT: Duration = Duration::from_millis(800);
+
+    /// Add `digit` typed at `now`, with `count` configurations listed
+    fn push(&mut self, digit: char, count: usize, now: Instant) -> NumberStep {
+        let mut digits = std::mem::take(&mut self.digits);
+        digits.push(digit);
+        self.typed_at = None;
+        let number = digits.parse().unwrap_or(usize::MAX);
+        if number == 0 || number > count {
+            return if digits.len() == 1 {
+                NumberStep::Ignored
+            } else {
+                NumberStep::OutOfRange(number)
+            };
         }
+        if number.saturating_mul(10) > count {
+            return NumberStep::Pick(number);
+        }
+        self.digits = digits;
+        self.typed_at = Some(now);
+        NumberStep::Pending
     }
 
-    // Fallback to simple numbered menu
-    handle_simple_interactive_menu(&configs.iter().collect::<Vec<_>>(), storage)
-}
+    /// The digits typed so far, if a number is pending
+    fn pending(&self) -> Option<&str> {
+        Some(self.digits.as_str()).filter(|digits| !digits.is_empty())
+    }
 
-/// Handle full interactive menu with arrow key navigation and pagination
-fn handle_full_interactive_menu(
-    stdout: &mut io::Stdout,
-    configs: &mut Vec<Configuration>,
-    selected_index: &mut usize,
-    storage: &ConfigStorage,
-    storage_mode: crate::config::types::StorageMode,
-) -> Result<()> {
-    // Handle empty configuration list
-    if configs.is_empty() {
-        println!("\r{}", "No configurations available".yellow());
-        println!(
-            "\r{}",
-            "Use 'cc-switch add <alias> <token> <url>' to add configurations first.".dimmed()
-        );
-        println!("\r{}", "Press any key to continue...".dimmed());
-        let _ = event::read(); // Wait for user input
-        return Ok(());
+    /// Time left at `now` before the pending number is picked
+    fn remaining(&self, now: Instant) -> Option<Duration> {
+        self.typed_at
+            .map(|typed_at| Self::TIMEOUT.saturating_sub(now.duration_since(typed_at)))
     }
 
-    const PAGE_SIZE: usize = 9; // Maximum 9 configs per page
+    /// Take the pending number (Enter or timeout)
+    fn finish(&mut self) -> Option<usize> {
+        let number = self.pending()?.parse().ok();
+        self.clear();
+        number
+    }
 
-    // Calculate pagination info
-    let total_pages = if configs.len() <= PAGE_SIZE {
-        1
-    } else {
-        configs.len().div_ceil(PAGE_SIZE)
-    };
-    let mut current_page = 0;
+    fn clear(&mut self) {
+        self.digits.clear();
+        self.typed_at = None;
+    }
+}
 
-    loop {
-        // Calculate current page config range
-        let start_idx = current_page * PAGE_SIZE;
-        let end_idx = std::cmp::min(start_idx + PAGE_SIZE, configs.len());
-        let page_configs = &configs[start_idx..end_idx];
+/// Configuration list, selection and page of the full interactive menu
+///
+/// The menu owns its configurations so that they can be reloaded after an
+/// edit. `rows` is what is displayed between the official and Exit entries;
+/// `selected_index` 0 is official, `1..=rows.len()` are rows and
+/// `rows.len() + 1` is Exit.
+struct MenuState {
+    configs: Vec<Configuration>,
+    grouped: bool,
+    /// Groups collapsed during this session
+    collapsed: BTreeSet<String>,
+    rows: Vec<MenuRow>,
+    selected_index: usize,
+    current_page: usize,
+    /// Key presses handled so far
+    keys_seen: usize,
+    /// Whether typed characters go to the filter query (`/`)
+    filtering: bool,
+    /// Case-insensitive filter on alias and URL; empty lists everything
+    query: String,
+    /// Only configurations carrying this tag are listed, also after reloads
+    tag: Option<String>,
+    /// Border width and page size for the current terminal size
+    layout: MenuLayout,
+    /// Where launches from the menu write the configuration (footer)
+    storage_mode: StorageMode,
+    /// Number being typed to pick a configuration
+    number: NumberInput,
+    /// Message shown until the next key press, e.g. an unknown number
+    notice: Option<String>,
+}
 
-        // Clear screen and redraw
-        execute!(stdout, terminal::Clear(terminal::ClearType::All))?;
-        execute!(stdout, crossterm::cursor::MoveTo(0, 0))?;
+impl MenuState {
+    /// Maximum configurations shown per page
+    const PAGE_SIZE: usize = 9;
+    /// Widest border of the menu
+    // Width needs to accommodate: ║ (1) + space (1) + text (76) + space (1) + ║ (1) = 80
+    // Text width includes arrows (↑↓) and Chinese characters counted as 2 columns each
+    const MAX_WIDTH: usize = 80;
+    /// Whether ↑ on official goes to Exit and ↓ on Exit goes to official
+    const WRAP_AROUND: bool = true;
+
+    fn new(configs: Vec<Configuration>, grouped: bool) -> Self {
+        let mut state = MenuState {
+            configs: Vec::new(),
+            grouped,
+            collapsed: Default::default(),
+            rows: Vec::new(),
+            selected_index: 0,
+            current_page: 0,
+            keys_seen: 0,
+            filtering: false,
+            query: String::new(),
+            tag: None,
+            layout: MenuLayout {
+                width: Self::MAX_WIDTH,
+                page_size: Self::PAGE_SIZE,
+            },
+            storage_mode: StorageMode::default(),
+            number: NumberInput::default(),
+            notice: None,
+        };
+        state.set_configs(configs);
+        state
+    }
 
-        // Header with pagination info - use BorderDrawing for compatibility
-        let border = BorderDrawing::new();
-        // Width needs to accommodate: ║ (1) + space (1) + text (76) + space (1) + ║ (1) = 80
-        // Text width includes arrows (↑↓) and Chinese characters counted as 2 columns each
-        const CONFIG_MENU_WIDTH: usize = 80;
+    /// List only configurations carrying `tag`
+    fn with_tag(mut self, tag: Option<&str>) -> Self {
+        self.tag = tag.map(str::to_string);
+        let configs = std::mem::take(&mut self.configs);
+        self.set_configs(configs);
+        self
+    }
 
-        println!(
-            "\r{}",
-            border
-                .draw_top_border("Select Configuration", CONFIG_MENU_WIDTH)
-                .green()
-        );
-        if total_pages > 1 {
-            println!(
-                "\r{}",
-                border
-                    .draw_middle_line(
-                        &format!("第 {} 页，共 {} 页", current_page + 1, total_pages),
-                        CONFIG_MENU_WIDTH
-                    )
-                    .green()
-            );
-            println!(
-                "\r{}",
-                border
-                    .draw_middle_line(
-                        "↑↓/jk导航，1-9快选，E-编辑，N/P翻页，R-官方，Q-退出，Enter确认",
-                        CONFIG_MENU_WIDTH
-                    )
-                    .green()
-            );
-        } else {
-            println!(
-                "\r{}",
-                border
-                    .draw_middle_line(
-                        "↑↓/jk导航，1-9快选，E-编辑，R-官方，Q-退出，Enter确认，Esc取消",
-                        CONFIG_MENU_WIDTH
-                    )
-                    .green()
-            );
+    fn set_configs(&mut self, mut configs: Vec<Configuration>) {
+        if let Some(tag) = &self.tag {
+            configs.retain(|config| config.has_tag(tag));
         }
-        println!("\r{}", border.draw_bottom_border(CONFIG_MENU_WIDTH).green());
-        println!();
+        configs.sort_by(|a, b| a.alias_name.cmp(&b.alias_name));
+        self.configs = configs;
+        self.rebuild_rows();
+    }
 
-        // Add official option (always visible, always red)
-        let official_index = 0;
-        if *selected_index == official_index {
-            println!(
-                "\r> {} {} {}",
-                "●".red().bold(),
-                "[R]".red().bold(),
-                "official".red().bold()
-            );
-            println!("\r    Use official Claude API (no custom configuration)");
-            println!();
-        } else {
-            println!("\r  {} {} {}", "○".red(), "[R]".red(), "official".red());
+    /// Whether `config` matches the filter query
+    fn matches_query(&self, config: &Configuration) -> bool {
+        let query = self.query.to_lowercase();
+        config.alias_name.to_lowercase().contains(&query)
+            || config.url.to_lowercase().contains(&query)
+    }
+
+    fn rebuild_rows(&mut self) {
+        let visible: Vec<usize> = (0..self.configs.len())
+            .filter(|&index| self.matches_query(&self.configs[index]))
+            .collect();
+        if !self.grouped {
+            self.rows = visible.into_iter().map(MenuRow::Config).collect();
+            return;
         }
 
-        // Draw current page configs with proper numbering
-        for (page_index, config) in page_configs.iter().enumerate() {
-            let actual_config_index = start_idx + page_index;
-            let display_number = page_index + 1; // Numbers 1-9 for current page
-            let actual_index = actual_config_index + 1; // +1 because official is at index 0
-            let number_label = format!("[{display_number}]");
+        let mut groups = GroupMembers::new();
+        for index in visible {
+            let config = &self.configs[index];
+            groups.entry(config.group()).or_default().push(index);
+        }
+        // Untagged configurations come after every tag group
+        let untagged = groups.remove(UNTAGGED_GROUP);
 
-            if *selected_index == actual_index {
-                println!(
-                    "\r> {} {} {}",
-                    "●".blue().bold(),
-                    number_label.blue().bold(),
-                    config.alias_name.blue().bold()
-                );
+        let mut rows = Vec::new();
+        for (group, members) in groups
+            .into_iter()
+            .chain(untagged.map(|m| (UNTAGGED_GROUP, m)))
+        {
+            // Matches are never hidden in collapsed groups while filtering
+            let collapsed = self.query.is_empty() && self.collapsed.contains(group);
+            rows.push(MenuRow::Header {
+                group: group.to_string(),
+                count: members.len(),
+                collapsed,
+            });
+            if !collapsed {
+                rows.extend(members.into_iter().map(MenuRow::Config));
+            }
+        }
+        self.rows = rows;
+    }
 
-                // Show details with improved formatting and alignment
-                let details = format_config_details(config, "\r    ", false);
-                for detail_line in details {
-                    println!("{detail_line}");
-                }
-                println!();
-            } else {
-                println!(
-                    "\r  {} {} {}",
-                    "○".dimmed(),
-                    number_label.dimmed(),
-                    config.alias_name.dimmed()
-                );
+    /// Fit the menu to a resized terminal, keeping the selection in view
+    fn set_layout(&mut self, layout: MenuLayout) {
+        self.layout = layout;
+        self.current_page = self.current_page.min(self.total_pages() - 1);
+        self.follow_selection();
+    }
+
+    /// Row index ranges of each page; a page holds at most `page_size`
+    /// configs
+    fn pages(&self) -> Vec<std::ops::Range<usize>> {
+        let mut pages = Vec::new();
+        let mut start = 0;
+        let mut configs_on_page = 0;
+        for (index, row) in self.rows.iter().enumerate() {
+            if configs_on_page == self.layout.page_size {
+                pages.push(start..index);
+                start = index;
+                configs_on_page = 0;
             }
+            if matches!(row, MenuRow::Config(_)) {
+                configs_on_page += 1;
+            }
+        }
+        if start < self.rows.len() || pages.is_empty() {
+            pages.push(start..self.rows.len());
         }
+        pages
+    }
 
-        // Add exit option (always visible)
-        let exit_index = configs.len() + 1;
-        if *selected_index == exit_index {
-            println!(
-                "\r> {} {} {}",
-                "●".yellow().bold(),
-                "[Q]".yellow().bold(),
-                "Exit".yellow().bold()
-            );
-            println!("\r    Exit without making changes");
-            println!();
+    fn total_pages(&self) -> usize {
+        self.pages().len()
+    }
+
+    /// Row range of the current page
+    fn page_range(&self) -> std::ops::Range<usize> {
+        let pages = self.pages();
+        pages[self.current_page.min(pages.len() - 1)].clone()
+    }
+
+    fn exit_index(&self) -> usize {
+        self.rows.len() + 1
+    }
+
+    /// The row at selection position `index`, if it is a row
+    fn row_at(&self, index: usize) -> Option<&MenuRow> {
+        index.checked_sub(1).and_then(|row| self.rows.get(row))
+    }
+
+    fn is_selectable(&self, index: usize) -> bool {
+        match self.row_at(index) {
+            Some(MenuRow::Header { collapsed, .. }) => *collapsed,
+            _ => index <= self.exit_index(),
+        }
+    }
+
+    /// Page to show for selection position `index`
+    ///
+    /// Official belongs to the first page and Exit to the last; `None` for
+    /// a position past Exit.
+    fn page_of(&self, index: usize) -> Option<usize> {
+        let pages = self.pages();
+        if index == 0 {
+            Some(0)
+        } else if index == self.exit_index() {
+            Some(pages.len() - 1)
         } else {
-            println!(
-                "\r  {} {} {}",
-                "○".dimmed(),
-                "[Q]".dimmed(),
-                "Exit".dimmed()
-            );
+            pages.iter().position(|range| range.contains(&(index - 1)))
         }
+    }
 
-        // Show pagination help if needed
-        if total_pages > 1 {
-            println!(
-                "\r{}",
-                format!(
-                    "Page Navigation: [N]ext, [P]revious (第 {} 页，共 {} 页)",
-                    current_page + 1,
-                    total_pages
-                )
-                .dimmed()
-            );
+    /// Move the page so that it shows the selected row
+    fn follow_selection(&mut self) {
+        if let Some(page) = self.page_of(self.selected_index) {
+            self.current_page = page;
         }
+    }
 
-        // Ensure output is flushed
-        stdout.flush()?;
+    /// Select position `index` and show its page
+    fn select(&mut self, index: usize) {
+        self.selected_index = index;
+        self.follow_selection();
+    }
 
-        // Handle input with error recovery
-        let event = match event::read() {
-            Ok(event) => event,
-            Err(e) => {
-                // Clean up terminal state on input error
-                cleanup_terminal(stdout);
-                return Err(e.into());
+    fn move_up(&mut self) {
+        let mut index = self.selected_index;
+        while index > 0 {
+            index -= 1;
+            if self.is_selectable(index) {
+                self.selected_index = index;
+                break;
             }
-        };
-
-        match event {
-            Event::Key(KeyEvent {
-                code,
-                kind: KeyEventKind::Press,
-                ..
-            }) => match code {
-                KeyCode::Up | KeyCode::Char('k') | KeyCode::Char('K') => {
-                    *selected_index = selected_index.saturating_sub(1);
-                }
-                KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('J')
-                    if *selected_index < configs.len() + 1 =>
-                {
-                    *selected_index += 1;
-                }
-                KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('J') => {}
-                KeyCode::PageDown | KeyCode::Char('n') | KeyCode::Char('N')
-                    if total_pages > 1 && current_page < total_pages - 1 =>
-                {
-                    current_page += 1;
-                    let new_page_start_idx = current_page * PAGE_SIZE;
-                    *selected_index = new_page_start_idx + 1;
-                }
-                KeyCode::PageDown | KeyCode::Char('n') | KeyCode::Char('N') => {}
-                KeyCode::PageUp | KeyCode::Char('p') | KeyCode::Char('P')
-                    if total_pages > 1 && current_page > 0 =>
-                {
-                    current_page -= 1;
-                    let new_page_start_idx = current_page * PAGE_SIZE;
-                    *selected_index = new_page_start_idx + 1;
-                }
-                KeyCode::PageUp | KeyCode::Char('p') | KeyCode::Char('P') => {}
-                KeyCode::Enter => {
-                    // Clean up terminal before processing selection
-                    cleanup_terminal(stdout);
-
-                    return handle_selection_action(
-                        &configs.iter().collect::<Vec<_>>(),
-                        *selected_index,
-                        storage,
-                        storage_mode,
-                    );
-                }
-                KeyCode::Esc => {
-                    // Clean up terminal before exit
-                    cleanup_terminal(stdout);
+        }
+        self.follow_selection();
+    }
 
-                    println!("\nSelection cancelled");
-                    return Ok(());
-                }
-                KeyCode::Char(c) if c.is_ascii_digit() => {
-                    let digit = c.to_digit(10).unwrap() as usize;
-                    // Map digit to current page config
-                    if digit >= 1 && digit <= page_configs.len() {
-                        let actual_config_index = start_idx + (digit - 1);
-                        let selection_index = actual_config_index + 1; // +1 because official is at index 0
+    fn move_down(&mut self) {
+        let mut index = self.selected_index;
+        while index < self.exit_index() {
+            index += 1;
+            if self.is_selectable(index) {
+                self.selected_index = index;
+                break;
+            }
+        }
+        self.follow_selection();
+    }
 
-                        // Clean up terminal before processing selection
-                        cleanup_terminal(stdout);
-
-                        return handle_selection_action(
-                            &configs.iter().collect::<Vec<_>>(),
-                            selection_index,
-                            storage,
-                            storage_mode,
-                        );
-                    }
-                    // Invalid digit - ignore silently
+    /// Apply a navigation key (arrows, j/k, Home/End, g/G, page keys, n/p);
+    /// returns false for any other key
+    ///
+    /// Navigation only reads the current state, so a burst of key repeats
+    /// is applied back to back and drawn once.
+    fn navigate(&mut self, code: KeyCode) -> bool {
+        let (page, pages) = (self.current_page, self.total_pages());
+        match code {
+            KeyCode::Up | KeyCode::Char('k' | 'K') => {
+                if Self::WRAP_AROUND && self.selected_index == 0 {
+                    self.select(self.exit_index());
+                } else {
+                    self.move_up();
                 }
-                KeyCode::Char('r') | KeyCode::Char('R') => {
-                    // Clean up terminal before processing selection
-                    cleanup_terminal(stdout);
-
-                    return handle_selection_action(
-                        &configs.iter().collect::<Vec<_>>(),
-                        0,
-                        storage,
-                        storage_mode,
-                    );
+            }
+            KeyCode::Down | KeyCode::Char('j' | 'J') => {
+                if Self::WRAP_AROUND && self.selected_index == self.exit_index() {
+                    self.select(0);
+                } else {
+                    self.move_down();
                 }
-                KeyCode::Char('e') | KeyCode::Char('E')
-                    if *selected_index > 0 && *selected_index <= configs.len() =>
-                {
-                    cleanup_terminal(stdout);
-                    let config_index = *selected_index - 1;
-                    let edit_result = handle_config_edit(&configs[config_index]);
-                    if execute!(
-                        stdout,
-                        terminal::EnterAlternateScreen,
-                        terminal::Clear(terminal::ClearType::All)
-                    )
-                    .is_ok()
-                        && terminal::enable_raw_mode().is_ok()
-                    {
-                        match edit_result {
-                            Ok(_) => {
-                                if let Ok(reloaded_storage) = ConfigStorage::load() {
-                                    *configs =
-                                        reloaded_storage.configurations.values().cloned().collect();
-                                    configs.sort_by(|a, b| a.alias_name.cmp(&b.alias_name));
-                                    if *selected_index > configs.len() + 1 {
-                                        *selected_index = configs.len() + 1;
-                                    }
-                                }
-                                continue;
-                            }
-                            Err(e) => {
-                                if e.downcast_ref::<EditModeError>()
-                                    == Some(&EditModeError::ReturnToMenu)
-                                {
-                                    continue;
-                                }
-                                cleanup_terminal(stdout);
-                                return Err(e);
-                            }
-                        }
-                    }
+            }
+            KeyCode::Home | KeyCode::Char('g') => self.select(0),
+            KeyCode::End | KeyCode::Char('G') => self.select(self.exit_index()),
+            KeyCode::PageDown | KeyCode::Char('n' | 'N') => {
+                if page + 1 < pages {
+                    self.go_to_page(page + 1);
                 }
-                KeyCode::Char('e') | KeyCode::Char('E') => {}
-                KeyCode::Char('q') | KeyCode::Char('Q') => {
-                    // Clean up terminal before processing selection
-                    cleanup_terminal(stdout);
-
-                    return handle_selection_action(
-                        &configs.iter().collect::<Vec<_>>(),
-                        configs.len() + 1,
-                        storage,
-                        storage_mode,
-                    );
+            }
+            KeyCode::PageUp | KeyCode::Char('p' | 'P') => {
+                if page > 0 {
+                    self.go_to_page(page - 1);
                 }
-                _ => {}
-            },
-            Event::Key(_) => {} // Ignore key release events
-            _ => {}
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Go to `page` and select its first selectable row
+    fn go_to_page(&mut self, page: usize) {
+        self.current_page = page.min(self.total_pages() - 1);
+        let range = self.page_range();
+        if let Some(row) = range.clone().find(|row| self.is_selectable(row + 1)) {
+            self.selected_index = row + 1;
+        }
+    }
+
+    /// Rows of the listed configurations, in order; the position of a row
+    /// here is its quick-select number minus one
+    fn config_rows(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.rows.len()).filter(|&row| matches!(self.rows[row], MenuRow::Config(_)))
+    }
+
+    /// Selection position of quick-select `number` (1-based)
+    ///
+    /// Numbers count configurations only, across pages and group headers.
+    fn number_target(&self, number: usize) -> Option<usize> {
+        self.config_rows()
+            .nth(number.checked_sub(1)?)
+            .map(|row| row + 1)
+    }
+
+    /// Type a quick-select digit, returning the position to pick when the
+    /// number is complete
+    ///
+    /// An unknown number of two or more digits leaves a notice.
+    fn type_digit(&mut self, digit: char, now: Instant) -> Option<usize> {
+        match self.number.push(digit, self.config_rows().count(), now) {
+            NumberStep::Pick(number) => self.number_target(number),
+            NumberStep::OutOfRange(number) => {
+                self.notice = Some(format!("没有编号为 {number} 的配置"));
+                None
+            }
+            NumberStep::Pending | NumberStep::Ignored => None,
+        }
+    }
+
+    /// Complete the number being typed (Enter or timeout), returning the
+    /// position to pick
+    fn finish_number(&mut self) -> Option<usize> {
+        self.number
+            .finish()
+            .and_then(|number| self.number_target(number))
+    }
+
+    /// Whether `code` resolves a selection on the very first key press
+    /// (a valid digit, R or Q), which `fast_select` launches without the
+    /// confirmation output
+    fn is_first_event_selection(&self, code: KeyCode) -> bool {
+        if self.keys_seen > 0 {
+            return false;
+        }
+        match code {
+            KeyCode::Char(c) if c.is_ascii_digit() => c
+                .to_digit(10)
+                .and_then(|digit| self.number_target(digit as usize))
+                .is_some(),
+            KeyCode::Char('r' | 'R' | 'q' | 'Q') => true,
+            _ => false,
+        }
+    }
+
+    /// The selected configuration, if the selection is a configuration row
+    fn selected_config(&self) -> Option<&Configuration> {
+        match self.row_at(self.selected_index) {
+            Some(MenuRow::Config(index)) => self.configs.get(*index),
+            _ => None,
+        }
+    }
+
+    /// Index understood by `handle_selection_action` for the selection
+    ///
+    /// 0 is official, `1..=configs.len()` are configurations in `configs`
+    /// order and `configs.len() + 1` is Exit.
+    fn action_index(&self) -> usize {
+        match self.row_at(self.selected_index) {
+            Some(MenuRow::Config(index)) => index + 1,
+            Some(MenuRow::Header { .. }) => 0,
+            None if self.selected_index == 0 => 0,
+            None => self.configs.len() + 1,
+        }
+    }
+
+    /// Select the row of `alias`, expanding its group if needed
+    ///
+    /// Returns false when the alias is not listed.
+    fn focus_alias(&mut self, alias: &str) -> bool {
+        let Some(config_index) = self.configs.iter().position(|c| c.alias_name == alias) else {
+            return false;
+        };
+        let group = self.configs[config_index].group().to_string();
+        if self.grouped && self.collapsed.remove(&group) {
+            self.rebuild_rows();
+        }
+        match self
+            .rows
+            .iter()
+            .position(|row| *row == MenuRow::Config(config_index))
+        {
+            Some(row) => {
+                self.selected_index = row + 1;
+                self.follow_selection();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replace the filter query and select the first match
+    ///
+    /// With no match the official row is selected, which Enter ignores
+    /// while filtering.
+    fn set_query(&mut self, query: String) {
+        self.query = query;
+        self.rebuild_rows();
+        self.selected_index = self
+            .rows
+            .iter()
+            .position(|row| matches!(row, MenuRow::Config(_)))
+            .map_or(0, |row| row + 1);
+        self.current_page = 0;
+        self.follow_selection();
+    }
+
+    /// Leave filter mode and list every configuration again, keeping the
+    /// highlighted configuration selected
+    fn clear_filter(&mut self) {
+        let focus = self.selected_config().map(|c| c.alias_name.clone());
+        self.filtering = false;
+        self.query.clear();
+        self.rebuild_rows();
+        if !focus.is_some_and(|alias| self.focus_alias(&alias)) {
+            self.selected_index = 0;
+            self.current_page = 0;
+        }
+    }
+
+    /// Drop `alias` from the list, keeping the cursor on the row that
+    /// follows it (or the last row when it was at the end)
+    fn remove(&mut self, alias: &str) {
+        let configs = std::mem::take(&mut self.configs)
+            .into_iter()
+            .filter(|config| config.alias_name != alias)
+            .collect();
+        self.reload(configs, alias);
+        if self.selected_index == self.exit_index() && !self.configs.is_empty() {
+            self.move_up();
+        }
+    }
+
+    /// Replace the configurations after an edit and select `focus_alias`
+    ///
+    /// Falls back to clamping the previous selection when the alias is gone,
+    /// then moves to the page that holds the selection.
+    fn reload(&mut self, configs: Vec<Configuration>, focus_alias: &str) {
+        self.set_configs(configs);
+        if !self.focus_alias(focus_alias) {
+            self.selected_index = self.selected_index.min(self.exit_index());
+            while !self.is_selectable(self.selected_index) {
+                self.selected_index -= 1;
+            }
+            self.current_page = self.current_page.min(self.total_pages() - 1);
+            self.follow_selection();
+        }
+    }
+
+    /// Switch between grouped and flat display, keeping the selection
+    fn toggle_grouped(&mut self) {
+        let focus = self.selected_config().map(|c| c.alias_name.clone());
+        let on_exit = self.selected_index == self.exit_index();
+        self.grouped = !self.grouped;
+        self.rebuild_rows();
+        match focus {
+            Some(alias) => {
+                self.focus_alias(&alias);
+            }
+            None if on_exit => self.selected_index = self.exit_index(),
+            None => self.selected_index = 0,
+        }
+        self.current_page = self.current_page.min(self.total_pages() - 1);
+        self.follow_selection();
+    }
+
+    /// Collapse or expand the group under the cursor (grouped mode only)
+    fn toggle_group(&mut self) {
+        if !self.grouped {
+            return;
+        }
+        match self.row_at(self.selected_index).cloned() {
+            Some(MenuRow::Config(index)) => {
+                let group = self.configs[index].group().to_string();
+                self.collapsed.insert(group.clone());
+                self.rebuild_rows();
+                if let Some(row) = self
+                    .rows
+                    .iter()
+                    .position(|row| matches!(row, MenuRow::Header { group: g, .. } if *g == group))
+                {
+                    self.selected_index = row + 1;
+                }
+            }
+            Some(MenuRow::Header {
+                group,
+                collapsed: true,
+                ..
+            }) => {
+                self.collapsed.remove(&group);
+                self.rebuild_rows();
+                // The header is no longer selectable; select its first member
+                self.move_down();
+            }
+            _ => {}
+        }
+        self.follow_selection();
+    }
+}
+
+/// Frame of the full interactive menu for `state`
+///
+/// The default configuration (`default_alias`) is marked with `★` and the
+/// last-used one is annotated.
+fn render_config_menu(
+    state: &MenuState,
+    mask: &FieldMask,
+    default_alias: Option<&str>,
+    last_used: Option<&str>,
+) -> Vec<String> {
+    let total_pages = state.total_pages();
+    let current_page = state.current_page;
+    let selected_index = state.selected_index;
+    let page_range = state.page_range();
+
+    let mut frame = Vec::new();
+
+    // Header with pagination info - use BorderDrawing for compatibility
+    let border = BorderDrawing::new();
+    let width = state.layout.width;
+
+    frame.push(format!(
+        "\r{}",
+        border
+            .draw_top_border("Select Configuration", width)
+            .green()
+    ));
+    if total_pages > 1 {
+        frame.push(format!(
+            "\r{}",
+            border
+                .draw_middle_line(
+                    &format!("第 {} 页，共 {} 页", current_page + 1, total_pages),
+                    width
+                )
+                .green()
+        ));
+        frame.push(format!(
+            "\r{}",
+            border
+                .draw_middle_line(
+                    "↑↓/jk/gG导航，数字快选，E-编辑，D-删除，N/P翻页，R-官方，Q-退出，Enter确认",
+                    width
+                )
+                .green()
+        ));
+    } else {
+        frame.push(format!(
+            "\r{}",
+            border
+                .draw_middle_line(
+                    "↑↓/jk/gG导航，1-9快选，E-编辑，D-删除，R-官方，Q-退出，Enter确认，Esc取消",
+                    width
+                )
+                .green()
+        ));
+    }
+    frame.push(format!("\r{}", border.draw_bottom_border(width).green()));
+    frame.push(String::new());
+
+    // Add official option (always visible, always red)
+    let official_index = 0;
+    if selected_index == official_index {
+        frame.push(format!(
+            "\r> {} {} {}",
+            "●".red().bold(),
+            "[R]".red().bold(),
+            "official".red().bold()
+        ));
+        frame.push("\r    Use official Claude API (no custom configuration)".to_string());
+        frame.push(String::new());
+    } else {
+        frame.push(format!(
+            "\r  {} {} {}",
+            "○".red(),
+            "[R]".red(),
+            "official".red()
+        ));
+    }
+
+    // Draw current page rows; quick-select numbers count configurations
+    // only, continuing across group headers and pages
+    let mut display_number = state
+        .config_rows()
+        .take_while(|&row| row < page_range.start)
+        .count();
+    for row_index in page_range {
+        let actual_index = row_index + 1; // +1 because official is at index 0
+        match &state.rows[row_index] {
+            MenuRow::Header {
+                group,
+                count,
+                collapsed,
+            } => {
+                let marker = if *collapsed { "+" } else { "-" };
+                let header = format!("{marker} {group} ({count})");
+                if selected_index == actual_index {
+                    frame.push(format!("\r> {}", header.bold()));
+                } else {
+                    frame.push(format!("\r  {}", header.dimmed()));
+                }
+            }
+            MenuRow::Config(config_index) => {
+                let config = &state.configs[*config_index];
+                display_number += 1;
+                let number_label = format!("[{display_number}]");
+
+                let label = format!(
+                    "{}{}",
+                    color_label(config),
+                    default_marker(&config.alias_name, default_alias)
+                );
+                let note = format!(
+                    "{}{}",
+                    tags_note(config),
+                    last_used_note(&config.alias_name, last_used)
+                );
+                if selected_index == actual_index {
+                    frame.push(format!(
+                        "\r> {} {} {}{}{}",
+                        "●".blue().bold(),
+                        number_label.blue().bold(),
+                        label,
+                        config.alias_name.blue().bold(),
+                        note.dimmed()
+                    ));
+
+                    // Show details with improved formatting and alignment
+                    let details = format_config_details(config, "\r    ", false, mask);
+                    frame.extend(details);
+                    frame.push(String::new());
+                } else {
+                    frame.push(format!(
+                        "\r  {} {} {}{}{}",
+                        "○".dimmed(),
+                        number_label.dimmed(),
+                        label,
+                        config.alias_name.dimmed(),
+                        note.dimmed()
+                    ));
+                }
+            }
+        }
+    }
+
+    frame.push(format!(
+        "\r  {} {} {}",
+        "+".green(),
+        "[A]".green(),
+        "Add new configuration".green()
+    ));
+
+    // Add exit option (always visible)
+    if selected_index == state.exit_index() {
+        frame.push(format!(
+            "\r> {} {} {}",
+            "●".yellow().bold(),
+            "[Q]".yellow().bold(),
+            "Exit".yellow().bold()
+        ));
+        frame.push("\r    Exit without making changes".to_string());
+        frame.push(String::new());
+    } else {
+        frame.push(format!(
+            "\r  {} {} {}",
+            "○".dimmed(),
+            "[Q]".dimmed(),
+            "Exit".dimmed()
+        ));
+    }
+
+    // Show pagination help if needed
+    if total_pages > 1 {
+        frame.push(format!(
+            "\r{}",
+            format!(
+                "Page Navigation: [N]ext, [P]revious (第 {} 页，共 {} 页)",
+                current_page + 1,
+                total_pages
+            )
+            .dimmed()
+        ));
+    }
+    if state.filtering {
+        let matches = if state.rows.is_empty() {
+            "  (no matches)"
+        } else {
+            ""
+        };
+        frame.push(format!(
+            "\r{}{}",
+            format!("/{}", state.query).cyan().bold(),
+            matches.dimmed()
+        ));
+        frame.push(format!(
+            "\r{}",
+            "输入以筛选别名或 URL，Backspace 删除，Enter 选择，Esc 清除筛选".dimmed()
+        ));
+        return frame;
+    }
+    if let Some(digits) = state.number.pending() {
+        frame.push(format!(
+            "\r{}",
+            format!("Selection: {digits}_").cyan().bold()
+        ));
+    }
+    if let Some(notice) = &state.notice {
+        frame.push(format!("\r{}", notice.red()));
+    }
+    let mut mode_help = if state.grouped {
+        "[/] 筛选  [M] 平铺显示  [Tab] 折叠/展开分组".to_string()
+    } else {
+        "[/] 筛选  [M] 按标签分组".to_string()
+    };
+    if last_used.is_some() {
+        mode_help.push_str("  [-] 上次使用");
+    }
+    mode_help.push_str(&format!("  存储模式: {}", state.storage_mode.as_str()));
+    frame.push(format!("\r{}", mode_help.dimmed()));
+
+    frame
+}
+
+/// Handle full interactive menu with arrow key navigation and pagination
+///
+/// `reloaded_storage` receives the storage reloaded after an edit in the
+/// menu, which the selection must then be launched against.
+fn handle_full_interactive_menu(
+    screen: &mut MenuScreen,
+    state: &mut MenuState,
+    storage: &ConfigStorage,
+    reloaded_storage: &mut Option<ConfigStorage>,
+) -> Result<MenuPick> {
+    // Handle empty configuration list
+    if state.configs.is_empty() {
+        println!("\r{}", "No configurations available".yellow());
+        println!(
+            "\r{}",
+            "Press A to add one now, or any other key to exit.".dimmed()
+        );
+        let add = matches!(
+            event::read(),
+            Ok(Event::Key(KeyEvent {
+                code: KeyCode::Char('a' | 'A'),
+                ..
+            }))
+        );
+        if !add || !add_and_reload(screen, state, reloaded_storage) || state.configs.is_empty() {
+            return Ok(MenuPick::exit());
+        }
+    }
+
+    let mask = FieldMask::from_storage(storage);
+    state.set_layout(MenuLayout::current(MenuState::MAX_WIDTH));
+
+    // Events read but not handled yet; a frame is drawn only once they are
+    // all handled, so bursts of key repeats cause a single redraw
+    let mut pending = std::collections::VecDeque::new();
+    // Configuration awaiting the y/N answer to a delete
+    let mut confirm_delete: Option<String> = None;
+    // Pick awaiting Enter in the launch preview (`confirm_before_launch`)
+    let mut preview: Option<LaunchPreview> = None;
+    // Whether the number being typed started as a fast selection
+    let mut number_quiet = false;
+
+    loop {
+        let storage = reloaded_storage.as_ref().unwrap_or(storage);
+
+        if pending.is_empty() {
+            let mut frame = match &preview {
+                Some(preview) => {
+                    render_launch_preview(preview.config.as_ref(), &mask, state.layout.width)
+                }
+                None => render_config_menu(
+                    state,
+                    &mask,
+                    storage.default_alias.as_deref(),
+                    storage.last_used.as_deref(),
+                ),
+            };
+            if let Some(alias) = &confirm_delete {
+                frame.push(format!(
+                    "\r{}",
+                    format!("Delete '{alias}'? y/N").yellow().bold()
+                ));
+            }
+            screen.draw(&frame)?;
+
+            // Handle input with error recovery
+            // A pending number is picked when no key follows in time
+            match read_event_burst(state.number.remaining(Instant::now())) {
+                Ok(events) => pending.extend(events),
+                Err(e) => {
+                    // Clean up terminal state on input error
+                    screen.leave();
+                    return Err(e.into());
+                }
+            }
+        }
+        let Some(event) = pending.pop_front() else {
+            if let Some(target) = state.finish_number() {
+                let (pick, previous_index) = pick_position(state, target, number_quiet);
+                if let Some(pick) =
+                    leave_or_preview(screen, storage, &mut preview, pick, previous_index)
+                {
+                    return Ok(pick);
+                }
+            }
+            continue;
+        };
+
+        match event {
+            Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind: KeyEventKind::Press,
+                ..
+            }) => {
+                // Ctrl-C leaves from anywhere, even while filtering or asking
+                if map_key(code, modifiers) == MenuCommand::Interrupt {
+                    screen.leave();
+
+                    println!("\nSelection cancelled");
+                    return Ok(MenuPick::exit());
+                }
+                if let Some(held) = preview.take() {
+                    match code {
+                        KeyCode::Enter => {
+                            screen.leave();
+                            return Ok(held.pick);
+                        }
+                        KeyCode::Esc => state.select(held.previous_index),
+                        _ => preview = Some(held),
+                    }
+                    continue;
+                }
+                if let Some(alias) = confirm_delete.take() {
+                    if matches!(code, KeyCode::Char('y' | 'Y')) {
+                        let mut latest = ConfigStorage::default();
+                        let deleted = latest
+                            .with_lock(|latest| {
+                                latest.remove_configuration(&alias);
+                                Ok(())
+                            })
+                            .map(|()| latest);
+                        match deleted {
+                            Ok(latest) => {
+                                state.remove(&alias);
+                                *reloaded_storage = Some(latest);
+                            }
+                            Err(e) => {
+                                screen.leave();
+                                return Err(e);
+                            }
+                        }
+                    }
+                    continue;
+                }
+                let quiet = storage.preferences.fast_select && state.is_first_event_selection(code);
+                state.keys_seen += 1;
+                state.notice = None;
+                if state.filtering {
+                    match code {
+                        KeyCode::Esc => state.clear_filter(),
+                        KeyCode::Backspace => {
+                            let mut query = state.query.clone();
+                            query.pop();
+                            state.set_query(query);
+                        }
+                        KeyCode::Enter if state.selected_config().is_some() => {
+                            let configs: Vec<&Configuration> = state.configs.iter().collect();
+                            let pick = MenuPick {
+                                selection: handle_selection_action(&configs, state.action_index()),
+                                quiet: false,
+                            };
+                            let previous_index = state.selected_index;
+                            if let Some(pick) = leave_or_preview(
+                                screen,
+                                storage,
+                                &mut preview,
+                                pick,
+                                previous_index,
+                            ) {
+                                return Ok(pick);
+                            }
+                            pending.clear();
+                        }
+                        KeyCode::Char(c) if !c.is_control() => {
+                            let query = format!("{}{c}", state.query);
+                            state.set_query(query);
+                        }
+                        KeyCode::Up
+                        | KeyCode::Down
+                        | KeyCode::Home
+                        | KeyCode::End
+                        | KeyCode::PageUp
+                        | KeyCode::PageDown => {
+                            state.navigate(code);
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+                if state.number.pending().is_some() {
+                    match code {
+                        KeyCode::Char(c) if c.is_ascii_digit() => {}
+                        KeyCode::Enter => {
+                            if let Some(target) = state.finish_number() {
+                                let (pick, previous_index) =
+                                    pick_position(state, target, number_quiet);
+                                if let Some(pick) = leave_or_preview(
+                                    screen,
+                                    storage,
+                                    &mut preview,
+                                    pick,
+                                    previous_index,
+                                ) {
+                                    return Ok(pick);
+                                }
+                                pending.clear();
+                            }
+                            continue;
+                        }
+                        KeyCode::Esc => {
+                            state.number.clear();
+                            continue;
+                        }
+                        // Any other key drops the number and acts as usual
+                        _ => state.number.clear(),
+                    }
+                }
+                if state.navigate(code) {
+                    continue;
+                }
+                let configs: Vec<&Configuration> = state.configs.iter().collect();
+                match code {
+                    KeyCode::Tab => state.toggle_group(),
+                    KeyCode::Char('/') => state.filtering = true,
+                    KeyCode::Char('m') | KeyCode::Char('M') => state.toggle_grouped(),
+                    KeyCode::Enter
+                        if matches!(
+                            state.row_at(state.selected_index),
+                            Some(MenuRow::Header { .. })
+                        ) =>
+                    {
+                        // Enter on a collapsed group expands it
+                        state.toggle_group();
+                    }
+                    KeyCode::Enter => {
+                        let pick = MenuPick {
+                            selection: handle_selection_action(&configs, state.action_index()),
+                            quiet: false,
+                        };
+                        let previous_index = state.selected_index;
+                        if let Some(pick) =
+                            leave_or_preview(screen, storage, &mut preview, pick, previous_index)
+                        {
+                            return Ok(pick);
+                        }
+                        pending.clear();
+                    }
+                    KeyCode::Esc => {
+                        // Clean up terminal before exit
+                        screen.leave();
+
+                        println!("\nSelection cancelled");
+                        return Ok(MenuPick::exit());
+                    }
+                    KeyCode::Char(c) if c.is_ascii_digit() => {
+                        if state.number.pending().is_none() {
+                            number_quiet = quiet;
+                        }
+                        // Numbers count configurations across all pages
+                        if let Some(target) = state.type_digit(c, Instant::now()) {
+                            let (pick, previous_index) = pick_position(state, target, number_quiet);
+                            if let Some(pick) = leave_or_preview(
+                                screen,
+                                storage,
+                                &mut preview,
+                                pick,
+                                previous_index,
+                            ) {
+                                return Ok(pick);
+                            }
+                            pending.clear();
+                        }
+                        // A pending or unknown number picks nothing yet
+                    }
+                    KeyCode::Char('r') | KeyCode::Char('R') => {
+                        let pick = MenuPick {
+                            selection: Selection::Official,
+                            quiet,
+                        };
+                        let previous_index = state.selected_index;
+                        if let Some(pick) =
+                            leave_or_preview(screen, storage, &mut preview, pick, previous_index)
+                        {
+                            return Ok(pick);
+                        }
+                        pending.clear();
+                    }
+                    KeyCode::Char('-') => {
+                        if let Some(alias) = &storage.last_used {
+                            let pick = MenuPick {
+                                selection: Selection::Config(alias.clone()),
+                                quiet: false,
+                            };
+                            let previous_index = state.selected_index;
+                            if let Some(pick) = leave_or_preview(
+                                screen,
+                                storage,
+                                &mut preview,
+                                pick,
+                                previous_index,
+                            ) {
+                                return Ok(pick);
+                            }
+                            pending.clear();
+                        }
+                    }
+                    KeyCode::Char('e') | KeyCode::Char('E')
+                        if state.selected_config().is_some() =>
+                    {
+                        screen.leave();
+                        // Keys typed ahead of the edit are not meant for the menu
+                        pending.clear();
+                        let edit_result = match state.selected_config() {
+                            Some(config) => handle_config_edit(config, &mask),
+                            None => continue,
+                        };
+                        if screen.resume() {
+                            // Storage may have changed underneath the menu even when
+                            // the edit was abandoned, so refresh in both cases
+                            let focus = match edit_result {
+                                Ok(saved_alias) => saved_alias,
+                                Err(e)
+                                    if e.downcast_ref::<EditModeError>()
+                                        == Some(&EditModeError::ReturnToMenu) =>
+                                {
+                                    match state.selected_config() {
+                                        Some(config) => config.alias_name.clone(),
+                                        None => continue,
+                                    }
+                                }
+                                Err(e) => {
+                                    screen.leave();
+                                    return Err(e);
+                                }
+                            };
+                            if let Ok(latest) = ConfigStorage::load() {
+                                state.reload(
+                                    latest.configurations.values().cloned().collect(),
+                                    &focus,
+                                );
+                                *reloaded_storage = Some(latest);
+                            }
+                            continue;
+                        }
+                    }
+                    KeyCode::Char('e') | KeyCode::Char('E') => {}
+                    KeyCode::Char('a' | 'A') => {
+                        // Keys typed ahead of the prompts are not meant for the menu
+                        pending.clear();
+                        add_and_reload(screen, state, reloaded_storage);
+                    }
+                    KeyCode::Char('d' | 'D') | KeyCode::Delete => {
+                        // Official, Exit and group headers can't be deleted
+                        if let Some(config) = state.selected_config() {
+                            confirm_delete = Some(config.alias_name.clone());
+                            // Keys typed ahead are not an answer to the prompt
+                            pending.clear();
+                        }
+                    }
+                    KeyCode::Char('q') | KeyCode::Char('Q') => {
+                        // Clean up terminal before processing selection
+                        screen.leave();
+
+                        if !quiet {
+                            println!("\nExiting...");
+                        }
+                        return Ok(MenuPick::exit());
+                    }
+                    _ => {}
+                }
+            }
+            Event::Key(_) => {} // Ignore key release events
+            Event::Resize(columns, rows) => {
+                state.set_layout(MenuLayout::for_terminal(
+                    columns as usize,
+                    rows as usize,
+                    MenuState::MAX_WIDTH,
+                ));
+                screen.force_redraw(columns as usize);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A menu pick held in the launch preview until Enter confirms it
+struct LaunchPreview {
+    pick: MenuPick,
+    /// Configuration shown in the preview; `None` for the official one
+    config: Option<Configuration>,
+    /// Highlighted row restored when Esc dismisses the preview
+    previous_index: usize,
+}
+
+/// Select position `target`, showing its page, and pick it
+///
+/// Returns the pick and the position selected before, which Esc in the
+/// launch preview goes back to.
+fn pick_position(state: &mut MenuState, target: usize, quiet: bool) -> (MenuPick, usize) {
+    let previous_index = state.selected_index;
+    state.select(target);
+    let configs: Vec<&Configuration> = state.configs.iter().collect();
+    let pick = MenuPick {
+        selection: handle_selection_action(&configs, state.action_index()),
+        quiet,
+    };
+    (pick, previous_index)
+}
+
+/// Leave the menu with `pick`, or hold it in the launch preview when the
+/// `confirm_before_launch` preference is set
+///
+/// Returns the pick when the menu should return it now. Exit is never
+/// previewed.
+fn leave_or_preview(
+    screen: &mut MenuScreen,
+    storage: &ConfigStorage,
+    preview: &mut Option<LaunchPreview>,
+    pick: MenuPick,
+    previous_index: usize,
+) -> Option<MenuPick> {
+    if !storage.preferences.confirm_before_launch || pick.selection == Selection::Exit {
+        screen.leave();
+        return Some(pick);
+    }
+    let config = match &pick.selection {
+        Selection::Config(alias) => storage.get_configuration(alias).cloned(),
+        _ => None,
+    };
+    *preview = Some(LaunchPreview {
+        pick,
+        config,
+        previous_index,
+    });
+    None
+}
+
+/// Frame of the launch preview: the full details of the picked
+/// configuration, or the official one when `config` is `None`
+fn render_launch_preview(
+    config: Option<&Configuration>,
+    mask: &FieldMask,
+    width: usize,
+) -> Vec<String> {
+    let border = BorderDrawing::new();
+    let mut frame = vec![
+        format!("\r{}", border.draw_top_border("Launch", width).green()),
+        format!(
+            "\r{}",
+            border
+                .draw_middle_line("Enter to launch, Esc to go back", width)
+                .green()
+        ),
+        format!("\r{}", border.draw_bottom_border(width).green()),
+        String::new(),
+    ];
+    match config {
+        Some(config) => {
+            frame.push(format!(
+                "\r  {}{}{}",
+                color_label(config),
+                config.alias_name.blue().bold(),
+                tags_note(config).dimmed()
+            ));
+            frame.extend(format_config_details(config, "\r    ", false, mask));
+        }
+        None => {
+            frame.push(format!("\r  {}", "official".red().bold()));
+            frame.push("\r    Use official Claude API (no custom configuration)".to_string());
+        }
+    }
+    frame
+}
+
+/// Leave the menu, run the add prompts and take the terminal back
+///
+/// On success the menu lists the new configuration, highlighted, and
+/// `reloaded_storage` receives the storage it was saved to. Returns false
+/// when the terminal could not be taken back.
+fn add_and_reload(
+    screen: &mut MenuScreen,
+    state: &mut MenuState,
+    reloaded_storage: &mut Option<ConfigStorage>,
+) -> bool {
+    screen.leave();
+    let added = add_from_menu();
+    if !screen.resume() {
+        return false;
+    }
+    if let Some(alias) = added
+        && let Ok(latest) = ConfigStorage::load()
+    {
+        state.reload(latest.configurations.values().cloned().collect(), &alias);
+        *reloaded_storage = Some(latest);
+    }
+    true
+}
+
+/// Prompt for an alias and run the interactive add prompts for it
+///
+/// Returns the alias when a configuration was saved; `None` when the user
+/// cancelled (empty alias or credential) or adding failed, in which case
+/// the reason is shown until Enter is pressed.
+fn add_from_menu() -> Option<String> {
+    println!(
+        "\n{}",
+        "Add new configuration (empty alias cancels)".green().bold()
+    );
+    let added = (|| -> Result<Option<String>> {
+        let alias = read_input("Alias name: ")?;
+        if alias.is_empty() {
+            return Ok(None);
+        }
+        let mut storage = ConfigStorage::load()?;
+        if storage.get_configuration(&alias).is_some() {
+            anyhow::bail!("Configuration '{}' already exists", alias);
+        }
+        crate::cli::main::handle_interactive_add(&alias, &mut storage)?;
+        Ok(storage.get_configuration(&alias).is_some().then_some(alias))
+    })();
+    match added {
+        Ok(alias) => alias,
+        Err(e) => {
+            eprintln!("{}", format!("Error: {e}").red());
+            let _ = read_input("Press Enter to return to the menu...");
+            None
         }
     }
 }
@@ -717,7 +1720,7 @@ fn handle_full_interactive_menu(
 fn handle_simple_interactive_menu(
     configs: &[&Configuration],
     storage: &ConfigStorage,
-) -> Result<()> {
+) -> Result<Selection> {
     const PAGE_SIZE: usize = 9; // Same page size as full interactive menu
 
     // If configs fit in one page, show the simple original menu
@@ -727,6 +1730,7 @@ fn handle_simple_interactive_menu(
 
     // Multi-page simple menu
     let total_pages = configs.len().div_ceil(PAGE_SIZE);
+    let mask = FieldMask::from_storage(storage);
     let mut current_page = 0;
 
     loop {
@@ -752,13 +1756,16 @@ fn handle_simple_interactive_menu(
             let display_number = page_index + 1;
 
             println!(
-                "{}. {}",
+                "{}. {}{}{}{}",
                 format!("[{display_number}]").green().bold(),
-                config.alias_name.green()
+                default_marker(&config.alias_name, storage.default_alias.as_deref()),
+                config.alias_name.green(),
+                tags_note(config).dimmed(),
+                last_used_note(&config.alias_name, storage.last_used.as_deref())
             );
 
             // Show config details with consistent formatting
-            let details = format_config_details(config, "   ", true);
+            let details = format_config_details(config, "   ", true, &mask);
             for detail_line in details {
                 println!("{detail_line}");
             }
@@ -783,23 +1790,9 @@ fn handle_simple_interactive_menu(
         let choice = input.trim().to_lowercase();
 
         match choice.as_str() {
-            "r" => {
-                // Official option
-                println!("{}", "Using official Claude configuration".blue());
-
-                // Update settings.json to remove Anthropic configuration
-                let mut settings = crate::config::types::ClaudeSettings::load(
-                    storage.get_claude_settings_dir().map(|s| s.as_str()),
-                )?;
-                settings.remove_anthropic_env();
-                settings.save(storage.get_claude_settings_dir().map(|s| s.as_str()))?;
-
-                return launch_claude_with_env(
-                    crate::daemon::build_official_env(),
-                    None,
-                    None,
-                    false,
-                );
+            "r" => return Ok(Selection::Official),
+            "-" if storage.last_used.is_some() => {
+                return Ok(Selection::from_alias(storage.last_used_alias()?));
             }
             "e" => {
                 // Edit functionality for simple menu
@@ -808,7 +1801,7 @@ fn handle_simple_interactive_menu(
             }
             "q" => {
                 println!("Exiting...");
-                return Ok(());
+                return Ok(Selection::Exit);
             }
             "n" if total_pages > 1 && current_page < total_pages - 1 => {
                 current_page += 1;
@@ -825,13 +1818,7 @@ fn handle_simple_interactive_menu(
                 {
                     let actual_config_index = start_idx + (digit - 1);
                     let selection_index = actual_config_index + 1; // +1 because official is at index 0
-                    let storage_mode = storage.default_storage_mode.clone().unwrap_or_default();
-                    return handle_selection_action(
-                        configs,
-                        selection_index,
-                        storage,
-                        storage_mode,
-                    );
+                    return Ok(handle_selection_action(configs, selection_index));
                 }
                 println!("无效选择，请重新输入");
             }
@@ -843,8 +1830,9 @@ fn handle_simple_interactive_menu(
 fn handle_simple_single_page_menu(
     configs: &[&Configuration],
     storage: &ConfigStorage,
-) -> Result<()> {
+) -> Result<Selection> {
     println!("\n{}", "Available Configurations:".blue().bold());
+    let mask = FieldMask::from_storage(storage);
 
     // Add official option (first)
     println!("1. {}", "official".red());
@@ -853,13 +1841,16 @@ fn handle_simple_single_page_menu(
 
     for (index, config) in configs.iter().enumerate() {
         println!(
-            "{}. {}",
+            "{}. {}{}{}{}",
             index + 2, // +2 because official is at position 1
-            config.alias_name.green()
+            default_marker(&config.alias_name, storage.default_alias.as_deref()),
+            config.alias_name.green(),
+            tags_note(config).dimmed(),
+            last_used_note(&config.alias_name, storage.last_used.as_deref())
         );
 
         // Show config details with consistent formatting
-        let details = format_config_details(config, "   ", true);
+        let details = format_config_details(config, "   ", true, &mask);
         for detail_line in details {
             println!("{detail_line}");
         }
@@ -874,643 +1865,1892 @@ fn handle_simple_single_page_menu(
     let mut input = String::new();
     io::stdin().read_line(&mut input)?;
 
-    match input.trim().parse::<usize>() {
-        Ok(1) => {
-            // Official option
-            println!("{}", "Using official Claude configuration".blue());
-
-            // Update settings.json to remove Anthropic configuration
-            let mut settings = crate::config::types::ClaudeSettings::load(
-                storage.get_claude_settings_dir().map(|s| s.as_str()),
-            )?;
-            settings.remove_anthropic_env();
-            settings.save(storage.get_claude_settings_dir().map(|s| s.as_str()))?;
+    if input.trim() == "-"
+        && let Some(alias) = &storage.last_used
+    {
+        return Ok(Selection::Config(alias.clone()));
+    }
 
-            launch_claude_with_env(crate::daemon::build_official_env(), None, None, false)
-        }
-        Ok(num) if num >= 2 && num <= configs.len() + 1 => {
-            let storage_mode = storage.default_storage_mode.clone().unwrap_or_default();
-            handle_selection_action(configs, num - 1, storage, storage_mode) // -1 to account for official option at index 0
+    match input.trim().parse::<usize>() {
+        // -1 to account for official option at position 1
+        Ok(num) if num >= 1 && num <= configs.len() + 1 => {
+            Ok(handle_selection_action(configs, num - 1))
         }
         Ok(num) if num == configs.len() + 2 => {
             println!("Exiting...");
-            Ok(())
+            Ok(Selection::Exit)
         }
         _ => {
             println!("Invalid selection");
+            Ok(Selection::Exit)
+        }
+    }
+}
+
+/// What was picked in a configuration menu, or named on the command line
+#[derive(Debug, Clone, PartialEq)]
+pub enum Selection {
+    /// The official Claude configuration
+    Official,
+    /// A stored configuration, possibly `alias@variant`
+    Config(String),
+    /// Leave without launching
+    Exit,
+}
+
+impl Selection {
+    /// Selection for an alias given on the command line
+    pub fn from_alias(alias: &str) -> Self {
+        if crate::config::is_official_alias(alias) {
+            Selection::Official
+        } else {
+            Selection::Config(alias.to_string())
+        }
+    }
+}
+
+/// Menu result: the selection and whether it was a fast selection
+#[derive(Debug, PartialEq)]
+struct MenuPick {
+    selection: Selection,
+    quiet: bool,
+}
+
+impl MenuPick {
+    fn exit() -> Self {
+        MenuPick {
+            selection: Selection::Exit,
+            quiet: false,
+        }
+    }
+}
+
+/// Map a menu index to a selection: 0 is official, `1..=configs.len()`
+/// are configurations and anything after is Exit
+fn handle_selection_action(configs: &[&Configuration], selected_index: usize) -> Selection {
+    match selected_index {
+        0 => Selection::Official,
+        index if index <= configs.len() => Selection::Config(configs[index - 1].alias_name.clone()),
+        _ => Selection::Exit,
+    }
+}
+
+/// Launch settings that come from the command line rather than the selection
+#[derive(Debug, Clone, Default)]
+pub struct LaunchOptions {
+    /// Passthrough arguments (`--claude-arg`)
+    pub claude_args: Vec<String>,
+    pub prompt: Option<String>,
+    pub resume: Option<String>,
+    pub continue_session: bool,
+    /// Launch profile run instead of Claude (`--with`)
+    pub with: Option<String>,
+    /// Skip the confirmation of tagged configurations (`--yes`)
+    pub yes: bool,
+    /// Show the configuration and ask before launching (`--confirm`)
+    pub confirm: bool,
+    /// Print only `→ alias` and no command preview (fast select)
+    pub quiet: bool,
+    /// Launch without `--dangerously-skip-permissions` whatever the
+    /// preference says (`--no-skip-permissions`)
+    pub no_skip_permissions: bool,
+    /// Storage mode for this launch instead of the stored default (`--mode`)
+    pub storage_mode: Option<StorageMode>,
+    /// Remove stale Anthropic variables from settings.json before an
+    /// official launch without asking (`--clean-settings`)
+    pub clean_settings: bool,
+    /// Unset inherited Claude variables the selection doesn't set, instead
+    /// of warning about them (`--clear-env`)
+    pub clear_env: bool,
+    /// Persist the switch and record it as last used, but don't start
+    /// Claude (`--no-launch`)
+    pub no_launch: bool,
+}
+
+impl LaunchOptions {
+    /// Storage mode a launch from `storage` uses
+    pub fn effective_storage_mode(&self, storage: &ConfigStorage) -> StorageMode {
+        self.storage_mode
+            .clone()
+            .or_else(|| storage.default_storage_mode.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Last steps of a launch, behind a trait so the pipeline can be tested
+pub trait Launcher {
+    /// Remember `alias_name` as the last configuration used
+    fn record_use(&mut self, alias_name: &str);
+
+    /// Run `invocation` with `env_config`; `quiet` skips the preview
+    fn launch(
+        &mut self,
+        env_config: EnvironmentConfig,
+        invocation: &ClaudeInvocation,
+        quiet: bool,
+    ) -> Result<()>;
+}
+
+/// Launcher replacing cc-switch with the launched process
+pub struct ExecLauncher;
+
+impl Launcher for ExecLauncher {
+    fn record_use(&mut self, alias_name: &str) {
+        // Update the latest storage under the lock so changes made since
+        // this process started, and concurrent launches, are kept; a
+        // failure only loses the history, never the launch
+        let recorded = ConfigStorage::default().with_lock(|latest| {
+            latest.record_use(alias_name, chrono::Utc::now());
             Ok(())
+        });
+        if let Err(e) = recorded {
+            eprintln!("Warning: could not record '{alias_name}' as last used: {e}");
+        }
+    }
+
+    fn launch(
+        &mut self,
+        env_config: EnvironmentConfig,
+        invocation: &ClaudeInvocation,
+        quiet: bool,
+    ) -> Result<()> {
+        if quiet {
+            exec_claude_invocation(env_config, invocation)
+        } else {
+            launch_claude_invocation(env_config, invocation)
         }
     }
 }
 
-/// Handle the actual selection and configuration switch
-fn handle_selection_action(
-    configs: &[&Configuration],
-    selected_index: usize,
+/// Apply `selection` to settings.json and launch it
+///
+/// Shared by the interactive menus and `cc-switch use`, so both go through
+/// the same confirmation, daemon proxy substitution, settings update and
+/// launcher.
+///
+/// # Errors
+/// Returns error if the alias doesn't resolve, confirmation is required but
+/// impossible, settings can't be written or the launch fails
+pub fn launch_selection(
     storage: &ConfigStorage,
-    storage_mode: crate::config::types::StorageMode,
+    selection: &Selection,
+    options: &LaunchOptions,
+    prompter: &mut dyn Prompter,
+    launcher: &mut dyn Launcher,
 ) -> Result<()> {
-    if selected_index == 0 {
-        // Official option (reset to default)
-        println!("{}", "\nUsing official Claude configuration".blue());
+    let settings_dir = storage.get_claude_settings_dir().map(|s| s.as_str());
+    let skip_permissions = !options.no_skip_permissions && storage.preferences.skip_permissions();
+    let alias_name = match selection {
+        Selection::Exit => return Ok(()),
+        Selection::Official => None,
+        Selection::Config(alias) => Some(alias.as_str()),
+    };
+    let target = match alias_name {
+        Some(alias) => crate::config::resolve_alias_target(storage, alias)?,
+        None => crate::config::AliasTarget::Official,
+    };
+    if options.confirm {
+        let config: Option<&Configuration> = match &target {
+            crate::config::AliasTarget::Config(config) => Some(config),
+            crate::config::AliasTarget::Official => None,
+        };
+        if !confirm_launch(config, &FieldMask::from_storage(storage), prompter)? {
+            println!("Aborted");
+            return Ok(());
+        }
+    }
 
-        // Update settings.json to remove Anthropic configuration
-        let mut settings = crate::config::types::ClaudeSettings::load(
-            storage.get_claude_settings_dir().map(|s| s.as_str()),
-        )?;
-        settings.remove_anthropic_env();
-        settings.save(storage.get_claude_settings_dir().map(|s| s.as_str()))?;
+    let (Some(alias_name), crate::config::AliasTarget::Config(config)) = (alias_name, target)
+    else {
+        if options.with.is_some() {
+            anyhow::bail!(
+                "'{}' has no launch profiles; --with requires a stored configuration",
+                alias_name.unwrap_or("official")
+            );
+        }
+        if options.quiet {
+            println!("→ official");
+        } else {
+            println!("{}", "\nUsing official Claude configuration".blue());
+        }
 
-        launch_claude_with_env(crate::daemon::build_official_env(), None, None, false)
-    } else if selected_index <= configs.len() {
-        // Switch to selected configuration
-        let config_index = selected_index - 1; // -1 because official is at index 0
-        let mut selected_config = configs[config_index].clone();
+        let program = if options.no_launch {
+            None
+        } else {
+            Some(crate::platform::resolve_claude_binary(storage)?)
+        };
+        clean_official_settings(
+            settings_dir,
+            options.clean_settings || options.yes,
+            prompter,
+        )?;
+        let Some(program) = program else {
+            println!("Not launching Claude (--no-launch)");
+            return Ok(());
+        };
 
-        // Consult daemon state: substitute proxy URL if daemon is alive.
-        let original_url = selected_config.url.clone();
         crate::daemon::print_version_mismatch_warning();
-        match crate::daemon::try_resolve_proxy(&selected_config.url) {
-            crate::daemon::ProxyResolution::Proxied { proxy_url } => {
-                selected_config.url = proxy_url;
-            }
-            crate::daemon::ProxyResolution::Direct => {
-                if !original_url.is_empty() {
-                    eprintln!(
-                        "\u{2139} cc daemon is not running \u{2014} traffic for '{}' will NOT be captured.",
-                        selected_config.alias_name
-                    );
-                    eprintln!("  Run `cc-switch daemon start` and re-run to enable capture.");
-                }
+        let mut env_config = crate::daemon::build_official_env();
+        handle_env_conflicts(&mut env_config, "official", options.clear_env);
+        let invocation = ClaudeInvocation::new(
+            skip_permissions,
+            &options.claude_args,
+            None,
+            None,
+            options.continue_session,
+        )
+        .with_program(program);
+        return launcher.launch(env_config, &invocation, options.quiet);
+    };
+
+    let mut config = config.into_owned();
+    if let Some(name) = options.with.as_deref() {
+        config.launch_profile(name)?;
+    }
+
+    // An answer to --confirm already covers the confirm tags
+    let yes = options.yes || options.confirm;
+    if !confirm_tagged_launch(&config, &storage.preferences, yes, prompter)? {
+        println!("Aborted");
+        return Ok(());
+    }
+
+    // Consult daemon state: substitute proxy URL if daemon is alive.
+    let original_url = config.url.clone();
+    crate::daemon::print_version_mismatch_warning();
+    match crate::daemon::try_resolve_proxy(&config.url) {
+        crate::daemon::ProxyResolution::Proxied { proxy_url } => {
+            config.url = proxy_url;
+        }
+        crate::daemon::ProxyResolution::Direct => {
+            if !original_url.is_empty() {
+                eprintln!(
+                    "{}",
+                    format!(
+                        "\u{2139} cc daemon is not running \u{2014} traffic for '{alias_name}' will NOT be captured."
+                    )
+                    .blue()
+                );
+                eprintln!(
+                    "{}",
+                    "  Run `cc-switch daemon start` and re-run to enable capture.".blue()
+                );
             }
         }
+    }
+
+    // The token command runs once; settings.json and the environment share its token
+    let resolved = config.with_resolved_token()?;
+    // Built before settings.json is touched, so a missing binary changes nothing
+    let mut plan = LaunchPlan::build(alias_name, &resolved, storage, options)?;
+    if plan.invocation.is_some() {
+        handle_env_conflicts(&mut plan.env_config, alias_name, options.clear_env);
+    }
 
-        let env_config = EnvironmentConfig::from_config(&selected_config)
-            .with_alias(&selected_config.alias_name);
+    // Update settings.json with the configuration
+    let mut settings = ClaudeSettings::load(settings_dir)?;
+    settings.skip_write_verification = storage.preferences.skip_write_verification;
+    settings.switch_to_config_with_mode(&resolved, plan.storage_mode.clone(), settings_dir)?;
 
+    if options.quiet {
+        println!("→ {alias_name}");
+    } else {
         println!(
             "\nSwitched to configuration '{}'",
-            selected_config.alias_name.green().bold()
+            alias_name.green().bold()
         );
 
         // Show selected configuration details with consistent formatting
-        let details = format_config_details(&selected_config, "", false);
-        for detail_line in details {
+        let mask = FieldMask::from_storage(storage);
+        for detail_line in format_config_details(&config, "", false, &mask) {
             println!("{detail_line}");
         }
-        if selected_config.url != original_url {
-            println!("  (proxied from: {})", original_url);
+        if config.url != original_url {
+            println!("  (proxied from: {})", mask.apply("url", original_url));
+        }
+        if let Some(quota) = crate::config::quota::probe_quota(&resolved) {
+            println!("  {quota}");
+        }
+    }
+
+    launcher.record_use(&config.alias_name);
+    let Some(invocation) = plan.invocation else {
+        match plan.storage_mode {
+            StorageMode::Config => {
+                println!(
+                    "Not launching Claude (--no-launch); settings.json now holds '{alias_name}'"
+                )
+            }
+            StorageMode::Env => println!(
+                "Not launching Claude (--no-launch); apply '{alias_name}' to this shell with: eval \"$(cc-switch env {alias_name})\""
+            ),
+        }
+        return Ok(());
+    };
+    launcher.launch(plan.env_config, &invocation, options.quiet)
+}
+
+/// Quote a single argument for display in a POSIX shell command line
+///
+/// Arguments made only of shell-safe characters are left as is; anything
+/// else is wrapped in single quotes with embedded quotes escaped.
+pub fn shell_quote(arg: &str) -> String {
+    let safe = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-./=:@,+%".contains(c));
+    if safe {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// The exact Claude CLI command line to run
+///
+/// Built once per launch; the "Executing:" preview and the process that is
+/// exec'd or spawned are both derived from the same program and argv.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClaudeInvocation {
+    /// Program to run (`claude`, or the `CLAUDE_BINARY` override)
+    pub program: std::path::PathBuf,
+    /// Arguments passed to the program, in order
+    pub args: Vec<String>,
+}
+
+impl ClaudeInvocation {
+    /// Build the argv for a Claude launch
+    ///
+    /// # Arguments
+    /// * `skip_permissions` - Whether to add `--dangerously-skip-permissions`
+    /// * `extra_args` - Passthrough arguments (e.g. from `--claude-arg`)
+    /// * `prompt` - Optional prompt, always passed last
+    /// * `resume` - Optional session ID for `--resume`
+    /// * `continue_session` - Whether to add `--continue`
+    pub fn new(
+        skip_permissions: bool,
+        extra_args: &[String],
+        prompt: Option<&str>,
+        resume: Option<&str>,
+        continue_session: bool,
+    ) -> Self {
+        let mut args = Vec::new();
+        if skip_permissions {
+            args.push("--dangerously-skip-permissions".to_string());
+        }
+        args.extend(extra_args.iter().cloned());
+        if let Some(session_id) = resume {
+            args.push("--resume".to_string());
+            args.push(session_id.to_string());
         }
+        if continue_session {
+            args.push("--continue".to_string());
+        }
+        if let Some(p) = prompt {
+            args.push(p.to_string());
+        }
+
+        ClaudeInvocation {
+            program: resolve_npm_cli("claude"),
+            args,
+        }
+    }
+
+    /// Run `program` instead of the default Claude binary
+    pub fn with_program(mut self, program: std::path::PathBuf) -> Self {
+        self.program = program;
+        self
+    }
+
+    /// Build the argv for a launch profile instead of Claude
+    ///
+    /// Claude-specific flags are not added: the profile's own arguments come
+    /// first, then the passthrough arguments, then the prompt.
+    pub fn for_profile(
+        profile: &LaunchProfile,
+        extra_args: &[String],
+        prompt: Option<&str>,
+    ) -> Self {
+        let mut args = profile.args.clone();
+        args.extend(extra_args.iter().cloned());
+        if let Some(p) = prompt {
+            args.push(p.to_string());
+        }
+
+        ClaudeInvocation {
+            program: resolve_npm_cli(&profile.command),
+            args,
+        }
+    }
+
+    /// Render the command line with shell quoting, as shown to the user
+    pub fn command_line(&self) -> String {
+        std::iter::once(self.program.to_string_lossy().into_owned())
+            .chain(self.args.iter().cloned())
+            .map(|arg| shell_quote(&arg))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Create the process command for this invocation
+    pub fn to_command(&self, env_config: &EnvironmentConfig) -> Command {
+        let mut command = Command::new(&self.program);
+        // Drop inherited values first so the variables set below still win
+        for key in &env_config.unset_keys {
+            command.env_remove(key);
+        }
+        // Explicitly pass environment variables to ensure they're inherited
+        command.envs(env_config.as_env_tuples());
+        command.args(&self.args);
+        command
+    }
+}
+
+/// Show what `use --confirm` is about to launch and ask to go ahead
+///
+/// `config` is `None` for the official configuration. Returns `Ok(true)`
+/// to proceed and `Ok(false)` when the user declines.
+///
+/// # Errors
+/// Fails without prompting when no terminal is available to ask
+pub fn confirm_launch(
+    config: Option<&Configuration>,
+    mask: &FieldMask,
+    prompter: &mut dyn Prompter,
+) -> Result<bool> {
+    let name = config.map_or("official", |config| config.alias_name.as_str());
+    if !prompter.is_interactive() {
+        anyhow::bail!("--confirm needs a terminal to ask before launching '{name}'");
+    }
+    match config {
+        Some(config) => {
+            println!(
+                "{}{}",
+                color_label(config),
+                config.alias_name.green().bold()
+            );
+            for detail_line in format_config_details(config, "", false, mask) {
+                println!("{detail_line}");
+            }
+        }
+        None => println!("{}", "official: Use official Claude API".red().bold()),
+    }
+    prompter.confirm(&format!("Launch '{name}'? [y/N] "))
+}
+
+/// Warn about inherited Claude variables that would mix with `env_config`
+///
+/// With `clear` (`--clear-env`) they are unset for the child instead.
+/// Values of tokens and keys are masked.
+fn handle_env_conflicts(env_config: &mut EnvironmentConfig, name: &str, clear: bool) {
+    let conflicts = env_config.unmanaged_conflicts();
+    if conflicts.is_empty() {
+        return;
+    }
+
+    if clear {
+        let keys: Vec<String> = conflicts.into_iter().map(|(key, _)| key).collect();
+        eprintln!("Unset for this launch: {}", keys.join(", "));
+        env_config.unset_keys.extend(keys);
+        return;
+    }
+
+    eprintln!(
+        "{}",
+        format!("\u{26a0} Inherited variables not set by '{name}' will still reach Claude:")
+            .yellow()
+    );
+    for (key, value) in conflicts {
+        let value = if key.contains("TOKEN") || key.contains("KEY") {
+            format_token_for_display(&value)
+        } else {
+            value
+        };
+        eprintln!("   {key}={value}");
+    }
+    eprintln!(
+        "{}",
+        "  Re-run with --clear-env to unset them for this launch.".yellow()
+    );
+}
+
+/// Remove Anthropic variables left in settings.json before an official launch
+///
+/// Claude reads the `env` block of settings.json itself, so values written
+/// there by config mode or another tool would still apply to the official
+/// session. On a terminal the user is asked first unless `clean` is set;
+/// without one they are removed. The removed keys are printed.
+///
+/// # Errors
+/// Fails when settings.json cannot be read or written
+pub fn clean_official_settings(
+    settings_dir: Option<&str>,
+    clean: bool,
+    prompter: &mut dyn Prompter,
+) -> Result<()> {
+    let mut settings = ClaudeSettings::load(settings_dir)?;
+    let stale: Vec<&str> = crate::config::ANTHROPIC_ENV_VARS
+        .iter()
+        .copied()
+        .filter(|name| settings.env.contains_key(*name))
+        .collect();
+    if stale.is_empty() {
+        return Ok(());
+    }
+
+    if !clean && prompter.is_interactive() {
+        println!("settings.json still sets {}", stale.join(", "));
+        if !prompter.confirm("Remove them before launching? [y/N] ")? {
+            println!(
+                "{}",
+                "Keeping them: Claude will use these values instead of the official setup".yellow()
+            );
+            return Ok(());
+        }
+    }
+
+    settings.remove_anthropic_env();
+    settings.save(settings_dir)?;
+    println!("Removed from settings.json: {}", stale.join(", "));
+    Ok(())
+}
+
+/// Ask before launching a configuration carrying one of the `confirm-tags`
+///
+/// Returns `Ok(true)` to proceed and `Ok(false)` when the user declines.
+///
+/// # Errors
+/// Fails without prompting when confirmation is required, `yes` is unset
+/// and no terminal is available to ask
+pub fn confirm_tagged_launch(
+    config: &Configuration,
+    preferences: &Preferences,
+    yes: bool,
+    prompter: &mut dyn Prompter,
+) -> Result<bool> {
+    let Some(tag) = preferences.confirm_tag_for(config) else {
+        return Ok(true);
+    };
+    if yes {
+        return Ok(true);
+    }
+    if !prompter.is_interactive() {
+        anyhow::bail!(
+            "Configuration '{}' is tagged '{}' and requires confirmation; re-run with --yes",
+            config.alias_name,
+            tag
+        );
+    }
+    prompter.confirm(&format!(
+        "You are switching to a '{tag}' configuration \u{2014} continue? [y/N] "
+    ))
+}
+
+/// Launch Claude CLI with environment variables and exec to replace current process
+///
+/// `extra_args` are passed through after `--dangerously-skip-permissions`,
+/// which is added when `skip_permissions` is set.
+pub fn launch_claude_with_env(
+    env_config: EnvironmentConfig,
+    skip_permissions: bool,
+    extra_args: &[String],
+    prompt: Option<&str>,
+    resume: Option<&str>,
+    continue_session: bool,
+) -> Result<()> {
+    let program = crate::platform::resolve_claude_binary(&ConfigStorage::load()?)?;
+    let invocation = ClaudeInvocation::new(
+        skip_permissions,
+        extra_args,
+        prompt,
+        resume,
+        continue_session,
+    )
+    .with_program(program);
+    launch_claude_invocation(env_config, &invocation)
+}
+
+/// Launch a prepared Claude invocation, exec'ing on Unix and spawning elsewhere
+///
+/// Every launch that prints a preview goes through here, so the preview and
+/// the launch itself cannot drift between entry points.
+pub fn launch_claude_invocation(
+    env_config: EnvironmentConfig,
+    invocation: &ClaudeInvocation,
+) -> Result<()> {
+    println!("\nLaunching Claude CLI...");
+    println!("Executing: {}", invocation.command_line());
+    exec_claude_invocation(env_config, invocation)
+}
+
+/// Run a prepared Claude invocation without printing a preview
+fn exec_claude_invocation(
+    env_config: EnvironmentConfig,
+    invocation: &ClaudeInvocation,
+) -> Result<()> {
+    // Clean up orphaned alias files from terminated sessions
+    let _ = ClaudeSettings::cleanup_orphan_alias_files();
+
+    // Write per-PID alias file for statusLine isolation
+    // On Unix with exec, this file won't be cleaned up (orphan), but that's acceptable
+    // On non-Unix, we clean it after the process exits
+    if let Some(alias) = env_config.env_vars.get("CC_SWITCH_CURRENT_ALIAS") {
+        ClaudeSettings::write_current_alias_for_pid(alias)?;
+    }
+
+    let mut command = invocation.to_command(&env_config);
+
+    // On Unix systems, use exec to replace current process
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        let error = command.exec();
+        // exec never returns on success, so if we get here, it failed
+        // Clean up per-PID file on exec failure
+        let _ = ClaudeSettings::clear_current_alias_for_pid();
+        anyhow::bail!("Failed to exec {}: {}", invocation.program.display(), error);
+    }
+
+    // On non-Unix systems, spawn and wait, then exit with Claude's code
+    #[cfg(not(unix))]
+    {
+        let code = crate::platform::spawn_and_wait(&mut command);
+
+        // Clean up per-PID file after Claude exits
+        let _ = ClaudeSettings::clear_current_alias_for_pid();
+
+        let code = code.with_context(|| {
+            format!(
+                "Failed to launch {}. Make sure 'claude' command is available in PATH",
+                invocation.program.display()
+            )
+        })?;
+        if code != 0 {
+            std::process::exit(code);
+        }
+        Ok(())
+    }
+}
+
+/// Execute claude command with or without --dangerously-skip-permissions using exec
+///
+/// # Arguments
+/// * `storage` - Supplies the skip-permissions preference and the Claude binary
+/// * `extra_args` - Passthrough arguments for Claude
+fn execute_claude_command(storage: &ConfigStorage, extra_args: &[String]) -> Result<()> {
+    let invocation = ClaudeInvocation::new(
+        storage.preferences.skip_permissions(),
+        extra_args,
+        None,
+        None,
+        false,
+    )
+    .with_program(crate::platform::resolve_claude_binary(storage)?);
+    launch_claude_invocation(EnvironmentConfig::default(), &invocation)
+}
+
+/// Read input from stdin with a prompt
+///
+/// # Arguments
+/// * `prompt` - The prompt to display to the user
+///
+/// # Returns
+/// The user's input as a String
+pub fn read_input(prompt: &str) -> Result<String> {
+    print!("{prompt}");
+    io::stdout().flush().context("Failed to flush stdout")?;
+    let mut input = String::new();
+    io::stdin()
+        .read_line(&mut input)
+        .context("Failed to read input")?;
+    Ok(input.trim().to_string())
+}
+
+/// Format configuration details with consistent indentation and alignment
+///
+/// This function provides unified formatting for configuration display across
+/// all interactive menus, ensuring consistent visual presentation. Fields
+/// listed in `mask` are rendered as `[hidden]`.
+///
+/// # Arguments
+/// * `config` - The configuration to format
+/// * `indent` - Base indentation string (e.g., "    " or "   ")
+/// * `compact` - Whether to use compact formatting (single line where possible)
+/// * `mask` - Fields whose values must not be displayed
+///
+/// # Returns
+/// Vector of formatted lines for configuration display
+fn format_config_details(
+    config: &Configuration,
+    indent: &str,
+    _compact: bool,
+    mask: &FieldMask,
+) -> Vec<String> {
+    let token_label = if config.api_key.is_some() {
+        "API Key:"
+    } else {
+        "Token:"
+    };
+    let number = |value: Option<u32>| value.map(|v| v.to_string());
+
+    // (field name, label, value) in display order; `None` values are skipped
+    let rows = [
+        ("token", token_label, Some(credential_for_display(config))),
+        ("url", "URL:", Some(config.url.clone())),
+        ("model", "Model:", config.model.clone()),
+        (
+            "small_fast_model",
+            "Small Fast Model:",
+            config.small_fast_model.clone(),
+        ),
+        (
+            "max_thinking_tokens",
+            "Max Thinking Tokens:",
+            number(config.max_thinking_tokens),
+        ),
+        (
+            "api_timeout_ms",
+            "API Timeout (ms):",
+            number(config.api_timeout_ms),
+        ),
+        (
+            "claude_code_disable_nonessential_traffic",
+            "Disable Nonessential Traffic:",
+            number(config.claude_code_disable_nonessential_traffic),
+        ),
+        (
+            "anthropic_default_sonnet_model",
+            "Default Sonnet Model:",
+            config.anthropic_default_sonnet_model.clone(),
+        ),
+        (
+            "anthropic_default_opus_model",
+            "Default Opus Model:",
+            config.anthropic_default_opus_model.clone(),
+        ),
+        (
+            "anthropic_default_haiku_model",
+            "Default Haiku Model:",
+            config.anthropic_default_haiku_model.clone(),
+        ),
+        (
+            "claude_code_subagent_model",
+            "Subagent Model:",
+            config.claude_code_subagent_model.clone(),
+        ),
+        (
+            "claude_code_disable_nonstreaming_fallback",
+            "Disable Nonstreaming Fallback:",
+            number(config.claude_code_disable_nonstreaming_fallback),
+        ),
+        (
+            "claude_code_effort_level",
+            "Effort Level:",
+            config.claude_code_effort_level.clone(),
+        ),
+        (
+            "disable_prompt_caching",
+            "Disable Prompt Caching:",
+            number(config.disable_prompt_caching),
+        ),
+        (
+            "claude_code_disable_experimental_betas",
+            "Disable Experimental Betas:",
+            number(config.claude_code_disable_experimental_betas),
+        ),
+        (
+            "disable_autoupdater",
+            "Disable Auto-Updater:",
+            number(config.disable_autoupdater),
+        ),
+        ("tags", "Tags:", config.field_value("tags")),
+        ("color", "Color:", config.color.clone()),
+        (
+            "launch_profiles",
+            "Launch Profiles:",
+            Some(config.launch_profile_names().join(", ")).filter(|names| !names.is_empty()),
+        ),
+        // Keyed as "url" so hiding the URL hides its variants too
+        ("url", "URL Variants:", format_url_variants(config)),
+        ("extra_env", "Extra Env:", config.field_value("extra_env")),
+        (
+            "launch_args",
+            "Launch Args:",
+            config.field_value("launch_args"),
+        ),
+    ];
+
+    // Find the widest label for alignment
+    let max_label_width = rows
+        .iter()
+        .map(|(_, label, _)| text_display_width(label))
+        .max()
+        .unwrap_or(0);
+
+    rows.into_iter()
+        .filter_map(|(field, label, value)| {
+            let value = value?;
+            let rendered = if mask.is_hidden(field) {
+                HIDDEN_PLACEHOLDER.dimmed()
+            } else {
+                match field {
+                    "token" => value.dimmed(),
+                    "url" => value.cyan(),
+                    _ => value.yellow(),
+                }
+            };
+            Some(format!(
+                "{}{} {}",
+                indent,
+                pad_text_to_width(label, max_label_width, TextAlignment::Left, ' '),
+                rendered
+            ))
+        })
+        .collect()
+}
+
+/// Render URL variants as `name=url` pairs, `None` when there are none
+fn format_url_variants(config: &Configuration) -> Option<String> {
+    if config.url_variants.is_empty() {
+        return None;
+    }
+    Some(
+        config
+            .url_variants
+            .iter()
+            .map(|(name, url)| format!("{name}={url}"))
+            .collect::<Vec<_>>()
+            .join(", "),
+    )
+}
+
+#[cfg(test)]
+mod main_menu_tests {
+    use super::*;
+
+    fn storage(aliases: &[&str]) -> ConfigStorage {
+        let mut storage = ConfigStorage::default();
+        for alias in aliases {
+            storage.add_configuration(Configuration {
+                alias_name: alias.to_string(),
+                ..Default::default()
+            });
+        }
+        storage
+    }
+
+    fn labels(items: &[MenuItem]) -> Vec<&str> {
+        items.iter().map(|item| item.label.as_str()).collect()
+    }
+
+    #[test]
+    fn relaunch_item_leads_for_the_active_configuration() {
+        let mut storage = storage(&["work", "home"]);
+        storage.last_used = Some("home".to_string());
+
+        let items = main_menu_items(&storage, Some("work"));
+        assert_eq!(
+            labels(&items),
+            [
+                "Relaunch with current configuration ('work')",
+                "Execute claude --dangerously-skip-permissions",
+                "Switch configuration",
+                "Exit",
+            ]
+        );
+        assert_eq!(
+            items[0].action,
+            MainMenuAction::Relaunch("work".to_string())
+        );
+        assert_eq!(
+            menu_choice("2", &items).map(|item| &item.action),
+            Some(&MainMenuAction::ExecuteClaude)
+        );
+        assert!(menu_choice("0", &items).is_none() && menu_choice("5", &items).is_none());
+    }
+
+    #[test]
+    fn relaunch_falls_back_to_the_last_used_configuration_or_is_hidden() {
+        let mut storage = storage(&["home"]);
+        storage.last_used = Some("home".to_string());
+        let items = main_menu_items(&storage, Some("deleted"));
+        assert_eq!(items[0].label, "Relaunch with last configuration ('home')");
+
+        storage.last_used = Some("deleted".to_string());
+        storage.preferences.no_skip_permissions = true;
+        let items = main_menu_items(&storage, None);
+        assert_eq!(
+            labels(&items),
+            ["Execute claude", "Switch configuration", "Exit"]
+        );
+        assert_eq!(
+            menu_choice("3", &items).map(|item| &item.action),
+            Some(&MainMenuAction::Exit)
+        );
+    }
+}
+
+#[cfg(test)]
+mod confirm_tests {
+    use super::*;
+
+    struct MockPrompter {
+        interactive: bool,
+        answer: bool,
+        asked: Vec<String>,
+    }
+
+    impl Prompter for MockPrompter {
+        fn is_interactive(&self) -> bool {
+            self.interactive
+        }
+
+        fn confirm(&mut self, prompt: &str) -> Result<bool> {
+            self.asked.push(prompt.to_string());
+            Ok(self.answer)
+        }
+
+        fn ask(&mut self, prompt: &str) -> Result<String> {
+            self.asked.push(prompt.to_string());
+            Ok(String::new())
+        }
+    }
+
+    fn prompter(interactive: bool, answer: bool) -> MockPrompter {
+        MockPrompter {
+            interactive,
+            answer,
+            asked: Vec::new(),
+        }
+    }
+
+    fn prod_config() -> Configuration {
+        Configuration {
+            alias_name: "billing".to_string(),
+            tags: vec!["Prod".to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn confirm_prod() -> Preferences {
+        Preferences {
+            confirm_tags: vec!["prod".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn untagged_config_launches_without_prompt() {
+        let mut p = prompter(false, false);
+        let config = Configuration::default();
+        assert!(confirm_tagged_launch(&config, &confirm_prod(), false, &mut p).unwrap());
+        assert!(p.asked.is_empty());
+    }
+
+    #[test]
+    fn yes_skips_prompt() {
+        let mut p = prompter(false, false);
+        assert!(confirm_tagged_launch(&prod_config(), &confirm_prod(), true, &mut p).unwrap());
+        assert!(p.asked.is_empty());
+    }
+
+    #[test]
+    fn non_tty_without_yes_fails() {
+        let mut p = prompter(false, true);
+        let err = confirm_tagged_launch(&prod_config(), &confirm_prod(), false, &mut p)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("--yes"), "got: {err}");
+        assert!(p.asked.is_empty());
+    }
+
+    #[test]
+    fn official_launch_cleans_settings_only_when_accepted() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path().to_string_lossy().into_owned();
+        let settings_path = tmp.path().join("settings.json");
+        let original = serde_json::json!({
+            "theme": "dark",
+            "env": {
+                "ANTHROPIC_AUTH_TOKEN": "sk-stale",
+                "ANTHROPIC_BASE_URL": "https://stale.example",
+                "EDITOR": "vim"
+            }
+        })
+        .to_string();
+        std::fs::write(&settings_path, &original).unwrap();
+
+        let mut decline = prompter(true, false);
+        clean_official_settings(Some(&dir), false, &mut decline).unwrap();
+        assert_eq!(decline.asked, vec!["Remove them before launching? [y/N] "]);
+        assert_eq!(std::fs::read_to_string(&settings_path).unwrap(), original);
+
+        let mut accept = prompter(true, true);
+        clean_official_settings(Some(&dir), false, &mut accept).unwrap();
+        let cleaned: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&settings_path).unwrap()).unwrap();
+        assert_eq!(cleaned["theme"], "dark");
+        assert_eq!(cleaned["env"], serde_json::json!({ "EDITOR": "vim" }));
+
+        // Nothing left to remove: no question and no rewrite
+        let mut untouched = prompter(true, true);
+        clean_official_settings(Some(&dir), false, &mut untouched).unwrap();
+        assert!(untouched.asked.is_empty());
+    }
+
+    #[test]
+    fn confirm_launch_needs_a_terminal_and_an_answer() {
+        let config = prod_config();
+        let mut p = prompter(false, true);
+        let err = confirm_launch(Some(&config), &FieldMask::default(), &mut p).unwrap_err();
+        assert!(err.to_string().contains("--confirm"), "got: {err}");
+        assert!(p.asked.is_empty());
+
+        let mut accept = prompter(true, true);
+        assert!(confirm_launch(Some(&config), &FieldMask::default(), &mut accept).unwrap());
+        assert_eq!(accept.asked, vec!["Launch 'billing'? [y/N] "]);
+
+        let mut decline = prompter(true, false);
+        assert!(!confirm_launch(None, &FieldMask::default(), &mut decline).unwrap());
+        assert_eq!(decline.asked, vec!["Launch 'official'? [y/N] "]);
+    }
+
+    #[test]
+    fn tty_prompt_answer_decides() {
+        let mut accept = prompter(true, true);
+        assert!(
+            confirm_tagged_launch(&prod_config(), &confirm_prod(), false, &mut accept).unwrap()
+        );
+        assert_eq!(
+            accept.asked,
+            vec!["You are switching to a 'prod' configuration \u{2014} continue? [y/N] "]
+        );
+
+        let mut decline = prompter(true, false);
+        assert!(
+            !confirm_tagged_launch(&prod_config(), &confirm_prod(), false, &mut decline).unwrap()
+        );
+    }
+}
+
+#[cfg(test)]
+mod invocation_tests {
+    use super::*;
+
+    #[test]
+    fn reset_env_removes_inherited_variables() {
+        let env = EnvironmentConfig::empty()
+            .with_alias("official")
+            .with_base_url("http://127.0.0.1:9876");
+        let command = ClaudeInvocation::new(true, &[], None, None, false).to_command(&env);
+        let envs: BTreeMap<String, Option<String>> = command
+            .get_envs()
+            .map(|(key, value)| {
+                (
+                    key.to_string_lossy().into_owned(),
+                    value.map(|v| v.to_string_lossy().into_owned()),
+                )
+            })
+            .collect();
+
+        assert_eq!(envs["ANTHROPIC_MODEL"], None);
+        assert_eq!(envs["API_TIMEOUT_MS"], None);
+        assert_eq!(envs["ANTHROPIC_DEFAULT_OPUS_MODEL"], None);
+        assert_eq!(
+            envs["ANTHROPIC_BASE_URL"].as_deref(),
+            Some("http://127.0.0.1:9876"),
+            "variables set explicitly still reach the child"
+        );
+        assert_eq!(envs["CC_SWITCH_CURRENT_ALIAS"].as_deref(), Some("official"));
+    }
+
+    #[test]
+    fn shell_quote_only_quotes_when_needed() {
+        assert_eq!(shell_quote("--model=opus"), "--model=opus");
+        assert_eq!(shell_quote("fix the bug"), "'fix the bug'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn preview_matches_launched_argv() {
+        let extra = vec!["--model".to_string(), "claude opus".to_string()];
+        let invocation = ClaudeInvocation::new(true, &extra, Some("fix it"), Some("abc123"), true);
+
+        assert_eq!(
+            invocation.args,
+            vec![
+                "--dangerously-skip-permissions",
+                "--model",
+                "claude opus",
+                "--resume",
+                "abc123",
+                "--continue",
+                "fix it",
+            ]
+        );
+
+        let command = invocation.to_command(&EnvironmentConfig::empty());
+        let launched: Vec<String> = std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|arg| shell_quote(&arg.to_string_lossy()))
+            .collect();
+        assert_eq!(invocation.command_line(), launched.join(" "));
+        assert!(
+            invocation
+                .command_line()
+                .ends_with("--dangerously-skip-permissions --model 'claude opus' --resume abc123 --continue 'fix it'")
+        );
+    }
+
+    #[test]
+    fn skip_permissions_flag_is_optional() {
+        let extra = vec!["--model".to_string(), "opus".to_string()];
+        let invocation = ClaudeInvocation::new(false, &extra, None, None, false);
+        assert_eq!(invocation.args, vec!["--model", "opus"]);
+        assert!(
+            !invocation
+                .command_line()
+                .contains("--dangerously-skip-permissions")
+        );
+    }
+
+    #[test]
+    fn profile_replaces_claude_and_keeps_environment() {
+        let profile = LaunchProfile {
+            command: "aider".to_string(),
+            args: vec!["--model".to_string(), "claude".to_string()],
+        };
+        let extra = vec!["--yes-always".to_string()];
+        let invocation = ClaudeInvocation::for_profile(&profile, &extra, Some("fix it"));
+
+        assert_eq!(invocation.program, std::path::PathBuf::from("aider"));
+        assert_eq!(
+            invocation.args,
+            vec!["--model", "claude", "--yes-always", "fix it"]
+        );
+
+        let config = Configuration {
+            alias_name: "relay".to_string(),
+            token: "sk-relay".to_string(),
+            url: "https://relay.example".to_string(),
+            ..Default::default()
+        };
+        let command = invocation.to_command(&EnvironmentConfig::from_config(&config));
+        let base_url = command
+            .get_envs()
+            .find(|(key, _)| *key == "ANTHROPIC_BASE_URL")
+            .and_then(|(_, value)| value);
+        assert_eq!(
+            base_url,
+            Some(std::ffi::OsStr::new("https://relay.example"))
+        );
+    }
+}
+
+#[cfg(test)]
+mod config_details_tests {
+    use super::*;
+
+    #[test]
+    fn test_format_config_details_hides_masked_fields() {
+        let config = Configuration {
+            alias_name: "relay".to_string(),
+            token: "sk-secret-relay-token-0123456789".to_string(),
+            url: "https://internal.relay.example".to_string(),
+            model: Some("claude-sonnet-4".to_string()),
+            ..Default::default()
+        };
+        let mask = FieldMask::new(&["token", "url"]);
+        let output = format_config_details(&config, "  ", false, &mask).join("\n");
+
+        assert!(!output.contains("sk-secret"));
+        assert!(!output.contains("internal.relay.example"));
+        assert!(output.contains(HIDDEN_PLACEHOLDER));
+        assert!(output.contains("claude-sonnet-4"));
+    }
+
+    #[test]
+    fn test_format_config_details_skips_unset_fields() {
+        let config = Configuration {
+            alias_name: "plain".to_string(),
+            token: "sk-ant-REDACTED".to_string(),
+            url: "https://api.anthropic.com".to_string(),
+            ..Default::default()
+        };
+        let lines = format_config_details(&config, "", false, &FieldMask::default());
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].contains("https://api.anthropic.com"));
+    }
+
+    #[test]
+    fn test_format_config_details_lists_url_variants() {
+        let mut config = Configuration {
+            alias_name: "relay".to_string(),
+            token: "sk-test".to_string(),
+            url: "https://relay.example".to_string(),
+            ..Default::default()
+        };
+        config
+            .url_variants
+            .insert("eu".to_string(), "https://eu.relay.example".to_string());
+        config
+            .url_variants
+            .insert("us".to_string(), "https://us.relay.example".to_string());
+
+        let output = format_config_details(&config, "", false, &FieldMask::default()).join("\n");
+        assert!(output.contains("URL Variants:"));
+        assert!(output.contains("eu=https://eu.relay.example, us=https://us.relay.example"));
 
-        // Update settings.json with the configuration
-        let mut settings = crate::config::types::ClaudeSettings::load(
-            storage.get_claude_settings_dir().map(|s| s.as_str()),
-        )?;
-        settings.switch_to_config_with_mode(
-            &selected_config,
-            storage_mode,
-            storage.get_claude_settings_dir().map(|s| s.as_str()),
-        )?;
+        let masked =
+            format_config_details(&config, "", false, &FieldMask::new(&["url"])).join("\n");
+        assert!(!masked.contains("eu.relay.example"));
+    }
 
-        launch_claude_with_env(env_config, None, None, false)
-    } else {
-        // Exit
-        println!("\nExiting...");
-        Ok(())
+    #[test]
+    fn test_no_color_override_strips_escape_codes() {
+        let config = Configuration {
+            alias_name: "relay".to_string(),
+            token: "sk-ant-REDACTED".to_string(),
+            url: "https://relay.example".to_string(),
+            model: Some("opus".to_string()),
+            color: Some("red".to_string()),
+            tags: vec!["prod".to_string()],
+            ..Default::default()
+        };
+        colored::control::set_override(false);
+        let details = format_config_details(&config, "  ", false, &FieldMask::new(&["url"]));
+        let mut state = MenuState::new(vec![config], false);
+        state.selected_index = 1;
+        let menu = render_config_menu(&state, &FieldMask::default(), None, None);
+
+        assert!(details.iter().any(|line| line.contains("opus")));
+        assert!(details.iter().any(|line| line.contains(HIDDEN_PLACEHOLDER)));
+        for line in details.iter().chain(&menu) {
+            assert!(!line.contains('\x1b'), "escape code in {line:?}");
+        }
+        // The markers are plain characters and stay
+        assert!(menu.iter().any(|line| line.contains('●')));
+        // D deletes from a single page too
+        assert!(menu.iter().any(|line| line.contains("D-删除")));
+        assert!(menu.iter().any(|line| line.contains('○')));
     }
 }
 
-/// Launch Claude CLI with environment variables and exec to replace current process
-pub fn launch_claude_with_env(
-    env_config: EnvironmentConfig,
-    prompt: Option<&str>,
-    resume: Option<&str>,
-    continue_session: bool,
-) -> Result<()> {
-    println!("\nLaunching Claude CLI...");
+#[cfg(test)]
+mod menu_state_tests {
+    use super::*;
 
-    // Clean up orphaned alias files from terminated sessions
-    let _ = ClaudeSettings::cleanup_orphan_alias_files();
+    fn configs(aliases: &[&str]) -> Vec<Configuration> {
+        aliases
+            .iter()
+            .map(|alias| Configuration {
+                alias_name: alias.to_string(),
+                ..Default::default()
+            })
+            .collect()
+    }
 
-    // Write per-PID alias file for statusLine isolation
-    // On Unix with exec, this file won't be cleaned up (orphan), but that's acceptable
-    // On non-Unix, we clean it after the process exits
-    if let Some(alias) = env_config.env_vars.get("CC_SWITCH_CURRENT_ALIAS") {
-        ClaudeSettings::write_current_alias_for_pid(alias)?;
+    fn aliases(state: &MenuState) -> Vec<&str> {
+        state
+            .configs
+            .iter()
+            .map(|config| config.alias_name.as_str())
+            .collect()
     }
 
-    // On Unix systems, use exec to replace current process
-    #[cfg(unix)]
-    {
-        use std::os::unix::process::CommandExt;
-        let mut command = Command::new(resolve_npm_cli("claude"));
-        // Explicitly pass environment variables to ensure they're inherited
-        command.envs(env_config.as_env_tuples());
-        command.arg("--dangerously-skip-permissions");
-        if let Some(session_id) = resume {
-            command.args(["--resume", session_id]);
-        }
-        if continue_session {
-            command.arg("--continue");
-        }
-        if let Some(p) = prompt {
-            command.arg(p);
-        }
-        let error = command.exec();
-        // exec never returns on success, so if we get here, it failed
-        // Clean up per-PID file on exec failure
-        let _ = ClaudeSettings::clear_current_alias_for_pid();
-        anyhow::bail!("Failed to exec claude: {}", error);
+    #[test]
+    fn new_sorts_configs_and_selects_official() {
+        let state = MenuState::new(configs(&["zeta", "alpha", "mid"]), false);
+        assert_eq!(aliases(&state), vec!["alpha", "mid", "zeta"]);
+        assert_eq!(state.selected_index, 0);
+        assert!(state.selected_config().is_none());
     }
 
-    // On non-Unix systems, fallback to spawn and wait
-    #[cfg(not(unix))]
-    {
-        use std::process::Stdio;
-        let mut command = Command::new(resolve_npm_cli("claude"));
-        // Explicitly pass environment variables to ensure they're inherited
-        command.envs(env_config.as_env_tuples());
-        command.arg("--dangerously-skip-permissions");
-        if let Some(session_id) = resume {
-            command.args(["--resume", session_id]);
-        }
-        if continue_session {
-            command.arg("--continue");
-        }
-        if let Some(p) = prompt {
-            command.arg(p);
-        }
-        command
-            .stdin(Stdio::inherit())
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit());
+    #[test]
+    fn render_marks_default_and_last_used_configurations() {
+        let state = MenuState::new(configs(&["alpha", "beta"]), false);
+        let frame = render_config_menu(&state, &FieldMask::default(), Some("beta"), Some("alpha"));
+        let row = |alias: &str| {
+            frame
+                .iter()
+                .find(|line| line.contains(alias))
+                .cloned()
+                .unwrap_or_default()
+        };
+        assert!(row("beta").contains("★ "), "got: {frame:?}");
+        assert!(!row("alpha").contains('★'));
+        assert!(row("alpha").contains("(last used)"));
+        assert!(!row("beta").contains("(last used)"));
+        assert!(frame.last().unwrap().contains("[-]"));
+        assert!(
+            frame
+                .iter()
+                .any(|line| line.contains("[A] Add new configuration"))
+        );
+    }
 
-        let mut child = command.spawn().context(
-            "Failed to launch Claude CLI. Make sure 'claude' command is available in PATH",
-        )?;
+    #[test]
+    fn render_footer_shows_storage_mode() {
+        let mut state = MenuState::new(configs(&["alpha"]), false);
+        let frame = render_config_menu(&state, &FieldMask::default(), None, None);
+        assert!(frame.last().unwrap().contains("存储模式: env"));
+
+        state.storage_mode = StorageMode::Config;
+        let frame = render_config_menu(&state, &FieldMask::default(), None, None);
+        assert!(frame.last().unwrap().contains("存储模式: config"));
+    }
 
-        let status = child.wait()?;
+    #[test]
+    fn rename_during_open_menu_follows_renamed_entry() {
+        let names: Vec<String> = (0..12).map(|i| format!("cfg{i:02}")).collect();
+        let refs: Vec<&str> = names.iter().map(String::as_str).collect();
+        let mut state = MenuState::new(configs(&refs), false);
+
+        // Select "cfg01" on the first page, then rename it so it sorts last
+        state.selected_index = 2;
+        assert_eq!(state.selected_config().unwrap().alias_name, "cfg01");
+        let mut edited = configs(&refs);
+        edited[1].alias_name = "zz-renamed".to_string();
+        state.reload(edited, "zz-renamed");
+
+        assert_eq!(state.selected_config().unwrap().alias_name, "zz-renamed");
+        assert_eq!(state.selected_index, 12);
+        assert_eq!(state.current_page, 1, "page follows the selection");
+        assert!(state.page_range().contains(&(state.selected_index - 1)));
+    }
 
-        // Clean up per-PID file after Claude exits
-        let _ = ClaudeSettings::clear_current_alias_for_pid();
+    #[test]
+    fn filter_narrows_rows_pages_and_digits() {
+        let mut names: Vec<String> = (0..20).map(|i| format!("cfg{i:02}")).collect();
+        names.push("Relay-EU".to_string());
+        let refs: Vec<&str> = names.iter().map(String::as_str).collect();
+        let mut all = configs(&refs);
+        all[0].url = "https://relay.example".to_string();
+        let mut state = MenuState::new(all, false);
+        assert_eq!(state.total_pages(), 3);
+
+        state.filtering = true;
+        state.set_query("RELAY".to_string());
+        let listed = |state: &MenuState| -> Vec<String> {
+            state
+                .rows
+                .iter()
+                .filter_map(|row| match row {
+                    MenuRow::Config(index) => Some(state.configs[*index].alias_name.clone()),
+                    MenuRow::Header { .. } => None,
+                })
+                .collect()
+        };
+        assert_eq!(listed(&state), ["Relay-EU", "cfg00"], "alias or URL match");
+        assert_eq!(state.total_pages(), 1);
+        assert_eq!(state.selected_config().unwrap().alias_name, "Relay-EU");
+        let second = state.number_target(2).unwrap();
+        assert_eq!(state.row_at(second), Some(&MenuRow::Config(1)));
+
+        state.set_query("nothing".to_string());
+        assert!(state.rows.is_empty());
+        assert_eq!(state.selected_index, 0);
+
+        state.set_query("cfg1".to_string());
+        state.move_down();
+        assert_eq!(state.selected_config().unwrap().alias_name, "cfg11");
+        state.clear_filter();
+        assert!(!state.filtering);
+        assert_eq!(state.rows.len(), 21);
+        assert_eq!(state.selected_config().unwrap().alias_name, "cfg11");
+        assert_eq!(state.current_page, 1);
+    }
 
-        if !status.success() {
-            anyhow::bail!("Claude CLI exited with error status: {}", status);
-        }
-        Ok(())
+    #[test]
+    fn remove_keeps_cursor_and_drops_emptied_page() {
+        let names: Vec<String> = (0..10).map(|i| format!("cfg{i:02}")).collect();
+        let refs: Vec<&str> = names.iter().map(String::as_str).collect();
+        let mut state = MenuState::new(configs(&refs), false);
+
+        // Deleting a middle entry moves the cursor onto the next one
+        state.selected_index = 3;
+        state.remove("cfg02");
+        assert_eq!(state.selected_config().unwrap().alias_name, "cfg03");
+
+        // cfg09 is alone on the second page; deleting it returns to page 1
+        let mut state = MenuState::new(configs(&refs), false);
+        assert_eq!(state.total_pages(), 2);
+        state.selected_index = 10;
+        state.follow_selection();
+        assert_eq!(state.current_page, 1);
+        state.remove("cfg09");
+        assert_eq!(state.total_pages(), 1);
+        assert_eq!(state.current_page, 0);
+        assert_eq!(state.selected_config().unwrap().alias_name, "cfg08");
     }
-}
 
-/// Execute claude command with or without --dangerously-skip-permissions using exec
-///
-/// # Arguments
-/// * `skip_permissions` - Whether to add --dangerously-skip-permissions flag
-fn execute_claude_command(skip_permissions: bool) -> Result<()> {
-    println!("Launching Claude CLI...");
+    #[test]
+    fn layout_fits_terminal_size() {
+        let layout = |columns, rows| {
+            let layout = MenuLayout::for_terminal(columns, rows, MenuState::MAX_WIDTH);
+            (layout.width, layout.page_size)
+        };
+        // Wide and tall: capped at the usual width and page size
+        assert_eq!(layout(200, 60), (80, 9));
+        assert_eq!(layout(72, 60), (72, 9));
+        // Too narrow: the minimum width, wrapping instead
+        assert_eq!(layout(20, 60), (MIN_MENU_WIDTH, 9));
+        // Short terminals get shorter pages, never empty ones
+        assert_eq!(layout(100, 20), (80, 4));
+        assert_eq!(layout(100, 5), (80, 1));
+
+        // Shrinking keeps the selected configuration on the shown page
+        let names: Vec<String> = (0..9).map(|i| format!("cfg{i:02}")).collect();
+        let refs: Vec<&str> = names.iter().map(String::as_str).collect();
+        let mut state = MenuState::new(configs(&refs), false);
+        state.selected_index = 7;
+        state.set_layout(MenuLayout::for_terminal(100, 20, MenuState::MAX_WIDTH));
+        assert_eq!(state.total_pages(), 3);
+        assert_eq!(state.current_page, 1);
+        assert!(state.page_range().contains(&6));
+        state.set_layout(MenuLayout::for_terminal(100, 60, MenuState::MAX_WIDTH));
+        assert_eq!(state.current_page, 0);
+    }
 
-    // On Unix systems, use exec to replace current process
-    #[cfg(unix)]
-    {
-        use std::os::unix::process::CommandExt;
-        let mut command = Command::new(resolve_npm_cli("claude"));
-        if skip_permissions {
-            command.arg("--dangerously-skip-permissions");
+    #[test]
+    fn navigation_burst_ends_in_one_final_state() {
+        // 10 pages of 9 configurations
+        let names: Vec<String> = (0..90).map(|i| format!("cfg{i:02}")).collect();
+        let refs: Vec<&str> = names.iter().map(String::as_str).collect();
+        let mut state = MenuState::new(configs(&refs), false);
+
+        // Key repeat: 40 downs, 3 ups and 2 page-downs handled back to back
+        let burst = std::iter::repeat_n(KeyCode::Down, 40)
+            .chain(std::iter::repeat_n(KeyCode::Char('k'), 3))
+            .chain(std::iter::repeat_n(KeyCode::PageDown, 2));
+        for code in burst {
+            assert!(state.navigate(code));
         }
+        assert_eq!(state.selected_config().unwrap().alias_name, "cfg54");
+        assert_eq!(state.current_page, 6);
+        assert!(state.page_range().contains(&(state.selected_index - 1)));
 
-        let error = command.exec();
-        // exec never returns on success, so if we get here, it failed
-        anyhow::bail!("Failed to exec claude: {}", error);
+        // Page keys stop at the last page; other keys are not navigation
+        for _ in 0..20 {
+            state.navigate(KeyCode::Char('n'));
+        }
+        assert_eq!(state.current_page, 9);
+        assert!(!state.navigate(KeyCode::Enter));
+        assert!(!state.navigate(KeyCode::Char('3')));
     }
 
-    // On non-Unix systems, fallback to spawn and wait
-    #[cfg(not(unix))]
-    {
-        use std::process::Stdio;
-        let mut command = Command::new(resolve_npm_cli("claude"));
-        if skip_permissions {
-            command.arg("--dangerously-skip-permissions");
-        }
+    #[test]
+    fn jumps_and_wraps_recalculate_the_page() {
+        // 3 pages: 9 + 9 + 2 configurations
+        let names: Vec<String> = (0..20).map(|i| format!("cfg{i:02}")).collect();
+        let refs: Vec<&str> = names.iter().map(String::as_str).collect();
+        let mut state = MenuState::new(configs(&refs), false);
+        let exit = state.exit_index();
+
+        // End/G shows the last page, Home/g the first
+        assert!(state.navigate(KeyCode::End));
+        assert_eq!((state.selected_index, state.current_page), (exit, 2));
+        assert!(state.navigate(KeyCode::Home));
+        assert_eq!((state.selected_index, state.current_page), (0, 0));
+        state.navigate(KeyCode::Char('G'));
+        assert_eq!(state.current_page, 2);
+        state.navigate(KeyCode::Char('g'));
+        assert_eq!(state.current_page, 0);
+
+        // ↑ on official wraps to Exit, ↓ on Exit back to official
+        assert!(state.navigate(KeyCode::Up));
+        assert_eq!((state.selected_index, state.current_page), (exit, 2));
+        assert!(state.navigate(KeyCode::Char('j')));
+        assert_eq!((state.selected_index, state.current_page), (0, 0));
+
+        // Stepping off either end of a page moves to the neighbouring page
+        state.navigate(KeyCode::Char('k'));
+        state.navigate(KeyCode::Char('k'));
+        state.navigate(KeyCode::Char('k'));
+        assert_eq!(state.selected_config().unwrap().alias_name, "cfg18");
+        assert_eq!(state.current_page, 2);
+        state.navigate(KeyCode::Char('k'));
+        assert_eq!(state.selected_config().unwrap().alias_name, "cfg17");
+        assert_eq!(state.current_page, 1);
+
+        // Page of each position: official first, Exit last, rows by page
+        assert_eq!(state.page_of(0), Some(0));
+        assert_eq!(state.page_of(9), Some(0));
+        assert_eq!(state.page_of(10), Some(1));
+        assert_eq!(state.page_of(19), Some(2));
+        assert_eq!(state.page_of(exit), Some(2));
+        assert_eq!(state.page_of(exit + 1), None);
+    }
 
-        command
-            .stdin(Stdio::inherit())
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit());
+    #[test]
+    fn reload_clamps_selection_when_alias_is_gone() {
+        let mut state = MenuState::new(configs(&["a", "b", "c"]), false);
+        state.selected_index = 3;
+        state.reload(configs(&["a"]), "c");
+        assert_eq!(state.selected_index, 2, "clamped to Exit");
+        assert_eq!(state.current_page, 0);
+        assert!(state.selected_config().is_none());
+    }
 
-        let mut child = command.spawn().context(
-            "Failed to launch Claude CLI. Make sure 'claude' command is available in PATH",
-        )?;
+    #[test]
+    fn reload_remaps_selection_after_rename_delete_or_no_change() {
+        let aliases: Vec<String> = (0..25).map(|i| format!("cfg{i:02}")).collect();
+        let names: Vec<&str> = aliases.iter().map(String::as_str).collect();
+        let mut state = MenuState::new(configs(&names), false);
+        assert!(state.focus_alias("cfg12"));
+        let (index, page) = (state.selected_index, state.current_page);
+        assert_eq!(page, 1);
+
+        // Unchanged: same row, same page, refreshed values
+        let mut refreshed = configs(&names);
+        refreshed[12].url = "https://edited.example".to_string();
+        state.reload(refreshed, "cfg12");
+        assert_eq!((state.selected_index, state.current_page), (index, page));
+        assert_eq!(
+            state.selected_config().map(|c| c.url.as_str()),
+            Some("https://edited.example")
+        );
 
-        let status = child
-            .wait()
-            .context("Failed to wait for Claude CLI process")?;
+        // Renamed: follows the new alias to its sorted position
+        let mut renamed: Vec<&str> = names.clone();
+        renamed[12] = "aaa";
+        state.reload(configs(&renamed), "aaa");
+        assert_eq!(
+            state.selected_config().map(|c| c.alias_name.as_str()),
+            Some("aaa")
+        );
+        assert_eq!(state.current_page, 0);
+
+        // Deleted: the index is kept within the list and the page follows it
+        assert!(state.focus_alias("cfg24"));
+        let shrunk: Vec<&str> = names[..5].to_vec();
+        state.reload(configs(&shrunk), "cfg24");
+        assert_eq!(state.current_page, state.total_pages() - 1);
+        assert!(state.selected_index <= state.exit_index());
+        assert!(state.is_selectable(state.selected_index));
+    }
 
-        if !status.success() {
-            anyhow::bail!("Claude CLI exited with error status: {}", status);
+    fn tagged(alias: &str, tags: &[&str]) -> Configuration {
+        Configuration {
+            alias_name: alias.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..Default::default()
         }
-        Ok(())
     }
-}
 
-/// Read input from stdin with a prompt
-///
-/// # Arguments
-/// * `prompt` - The prompt to display to the user
-///
-/// # Returns
-/// The user's input as a String
-pub fn read_input(prompt: &str) -> Result<String> {
-    print!("{prompt}");
-    io::stdout().flush().context("Failed to flush stdout")?;
-    let mut input = String::new();
-    io::stdin()
-        .read_line(&mut input)
-        .context("Failed to read input")?;
-    Ok(input.trim().to_string())
-}
+    fn grouped_state() -> MenuState {
+        MenuState::new(
+            vec![
+                tagged("solo", &[]),
+                tagged("eu-relay", &["prod", "eu"]),
+                tagged("billing", &["prod"]),
+                tagged("sandbox", &["dev"]),
+            ],
+            true,
+        )
+    }
 
-/// Read sensitive input (token) with a prompt (without echoing)
-///
-/// # Arguments
-/// * `prompt` - The prompt to display to the user
-///
-/// # Returns
-/// The user's input as a String
-pub fn read_sensitive_input(prompt: &str) -> Result<String> {
-    print!("{prompt}");
-    io::stdout().flush().context("Failed to flush stdout")?;
-    let mut input = String::new();
-    io::stdin()
-        .read_line(&mut input)
-        .context("Failed to read input")?;
-    Ok(input.trim().to_string())
-}
+    fn row_labels(state: &MenuState) -> Vec<String> {
+        state
+            .rows
+            .iter()
+            .map(|row| match row {
+                MenuRow::Header {
+                    group, collapsed, ..
+                } => format!("{}{group}", if *collapsed { "+" } else { "-" }),
+                MenuRow::Config(index) => state.configs[*index].alias_name.clone(),
+            })
+            .collect()
+    }
 
-/// Format configuration details with consistent indentation and alignment
-///
-/// This function provides unified formatting for configuration display across
-/// all interactive menus, ensuring consistent visual presentation.
-///
-/// # Arguments
-/// * `config` - The configuration to format
-/// * `indent` - Base indentation string (e.g., "    " or "   ")
-/// * `compact` - Whether to use compact formatting (single line where possible)
-///
-/// # Returns  
-/// Vector of formatted lines for configuration display
-fn format_config_details(config: &Configuration, indent: &str, _compact: bool) -> Vec<String> {
-    let mut lines = Vec::new();
+    #[test]
+    fn filter_shows_matches_in_collapsed_groups() {
+        let mut state = grouped_state();
+        state.focus_alias("billing");
+        state.toggle_group();
+        assert!(row_labels(&state).contains(&"+prod".to_string()));
+
+        state.filtering = true;
+        state.set_query("EU".to_string());
+        assert_eq!(row_labels(&state), ["-prod", "eu-relay"]);
+        assert_eq!(state.selected_config().unwrap().alias_name, "eu-relay");
+
+        state.clear_filter();
+        assert_eq!(
+            row_labels(&state),
+            [
+                "-dev",
+                "sandbox",
+                "-prod",
+                "billing",
+                "eu-relay",
+                "-untagged",
+                "solo"
+            ],
+            "the highlighted match stays visible"
+        );
+    }
 
-    // Calculate optimal field width for alignment
-    let terminal_width = get_terminal_width();
-    let _available_width = terminal_width.saturating_sub(text_display_width(indent) + 8);
+    #[test]
+    fn tag_filter_survives_reloads() {
+        let configs = || {
+            vec![
+                tagged("solo", &[]),
+                tagged("eu-relay", &["prod", "eu"]),
+                tagged("billing", &["Prod"]),
+            ]
+        };
+        let mut state = MenuState::new(configs(), false).with_tag(Some("prod"));
+        assert_eq!(row_labels(&state), vec!["billing", "eu-relay"]);
 
-    // Field labels with consistent width for alignment
-    let token_label = if config.api_key.is_some() {
-        "API Key:"
-    } else {
-        "Token:"
-    };
-    let url_label = "URL:";
-    let model_label = "Model:";
-    let small_model_label = "Small Fast Model:";
-    let max_thinking_tokens_label = "Max Thinking Tokens:";
-    let api_timeout_ms_label = "API Timeout (ms):";
-    let disable_nonessential_traffic_label = "Disable Nonessential Traffic:";
-    let default_sonnet_model_label = "Default Sonnet Model:";
-    let default_opus_model_label = "Default Opus Model:";
-    let default_haiku_model_label = "Default Haiku Model:";
-    let subagent_model_label = "Subagent Model:";
-    let disable_nonstreaming_fallback_label = "Disable Nonstreaming Fallback:";
-    let effort_level_label = "Effort Level:";
-    let disable_prompt_caching_label = "Disable Prompt Caching:";
-    let disable_experimental_betas_label = "Disable Experimental Betas:";
-    let disable_autoupdater_label = "Disable Auto-Updater:";
+        state.reload(configs(), "billing");
+        assert_eq!(row_labels(&state), vec!["billing", "eu-relay"]);
+    }
 
-    // Find the widest label for alignment
-    let max_label_width = [
-        token_label,
-        url_label,
-        model_label,
-        small_model_label,
-        max_thinking_tokens_label,
-        api_timeout_ms_label,
-        disable_nonessential_traffic_label,
-        default_sonnet_model_label,
-        default_opus_model_label,
-        default_haiku_model_label,
-        subagent_model_label,
-        disable_nonstreaming_fallback_label,
-        effort_level_label,
-        disable_prompt_caching_label,
-        disable_experimental_betas_label,
-        disable_autoupdater_label,
-    ]
-    .iter()
-    .map(|label| text_display_width(label))
-    .max()
-    .unwrap_or(0);
-
-    // Format token/api_key with proper alignment
-    let (_, auth_value) = config.auth_env_pair();
-    let token_line = format!(
-        "{}{} {}",
-        indent,
-        pad_text_to_width(token_label, max_label_width, TextAlignment::Left, ' '),
-        format_token_for_display(auth_value).dimmed()
-    );
-    lines.push(token_line);
-
-    // Format URL with proper alignment
-    let url_line = format!(
-        "{}{} {}",
-        indent,
-        pad_text_to_width(url_label, max_label_width, TextAlignment::Left, ' '),
-        config.url.cyan()
-    );
-    lines.push(url_line);
-
-    // Format model information if available
-    if let Some(model) = &config.model {
-        let model_line = format!(
-            "{}{} {}",
-            indent,
-            pad_text_to_width(model_label, max_label_width, TextAlignment::Left, ' '),
-            model.yellow()
+    #[test]
+    fn grouped_rows_put_headers_before_members_and_untagged_last() {
+        let state = grouped_state();
+        assert_eq!(
+            row_labels(&state),
+            vec![
+                "-dev",
+                "sandbox",
+                "-prod",
+                "billing",
+                "eu-relay",
+                "-untagged",
+                "solo"
+            ]
         );
-        lines.push(model_line);
     }
 
-    // Format small fast model if available
-    if let Some(small_fast_model) = &config.small_fast_model {
-        let small_model_line = format!(
-            "{}{} {}",
-            indent,
-            pad_text_to_width(small_model_label, max_label_width, TextAlignment::Left, ' '),
-            small_fast_model.yellow()
-        );
-        lines.push(small_model_line);
-    }
-
-    // Format max thinking tokens if available
-    if let Some(max_thinking_tokens) = config.max_thinking_tokens {
-        let tokens_line = format!(
-            "{}{} {}",
-            indent,
-            pad_text_to_width(
-                max_thinking_tokens_label,
-                max_label_width,
-                TextAlignment::Left,
-                ' '
-            ),
-            format!("{}", max_thinking_tokens).yellow()
-        );
-        lines.push(tokens_line);
-    }
-
-    // Format API timeout if available
-    if let Some(api_timeout_ms) = config.api_timeout_ms {
-        let timeout_line = format!(
-            "{}{} {}",
-            indent,
-            pad_text_to_width(
-                api_timeout_ms_label,
-                max_label_width,
-                TextAlignment::Left,
-                ' '
-            ),
-            format!("{}", api_timeout_ms).yellow()
-        );
-        lines.push(timeout_line);
-    }
-
-    // Format disable nonessential traffic flag if available
-    if let Some(disable_flag) = config.claude_code_disable_nonessential_traffic {
-        let flag_line = format!(
-            "{}{} {}",
-            indent,
-            pad_text_to_width(
-                disable_nonessential_traffic_label,
-                max_label_width,
-                TextAlignment::Left,
-                ' '
-            ),
-            format!("{}", disable_flag).yellow()
-        );
-        lines.push(flag_line);
-    }
-
-    // Format default Sonnet model if available
-    if let Some(sonnet_model) = &config.anthropic_default_sonnet_model {
-        let sonnet_line = format!(
-            "{}{} {}",
-            indent,
-            pad_text_to_width(
-                default_sonnet_model_label,
-                max_label_width,
-                TextAlignment::Left,
-                ' '
-            ),
-            sonnet_model.yellow()
-        );
-        lines.push(sonnet_line);
-    }
-
-    // Format default Opus model if available
-    if let Some(opus_model) = &config.anthropic_default_opus_model {
-        let opus_line = format!(
-            "{}{} {}",
-            indent,
-            pad_text_to_width(
-                default_opus_model_label,
-                max_label_width,
-                TextAlignment::Left,
-                ' '
-            ),
-            opus_model.yellow()
-        );
-        lines.push(opus_line);
-    }
-
-    // Format default Haiku model if available
-    if let Some(haiku_model) = &config.anthropic_default_haiku_model {
-        let haiku_line = format!(
-            "{}{} {}",
-            indent,
-            pad_text_to_width(
-                default_haiku_model_label,
-                max_label_width,
-                TextAlignment::Left,
-                ' '
-            ),
-            haiku_model.yellow()
-        );
-        lines.push(haiku_line);
-    }
-
-    // Format subagent model if available
-    if let Some(subagent_model) = &config.claude_code_subagent_model {
-        let subagent_line = format!(
-            "{}{} {}",
-            indent,
-            pad_text_to_width(
-                subagent_model_label,
-                max_label_width,
-                TextAlignment::Left,
-                ' '
-            ),
-            subagent_model.yellow()
-        );
-        lines.push(subagent_line);
-    }
-
-    // Format disable non-streaming fallback if available
-    if let Some(disable_flag) = config.claude_code_disable_nonstreaming_fallback {
-        let flag_line = format!(
-            "{}{} {}",
-            indent,
-            pad_text_to_width(
-                disable_nonstreaming_fallback_label,
-                max_label_width,
-                TextAlignment::Left,
-                ' '
-            ),
-            format!("{}", disable_flag).yellow()
-        );
-        lines.push(flag_line);
-    }
-
-    // Format effort level if available
-    if let Some(effort_level) = &config.claude_code_effort_level {
-        let effort_line = format!(
-            "{}{} {}",
-            indent,
-            pad_text_to_width(
-                effort_level_label,
-                max_label_width,
-                TextAlignment::Left,
-                ' '
-            ),
-            effort_level.yellow()
-        );
-        lines.push(effort_line);
-    }
-
-    // Format disable prompt caching if available
-    if let Some(disable_flag) = config.disable_prompt_caching {
-        let flag_line = format!(
-            "{}{} {}",
-            indent,
-            pad_text_to_width(
-                disable_prompt_caching_label,
-                max_label_width,
-                TextAlignment::Left,
-                ' '
-            ),
-            format!("{}", disable_flag).yellow()
+    #[test]
+    fn navigation_skips_expanded_headers() {
+        let mut state = grouped_state();
+        state.move_down();
+        assert_eq!(state.selected_config().unwrap().alias_name, "sandbox");
+        state.move_down();
+        assert_eq!(state.selected_config().unwrap().alias_name, "billing");
+        state.move_up();
+        assert_eq!(state.selected_config().unwrap().alias_name, "sandbox");
+        state.move_up();
+        assert_eq!(
+            state.selected_index, 0,
+            "official is above the first header"
         );
-        lines.push(flag_line);
-    }
-
-    // Format disable experimental betas if available
-    if let Some(disable_flag) = config.claude_code_disable_experimental_betas {
-        let flag_line = format!(
-            "{}{} {}",
-            indent,
-            pad_text_to_width(
-                disable_experimental_betas_label,
-                max_label_width,
-                TextAlignment::Left,
-                ' '
-            ),
-            format!("{}", disable_flag).yellow()
+    }
+
+    #[test]
+    fn digits_continue_across_groups() {
+        let state = grouped_state();
+        let alias_for = |digit| {
+            let target = state.number_target(digit).unwrap();
+            match state.row_at(target) {
+                Some(MenuRow::Config(index)) => state.configs[*index].alias_name.clone(),
+                other => panic!("digit {digit} hit {other:?}"),
+            }
+        };
+        assert_eq!(alias_for(1), "sandbox");
+        assert_eq!(alias_for(2), "billing");
+        assert_eq!(alias_for(4), "solo");
+        assert_eq!(state.number_target(5), None);
+    }
+
+    #[test]
+    fn number_input_waits_only_while_a_longer_number_fits() {
+        let start = Instant::now();
+        let mut input = NumberInput::default();
+        // With 5 configurations every digit is complete at once
+        assert_eq!(input.push('3', 5, start), NumberStep::Pick(3));
+        assert_eq!(input.push('7', 5, start), NumberStep::Ignored);
+        assert_eq!(input.push('0', 5, start), NumberStep::Ignored);
+        assert_eq!(input.pending(), None);
+
+        // With 12, "1" may become 10-12 but "2" can't grow
+        assert_eq!(input.push('2', 12, start), NumberStep::Pick(2));
+        assert_eq!(input.push('1', 12, start), NumberStep::Pending);
+        assert_eq!(input.pending(), Some("1"));
+        assert_eq!(input.remaining(start), Some(NumberInput::TIMEOUT));
+        let later = start + Duration::from_millis(500);
+        assert_eq!(
+            input.remaining(later),
+            Some(NumberInput::TIMEOUT - Duration::from_millis(500))
         );
-        lines.push(flag_line);
-    }
-
-    // Format disable auto-updater if available
-    if let Some(disable_flag) = config.disable_autoupdater {
-        let flag_line = format!(
-            "{}{} {}",
-            indent,
-            pad_text_to_width(
-                disable_autoupdater_label,
-                max_label_width,
-                TextAlignment::Left,
-                ' '
-            ),
-            format!("{}", disable_flag).yellow()
+        assert_eq!(input.push('2', 12, later), NumberStep::Pick(12));
+        assert_eq!(input.pending(), None);
+        assert_eq!(input.remaining(later), None);
+
+        assert_eq!(input.push('1', 12, start), NumberStep::Pending);
+        assert_eq!(input.push('5', 12, start), NumberStep::OutOfRange(15));
+        assert_eq!(input.pending(), None);
+
+        // Enter or the timeout completes the pending number
+        assert_eq!(input.push('1', 12, start), NumberStep::Pending);
+        assert_eq!(
+            input.remaining(start + Duration::from_secs(2)),
+            Some(Duration::ZERO)
         );
-        lines.push(flag_line);
+        assert_eq!(input.finish(), Some(1));
+        assert_eq!(input.finish(), None);
+
+        // Three digits once there are a hundred configurations
+        assert_eq!(input.push('1', 120, start), NumberStep::Pending);
+        assert_eq!(input.push('0', 120, start), NumberStep::Pending);
+        assert_eq!(input.push('7', 120, start), NumberStep::Pick(107));
     }
 
-    lines
-}
+    #[test]
+    fn numbers_reach_configurations_on_other_pages() {
+        let names: Vec<String> = (1..=12).map(|i| format!("cfg{i:02}")).collect();
+        let refs: Vec<&str> = names.iter().map(String::as_str).collect();
+        let mut state = MenuState::new(configs(&refs), false);
+        assert_eq!(state.total_pages(), 2);
+        let now = Instant::now();
+
+        assert_eq!(state.type_digit('1', now), None);
+        let frame = render_config_menu(&state, &FieldMask::default(), None, None).join("\n");
+        assert!(frame.contains("Selection: 1_"), "{frame}");
+        let target = state.type_digit('2', now).unwrap();
+        state.select(target);
+        assert_eq!(state.selected_config().unwrap().alias_name, "cfg12");
+        assert_eq!(state.current_page, 1);
+        // Labels continue on the second page
+        let frame = render_config_menu(&state, &FieldMask::default(), None, None).join("\n");
+        assert!(frame.contains("[12]") && !frame.contains("[1] "), "{frame}");
+
+        assert_eq!(state.type_digit('1', now), None);
+        assert_eq!(state.type_digit('9', now), None);
+        assert_eq!(state.notice.as_deref(), Some("没有编号为 19 的配置"));
+
+        assert_eq!(state.type_digit('1', now), None);
+        let target = state.finish_number().unwrap();
+        assert_eq!(state.row_at(target), Some(&MenuRow::Config(0)));
+        assert_eq!(state.finish_number(), None);
+    }
 
-#[cfg(test)]
-mod border_drawing_tests {
-    use super::*;
+    #[test]
+    fn launch_preview_shows_details_and_keys() {
+        let config = Configuration {
+            alias_name: "relay".to_string(),
+            url: "https://relay.example".to_string(),
+            model: Some("opus".to_string()),
+            ..Default::default()
+        };
+        let frame = render_launch_preview(Some(&config), &FieldMask::new(&["url"]), 60).join("\n");
+        assert!(frame.contains("Enter to launch, Esc to go back"));
+        assert!(frame.contains("relay") && frame.contains("opus"));
+        assert!(!frame.contains("https://relay.example"));
+
+        let official = render_launch_preview(None, &FieldMask::default(), 60).join("\n");
+        assert!(official.contains("official"));
+    }
 
     #[test]
-    fn test_border_drawing_unicode_support() {
-        let _border = BorderDrawing::new();
-        // Should create without panic - testing that BorderDrawing can be instantiated
+    fn only_the_first_key_can_be_a_fast_selection() {
+        let mut state = MenuState::new(configs(&["a", "b"]), false);
+        assert!(state.is_first_event_selection(KeyCode::Char('2')));
+        assert!(state.is_first_event_selection(KeyCode::Char('R')));
+        assert!(state.is_first_event_selection(KeyCode::Char('q')));
+        assert!(!state.is_first_event_selection(KeyCode::Char('3')));
+        assert!(!state.is_first_event_selection(KeyCode::Enter));
+        assert!(!state.is_first_event_selection(KeyCode::Down));
+
+        state.keys_seen += 1;
+        state.move_down();
+        assert!(!state.is_first_event_selection(KeyCode::Char('1')));
+        assert!(!state.is_first_event_selection(KeyCode::Char('r')));
     }
 
     #[test]
-    fn test_border_drawing_top_border() {
-        let border = BorderDrawing {
-            unicode_supported: true,
-        };
-        let result = border.draw_top_border("Test", 20);
-        assert!(!result.is_empty());
-        assert!(result.contains("Test"));
+    fn action_index_maps_rows_back_to_config_order() {
+        let mut state = grouped_state();
+        state.move_down();
+        // configs are sorted by alias: billing, eu-relay, sandbox, solo
+        assert_eq!(state.action_index(), 3);
+        state.selected_index = state.exit_index();
+        assert_eq!(state.action_index(), 5);
     }
 
     #[test]
-    fn test_border_drawing_ascii_fallback() {
-        let border = BorderDrawing {
-            unicode_supported: false,
-        };
-        let result = border.draw_top_border("Test", 20);
-        assert!(!result.is_empty());
-        assert!(result.contains("Test"));
-        assert!(result.contains("+"));
-        assert!(result.contains("-"));
+    fn tab_collapses_and_expands_group_under_cursor() {
+        let mut state = grouped_state();
+        state.move_down();
+        state.move_down();
+        assert_eq!(state.selected_config().unwrap().alias_name, "billing");
+
+        state.toggle_group();
+        assert_eq!(
+            row_labels(&state),
+            vec!["-dev", "sandbox", "+prod", "-untagged", "solo"]
+        );
+        assert!(
+            matches!(state.row_at(state.selected_index), Some(MenuRow::Header { group, .. }) if group == "prod"),
+            "collapsed header stays selectable"
+        );
+        state.move_down();
+        assert_eq!(state.selected_config().unwrap().alias_name, "solo");
+        state.move_up();
+
+        state.toggle_group();
+        assert_eq!(state.selected_config().unwrap().alias_name, "billing");
+        assert_eq!(row_labels(&state).len(), 7);
     }
 
     #[test]
-    fn test_border_drawing_middle_line() {
-        let border = BorderDrawing {
-            unicode_supported: true,
-        };
-        let result = border.draw_middle_line("Test message", 30);
-        assert!(!result.is_empty());
-        assert!(result.contains("Test message"));
+    fn collapsed_groups_survive_reload_unless_focused() {
+        let mut state = grouped_state();
+        state.move_down();
+        state.toggle_group(); // collapse dev
+        let configs = state.configs.clone();
+        state.reload(configs.clone(), "billing");
+        assert!(state.collapsed.contains("dev"));
+        assert_eq!(state.selected_config().unwrap().alias_name, "billing");
+
+        state.reload(configs, "sandbox");
+        assert!(
+            !state.collapsed.contains("dev"),
+            "focused group is expanded"
+        );
+        assert_eq!(state.selected_config().unwrap().alias_name, "sandbox");
     }
 
     #[test]
-    fn test_border_drawing_bottom_border() {
-        let border = BorderDrawing {
-            unicode_supported: true,
-        };
-        let result = border.draw_bottom_border(20);
-        assert!(!result.is_empty());
+    fn toggling_mode_keeps_selected_config() {
+        let mut state = grouped_state();
+        state.move_down();
+        state.move_down();
+        state.toggle_grouped();
+        assert!(!state.grouped);
+        assert_eq!(state.selected_config().unwrap().alias_name, "billing");
+        assert_eq!(state.selected_index, 1);
+        state.toggle_grouped();
+        assert_eq!(state.selected_config().unwrap().alias_name, "billing");
     }
 
     #[test]
-    fn test_border_drawing_width_consistency() {
-        let border = BorderDrawing {
-            unicode_supported: true,
-        };
-        let width = 30;
-        let top = border.draw_top_border("Title", width);
-        let middle = border.draw_middle_line("Content", width);
-        let bottom = border.draw_bottom_border(width);
+    fn grouped_pages_hold_nine_configs_plus_headers() {
+        let configs: Vec<Configuration> = (0..12)
+            .map(|i| tagged(&format!("c{i:02}"), &[if i < 5 { "a" } else { "b" }]))
+            .collect();
+        let state = MenuState::new(configs, true);
+        let pages = state.pages();
+        assert_eq!(pages.len(), 2);
+        let configs_on_first = pages[0]
+            .clone()
+            .filter(|&row| matches!(state.rows[row], MenuRow::Config(_)))
+            .count();
+        assert_eq!(configs_on_first, 9);
+        assert_eq!(pages[0], 0..11, "two headers plus nine configs");
+    }
 
-        // All borders should have the same character length (approximately)
-        assert!(top.chars().count() >= width - 2);
-        assert!(middle.chars().count() >= width - 2);
-        assert!(bottom.chars().count() >= width - 2);
+    #[test]
+    fn total_pages_is_at_least_one() {
+        assert_eq!(MenuState::new(Vec::new(), false).total_pages(), 1);
+        let names: Vec<String> = (0..10).map(|i| format!("c{i}")).collect();
+        let refs: Vec<&str> = names.iter().map(String::as_str).collect();
+        assert_eq!(MenuState::new(configs(&refs), false).total_pages(), 2);
     }
 }
 
@@ -1846,8 +4086,25 @@ impl std::fmt::Display for EditModeError {
 
 impl std::error::Error for EditModeError {}
 
+/// Open the field editor for one stored configuration (`cc-switch edit`)
+///
+/// # Errors
+/// Returns error if the alias is missing, input fails or saving fails
+pub fn edit_configuration(storage: &ConfigStorage, alias_name: &str) -> Result<()> {
+    let config = storage
+        .get_configuration(alias_name)
+        .ok_or_else(|| crate::exit_code::config_not_found(alias_name))?;
+    match handle_config_edit(config, &FieldMask::from_storage(storage)) {
+        Err(e) if e.downcast_ref::<EditModeError>() == Some(&EditModeError::ReturnToMenu) => Ok(()),
+        result => result.map(|_| ()),
+    }
+}
+
 /// Handle configuration editing with interactive field selection
-fn handle_config_edit(config: &Configuration) -> Result<()> {
+///
+/// Returns the alias the configuration is stored under afterwards, which
+/// differs from `config.alias_name` when the alias was renamed and saved.
+fn handle_config_edit(config: &Configuration, mask: &FieldMask) -> Result<String> {
     println!("\n{}", "配置编辑模式".green().bold());
     println!("{}", "===================".green());
     println!("正在编辑配置: {}", config.alias_name.cyan().bold());
@@ -1859,11 +4116,11 @@ fn handle_config_edit(config: &Configuration) -> Result<()> {
 
     loop {
         // Display current field values
-        display_edit_menu(&editing_config);
+        display_edit_menu(&editing_config, mask);
 
         // Get user input for field selection
         println!("\n{}", "提示: 可使用大小写字母".dimmed());
-        print!("请选择要编辑的字段 (1-9, A-H), 或输入 S 保存, Q 返回上一级菜单: ");
+        print!("请选择要编辑的字段 (1-9, A-K), 或输入 S 保存, Q 返回上一级菜单: ");
         io::stdout().flush()?;
 
         let mut input = String::new();
@@ -1872,7 +4129,7 @@ fn handle_config_edit(config: &Configuration) -> Result<()> {
 
         // Note: Both lowercase and uppercase are accepted for commands
         match input {
-            "1" => edit_field_alias(&mut editing_config)?,
+            "1" => edit_field_alias(&mut editing_config, &original_alias)?,
             "2" => edit_field_token(&mut editing_config)?,
             "3" => edit_field_url(&mut editing_config)?,
             "4" => edit_field_model(&mut editing_config)?,
@@ -1893,10 +4150,13 @@ fn handle_config_edit(config: &Configuration) -> Result<()> {
                 edit_field_claude_code_disable_experimental_betas(&mut editing_config)?
             }
             "17" | "h" | "H" => edit_field_disable_autoupdater(&mut editing_config)?,
-            "s" | "S" => {
-                // Save changes
-                return save_configuration_changes(&original_alias, &editing_config);
-            }
+            "18" | "i" | "I" => edit_field_url_variants(&mut editing_config)?,
+            "19" | "j" | "J" => edit_field_color(&mut editing_config)?,
+            "20" | "k" | "K" => edit_field_launch_args(&mut editing_config)?,
+            "s" | "S" => match save_configuration_changes(&original_alias, &editing_config)? {
+                SaveOutcome::Saved(alias) => return Ok(alias),
+                SaveOutcome::KeepEditing => println!("{}", "继续编辑，修改已保留".yellow()),
+            },
             "q" | "Q" => {
                 println!("\n{}", "返回上一级菜单".blue());
                 return Err(EditModeError::ReturnToMenu.into());
@@ -1909,141 +4169,167 @@ fn handle_config_edit(config: &Configuration) -> Result<()> {
 }
 
 /// Display the edit menu with current field values
-fn display_edit_menu(config: &Configuration) {
+fn display_edit_menu(config: &Configuration, mask: &FieldMask) {
+    let optional_value = |field: &str, value: Option<String>| match value {
+        Some(value) => mask.apply(field, value),
+        None => "[未设置]".to_string(),
+    };
+
     println!("\n{}", "当前配置值:".blue().bold());
     println!("{}", "─────────────────────────".blue());
 
     println!("1. 别名 (alias_name): {}", config.alias_name.green());
 
-    let (auth_label, auth_value) = config.auth_env_pair();
+    let (auth_label, _) = config.auth_env_pair();
     println!(
         "2. 令牌 ({}): {}",
         auth_label,
-        format_token_for_display(auth_value).green()
+        mask.apply("token", credential_for_display(config)).green()
     );
 
-    println!("3. URL (ANTHROPIC_BASE_URL): {}", config.url.green());
+    println!(
+        "3. URL (ANTHROPIC_BASE_URL): {}",
+        mask.apply("url", &config.url).green()
+    );
 
     println!(
         "4. 模型 (ANTHROPIC_MODEL): {}",
-        config.model.as_deref().unwrap_or("[未设置]").green()
+        optional_value("model", config.model.clone()).green()
     );
 
     println!(
         "5. 快速模型 (ANTHROPIC_SMALL_FAST_MODEL): {}",
-        config
-            .small_fast_model
-            .as_deref()
-            .unwrap_or("[未设置]")
-            .green()
+        optional_value("small_fast_model", config.small_fast_model.clone()).green()
     );
 
     println!(
         "6. 最大思考令牌数 (ANTHROPIC_MAX_THINKING_TOKENS): {}",
-        config
-            .max_thinking_tokens
-            .map(|t| t.to_string())
-            .unwrap_or("[未设置]".to_string())
-            .green()
+        optional_value(
+            "max_thinking_tokens",
+            config.max_thinking_tokens.map(|t| t.to_string())
+        )
+        .green()
     );
 
     println!(
         "7. API超时时间 (API_TIMEOUT_MS): {}",
-        config
-            .api_timeout_ms
-            .map(|t| t.to_string())
-            .unwrap_or("[未设置]".to_string())
-            .green()
+        optional_value(
+            "api_timeout_ms",
+            config.api_timeout_ms.map(|t| t.to_string())
+        )
+        .green()
     );
 
     println!(
         "8. 禁用非必要流量 (CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC): {}",
-        config
-            .claude_code_disable_nonessential_traffic
-            .map(|t| t.to_string())
-            .unwrap_or("[未设置]".to_string())
-            .green()
+        optional_value(
+            "claude_code_disable_nonessential_traffic",
+            config
+                .claude_code_disable_nonessential_traffic
+                .map(|t| t.to_string())
+        )
+        .green()
     );
 
     println!(
         "9. 默认 Sonnet 模型 (ANTHROPIC_DEFAULT_SONNET_MODEL): {}",
-        config
-            .anthropic_default_sonnet_model
-            .as_deref()
-            .unwrap_or("[未设置]")
-            .green()
+        optional_value(
+            "anthropic_default_sonnet_model",
+            config.anthropic_default_sonnet_model.clone()
+        )
+        .green()
     );
 
     println!(
         "A. 默认 Opus 模型 (ANTHROPIC_DEFAULT_OPUS_MODEL): {}",
-        config
-            .anthropic_default_opus_model
-            .as_deref()
-            .unwrap_or("[未设置]")
-            .green()
+        optional_value(
+            "anthropic_default_opus_model",
+            config.anthropic_default_opus_model.clone()
+        )
+        .green()
     );
 
     println!(
         "B. 默认 Haiku 模型 (ANTHROPIC_DEFAULT_HAIKU_MODEL): {}",
-        config
-            .anthropic_default_haiku_model
-            .as_deref()
-            .unwrap_or("[未设置]")
-            .green()
+        optional_value(
+            "anthropic_default_haiku_model",
+            config.anthropic_default_haiku_model.clone()
+        )
+        .green()
     );
 
     println!(
         "C. 子代理模型 (CLAUDE_CODE_SUBAGENT_MODEL): {}",
-        config
-            .claude_code_subagent_model
-            .as_deref()
-            .unwrap_or("[未设置]")
-            .green()
+        optional_value(
+            "claude_code_subagent_model",
+            config.claude_code_subagent_model.clone()
+        )
+        .green()
     );
 
     println!(
         "D. 禁用非流式回退 (CLAUDE_CODE_DISABLE_NONSTREAMING_FALLBACK): {}",
-        config
-            .claude_code_disable_nonstreaming_fallback
-            .map(|t| t.to_string())
-            .unwrap_or("[未设置]".to_string())
-            .green()
+        optional_value(
+            "claude_code_disable_nonstreaming_fallback",
+            config
+                .claude_code_disable_nonstreaming_fallback
+                .map(|t| t.to_string())
+        )
+        .green()
     );
 
     println!(
         "E. 努力级别 (CLAUDE_CODE_EFFORT_LEVEL): {}",
-        config
-            .claude_code_effort_level
-            .as_deref()
-            .unwrap_or("[未设置]")
-            .green()
+        optional_value(
+            "claude_code_effort_level",
+            config.claude_code_effort_level.clone()
+        )
+        .green()
     );
 
     println!(
         "F. 禁用提示缓存 (DISABLE_PROMPT_CACHING): {}",
-        config
-            .disable_prompt_caching
-            .map(|t| t.to_string())
-            .unwrap_or("[未设置]".to_string())
-            .green()
+        optional_value(
+            "disable_prompt_caching",
+            config.disable_prompt_caching.map(|t| t.to_string())
+        )
+        .green()
     );
 
     println!(
         "G. 禁用实验性功能 (CLAUDE_CODE_DISABLE_EXPERIMENTAL_BETAS): {}",
-        config
-            .claude_code_disable_experimental_betas
-            .map(|t| t.to_string())
-            .unwrap_or("[未设置]".to_string())
-            .green()
+        optional_value(
+            "claude_code_disable_experimental_betas",
+            config
+                .claude_code_disable_experimental_betas
+                .map(|t| t.to_string())
+        )
+        .green()
     );
 
     println!(
         "H. 禁用自动更新 (DISABLE_AUTOUPDATER): {}",
-        config
-            .disable_autoupdater
-            .map(|t| t.to_string())
-            .unwrap_or("[未设置]".to_string())
-            .green()
+        optional_value(
+            "disable_autoupdater",
+            config.disable_autoupdater.map(|t| t.to_string())
+        )
+        .green()
+    );
+
+    println!(
+        "I. URL 变体 (url_variants): {}",
+        optional_value("url", format_url_variants(config)).green()
+    );
+
+    println!(
+        "J. 标签颜色 (color): {}{}",
+        color_label(config),
+        optional_value("color", config.color.clone()).green()
+    );
+
+    println!(
+        "K. 启动参数 (launch_args): {}",
+        optional_value("launch_args", config.field_value("launch_args")).green()
     );
 
     println!("{}", "─────────────────────────".blue());
@@ -2078,6 +4364,25 @@ pub(crate) fn edit_string_field(
     }
 }
 
+/// Edit a token-like field without echoing the new value
+///
+/// `current_display` is the masked current value.
+fn edit_secret_field(field_name: &str, current_display: &str) -> Result<Option<String>> {
+    println!("\n编辑{field_name}:");
+    println!("当前值: {}", current_display.cyan());
+    let input = read_sensitive_input("新值 (回车保持不变): ")?;
+
+    if !input.is_empty() {
+        println!(
+            "{field_name}已更新为: {}",
+            format_token_for_display(&input).green()
+        );
+        Ok(Some(input))
+    } else {
+        Ok(None)
+    }
+}
+
 /// Type alias for optional string field result
 pub(crate) type OptionalStringResult = Result<Option<Option<String>>>;
 
@@ -2144,23 +4449,55 @@ fn edit_optional_u32_field(field_name: &str, current_value: Option<u32>) -> Opti
     }
 }
 
-/// Edit alias field
-fn edit_field_alias(config: &mut Configuration) -> Result<()> {
+/// Whether a new alias typed in the edit menu is free
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum AliasAvailability {
+    /// Unchanged, or not used by another configuration
+    Free,
+    /// Used by another configuration; saving asks before overwriting it
+    Taken,
+}
+
+/// Check `candidate` as the new alias of the configuration stored as
+/// `original_alias`, given the aliases in storage
+///
+/// # Errors
+/// Returns the [`crate::config::validate_alias_name`] error for an invalid name
+pub(crate) fn check_alias_rename(
+    candidate: &str,
+    original_alias: &str,
+    existing: &BTreeSet<&str>,
+) -> Result<AliasAvailability> {
+    crate::config::validate_alias_name(candidate)?;
+    if candidate != original_alias && existing.contains(candidate) {
+        Ok(AliasAvailability::Taken)
+    } else {
+        Ok(AliasAvailability::Free)
+    }
+}
+
+/// Edit alias field, warning right away when the new alias is taken
+fn edit_field_alias(config: &mut Configuration, original_alias: &str) -> Result<()> {
+    let storage = ConfigStorage::load()?;
+    let existing: BTreeSet<&str> = storage.configurations.keys().map(String::as_str).collect();
+    let availability = std::cell::Cell::new(AliasAvailability::Free);
     let validator = |input: &str| -> Result<()> {
-        if input.contains(char::is_whitespace) {
-            anyhow::bail!("错误: 别名不能包含空白字符");
-        }
-        if input == "cc" {
-            anyhow::bail!("错误: 'cc' 是保留名称");
-        }
-        if input == "official" {
-            anyhow::bail!("错误: 'official' 是保留名称");
-        }
+        let checked = check_alias_rename(input, original_alias, &existing)
+            .map_err(|e| anyhow::anyhow!("错误: {e}"))?;
+        availability.set(checked);
         Ok(())
     };
 
     match edit_string_field("别名", &config.alias_name, validator) {
-        Ok(Some(new_value)) => config.alias_name = new_value,
+        Ok(Some(new_value)) => {
+            if availability.get() == AliasAvailability::Taken {
+                println!(
+                    "{}",
+                    format!("别名 '{new_value}' 已存在，保存时需要确认是否覆盖").yellow()
+                );
+            }
+            config.alias_name = new_value;
+        }
         Ok(None) => {}
         Err(e) => println!("{}", e.to_string().red()),
     }
@@ -2171,32 +4508,27 @@ fn edit_field_alias(config: &mut Configuration) -> Result<()> {
 fn edit_field_token(config: &mut Configuration) -> Result<()> {
     let (auth_label, auth_value) = config.auth_env_pair();
     let label = format!("令牌 ({})", auth_label);
-    let no_validator = |_: &str| -> Result<()> { Ok(()) };
+    let before = auth_value.to_string();
+    let current = credential_for_display(config);
 
     let auth_type = read_input("切换认证类型? (1) AUTH_TOKEN (2) API_KEY (Enter 保持不变): ")?;
     match auth_type.as_str() {
         "2" => {
-            if let Some(new_value) =
-                edit_string_field(&label, &format_token_for_display(auth_value), no_validator)?
-            {
+            if let Some(new_value) = edit_secret_field(&label, &current)? {
                 config.api_key = Some(new_value);
                 config.token = String::new();
                 println!("{}", "已切换到 ANTHROPIC_API_KEY 并更新".green());
             }
         }
         "1" => {
-            if let Some(new_value) =
-                edit_string_field(&label, &format_token_for_display(auth_value), no_validator)?
-            {
+            if let Some(new_value) = edit_secret_field(&label, &current)? {
                 config.token = new_value;
                 config.api_key = None;
                 println!("{}", "已切换到 ANTHROPIC_AUTH_TOKEN 并更新".green());
             }
         }
         _ => {
-            if let Some(new_value) =
-                edit_string_field(&label, &format_token_for_display(auth_value), no_validator)?
-            {
+            if let Some(new_value) = edit_secret_field(&label, &current)? {
                 if config.api_key.is_some() {
                     config.api_key = Some(new_value);
                 } else {
@@ -2206,22 +4538,44 @@ fn edit_field_token(config: &mut Configuration) -> Result<()> {
             }
         }
     }
+    if config.auth_env_pair().1 != before {
+        // A stored token replaces the command
+        config.token_command = None;
+        for warning in config.token_warnings() {
+            println!("{}", format!("警告: {warning}").yellow());
+        }
+    }
     Ok(())
 }
 
 /// Edit URL field
 fn edit_field_url(config: &mut Configuration) -> Result<()> {
-    let no_validator = |_: &str| -> Result<()> { Ok(()) };
-    if let Some(new_value) = edit_string_field("URL", &config.url, no_validator)? {
-        config.url = new_value;
+    let validator = |value: &str| validate_base_url(value).map(|_| ());
+    if let Some(new_value) = edit_string_field("URL", &config.url, validator)? {
+        config.url = validate_base_url(&new_value)?;
     }
     Ok(())
 }
 
+/// Print the warning (or, off the official API, the note) about the model
+/// in `field` when it isn't a known Anthropic model
+fn print_model_notice(config: &Configuration, field: &str) {
+    for notice in config.model_notices() {
+        if notice.field == field {
+            let label = match notice.level {
+                ModelNoticeLevel::Warning => "警告",
+                ModelNoticeLevel::Note => "提示",
+            };
+            println!("{}", format!("{label}: {notice}").yellow());
+        }
+    }
+}
+
 /// Edit model field
 fn edit_field_model(config: &mut Configuration) -> Result<()> {
     if let Some(result) = edit_optional_string_field("模型", config.model.as_deref())? {
         config.model = result;
+        print_model_notice(config, "model");
     }
     Ok(())
 }
@@ -2232,6 +4586,7 @@ fn edit_field_small_fast_model(config: &mut Configuration) -> Result<()> {
         edit_optional_string_field("快速模型", config.small_fast_model.as_deref())?
     {
         config.small_fast_model = result;
+        print_model_notice(config, "small_fast_model");
     }
     Ok(())
 }
@@ -2272,6 +4627,7 @@ fn edit_field_anthropic_default_sonnet_model(config: &mut Configuration) -> Resu
         config.anthropic_default_sonnet_model.as_deref(),
     )? {
         config.anthropic_default_sonnet_model = result;
+        print_model_notice(config, "anthropic_default_sonnet_model");
     }
     Ok(())
 }
@@ -2283,6 +4639,7 @@ fn edit_field_anthropic_default_opus_model(config: &mut Configuration) -> Result
         config.anthropic_default_opus_model.as_deref(),
     )? {
         config.anthropic_default_opus_model = result;
+        print_model_notice(config, "anthropic_default_opus_model");
     }
     Ok(())
 }
@@ -2294,6 +4651,7 @@ fn edit_field_anthropic_default_haiku_model(config: &mut Configuration) -> Resul
         config.anthropic_default_haiku_model.as_deref(),
     )? {
         config.anthropic_default_haiku_model = result;
+        print_model_notice(config, "anthropic_default_haiku_model");
     }
     Ok(())
 }
@@ -2304,6 +4662,7 @@ fn edit_field_claude_code_subagent_model(config: &mut Configuration) -> Result<(
         edit_optional_string_field("子代理模型", config.claude_code_subagent_model.as_deref())?
     {
         config.claude_code_subagent_model = result;
+        print_model_notice(config, "claude_code_subagent_model");
     }
     Ok(())
 }
@@ -2359,35 +4718,571 @@ fn edit_field_disable_autoupdater(config: &mut Configuration) -> Result<()> {
     Ok(())
 }
 
+/// Add, change or remove URL variants until an empty name is entered
+fn edit_field_url_variants(config: &mut Configuration) -> Result<()> {
+    println!(
+        "\n编辑URL 变体 (使用 cc-switch use {}@<变体>):",
+        config.alias_name
+    );
+    loop {
+        println!(
+            "当前变体: {}",
+            format_url_variants(config)
+                .unwrap_or("[未设置]".to_string())
+                .cyan()
+        );
+        let name = read_input("变体名称 (回车结束): ")?;
+        if name.is_empty() {
+            return Ok(());
+        }
+        if name.contains(char::is_whitespace) || name.contains('@') {
+            println!("{}", "错误: 变体名称不能包含空白字符或 '@'".red());
+            continue;
+        }
+        let url = read_input("URL (留空删除该变体): ")?;
+        if url.is_empty() {
+            if config.url_variants.remove(&name).is_some() {
+                println!("{}", format!("变体 '{name}' 已删除").green());
+            }
+        } else {
+            config.url_variants.insert(name.clone(), url);
+            println!("{}", format!("变体 '{name}' 已更新").green());
+        }
+    }
+}
+
+/// Edit the label color
+fn edit_field_color(config: &mut Configuration) -> Result<()> {
+    println!(
+        "\n可选颜色: {}",
+        crate::config::LABEL_COLORS.join(", ").dimmed()
+    );
+    if let Some(result) = edit_optional_string_field("标签颜色", config.color.as_deref())? {
+        match result.as_deref().map(crate::config::parse_label_color) {
+            Some(Err(e)) => println!("{}", format!("错误: {e}").red()),
+            Some(Ok(color)) => config.color = Some(color),
+            None => config.color = None,
+        }
+    }
+    Ok(())
+}
+
+/// Edit the arguments passed to Claude, entered as one shell-quoted line
+fn edit_field_launch_args(config: &mut Configuration) -> Result<()> {
+    let current = config.field_value("launch_args");
+    if let Some(result) = edit_optional_string_field("启动参数", current.as_deref())? {
+        match crate::config::types::parse_launch_args(result.as_deref().unwrap_or_default()) {
+            Ok(args) => config.launch_args = args,
+            Err(e) => println!("{}", format!("错误: {e}").red()),
+        }
+    }
+    Ok(())
+}
+
+/// What became of an attempt to save an edited configuration
+#[derive(Debug, PartialEq)]
+pub(crate) enum SaveOutcome {
+    /// Saved under this alias
+    Saved(String),
+    /// Not saved; the edit menu continues with the changes intact
+    KeepEditing,
+}
+
 /// Save configuration changes to disk and handle alias conflicts
-fn save_configuration_changes(original_alias: &str, new_config: &Configuration) -> Result<()> {
-    // Load current storage
+///
+/// The conflict prompts run against a snapshot of the store, before the
+/// lock is taken, so another cc-switch is never kept waiting on them.
+fn save_configuration_changes(
+    original_alias: &str,
+    new_config: &Configuration,
+) -> Result<SaveOutcome> {
     let mut storage = ConfigStorage::load()?;
+    let Some(config) =
+        resolve_alias_conflict(&storage, original_alias, new_config, &mut StdinPrompter)?
+    else {
+        return Ok(SaveOutcome::KeepEditing);
+    };
+    let overwrite = renames_onto_existing(&storage, original_alias, &config);
+    let outcome = storage.with_lock(|storage| {
+        commit_configuration_changes(storage, original_alias, config, overwrite)
+    })?;
+    if matches!(outcome, SaveOutcome::Saved(_)) {
+        println!("\n{}", "配置已成功保存!".green().bold());
+    }
+    Ok(outcome)
+}
 
-    // Check for alias conflicts if alias changed
-    if original_alias != new_config.alias_name
-        && storage.get_configuration(&new_config.alias_name).is_some()
-    {
+/// Whether saving `config` renames `original_alias` onto another stored alias
+fn renames_onto_existing(
+    storage: &ConfigStorage,
+    original_alias: &str,
+    config: &Configuration,
+) -> bool {
+    original_alias != config.alias_name && storage.get_configuration(&config.alias_name).is_some()
+}
+
+/// Resolve a rename of `original_alias` onto an existing alias by
+/// overwriting it or picking another alias
+///
+/// Returns the configuration to save, or `None` to go back to editing.
+fn resolve_alias_conflict(
+    storage: &ConfigStorage,
+    original_alias: &str,
+    new_config: &Configuration,
+    prompter: &mut dyn Prompter,
+) -> Result<Option<Configuration>> {
+    let mut config = new_config.clone();
+
+    while renames_onto_existing(storage, original_alias, &config) {
         println!("\n{}", "别名冲突!".red().bold());
-        println!("配置 '{}' 已存在", new_config.alias_name.yellow());
-        print!("是否覆盖现有配置? (y/N): ");
-        io::stdout().flush()?;
+        println!("配置 '{}' 已存在", config.alias_name.yellow());
+        let choice = prompter.ask("[o] 覆盖现有配置  [r] 使用其他别名  [e] 继续编辑 (默认): ")?;
+        match choice.to_lowercase().as_str() {
+            "o" | "overwrite" => break,
+            "r" | "rename" => {
+                let alias = prompter.ask("新别名: ")?;
+                if alias.is_empty() {
+                    continue;
+                }
+                match crate::config::validate_alias_name(&alias) {
+                    Ok(()) => config.alias_name = alias,
+                    Err(e) => println!("{}", format!("错误: {e}").red()),
+                }
+            }
+            _ => return Ok(None),
+        }
+    }
+    Ok(Some(config))
+}
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-        let input = input.trim().to_lowercase();
+/// Store `config` in place of `original_alias` in the locked `storage`
+///
+/// `overwrite` is whether the user agreed to replace the alias `config` is
+/// renamed to; one that another run added since then sends the user back to
+/// editing instead.
+fn commit_configuration_changes(
+    storage: &mut ConfigStorage,
+    original_alias: &str,
+    config: Configuration,
+    overwrite: bool,
+) -> Result<SaveOutcome> {
+    if !overwrite && renames_onto_existing(storage, original_alias, &config) {
+        println!(
+            "\n{}",
+            format!(
+                "配置 '{}' 刚被另一个 cc-switch 添加, 未保存",
+                config.alias_name
+            )
+            .red()
+        );
+        return Ok(SaveOutcome::KeepEditing);
+    }
+    // The old alias is removed and the new one inserted in one step, which
+    // also replaces an overwritten configuration
+    let alias_name = config.alias_name.clone();
+    storage.update_configuration(original_alias, config)?;
+    Ok(SaveOutcome::Saved(alias_name))
+}
 
-        if input != "y" && input != "yes" {
-            println!("{}", "编辑已取消".yellow());
-            return Ok(());
+#[cfg(test)]
+mod save_tests {
+    use super::*;
+
+    /// Prompter answering `ask` from a script, in order
+    struct ScriptedAnswers(Vec<&'static str>);
+
+    impl Prompter for ScriptedAnswers {
+        fn is_interactive(&self) -> bool {
+            true
+        }
+
+        fn confirm(&mut self, _prompt: &str) -> Result<bool> {
+            Ok(false)
+        }
+
+        fn ask(&mut self, _prompt: &str) -> Result<String> {
+            Ok(self.0.remove(0).to_string())
+        }
+    }
+
+    fn config(alias: &str, url: &str) -> Configuration {
+        Configuration {
+            alias_name: alias.to_string(),
+            token: "sk-ant-token".to_string(),
+            url: url.to_string(),
+            ..Default::default()
         }
     }
 
-    // Update configuration using the method from config_storage.rs
-    storage.update_configuration(original_alias, new_config.clone())?;
-    storage.save()?;
+    /// Storage with `work` and `home`, and `work` edited into a rename onto `home`
+    fn colliding_rename() -> (ConfigStorage, Configuration) {
+        let mut storage = ConfigStorage::default();
+        storage.add_configuration(config("work", "https://work.example.com"));
+        storage.add_configuration(config("home", "https://home.example.com"));
+        (storage, config("home", "https://edited.example.com"))
+    }
 
-    println!("\n{}", "配置已成功保存!".green().bold());
+    fn apply(
+        storage: &mut ConfigStorage,
+        edited: &Configuration,
+        answers: Vec<&'static str>,
+    ) -> SaveOutcome {
+        let mut prompter = ScriptedAnswers(answers);
+        let resolved = resolve_alias_conflict(storage, "work", edited, &mut prompter).unwrap();
+        assert!(prompter.0.is_empty(), "unused answers: {:?}", prompter.0);
+        let Some(config) = resolved else {
+            return SaveOutcome::KeepEditing;
+        };
+        let overwrite = renames_onto_existing(storage, "work", &config);
+        commit_configuration_changes(storage, "work", config, overwrite).unwrap()
+    }
 
-    Ok(())
+    #[test]
+    fn overwrite_replaces_the_existing_alias() {
+        let (mut storage, edited) = colliding_rename();
+        assert_eq!(
+            apply(&mut storage, &edited, vec!["o"]),
+            SaveOutcome::Saved("home".to_string())
+        );
+        assert!(storage.get_configuration("work").is_none());
+        assert_eq!(
+            storage.get_configuration("home").unwrap().url,
+            "https://edited.example.com"
+        );
+    }
+
+    #[test]
+    fn another_alias_is_validated_and_rechecked() {
+        // Invalid, empty, colliding again, then free
+        let (mut storage, edited) = colliding_rename();
+        let outcome = apply(
+            &mut storage,
+            &edited,
+            vec!["r", "has space", "r", "", "r", "home", "r", "office"],
+        );
+        assert_eq!(outcome, SaveOutcome::Saved("office".to_string()));
+        assert!(storage.get_configuration("work").is_none());
+        assert_eq!(
+            storage.get_configuration("home").unwrap().url,
+            "https://home.example.com"
+        );
+        assert_eq!(
+            storage.get_configuration("office").unwrap().url,
+            "https://edited.example.com"
+        );
+    }
+
+    #[test]
+    fn alias_rename_check_uses_full_validation_and_storage() {
+        let existing: BTreeSet<&str> = ["work", "home"].into_iter().collect();
+        let check = |candidate| check_alias_rename(candidate, "work", &existing);
+
+        for invalid in ["", "has space", "cc", "official"] {
+            assert!(check(invalid).is_err(), "{invalid:?} should be rejected");
+        }
+        assert_eq!(check("home").unwrap(), AliasAvailability::Taken);
+        assert_eq!(check("office").unwrap(), AliasAvailability::Free);
+        // Its own name is never a conflict
+        assert_eq!(check("work").unwrap(), AliasAvailability::Free);
+    }
+
+    #[test]
+    fn renaming_back_to_the_original_alias_saves_without_asking() {
+        let (mut storage, _) = colliding_rename();
+        let edited = config("work", "https://edited.example.com");
+        assert_eq!(
+            apply(&mut storage, &edited, Vec::new()),
+            SaveOutcome::Saved("work".to_string())
+        );
+        assert_eq!(
+            storage.get_configuration("work").unwrap().url,
+            "https://edited.example.com"
+        );
+        assert!(storage.get_configuration("home").is_some());
+    }
+
+    #[test]
+    fn keep_editing_leaves_storage_untouched() {
+        for answer in ["e", ""] {
+            let (mut storage, edited) = colliding_rename();
+            assert_eq!(
+                apply(&mut storage, &edited, vec![answer]),
+                SaveOutcome::KeepEditing
+            );
+            assert_eq!(
+                storage.get_configuration("work").unwrap().url,
+                "https://work.example.com"
+            );
+            assert_eq!(
+                storage.get_configuration("home").unwrap().url,
+                "https://home.example.com"
+            );
+        }
+    }
+
+    #[test]
+    fn an_alias_taken_after_the_prompts_is_not_overwritten() {
+        // `office` was free when the user picked it, then another run added it
+        let (mut storage, _) = colliding_rename();
+        storage.add_configuration(config("office", "https://office.example.com"));
+        let edited = config("office", "https://edited.example.com");
+        assert_eq!(
+            commit_configuration_changes(&mut storage, "work", edited, false).unwrap(),
+            SaveOutcome::KeepEditing
+        );
+        assert_eq!(
+            storage.get_configuration("office").unwrap().url,
+            "https://office.example.com"
+        );
+        assert!(storage.get_configuration("work").is_some());
+    }
+}
+
+#[cfg(test)]
+mod selection_tests {
+    use super::*;
+    use crate::config::types::ClaudePaths;
+
+    /// What a launcher was asked to run
+    #[derive(Debug, PartialEq)]
+    struct Launched {
+        env_vars: BTreeMap<String, String>,
+        unset_keys: Vec<String>,
+        invocation: ClaudeInvocation,
+    }
+
+    /// Launches, then aliases recorded as last used
+    #[derive(Default)]
+    struct RecordingLauncher(Vec<Launched>, Vec<String>);
+
+    impl Launcher for RecordingLauncher {
+        fn record_use(&mut self, alias_name: &str) {
+            self.1.push(alias_name.to_string());
+        }
+
+        fn launch(
+            &mut self,
+            env_config: EnvironmentConfig,
+            invocation: &ClaudeInvocation,
+            _quiet: bool,
+        ) -> Result<()> {
+            self.0.push(Launched {
+                env_vars: env_config.env_vars,
+                unset_keys: env_config.unset_keys,
+                invocation: invocation.clone(),
+            });
+            Ok(())
+        }
+    }
+
+    fn storage(settings_dir: &std::path::Path) -> ConfigStorage {
+        let mut storage = ConfigStorage {
+            claude: ClaudePaths {
+                settings_dir: Some(settings_dir.to_string_lossy().into_owned()),
+            },
+            // Any executable will do: launches are only recorded
+            claude_binary: Some(
+                std::env::current_exe()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned(),
+            ),
+            ..Default::default()
+        };
+        for (alias, url) in [
+            ("home", "https://home.example.com"),
+            ("work", "https://work.example.com"),
+        ] {
+            storage.add_configuration(Configuration {
+                alias_name: alias.to_string(),
+                token: format!("sk-ant-{alias}"),
+                url: url.to_string(),
+                model: Some("opus".to_string()),
+                ..Default::default()
+            });
+        }
+        storage
+    }
+
+    fn launch(storage: &ConfigStorage, selection: &Selection) -> Vec<Launched> {
+        let mut launcher = RecordingLauncher::default();
+        launch_selection(
+            storage,
+            selection,
+            &LaunchOptions::default(),
+            &mut StdinPrompter,
+            &mut launcher,
+        )
+        .unwrap();
+        launcher.0
+    }
+
+    #[test]
+    fn menu_indices_map_to_selections() {
+        let storage = ConfigStorage::default();
+        let configs = [
+            Configuration {
+                alias_name: "home".to_string(),
+                ..Default::default()
+            },
+            Configuration {
+                alias_name: "work".to_string(),
+                ..Default::default()
+            },
+        ];
+        let configs: Vec<&Configuration> = configs.iter().collect();
+        assert_eq!(handle_selection_action(&configs, 0), Selection::Official);
+        assert_eq!(
+            handle_selection_action(&configs, 2),
+            Selection::Config("work".to_string())
+        );
+        assert_eq!(handle_selection_action(&configs, 3), Selection::Exit);
+        assert!(launch(&storage, &Selection::Exit).is_empty());
+    }
+
+    #[test]
+    fn menu_and_direct_paths_launch_identically() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let storage = storage(tmp.path());
+        let sorted: Vec<&Configuration> = storage.configurations.values().collect();
+
+        for (index, alias) in [(0, "cc"), (2, "work")] {
+            let from_menu = launch(&storage, &handle_selection_action(&sorted, index));
+            let direct = launch(&storage, &Selection::from_alias(alias));
+            assert_eq!(from_menu.len(), 1);
+            assert_eq!(from_menu, direct, "{alias} launched differently");
+        }
+
+        let direct = launch(&storage, &Selection::from_alias("work"));
+        assert_eq!(
+            direct[0].env_vars.get("CC_SWITCH_CURRENT_ALIAS"),
+            Some(&"work".to_string())
+        );
+        assert_eq!(
+            direct[0].env_vars.get("ANTHROPIC_MODEL"),
+            Some(&"opus".to_string())
+        );
+    }
+
+    #[test]
+    fn only_named_launches_are_recorded_as_last_used() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let storage = storage(tmp.path());
+        let mut launcher = RecordingLauncher::default();
+        for selection in [Selection::Official, Selection::from_alias("work")] {
+            launch_selection(
+                &storage,
+                &selection,
+                &LaunchOptions::default(),
+                &mut StdinPrompter,
+                &mut launcher,
+            )
+            .unwrap();
+        }
+        assert_eq!(launcher.0.len(), 2);
+        assert_eq!(launcher.1, ["work"]);
+    }
+
+    #[test]
+    fn no_launch_records_the_switch_without_launching() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut storage = storage(tmp.path());
+        // Never resolved: nothing is launched
+        storage.claude_binary = Some("/nonexistent/claude".to_string());
+        let options = LaunchOptions {
+            no_launch: true,
+            ..Default::default()
+        };
+        let mut launcher = RecordingLauncher::default();
+        for selection in [Selection::from_alias("work"), Selection::Official] {
+            launch_selection(
+                &storage,
+                &selection,
+                &options,
+                &mut StdinPrompter,
+                &mut launcher,
+            )
+            .unwrap();
+        }
+        assert!(launcher.0.is_empty());
+        assert_eq!(launcher.1, ["work"]);
+    }
+
+    #[test]
+    fn skip_permissions_follows_preference_and_flag() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut storage = storage(tmp.path());
+        let skips = |launched: &[Launched]| {
+            launched[0]
+                .invocation
+                .args
+                .contains(&"--dangerously-skip-permissions".to_string())
+        };
+        assert!(skips(&launch(&storage, &Selection::from_alias("work"))));
+
+        let mut launcher = RecordingLauncher::default();
+        let options = LaunchOptions {
+            no_skip_permissions: true,
+            ..Default::default()
+        };
+        launch_selection(
+            &storage,
+            &Selection::Official,
+            &options,
+            &mut StdinPrompter,
+            &mut launcher,
+        )
+        .unwrap();
+        assert!(!skips(&launcher.0));
+
+        storage.preferences.no_skip_permissions = true;
+        assert!(!skips(&launch(&storage, &Selection::from_alias("work"))));
+    }
+
+    #[test]
+    fn stored_launch_args_come_before_passthrough_args() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut storage = storage(tmp.path());
+        let args = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        storage.configurations.get_mut("work").unwrap().launch_args =
+            args(&["--add-dir", "/src/mono repo"]);
+
+        let mut launcher = RecordingLauncher::default();
+        let options = LaunchOptions {
+            claude_args: args(&["--model", "opus"]),
+            prompt: Some("fix it".to_string()),
+            ..Default::default()
+        };
+        for alias in ["work", "home"] {
+            launch_selection(
+                &storage,
+                &Selection::from_alias(alias),
+                &options,
+                &mut StdinPrompter,
+                &mut launcher,
+            )
+            .unwrap();
+        }
+        assert_eq!(
+            launcher.0[0].invocation.args,
+            args(&[
+                "--dangerously-skip-permissions",
+                "--add-dir",
+                "/src/mono repo",
+                "--model",
+                "opus",
+                "fix it"
+            ])
+        );
+        // Other configurations keep only the passthrough arguments
+        assert_eq!(
+            launcher.0[1].invocation.args,
+            args(&[
+                "--dangerously-skip-permissions",
+                "--model",
+                "opus",
+                "fix it"
+            ])
+        );
+    }
 }
@@ -0,0 +1,275 @@
+//! What launching a configuration consists of: the environment Claude
+//! receives, the command line that is exec'd and the storage mode.
+//!
+//! [`launch_selection`](crate::interactive::launch_selection) launches from a
+//! [`LaunchPlan`] and `cc-switch resolve` prints one built the same way, so
+//! the printed plan is the launch rather than a description of it.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::cli::display_utils::format_token_for_display;
+use crate::config::EnvironmentConfig;
+use crate::config::types::{ConfigStorage, Configuration, StorageMode};
+use crate::interactive::interactive::{ClaudeInvocation, LaunchOptions};
+
+/// Variables holding a credential, masked unless revealed
+const SECRET_VARS: [&str; 2] = ["ANTHROPIC_AUTH_TOKEN", "ANTHROPIC_API_KEY"];
+
+/// A launch of one configuration, built before anything is changed
+#[derive(Clone)]
+pub struct LaunchPlan {
+    /// Alias as selected, including a `@variant` suffix
+    pub alias: String,
+    /// Variables set for, and inherited ones removed from, the process
+    pub env_config: EnvironmentConfig,
+    /// Command run, `None` with `--no-launch`
+    pub invocation: Option<ClaudeInvocation>,
+    pub storage_mode: StorageMode,
+}
+
+impl LaunchPlan {
+    /// Plan launching `config`, selected as `alias`
+    ///
+    /// `config` is the configuration as launched: with the daemon proxy URL
+    /// substituted and the token command already run. Launch profiles,
+    /// per-configuration launch arguments and the skip-permissions
+    /// preference are applied as for a launch with `options`.
+    ///
+    /// # Errors
+    /// Returns error if the launch profile doesn't exist or the Claude
+    /// binary can't be found
+    pub fn build(
+        alias: &str,
+        config: &Configuration,
+        storage: &ConfigStorage,
+        options: &LaunchOptions,
+    ) -> Result<Self> {
+        let skip_permissions =
+            !options.no_skip_permissions && storage.preferences.skip_permissions();
+        let invocation = match options.with.as_deref() {
+            _ if options.no_launch => None,
+            Some(name) => Some(ClaudeInvocation::for_profile(
+                config.launch_profile(name)?,
+                &options.claude_args,
+                options.prompt.as_deref(),
+            )),
+            None => Some(
+                ClaudeInvocation::new(
+                    skip_permissions,
+                    &config.claude_args(&options.claude_args),
+                    options.prompt.as_deref(),
+                    options.resume.as_deref(),
+                    options.continue_session,
+                )
+                .with_program(crate::platform::resolve_claude_binary(storage)?),
+            ),
+        };
+
+        Ok(LaunchPlan {
+            alias: alias.to_string(),
+            env_config: EnvironmentConfig::from_config(config).with_alias(alias),
+            invocation,
+            storage_mode: options.effective_storage_mode(storage),
+        })
+    }
+
+    /// Program followed by its arguments, empty without an invocation
+    pub fn argv(&self) -> Vec<String> {
+        self.invocation
+            .iter()
+            .flat_map(|invocation| {
+                std::iter::once(invocation.program.to_string_lossy().into_owned())
+                    .chain(invocation.args.iter().cloned())
+            })
+            .collect()
+    }
+
+    /// The plan with its credentials masked as in `list`
+    pub fn masked(mut self) -> Self {
+        for name in SECRET_VARS {
+            if let Some(value) = self.env_config.env_vars.get_mut(name) {
+                *value = format_token_for_display(value);
+            }
+        }
+        self
+    }
+}
+
+impl fmt::Display for LaunchPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Alias:   {}", self.alias)?;
+        writeln!(f, "Storage: {}", self.storage_mode.as_str())?;
+        if let Some(invocation) = &self.invocation {
+            writeln!(f, "Command: {}", invocation.command_line())?;
+        }
+        writeln!(f, "Environment:")?;
+        for (key, value) in &self.env_config.env_vars {
+            writeln!(f, "  {key}={value}")?;
+        }
+        if !self.env_config.unset_keys.is_empty() {
+            writeln!(f, "Unset:")?;
+            for key in &self.env_config.unset_keys {
+                writeln!(f, "  {key}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// JSON layout of a [`LaunchPlan`]
+#[derive(Serialize)]
+struct LaunchPlanJson<'a> {
+    alias: &'a str,
+    storage_mode: &'a StorageMode,
+    argv: Vec<String>,
+    env: &'a BTreeMap<String, String>,
+    unset: &'a [String],
+}
+
+impl Serialize for LaunchPlan {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        LaunchPlanJson {
+            alias: &self.alias,
+            storage_mode: &self.storage_mode,
+            argv: self.argv(),
+            env: &self.env_config.env_vars,
+            unset: &self.env_config.unset_keys,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::types::LaunchProfile;
+
+    /// Environment variables as `(name, value)` pairs
+    type EnvPairs<'a> = Vec<(&'a str, &'a str)>;
+
+    /// A configuration setting every field that reaches the launch
+    fn full_config() -> Configuration {
+        Configuration {
+            alias_name: "work".to_string(),
+            token: "sk-ant-REDACTED".to_string(),
+            url: "https://api.example.com".to_string(),
+            model: Some("claude-sonnet-4-5".to_string()),
+            small_fast_model: Some("claude-haiku-4-5".to_string()),
+            max_thinking_tokens: Some(8000),
+            api_timeout_ms: Some(60000),
+            claude_code_disable_nonessential_traffic: Some(1),
+            extra_env: BTreeMap::from([(
+                "ANTHROPIC_CUSTOM_HEADERS".to_string(),
+                "X-Org: 1".to_string(),
+            )]),
+            launch_args: vec!["--add-dir".to_string(), "/work".to_string()],
+            launch_profiles: BTreeMap::from([(
+                "review".to_string(),
+                LaunchProfile {
+                    command: "/bin/echo".to_string(),
+                    args: vec!["review".to_string()],
+                },
+            )]),
+            ..Default::default()
+        }
+    }
+
+    fn storage() -> ConfigStorage {
+        ConfigStorage {
+            claude_binary: Some("/bin/sh".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn plan_holds_the_environment_argv_and_storage_mode_of_a_launch() {
+        let options = LaunchOptions {
+            claude_args: vec!["--verbose".to_string()],
+            storage_mode: Some(StorageMode::Config),
+            ..Default::default()
+        };
+        let plan = LaunchPlan::build("work", &full_config(), &storage(), &options).unwrap();
+
+        assert_eq!(
+            plan.argv(),
+            [
+                "/bin/sh",
+                "--dangerously-skip-permissions",
+                "--add-dir",
+                "/work",
+                "--verbose"
+            ]
+        );
+        assert_eq!(plan.storage_mode, StorageMode::Config);
+        let env: EnvPairs = plan
+            .env_config
+            .env_vars
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        assert_eq!(
+            env,
+            [
+                (
+                    "ANTHROPIC_AUTH_TOKEN",
+                    "sk-ant-REDACTED"
+                ),
+                ("ANTHROPIC_BASE_URL", "https://api.example.com"),
+                ("ANTHROPIC_CUSTOM_HEADERS", "X-Org: 1"),
+                ("ANTHROPIC_MAX_THINKING_TOKENS", "8000"),
+                ("ANTHROPIC_MODEL", "claude-sonnet-4-5"),
+                ("ANTHROPIC_SMALL_FAST_MODEL", "claude-haiku-4-5"),
+                ("API_TIMEOUT_MS", "60000"),
+                ("CC_SWITCH_CURRENT_ALIAS", "work"),
+                ("CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC", "1"),
+            ]
+        );
+
+        let masked = plan.masked();
+        assert_eq!(
+            masked.env_config.env_vars["ANTHROPIC_AUTH_TOKEN"],
+            "sk-ant-api03...stuvwxyz"
+        );
+        let json = serde_json::to_value(&masked).unwrap();
+        assert_eq!(json["alias"], "work");
+        assert_eq!(json["storage_mode"], "config");
+        assert_eq!(json["argv"][0], "/bin/sh");
+        assert_eq!(json["env"]["ANTHROPIC_MODEL"], "claude-sonnet-4-5");
+        assert_eq!(
+            masked.to_string().lines().take(3).collect::<Vec<_>>(),
+            [
+                "Alias:   work",
+                "Storage: config",
+                "Command: /bin/sh --dangerously-skip-permissions --add-dir /work --verbose",
+            ]
+        );
+    }
+
+    #[test]
+    fn profiles_and_no_launch_change_only_the_invocation() {
+        let mut storage = storage();
+        storage.preferences.no_skip_permissions = true;
+        let options = LaunchOptions {
+            with: Some("review".to_string()),
+            ..Default::default()
+        };
+        let plan = LaunchPlan::build("work@eu", &full_config(), &storage, &options).unwrap();
+        assert_eq!(plan.argv(), ["/bin/echo", "review"]);
+        assert_eq!(
+            plan.env_config.env_vars["CC_SWITCH_CURRENT_ALIAS"],
+            "work@eu"
+        );
+        assert_eq!(plan.storage_mode, StorageMode::Env);
+
+        let options = LaunchOptions {
+            no_launch: true,
+            ..Default::default()
+        };
+        let plan = LaunchPlan::build("work", &full_config(), &storage, &options).unwrap();
+        assert!(plan.invocation.is_none() && plan.argv().is_empty());
+        assert!(!plan.to_string().contains("Command:"));
+    }
+}
@@ -1,10 +1,18 @@
 pub mod codex_interactive;
 #[allow(clippy::module_inception)]
 pub mod interactive;
+pub mod launch_plan;
+pub mod prompter;
+pub mod screen;
+pub mod secret;
 
 // Re-export functions for convenience
 pub use crate::interactive::codex_interactive::handle_codex_interactive_selection;
 pub use crate::interactive::interactive::{
-    handle_current_command, handle_interactive_selection, launch_claude_with_env, read_input,
-    read_sensitive_input,
+    ClaudeInvocation, ExecLauncher, LaunchOptions, Launcher, Selection, confirm_tagged_launch,
+    edit_configuration, handle_current_command, handle_interactive_selection,
+    launch_claude_invocation, launch_claude_with_env, launch_selection, read_input, shell_quote,
 };
+pub use crate::interactive::launch_plan::LaunchPlan;
+pub use crate::interactive::prompter::{Prompter, StdinPrompter};
+pub use crate::interactive::secret::read_sensitive_input;
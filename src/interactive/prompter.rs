@@ -0,0 +1,35 @@
+//! Yes/no confirmations and short answers behind a trait so pre-launch
+//! checks and prompts can be tested without a terminal.
+
+use anyhow::Result;
+use std::io::IsTerminal;
+
+/// Source of answers to yes/no questions
+pub trait Prompter {
+    /// Whether a user is present to answer (stdin is a terminal)
+    fn is_interactive(&self) -> bool;
+
+    /// Ask a yes/no question; only `y`/`yes` count as consent
+    fn confirm(&mut self, prompt: &str) -> Result<bool>;
+
+    /// Ask for a line of input, returned trimmed
+    fn ask(&mut self, prompt: &str) -> Result<String>;
+}
+
+/// Prompter reading answers from stdin
+pub struct StdinPrompter;
+
+impl Prompter for StdinPrompter {
+    fn is_interactive(&self) -> bool {
+        std::io::stdin().is_terminal()
+    }
+
+    fn confirm(&mut self, prompt: &str) -> Result<bool> {
+        let answer = crate::interactive::read_input(prompt)?;
+        Ok(matches!(answer.to_lowercase().as_str(), "y" | "yes"))
+    }
+
+    fn ask(&mut self, prompt: &str) -> Result<String> {
+        crate::interactive::read_input(prompt)
+    }
+}
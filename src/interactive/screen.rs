@@ -0,0 +1,510 @@
+//! Terminal handling for the raw-mode menus.
+//!
+//! Menus draw whole frames through [`MenuScreen`], either on the alternate
+//! screen or inline below the prompt (redrawn in place with cursor-up), and
+//! fall back to the numbered simple menus when neither is available.
+//! `CC_SWITCH_UI=inline|fullscreen|simple` forces a mode for debugging, and
+//! `CC_SWITCH_DEBUG_FRAMES` prints how many frames a menu drew on exit.
+//!
+//! Raw mode and the alternate screen are held by a [`TerminalGuard`], so
+//! the terminal is restored however a menu is left: Esc or Ctrl-C, an
+//! early `?` return, or a panic.
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyModifiers};
+use crossterm::{cursor, event, execute, queue, terminal};
+use std::io::{self, Write};
+use std::sync::Once;
+use std::time::Duration;
+
+use crate::cli::display_utils::text_display_width;
+
+/// How the interactive menus are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiMode {
+    /// Alternate screen, cleared for every frame
+    Fullscreen,
+    /// Below the current prompt, redrawn in place; scrollback is untouched
+    Inline,
+    /// Numbered menus read line by line, no raw mode
+    Simple,
+}
+
+/// Pick the UI mode from `CC_SWITCH_UI` and `TERM`
+///
+/// A valid `CC_SWITCH_UI` wins; otherwise `TERM=dumb` selects inline mode
+/// and everything else fullscreen.
+pub fn preferred_ui_mode(ui_override: Option<&str>, term: Option<&str>) -> UiMode {
+    match ui_override.map(str::trim) {
+        Some("fullscreen") => return UiMode::Fullscreen,
+        Some("inline") => return UiMode::Inline,
+        Some("simple") => return UiMode::Simple,
+        _ => {}
+    }
+    if term == Some("dumb") {
+        UiMode::Inline
+    } else {
+        UiMode::Fullscreen
+    }
+}
+
+/// [`preferred_ui_mode`] for the current environment
+pub fn ui_mode_from_env() -> UiMode {
+    preferred_ui_mode(
+        std::env::var("CC_SWITCH_UI").ok().as_deref(),
+        std::env::var("TERM").ok().as_deref(),
+    )
+}
+
+/// Width of `line` on screen, ignoring ANSI escape sequences and `\r`
+fn visible_width(line: &str) -> usize {
+    let mut visible = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\u{1b}' => {
+                for c in chars.by_ref() {
+                    if c.is_ascii_alphabetic() {
+                        break;
+                    }
+                }
+            }
+            '\r' => {}
+            c => visible.push(c),
+        }
+    }
+    text_display_width(&visible)
+}
+
+/// Terminal rows taken by `lines` at `columns` width, counting wrapped lines
+pub fn rows_for_lines(lines: &[String], columns: usize) -> usize {
+    let columns = columns.max(1);
+    lines
+        .iter()
+        .map(|line| visible_width(line).div_ceil(columns).max(1))
+        .sum()
+}
+
+/// Block for the next terminal event, then take every event already
+/// queued behind it
+///
+/// Key repeats arrive faster than a frame can be drawn; handling the whole
+/// burst before drawing keeps navigation from lagging behind the keyboard.
+/// With a `timeout`, no more than that is waited and an empty burst means
+/// nothing arrived.
+pub(crate) fn read_event_burst(timeout: Option<Duration>) -> io::Result<Vec<event::Event>> {
+    if let Some(timeout) = timeout
+        && !event::poll(timeout)?
+    {
+        return Ok(Vec::new());
+    }
+    let mut events = vec![event::read()?];
+    while event::poll(Duration::ZERO)? {
+        events.push(event::read()?);
+    }
+    Ok(events)
+}
+
+/// What a key press asks of a menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MenuCommand {
+    /// Leave the menu at once, choosing nothing (Ctrl-C)
+    Interrupt,
+    /// Go back one step, or leave the menu at its top level (Esc)
+    Back,
+    Up,
+    Down,
+    Confirm,
+    /// Any other key, left to the menu
+    Other(KeyCode),
+}
+
+/// Map a key press to the command it gives a menu
+///
+/// Raw mode turns Ctrl-C into an ordinary key press instead of a signal,
+/// so menus have to treat it as a way out themselves.
+pub(crate) fn map_key(code: KeyCode, modifiers: KeyModifiers) -> MenuCommand {
+    match code {
+        KeyCode::Char('c' | 'C') if modifiers.contains(KeyModifiers::CONTROL) => {
+            MenuCommand::Interrupt
+        }
+        KeyCode::Esc => MenuCommand::Back,
+        KeyCode::Up | KeyCode::Char('k') => MenuCommand::Up,
+        KeyCode::Down | KeyCode::Char('j') => MenuCommand::Down,
+        KeyCode::Enter => MenuCommand::Confirm,
+        code => MenuCommand::Other(code),
+    }
+}
+
+/// Terminal state changes a [`TerminalGuard`] makes and undoes
+pub(crate) trait RawTerminal {
+    fn enable_raw_mode(&mut self) -> io::Result<()>;
+    fn disable_raw_mode(&mut self) -> io::Result<()>;
+    fn enter_alternate_screen(&mut self) -> io::Result<()>;
+    fn leave_alternate_screen(&mut self) -> io::Result<()>;
+}
+
+/// The real terminal, through crossterm
+pub(crate) struct CrosstermTerminal;
+
+impl RawTerminal for CrosstermTerminal {
+    fn enable_raw_mode(&mut self) -> io::Result<()> {
+        terminal::enable_raw_mode()
+    }
+
+    fn disable_raw_mode(&mut self) -> io::Result<()> {
+        terminal::disable_raw_mode()
+    }
+
+    fn enter_alternate_screen(&mut self) -> io::Result<()> {
+        execute!(io::stdout(), terminal::EnterAlternateScreen)
+    }
+
+    fn leave_alternate_screen(&mut self) -> io::Result<()> {
+        execute!(io::stdout(), terminal::LeaveAlternateScreen)
+    }
+}
+
+/// Raw mode, and the alternate screen once entered, undone when dropped
+pub(crate) struct TerminalGuard<T: RawTerminal = CrosstermTerminal> {
+    terminal: T,
+    alternate_screen: bool,
+}
+
+impl<T: RawTerminal> TerminalGuard<T> {
+    /// Switch `terminal` to raw mode
+    ///
+    /// # Errors
+    /// Returns error if raw mode can't be enabled; nothing is left to undo
+    pub(crate) fn raw(mut terminal: T) -> io::Result<Self> {
+        terminal.enable_raw_mode()?;
+        Ok(TerminalGuard {
+            terminal,
+            alternate_screen: false,
+        })
+    }
+
+    /// Switch to the alternate screen, left again on drop
+    ///
+    /// # Errors
+    /// Returns error if the terminal has no alternate screen
+    pub(crate) fn enter_alternate_screen(&mut self) -> io::Result<()> {
+        self.terminal.enter_alternate_screen()?;
+        self.alternate_screen = true;
+        Ok(())
+    }
+}
+
+impl<T: RawTerminal> Drop for TerminalGuard<T> {
+    fn drop(&mut self) {
+        if self.alternate_screen {
+            let _ = self.terminal.leave_alternate_screen();
+        }
+        let _ = self.terminal.disable_raw_mode();
+    }
+}
+
+/// Restore the terminal before a panic message is printed
+///
+/// Release builds abort on panic without unwinding, so no guard would be
+/// dropped; the hook runs either way. Installed once, by the first menu.
+fn restore_terminal_on_panic() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if terminal::is_raw_mode_enabled().unwrap_or(false) {
+                let _ = execute!(io::stdout(), terminal::LeaveAlternateScreen);
+                let _ = terminal::disable_raw_mode();
+            }
+            previous(info);
+        }));
+    });
+}
+
+/// Raw-mode drawing surface of a menu, restored when left or dropped
+pub(crate) struct MenuScreen {
+    stdout: io::Stdout,
+    mode: UiMode,
+    /// Rows of the last inline frame, erased before the next one
+    drawn_rows: usize,
+    /// Held while the menu has the terminal, `None` once left
+    guard: Option<TerminalGuard>,
+    /// Last frame drawn, to skip redrawing an unchanged one
+    last_frame: Vec<String>,
+    /// Frames drawn and skipped, for `CC_SWITCH_DEBUG_FRAMES`
+    frames_drawn: usize,
+    frames_skipped: usize,
+}
+
+impl MenuScreen {
+    /// Set up the terminal for `preferred`, degrading from fullscreen to
+    /// inline when the alternate screen can't be entered
+    ///
+    /// Returns `None` when the simple menu should be used instead.
+    pub(crate) fn enter(preferred: UiMode) -> Option<MenuScreen> {
+        if preferred == UiMode::Simple {
+            return None;
+        }
+        restore_terminal_on_panic();
+        let mut guard = TerminalGuard::raw(CrosstermTerminal).ok()?;
+        let mut stdout = io::stdout();
+        let mode = if preferred == UiMode::Fullscreen && guard.enter_alternate_screen().is_ok() {
+            let _ = execute!(stdout, terminal::Clear(terminal::ClearType::All));
+            UiMode::Fullscreen
+        } else if execute!(stdout, cursor::MoveToColumn(0)).is_ok() {
+            UiMode::Inline
+        } else {
+            return None;
+        };
+        Some(MenuScreen {
+            stdout,
+            mode,
+            drawn_rows: 0,
+            guard: Some(guard),
+            last_frame: Vec::new(),
+            frames_drawn: 0,
+            frames_skipped: 0,
+        })
+    }
+
+    /// Replace the previous frame with `lines`, unless they are unchanged
+    ///
+    /// The frame is written in one piece with a single flush.
+    pub(crate) fn draw(&mut self, lines: &[String]) -> Result<()> {
+        if self.last_frame == lines {
+            self.frames_skipped += 1;
+            return Ok(());
+        }
+        match self.mode {
+            UiMode::Fullscreen => queue!(
+                self.stdout,
+                terminal::Clear(terminal::ClearType::All),
+                cursor::MoveTo(0, 0)
+            )?,
+            _ => self.queue_erase_inline()?,
+        }
+        let mut frame = String::new();
+        for line in lines {
+            frame.push_str(line);
+            frame.push('\n');
+        }
+        self.stdout.write_all(frame.as_bytes())?;
+        if self.mode == UiMode::Inline {
+            let columns = terminal::size().map(|(w, _)| w as usize).unwrap_or(80);
+            self.drawn_rows = rows_for_lines(lines, columns);
+        }
+        self.stdout.flush()?;
+        self.last_frame = lines.to_vec();
+        self.frames_drawn += 1;
+        Ok(())
+    }
+
+    /// Draw the next frame in full even if unchanged, after the terminal was
+    /// resized to `columns` wide
+    ///
+    /// An inline frame has rewrapped at the new width, so its rows are
+    /// counted again before it is erased.
+    pub(crate) fn force_redraw(&mut self, columns: usize) {
+        if self.mode == UiMode::Inline && !self.last_frame.is_empty() {
+            self.drawn_rows = rows_for_lines(&self.last_frame, columns);
+        }
+        self.last_frame.clear();
+    }
+
+    /// Move back to the first row of the inline frame and clear below it
+    fn queue_erase_inline(&mut self) -> Result<()> {
+        if self.drawn_rows > 0 {
+            let rows = u16::try_from(self.drawn_rows).unwrap_or(u16::MAX);
+            queue!(self.stdout, cursor::MoveUp(rows))?;
+        }
+        queue!(
+            self.stdout,
+            cursor::MoveToColumn(0),
+            terminal::Clear(terminal::ClearType::FromCursorDown)
+        )?;
+        self.drawn_rows = 0;
+        Ok(())
+    }
+
+    /// Restore the terminal: leave the alternate screen, or erase the inline
+    /// menu so the cursor is back where the menu started
+    pub(crate) fn leave(&mut self) {
+        let Some(guard) = self.guard.take() else {
+            return;
+        };
+        // Whatever is shown next (prompts, a resumed menu) starts afresh
+        self.last_frame.clear();
+        if self.mode != UiMode::Fullscreen {
+            let _ = self.queue_erase_inline();
+            let _ = self.stdout.flush();
+        }
+        // Leaves the alternate screen and raw mode
+        drop(guard);
+    }
+
+    /// Take over the terminal again after [`MenuScreen::leave`] (e.g. after
+    /// the edit prompts)
+    pub(crate) fn resume(&mut self) -> bool {
+        if self.guard.is_some() {
+            return true;
+        }
+        let Ok(mut guard) = TerminalGuard::raw(CrosstermTerminal) else {
+            return false;
+        };
+        if self.mode == UiMode::Fullscreen {
+            if guard.enter_alternate_screen().is_err() {
+                return false;
+            }
+            let _ = execute!(self.stdout, terminal::Clear(terminal::ClearType::All));
+        }
+        self.guard = Some(guard);
+        true
+    }
+}
+
+impl Drop for MenuScreen {
+    fn drop(&mut self) {
+        self.leave();
+        if std::env::var_os("CC_SWITCH_DEBUG_FRAMES").is_some() {
+            eprintln!(
+                "menu frames: {} drawn, {} unchanged skipped",
+                self.frames_drawn, self.frames_skipped
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn override_wins_over_term() {
+        assert_eq!(
+            preferred_ui_mode(Some("fullscreen"), Some("dumb")),
+            UiMode::Fullscreen
+        );
+        assert_eq!(
+            preferred_ui_mode(Some("inline"), Some("xterm")),
+            UiMode::Inline
+        );
+        assert_eq!(
+            preferred_ui_mode(Some("simple"), Some("xterm")),
+            UiMode::Simple
+        );
+    }
+
+    #[test]
+    fn dumb_terminal_defaults_to_inline() {
+        assert_eq!(preferred_ui_mode(None, Some("dumb")), UiMode::Inline);
+        assert_eq!(
+            preferred_ui_mode(Some("bogus"), Some("dumb")),
+            UiMode::Inline
+        );
+        assert_eq!(
+            preferred_ui_mode(None, Some("xterm-256color")),
+            UiMode::Fullscreen
+        );
+        assert_eq!(preferred_ui_mode(None, None), UiMode::Fullscreen);
+    }
+
+    #[test]
+    fn rows_count_wrapped_lines_without_escape_codes() {
+        let lines = vec![
+            "\r\u{1b}[1;34m> menu\u{1b}[0m".to_string(),
+            String::new(),
+            "x".repeat(25),
+            "中".repeat(10),
+        ];
+        // 1 + 1 (empty) + 3 (25 cols) + 2 (20 cols)
+        assert_eq!(rows_for_lines(&lines, 10), 7);
+    }
+
+    /// Changes made to a [`MockTerminal`], shared with the test
+    type Calls = std::rc::Rc<std::cell::RefCell<Vec<&'static str>>>;
+
+    /// Terminal recording the changes made to it
+    #[derive(Default)]
+    struct MockTerminal {
+        calls: Calls,
+        no_alternate_screen: bool,
+    }
+
+    impl RawTerminal for MockTerminal {
+        fn enable_raw_mode(&mut self) -> io::Result<()> {
+            self.calls.borrow_mut().push("raw on");
+            Ok(())
+        }
+
+        fn disable_raw_mode(&mut self) -> io::Result<()> {
+            self.calls.borrow_mut().push("raw off");
+            Ok(())
+        }
+
+        fn enter_alternate_screen(&mut self) -> io::Result<()> {
+            if self.no_alternate_screen {
+                return Err(io::Error::other("no alternate screen"));
+            }
+            self.calls.borrow_mut().push("alternate on");
+            Ok(())
+        }
+
+        fn leave_alternate_screen(&mut self) -> io::Result<()> {
+            self.calls.borrow_mut().push("alternate off");
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn guard_restores_the_terminal_when_dropped_on_an_early_return() {
+        let terminal = MockTerminal::default();
+        let calls = terminal.calls.clone();
+        let menu = || -> io::Result<()> {
+            let mut guard = TerminalGuard::raw(terminal)?;
+            guard.enter_alternate_screen()?;
+            Err(io::Error::other("input failed"))
+        };
+        assert!(menu().is_err());
+        assert_eq!(
+            *calls.borrow(),
+            ["raw on", "alternate on", "alternate off", "raw off"]
+        );
+
+        // Only what was entered is left
+        let terminal = MockTerminal {
+            no_alternate_screen: true,
+            ..Default::default()
+        };
+        let calls = terminal.calls.clone();
+        let mut guard = TerminalGuard::raw(terminal).unwrap();
+        assert!(guard.enter_alternate_screen().is_err());
+        drop(guard);
+        assert_eq!(*calls.borrow(), ["raw on", "raw off"]);
+    }
+
+    #[test]
+    fn ctrl_c_interrupts_and_other_keys_keep_their_meaning() {
+        assert_eq!(
+            map_key(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            MenuCommand::Interrupt
+        );
+        assert_eq!(
+            map_key(KeyCode::Char('c'), KeyModifiers::NONE),
+            MenuCommand::Other(KeyCode::Char('c'))
+        );
+        assert_eq!(map_key(KeyCode::Esc, KeyModifiers::NONE), MenuCommand::Back);
+        assert_eq!(
+            map_key(KeyCode::Char('k'), KeyModifiers::NONE),
+            MenuCommand::Up
+        );
+        assert_eq!(
+            map_key(KeyCode::Down, KeyModifiers::NONE),
+            MenuCommand::Down
+        );
+        assert_eq!(
+            map_key(KeyCode::Enter, KeyModifiers::NONE),
+            MenuCommand::Confirm
+        );
+    }
+}
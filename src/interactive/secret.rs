@@ -0,0 +1,213 @@
+//! No-echo entry of tokens and API keys.
+//!
+//! On a terminal the secret is typed in raw mode and shown as one `*` per
+//! character, so it never reaches the scrollback. Piped input, or a
+//! terminal that refuses raw mode, falls back to a plain line read.
+
+use anyhow::{Context, Result};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::{queue, terminal};
+use std::io::{self, IsTerminal, Write};
+
+/// What a key press did to the secret being typed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SecretStep {
+    /// Keep reading; the masked line may have changed
+    Continue,
+    /// Enter: the secret is complete
+    Submit,
+    /// Esc or Ctrl-C
+    Cancel,
+}
+
+/// Secret typed so far
+#[derive(Debug, Default)]
+struct SecretInput {
+    value: String,
+}
+
+impl SecretInput {
+    /// Apply one terminal event
+    fn handle(&mut self, event: Event) -> SecretStep {
+        let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event
+        else {
+            if let Event::Paste(text) = event {
+                self.value.extend(text.chars().filter(|c| !c.is_control()));
+            }
+            return SecretStep::Continue;
+        };
+
+        let ctrl = modifiers.contains(KeyModifiers::CONTROL);
+        match code {
+            KeyCode::Enter => SecretStep::Submit,
+            KeyCode::Esc => SecretStep::Cancel,
+            KeyCode::Char('c' | 'C') if ctrl => SecretStep::Cancel,
+            KeyCode::Char('u' | 'U') if ctrl => {
+                self.value.clear();
+                SecretStep::Continue
+            }
+            KeyCode::Backspace => {
+                self.value.pop();
+                SecretStep::Continue
+            }
+            KeyCode::Char(c) if !ctrl && !c.is_control() => {
+                self.value.push(c);
+                SecretStep::Continue
+            }
+            _ => SecretStep::Continue,
+        }
+    }
+
+    /// The secret as it is shown: one `*` per character
+    fn masked(&self) -> String {
+        "*".repeat(self.value.chars().count())
+    }
+}
+
+/// Read a secret after `prompt` from `next_event`, echoing `*` to `out`
+///
+/// The terminal must already be in raw mode.
+///
+/// # Errors
+/// Returns error if the input is cancelled or reading an event fails
+fn read_secret(
+    prompt: &str,
+    mut next_event: impl FnMut() -> io::Result<Event>,
+    out: &mut impl Write,
+) -> Result<String> {
+    let mut input = SecretInput::default();
+    loop {
+        queue!(
+            out,
+            crossterm::cursor::MoveToColumn(0),
+            terminal::Clear(terminal::ClearType::CurrentLine)
+        )?;
+        write!(out, "{prompt}{}", input.masked())?;
+        out.flush()?;
+
+        let event = next_event().context("Failed to read input")?;
+        match input.handle(event) {
+            SecretStep::Continue => {}
+            SecretStep::Submit => {
+                write!(out, "\r\n")?;
+                out.flush()?;
+                return Ok(input.value.trim().to_string());
+            }
+            SecretStep::Cancel => {
+                write!(out, "\r\n")?;
+                out.flush()?;
+                anyhow::bail!("Input cancelled");
+            }
+        }
+    }
+}
+
+/// Read sensitive input (token) with a prompt (without echoing)
+///
+/// Falls back to an ordinary line read when stdin is not a terminal.
+///
+/// # Arguments
+/// * `prompt` - The prompt to display to the user
+///
+/// # Returns
+/// The user's input as a String
+///
+/// # Errors
+/// Returns error if reading fails or the user cancels with Esc / Ctrl-C
+pub fn read_sensitive_input(prompt: &str) -> Result<String> {
+    if !io::stdin().is_terminal() || terminal::enable_raw_mode().is_err() {
+        return crate::interactive::read_input(prompt);
+    }
+    let result = read_secret(prompt, event::read, &mut io::stdout());
+    let _ = terminal::disable_raw_mode();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> Event {
+        Event::Key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn ctrl(c: char) -> Event {
+        Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL))
+    }
+
+    fn typed(text: &str) -> Vec<Event> {
+        text.chars().map(|c| key(KeyCode::Char(c))).collect()
+    }
+
+    /// Feed `events` to `read_secret`, returning its result and output
+    fn run(events: Vec<Event>) -> (Result<String>, String) {
+        let mut events = events.into_iter();
+        let mut out = Vec::new();
+        let result = read_secret(
+            "Token: ",
+            || {
+                events
+                    .next()
+                    .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))
+            },
+            &mut out,
+        );
+        (result, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn characters_accumulate_and_are_masked() {
+        let mut events = typed("sk-ant-1");
+        events.push(key(KeyCode::Enter));
+        let (result, output) = run(events);
+        assert_eq!(result.unwrap(), "sk-ant-1");
+        assert!(output.contains("Token: ********"));
+        assert!(!output.contains("sk-ant"));
+    }
+
+    #[test]
+    fn backspace_and_ctrl_u_edit_the_secret() {
+        let mut events = typed("wrong");
+        events.push(ctrl('u'));
+        events.extend(typed("tokenx"));
+        events.push(key(KeyCode::Backspace));
+        events.push(key(KeyCode::Enter));
+        assert_eq!(run(events).0.unwrap(), "token");
+
+        // Backspace on an empty secret is harmless
+        let events = vec![key(KeyCode::Backspace), key(KeyCode::Enter)];
+        assert_eq!(run(events).0.unwrap(), "");
+    }
+
+    #[test]
+    fn esc_and_ctrl_c_cancel() {
+        for cancel in [key(KeyCode::Esc), ctrl('c')] {
+            let mut events = typed("secret");
+            events.push(cancel);
+            let error = run(events).0.unwrap_err();
+            assert!(error.to_string().contains("cancelled"));
+        }
+    }
+
+    #[test]
+    fn releases_and_other_keys_are_ignored() {
+        let release = Event::Key(KeyEvent::new_with_kind(
+            KeyCode::Char('x'),
+            KeyModifiers::NONE,
+            KeyEventKind::Release,
+        ));
+        let events = vec![
+            release,
+            key(KeyCode::Left),
+            key(KeyCode::Char('a')),
+            Event::Paste("bc\n".to_string()),
+            key(KeyCode::Enter),
+        ];
+        assert_eq!(run(events).0.unwrap(), "abc");
+    }
+}
@@ -7,10 +7,12 @@ pub mod cli;
 pub mod codex;
 pub mod config;
 pub mod daemon;
+pub mod exit_code;
 pub mod interactive;
 
 pub mod claude_settings;
 pub mod platform;
+pub mod reporting;
 pub mod statusline;
 pub mod utils;
 
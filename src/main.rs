@@ -1,5 +1,11 @@
-use anyhow::Result;
+use cc_switch::exit_code::ExitCode;
 
-fn main() -> Result<()> {
-    cc_switch::run()
+fn main() -> std::process::ExitCode {
+    match cc_switch::run() {
+        Ok(()) => ExitCode::Success.into(),
+        Err(err) => {
+            eprintln!("Error: {err:?}");
+            ExitCode::of(&err).into()
+        }
+    }
 }
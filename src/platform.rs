@@ -4,10 +4,23 @@
 //! `.ps1` shims rather than `.exe`, and `std::process::Command::new("name")` does
 //! not always pick them up depending on how PATHEXT is configured.
 //!
+//! `resolve_claude_binary` picks the Claude binary every launch runs, so a
+//! `claude` installed outside PATH can be configured once.
+//!
+//! `spawn_and_wait` runs a launch where exec isn't available, passing the
+//! child's exit code on and leaving Ctrl-C to the child.
+//!
 //! `unicode_support_enabled` centralizes the heuristic used by the interactive UI
 //! to decide between Unicode box-drawing and ASCII fallback.
 
-use std::path::PathBuf;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::config::ConfigStorage;
+
+/// Environment variable naming the Claude binary for one run
+pub const CLAUDE_BIN_ENV: &str = "CC_SWITCH_CLAUDE_BIN";
 
 /// Resolve a Node/npm-style CLI name to an executable path.
 ///
@@ -39,6 +52,63 @@ pub fn resolve_npm_cli(name: &str) -> PathBuf {
     PathBuf::from(name)
 }
 
+/// Pick the Claude binary without checking it exists.
+///
+/// Precedence (highest first):
+/// 1. `env_override` — the value of `CC_SWITCH_CLAUDE_BIN`.
+/// 2. `stored` — the `claude-path` setting.
+/// 3. `claude`, resolved by [`resolve_npm_cli`] (so `CLAUDE_BINARY` still
+///    applies).
+///
+/// Empty values are ignored. Returns the path and where it came from.
+pub fn choose_claude_binary(
+    env_override: Option<&str>,
+    stored: Option<&str>,
+) -> (PathBuf, &'static str) {
+    fn non_empty(value: Option<&str>) -> Option<&str> {
+        value.map(str::trim).filter(|v| !v.is_empty())
+    }
+    if let Some(path) = non_empty(env_override) {
+        (PathBuf::from(path), CLAUDE_BIN_ENV)
+    } else if let Some(path) = non_empty(stored) {
+        (PathBuf::from(path), "the claude-path setting")
+    } else {
+        (resolve_npm_cli("claude"), "the default")
+    }
+}
+
+/// The Claude binary launches from `storage` run.
+///
+/// See [`choose_claude_binary`] for the precedence.
+///
+/// # Errors
+/// Returns error, naming where the path came from, when it is not an
+/// executable file or is not found on PATH
+pub fn resolve_claude_binary(storage: &ConfigStorage) -> Result<PathBuf> {
+    let env_override = std::env::var(CLAUDE_BIN_ENV).ok();
+    let (path, source) =
+        choose_claude_binary(env_override.as_deref(), storage.claude_binary.as_deref());
+    check_executable(&path, source)?;
+    Ok(path)
+}
+
+/// Fail unless `path` is an executable file, or a name found on PATH.
+///
+/// # Errors
+/// Returns error mentioning `source` and how to configure the binary
+pub fn check_executable(path: &Path, source: &str) -> Result<()> {
+    if which::which(path).is_err() {
+        anyhow::bail!(
+            "Claude binary '{}' (from {}) was not found or is not executable. \
+             Set its location with `cc-switch config set claude-path <PATH>` or {}",
+            path.display(),
+            source,
+            CLAUDE_BIN_ENV
+        );
+    }
+    Ok(())
+}
+
 /// Decide whether the terminal supports Unicode box-drawing characters.
 ///
 /// Precedence (highest first):
@@ -71,3 +141,64 @@ pub fn unicode_support_enabled() -> bool {
         true
     }
 }
+
+/// Spawn `command` on this terminal and wait for it, returning its exit
+/// code (1 when it was killed without one).
+///
+/// Launches use this where the process can't be replaced with exec, then
+/// exit with the code returned. On Windows every process attached to the
+/// console receives Ctrl-C, so this one ignores it until the child exits
+/// and the child alone decides what it means.
+///
+/// # Errors
+/// Returns error if the command can't be started or waited for
+pub fn spawn_and_wait(command: &mut Command) -> std::io::Result<i32> {
+    command
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit());
+
+    #[cfg(windows)]
+    let _ctrl_c = console::IgnoreCtrlC::install();
+
+    let status = command.spawn()?.wait()?;
+    Ok(status.code().unwrap_or(1))
+}
+
+#[cfg(windows)]
+mod console {
+    type HandlerRoutine = unsafe extern "system" fn(ctrl_type: u32) -> i32;
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn SetConsoleCtrlHandler(handler: Option<HandlerRoutine>, add: i32) -> i32;
+    }
+
+    /// Report every console control event as handled, so it doesn't end
+    /// this process
+    unsafe extern "system" fn handled(_ctrl_type: u32) -> i32 {
+        1
+    }
+
+    /// Keeps this process running through Ctrl-C until dropped
+    ///
+    /// Unlike `SetConsoleCtrlHandler(NULL, TRUE)`, a handler routine isn't
+    /// inherited, so children still get the default Ctrl-C behaviour.
+    pub struct IgnoreCtrlC;
+
+    impl IgnoreCtrlC {
+        pub fn install() -> Self {
+            // SAFETY: `handled` is a plain function that lives as long as the
+            // process and touches no state
+            unsafe { SetConsoleCtrlHandler(Some(handled), 1) };
+            IgnoreCtrlC
+        }
+    }
+
+    impl Drop for IgnoreCtrlC {
+        fn drop(&mut self) {
+            // SAFETY: removes the handler added by `install`
+            unsafe { SetConsoleCtrlHandler(Some(handled), 0) };
+        }
+    }
+}
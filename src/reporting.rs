@@ -0,0 +1,569 @@
+//! Structured reports for diagnostic commands.
+//!
+//! Each diagnostic builds one of the report structs below; `--json` prints it
+//! with [`to_json`] and the human output is rendered from the same struct, so
+//! the two cannot diverge. JSON output carries a top-level `schema_version`
+//! which is bumped on incompatible changes.
+
+use anyhow::Result;
+use colored::Colorize;
+use serde::Serialize;
+use std::path::Path;
+
+use crate::config::config_storage::{backup_path_of, lock_path_of};
+
+/// Version of the JSON schema shared by all reports
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct Versioned<'a, T: Serialize> {
+    schema_version: u32,
+    #[serde(flatten)]
+    report: &'a T,
+}
+
+/// Serialize a report as pretty JSON with the schema version
+///
+/// # Errors
+/// Returns error if serialization fails
+pub fn to_json<T: Serialize>(report: &T) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&Versioned {
+        schema_version: SCHEMA_VERSION,
+        report,
+    })?)
+}
+
+/// Which configuration is active and where that was detected
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StatusReport {
+    /// `alias`, `custom` (values matching no stored alias) or `official`
+    pub state: String,
+    /// Alias of the active configuration, if any
+    pub active_alias: Option<String>,
+    /// Which values identified the alias (e.g. `token+url`)
+    pub matched_by: Option<String>,
+    /// Where the active configuration was detected (e.g. `environment`)
+    pub source: Option<String>,
+    /// ANTHROPIC_BASE_URL in effect
+    pub url: Option<String>,
+    /// Token in effect, masked
+    pub token: Option<String>,
+    /// ANTHROPIC_MODEL in effect
+    pub model: Option<String>,
+}
+
+impl StatusReport {
+    /// Human-readable rendering
+    pub fn render_human(&self) -> Vec<String> {
+        let active = match (&self.active_alias, self.state.as_str()) {
+            (Some(alias), _) => {
+                let matched_by = self.matched_by.as_deref().unwrap_or("alias");
+                format!(
+                    "Active: {} (matched by {matched_by})",
+                    format!("'{alias}'").green()
+                )
+            }
+            (None, "custom") => "Active: custom values not matching any stored alias".to_string(),
+            (None, _) => "Active: official/default".to_string(),
+        };
+        let mut lines = vec![active];
+        for (label, value) in [
+            ("Source:", &self.source),
+            ("URL:   ", &self.url),
+            ("Token: ", &self.token),
+            ("Model: ", &self.model),
+        ] {
+            if let Some(value) = value {
+                lines.push(format!("  {label} {value}"));
+            }
+        }
+        lines
+    }
+}
+
+/// Result of one diagnostic check
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DoctorCheck {
+    /// What was checked
+    pub name: String,
+    /// Whether the check passed
+    pub passed: bool,
+    /// Passed, but with something worth fixing (shown with `fix`)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub warning: bool,
+    /// Details shown next to the result
+    pub detail: String,
+    /// Command or step that fixes a failed check
+    pub fix: Option<String>,
+}
+
+impl DoctorCheck {
+    pub(crate) fn pass(name: &str, detail: impl Into<String>) -> Self {
+        DoctorCheck {
+            name: name.to_string(),
+            passed: true,
+            warning: false,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    pub(crate) fn warn(name: &str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        DoctorCheck {
+            name: name.to_string(),
+            passed: true,
+            warning: true,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    pub(crate) fn fail(name: &str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        DoctorCheck {
+            name: name.to_string(),
+            passed: false,
+            warning: false,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// Results of a doctor run
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DoctorReport {
+    /// What was diagnosed (e.g. `fish completion setup`)
+    pub subject: String,
+    pub checks: Vec<DoctorCheck>,
+    /// Files the checks looked at, when the subject is cc-switch's own setup
+    #[serde(flatten)]
+    pub paths: Option<PathsReport>,
+}
+
+impl DoctorReport {
+    /// Number of failed checks
+    pub fn failures(&self) -> usize {
+        self.checks.iter().filter(|check| !check.passed).count()
+    }
+
+    /// Number of checks that passed with a warning
+    pub fn warnings(&self) -> usize {
+        self.checks.iter().filter(|check| check.warning).count()
+    }
+
+    /// Human-readable rendering with ✓/!/✗ per check
+    pub fn render_human(&self) -> Vec<String> {
+        let mut lines = vec![format!("Checking {}:", self.subject)];
+        for check in &self.checks {
+            let mark = match (check.passed, check.warning) {
+                (false, _) => "✗".red(),
+                (true, true) => "!".yellow(),
+                (true, false) => "✓".green(),
+            };
+            lines.push(format!("  {} {}: {}", mark, check.name, check.detail));
+            if let Some(fix) = &check.fix {
+                lines.push(format!("      fix: {fix}"));
+            }
+        }
+        match (self.failures(), self.warnings()) {
+            (0, 0) => lines.push("All checks passed".to_string()),
+            (0, warned) => lines.push(format!("All checks passed, {warned} warning(s)")),
+            (failed, _) => lines.push(format!("{failed} problem(s) found")),
+        }
+        lines
+    }
+}
+
+/// A file cc-switch reads or writes
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PathEntry {
+    /// What the path is used for (e.g. `storage`)
+    pub name: String,
+    pub path: String,
+    pub exists: bool,
+}
+
+/// Locations cc-switch uses
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PathsReport {
+    pub paths: Vec<PathEntry>,
+}
+
+impl PathsReport {
+    /// The storage file at `storage_path` with its backup and lock file,
+    /// and the Claude settings file at `settings_path`
+    pub fn for_storage(storage_path: &Path, settings_path: &Path) -> Self {
+        let entry = |name: &str, path: &Path| PathEntry {
+            name: name.to_string(),
+            path: path.display().to_string(),
+            exists: path.exists(),
+        };
+        PathsReport {
+            paths: vec![
+                entry("storage", storage_path),
+                entry("backup", &backup_path_of(storage_path)),
+                entry("lock", &lock_path_of(storage_path)),
+                entry("settings", settings_path),
+            ],
+        }
+    }
+
+    /// Human-readable rendering, aligned by name
+    pub fn render_human(&self) -> Vec<String> {
+        let width = self
+            .paths
+            .iter()
+            .map(|entry| entry.name.len())
+            .max()
+            .unwrap_or(0);
+        self.paths
+            .iter()
+            .map(|entry| {
+                let missing = if entry.exists { "" } else { " (missing)" };
+                format!("{:width$}  {}{}", entry.name, entry.path, missing)
+            })
+            .collect()
+    }
+}
+
+/// Version and build metadata of the running binary
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VersionReport {
+    pub version: String,
+    /// Short git commit hash, `unknown` outside a git checkout
+    pub commit: String,
+    /// UTC build date (YYYY-MM-DD)
+    pub build_date: String,
+    /// Target triple the binary was built for
+    pub target: String,
+    /// Enabled cargo features
+    pub features: Vec<String>,
+    /// Configuration storage file in use
+    pub storage_path: Option<String>,
+    /// Files cc-switch uses, when they could be located
+    #[serde(flatten)]
+    pub paths: Option<PathsReport>,
+}
+
+impl VersionReport {
+    /// Metadata baked in by `build.rs` plus the given storage path and paths
+    pub fn for_this_build(storage_path: Option<String>, paths: Option<PathsReport>) -> Self {
+        let mut features = Vec::new();
+        if cfg!(feature = "web-ui") {
+            features.push("web-ui".to_string());
+        }
+        if cfg!(feature = "keyring") {
+            features.push("keyring".to_string());
+        }
+        VersionReport {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            commit: env!("CC_SWITCH_GIT_COMMIT").to_string(),
+            build_date: env!("CC_SWITCH_BUILD_DATE").to_string(),
+            target: env!("CC_SWITCH_TARGET").to_string(),
+            features,
+            storage_path,
+            paths,
+        }
+    }
+
+    /// Single line identical to `cc-switch --version`
+    pub fn render_short(&self) -> String {
+        format!("cc-switch {}", self.version)
+    }
+
+    /// Human-readable rendering with one metadata field per line
+    pub fn render_human(&self) -> Vec<String> {
+        let features = if self.features.is_empty() {
+            "none".to_string()
+        } else {
+            self.features.join(", ")
+        };
+        vec![
+            self.render_short(),
+            format!("  commit:   {}", self.commit),
+            format!("  built:    {}", self.build_date),
+            format!("  target:   {}", self.target),
+            format!("  features: {features}"),
+            format!(
+                "  storage:  {}",
+                self.storage_path.as_deref().unwrap_or("unknown")
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Remove ANSI escape sequences so snapshots don't depend on color state
+    fn strip_ansi(line: &str) -> String {
+        let mut out = String::new();
+        let mut chars = line.chars();
+        while let Some(c) = chars.next() {
+            if c == '\u{1b}' {
+                for c in chars.by_ref() {
+                    if c == 'm' {
+                        break;
+                    }
+                }
+            } else {
+                out.push(c);
+            }
+        }
+        out
+    }
+
+    fn human(lines: Vec<String>) -> String {
+        lines
+            .iter()
+            .map(|line| strip_ansi(line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn doctor_report() -> DoctorReport {
+        DoctorReport {
+            subject: "fish completion setup".to_string(),
+            checks: vec![
+                DoctorCheck::pass(
+                    "Completion file",
+                    "/home/u/.config/fish/completions/cc-switch.fish",
+                ),
+                DoctorCheck::fail(
+                    "Up to date",
+                    "differs from this binary",
+                    "cc-switch completion fish",
+                ),
+            ],
+            paths: None,
+        }
+    }
+
+    #[test]
+    fn doctor_report_marks_warnings() {
+        let report = DoctorReport {
+            subject: "cc-switch setup".to_string(),
+            checks: vec![DoctorCheck::warn(
+                "Terminal",
+                "NO_COLOR is set",
+                "unset NO_COLOR",
+            )],
+            paths: None,
+        };
+        assert_eq!(report.failures(), 0);
+        assert_eq!(
+            human(report.render_human()),
+            "Checking cc-switch setup:\n\
+             \x20 ! Terminal: NO_COLOR is set\n\
+             \x20     fix: unset NO_COLOR\n\
+             All checks passed, 1 warning(s)"
+        );
+        assert!(to_json(&report).unwrap().contains("\"warning\": true"));
+    }
+
+    #[test]
+    fn doctor_report_human() {
+        assert_eq!(
+            human(doctor_report().render_human()),
+            "Checking fish completion setup:\n\
+             \x20 ✓ Completion file: /home/u/.config/fish/completions/cc-switch.fish\n\
+             \x20 ✗ Up to date: differs from this binary\n\
+             \x20     fix: cc-switch completion fish\n\
+             1 problem(s) found"
+        );
+    }
+
+    #[test]
+    fn doctor_report_json() {
+        assert_eq!(
+            to_json(&doctor_report()).unwrap(),
+            r#"{
+  "schema_version": 1,
+  "subject": "fish completion setup",
+  "checks": [
+    {
+      "name": "Completion file",
+      "passed": true,
+      "detail": "/home/u/.config/fish/completions/cc-switch.fish",
+      "fix": null
+    },
+    {
+      "name": "Up to date",
+      "passed": false,
+      "detail": "differs from this binary",
+      "fix": "cc-switch completion fish"
+    }
+  ]
+}"#
+        );
+    }
+
+    #[test]
+    fn status_report_renderings() {
+        let report = StatusReport {
+            state: "alias".to_string(),
+            active_alias: Some("relay".to_string()),
+            matched_by: Some("token+url".to_string()),
+            source: Some("environment".to_string()),
+            url: Some("https://relay.example".to_string()),
+            token: Some("sk-a***".to_string()),
+            model: None,
+        };
+        assert_eq!(
+            human(report.render_human()),
+            "Active: 'relay' (matched by token+url)\n  Source: environment\n  URL:    https://relay.example\n  Token:  sk-a***"
+        );
+        assert_eq!(
+            to_json(&report).unwrap(),
+            r#"{
+  "schema_version": 1,
+  "state": "alias",
+  "active_alias": "relay",
+  "matched_by": "token+url",
+  "source": "environment",
+  "url": "https://relay.example",
+  "token": "sk-a***",
+  "model": null
+}"#
+        );
+
+        let custom = StatusReport {
+            state: "custom".to_string(),
+            active_alias: None,
+            matched_by: None,
+            source: Some("settings.json".to_string()),
+            url: Some("https://other.example".to_string()),
+            token: None,
+            model: None,
+        };
+        assert_eq!(
+            human(custom.render_human()),
+            "Active: custom values not matching any stored alias\n  Source: settings.json\n  URL:    https://other.example"
+        );
+
+        let none = StatusReport {
+            state: "official".to_string(),
+            active_alias: None,
+            matched_by: None,
+            source: None,
+            url: None,
+            token: None,
+            model: None,
+        };
+        assert_eq!(human(none.render_human()), "Active: official/default");
+    }
+
+    #[test]
+    fn version_report_renderings() {
+        let report = VersionReport {
+            version: "1.2.3".to_string(),
+            commit: "unknown".to_string(),
+            build_date: "2026-01-02".to_string(),
+            target: "x86_64-unknown-linux-gnu".to_string(),
+            features: Vec::new(),
+            storage_path: Some("/home/u/.claude/cc_auto_switch_setting.json".to_string()),
+            paths: None,
+        };
+        assert_eq!(report.render_short(), "cc-switch 1.2.3");
+        assert_eq!(
+            human(report.render_human()),
+            "cc-switch 1.2.3\n\
+             \x20 commit:   unknown\n\
+             \x20 built:    2026-01-02\n\
+             \x20 target:   x86_64-unknown-linux-gnu\n\
+             \x20 features: none\n\
+             \x20 storage:  /home/u/.claude/cc_auto_switch_setting.json"
+        );
+        assert_eq!(
+            to_json(&report).unwrap(),
+            r#"{
+  "schema_version": 1,
+  "version": "1.2.3",
+  "commit": "unknown",
+  "build_date": "2026-01-02",
+  "target": "x86_64-unknown-linux-gnu",
+  "features": [],
+  "storage_path": "/home/u/.claude/cc_auto_switch_setting.json"
+}"#
+        );
+    }
+
+    #[test]
+    fn paths_report_renderings() {
+        let report = PathsReport {
+            paths: vec![
+                PathEntry {
+                    name: "storage".to_string(),
+                    path: "/home/u/.config/cc-switch/configurations.json".to_string(),
+                    exists: true,
+                },
+                PathEntry {
+                    name: "settings".to_string(),
+                    path: "/home/u/.claude/settings.json".to_string(),
+                    exists: false,
+                },
+            ],
+        };
+        assert_eq!(
+            human(report.render_human()),
+            "storage   /home/u/.config/cc-switch/configurations.json\n\
+             settings  /home/u/.claude/settings.json (missing)"
+        );
+        assert_eq!(
+            to_json(&report).unwrap(),
+            r#"{
+  "schema_version": 1,
+  "paths": [
+    {
+      "name": "storage",
+      "path": "/home/u/.config/cc-switch/configurations.json",
+      "exists": true
+    },
+    {
+      "name": "settings",
+      "path": "/home/u/.claude/settings.json",
+      "exists": false
+    }
+  ]
+}"#
+        );
+    }
+
+    #[test]
+    fn paths_report_lists_storage_backup_lock_and_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = dir.path().join("configurations.json");
+        std::fs::write(&storage, "{}").unwrap();
+        let report = PathsReport::for_storage(&storage, &dir.path().join("settings.json"));
+        let names: Vec<&str> = report
+            .paths
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect();
+        assert_eq!(names, ["storage", "backup", "lock", "settings"]);
+        let exists: Vec<bool> = report.paths.iter().map(|entry| entry.exists).collect();
+        assert_eq!(exists, [true, false, false, false]);
+        assert!(report.paths[2].path.ends_with("configurations.json.lock"));
+    }
+
+    #[test]
+    fn doctor_report_json_carries_its_paths() {
+        let mut report = doctor_report();
+        report.paths = Some(PathsReport {
+            paths: vec![PathEntry {
+                name: "lock".to_string(),
+                path: "/tmp/s.json.lock".to_string(),
+                exists: false,
+            }],
+        });
+        let json: serde_json::Value = serde_json::from_str(&to_json(&report).unwrap()).unwrap();
+        assert_eq!(json["paths"][0]["name"], "lock");
+        assert!(
+            to_json(&doctor_report())
+                .unwrap()
+                .find("\"paths\"")
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,95 @@
+//! Blocking HTTP requests for short probes that run before a launch.
+
+use anyhow::{Context, Result};
+use std::time::Duration;
+
+/// Type alias for a response header (name, value)
+type Header = (String, String);
+/// Type alias for a request header (name, value)
+pub type RequestHeader<'a> = (&'a str, &'a str);
+
+/// The parts of an HTTP response a probe reads
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CapturedResponse {
+    pub status: u16,
+    /// Header names are lowercase
+    pub headers: Vec<Header>,
+    pub body: String,
+}
+
+impl CapturedResponse {
+    /// Value of header `name`, matched case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let name = name.to_ascii_lowercase();
+        self.headers
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// GET `url` with extra `headers`; `timeout` bounds the whole request,
+/// body included
+///
+/// # Errors
+/// Returns error if the request fails or times out
+pub fn get(url: &str, headers: &[RequestHeader], timeout: Duration) -> Result<CapturedResponse> {
+    send(reqwest::Method::GET, url, headers, None, timeout)
+}
+
+/// POST `body` as JSON to `url` with extra `headers`; `timeout` bounds the
+/// whole request, body included
+///
+/// # Errors
+/// Returns error if the request fails or times out
+pub fn post_json(
+    url: &str,
+    headers: &[RequestHeader],
+    body: &str,
+    timeout: Duration,
+) -> Result<CapturedResponse> {
+    send(reqwest::Method::POST, url, headers, Some(body), timeout)
+}
+
+fn send(
+    method: reqwest::Method,
+    url: &str,
+    headers: &[RequestHeader],
+    body: Option<&str>,
+    timeout: Duration,
+) -> Result<CapturedResponse> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(timeout)
+        .connect_timeout(timeout)
+        .build()
+        .context("Failed to build HTTP client")?;
+    let mut request = client.request(method, url);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    if let Some(body) = body {
+        request = request
+            .header("content-type", "application/json")
+            .body(body.to_string());
+    }
+    let response = request
+        .send()
+        .with_context(|| format!("Request to {url} failed"))?;
+
+    let status = response.status().as_u16();
+    let headers = response
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| {
+            Some((name.as_str().to_string(), value.to_str().ok()?.to_string()))
+        })
+        .collect();
+    let body = response
+        .text()
+        .with_context(|| format!("Failed to read response from {url}"))?;
+    Ok(CapturedResponse {
+        status,
+        headers,
+        body,
+    })
+}
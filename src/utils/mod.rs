@@ -0,0 +1,638 @@
+use anyhow::{Context, Result};
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+pub mod http;
+
+/// Environment variable naming the directory of the configuration storage
+/// file; Claude's settings.json stays where Claude reads it
+pub const CONFIG_DIR_ENV: &str = "CC_SWITCH_CONFIG_DIR";
+
+/// Environment variable Claude reads its configuration directory from,
+/// instead of `~/.claude`
+pub const CLAUDE_CONFIG_DIR_ENV: &str = "CLAUDE_CONFIG_DIR";
+
+/// Environment variable naming the configuration storage file itself
+pub const CONFIG_FILE_ENV: &str = "CC_SWITCH_CONFIG";
+
+/// Base directory of the XDG Base Directory specification
+const XDG_CONFIG_HOME_ENV: &str = "XDG_CONFIG_HOME";
+
+/// Name of the storage file in `$CC_SWITCH_CONFIG_DIR` and the legacy
+/// `~/.claude`
+const STORAGE_FILE_NAME: &str = "cc_auto_switch_setting.json";
+
+/// Storage file given with `--config`, set once at startup
+static CONFIG_PATH_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Use `path` as the configuration storage file for this process
+/// (`--config`); only the first call has an effect
+pub fn set_config_path_override(path: PathBuf) {
+    let _ = CONFIG_PATH_OVERRIDE.set(path);
+}
+
+/// What chose the configuration storage file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoragePathSource {
+    /// `--config`
+    Flag,
+    /// `$CC_SWITCH_CONFIG`
+    FileEnv,
+    /// `$CC_SWITCH_CONFIG_DIR`
+    DirEnv,
+    /// `$XDG_CONFIG_HOME`
+    Xdg,
+    /// `~/.config`, the XDG default
+    Default,
+    /// An existing store in `~/.claude`
+    Legacy,
+}
+
+impl StoragePathSource {
+    /// Flag or variable that chose the file; `None` for the defaults
+    pub fn origin(self) -> Option<&'static str> {
+        match self {
+            StoragePathSource::Flag => Some("--config"),
+            StoragePathSource::FileEnv => Some(CONFIG_FILE_ENV),
+            StoragePathSource::DirEnv => Some(CONFIG_DIR_ENV),
+            StoragePathSource::Xdg => Some(XDG_CONFIG_HOME_ENV),
+            StoragePathSource::Default | StoragePathSource::Legacy => None,
+        }
+    }
+}
+
+/// Configuration storage file and what chose it
+pub type StorageLocation = (PathBuf, StoragePathSource);
+
+/// Choose the configuration storage file
+///
+/// In order: `flag` (`--config`), `$CC_SWITCH_CONFIG`,
+/// `$CC_SWITCH_CONFIG_DIR/cc_auto_switch_setting.json`, then
+/// `cc-switch/configurations.json` in `$XDG_CONFIG_HOME`, or in
+/// `~/.config` when that variable is unset or not an absolute path. A
+/// store already in `~/.claude/cc_auto_switch_setting.json` is kept in use
+/// over an XDG file that doesn't exist yet. Variables set to an empty value
+/// are ignored.
+///
+/// # Errors
+/// Returns error if the default location is needed but `home` is unknown
+pub fn resolve_config_storage_path(
+    flag: Option<&Path>,
+    env: impl Fn(&str) -> Option<OsString>,
+    home: Option<&Path>,
+) -> Result<StorageLocation> {
+    let var = |name| env(name).filter(|value| !value.is_empty());
+    if let Some(path) = flag {
+        return Ok((path.to_path_buf(), StoragePathSource::Flag));
+    }
+    if let Some(path) = var(CONFIG_FILE_ENV) {
+        return Ok((PathBuf::from(path), StoragePathSource::FileEnv));
+    }
+    if let Some(dir) = var(CONFIG_DIR_ENV) {
+        return Ok((
+            PathBuf::from(dir).join(STORAGE_FILE_NAME),
+            StoragePathSource::DirEnv,
+        ));
+    }
+
+    let home = home.context("Could not find home directory")?;
+    // The specification says to ignore relative paths
+    let (config_home, source) = match var(XDG_CONFIG_HOME_ENV)
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+    {
+        Some(dir) => (dir, StoragePathSource::Xdg),
+        None => (home.join(".config"), StoragePathSource::Default),
+    };
+    let path = config_home.join("cc-switch").join("configurations.json");
+    let legacy = home.join(".claude").join(STORAGE_FILE_NAME);
+    if !path.exists() && legacy.exists() {
+        return Ok((legacy, StoragePathSource::Legacy));
+    }
+    Ok((path, source))
+}
+
+/// [`resolve_config_storage_path`] for this process
+///
+/// # Errors
+/// Returns error if home directory cannot be found
+pub fn config_storage_location() -> Result<StorageLocation> {
+    resolve_config_storage_path(
+        CONFIG_PATH_OVERRIDE.get().map(PathBuf::as_path),
+        |name| std::env::var_os(name),
+        dirs::home_dir().as_deref(),
+    )
+}
+
+/// Directory holding Claude's settings.json unless a settings directory is
+/// configured
+///
+/// Returns `$CLAUDE_CONFIG_DIR` when set to a non-empty value, else
+/// `~/.claude`, the same directory Claude itself reads.
+///
+/// # Errors
+/// Returns error if home directory cannot be found
+pub fn get_claude_dir() -> Result<PathBuf> {
+    if let Some(dir) = std::env::var_os(CLAUDE_CONFIG_DIR_ENV).filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+    let home_dir = dirs::home_dir().context("Could not find home directory")?;
+    Ok(home_dir.join(".claude"))
+}
+
+/// Get the path to the configuration storage file
+///
+/// See [`resolve_config_storage_path`] for how it is chosen.
+///
+/// # Errors
+/// Returns error if home directory cannot be found
+pub fn get_config_storage_path() -> Result<PathBuf> {
+    Ok(config_storage_location()?.0)
+}
+
+/// Replace `path` with `contents` atomically
+///
+/// The contents go to a temporary file in the same directory, which is
+/// synced and renamed over `path`, so a crash or a full disk leaves either
+/// the old file or the new one, never a partial write. An existing file's
+/// permissions are kept; a new one is readable by the owner only.
+///
+/// # Errors
+/// Returns error if the temporary file cannot be written or renamed
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let mut file = tempfile::NamedTempFile::new_in(dir)
+        .with_context(|| format!("Failed to create a temporary file in {}", dir.display()))?;
+    file.write_all(contents.as_ref())
+        .and_then(|()| file.as_file().sync_all())
+        .with_context(|| format!("Failed to write to {}", path.display()))?;
+    if let Ok(metadata) = fs::metadata(path) {
+        let _ = fs::set_permissions(file.path(), metadata.permissions());
+    }
+    file.persist(path)
+        .with_context(|| format!("Failed to write to {}", path.display()))?;
+    Ok(())
+}
+
+/// Write `contents` to `path`, then read the file back and check it with
+/// `verify`
+///
+/// Guards against filesystems (e.g. NFS homes) that accept a write but
+/// persist a truncated file. On a failed check the previous contents are
+/// restored, or the file removed if it didn't exist before.
+///
+/// # Errors
+/// Returns error if the file cannot be written or the read-back check fails
+pub fn write_verified(
+    path: &Path,
+    contents: &str,
+    verify: impl Fn(&str) -> Result<()>,
+) -> Result<()> {
+    write_verified_with(path, contents, |path| fs::read_to_string(path), verify)
+}
+
+/// [`write_verified`] with the read-back step injected, for tests
+pub(crate) fn write_verified_with(
+    path: &Path,
+    contents: &str,
+    read_back: impl Fn(&Path) -> io::Result<String>,
+    verify: impl Fn(&str) -> Result<()>,
+) -> Result<()> {
+    let previous = fs::read(path).ok();
+    write_atomic(path, contents)?;
+
+    let check = read_back(path)
+        .with_context(|| format!("Failed to read back {}", path.display()))
+        .and_then(|written| verify(&written));
+    if let Err(e) = check {
+        let restored = match &previous {
+            Some(bytes) => write_atomic(path, bytes).is_ok(),
+            None => fs::remove_file(path).is_ok(),
+        };
+        let outcome = if restored {
+            "previous contents restored"
+        } else {
+            "previous contents could NOT be restored"
+        };
+        return Err(e.context(format!(
+            "Write verification failed for {} ({})",
+            path.display(),
+            outcome
+        )));
+    }
+    Ok(())
+}
+
+/// Extensions whose imports accept `//` and `/* */` comments and trailing
+/// commas (JSONC / JSON5 style)
+const LENIENT_JSON_EXTENSIONS: [&str; 3] = ["json", "jsonc", "json5"];
+
+/// Whether `path` has an extension parsed leniently by [`strip_json_comments`]
+pub fn is_lenient_json_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| LENIENT_JSON_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// Blank out comments and trailing commas so the result parses as JSON
+///
+/// Removed characters are replaced by spaces and newlines are kept, so
+/// line and column numbers in parse errors still match the original text.
+/// String contents are never touched.
+pub fn strip_json_comments(input: &str) -> String {
+    let chars: Vec<char> = input.chars().collect();
+    let mut out: Vec<char> = Vec::with_capacity(chars.len());
+    let mut i = 0;
+    let mut in_string = false;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if in_string {
+            out.push(c);
+            if c == '\\' && i + 1 < chars.len() {
+                out.push(chars[i + 1]);
+                i += 1;
+            } else if c == '"' {
+                in_string = false;
+            }
+            i += 1;
+            continue;
+        }
+        match (c, chars.get(i + 1)) {
+            ('"', _) => {
+                in_string = true;
+                out.push(c);
+                i += 1;
+            }
+            ('/', Some('/')) => {
+                while i < chars.len() && chars[i] != '\n' {
+                    out.push(if chars[i] == '\r' { '\r' } else { ' ' });
+                    i += 1;
+                }
+            }
+            ('/', Some('*')) => {
+                out.extend([' ', ' ']);
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    out.push(if chars[i] == '\n' { '\n' } else { ' ' });
+                    i += 1;
+                }
+                let closing = (chars.len() - i).min(2);
+                out.extend(std::iter::repeat_n(' ', closing));
+                i += closing;
+            }
+            _ => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    // Comments are gone, so a comma followed only by whitespace and a
+    // closing bracket is a trailing comma
+    let mut in_string = false;
+    let mut escaped = false;
+    for i in 0..out.len() {
+        let c = out[i];
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        if c == '"' {
+            in_string = true;
+        } else if c == ','
+            && matches!(
+                out[i + 1..].iter().find(|c| !c.is_whitespace()),
+                Some('}' | ']')
+            )
+        {
+            out[i] = ' ';
+        }
+    }
+
+    out.into_iter().collect()
+}
+
+/// Get the path to the Claude settings file
+///
+/// Returns the path to settings.json, using custom directory if configured
+/// Defaults to `settings.json` in [`get_claude_dir`]
+///
+/// # Errors
+/// Returns error if home directory cannot be found or path is invalid
+pub fn get_claude_settings_path(custom_dir: Option<&str>) -> Result<PathBuf> {
+    if let Some(dir) = custom_dir {
+        let custom_path = PathBuf::from(dir);
+        if custom_path.is_absolute() {
+            Ok(custom_path.join("settings.json"))
+        } else {
+            // If relative path, resolve from home directory
+            let home_dir = dirs::home_dir().context("Could not find home directory")?;
+            Ok(home_dir.join(custom_path).join("settings.json"))
+        }
+    } else {
+        Ok(get_claude_dir()?.join("settings.json"))
+    }
+}
+
+/// Read input from stdin with a prompt
+///
+/// # Arguments
+/// * `prompt` - The prompt to display to the user
+///
+/// # Returns
+/// The user's input as a String
+pub fn read_input(prompt: &str) -> Result<String> {
+    print!("{prompt}");
+    io::stdout().flush().context("Failed to flush stdout")?;
+    let mut input = String::new();
+    io::stdin()
+        .read_line(&mut input)
+        .context("Failed to read input")?;
+    Ok(input.trim().to_string())
+}
+
+/// Read sensitive input (token) with a prompt (without echoing)
+///
+/// # Arguments
+/// * `prompt` - The prompt to display to the user
+///
+/// # Returns
+/// The user's input as a String
+pub fn read_sensitive_input(prompt: &str) -> Result<String> {
+    crate::interactive::read_sensitive_input(prompt)
+}
+
+/// Execute claude command with or without --dangerously-skip-permissions
+///
+/// Launches through the same path as every other launch (see
+/// [`crate::interactive::launch_claude_invocation`]).
+///
+/// # Arguments
+/// * `skip_permissions` - Whether to add --dangerously-skip-permissions flag
+pub fn execute_claude_command(skip_permissions: bool) -> Result<()> {
+    let storage = crate::config::ConfigStorage::load()?;
+    let invocation =
+        crate::interactive::ClaudeInvocation::new(skip_permissions, &[], None, None, false)
+            .with_program(crate::platform::resolve_claude_binary(&storage)?);
+    crate::interactive::launch_claude_invocation(
+        crate::config::EnvironmentConfig::default(),
+        &invocation,
+    )
+}
+
+/// Launch Claude CLI with --dangerously-skip-permissions
+pub fn launch_claude() -> Result<()> {
+    execute_claude_command(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::types::{ConfigStorage, Configuration};
+
+    fn storage_with(aliases: &[&str]) -> ConfigStorage {
+        let mut storage = ConfigStorage::default();
+        for alias in aliases {
+            storage.add_configuration(Configuration {
+                alias_name: alias.to_string(),
+                token: "sk-test".to_string(),
+                url: "https://api.example.com".to_string(),
+                ..Default::default()
+            });
+        }
+        storage
+    }
+
+    #[test]
+    fn verified_write_keeps_matching_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("storage.json");
+        let storage = storage_with(&["a", "b"]);
+        let json = serde_json::to_string_pretty(&storage).unwrap();
+
+        write_verified(&path, &json, |written| storage.check_written(written)).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), json);
+    }
+
+    #[test]
+    fn truncated_read_back_restores_previous_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("storage.json");
+        let previous = serde_json::to_string_pretty(&storage_with(&["a"])).unwrap();
+        fs::write(&path, &previous).unwrap();
+
+        let storage = storage_with(&["a", "b"]);
+        let json = serde_json::to_string_pretty(&storage).unwrap();
+        // Simulate a filesystem that persisted only half of the file
+        let truncate = |path: &Path| {
+            let written = fs::read_to_string(path)?;
+            Ok(written[..written.len() / 2].to_string())
+        };
+        let err = write_verified_with(&path, &json, truncate, |written| {
+            storage.check_written(written)
+        })
+        .unwrap_err();
+
+        assert!(format!("{err:#}").contains("previous contents restored"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), previous);
+    }
+
+    #[test]
+    fn alias_mismatch_fails_and_removes_new_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("storage.json");
+        let storage = storage_with(&["a", "b"]);
+        let json = serde_json::to_string_pretty(&storage).unwrap();
+        let stale = serde_json::to_string_pretty(&storage_with(&["a"])).unwrap();
+
+        let err = write_verified_with(
+            &path,
+            &json,
+            |_| Ok(stale.clone()),
+            |written| storage.check_written(written),
+        )
+        .unwrap_err();
+
+        assert!(format!("{err:#}").contains("has 1 configurations (a), expected 2 (a, b)"));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn jsonc_comments_and_trailing_commas_are_blanked() {
+        let input = "{\n  // provider docs\n  \"env\": {\n    \"URL\": \"https://x//y\", /* inline */\n    \"A\": \"1\",\n  },\n}\n";
+        let stripped = strip_json_comments(input);
+        assert_eq!(stripped.lines().count(), input.lines().count());
+        let value: serde_json::Value = serde_json::from_str(&stripped).unwrap();
+        assert_eq!(value["env"]["URL"], "https://x//y");
+        assert_eq!(value["env"]["A"], "1");
+    }
+
+    #[test]
+    fn jsonc_errors_keep_original_positions() {
+        let input = "{\n  // comment\n  \"env\": {\n    \"A\": oops\n  }\n}\n";
+        let err =
+            serde_json::from_str::<serde_json::Value>(&strip_json_comments(input)).unwrap_err();
+        assert_eq!((err.line(), err.column()), (4, 10));
+    }
+
+    #[test]
+    fn commas_and_slashes_inside_strings_are_kept() {
+        let input = r#"{"a": "x, ]", "b": "/* no */", "c": "q\"//"}"#;
+        assert_eq!(strip_json_comments(input), input);
+    }
+
+    #[test]
+    fn lenient_extensions() {
+        assert!(is_lenient_json_path(Path::new("a.json")));
+        assert!(is_lenient_json_path(Path::new("a.JSONC")));
+        assert!(is_lenient_json_path(Path::new("a.json5")));
+        assert!(!is_lenient_json_path(Path::new("a.txt")));
+        assert!(!is_lenient_json_path(Path::new("settings")));
+    }
+
+    /// `(name, value)` of an environment variable set
+    type Var<'a> = (&'a str, &'a str);
+
+    /// [`resolve_config_storage_path`] with `vars` as the environment
+    fn resolve_in(home: &Path, flag: Option<&Path>, vars: &[Var]) -> StorageLocation {
+        let vars: std::collections::BTreeMap<String, OsString> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), OsString::from(value)))
+            .collect();
+        resolve_config_storage_path(flag, |name| vars.get(name).cloned(), Some(home)).unwrap()
+    }
+
+    /// Every variable, each overriding the ones after it
+    fn all_vars(xdg: &str) -> [Var<'_>; 3] {
+        [
+            (CONFIG_FILE_ENV, "/env/store.json"),
+            (CONFIG_DIR_ENV, "/dir"),
+            (XDG_CONFIG_HOME_ENV, xdg),
+        ]
+    }
+
+    #[test]
+    fn storage_path_flag_wins() {
+        let home = tempfile::TempDir::new().unwrap();
+        assert_eq!(
+            resolve_in(
+                home.path(),
+                Some(Path::new("/flag.json")),
+                &all_vars("/xdg")
+            ),
+            (PathBuf::from("/flag.json"), StoragePathSource::Flag)
+        );
+    }
+
+    #[test]
+    fn storage_path_from_cc_switch_config() {
+        let home = tempfile::TempDir::new().unwrap();
+        assert_eq!(
+            resolve_in(home.path(), None, &all_vars("/xdg")),
+            (PathBuf::from("/env/store.json"), StoragePathSource::FileEnv)
+        );
+        assert_eq!(
+            StoragePathSource::FileEnv.origin(),
+            Some("CC_SWITCH_CONFIG")
+        );
+    }
+
+    #[test]
+    fn storage_path_from_cc_switch_config_dir() {
+        let home = tempfile::TempDir::new().unwrap();
+        assert_eq!(
+            resolve_in(home.path(), None, &all_vars("/xdg")[1..]),
+            (
+                PathBuf::from("/dir/cc_auto_switch_setting.json"),
+                StoragePathSource::DirEnv
+            )
+        );
+        // An empty value is ignored
+        assert_eq!(
+            resolve_in(
+                home.path(),
+                None,
+                &[(CONFIG_FILE_ENV, ""), (CONFIG_DIR_ENV, "/dir")]
+            )
+            .1,
+            StoragePathSource::DirEnv
+        );
+    }
+
+    #[test]
+    fn storage_path_in_xdg_config_home_when_set() {
+        let home = tempfile::TempDir::new().unwrap();
+        let xdg = home.path().join("xdg");
+        assert_eq!(
+            resolve_in(home.path(), None, &all_vars(xdg.to_str().unwrap())[2..]),
+            (
+                xdg.join("cc-switch").join("configurations.json"),
+                StoragePathSource::Xdg
+            )
+        );
+        assert_eq!(StoragePathSource::Xdg.origin(), Some("XDG_CONFIG_HOME"));
+    }
+
+    #[test]
+    fn storage_path_in_dot_config_when_xdg_is_unset() {
+        let home = tempfile::TempDir::new().unwrap();
+        let expected = (
+            home.path()
+                .join(".config")
+                .join("cc-switch")
+                .join("configurations.json"),
+            StoragePathSource::Default,
+        );
+        assert_eq!(resolve_in(home.path(), None, &[]), expected);
+        // The specification says to ignore a relative XDG_CONFIG_HOME
+        assert_eq!(
+            resolve_in(home.path(), None, &[(XDG_CONFIG_HOME_ENV, "relative/xdg")]),
+            expected
+        );
+        assert_eq!(StoragePathSource::Default.origin(), None);
+        assert!(resolve_config_storage_path(None, |_| None, None).is_err());
+    }
+
+    #[test]
+    fn storage_path_keeps_an_existing_legacy_store() {
+        let home = tempfile::TempDir::new().unwrap();
+        let legacy = home
+            .path()
+            .join(".claude")
+            .join("cc_auto_switch_setting.json");
+        fs::create_dir_all(legacy.parent().unwrap()).unwrap();
+        fs::write(&legacy, "{}").unwrap();
+        let xdg = home.path().join("xdg");
+        let xdg_vars = &all_vars(xdg.to_str().unwrap())[2..];
+
+        // Over an XDG file that doesn't exist yet, set or not...
+        for vars in [&[][..], xdg_vars] {
+            assert_eq!(
+                resolve_in(home.path(), None, vars),
+                (legacy.clone(), StoragePathSource::Legacy)
+            );
+        }
+        // ...but not over an existing one
+        let xdg_path = xdg.join("cc-switch").join("configurations.json");
+        fs::create_dir_all(xdg_path.parent().unwrap()).unwrap();
+        fs::write(&xdg_path, "{}").unwrap();
+        assert_eq!(
+            resolve_in(home.path(), None, xdg_vars),
+            (xdg_path, StoragePathSource::Xdg)
+        );
+        assert_eq!(StoragePathSource::Legacy.origin(), None);
+    }
+}
@@ -0,0 +1,34 @@
+//! Helpers shared by the tests that run the cc-switch binary.
+
+/// cc-switch run with `home` as HOME and none of the developer's
+/// cc-switch (`CC_SWITCH_CONFIG`, `CC_SWITCH_CONFIG_DIR`, ...), Claude
+/// binary, Claude config directory or Anthropic variables, so a test can neither read nor write
+/// their real store or settings
+pub fn cc_switch(home: &std::path::Path) -> std::process::Command {
+    let mut command = std::process::Command::new(env!("CARGO_BIN_EXE_cc-switch"));
+    command
+        .env("HOME", home)
+        .env_remove("XDG_CONFIG_HOME")
+        .env_remove("CLAUDE_BINARY")
+        .env_remove("CLAUDE_CONFIG_DIR");
+    for (name, _) in std::env::vars_os() {
+        if name
+            .to_str()
+            .is_some_and(|name| name.starts_with("CC_SWITCH_"))
+        {
+            command.env_remove(name);
+        }
+    }
+    for var in cc_switch::config::ANTHROPIC_ENV_VARS {
+        command.env_remove(var);
+    }
+    command
+}
+
+/// Store a [`cc_switch`] run creates under `home`: the XDG default, as
+/// `XDG_CONFIG_HOME` is unset
+pub fn storage_path(home: &std::path::Path) -> std::path::PathBuf {
+    home.join(".config")
+        .join("cc-switch")
+        .join("configurations.json")
+}
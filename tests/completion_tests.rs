@@ -184,6 +184,321 @@ mod tests {
         assert!(result.is_ok(), "Should handle multiple configurations");
     }
 
+    #[test]
+    fn test_alias_completion_candidates_official_only_when_requested() {
+        use cc_switch::config::{ConfigStorage, Configuration};
+
+        let mut storage = ConfigStorage::default();
+        for alias in ["work", "current", "alpha"] {
+            storage.add_configuration(Configuration {
+                alias_name: alias.to_string(),
+                ..Default::default()
+            });
+        }
+
+        assert_eq!(
+            alias_completion_candidates(&storage, true),
+            vec!["cc", "official", "current", "alpha", "work"]
+        );
+        assert_eq!(
+            alias_completion_candidates(&storage, false),
+            vec!["current", "alpha", "work"]
+        );
+    }
+
+    #[test]
+    fn test_alias_completion_candidates_include_url_variants() {
+        use cc_switch::config::{ConfigStorage, Configuration};
+
+        let mut storage = ConfigStorage::default();
+        let mut relay = Configuration {
+            alias_name: "relay".to_string(),
+            ..Default::default()
+        };
+        relay
+            .url_variants
+            .insert("eu".to_string(), "https://eu.example".to_string());
+        storage.add_configuration(relay);
+        storage.add_configuration(Configuration {
+            alias_name: "work".to_string(),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            alias_completion_candidates(&storage, true),
+            vec!["cc", "official", "relay", "relay@eu", "work"]
+        );
+        assert_eq!(
+            alias_completion_candidates(&storage, false),
+            vec!["relay", "work"]
+        );
+    }
+
+    #[test]
+    fn test_zsh_and_bash_complete_aliases_dynamically() {
+        let zsh = String::from_utf8(render_completion("zsh").unwrap().unwrap()).unwrap();
+        assert!(zsh.contains("Configuration alias name to switch to:_cc_switch_aliases'"));
+        assert!(zsh.contains("(one or more):_cc_switch_stored_aliases'"));
+        assert!(zsh.contains("alias name to edit:_cc_switch_stored_aliases'"));
+        assert!(zsh.contains("cc-switch --list-aliases"));
+        let helpers = zsh.find("_cc_switch_aliases() {").expect("helper defined");
+        let dispatch = zsh.rfind("compdef _cc-switch cc-switch").expect("compdef");
+        assert!(
+            helpers < dispatch,
+            "helpers must be defined before dispatch"
+        );
+
+        let bash = String::from_utf8(render_completion("bash").unwrap().unwrap()).unwrap();
+        assert!(bash.contains("cc-switch --list-aliases"));
+        assert!(bash.contains("cc-switch --list-stored-aliases"));
+        assert!(bash.trim_end().ends_with("fi"));
+        assert!(bash.contains("complete -F _cc_switch_dynamic"));
+    }
+
+    #[test]
+    fn test_every_alias_positional_gets_dynamic_completion() {
+        use cc_switch::cli::Cli;
+        use clap::CommandFactory;
+
+        fn walk(command: &clap::Command, path: &mut Vec<String>, found: &mut Vec<Vec<String>>) {
+            for sub in command.get_subcommands() {
+                path.push(sub.get_name().to_string());
+                for arg in sub.get_positionals() {
+                    let id = arg.get_id().as_str();
+                    if (id == "alias_name" || id == "alias_names") && sub.get_name() != "add" {
+                        let value_name = arg.get_value_names().and_then(|names| names.first());
+                        assert!(
+                            value_name.is_some_and(|name| {
+                                AliasSource::from_value_name(name.as_str()).is_some()
+                            }),
+                            "{path:?} {id} needs an ALIAS, STORED_ALIAS or CODEX_ALIAS value name"
+                        );
+                        found.push(path.clone());
+                    }
+                }
+                walk(sub, path, found);
+                path.pop();
+            }
+        }
+
+        let command = Cli::command();
+        let mut found = Vec::new();
+        walk(&command, &mut Vec::new(), &mut found);
+        assert!(found.iter().any(|path| path == &["codex", "use"]));
+
+        let args = alias_args(&command);
+        let fish = String::from_utf8(render_completion("fish").unwrap().unwrap()).unwrap();
+        let zsh = String::from_utf8(render_completion("zsh").unwrap().unwrap()).unwrap();
+        let bash = String::from_utf8(render_completion("bash").unwrap().unwrap()).unwrap();
+        for path in &found {
+            let arg = args
+                .iter()
+                .find(|arg| &arg.path == path)
+                .unwrap_or_else(|| panic!("{path:?} missing from alias_args"));
+            let flag = arg.source.list_flag();
+            let name = path.last().unwrap();
+            assert!(
+                fish.lines().any(|line| {
+                    line.starts_with("complete -c cc-switch ")
+                        && line.contains(flag)
+                        && (line.contains(&format!("_from {name}'"))
+                            || line.contains(&format!("using_subcommand {name}'")))
+                }),
+                "{path:?} missing from fish completion"
+            );
+            assert!(
+                zsh.contains(&format!(":{}'", arg.source.zsh_function())),
+                "{path:?} missing from zsh completion"
+            );
+            assert!(
+                !zsh.contains(&format!("{} -- {}:_default'", arg.id, arg.help)),
+                "{path:?} still completes files in zsh"
+            );
+            assert!(
+                bash.contains(&format!("\"${{COMP_WORDS[{}]}}\" == {name}", path.len())),
+                "{path:?} missing from bash completion"
+            );
+        }
+
+        // The cs subcommand list follows the CLI; stale names are gone
+        let cs = fish
+            .lines()
+            .find(|line| line.starts_with("complete -c cs -n '__fish_use_subcommand'"))
+            .unwrap();
+        assert!(cs.contains(" edit ") && !cs.contains("switch") && !cs.contains("set-default-dir"));
+    }
+
+    // install path Tests
+    #[test]
+    fn test_completion_install_paths_follow_xdg_and_fpath() {
+        use std::ffi::OsString;
+        use std::path::Path;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let home = tmp.path();
+        let none = |_: &str| -> Option<OsString> { None };
+
+        assert_eq!(
+            resolve_completion_install_path("fish", none, home),
+            Some(home.join(".config/fish/completions/cc-switch.fish"))
+        );
+        assert_eq!(
+            resolve_completion_install_path("bash", none, home),
+            Some(home.join(".local/share/bash-completion/completions/cc-switch"))
+        );
+        assert_eq!(
+            resolve_completion_install_path("zsh", none, home),
+            Some(home.join(".zsh/completions/_cc-switch"))
+        );
+        assert_eq!(resolve_completion_install_path("elvish", none, home), None);
+
+        let xdg = |var: &str| match var {
+            "XDG_CONFIG_HOME" => Some(OsString::from("/xdg/config")),
+            "XDG_DATA_HOME" => Some(OsString::from("relative/data")),
+            _ => None,
+        };
+        assert_eq!(
+            resolve_completion_install_path("fish", xdg, home),
+            Some(Path::new("/xdg/config/fish/completions/cc-switch.fish").to_path_buf())
+        );
+        assert_eq!(
+            resolve_completion_install_path("bash", xdg, home),
+            Some(home.join(".local/share/bash-completion/completions/cc-switch")),
+            "a relative XDG_DATA_HOME is ignored"
+        );
+
+        // The first existing $FPATH directory under home wins
+        let functions = home.join("zfunc");
+        std::fs::create_dir(&functions).unwrap();
+        let fpath = std::env::join_paths([
+            Path::new("/usr/share/zsh/functions"),
+            &home.join("missing"),
+            &functions,
+        ])
+        .unwrap();
+        assert_eq!(
+            resolve_completion_install_path(
+                "zsh",
+                |var| (var == "FPATH").then(|| fpath.clone()),
+                home
+            ),
+            Some(functions.join("_cc-switch"))
+        );
+    }
+
+    // stale completion hint Tests
+    #[test]
+    fn test_stale_completion_hint_only_for_stale_files_once() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("_cc-switch");
+        let marker = tmp.path().join("hint-marker");
+
+        // Completion not installed: nothing to refresh
+        assert_eq!(
+            take_stale_completion_hint("zsh", &path, b"new", &marker),
+            None
+        );
+
+        std::fs::write(&path, b"new").unwrap();
+        assert_eq!(
+            take_stale_completion_hint("zsh", &path, b"new", &marker),
+            None,
+            "up-to-date file needs no hint"
+        );
+
+        std::fs::write(&path, b"old").unwrap();
+        let hint = take_stale_completion_hint("zsh", &path, b"new", &marker)
+            .expect("stale file should produce a hint");
+        assert!(hint.contains("cc-switch completion zsh"), "got: {hint}");
+        assert_eq!(
+            take_stale_completion_hint("zsh", &path, b"new", &marker),
+            None,
+            "hint is shown once per version"
+        );
+        assert!(
+            take_stale_completion_hint("bash", &path, b"new", &marker).is_some(),
+            "another shell gets its own hint"
+        );
+    }
+
+    // completion doctor Tests
+    #[test]
+    fn test_check_completion_file_missing_stale_and_fresh() {
+        let path = std::path::Path::new("/tmp/_cc-switch");
+
+        let missing = check_completion_file("zsh", path, None, b"new");
+        assert_eq!(missing.len(), 1);
+        assert!(!missing[0].passed);
+        assert_eq!(
+            missing[0].fix.as_deref(),
+            Some("cc-switch completion zsh --install --force")
+        );
+
+        let stale = check_completion_file("zsh", path, Some(b"old"), b"new");
+        assert!(stale[0].passed);
+        assert!(!stale[1].passed, "differing content must be reported stale");
+        assert!(stale[1].detail.contains("stale"));
+
+        let fresh = check_completion_file("zsh", path, Some(b"new"), b"new");
+        assert!(fresh.iter().all(|c| c.passed));
+    }
+
+    #[test]
+    fn test_check_zsh_fpath() {
+        let dir = std::path::Path::new("/home/u/.zsh/completions");
+        assert!(check_zsh_fpath("/usr/share/zsh/functions /home/u/.zsh/completions\n", dir).passed);
+
+        let missing = check_zsh_fpath("/usr/share/zsh/functions", dir);
+        assert!(!missing.passed);
+        assert!(
+            missing
+                .fix
+                .unwrap()
+                .contains("fpath=(/home/u/.zsh/completions $fpath)")
+        );
+    }
+
+    #[test]
+    fn test_check_list_aliases_duration_threshold() {
+        assert!(check_list_aliases_duration(std::time::Duration::from_millis(20)).passed);
+        assert!(
+            !check_list_aliases_duration(
+                LIST_ALIASES_THRESHOLD + std::time::Duration::from_millis(1)
+            )
+            .passed
+        );
+    }
+
+    #[test]
+    fn test_render_completion_is_deterministic() {
+        let first = render_completion("zsh")
+            .unwrap()
+            .expect("zsh has an install path");
+        let second = render_completion("zsh").unwrap().unwrap();
+        assert_eq!(first, second, "doctor relies on stable output");
+        assert!(render_completion("elvish").unwrap().is_none());
+        assert!(render_completion("tcsh").is_err());
+    }
+
+    #[test]
+    fn test_cli_parses_completion_doctor() {
+        use cc_switch::cli::{Cli, Commands};
+        use clap::Parser;
+
+        let cli = Cli::try_parse_from(["cc-switch", "completion", "doctor", "zsh"]).unwrap();
+        match cli.command {
+            Some(Commands::Completion {
+                shell,
+                doctor_shell,
+                ..
+            }) => {
+                assert_eq!(shell, "doctor");
+                assert_eq!(doctor_shell.as_deref(), Some("zsh"));
+            }
+            _ => panic!("Expected Completion command"),
+        }
+    }
+
     // Integration Tests for Shell-specific Logic
     #[test]
     fn test_supported_shells_list() {
@@ -506,4 +821,73 @@ mod tests {
             duration
         );
     }
+
+    // render_config_functions Tests
+    fn storage_with_aliases(aliases: &[&str]) -> cc_switch::config::ConfigStorage {
+        let mut storage = cc_switch::config::ConfigStorage::default();
+        for alias in aliases {
+            storage.add_configuration(cc_switch::config::Configuration {
+                alias_name: alias.to_string(),
+                token: "sk-test".to_string(),
+                url: "https://api.example.com".to_string(),
+                ..Default::default()
+            });
+        }
+        storage
+    }
+
+    #[test]
+    fn test_config_functions_sanitize_tricky_aliases() {
+        let storage = storage_with_aliases(&["work", "relay.eu", "relay_eu", "工作", "2fast"]);
+
+        let bash = render_config_functions("bash", &storage, "cc").unwrap();
+        assert_eq!(
+            bash.script,
+            "cc2fast() { cc-switch use 2fast \"$@\"; }\n\
+             ccrelay_eu() { cc-switch use relay.eu \"$@\"; }\n\
+             ccwork() { cc-switch use work \"$@\"; }\n\
+             cc__() { cc-switch use '工作' \"$@\"; }\n"
+        );
+        assert_eq!(
+            bash.warnings,
+            vec![
+                "Skipped 'relay_eu': its function name 'ccrelay_eu' is already used by 'relay.eu'"
+            ]
+        );
+
+        let fish = render_config_functions("fish", &storage, "cc").unwrap();
+        assert!(
+            fish.script
+                .starts_with("function cc2fast; cc-switch use '2fast' $argv; end\n")
+        );
+        assert_eq!(
+            render_config_functions("zsh", &storage, "cc")
+                .unwrap()
+                .script,
+            bash.script
+        );
+    }
+
+    #[test]
+    fn test_config_functions_prefix_rules() {
+        let storage = storage_with_aliases(&["2fast", "cs"]);
+
+        // Without a prefix names must still start with a letter or `_`, and
+        // the fixed aliases are never shadowed
+        let functions = render_config_functions("bash", &storage, "").unwrap();
+        assert_eq!(
+            functions.script,
+            "_2fast() { cc-switch use 2fast \"$@\"; }\n"
+        );
+        assert_eq!(
+            functions.warnings,
+            vec!["Skipped 'cs': its function name 'cs' is already used by the `cs` alias"]
+        );
+
+        let err = render_config_functions("bash", &storage, "c-")
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("Invalid prefix 'c-'"));
+        assert!(render_config_functions("powershell", &storage, "cc").is_err());
+    }
 }
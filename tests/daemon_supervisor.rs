@@ -20,6 +20,7 @@ mod daemon_supervisor {
             alias_name: alias.to_string(),
             token: "sk-test".to_string(),
             api_key: None,
+            token_command: None,
             url: url.to_string(),
             model: None,
             small_fast_model: None,
@@ -37,19 +38,28 @@ mod daemon_supervisor {
             disable_prompt_caching: None,
             claude_code_disable_experimental_betas: None,
             disable_autoupdater: None,
+            tags: Vec::new(),
+            launch_profiles: Default::default(),
+            url_variants: Default::default(),
+            quota_probe: None,
+            extra_env: Default::default(),
+            color: None,
+            provider: None,
+            launch_args: Vec::new(),
         }
     }
 
-    fn make_storage(configs: &[(&str, &str)]) -> ConfigStorage {
+    /// Alias and URL of a stored configuration
+    type AliasUrl<'a> = (&'a str, &'a str);
+
+    fn make_storage(configs: &[AliasUrl]) -> ConfigStorage {
         let mut configurations = BTreeMap::new();
         for (alias, url) in configs {
             configurations.insert(alias.to_string(), make_config(alias, url));
         }
         ConfigStorage {
             configurations,
-            claude_settings_dir: None,
-            default_storage_mode: None,
-            codex_configurations: None,
+            ..Default::default()
         }
     }
 
@@ -9,6 +9,7 @@ mod error_handling_tests {
             alias_name: alias.to_string(),
             token: token.to_string(),
             api_key: None,
+            token_command: None,
             url: url.to_string(),
             model: None,
             small_fast_model: None,
@@ -26,6 +27,14 @@ mod error_handling_tests {
             disable_prompt_caching: None,
             claude_code_disable_experimental_betas: None,
             disable_autoupdater: None,
+            tags: Vec::new(),
+            launch_profiles: Default::default(),
+            url_variants: Default::default(),
+            quota_probe: None,
+            extra_env: Default::default(),
+            color: None,
+            provider: None,
+            launch_args: Vec::new(),
         }
     }
 
@@ -1,3 +1,5 @@
+mod common;
+
 #[cfg(test)]
 #[allow(clippy::module_inception)]
 mod integration_tests {
@@ -16,6 +18,7 @@ mod integration_tests {
             alias_name: alias.to_string(),
             token: token.to_string(),
             api_key: None,
+            token_command: None,
             url: url.to_string(),
             model: None,
             small_fast_model: None,
@@ -33,6 +36,14 @@ mod integration_tests {
             disable_prompt_caching: None,
             claude_code_disable_experimental_betas: None,
             disable_autoupdater: None,
+            tags: Vec::new(),
+            launch_profiles: Default::default(),
+            url_variants: Default::default(),
+            quota_probe: None,
+            extra_env: Default::default(),
+            color: None,
+            provider: None,
+            launch_args: Vec::new(),
         }
     }
 
@@ -147,15 +158,14 @@ mod integration_tests {
 
     #[test]
     fn test_integration_config_path_resolution() {
-        let result = get_config_storage_path();
+        // Without the developer's overrides, which would pick another file
+        let home = create_test_temp_dir();
+        let result =
+            cc_switch::utils::resolve_config_storage_path(None, |_| None, Some(home.path()));
         assert!(result.is_ok());
 
-        let path = result.unwrap();
-        assert!(path.to_string_lossy().contains(".claude"));
-        assert!(
-            path.to_string_lossy()
-                .ends_with("cc_auto_switch_setting.json")
-        );
+        let (path, _) = result.unwrap();
+        assert_eq!(path, crate::common::storage_path(home.path()));
     }
 
     #[test]
@@ -194,4 +204,106 @@ mod integration_tests {
             Some("claude-3-5-sonnet-20241022")
         );
     }
+
+    /// Fake Claude binary writing its arguments and environment to files
+    #[cfg(unix)]
+    fn fake_claude(dir: &std::path::Path) -> std::path::PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let script = dir.join("fake-claude");
+        fs::write(
+            &script,
+            format!(
+                "#!/bin/sh\nfor arg in \"$@\"; do echo \"$arg\"; done > '{args}'\nenv > '{env}'\n",
+                args = dir.join("claude_args").display(),
+                env = dir.join("claude_env").display()
+            ),
+        )
+        .expect("Should write fake claude");
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755))
+            .expect("Should make fake claude executable");
+        script
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_integration_end_to_end_with_fake_claude() {
+        let tmp = create_test_temp_dir();
+        let home = tmp.path().join("home");
+        let config_dir = tmp.path().join("config");
+        fs::create_dir_all(&home).expect("Should create home");
+        let claude = fake_claude(tmp.path());
+        let run = |args: &[&str]| {
+            let output = crate::common::cc_switch(&home)
+                .env(cc_switch::utils::CONFIG_DIR_ENV, &config_dir)
+                .env("CLAUDE_BINARY", &claude)
+                .args(args)
+                .output()
+                .expect("Should run cc-switch");
+            assert!(
+                output.status.success(),
+                "{args:?} failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+            String::from_utf8_lossy(&output.stdout).into_owned()
+        };
+
+        run(&[
+            "add",
+            "work",
+            "sk-ant-REDACTED",
+            "https://work.example.com",
+            "--model",
+            "opus",
+        ]);
+        let storage_path = config_dir.join("cc_auto_switch_setting.json");
+        assert!(storage_path.exists(), "storage goes to the config dir");
+        assert!(!crate::common::storage_path(&home).exists());
+
+        let listed: serde_json::Value =
+            serde_json::from_str(&run(&["list", "--format", "json"])).expect("Should be JSON");
+        assert_eq!(listed[0]["alias_name"], "work");
+        assert_eq!(listed[0]["model"], "opus");
+
+        // --no-launch switches without starting the fake binary; settings.json
+        // stays where Claude reads it, not next to the storage
+        let settings_path = home.join(".claude").join("settings.json");
+        run(&["use", "work", "--no-launch", "--mode", "settings"]);
+        assert!(!config_dir.join("settings.json").exists());
+        let settings = fs::read_to_string(&settings_path).expect("settings.json is written");
+        assert!(settings.contains("https://work.example.com"));
+        assert!(!tmp.path().join("claude_env").exists());
+
+        // A launch hands the fake binary exactly the configuration's environment
+        run(&["use", "work"]);
+        let storage: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&storage_path).unwrap()).unwrap();
+        let stored: Configuration =
+            serde_json::from_value(storage["configurations"]["work"].clone()).unwrap();
+        let child_env =
+            fs::read_to_string(tmp.path().join("claude_env")).expect("fake claude should run");
+        let child_env: std::collections::BTreeMap<&str, &str> = child_env
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .collect();
+        let expected = EnvironmentConfig::from_config(&stored);
+        assert!(!expected.env_vars.is_empty());
+        for (name, value) in &expected.env_vars {
+            assert_eq!(
+                child_env.get(name.as_str()),
+                Some(&value.as_str()),
+                "{name} as received by claude"
+            );
+        }
+        let args = fs::read_to_string(tmp.path().join("claude_args")).unwrap();
+        assert_eq!(
+            args.lines().collect::<Vec<_>>(),
+            ["--dangerously-skip-permissions"]
+        );
+
+        run(&["remove", "work"]);
+        let listed: serde_json::Value =
+            serde_json::from_str(&run(&["list", "--format", "json"])).expect("Should be JSON");
+        assert_eq!(listed, serde_json::json!([]));
+    }
 }
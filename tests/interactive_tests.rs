@@ -9,6 +9,7 @@ mod tests {
             alias_name: alias.to_string(),
             token: token.to_string(),
             api_key: None,
+            token_command: None,
             url: url.to_string(),
             model: None,
             small_fast_model: None,
@@ -26,6 +27,14 @@ mod tests {
             disable_prompt_caching: None,
             claude_code_disable_experimental_betas: None,
             disable_autoupdater: None,
+            tags: Vec::new(),
+            launch_profiles: Default::default(),
+            url_variants: Default::default(),
+            quota_probe: None,
+            extra_env: Default::default(),
+            color: None,
+            provider: None,
+            launch_args: Vec::new(),
         }
     }
 
@@ -41,6 +50,7 @@ mod tests {
             alias_name: alias.to_string(),
             token: token.to_string(),
             api_key: None,
+            token_command: None,
             url: url.to_string(),
             model: model.map(String::from),
             small_fast_model: small_fast_model.map(String::from),
@@ -58,6 +68,14 @@ mod tests {
             disable_prompt_caching: None,
             claude_code_disable_experimental_betas: None,
             disable_autoupdater: None,
+            tags: Vec::new(),
+            launch_profiles: Default::default(),
+            url_variants: Default::default(),
+            quota_probe: None,
+            extra_env: Default::default(),
+            color: None,
+            provider: None,
+            launch_args: Vec::new(),
         }
     }
 
@@ -246,6 +264,7 @@ mod tests {
             alias_name: "".to_string(),
             token: "".to_string(),
             api_key: None,
+            token_command: None,
             url: "".to_string(),
             model: Some("".to_string()),
             small_fast_model: Some("".to_string()),
@@ -263,6 +282,14 @@ mod tests {
             disable_prompt_caching: None,
             claude_code_disable_experimental_betas: None,
             disable_autoupdater: None,
+            tags: Vec::new(),
+            launch_profiles: Default::default(),
+            url_variants: Default::default(),
+            quota_probe: None,
+            extra_env: Default::default(),
+            color: None,
+            provider: None,
+            launch_args: Vec::new(),
         };
 
         let env_config = EnvironmentConfig::from_config(&config);
@@ -414,7 +441,7 @@ mod tests {
     fn test_handle_interactive_selection_empty_storage() {
         let storage = ConfigStorage::default();
 
-        let result = handle_interactive_selection(&storage);
+        let result = handle_interactive_selection(&storage, None, Default::default());
 
         match result {
             Ok(_) => {
@@ -1,15 +1,28 @@
+mod common;
+
 #[cfg(test)]
 mod tests {
+    use crate::common::{cc_switch, storage_path};
     use cc_switch::cli::{Cli, Commands};
     use cc_switch::config::{ConfigStorage, Configuration, EnvironmentConfig};
     use clap::Parser;
 
+    /// The configuration listed under `alias` in `list` JSON output
+    fn listed_config<'a>(list: &'a serde_json::Value, alias: &str) -> &'a serde_json::Value {
+        list.as_array()
+            .expect("list prints an array")
+            .iter()
+            .find(|config| config["alias_name"] == alias)
+            .unwrap_or(&serde_json::Value::Null)
+    }
+
     /// Helper function to create a test configuration
     fn create_test_config(alias: &str, token: &str, url: &str) -> Configuration {
         Configuration {
             alias_name: alias.to_string(),
             token: token.to_string(),
             api_key: None,
+            token_command: None,
             url: url.to_string(),
             model: None,
             small_fast_model: None,
@@ -27,6 +40,14 @@ mod tests {
             disable_prompt_caching: None,
             claude_code_disable_experimental_betas: None,
             disable_autoupdater: None,
+            tags: Vec::new(),
+            launch_profiles: Default::default(),
+            url_variants: Default::default(),
+            quota_probe: None,
+            extra_env: Default::default(),
+            color: None,
+            provider: None,
+            launch_args: Vec::new(),
         }
     }
 
@@ -42,6 +63,7 @@ mod tests {
             alias_name: alias.to_string(),
             token: token.to_string(),
             api_key: None,
+            token_command: None,
             url: url.to_string(),
             model: model.map(String::from),
             small_fast_model: small_fast_model.map(String::from),
@@ -59,6 +81,14 @@ mod tests {
             disable_prompt_caching: None,
             claude_code_disable_experimental_betas: None,
             disable_autoupdater: None,
+            tags: Vec::new(),
+            launch_profiles: Default::default(),
+            url_variants: Default::default(),
+            quota_probe: None,
+            extra_env: Default::default(),
+            color: None,
+            provider: None,
+            launch_args: Vec::new(),
         }
     }
 
@@ -68,9 +98,10 @@ mod tests {
         use cc_switch::config::types::AddCommandParams;
 
         let params = AddCommandParams {
-            alias_name: "test".to_string(),
+            alias_name: Some("test".to_string()),
             token: Some("sk-ant-test".to_string()),
             api_key: None,
+            token_command: None,
             url: Some("https://api.test.com".to_string()),
             model: None,
             small_fast_model: None,
@@ -86,14 +117,26 @@ mod tests {
             disable_prompt_caching: None,
             claude_code_disable_experimental_betas: None,
             disable_autoupdater: None,
+            claude_code_experimental_agent_teams: None,
+            claude_code_disable_1m_context: None,
+            tags: Vec::new(),
+            color: None,
+            provider: None,
+            preset: None,
             force: false,
             interactive: false,
             token_arg: None,
             url_arg: None,
             from_file: None,
+            strict_json: false,
+            no_validate: false,
+            extra_env: Default::default(),
+            launch_args: Vec::new(),
+            output: Default::default(),
+            reveal: false,
         };
 
-        assert_eq!(params.alias_name, "test");
+        assert_eq!(params.alias_name.as_deref(), Some("test"));
         assert_eq!(params.token, Some("sk-ant-test".to_string()));
         assert_eq!(params.url, Some("https://api.test.com".to_string()));
         assert!(!params.force);
@@ -133,7 +176,7 @@ mod tests {
                 from_file: _,
                 ..
             }) => {
-                assert_eq!(alias_name, "my-config");
+                assert_eq!(alias_name.as_deref(), Some("my-config"));
                 assert_eq!(token_arg, Some("sk-ant-test-token".to_string()));
                 assert_eq!(url_arg, Some("https://api.test.com".to_string()));
                 assert!(!force);
@@ -183,7 +226,7 @@ mod tests {
                 from_file: _,
                 ..
             }) => {
-                assert_eq!(alias_name, "my-config");
+                assert_eq!(alias_name.as_deref(), Some("my-config"));
                 assert_eq!(token, Some("sk-ant-flag-token".to_string()));
                 assert_eq!(url, Some("https://flag.api.com".to_string()));
                 assert!(force);
@@ -225,7 +268,7 @@ mod tests {
                 max_thinking_tokens: _,
                 ..
             }) => {
-                assert_eq!(alias_name, "model-config");
+                assert_eq!(alias_name.as_deref(), Some("model-config"));
                 assert_eq!(token, Some("sk-ant-model-token".to_string()));
                 assert_eq!(url, Some("https://model.api.com".to_string()));
                 assert_eq!(model, Some("claude-3-5-sonnet-20241022".to_string()));
@@ -245,7 +288,7 @@ mod tests {
         let cli = Cli::try_parse_from(args).expect("Should parse remove command");
 
         match cli.command {
-            Some(Commands::Remove { alias_names }) => {
+            Some(Commands::Remove { alias_names, .. }) => {
                 assert_eq!(alias_names, vec!["config-to-remove"]);
             }
             _ => panic!("Expected Remove command"),
@@ -259,7 +302,7 @@ mod tests {
         let cli = Cli::try_parse_from(args).expect("Should parse remove command");
 
         match cli.command {
-            Some(Commands::Remove { alias_names }) => {
+            Some(Commands::Remove { alias_names, .. }) => {
                 assert_eq!(alias_names, vec!["config1", "config2", "config3"]);
             }
             _ => panic!("Expected Remove command"),
@@ -294,6 +337,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cli_use_passes_arguments_after_double_dash_through() {
+        let cli = Cli::try_parse_from([
+            "cc-switch",
+            "use",
+            "my-config",
+            "fix the bug",
+            "--",
+            "--resume",
+            "--model",
+            "claude-3-5-haiku",
+        ])
+        .expect("Should parse use with passthrough arguments");
+
+        match cli.command {
+            Some(Commands::Use {
+                alias_name,
+                prompt,
+                passthrough,
+                ..
+            }) => {
+                assert_eq!(alias_name, "my-config");
+                assert_eq!(prompt, vec!["fix the bug"]);
+                assert_eq!(passthrough, vec!["--resume", "--model", "claude-3-5-haiku"]);
+            }
+            _ => panic!("Expected Use command"),
+        }
+
+        let cli = Cli::try_parse_from(["cc-switch", "use", "my-config"])
+            .expect("Should parse use without passthrough arguments");
+        match cli.command {
+            Some(Commands::Use { passthrough, .. }) => assert!(passthrough.is_empty()),
+            _ => panic!("Expected Use command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_bare_invocation_accepts_passthrough_arguments() {
+        let cli = Cli::try_parse_from(["cc-switch", "--", "--model", "opus"])
+            .expect("Should parse bare passthrough arguments");
+
+        assert!(cli.command.is_none());
+        assert_eq!(cli.claude_args, vec!["--model", "opus"]);
+    }
+
     #[test]
     fn test_cli_completion_command() {
         let args = vec!["cc-switch", "completion", "fish"];
@@ -301,7 +389,7 @@ mod tests {
         let cli = Cli::try_parse_from(args).expect("Should parse completion command");
 
         match cli.command {
-            Some(Commands::Completion { shell }) => {
+            Some(Commands::Completion { shell, .. }) => {
                 assert_eq!(shell, "fish");
             }
             _ => panic!("Expected Completion command"),
@@ -422,7 +510,7 @@ mod tests {
 
         if let Ok(cli) = result {
             match cli.command {
-                Some(Commands::Completion { shell }) => {
+                Some(Commands::Completion { shell, .. }) => {
                     assert_eq!(shell, "fish", "Should default to fish shell");
                 }
                 _ => panic!("Expected Completion command"),
@@ -464,7 +552,7 @@ mod tests {
         if let Ok(cli) = result
             && let Some(Commands::Add { alias_name, .. }) = cli.command
         {
-            assert_eq!(alias_name, "test-config_123");
+            assert_eq!(alias_name.as_deref(), Some("test-config_123"));
         }
     }
 
@@ -489,7 +577,7 @@ mod tests {
                 ..
             }) = cli.command
         {
-            assert_eq!(alias_name, "测试-config");
+            assert_eq!(alias_name.as_deref(), Some("测试-config"));
             assert_eq!(token_arg, Some("sk-ant-测试".to_string()));
             assert_eq!(url_arg, Some("https://αpi.测试.com".to_string()));
         }
@@ -514,7 +602,7 @@ mod tests {
                 ..
             }) = cli.command
         {
-            assert_eq!(alias_name.len(), 1000);
+            assert_eq!(alias_name.unwrap().len(), 1000);
             assert_eq!(token_arg.as_ref().unwrap().len(), 1007); // "sk-ant-" + 1000
             assert_eq!(url_arg.as_ref().unwrap().len(), 1011); // "https://" + 1000 + "com"
         }
@@ -587,7 +675,7 @@ mod tests {
 
     #[test]
     fn test_cli_add_from_file_no_value() {
-        let args = vec!["cc-switch", "add", "work", "--from-file"];
+        let args = vec!["cc-switch", "add", "--from-file"];
         let cli = Cli::try_parse_from(args).expect("Should parse --from-file with no value");
         match cli.command {
             Some(Commands::Add {
@@ -595,7 +683,7 @@ mod tests {
                 from_file,
                 ..
             }) => {
-                assert_eq!(alias_name, "work");
+                assert_eq!(alias_name, None);
                 assert_eq!(
                     from_file,
                     Some(None),
@@ -608,13 +696,7 @@ mod tests {
 
     #[test]
     fn test_cli_add_from_file_with_value() {
-        let args = vec![
-            "cc-switch",
-            "add",
-            "work",
-            "--from-file",
-            "/tmp/config.json",
-        ];
+        let args = vec!["cc-switch", "add", "--from-file", "/tmp/config.json"];
         let cli = Cli::try_parse_from(args).expect("Should parse --from-file with path");
         match cli.command {
             Some(Commands::Add {
@@ -622,7 +704,7 @@ mod tests {
                 from_file,
                 ..
             }) => {
-                assert_eq!(alias_name, "work");
+                assert_eq!(alias_name, None);
                 assert_eq!(from_file, Some(Some("/tmp/config.json".to_string())));
             }
             _ => panic!("Expected Add command"),
@@ -631,12 +713,58 @@ mod tests {
 
     #[test]
     fn test_cli_add_alias_required() {
-        let args = vec!["cc-switch", "add", "--from-file", "/tmp/config.json"];
+        let args = vec![
+            "cc-switch",
+            "add",
+            "-t",
+            "sk-ant-test",
+            "-u",
+            "https://x.example",
+        ];
         let result = Cli::try_parse_from(args);
         assert!(
             result.is_err(),
-            "alias_name must be required even with --from-file"
+            "alias_name must be required without --from-file"
+        );
+    }
+
+    #[test]
+    fn test_add_from_file_rejects_an_alias_argument() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let storage_path = storage_path(tmp.path());
+        std::fs::create_dir_all(storage_path.parent().unwrap()).unwrap();
+        let existing = r#"{"configurations":{}}"#;
+        std::fs::write(&storage_path, existing).unwrap();
+        let file = tmp.path().join("f.json");
+        std::fs::write(
+            &file,
+            r#"{"env": {"ANTHROPIC_AUTH_TOKEN": "sk-ant-test", "ANTHROPIC_BASE_URL": "https://x.example"}}"#,
+        )
+        .unwrap();
+
+        let output = cc_switch(tmp.path())
+            .args(["add", "foo", "--from-file"])
+            .arg(&file)
+            .output()
+            .expect("Should run cc-switch");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(output.status.code(), Some(4), "stderr: {stderr}");
+        assert!(
+            stderr.contains("--from-file cannot be combined with an alias argument"),
+            "stderr: {stderr}"
         );
+        assert_eq!(std::fs::read_to_string(&storage_path).unwrap(), existing);
+
+        // Without the alias the configuration is named after the file
+        let output = cc_switch(tmp.path())
+            .args(["add", "--from-file"])
+            .arg(&file)
+            .output()
+            .expect("Should run cc-switch");
+        assert!(output.status.success());
+        let stored: ConfigStorage =
+            serde_json::from_str(&std::fs::read_to_string(&storage_path).unwrap()).unwrap();
+        assert!(stored.get_configuration("f").is_some());
     }
 
     #[test]
@@ -648,17 +776,13 @@ mod tests {
         //
         // We exercise this by running the binary against a non-existent HOME.
 
-        use std::process::Command;
-
         // Build the binary (cargo test ensures it's already built, but we point
         // at the debug binary path directly).
-        let bin = env!("CARGO_BIN_EXE_cc-switch");
 
         // Case A: bare `--from-file` with a HOME pointing at an empty dir.
         let tmp = tempfile::TempDir::new().expect("tempdir");
-        let output_a = Command::new(bin)
-            .env("HOME", tmp.path())
-            .args(["add", "work", "--from-file"])
+        let output_a = cc_switch(tmp.path())
+            .args(["add", "--from-file"])
             .output()
             .expect("Should run cc-switch");
         let stderr_a = String::from_utf8_lossy(&output_a.stderr);
@@ -678,14 +802,8 @@ mod tests {
         );
 
         // Case B: explicit `--from-file /nonexistent/path.json`.
-        let output_b = Command::new(bin)
-            .env("HOME", tmp.path())
-            .args([
-                "add",
-                "work",
-                "--from-file",
-                "/nonexistent/cc-switch-test.json",
-            ])
+        let output_b = cc_switch(tmp.path())
+            .args(["add", "--from-file", "/nonexistent/cc-switch-test.json"])
             .output()
             .expect("Should run cc-switch");
         let stderr_b = String::from_utf8_lossy(&output_b.stderr);
@@ -704,4 +822,3271 @@ mod tests {
             stderr_b
         );
     }
+
+    #[test]
+    fn test_add_from_file_accepts_jsonc_unless_strict() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let fixture = tmp.path().join("provider.jsonc");
+        std::fs::write(
+            &fixture,
+            r#"{
+  // Token from the provider dashboard
+  "env": {
+    "ANTHROPIC_AUTH_TOKEN": "sk-jsonc", /* keep secret */
+    "ANTHROPIC_BASE_URL": "https://relay.example//v1",
+  },
+}
+"#,
+        )
+        .unwrap();
+
+        let strict = cc_switch(tmp.path())
+            .args(["add", "--strict-json", "--from-file"])
+            .arg(&fixture)
+            .output()
+            .expect("Should run cc-switch");
+        assert!(!strict.status.success());
+        assert!(String::from_utf8_lossy(&strict.stderr).contains("line 2 column 3"));
+
+        let lenient = cc_switch(tmp.path())
+            .args(["add", "--from-file"])
+            .arg(&fixture)
+            .output()
+            .expect("Should run cc-switch");
+        assert!(
+            lenient.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&lenient.stderr)
+        );
+        let storage = std::fs::read_to_string(storage_path(tmp.path())).unwrap();
+        assert!(storage.contains("sk-jsonc"));
+        assert!(storage.contains("https://relay.example//v1"));
+    }
+
+    #[test]
+    fn test_add_from_file_jsonc_error_reports_original_line() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let fixture = tmp.path().join("broken.json");
+        std::fs::write(
+            &fixture,
+            "{\n  // comment\n  /* block\n     comment */\n  \"env\": {\n    \"ANTHROPIC_AUTH_TOKEN\": oops\n  }\n}\n",
+        )
+        .unwrap();
+
+        let output = cc_switch(tmp.path())
+            .args(["add", "--from-file"])
+            .arg(&fixture)
+            .output()
+            .expect("Should run cc-switch");
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("line 6 column 29"), "stderr: {stderr}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_edit_with_editor_renames_and_removes_temp_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let add = cc_switch(tmp.path())
+            .args(["add", "work", "-t", "sk-edit", "-u", "https://old.example"])
+            .output()
+            .expect("Should run cc-switch");
+        assert!(add.status.success());
+
+        // The "editor" records the file it got and its mode, then rewrites it
+        let log = tmp.path().join("editor.log");
+        let editor = tmp.path().join("editor.sh");
+        std::fs::write(
+            &editor,
+            format!(
+                "#!/bin/sh\necho \"$1\" > '{log}'\nls -l \"$1\" | cut -c1-10 >> '{log}'\n\
+                 sed -e 's#https://old.example#https://new.example#' \
+                 -e 's#\"work\"#\"work2\"#' \"$1\" > \"$1.new\" && mv \"$1.new\" \"$1\"\n",
+                log = log.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&editor, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let output = cc_switch(tmp.path())
+            .env_remove("VISUAL")
+            .env("EDITOR", &editor)
+            .args(["edit", "work", "--editor"])
+            .output()
+            .expect("Should run cc-switch");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        assert!(stdout.contains("alias: work -> work2"), "stdout: {stdout}");
+        assert!(stdout.contains("url: https://old.example -> https://new.example"));
+
+        let log = std::fs::read_to_string(&log).unwrap();
+        let mut lines = log.lines();
+        let temp_path = lines.next().unwrap();
+        assert_eq!(lines.next(), Some("-rw-------"));
+        assert!(!std::path::Path::new(temp_path).exists());
+
+        let storage = std::fs::read_to_string(storage_path(tmp.path())).unwrap();
+        assert!(storage.contains("\"work2\""));
+        assert!(!storage.contains("\"work\""));
+        assert!(storage.contains("https://new.example"));
+    }
+
+    #[test]
+    fn test_add_from_file_parse_failure_leaves_storage_untouched() {
+        // A file that exists but fails to parse must not create any
+        // configuration, nor rewrite an existing storage file.
+
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let storage_path = storage_path(tmp.path());
+
+        // The file has an env section but no ANTHROPIC_BASE_URL.
+        let bad_file = tmp.path().join("bad.json");
+        std::fs::write(
+            &bad_file,
+            r#"{"env": {"ANTHROPIC_AUTH_TOKEN": "sk-ant-test"}}"#,
+        )
+        .expect("write bad file");
+
+        let output = cc_switch(tmp.path())
+            .args(["add", "--from-file"])
+            .arg(&bad_file)
+            .output()
+            .expect("Should run cc-switch");
+        assert!(!output.status.success(), "expected parse failure");
+        assert!(
+            !storage_path.exists(),
+            "storage must not be created on a failed import"
+        );
+
+        // With an existing storage file, its contents must be byte-identical.
+        std::fs::create_dir_all(storage_path.parent().unwrap()).expect("create .claude");
+        let existing = r#"{"configurations":{}}"#;
+        std::fs::write(&storage_path, existing).expect("write storage");
+
+        let output = cc_switch(tmp.path())
+            .args(["add", "--from-file"])
+            .arg(&bad_file)
+            .output()
+            .expect("Should run cc-switch");
+        assert!(!output.status.success(), "expected parse failure");
+        assert_eq!(
+            std::fs::read_to_string(&storage_path).expect("read storage"),
+            existing
+        );
+    }
+
+    #[test]
+    fn test_official_alias_policy_per_command() {
+        // Every command parsed with the `cc` pseudo-alias (where it takes an
+        // alias) must carry a deliberate policy for it.
+        use cc_switch::cli::OfficialAliasPolicy;
+
+        /// Arguments of a command and the policy it must have
+        type Case<'a> = (&'a [&'a str], OfficialAliasPolicy);
+
+        let cases: &[Case] = &[
+            (&["use", "cc"], OfficialAliasPolicy::Reset),
+            (&["env", "cc"], OfficialAliasPolicy::Reset),
+            (
+                &["add", "cc", "-t", "sk", "-u", "https://x"],
+                OfficialAliasPolicy::Reject,
+            ),
+            (&["remove", "cc"], OfficialAliasPolicy::Reject),
+            (&["rename", "cc", "work"], OfficialAliasPolicy::Reject),
+            (&["clone", "cc", "work"], OfficialAliasPolicy::Reject),
+            (&["export", "cc"], OfficialAliasPolicy::Reject),
+            (&["test", "cc"], OfficialAliasPolicy::Reject),
+            (&["set-default", "cc"], OfficialAliasPolicy::Reject),
+            (&["resolve", "cc"], OfficialAliasPolicy::Reject),
+            (&["edit", "cc", "--editor"], OfficialAliasPolicy::Reject),
+            (&["list"], OfficialAliasPolicy::NotApplicable),
+            (
+                &["set", "--where", "url=https://x", "url", "https://y"],
+                OfficialAliasPolicy::NotApplicable,
+            ),
+            (&["completion", "fish"], OfficialAliasPolicy::NotApplicable),
+            (&["unuse"], OfficialAliasPolicy::NotApplicable),
+            (&["current"], OfficialAliasPolicy::NotApplicable),
+            (&["version"], OfficialAliasPolicy::NotApplicable),
+            (&["doctor"], OfficialAliasPolicy::NotApplicable),
+            (&["export-shell"], OfficialAliasPolicy::NotApplicable),
+            (&["alias-map"], OfficialAliasPolicy::NotApplicable),
+            (&["alias", "zsh"], OfficialAliasPolicy::NotApplicable),
+            (&["codex", "list"], OfficialAliasPolicy::NotApplicable),
+            (&["daemon", "status"], OfficialAliasPolicy::NotApplicable),
+            (
+                &["config", "set", "hidden-fields", "token"],
+                OfficialAliasPolicy::NotApplicable,
+            ),
+            (&["restore-backup"], OfficialAliasPolicy::NotApplicable),
+            (&["migrate"], OfficialAliasPolicy::NotApplicable),
+            (&["prune"], OfficialAliasPolicy::NotApplicable),
+            (
+                &["backup", "store.json"],
+                OfficialAliasPolicy::NotApplicable,
+            ),
+            (
+                &["restore", "store.json"],
+                OfficialAliasPolicy::NotApplicable,
+            ),
+            (&["import", "providers"], OfficialAliasPolicy::NotApplicable),
+            (&["settings", "path"], OfficialAliasPolicy::NotApplicable),
+            (&["secure", "enable"], OfficialAliasPolicy::NotApplicable),
+            (&["statusline"], OfficialAliasPolicy::NotApplicable),
+        ];
+
+        for (args, expected) in cases {
+            let cli = Cli::try_parse_from(std::iter::once("cc-switch").chain(args.iter().copied()))
+                .unwrap_or_else(|e| panic!("failed to parse {args:?}: {e}"));
+            let command = cli.command.expect("subcommand");
+            assert_eq!(command.official_alias_policy(), *expected, "args: {args:?}");
+        }
+    }
+
+    #[test]
+    fn test_remove_official_alias_is_rejected() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let output = cc_switch(tmp.path())
+            .args(["remove", "cc"])
+            .output()
+            .expect("Should run cc-switch");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(!output.status.success(), "remove cc must fail");
+        assert!(
+            stderr.contains("'cc' is a reserved name"),
+            "got stderr: {stderr}"
+        );
+    }
+
+    #[test]
+    fn test_rename_requires_force_to_replace_existing_alias() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+        };
+        assert!(
+            run(&["add", "test1", "-t", "sk-one", "-u", "https://one.example"])
+                .status
+                .success()
+        );
+        assert!(
+            run(&["add", "work", "-t", "sk-two", "-u", "https://two.example"])
+                .status
+                .success()
+        );
+
+        let refused = run(&["rename", "test1", "work"]);
+        assert!(!refused.status.success());
+        assert!(String::from_utf8_lossy(&refused.stderr).contains("--force"));
+        let reserved = run(&["rename", "test1", "cc"]);
+        assert!(!reserved.status.success());
+        let missing = run(&["rename", "nope", "other"]);
+        assert!(String::from_utf8_lossy(&missing.stderr).contains("'nope' not found"));
+
+        assert!(
+            run(&["rename", "test1", "work", "--force"])
+                .status
+                .success()
+        );
+        let storage: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(storage_path(tmp.path())).unwrap())
+                .unwrap();
+        let configs = storage["configurations"].as_object().unwrap();
+        assert!(!configs.contains_key("test1"));
+        assert_eq!(configs["work"]["alias_name"], "work");
+        assert_eq!(configs["work"]["token"], "sk-one");
+    }
+
+    #[test]
+    fn test_interactive_add_with_empty_token_saves_nothing() {
+        use std::io::Write;
+        use std::process::Stdio;
+
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let mut child = cc_switch(tmp.path())
+            .args(["add", "work", "-i"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("Should run cc-switch");
+        child.stdin.take().unwrap().write_all(b"1\n\n").unwrap();
+        let output = child.wait_with_output().unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success());
+        assert!(stdout.contains("was not added"), "got: {stdout}");
+        assert!(!storage_path(tmp.path()).exists());
+    }
+
+    #[test]
+    fn test_clone_copies_config_and_applies_overrides() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+        };
+        let stored = |alias: &str| -> serde_json::Value {
+            let storage: serde_json::Value =
+                serde_json::from_str(&std::fs::read_to_string(storage_path(tmp.path())).unwrap())
+                    .unwrap();
+            storage["configurations"][alias].clone()
+        };
+        assert!(
+            run(&[
+                "add",
+                "work",
+                "-t",
+                "sk-one",
+                "-u",
+                "https://one.example",
+                "-m",
+                "opus",
+                "--tag",
+                "prod",
+            ])
+            .status
+            .success()
+        );
+
+        let cloned = run(&["clone", "work", "work-haiku", "-m", "haiku"]);
+        let stdout = String::from_utf8_lossy(&cloned.stdout);
+        assert!(
+            cloned.status.success(),
+            "{}",
+            String::from_utf8_lossy(&cloned.stderr)
+        );
+        assert!(stdout.contains("Overridden: model"), "got: {stdout}");
+        assert!(
+            stdout.contains("Inherited: token, url, tags"),
+            "got: {stdout}"
+        );
+        let copy = stored("work-haiku");
+        assert_eq!(copy["alias_name"], "work-haiku");
+        assert_eq!(copy["model"], "haiku");
+        assert_eq!(copy["token"], "sk-one");
+        assert_eq!(copy["tags"], serde_json::json!(["prod"]));
+        assert_eq!(stored("work")["model"], "opus");
+
+        let taken = run(&["clone", "work", "work-haiku"]);
+        assert!(!taken.status.success());
+        assert!(String::from_utf8_lossy(&taken.stderr).contains("--force"));
+        assert!(
+            run(&["clone", "work", "work-haiku", "--force"])
+                .status
+                .success()
+        );
+        assert_eq!(stored("work-haiku")["model"], "opus");
+
+        assert!(!run(&["clone", "missing", "other"]).status.success());
+        assert!(!run(&["clone", "work", "cc"]).status.success());
+    }
+
+    #[test]
+    fn test_edit_flags_update_only_given_fields() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+        };
+        let stored = || -> serde_json::Value {
+            let storage: serde_json::Value =
+                serde_json::from_str(&std::fs::read_to_string(storage_path(tmp.path())).unwrap())
+                    .unwrap();
+            storage["configurations"]["work"].clone()
+        };
+        assert!(
+            run(&[
+                "add",
+                "work",
+                "-t",
+                "sk-one",
+                "-u",
+                "https://one.example",
+                "-m",
+                "opus",
+                "--small-fast-model",
+                "haiku",
+            ])
+            .status
+            .success()
+        );
+
+        let edited = run(&[
+            "edit",
+            "work",
+            "-u",
+            "https://two.example",
+            "--max-thinking-tokens",
+            "8192",
+            "--model",
+            "",
+        ]);
+        assert!(
+            edited.status.success(),
+            "{}",
+            String::from_utf8_lossy(&edited.stderr)
+        );
+        let work = stored();
+        assert_eq!(work["url"], "https://two.example");
+        assert_eq!(work["token"], "sk-one");
+        assert_eq!(work["max_thinking_tokens"], 8192);
+        assert!(work.get("model").is_none());
+        assert_eq!(work["small_fast_model"], "haiku");
+
+        assert!(
+            run(&[
+                "edit",
+                "work",
+                "--unset",
+                "small-fast-model,max_thinking_tokens"
+            ])
+            .status
+            .success()
+        );
+        let work = stored();
+        assert!(work.get("small_fast_model").is_none());
+        assert!(work.get("max_thinking_tokens").is_none());
+
+        let invalid = run(&[
+            "edit",
+            "work",
+            "--api-timeout-ms",
+            "soon",
+            "-u",
+            "https://x",
+        ]);
+        assert!(!invalid.status.success());
+        assert_eq!(stored()["url"], "https://two.example");
+        assert!(!run(&["edit", "work", "--unset", "url"]).status.success());
+        let missing = run(&["edit", "nope", "-m", "opus"]);
+        assert!(String::from_utf8_lossy(&missing.stderr).contains("'nope' not found"));
+        assert!(
+            !run(&["edit", "work", "--editor", "-m", "opus"])
+                .status
+                .success()
+        );
+    }
+
+    #[test]
+    fn test_export_then_import_round_trips_configuration() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let storage_path = storage_path(tmp.path());
+        std::fs::create_dir_all(storage_path.parent().unwrap()).unwrap();
+        let original = Configuration {
+            alias_name: "work".to_string(),
+            token: "sk-work-token".to_string(),
+            url: "https://api.work.example".to_string(),
+            model: Some("opus".to_string()),
+            small_fast_model: Some("haiku".to_string()),
+            max_thinking_tokens: Some(8192),
+            api_timeout_ms: Some(600000),
+            claude_code_disable_nonessential_traffic: Some(1),
+            anthropic_default_sonnet_model: Some("sonnet-4".to_string()),
+            anthropic_default_opus_model: Some("opus-4".to_string()),
+            anthropic_default_haiku_model: Some("haiku-4".to_string()),
+            claude_code_experimental_agent_teams: Some(1),
+            claude_code_disable_1m_context: Some(1),
+            claude_code_subagent_model: Some("sonnet".to_string()),
+            claude_code_disable_nonstreaming_fallback: Some(1),
+            claude_code_effort_level: Some("high".to_string()),
+            disable_prompt_caching: Some(0),
+            claude_code_disable_experimental_betas: Some(1),
+            disable_autoupdater: Some(1),
+            ..Default::default()
+        };
+        let mut storage = ConfigStorage::default();
+        storage.add_configuration(original.clone());
+        std::fs::write(&storage_path, serde_json::to_string(&storage).unwrap()).unwrap();
+
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+        };
+        let stdout = run(&["export", "work"]);
+        assert!(stdout.status.success());
+        let printed: serde_json::Value = serde_json::from_slice(&stdout.stdout).unwrap();
+        assert_eq!(printed["env"]["ANTHROPIC_AUTH_TOKEN"], "sk-work-token");
+
+        let dir = tmp.path().join("exports");
+        std::fs::create_dir(&dir).unwrap();
+        assert!(
+            run(&["export", "work", "--output", dir.to_str().unwrap()])
+                .status
+                .success()
+        );
+        let exported = dir.join("work.json");
+        assert_eq!(
+            std::fs::read_to_string(&exported).unwrap().trim(),
+            String::from_utf8_lossy(&stdout.stdout).trim()
+        );
+
+        let copy = dir.join("copy.json");
+        std::fs::copy(&exported, &copy).unwrap();
+        let import = run(&["add", "--from-file", copy.to_str().unwrap()]);
+        assert!(
+            import.status.success(),
+            "{}",
+            String::from_utf8_lossy(&import.stderr)
+        );
+        let stored: ConfigStorage =
+            serde_json::from_str(&std::fs::read_to_string(&storage_path).unwrap()).unwrap();
+        let imported = stored.get_configuration("copy").expect("imported");
+        // Configuration has no Debug (it holds tokens); compare serialized
+        assert_eq!(
+            serde_json::to_value(imported).unwrap(),
+            serde_json::to_value(Configuration {
+                alias_name: "copy".to_string(),
+                ..original
+            })
+            .unwrap()
+        );
+    }
+
+    fn relay_storage() -> ConfigStorage {
+        let mut storage = ConfigStorage::default();
+        for (alias, url) in [
+            ("relay-a", "https://old.relay.example"),
+            ("relay-b", "https://old.relay.example"),
+            ("direct", "https://api.anthropic.com"),
+        ] {
+            storage.add_configuration(create_test_config(alias, "sk-ant-test", url));
+        }
+        storage
+    }
+
+    #[test]
+    fn test_apply_field_to_aliases_updates_only_given_aliases() {
+        use cc_switch::cli::main::apply_field_to_aliases;
+
+        let mut storage = relay_storage();
+        let aliases = vec!["relay-a".to_string(), "relay-b".to_string()];
+        apply_field_to_aliases(&mut storage, &aliases, "url", "https://new.relay.example")
+            .expect("apply");
+
+        for alias in &aliases {
+            assert_eq!(
+                storage.get_configuration(alias).unwrap().url,
+                "https://new.relay.example"
+            );
+        }
+        assert_eq!(
+            storage.get_configuration("direct").unwrap().url,
+            "https://api.anthropic.com"
+        );
+    }
+
+    #[test]
+    fn test_apply_field_to_aliases_is_all_or_nothing() {
+        use cc_switch::cli::main::apply_field_to_aliases;
+
+        let mut storage = relay_storage();
+        let aliases = vec!["relay-a".to_string(), "missing".to_string()];
+        assert!(apply_field_to_aliases(&mut storage, &aliases, "url", "https://new").is_err());
+        assert_eq!(
+            storage.get_configuration("relay-a").unwrap().url,
+            "https://old.relay.example",
+            "no entry may change when any entry fails"
+        );
+    }
+
+    #[test]
+    fn test_batch_set_dry_run_and_apply() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let storage_path = storage_path(tmp.path());
+        std::fs::create_dir_all(storage_path.parent().unwrap()).expect("create .claude");
+        let initial = serde_json::to_string_pretty(&relay_storage()).expect("serialize");
+        std::fs::write(&storage_path, &initial).expect("write storage");
+
+        let run = |extra: &[&str]| {
+            cc_switch(tmp.path())
+                .args(["set", "--where", "url=https://old.relay.example"])
+                .args(["url", "https://new.relay.example"])
+                .args(extra)
+                .output()
+                .expect("Should run cc-switch")
+        };
+
+        // Dry run previews both matches and leaves storage untouched
+        let output = run(&["--dry-run"]);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        assert!(stdout.contains("2 configuration(s) match"), "got: {stdout}");
+        assert!(
+            stdout.contains("relay-a: url https://old.relay.example -> https://new.relay.example")
+        );
+        assert!(stdout.contains("relay-b:"));
+        assert!(!stdout.contains("direct:"));
+        assert_eq!(std::fs::read_to_string(&storage_path).unwrap(), initial);
+
+        // --yes applies without prompting
+        let output = run(&["--yes"]);
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let saved: ConfigStorage =
+            serde_json::from_str(&std::fs::read_to_string(&storage_path).unwrap()).unwrap();
+        assert_eq!(
+            saved.configurations["relay-a"].url,
+            "https://new.relay.example"
+        );
+        assert_eq!(
+            saved.configurations["relay-b"].url,
+            "https://new.relay.example"
+        );
+        assert_eq!(
+            saved.configurations["direct"].url,
+            "https://api.anthropic.com"
+        );
+    }
+
+    #[test]
+    fn test_unuse_persistent_cleans_settings_with_backup() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let settings_path = tmp.path().join(".claude").join("settings.json");
+        std::fs::create_dir_all(settings_path.parent().unwrap()).expect("create .claude");
+        let initial = r#"{"env":{"ANTHROPIC_BASE_URL":"https://relay.example","EDITOR":"vim"}}"#;
+        std::fs::write(&settings_path, initial).expect("write settings");
+
+        let output = cc_switch(tmp.path())
+            .args(["unuse", "--shell", "fish", "--persistent"])
+            .output()
+            .expect("Should run cc-switch");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "stderr: {stderr}");
+        assert!(
+            stdout.contains("set -e ANTHROPIC_BASE_URL"),
+            "got: {stdout}"
+        );
+        assert!(stdout.contains("set -e CC_SWITCH_CURRENT_ALIAS"));
+        assert!(
+            stderr.contains("settings.json: removed ANTHROPIC_BASE_URL"),
+            "got: {stderr}"
+        );
+
+        let saved: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&settings_path).unwrap()).unwrap();
+        assert!(saved["env"].get("ANTHROPIC_BASE_URL").is_none());
+        assert_eq!(saved["env"]["EDITOR"], "vim");
+        let backup = settings_path.with_extension("json.bak");
+        assert_eq!(std::fs::read_to_string(backup).unwrap(), initial);
+    }
+
+    #[test]
+    fn test_unuse_without_persistent_leaves_settings() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let output = cc_switch(tmp.path())
+            .args(["unuse", "--shell", "bash"])
+            .output()
+            .expect("Should run cc-switch");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "stderr: {stderr}");
+        assert!(stdout.starts_with("unset ANTHROPIC_"), "got: {stdout}");
+        assert!(stderr.contains("settings.json: unchanged"), "got: {stderr}");
+        assert!(!tmp.path().join(".claude").join("settings.json").exists());
+    }
+
+    #[test]
+    fn test_use_dash_without_history_errors() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let output = cc_switch(tmp.path())
+            .args(["use", "-"])
+            .output()
+            .expect("Should run cc-switch");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(!output.status.success());
+        assert!(
+            stderr.contains("No configuration has been used yet"),
+            "got: {stderr}"
+        );
+    }
+
+    #[test]
+    fn test_env_prints_statements_for_config_and_official() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+        };
+        assert!(
+            run(&[
+                "add",
+                "work",
+                "-t",
+                "sk-ant-api03-x",
+                "-u",
+                "https://api.example.com"
+            ])
+            .status
+            .success()
+        );
+
+        let output = run(&["env", "work", "--shell", "fish"]);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success());
+        assert!(stdout.contains("set -gx ANTHROPIC_BASE_URL 'https://api.example.com'\n"));
+        assert!(stdout.contains("set -gx CC_SWITCH_CURRENT_ALIAS 'work'\n"));
+
+        let output = run(&["env", "cc", "--shell", "bash"]);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success());
+        assert!(stdout.starts_with("unset ANTHROPIC_"), "got: {stdout}");
+        assert!(!stdout.contains("export"));
+    }
+
+    #[test]
+    fn test_force_color_keeps_ansi_codes_when_piped() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |extra: &[&str], no_color: bool| {
+            let mut command = cc_switch(tmp.path());
+            command
+                .env_remove("NO_COLOR")
+                .env_remove("CLICOLOR_FORCE")
+                .env_remove("CLICOLOR")
+                .args(["completion", "doctor", "fish"])
+                .args(extra);
+            if no_color {
+                command.env("NO_COLOR", "1");
+            }
+            let output = command.output().expect("Should run cc-switch");
+            String::from_utf8_lossy(&output.stdout).into_owned()
+        };
+
+        let plain = run(&[], false);
+        assert!(plain.contains("Completion file"), "got: {plain}");
+        assert!(!plain.contains('\x1b'), "piped output must not be colored");
+
+        assert!(run(&["--force-color"], false).contains("\x1b["));
+        assert!(
+            run(&["--force-color"], true).contains("\x1b["),
+            "the flag takes precedence over NO_COLOR"
+        );
+    }
+
+    #[test]
+    fn test_no_color_flag_beats_clicolor_force() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |extra: &[&str]| {
+            cc_switch(tmp.path())
+                .env_remove("NO_COLOR")
+                .env("CLICOLOR_FORCE", "1")
+                .args(["completion", "doctor", "fish"])
+                .args(extra)
+                .output()
+                .expect("Should run cc-switch")
+        };
+
+        assert!(String::from_utf8_lossy(&run(&[]).stdout).contains("\x1b["));
+        let plain = run(&["--no-color"]);
+        assert!(plain.status.success());
+        let stdout = String::from_utf8_lossy(&plain.stdout);
+        assert!(stdout.contains("Completion file"), "got: {stdout}");
+        assert!(!stdout.contains('\x1b'));
+        assert!(!run(&["--no-color", "--force-color"]).status.success());
+    }
+
+    #[test]
+    fn test_completion_doctor_json_report() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let output = cc_switch(tmp.path())
+            .args(["completion", "doctor", "fish", "--json"])
+            .output()
+            .expect("Should run cc-switch");
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        let report: serde_json::Value =
+            serde_json::from_slice(&output.stdout).expect("stdout should be JSON");
+        assert_eq!(report["schema_version"], 1);
+        assert_eq!(report["subject"], "fish completion setup");
+        let checks = report["checks"].as_array().expect("checks array");
+        assert_eq!(checks[0]["name"], "Completion file");
+        assert_eq!(checks[0]["passed"], false);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_use_official_does_not_leak_managed_variables() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let dump = tmp.path().join("child_env");
+        let mock = tmp.path().join("mock-claude");
+        std::fs::write(&mock, format!("#!/bin/sh\nenv > '{}'\n", dump.display()))
+            .expect("write mock");
+        std::fs::set_permissions(&mock, std::fs::Permissions::from_mode(0o755))
+            .expect("chmod mock");
+
+        let mut command = cc_switch(tmp.path());
+        command.env("CLAUDE_BINARY", &mock).args(["use", "cc"]);
+        for name in cc_switch::config::ANTHROPIC_ENV_VARS {
+            command.env(name, "leaked");
+        }
+        let output = command.output().expect("Should run cc-switch");
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        let child_env = std::fs::read_to_string(&dump).expect("mock should run");
+        for line in child_env.lines() {
+            if let Some((name, value)) = line.split_once('=') {
+                assert!(
+                    !(cc_switch::config::ANTHROPIC_ENV_VARS.contains(&name) && value == "leaked"),
+                    "{name} leaked into the official session"
+                );
+            }
+        }
+        assert!(child_env.contains("CC_SWITCH_CURRENT_ALIAS=official"));
+    }
+
+    #[test]
+    fn test_color_label_in_list_follows_color_policy() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str], no_color: bool| {
+            let mut command = cc_switch(tmp.path());
+            command
+                .env_remove("CLICOLOR_FORCE")
+                .env_remove("CLICOLOR")
+                .args(args);
+            if no_color {
+                command.env("NO_COLOR", "1");
+            } else {
+                command.env_remove("NO_COLOR");
+            }
+            command.output().expect("Should run cc-switch")
+        };
+
+        let invalid = run(
+            &[
+                "add",
+                "prod",
+                "sk-test",
+                "https://prod.example",
+                "--color",
+                "mauve",
+            ],
+            true,
+        );
+        assert!(!invalid.status.success());
+        let stderr = String::from_utf8_lossy(&invalid.stderr);
+        assert!(stderr.contains("Valid colors: black, red"), "got: {stderr}");
+
+        let added = run(
+            &[
+                "add",
+                "prod",
+                "sk-test",
+                "https://prod.example",
+                "--color",
+                "Red",
+            ],
+            true,
+        );
+        assert!(
+            added.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&added.stderr)
+        );
+
+        let colored = run(&["--force-color", "list", "--name"], false);
+        let stdout = String::from_utf8_lossy(&colored.stdout);
+        assert!(stdout.contains("\u{1b}[31m■"), "got: {stdout:?}");
+
+        let plain = run(&["list", "--name"], true);
+        assert_eq!(
+            String::from_utf8_lossy(&plain.stdout),
+            "prod: https://prod.example\n"
+        );
+
+        let json = run(&["list"], true);
+        assert!(String::from_utf8_lossy(&json.stdout).contains("\"color\": \"red\""));
+    }
+
+    #[test]
+    fn test_list_masks_tokens_unless_reveal() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let run = |args: &[&str]| {
+            let output = cc_switch(tmp.path())
+                .args(args)
+                .output()
+                .expect("Should run cc-switch");
+            String::from_utf8_lossy(&output.stdout).into_owned()
+        };
+        let token = "sk-relay-secret-token-0123456789";
+        run(&["add", "relay", "-t", token, "-u", "https://relay.example"]);
+
+        for args in [&["list"][..], &["list", "--plain"]] {
+            let stdout = run(args);
+            assert!(!stdout.contains(token), "{args:?} leaked: {stdout}");
+            assert!(stdout.contains("sk-relay-sec...23456789"), "got: {stdout}");
+        }
+        assert!(run(&["list", "--reveal"]).contains(token));
+        assert!(run(&["list", "--plain", "--reveal"]).contains(token));
+    }
+
+    #[test]
+    fn test_list_orphaned_reports_missing_launch_commands() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let run = || {
+            cc_switch(tmp.path())
+                .args(["list", "--orphaned"])
+                .output()
+                .unwrap()
+        };
+        let storage_path = storage_path(tmp.path());
+        std::fs::create_dir_all(storage_path.parent().unwrap()).unwrap();
+        let write = |command: &str| {
+            let storage = serde_json::json!({
+                "configurations": {
+                    "work": {
+                        "alias_name": "work",
+                        "token": "sk-test",
+                        "url": "https://work.example",
+                        "launch_profiles": {"tool": {"command": command}}
+                    }
+                },
+                "claude_settings_dir": null
+            });
+            std::fs::write(&storage_path, storage.to_string()).unwrap();
+        };
+
+        write("cc-switch-missing-tool");
+        let broken = run();
+        assert!(!broken.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&broken.stdout),
+            "work: launch profile 'tool' runs 'cc-switch-missing-tool', which is not found\n"
+        );
+
+        write(env!("CARGO_BIN_EXE_cc-switch"));
+        let clean = run();
+        assert!(clean.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&clean.stdout),
+            "No orphaned configurations\n"
+        );
+    }
+
+    #[test]
+    fn test_version_short_matches_version_flag() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            let output = cc_switch(tmp.path())
+                .args(args)
+                .output()
+                .expect("Should run cc-switch");
+            assert!(output.status.success());
+            String::from_utf8(output.stdout).expect("utf-8 stdout")
+        };
+
+        let short = run(&["version", "--short"]);
+        assert_eq!(short, format!("cc-switch {}\n", env!("CARGO_PKG_VERSION")));
+        assert_eq!(short, run(&["--version"]));
+
+        let full = run(&["version"]);
+        assert!(full.starts_with(&short), "got: {full}");
+        assert!(full.contains("commit:"), "got: {full}");
+        assert!(full.contains("configurations.json"), "got: {full}");
+    }
+
+    #[test]
+    fn test_version_json_reports_build_metadata() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let output = cc_switch(tmp.path())
+            .args(["version", "--json"])
+            .output()
+            .expect("Should run cc-switch");
+        assert!(output.status.success());
+
+        let report: serde_json::Value =
+            serde_json::from_slice(&output.stdout).expect("stdout should be JSON");
+        assert_eq!(report["schema_version"], 1);
+        assert_eq!(report["version"], env!("CARGO_PKG_VERSION"));
+        assert!(!report["commit"].as_str().unwrap().is_empty());
+        assert!(!report["target"].as_str().unwrap().is_empty());
+        assert!(report["features"].is_array());
+        let names: Vec<&str> = report["paths"]
+            .as_array()
+            .expect("paths should be listed")
+            .iter()
+            .map(|entry| entry["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["storage", "backup", "lock", "settings"]);
+        assert_eq!(
+            report["paths"][0]["path"],
+            storage_path(tmp.path()).display().to_string()
+        );
+    }
+
+    /// Prompter with a fixed answer that records the questions asked
+    struct ScriptedPrompter {
+        interactive: bool,
+        answer: bool,
+        asked: Vec<String>,
+    }
+
+    impl cc_switch::interactive::Prompter for ScriptedPrompter {
+        fn is_interactive(&self) -> bool {
+            self.interactive
+        }
+
+        fn confirm(&mut self, prompt: &str) -> anyhow::Result<bool> {
+            self.asked.push(prompt.to_string());
+            Ok(self.answer)
+        }
+
+        fn ask(&mut self, prompt: &str) -> anyhow::Result<String> {
+            self.asked.push(prompt.to_string());
+            Ok(String::new())
+        }
+    }
+
+    fn scripted(interactive: bool, answer: bool) -> ScriptedPrompter {
+        ScriptedPrompter {
+            interactive,
+            answer,
+            asked: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_use_missing_alias_creates_on_yes() {
+        use cc_switch::cli::main::ensure_alias_for_use;
+
+        let mut storage = relay_storage();
+        let mut prompter = scripted(true, true);
+        let mut created = Vec::new();
+        ensure_alias_for_use(&mut storage, "newrelay", &mut prompter, |alias, storage| {
+            created.push(alias.to_string());
+            storage.add_configuration(create_test_config(alias, "sk-new", "https://new.example"));
+            Ok(())
+        })
+        .expect("alias should exist after creation");
+
+        assert_eq!(created, vec!["newrelay"]);
+        assert_eq!(
+            prompter.asked,
+            vec!["Configuration 'newrelay' doesn't exist \u{2014} create it now? [y/N] "]
+        );
+        assert!(storage.get_configuration("newrelay").is_some());
+    }
+
+    #[test]
+    fn test_use_missing_alias_declined_keeps_error() {
+        use cc_switch::cli::main::ensure_alias_for_use;
+
+        let mut storage = relay_storage();
+        let mut prompter = scripted(true, false);
+        let err = ensure_alias_for_use(&mut storage, "newrelay", &mut prompter, |_, _| {
+            panic!("must not create when declined")
+        })
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Configuration 'newrelay' not found");
+        assert_eq!(prompter.asked.len(), 1);
+    }
+
+    #[test]
+    fn test_use_missing_alias_without_tty_does_not_prompt() {
+        use cc_switch::cli::main::ensure_alias_for_use;
+
+        let mut storage = relay_storage();
+        let mut prompter = scripted(false, true);
+        let err = ensure_alias_for_use(&mut storage, "newrelay", &mut prompter, |_, _| {
+            panic!("must not create without a terminal")
+        })
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Configuration 'newrelay' not found");
+        assert!(prompter.asked.is_empty());
+
+        // Existing and reserved aliases pass straight through
+        for alias in ["relay-a", "cc"] {
+            ensure_alias_for_use(&mut storage, alias, &mut prompter, |_, _| {
+                panic!("must not create existing aliases")
+            })
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_corrupt_storage_falls_back_to_backup_and_restores() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+        };
+        let storage_path = storage_path(tmp.path());
+        let backup_path = storage_path.with_extension("json.bak");
+
+        for alias in ["first", "second"] {
+            let output = run(&[
+                "add",
+                alias,
+                "-t",
+                "sk-ant-test",
+                "-u",
+                "https://a.example.com",
+            ]);
+            assert!(output.status.success());
+        }
+        // The backup holds the version before the last save
+        let backup = std::fs::read_to_string(&backup_path).unwrap();
+        assert!(backup.contains("first") && !backup.contains("second"));
+
+        // A save cut off half way
+        let full = std::fs::read_to_string(&storage_path).unwrap();
+        std::fs::write(&storage_path, &full[..full.len() / 2]).unwrap();
+
+        let output = run(&["list", "--plain"]);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "stderr: {stderr}");
+        assert!(stderr.contains("using the backup"), "got: {stderr}");
+        assert!(String::from_utf8_lossy(&output.stdout).contains("first"));
+
+        let output = run(&["restore-backup"]);
+        assert!(output.status.success());
+        assert_eq!(std::fs::read_to_string(&storage_path).unwrap(), backup);
+        let output = run(&["list", "--plain"]);
+        assert!(output.stderr.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_storage_is_private_to_the_owner() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+        };
+        let storage_path = storage_path(tmp.path());
+        let dir = storage_path.parent().unwrap().to_path_buf();
+        let backup_path = storage_path.with_extension("json.bak");
+        let mode =
+            |path: &std::path::Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+        for alias in ["first", "second"] {
+            let output = run(&[
+                "add",
+                alias,
+                "-t",
+                "sk-ant-test",
+                "-u",
+                "https://a.example.com",
+            ]);
+            assert!(output.status.success());
+        }
+        assert_eq!(mode(&storage_path), 0o600);
+        assert_eq!(mode(&backup_path), 0o600);
+        assert_eq!(mode(&dir), 0o700);
+
+        // A world-readable file is reported, and the next save restricts it
+        std::fs::set_permissions(&storage_path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        let output = run(&["list", "--plain"]);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("readable by other users"));
+        assert!(run(&["remove", "second"]).status.success());
+        assert_eq!(mode(&storage_path), 0o600);
+    }
+
+    #[test]
+    fn test_import_adds_files_skips_existing_and_reports_failures() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+        };
+        let providers = tmp.path().join("providers");
+        std::fs::create_dir(&providers).unwrap();
+        let settings = |url: &str| {
+            format!(
+                r#"{{"env": {{"ANTHROPIC_AUTH_TOKEN": "sk-test", "ANTHROPIC_BASE_URL": "{url}"}}}}"#
+            )
+        };
+        std::fs::write(
+            providers.join("alpha.json"),
+            settings("https://a.example.com"),
+        )
+        .unwrap();
+        std::fs::write(
+            providers.join("beta.jsonc"),
+            settings("https://b.example.com/"),
+        )
+        .unwrap();
+        std::fs::write(providers.join("broken.json"), "{ not json").unwrap();
+        std::fs::write(providers.join("notes.txt"), "ignored").unwrap();
+        let providers = providers.to_str().unwrap();
+
+        let output = run(&[
+            "add",
+            "alpha",
+            "-t",
+            "sk-ant-old",
+            "-u",
+            "https://old.example.com",
+        ]);
+        assert!(output.status.success());
+
+        // Dry run saves nothing
+        let output = run(&["import", providers, "--dry-run"]);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            stdout.contains("Would import 1, skipped 1 (already exist), failed 1"),
+            "got: {stdout}"
+        );
+        let list = String::from_utf8_lossy(&run(&["list", "--plain"]).stdout).into_owned();
+        assert!(!list.contains("beta"));
+
+        let output = run(&["import", providers]);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(!output.status.success());
+        assert!(
+            stdout.contains("Imported 1, skipped 1 (already exist), failed 1"),
+            "got: {stdout}"
+        );
+        assert!(stderr.contains("broken.json"), "got: {stderr}");
+        let list = String::from_utf8_lossy(&run(&["list", "--plain"]).stdout).into_owned();
+        assert!(list.contains("https://b.example.com") && list.contains("https://old.example.com"));
+
+        // --force replaces the existing alias
+        let output = run(&["import", providers, "--force"]);
+        assert!(String::from_utf8_lossy(&output.stdout).contains("Imported 2, skipped 0"));
+        let list = String::from_utf8_lossy(&run(&["list", "--plain"]).stdout).into_owned();
+        assert!(list.contains("https://a.example.com") && !list.contains("old.example.com"));
+    }
+
+    #[test]
+    fn test_test_command_reports_pass_and_fail() {
+        use std::io::{BufRead, BufReader, Write};
+        use std::net::TcpListener;
+
+        // Accepts 'sk-good' and rejects every other token; /quota reports
+        // 30 of 120 used in its headers
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(&stream);
+                let mut request = String::new();
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap_or(0) > 2 {
+                    request.push_str(&line.to_ascii_lowercase());
+                    line.clear();
+                }
+                let (status, quota) = if request.starts_with("get /quota ") {
+                    ("200 OK", "x-used: 30\r\nx-limit: 120\r\n")
+                } else if !request.starts_with("get /v1/models ") {
+                    ("404 Not Found", "")
+                } else if request.contains("authorization: bearer sk-good") {
+                    ("200 OK", "")
+                } else {
+                    ("401 Unauthorized", "")
+                };
+                let _ = write!(
+                    &stream,
+                    "HTTP/1.1 {status}\r\n{quota}content-length: 2\r\nconnection: close\r\n\r\n{{}}"
+                );
+            }
+        });
+
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .env("NO_COLOR", "1")
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+        };
+        assert!(
+            run(&["add", "good", "-t", "sk-good", "-u", &url])
+                .status
+                .success()
+        );
+        assert!(
+            run(&["add", "bad", "-t", "sk-bad", "-u", &url])
+                .status
+                .success()
+        );
+
+        let output = run(&["test", "good"]);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success(), "got: {stdout}");
+        assert!(stdout.contains("✓ good  HTTP 200"), "got: {stdout}");
+        assert!(!stdout.contains("quota:"), "got: {stdout}");
+
+        // A passing configuration with a quota probe also shows its quota
+        let storage_path = storage_path(tmp.path());
+        let mut storage: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&storage_path).unwrap()).unwrap();
+        storage["configurations"]["good"]["quota_probe"] = serde_json::json!({"url": format!("{url}/quota"), "used": "x-used", "limit": "x-limit"});
+        std::fs::write(&storage_path, storage.to_string()).unwrap();
+        let output = run(&["test", "good"]);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            stdout.contains("\n  quota: 25% used (30 / 120)"),
+            "got: {stdout}"
+        );
+
+        let output = run(&["test", "--all"]);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(!output.status.success());
+        assert!(stdout.contains("✗ bad   HTTP 401"), "got: {stdout}");
+        assert!(stdout.contains("token rejected"), "got: {stdout}");
+        assert!(String::from_utf8_lossy(&output.stderr).contains("1 of 2 configuration(s) failed"));
+
+        let output = run(&["test", "cc"]);
+        assert!(!output.status.success());
+    }
+
+    #[test]
+    fn test_current_json_reports_matched_alias() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str], env: &[[&str; 2]]| {
+            let mut command = cc_switch(tmp.path());
+            command.args(args);
+            for name in cc_switch::config::managed_env_var_names() {
+                command.env_remove(name);
+            }
+            command.envs(env.iter().map(|[name, value]| (name, value)));
+            command.output().expect("Should run cc-switch")
+        };
+        let token = "sk-ant-REDACTED";
+        assert!(
+            run(
+                &["add", "work", "-t", token, "-u", "https://work.example.com"],
+                &[]
+            )
+            .status
+            .success()
+        );
+
+        let report = |env: &[[&str; 2]]| {
+            let output = run(&["current", "--json"], env);
+            assert!(output.status.success());
+            serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+        };
+        let active = report(&[
+            ["ANTHROPIC_AUTH_TOKEN", token],
+            ["ANTHROPIC_BASE_URL", "https://work.example.com"],
+        ]);
+        assert_eq!(active["state"], "alias");
+        assert_eq!(active["active_alias"], "work");
+        assert_eq!(active["matched_by"], "token+url");
+        assert_eq!(active["token"], "sk-ant-api03...stuvwxyz");
+
+        assert_eq!(report(&[])["state"], "official");
+    }
+
+    #[test]
+    fn test_extra_env_from_flags_and_imported_files() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+        };
+        let stored = |alias: &str| {
+            let output = run(&["list", "--reveal"]);
+            let list: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+            listed_config(&list, alias)["extra_env"].clone()
+        };
+
+        let output = run(&[
+            "add",
+            "relay",
+            "-t",
+            "sk-test",
+            "-u",
+            "https://relay.example.com",
+            "--env",
+            "ANTHROPIC_CUSTOM_HEADERS=X-Org: 1",
+            "--env",
+            "my_proxy=http://proxy:3128",
+        ]);
+        assert!(output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("'my_proxy' is not"));
+        assert_eq!(
+            stored("relay"),
+            serde_json::json!({
+                "ANTHROPIC_CUSTOM_HEADERS": "X-Org: 1",
+                "my_proxy": "http://proxy:3128"
+            })
+        );
+
+        // Built-in variables are rejected, not overridden
+        let output = run(&["edit", "relay", "--env", "ANTHROPIC_MODEL=opus"]);
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("dedicated option"));
+
+        assert!(
+            run(&["edit", "relay", "--env", "my_proxy="])
+                .status
+                .success()
+        );
+        assert_eq!(
+            stored("relay"),
+            serde_json::json!({"ANTHROPIC_CUSTOM_HEADERS": "X-Org: 1"})
+        );
+
+        // Unrecognized keys of an imported env section are kept
+        let file = tmp.path().join("imported.json");
+        std::fs::write(
+            &file,
+            r#"{"env": {"ANTHROPIC_AUTH_TOKEN": "sk-test", "ANTHROPIC_BASE_URL": "https://x.example.com", "HTTPS_PROXY": "http://proxy", "NODE_MAX": 4}}"#,
+        )
+        .unwrap();
+        let output = run(&["add", "--from-file", file.to_str().unwrap()]);
+        assert!(output.status.success());
+        assert_eq!(
+            stored("imported"),
+            serde_json::json!({"HTTPS_PROXY": "http://proxy", "NODE_MAX": "4"})
+        );
+    }
+
+    #[test]
+    fn test_from_file_warns_about_ignored_env_keys() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+        };
+        let file = tmp.path().join("other.json");
+        std::fs::write(
+            &file,
+            r#"{"env": {
+                "ANTHROPIC_AUTH_TOKEN": "sk-test",
+                "ANTHROPIC_BASE_URL": "https://x.example.com",
+                "ANTHROPIC_MAX_THINKING_TOKENS": "8192",
+                "CC_SWITCH_CURRENT_ALIAS": "other",
+                "HTTPS_PROXY": "http://proxy",
+                "NESTED": {"a": 1}
+            }}"#,
+        )
+        .unwrap();
+
+        let output = run(&["add", "--from-file", file.to_str().unwrap()]);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "got: {stderr}");
+        assert!(
+            stderr.contains(
+                "Warning: ignored keys from env section: CC_SWITCH_CURRENT_ALIAS, NESTED"
+            ),
+            "got: {stderr}"
+        );
+
+        let list: serde_json::Value =
+            serde_json::from_slice(&run(&["list", "--reveal"]).stdout).unwrap();
+        assert_eq!(
+            listed_config(&list, "other")["url"],
+            "https://x.example.com"
+        );
+        // Numbers written as strings are read as numbers
+        assert_eq!(listed_config(&list, "other")["max_thinking_tokens"], 8192);
+        assert_eq!(
+            listed_config(&list, "other")["extra_env"],
+            serde_json::json!({"HTTPS_PROXY": "http://proxy"})
+        );
+
+        // Nothing to report for a file that is read in full
+        let file = tmp.path().join("clean.json");
+        std::fs::write(
+            &file,
+            r#"{"env": {"ANTHROPIC_AUTH_TOKEN": "sk-test", "ANTHROPIC_BASE_URL": "https://x.example.com"}}"#,
+        )
+        .unwrap();
+        let output = run(&["add", "--from-file", file.to_str().unwrap()]);
+        assert!(output.status.success());
+        assert!(!String::from_utf8_lossy(&output.stderr).contains("ignored keys"));
+    }
+
+    #[test]
+    fn test_from_file_reads_serialized_configurations() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+        };
+        let token = "sk-ant-REDACTED";
+
+        // A single configuration, with fields `add` has no flags for
+        let file = tmp.path().join("single.json");
+        std::fs::write(
+            &file,
+            serde_json::json!({
+                "alias_name": "elsewhere",
+                "token": token,
+                "url": "https://x.example.com",
+                "model": "opus",
+                "tags": ["prod"],
+                "url_variants": {"eu": "https://eu.example.com"},
+                "extra_env": {"HTTPS_PROXY": "http://proxy"}
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let output = run(&["add", "--from-file", file.to_str().unwrap()]);
+        assert!(
+            output.status.success(),
+            "got: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        // `list --reveal` output holding one configuration
+        let listed = run(&["list", "--reveal"]);
+        let file = tmp.path().join("copy.json");
+        std::fs::write(&file, &listed.stdout).unwrap();
+        let output = run(&["add", "--from-file", file.to_str().unwrap()]);
+        assert!(
+            output.status.success(),
+            "got: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        let list: serde_json::Value =
+            serde_json::from_slice(&run(&["list", "--reveal"]).stdout).unwrap();
+        for alias in ["single", "copy"] {
+            assert_eq!(listed_config(&list, alias)["alias_name"], alias);
+            assert_eq!(listed_config(&list, alias)["token"], token);
+            assert_eq!(listed_config(&list, alias)["model"], "opus");
+            assert_eq!(
+                listed_config(&list, alias)["tags"],
+                serde_json::json!(["prod"])
+            );
+            assert_eq!(
+                listed_config(&list, alias)["url_variants"]["eu"],
+                "https://eu.example.com"
+            );
+            assert_eq!(
+                listed_config(&list, alias)["extra_env"]["HTTPS_PROXY"],
+                "http://proxy"
+            );
+        }
+
+        // Masked tokens and several configurations are refused
+        let masked = run(&["list"]);
+        let file = tmp.path().join("masked.json");
+        let masked: serde_json::Value = serde_json::from_slice(&masked.stdout).unwrap();
+        std::fs::write(
+            &file,
+            serde_json::json!({"single": listed_config(&masked, "single")}).to_string(),
+        )
+        .unwrap();
+        let output = run(&["add", "--from-file", file.to_str().unwrap()]);
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("is masked"));
+
+        let file = tmp.path().join("both.json");
+        std::fs::write(&file, &run(&["list", "--reveal"]).stdout).unwrap();
+        let output = run(&["add", "--from-file", file.to_str().unwrap()]);
+        assert!(!output.status.success());
+        assert!(
+            String::from_utf8_lossy(&output.stderr)
+                .contains("holds 2 configurations (copy, single)")
+        );
+    }
+
+    #[test]
+    fn test_from_file_type_errors_name_the_field() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let add_error = |content: &str| {
+            let file = tmp.path().join("config.json");
+            std::fs::write(&file, content).unwrap();
+            let output = cc_switch(tmp.path())
+                .args(["add", "--from-file", file.to_str().unwrap()])
+                .output()
+                .expect("Should run cc-switch");
+            assert!(!output.status.success());
+            String::from_utf8_lossy(&output.stderr).to_string()
+        };
+
+        let stderr = add_error(
+            r#"{"env": {"ANTHROPIC_AUTH_TOKEN": 12345, "ANTHROPIC_BASE_URL": "https://x.example.com"}}"#,
+        );
+        assert!(
+            stderr.contains("ANTHROPIC_AUTH_TOKEN in file")
+                && stderr.contains("must be a string, found a number"),
+            "got: {stderr}"
+        );
+
+        let stderr = add_error(
+            r#"{"env": {"ANTHROPIC_AUTH_TOKEN": "sk-test", "ANTHROPIC_BASE_URL": "https://x.example.com", "API_TIMEOUT_MS": "soon"}}"#,
+        );
+        assert!(
+            stderr.contains("API_TIMEOUT_MS in file") && stderr.contains("found \"soon\""),
+            "got: {stderr}"
+        );
+
+        let stderr = add_error("{\n  \"token\": 12345,\n  \"url\": \"https://x.example.com\"\n}");
+        assert!(
+            stderr.contains("Invalid configuration in file")
+                && stderr.contains("invalid type: integer `12345`, expected a string")
+                && stderr.contains("line 2"),
+            "got: {stderr}"
+        );
+
+        // Nothing is stored after a failed import
+        let config_path = storage_path(tmp.path());
+        assert!(!config_path.exists());
+    }
+
+    #[test]
+    fn test_list_filters_and_groups_by_tag() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+        };
+        for args in [
+            [
+                "add",
+                "work",
+                "-t",
+                "sk-work",
+                "-u",
+                "https://work.example.com",
+                "--tag",
+                "work",
+            ],
+            [
+                "add",
+                "home",
+                "-t",
+                "sk-home",
+                "-u",
+                "https://home.example.com",
+                "--tag",
+                "personal",
+            ],
+            [
+                "add",
+                "lab",
+                "-t",
+                "sk-lab",
+                "-u",
+                "https://lab.example.com",
+                "--tag",
+                "Work",
+            ],
+        ] {
+            assert!(run(&args).status.success());
+        }
+
+        let list: serde_json::Value =
+            serde_json::from_slice(&run(&["list", "--tag", "work"]).stdout).unwrap();
+        let aliases: Vec<&str> = list
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|config| config["alias_name"].as_str().unwrap())
+            .collect();
+        assert_eq!(aliases, ["lab", "work"]);
+
+        let output = run(&["list", "--plain", "--group-by-tag"]);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success());
+        let headings: Vec<&str> = stdout.lines().filter(|l| !l.starts_with(' ')).collect();
+        assert_eq!(headings, ["Work (1):", "personal (1):", "work (1):"]);
+
+        // --group-by-tag needs --plain
+        assert!(!run(&["list", "--group-by-tag"]).status.success());
+
+        for args in [&["list", "--tag", "nope"][..], &["--tag", "nope"][..]] {
+            let output = run(args);
+            assert!(!output.status.success());
+            assert!(
+                String::from_utf8_lossy(&output.stderr)
+                    .contains("no configurations match tag 'nope'"),
+                "{args:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_alias_command_prints_per_config_functions() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+        };
+        assert!(
+            run(&[
+                "add",
+                "work",
+                "-t",
+                "sk-work",
+                "-u",
+                "https://w.example.com"
+            ])
+            .status
+            .success()
+        );
+
+        let output = run(&["alias", "bash"]);
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "alias cs='cc-switch'\nalias cx='cc-switch codex'\n"
+        );
+
+        let output = run(&["alias", "bash", "--per-config", "--prefix", "c_"]);
+        assert!(output.status.success());
+        assert!(
+            String::from_utf8_lossy(&output.stdout)
+                .ends_with("c_work() { cc-switch use work \"$@\"; }\n")
+        );
+
+        // --prefix only applies to per-configuration functions
+        assert!(!run(&["alias", "bash", "--prefix", "c_"]).status.success());
+    }
+
+    #[test]
+    fn test_list_aliases_prints_one_alias_per_line() {
+        // The zsh and bash completion helpers split this output on newlines
+
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+        };
+        for alias in ["work", "relay.eu"] {
+            assert!(
+                run(&["add", alias, "-t", "sk-test", "-u", "https://x.example.com"])
+                    .status
+                    .success()
+            );
+        }
+
+        let output = run(&["--list-aliases"]);
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "cc\nofficial\nrelay.eu\nwork\n"
+        );
+        let output = run(&["--list-stored-aliases"]);
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "relay.eu\nwork\n");
+    }
+
+    #[test]
+    fn test_output_json_prints_one_document_per_command() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+        };
+        let json = |output: &std::process::Output| -> serde_json::Value {
+            serde_json::from_slice(&output.stdout)
+                .unwrap_or_else(|e| panic!("{e}: {}", String::from_utf8_lossy(&output.stdout)))
+        };
+        let token = "sk-ant-REDACTED";
+
+        let output = run(&[
+            "--format",
+            "json",
+            "add",
+            "work",
+            "-t",
+            token,
+            "-u",
+            "https://w.example.com",
+        ]);
+        assert!(output.status.success());
+        let added = json(&output);
+        assert_eq!(added["alias_name"], "work");
+        assert_eq!(added["token"], "sk-ant-api03...cdefghij");
+        assert!(String::from_utf8_lossy(&output.stderr).contains("added successfully"));
+
+        let output = run(&[
+            "add",
+            "home",
+            "-t",
+            token,
+            "-u",
+            "https://h.example.com",
+            "--reveal",
+            "--format",
+            "json",
+        ]);
+        assert_eq!(json(&output)["token"], token);
+
+        // Refusing to overwrite is an error, not an empty success
+        let output = run(&[
+            "--format",
+            "json",
+            "add",
+            "work",
+            "-t",
+            token,
+            "-u",
+            "https://w.example.com",
+        ]);
+        assert!(!output.status.success());
+        assert!(output.stdout.is_empty());
+
+        let listed = json(&run(&["list", "--format", "json"]));
+        assert_eq!(listed.as_array().unwrap().len(), 2);
+        assert!(
+            !run(&["list", "--plain", "--format", "json"])
+                .status
+                .success()
+        );
+
+        let current = json(&run(&["current", "--format", "json"]));
+        assert!(current["schema_version"].is_number());
+
+        let output = run(&["remove", "work", "nope", "--format", "json"]);
+        assert!(output.status.success());
+        assert_eq!(
+            json(&output),
+            serde_json::json!({"removed": ["work"], "not_found": ["nope"]})
+        );
+
+        // Text mode is unchanged
+        let output = run(&["remove", "home"]);
+        assert!(
+            String::from_utf8_lossy(&output.stdout)
+                .contains("Configuration 'home' removed successfully")
+        );
+    }
+
+    #[test]
+    fn test_exit_codes_tell_failures_apart() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+                .status
+                .code()
+        };
+        let add = ["add", "work", "-t", "sk-one", "-u", "https://one.example"];
+
+        assert_eq!(run(&add), Some(0));
+        assert_eq!(run(&add), Some(3), "existing alias without --force");
+        assert_eq!(run(&["add", "b", "-t", "sk", "-u", "ftp://x"]), Some(4));
+        assert_eq!(run(&["add", "a b", "-t", "sk", "-u", "https://x"]), Some(4));
+        assert_eq!(run(&["export", "nope"]), Some(2));
+        assert_eq!(run(&["rename", "nope", "other"]), Some(2));
+        assert_eq!(run(&["remove", "cc"]), Some(4));
+        assert_eq!(run(&["--store", "disk"]), Some(4));
+        assert_eq!(run(&["no-such-command"]), Some(4));
+        assert_eq!(run(&["--help"]), Some(0));
+
+        assert_eq!(
+            run(&["add", "home", "-t", "sk", "-u", "https://x"]),
+            Some(0)
+        );
+        assert_eq!(run(&["rename", "work", "home"]), Some(3));
+        // A partial miss still succeeds unless --strict is given
+        assert_eq!(run(&["remove", "nope", "--strict"]), Some(2));
+        assert_eq!(run(&["remove", "work", "nope"]), Some(0));
+        assert_eq!(run(&["remove", "home", "gone", "--strict"]), Some(2));
+        assert_eq!(run(&["remove", "home"]), Some(2), "already removed");
+    }
+
+    #[test]
+    fn test_confirm_before_launch_preference_and_use_flag() {
+        use std::process::Stdio;
+
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .stdin(Stdio::null())
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+        };
+        assert!(
+            run(&["add", "work", "-t", "sk-one", "-u", "https://one.example"])
+                .status
+                .success()
+        );
+
+        // Without a terminal --confirm refuses instead of launching
+        let output = run(&["use", "work", "--confirm"]);
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("--confirm needs a terminal"));
+        assert!(!run(&["use", "work", "--confirm", "--yes"]).status.success());
+
+        assert!(
+            run(&["config", "set", "confirm-launch", "on"])
+                .status
+                .success()
+        );
+        let storage: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(storage_path(tmp.path())).unwrap())
+                .unwrap();
+        assert_eq!(storage["preferences"]["confirm_before_launch"], true);
+        assert!(
+            !run(&["config", "set", "confirm-launch", "maybe"])
+                .status
+                .success()
+        );
+    }
+
+    #[test]
+    fn test_config_set_settings_dir() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+        };
+        let stored_dir = || -> serde_json::Value {
+            let storage: serde_json::Value =
+                serde_json::from_str(&std::fs::read_to_string(storage_path(tmp.path())).unwrap())
+                    .unwrap();
+            storage["claude"]["settings_dir"].clone()
+        };
+
+        let output = run(&["config", "set", "settings-dir", "alt-claude"]);
+        assert!(output.status.success());
+        let expected = tmp.path().join("alt-claude").join("settings.json");
+        assert!(
+            String::from_utf8_lossy(&output.stdout).contains(&*expected.to_string_lossy()),
+            "got: {}",
+            String::from_utf8_lossy(&output.stdout)
+        );
+        assert_eq!(stored_dir(), "alt-claude");
+
+        assert!(run(&["config", "set", "settings-dir", ""]).status.success());
+        assert!(stored_dir().is_null());
+    }
+
+    #[test]
+    fn test_mode_flag_selects_where_use_writes() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let mock = tmp.path().join("mock-claude");
+        std::fs::write(&mock, "#!/bin/sh\nexit 0\n").expect("write mock");
+        std::fs::set_permissions(&mock, std::fs::Permissions::from_mode(0o755))
+            .expect("chmod mock");
+        let run = |args: &[&str]| {
+            let mut command = cc_switch(tmp.path());
+            command.env("CLAUDE_BINARY", &mock).args(args);
+            command.output().expect("Should run cc-switch")
+        };
+        let settings_path = tmp.path().join(".claude").join("settings.json");
+        let settings_env = || {
+            let settings: serde_json::Value =
+                serde_json::from_str(&std::fs::read_to_string(&settings_path).unwrap()).unwrap();
+            settings["env"].clone()
+        };
+
+        assert!(
+            run(&["add", "work", "sk-work", "https://work.example"])
+                .status
+                .success()
+        );
+        std::fs::create_dir_all(settings_path.parent().unwrap()).unwrap();
+        std::fs::write(&settings_path, "{\n  \"theme\": \"dark\"\n}\n").unwrap();
+
+        // Env mode (the default) hands the configuration to the child only
+        let output = run(&["use", "work"]);
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        assert_eq!(
+            std::fs::read_to_string(&settings_path).unwrap(),
+            "{\n  \"theme\": \"dark\"\n}\n"
+        );
+
+        let output = run(&["use", "work", "--mode", "settings"]);
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let env = settings_env();
+        assert_eq!(env["ANTHROPIC_AUTH_TOKEN"], "sk-work");
+        assert_eq!(env["ANTHROPIC_BASE_URL"], "https://work.example");
+
+        assert!(run(&["use", "cc", "--mode", "settings"]).status.success());
+        let env = settings_env();
+        assert!(env.get("ANTHROPIC_AUTH_TOKEN").is_none());
+        assert!(env.get("ANTHROPIC_BASE_URL").is_none());
+
+        // The persistent default applies when --mode is absent
+        assert!(
+            run(&["config", "set", "storage-mode", "settings"])
+                .status
+                .success()
+        );
+        assert!(run(&["use", "work"]).status.success());
+        assert_eq!(settings_env()["ANTHROPIC_AUTH_TOKEN"], "sk-work");
+
+        let misplaced = run(&["list", "--mode", "env"]);
+        assert_eq!(misplaced.status.code(), Some(4));
+        assert_eq!(
+            run(&["use", "work", "--mode", "bogus"]).status.code(),
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn test_use_official_cleans_stale_settings() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let mock = tmp.path().join("mock-claude");
+        std::fs::write(&mock, "#!/bin/sh\nexit 0\n").expect("write mock");
+        std::fs::set_permissions(&mock, std::fs::Permissions::from_mode(0o755))
+            .expect("chmod mock");
+        let settings_dir = tmp.path().join("claude-settings");
+        std::fs::create_dir_all(&settings_dir).unwrap();
+        let settings_path = settings_dir.join("settings.json");
+        std::fs::write(
+            &settings_path,
+            serde_json::json!({
+                "model": "opus",
+                "env": {
+                    "ANTHROPIC_AUTH_TOKEN": "sk-stale",
+                    "ANTHROPIC_BASE_URL": "https://stale.example",
+                    "HTTPS_PROXY": "http://proxy.local"
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .env("CLAUDE_BINARY", &mock)
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+        };
+
+        let dir = settings_dir.to_string_lossy();
+        assert!(
+            run(&["config", "set", "settings-dir", &dir])
+                .status
+                .success()
+        );
+        let output = run(&["use", "cc", "--clean-settings"]);
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            stdout.contains("Removed from settings.json: ANTHROPIC_AUTH_TOKEN, ANTHROPIC_BASE_URL"),
+            "stdout: {stdout}"
+        );
+
+        let settings: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&settings_path).unwrap()).unwrap();
+        assert_eq!(settings["model"], "opus");
+        assert_eq!(
+            settings["env"],
+            serde_json::json!({ "HTTPS_PROXY": "http://proxy.local" })
+        );
+    }
+
+    #[test]
+    fn test_use_warns_about_inherited_claude_variables() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let dump = tmp.path().join("child_env");
+        let mock = tmp.path().join("mock-claude");
+        std::fs::write(&mock, format!("#!/bin/sh\nenv > '{}'\n", dump.display()))
+            .expect("write mock");
+        std::fs::set_permissions(&mock, std::fs::Permissions::from_mode(0o755))
+            .expect("chmod mock");
+        let run = |args: &[&str]| {
+            let mut command = cc_switch(tmp.path());
+            command.env("CLAUDE_BINARY", &mock).args(args);
+            for (key, _) in std::env::vars_os() {
+                let key = key.to_string_lossy();
+                if key.starts_with("ANTHROPIC_") || key.starts_with("CLAUDE_CODE_") {
+                    command.env_remove(key.as_ref());
+                }
+            }
+            command
+                .env("ANTHROPIC_BASE_URL", "https://shell.example")
+                .env("ANTHROPIC_MODEL", "shell-model");
+            command.output().expect("Should run cc-switch")
+        };
+
+        assert!(
+            run(&["add", "work", "sk-work", "https://work.example"])
+                .status
+                .success()
+        );
+
+        let output = run(&["use", "work"]);
+        assert!(output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains("not set by 'work'") && stderr.contains("ANTHROPIC_MODEL=shell-model"),
+            "stderr: {stderr}"
+        );
+        assert!(!stderr.contains("ANTHROPIC_BASE_URL="), "stderr: {stderr}");
+        let child_env = std::fs::read_to_string(&dump).expect("mock should run");
+        assert!(child_env.contains("ANTHROPIC_MODEL=shell-model"));
+        assert!(child_env.contains("ANTHROPIC_BASE_URL=https://work.example"));
+
+        let output = run(&["use", "work", "--clear-env"]);
+        assert!(output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains("Unset for this launch: ANTHROPIC_MODEL"),
+            "stderr: {stderr}"
+        );
+        let child_env = std::fs::read_to_string(&dump).expect("mock should run");
+        assert!(!child_env.contains("ANTHROPIC_MODEL="));
+        assert!(child_env.contains("ANTHROPIC_BASE_URL=https://work.example"));
+    }
+
+    #[test]
+    fn test_doctor_fails_on_broken_setup() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let mock = tmp.path().join("mock-claude");
+        std::fs::write(&mock, "#!/bin/sh\necho '9.9.9 (Claude)'\n").expect("write mock");
+        std::fs::set_permissions(&mock, std::fs::Permissions::from_mode(0o755))
+            .expect("chmod mock");
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .env("CLAUDE_BINARY", &mock)
+                .env("TERM", "xterm")
+                .env_remove("NO_COLOR")
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+        };
+
+        assert!(
+            run(&["add", "work", "sk-work", "https://work.example"])
+                .status
+                .success()
+        );
+        let healthy = run(&["doctor", "--json"]);
+        assert!(
+            healthy.status.success(),
+            "stdout: {}",
+            String::from_utf8_lossy(&healthy.stdout)
+        );
+        let report: serde_json::Value = serde_json::from_slice(&healthy.stdout).unwrap();
+        assert_eq!(
+            report["checks"][0]["detail"],
+            format!("{} (9.9.9 (Claude))", mock.display())
+        );
+        assert_eq!(report["paths"][0]["name"], "storage");
+        assert_eq!(report["paths"][0]["exists"], true);
+        assert_eq!(report["paths"][2]["name"], "lock");
+        assert_eq!(
+            report["paths"][2]["path"],
+            format!("{}.lock", storage_path(tmp.path()).display())
+        );
+
+        std::fs::write(storage_path(tmp.path()), "{ broken").unwrap();
+        let broken = run(&["doctor"]);
+        assert_eq!(broken.status.code(), Some(1));
+        let stdout = String::from_utf8_lossy(&broken.stdout);
+        assert!(
+            stdout.contains("✗ Configuration storage"),
+            "stdout: {stdout}"
+        );
+        assert!(
+            stdout.contains("fix: cc-switch restore-backup"),
+            "stdout: {stdout}"
+        );
+    }
+
+    #[test]
+    fn test_claude_path_setting_and_override() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let dump = tmp.path().join("launched_by");
+        let write_mock = |name: &str| {
+            let mock = tmp.path().join(name);
+            std::fs::write(
+                &mock,
+                format!("#!/bin/sh\necho {name} > '{}'\n", dump.display()),
+            )
+            .expect("write mock");
+            std::fs::set_permissions(&mock, std::fs::Permissions::from_mode(0o755))
+                .expect("chmod mock");
+            mock
+        };
+        let stored = write_mock("stored-claude");
+        let overridden = write_mock("override-claude");
+        let run = |args: &[&str], env_override: Option<&std::path::Path>| {
+            let mut command = cc_switch(tmp.path());
+            command.args(args);
+            match env_override {
+                Some(path) => command.env("CC_SWITCH_CLAUDE_BIN", path),
+                None => command.env_remove("CC_SWITCH_CLAUDE_BIN"),
+            };
+            command.output().expect("Should run cc-switch")
+        };
+        let launched_by = || std::fs::read_to_string(&dump).unwrap().trim().to_string();
+
+        let missing = run(
+            &["config", "set", "claude-path", "/nonexistent/claude"],
+            None,
+        );
+        assert_eq!(missing.status.code(), Some(4));
+
+        let set = run(
+            &["config", "set", "claude-path", &stored.to_string_lossy()],
+            None,
+        );
+        assert!(set.status.success());
+        assert!(String::from_utf8_lossy(&set.stdout).contains("stored-claude"));
+
+        assert!(run(&["use", "cc"], None).status.success());
+        assert_eq!(launched_by(), "stored-claude");
+        assert!(run(&["use", "cc"], Some(&overridden)).status.success());
+        assert_eq!(launched_by(), "override-claude");
+
+        let broken = run(
+            &["use", "cc"],
+            Some(std::path::Path::new("/nonexistent/claude")),
+        );
+        assert!(!broken.status.success());
+        let stderr = String::from_utf8_lossy(&broken.stderr);
+        assert!(
+            stderr.contains("'/nonexistent/claude' (from CC_SWITCH_CLAUDE_BIN)")
+                && stderr.contains("config set claude-path"),
+            "stderr: {stderr}"
+        );
+    }
+
+    #[test]
+    fn test_use_no_launch_switches_without_claude() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            let mut command = cc_switch(tmp.path());
+            command
+                .env("CLAUDE_BINARY", "/nonexistent/claude")
+                .args(args);
+            command.output().expect("Should run cc-switch")
+        };
+        let settings_path = tmp.path().join(".claude").join("settings.json");
+        let token_in_settings = || {
+            let settings: serde_json::Value =
+                serde_json::from_str(&std::fs::read_to_string(&settings_path).unwrap()).unwrap();
+            settings["env"]["ANTHROPIC_AUTH_TOKEN"].clone()
+        };
+
+        assert!(
+            run(&["add", "work", "sk-work", "https://work.example"])
+                .status
+                .success()
+        );
+        // Without --no-launch the missing binary is an error
+        assert!(!run(&["use", "work"]).status.success());
+
+        let output = run(&["use", "work", "--no-launch", "--mode", "settings"]);
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            stdout.contains("Switched to configuration 'work'"),
+            "{stdout}"
+        );
+        assert!(stdout.contains("Not launching Claude"), "{stdout}");
+        assert_eq!(token_in_settings(), "sk-work");
+        assert!(run(&["use", "-", "--no-launch"]).status.success());
+
+        let env_mode = run(&["use", "work", "--no-launch"]);
+        assert!(String::from_utf8_lossy(&env_mode.stdout).contains("cc-switch env work"));
+
+        assert!(
+            run(&["use", "cc", "--no-launch", "--mode", "settings"])
+                .status
+                .success()
+        );
+        assert!(token_in_settings().is_null());
+
+        let conflicting = run(&["use", "work", "--no-launch", "--continue"]);
+        assert_eq!(conflicting.status.code(), Some(4));
+    }
+
+    #[test]
+    fn test_settings_follow_claude_config_dir_not_the_storage_dir() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let claude_dir = tmp.path().join("claude-config");
+        let storage_dir = tmp.path().join("storage");
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .env("CLAUDE_CONFIG_DIR", &claude_dir)
+                .env("CC_SWITCH_CONFIG_DIR", &storage_dir)
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+        };
+
+        assert!(
+            run(&["add", "work", "sk-work", "https://work.example"])
+                .status
+                .success()
+        );
+        assert!(
+            run(&["use", "work", "--no-launch", "--mode", "settings"])
+                .status
+                .success()
+        );
+        let settings = std::fs::read_to_string(claude_dir.join("settings.json"))
+            .expect("settings.json goes where Claude reads it");
+        assert!(settings.contains("sk-work"));
+        assert!(storage_dir.join("cc_auto_switch_setting.json").exists());
+        assert!(!storage_dir.join("settings.json").exists());
+        assert!(!tmp.path().join(".claude").join("settings.json").exists());
+    }
+
+    #[test]
+    fn test_token_warnings_follow_the_provider() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+        };
+        let stderr = |output: &std::process::Output| {
+            assert!(
+                output.status.success(),
+                "stderr: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+            String::from_utf8_lossy(&output.stderr).into_owned()
+        };
+        let anthropic_token = "sk-ant-REDACTED";
+
+        // A gateway proxying the official API may take an official token
+        let relay = stderr(&run(&[
+            "add",
+            "relay",
+            anthropic_token,
+            "https://relay.example/anthropic",
+        ]));
+        assert!(!relay.contains("Warning"), "{relay}");
+
+        let official = stderr(&run(&[
+            "add",
+            "official-key",
+            "gw-0123456789abcdef",
+            "https://api.anthropic.com",
+        ]));
+        assert!(
+            official.contains("Anthropic tokens start with 'sk-ant-'"),
+            "{official}"
+        );
+        assert!(official.contains("detected from the URL"), "{official}");
+
+        let router = stderr(&run(&[
+            "add",
+            "router",
+            anthropic_token,
+            "https://llm.example",
+            "--provider",
+            "openrouter",
+        ]));
+        assert!(router.contains("'sk-or-'"), "{router}");
+        assert!(!router.contains("detected from the URL"), "{router}");
+        let storage = std::fs::read_to_string(storage_path(tmp.path())).unwrap();
+        assert!(storage.contains(r#""provider": "openrouter""#), "{storage}");
+
+        let pasted = stderr(&run(&["edit", "relay", "--token", "sk-ant-api03\n"]));
+        assert!(pasted.contains("line break"), "{pasted}");
+        assert!(pasted.contains("truncated"), "{pasted}");
+
+        let cleared = stderr(&run(&["edit", "router", "--unset", "provider"]));
+        assert!(!cleared.contains("Warning"), "{cleared}");
+    }
+
+    #[test]
+    fn test_list_sorts_and_prints_aligned_table() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            let output = cc_switch(tmp.path())
+                .args(args)
+                .output()
+                .expect("Should run cc-switch");
+            assert!(
+                output.status.success(),
+                "stderr: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+            String::from_utf8_lossy(&output.stdout).into_owned()
+        };
+        for (alias, url, model) in [
+            ("zeta", "https://a.example", "opus"),
+            ("工作", "https://c.example", "haiku"),
+            ("alpha", "https://b.example", "sonnet"),
+        ] {
+            run(&[
+                "add",
+                alias,
+                "sk-test-0123456789abcdefghij",
+                url,
+                "--model",
+                model,
+            ]);
+        }
+        let aliases = |args: &[&str]| -> Vec<String> {
+            let list: serde_json::Value = serde_json::from_str(&run(args)).unwrap();
+            list.as_array()
+                .unwrap()
+                .iter()
+                .map(|config| config["alias_name"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        assert_eq!(aliases(&["list"]), ["alpha", "zeta", "工作"]);
+        assert_eq!(aliases(&["list", "--reverse"]), ["工作", "zeta", "alpha"]);
+        assert_eq!(
+            aliases(&["list", "--sort", "url"]),
+            ["zeta", "alpha", "工作"]
+        );
+        assert_eq!(
+            aliases(&["list", "--sort", "model"]),
+            ["工作", "zeta", "alpha"]
+        );
+
+        let plain = run(&["list", "--plain"]);
+        assert_eq!(plain, run(&["list", "--plain"]), "output is stable");
+        let lines: Vec<&str> = plain.lines().collect();
+        assert_eq!(
+            lines[1].split_whitespace().collect::<Vec<_>>(),
+            ["Alias", "URL", "Model", "Token"]
+        );
+        // Piped output is never truncated, and columns line up
+        assert!(
+            lines[4].starts_with("  工作   https://c.example  haiku "),
+            "got: {plain}"
+        );
+        assert!(
+            lines[2].starts_with("  alpha  https://b.example  sonnet"),
+            "got: {plain}"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_launch_args_reach_claude_after_the_defaults() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let dump = tmp.path().join("claude_args");
+        let mock = tmp.path().join("mock-claude");
+        std::fs::write(
+            &mock,
+            format!(
+                "#!/bin/sh\nfor arg in \"$@\"; do echo \"$arg\"; done > '{}'\n",
+                dump.display()
+            ),
+        )
+        .expect("write mock");
+        std::fs::set_permissions(&mock, std::fs::Permissions::from_mode(0o755))
+            .expect("chmod mock");
+        let run = |args: &[&str]| {
+            let mut command = cc_switch(tmp.path());
+            command.env("CLAUDE_BINARY", &mock).args(args);
+            command.output().expect("Should run cc-switch")
+        };
+
+        let output = run(&[
+            "add",
+            "mono",
+            "sk-mono",
+            "https://mono.example",
+            "--launch-arg",
+            "--add-dir",
+            "--launch-arg",
+            "/src/mono repo",
+        ]);
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let plain = String::from_utf8_lossy(&run(&["list", "--plain"]).stdout).into_owned();
+        assert!(
+            plain.contains("launch_args=--add-dir '/src/mono repo'"),
+            "{plain}"
+        );
+
+        let output = run(&["use", "mono", "--claude-arg", "--verbose"]);
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let args = std::fs::read_to_string(&dump).expect("mock should run");
+        assert_eq!(
+            args.lines().collect::<Vec<_>>(),
+            [
+                "--dangerously-skip-permissions",
+                "--add-dir",
+                "/src/mono repo",
+                "--verbose"
+            ]
+        );
+
+        // edit replaces them; --unset clears them
+        assert!(
+            run(&["edit", "mono", "--launch-arg", "--continue"])
+                .status
+                .success()
+        );
+        let plain = String::from_utf8_lossy(&run(&["list", "--plain"]).stdout).into_owned();
+        assert!(plain.contains("launch_args=--continue"), "{plain}");
+        assert!(
+            run(&["edit", "mono", "--unset", "launch_args"])
+                .status
+                .success()
+        );
+        let plain = String::from_utf8_lossy(&run(&["list", "--plain"]).stdout).into_owned();
+        assert!(!plain.contains("launch_args"), "{plain}");
+    }
+
+    #[test]
+    fn test_legacy_store_is_migrated_once_and_kept() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+        };
+        let legacy_dir = tmp.path().join(".cc_auto_switch");
+        std::fs::create_dir_all(&legacy_dir).unwrap();
+        let legacy_path = legacy_dir.join("configurations.json");
+        let legacy = r#"{
+            "configurations": {
+                "work": {"alias_name": "work", "token": "sk-work", "url": "https://work.example"},
+                "home": {"alias_name": "home", "token": "sk-home", "url": "https://home.example"}
+            },
+            "claude_settings_dir": null
+        }"#;
+        std::fs::write(&legacy_path, legacy).unwrap();
+
+        let output = run(&["list", "--format", "json"]);
+        assert!(output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains("Migrated 2 configurations from"),
+            "{stderr}"
+        );
+        let list: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(listed_config(&list, "work")["url"], "https://work.example");
+        assert!(storage_path(tmp.path()).exists());
+        assert_eq!(std::fs::read_to_string(&legacy_path).unwrap(), legacy);
+
+        // Only once: the current store now exists
+        let output = run(&["list"]);
+        assert!(!String::from_utf8_lossy(&output.stderr).contains("Migrated"));
+
+        // Manual migration from another file keeps existing aliases
+        let other = tmp.path().join("old.json");
+        std::fs::write(
+            &other,
+            r#"{"configurations": {
+                "work": {"token": "sk-new", "url": "https://new.example"},
+                "relay": {"token": "sk-relay", "url": "https://relay.example"}
+            }}"#,
+        )
+        .unwrap();
+        let output = run(&["migrate", "--from", other.to_str().unwrap()]);
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("+ relay (added)"), "{stdout}");
+        assert!(
+            stdout.contains("= work (skipped, already exists)"),
+            "{stdout}"
+        );
+        assert!(
+            run(&["migrate", "--from", other.to_str().unwrap(), "--force"])
+                .status
+                .success()
+        );
+        let list: serde_json::Value =
+            serde_json::from_slice(&run(&["list", "--format", "json"]).stdout).unwrap();
+        assert_eq!(listed_config(&list, "work")["url"], "https://new.example");
+
+        let missing = run(&["migrate", "--from", "/nonexistent/configurations.json"]);
+        assert_eq!(missing.status.code(), Some(2));
+    }
+
+    #[test]
+    fn test_no_launch_use_is_counted_in_the_store() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            let mut command = cc_switch(tmp.path());
+            command
+                .env("CLAUDE_BINARY", "/nonexistent/claude")
+                .args(args);
+            command.output().expect("Should run cc-switch")
+        };
+        let store_path = storage_path(tmp.path());
+        let store = || -> serde_json::Value {
+            serde_json::from_str(&std::fs::read_to_string(&store_path).unwrap()).unwrap()
+        };
+
+        for args in [
+            &["add", "work", "sk-work", "https://work.example"][..],
+            &["add", "home", "sk-home", "https://home.example"],
+        ] {
+            assert!(run(args).status.success());
+        }
+        // Old stores have no counts and still load
+        assert!(store().get("use_counts").is_none());
+
+        for _ in 0..2 {
+            assert!(run(&["use", "work", "--no-launch"]).status.success());
+        }
+        let saved = store();
+        assert_eq!(saved["use_counts"], serde_json::json!({"work": 2}));
+        assert!(saved["last_used_at"]["work"].is_string());
+
+        let listed: serde_json::Value =
+            serde_json::from_slice(&run(&["list", "--sort", "last-used"]).stdout).unwrap();
+        assert_eq!(listed[0]["alias_name"], "work");
+        assert_eq!(listed[0]["use_count"], 2);
+        assert_eq!(listed[1]["use_count"], 0);
+
+        assert!(run(&["rename", "work", "job"]).status.success());
+        assert_eq!(store()["use_counts"], serde_json::json!({"job": 2}));
+        assert!(run(&["remove", "job"]).status.success());
+        assert!(store().get("use_counts").is_none());
+    }
+
+    #[test]
+    fn test_prune_reports_and_removes_duplicates() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            let mut command = cc_switch(tmp.path());
+            command.args(args);
+            command.output().expect("Should run cc-switch")
+        };
+        let aliases = || {
+            let listed: serde_json::Value = serde_json::from_slice(&run(&["list"]).stdout).unwrap();
+            listed
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|config| config["alias_name"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        for args in [
+            &["add", "work", "sk-work", "https://work.example"][..],
+            &["add", "work-copy", "sk-work", "https://work.example"],
+            &["add", "home", "sk-home", "https://home.example"],
+        ] {
+            assert!(run(args).status.success());
+        }
+
+        let report = run(&["prune"]);
+        assert!(report.status.success());
+        let stdout = String::from_utf8_lossy(&report.stdout);
+        assert!(
+            stdout.contains("Duplicates (1):\n  work-copy: duplicate of 'work'"),
+            "{stdout}"
+        );
+        assert_eq!(aliases(), ["home", "work", "work-copy"]);
+
+        // Without a terminal removals must be confirmed with --yes
+        assert!(!run(&["prune", "--apply"]).status.success());
+        assert!(!run(&["prune", "--yes"]).status.success());
+        assert!(run(&["prune", "--apply", "--yes"]).status.success());
+        assert_eq!(aliases(), ["home", "work"]);
+        let clean = run(&["prune"]);
+        assert!(String::from_utf8_lossy(&clean.stdout).contains("Nothing to prune"));
+    }
+
+    #[test]
+    fn test_config_path_overrides_pick_the_store() {
+        use std::path::Path;
+        /// Environment variables set for one run
+        type Vars<'a> = [(&'a str, &'a Path)];
+
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |vars: &Vars, args: &[&str]| {
+            let mut command = cc_switch(tmp.path());
+            command.envs(vars.iter().copied()).args(args);
+            command.output().expect("Should run cc-switch")
+        };
+        let add = |vars: &Vars, alias: &str| {
+            let output = run(vars, &["add", alias, "sk-test", "https://api.example.com"]);
+            assert!(
+                output.status.success(),
+                "stderr: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        };
+
+        let xdg = tmp.path().join("xdg");
+        add(&[("XDG_CONFIG_HOME", &xdg)], "from-xdg");
+        assert!(xdg.join("cc-switch/configurations.json").exists());
+        assert!(!storage_path(tmp.path()).exists());
+
+        let env_file = tmp.path().join("env-store.json");
+        add(
+            &[("XDG_CONFIG_HOME", &xdg), ("CC_SWITCH_CONFIG", &env_file)],
+            "from-env",
+        );
+        let flag_file = tmp.path().join("flag-store.json");
+        let flag = flag_file.to_str().unwrap();
+        let output = run(
+            &[("CC_SWITCH_CONFIG", &env_file)],
+            &[
+                "--config",
+                flag,
+                "add",
+                "from-flag",
+                "sk-test",
+                "https://api.example.com",
+            ],
+        );
+        assert!(output.status.success());
+
+        let stored = |path: &Path| {
+            let storage: serde_json::Value =
+                serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+            storage["configurations"]
+                .as_object()
+                .unwrap()
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            stored(&xdg.join("cc-switch/configurations.json")),
+            ["from-xdg"]
+        );
+        assert_eq!(stored(&env_file), ["from-env"]);
+        assert_eq!(stored(&flag_file), ["from-flag"]);
+
+        let doctor = run(&[], &["--config", flag, "doctor"]);
+        let stdout = String::from_utf8_lossy(&doctor.stdout);
+        assert!(
+            stdout.contains(&format!("{flag} (from --config) (1 configuration(s))")),
+            "{stdout}"
+        );
+    }
+
+    #[test]
+    fn test_completion_install_writes_the_script_once_without_force() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .env_remove("XDG_DATA_HOME")
+                .env_remove("FPATH")
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+        };
+
+        let printed = run(&["completion", "bash"]);
+        assert!(printed.status.success());
+        let script = String::from_utf8_lossy(&printed.stdout).into_owned();
+        assert!(script.contains("_cc_switch_dynamic"), "got: {script}");
+        assert!(
+            !tmp.path().join(".local").exists(),
+            "without --install nothing is written"
+        );
+
+        let installed = run(&["completion", "bash", "--install"]);
+        assert!(
+            installed.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&installed.stderr)
+        );
+        let path = tmp
+            .path()
+            .join(".local/share/bash-completion/completions/cc-switch");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), script);
+        let stdout = String::from_utf8_lossy(&installed.stdout);
+        assert!(
+            stdout.contains(&path.display().to_string()),
+            "got: {stdout}"
+        );
+
+        let again = run(&["completion", "bash", "--install"]);
+        assert!(!again.status.success());
+        assert!(String::from_utf8_lossy(&again.stderr).contains("--force"));
+        assert!(
+            run(&["completion", "bash", "--install", "--force"])
+                .status
+                .success()
+        );
+
+        // fish also gets the files completing the cs and cx aliases
+        assert!(run(&["completion", "fish", "--install"]).status.success());
+        let fish_dir = tmp.path().join(".config/fish/completions");
+        for name in ["cc-switch.fish", "cs.fish", "cx.fish"] {
+            assert!(fish_dir.join(name).is_file(), "{name} missing");
+        }
+
+        // An unwritable location surfaces as an error naming it
+        std::fs::write(tmp.path().join(".zsh"), "not a directory").unwrap();
+        let blocked = run(&["completion", "zsh", "--install"]);
+        assert!(!blocked.status.success());
+        let stderr = String::from_utf8_lossy(&blocked.stderr);
+        assert!(stderr.contains(".zsh/completions"), "got: {stderr}");
+
+        assert!(!run(&["completion", "elvish", "--install"]).status.success());
+        assert!(!run(&["completion", "fish", "--force"]).status.success());
+    }
+
+    #[test]
+    fn test_token_command_supplies_the_token_at_switch_time() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            let mut command = cc_switch(tmp.path());
+            command.args(args);
+            command.output().expect("Should run cc-switch")
+        };
+
+        let both = run(&[
+            "add",
+            "work",
+            "-t",
+            "sk-plain",
+            "--token-command",
+            "echo sk-cmd",
+        ]);
+        assert!(!both.status.success(), "token and command are exclusive");
+
+        let added = run(&[
+            "add",
+            "work",
+            "--token-command",
+            "echo sk-from-command",
+            "-u",
+            "https://relay.example",
+        ]);
+        assert!(
+            added.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&added.stderr)
+        );
+        let stored: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(storage_path(tmp.path())).unwrap())
+                .unwrap();
+        let work = &stored["configurations"]["work"];
+        assert_eq!(work["token"], "");
+        assert_eq!(work["token_command"], "echo sk-from-command");
+
+        let list = run(&["list", "--plain"]);
+        let listed = String::from_utf8_lossy(&list.stdout);
+        assert!(listed.contains("(from command)"), "got: {listed}");
+
+        let env = run(&["env", "work", "--shell", "bash"]);
+        let exported = String::from_utf8_lossy(&env.stdout);
+        assert!(exported.contains("sk-from-command"), "got: {exported}");
+
+        assert!(
+            run(&["edit", "work", "--token-command", "exit 7"])
+                .status
+                .success()
+        );
+        let failed = run(&["env", "work", "--shell", "bash"]);
+        assert!(!failed.status.success());
+        let stderr = String::from_utf8_lossy(&failed.stderr);
+        assert!(stderr.contains("Token command 'exit 7'"), "got: {stderr}");
+    }
+
+    #[test]
+    fn test_resolve_prints_the_launch_plan_without_launching() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            let mut command = cc_switch(tmp.path());
+            command.env("CC_SWITCH_CLAUDE_BIN", "/bin/sh").args(args);
+            command.output().expect("Should run cc-switch")
+        };
+
+        let added = run(&[
+            "add",
+            "work",
+            "-t",
+            "sk-ant-REDACTED",
+            "-u",
+            "https://relay.example",
+            "-m",
+            "claude-sonnet-4-5",
+            "--launch-arg",
+            "--verbose",
+        ]);
+        assert!(
+            added.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&added.stderr)
+        );
+
+        let resolved = run(&["resolve", "work"]);
+        assert!(resolved.status.success());
+        let plan = String::from_utf8_lossy(&resolved.stdout);
+        assert!(
+            plan.contains("Command: /bin/sh --dangerously-skip-permissions --verbose"),
+            "got: {plan}"
+        );
+        assert!(
+            plan.contains("ANTHROPIC_MODEL=claude-sonnet-4-5"),
+            "got: {plan}"
+        );
+        assert!(!plan.contains("abcdefghijklmnopqrstuvwxyz"), "got: {plan}");
+
+        let json = run(&["which", "work", "--json", "--reveal"]);
+        let plan: serde_json::Value = serde_json::from_slice(&json.stdout).unwrap();
+        assert_eq!(
+            plan["env"]["ANTHROPIC_AUTH_TOKEN"],
+            "sk-ant-REDACTED"
+        );
+        assert_eq!(plan["env"]["CC_SWITCH_CURRENT_ALIAS"], "work");
+        assert_eq!(plan["storage_mode"], "env");
+        assert_eq!(plan["argv"][1], "--dangerously-skip-permissions");
+
+        let official = run(&["resolve", "cc"]);
+        assert!(!official.status.success());
+        assert!(!tmp.path().join(".claude/settings.json").exists());
+    }
+
+    #[test]
+    fn test_remove_all_clears_the_store_only_when_confirmed() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            let mut command = cc_switch(tmp.path());
+            command.args(args);
+            command.output().expect("Should run cc-switch")
+        };
+
+        for alias in ["work", "home"] {
+            assert!(
+                run(&[
+                    "add",
+                    alias,
+                    "-t",
+                    "sk-ant-test",
+                    "-u",
+                    "https://relay.example"
+                ])
+                .status
+                .success()
+            );
+        }
+        assert!(!run(&["remove", "--all", "work"]).status.success());
+
+        let unconfirmed = run(&["remove", "--all"]);
+        assert!(!unconfirmed.status.success());
+        assert!(
+            String::from_utf8_lossy(&unconfirmed.stderr).contains("--yes"),
+            "stderr: {}",
+            String::from_utf8_lossy(&unconfirmed.stderr)
+        );
+        let listed = run(&["list", "--plain"]);
+        assert!(String::from_utf8_lossy(&listed.stdout).contains("work"));
+
+        let removed = run(&["remove", "--all", "--yes"]);
+        assert!(removed.status.success());
+        assert!(String::from_utf8_lossy(&removed.stdout).contains("Removed 2 configuration(s)"));
+        let stored: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(storage_path(tmp.path())).unwrap())
+                .unwrap();
+        assert_eq!(stored["configurations"], serde_json::json!({}));
+    }
+
+    #[test]
+    fn test_settings_subcommands_target_the_given_directory() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let dir = tmp.path().join("claude-work");
+        let dir_arg = dir.to_str().unwrap();
+        let run = |args: &[&str]| {
+            let mut command = cc_switch(tmp.path());
+            command.args(args);
+            command.output().expect("Should run cc-switch")
+        };
+
+        let path = run(&["settings", "path", "--dir", dir_arg]);
+        assert_eq!(
+            String::from_utf8_lossy(&path.stdout).trim(),
+            dir.join("settings.json").to_str().unwrap()
+        );
+        let shown = run(&["settings", "show", "--dir", dir_arg]);
+        assert!(String::from_utf8_lossy(&shown.stdout).contains("(no settings.json yet)"));
+        assert!(!dir.join("settings.json").exists(), "show creates nothing");
+
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("settings.json"),
+            r#"{"env": {"ANTHROPIC_AUTH_TOKEN": "sk-ant-test", "EDITOR": "vim"}, "model": "opus"}"#,
+        )
+        .unwrap();
+        let cleaned = run(&["settings", "clean", "--dir", dir_arg]);
+        assert!(cleaned.status.success());
+        assert!(String::from_utf8_lossy(&cleaned.stdout).contains("removed ANTHROPIC_AUTH_TOKEN"));
+        let saved: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(dir.join("settings.json")).unwrap())
+                .unwrap();
+        assert_eq!(
+            saved,
+            serde_json::json!({"env": {"EDITOR": "vim"}, "model": "opus"})
+        );
+    }
+
+    #[test]
+    fn test_secure_enable_without_keyring_support_leaves_the_store_alone() {
+        // With the feature the real keyring would be written to
+        if cfg!(feature = "keyring") {
+            return;
+        }
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            let mut command = cc_switch(tmp.path());
+            command.args(args);
+            command.output().expect("Should run cc-switch")
+        };
+
+        assert!(
+            run(&[
+                "add",
+                "work",
+                "-t",
+                "sk-ant-test",
+                "-u",
+                "https://relay.example"
+            ])
+            .status
+            .success()
+        );
+        let path = storage_path(tmp.path());
+        let before = std::fs::read_to_string(&path).unwrap();
+
+        let enabled = run(&["secure", "enable"]);
+        assert!(!enabled.status.success());
+        assert!(
+            String::from_utf8_lossy(&enabled.stderr).contains("without keyring support"),
+            "stderr: {}",
+            String::from_utf8_lossy(&enabled.stderr)
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), before);
+
+        // Nothing is in the keyring, so turning it off needs no keyring
+        let disabled = run(&["secure", "disable"]);
+        assert!(disabled.status.success());
+        assert!(String::from_utf8_lossy(&disabled.stdout).contains("Secure mode off"));
+        let stored: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(stored["configurations"]["work"]["token"], "sk-ant-test");
+    }
+
+    #[test]
+    fn test_add_preset_fills_the_url_and_models_the_flags_leave_unset() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            let mut command = cc_switch(tmp.path());
+            command.args(args);
+            command.output().expect("Should run cc-switch")
+        };
+
+        let added = run(&[
+            "add",
+            "work",
+            "--preset",
+            "openrouter",
+            "-t",
+            "sk-or-v1-0123456789abcdef0123456789abcdef",
+        ]);
+        assert!(
+            added.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&added.stderr)
+        );
+        let added = run(&[
+            "add",
+            "ds",
+            "--preset",
+            "DeepSeek",
+            "-t",
+            "sk-deepseek",
+            "-m",
+            "deepseek-reasoner",
+        ]);
+        assert!(added.status.success());
+        let stored: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(storage_path(tmp.path())).unwrap())
+                .unwrap();
+        let configs = &stored["configurations"];
+        assert_eq!(configs["work"]["url"], "https://openrouter.ai/api");
+        assert_eq!(configs["ds"]["url"], "https://api.deepseek.com/anthropic");
+        assert_eq!(configs["ds"]["model"], "deepseek-reasoner");
+        assert_eq!(configs["ds"]["small_fast_model"], "deepseek-v4-flash");
+
+        let unknown = run(&["add", "x", "--preset", "nope", "-t", "sk-test"]);
+        assert!(!unknown.status.success());
+        assert!(
+            String::from_utf8_lossy(&unknown.stderr).contains("Valid presets: anthropic"),
+            "stderr: {}",
+            String::from_utf8_lossy(&unknown.stderr)
+        );
+    }
+
+    #[test]
+    fn test_storage_files_are_migrated_forward_and_newer_ones_refused() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            let mut command = cc_switch(tmp.path());
+            command.args(args);
+            command.output().expect("Should run cc-switch")
+        };
+        let storage_path = storage_path(tmp.path());
+        std::fs::create_dir_all(storage_path.parent().unwrap()).unwrap();
+
+        // An unversioned file is read as version 1 and saved as the current one
+        std::fs::write(
+            &storage_path,
+            r#"{
+                "configurations": {
+                    "work": {"alias_name": "work", "token": "sk-work", "url": "https://work.example"}
+                },
+                "claude_settings_dir": "alt-claude"
+            }"#,
+        )
+        .unwrap();
+        let added = run(&["add", "home", "-t", "sk-home", "-u", "https://home.example"]);
+        assert!(
+            added.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&added.stderr)
+        );
+        let stored: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&storage_path).unwrap()).unwrap();
+        assert_eq!(
+            stored["version"],
+            cc_switch::config::migrations::CURRENT_VERSION
+        );
+        assert_eq!(stored["claude"]["settings_dir"], "alt-claude");
+        assert!(stored.get("claude_settings_dir").is_none());
+
+        // A newer file is left alone, even with a readable backup next to it
+        let newer = r#"{"version": 99, "configurations": {}}"#;
+        std::fs::write(&storage_path, newer).unwrap();
+        for args in [
+            &["list"][..],
+            &["add", "x", "-t", "sk-x", "-u", "https://x.example"],
+        ] {
+            let output = run(args);
+            assert!(!output.status.success());
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert!(
+                stderr.contains("found version 99, supported up to 2"),
+                "stderr: {stderr}"
+            );
+        }
+        assert_eq!(std::fs::read_to_string(&storage_path).unwrap(), newer);
+    }
+
+    #[test]
+    fn test_edit_and_set_reject_an_invalid_url() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let run = |args: &[&str]| {
+            cc_switch(tmp.path())
+                .args(args)
+                .output()
+                .expect("Should run cc-switch")
+        };
+        assert!(
+            run(&["add", "work", "-t", "sk-work", "-u", "https://work.example"])
+                .status
+                .success()
+        );
+
+        for args in [
+            &["edit", "work", "--url", "htps://bad url"][..],
+            &[
+                "set",
+                "--where",
+                "alias=work",
+                "url",
+                "ftp://x",
+                "--dry-run",
+            ],
+        ] {
+            let output = run(args);
+            assert!(!output.status.success(), "{args:?} succeeded");
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert!(stderr.contains("Invalid URL"), "{args:?}: {stderr}");
+        }
+        let stored: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(storage_path(tmp.path())).unwrap())
+                .unwrap();
+        assert_eq!(
+            stored["configurations"]["work"]["url"],
+            "https://work.example"
+        );
+
+        assert!(
+            run(&["edit", "work", "--url", "https://new.example/"])
+                .status
+                .success()
+        );
+        let list: serde_json::Value =
+            serde_json::from_slice(&run(&["list", "--format", "json"]).stdout).unwrap();
+        assert_eq!(listed_config(&list, "work")["url"], "https://new.example");
+    }
 }
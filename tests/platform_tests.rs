@@ -3,8 +3,11 @@
 //! These tests are platform-independent — Windows-only PATHEXT probing is
 //! exercised by the CI `shell-smoke` job, not here.
 
-use cc_switch::platform::{resolve_npm_cli, unicode_support_enabled};
+use cc_switch::platform::{
+    CLAUDE_BIN_ENV, choose_claude_binary, resolve_npm_cli, spawn_and_wait, unicode_support_enabled,
+};
 use std::path::PathBuf;
+use std::process::Command;
 
 #[test]
 fn env_override_takes_precedence() {
@@ -40,3 +43,44 @@ fn ascii_env_override_forces_ascii() {
     }
     assert!(!supported, "CC_SWITCH_ASCII=1 must force ASCII fallback");
 }
+
+#[test]
+fn claude_binary_precedence_is_env_then_setting_then_default() {
+    assert_eq!(
+        choose_claude_binary(Some("/env/claude"), Some("/stored/claude")),
+        (PathBuf::from("/env/claude"), CLAUDE_BIN_ENV)
+    );
+    assert_eq!(
+        choose_claude_binary(None, Some("/stored/claude")).0,
+        PathBuf::from("/stored/claude")
+    );
+    // Empty values fall through to the next source
+    assert_eq!(
+        choose_claude_binary(Some(""), Some("  /stored/claude ")).0,
+        PathBuf::from("/stored/claude")
+    );
+    assert_eq!(
+        choose_claude_binary(None, Some("")).0,
+        resolve_npm_cli("claude")
+    );
+}
+
+/// A command exiting with `code`, through the platform's shell
+fn exiting_with(code: i32) -> Command {
+    #[cfg(windows)]
+    let mut command = Command::new("cmd");
+    #[cfg(windows)]
+    command.args(["/C", &format!("exit {code}")]);
+    #[cfg(not(windows))]
+    let mut command = Command::new("sh");
+    #[cfg(not(windows))]
+    command.args(["-c", &format!("exit {code}")]);
+    command
+}
+
+#[test]
+fn spawn_and_wait_returns_the_child_exit_code() {
+    assert_eq!(spawn_and_wait(&mut exiting_with(0)).unwrap(), 0);
+    assert_eq!(spawn_and_wait(&mut exiting_with(3)).unwrap(), 3);
+    assert!(spawn_and_wait(&mut Command::new("definitely_nonexistent_binary_xyz123abc")).is_err());
+}
@@ -18,6 +18,7 @@ mod tests {
             alias_name: alias.to_string(),
             token: token.to_string(),
             api_key: None,
+            token_command: None,
             url: url.to_string(),
             model: None,
             small_fast_model: None,
@@ -35,6 +36,14 @@ mod tests {
             disable_prompt_caching: None,
             claude_code_disable_experimental_betas: None,
             disable_autoupdater: None,
+            tags: Vec::new(),
+            launch_profiles: Default::default(),
+            url_variants: Default::default(),
+            quota_probe: None,
+            extra_env: Default::default(),
+            color: None,
+            provider: None,
+            launch_args: Vec::new(),
         }
     }
 
@@ -113,6 +122,75 @@ mod tests {
         assert!(!storage.remove_configuration("nonexistent"));
     }
 
+    #[test]
+    fn test_config_storage_rename_configuration() {
+        let mut storage = ConfigStorage::default();
+        storage.add_configuration(create_test_config(
+            "test1",
+            "sk-ant-test",
+            "https://api.test.com",
+        ));
+
+        storage.rename_configuration("test1", "work").unwrap();
+        assert!(!storage.configurations.contains_key("test1"));
+        let renamed = storage.get_configuration("work").unwrap();
+        assert_eq!(renamed.alias_name, "work");
+        assert_eq!(renamed.token, "sk-ant-test");
+        assert_eq!(renamed.url, "https://api.test.com");
+
+        assert!(storage.rename_configuration("missing", "other").is_err());
+        assert!(storage.rename_configuration("work", "cc").is_err());
+        assert!(storage.get_configuration("work").is_some());
+    }
+
+    #[test]
+    fn test_config_storage_default_alias_follows_rename_and_remove() {
+        let mut storage = ConfigStorage::default();
+        storage.add_configuration(create_test_config(
+            "test1",
+            "sk-ant-test",
+            "https://api.test.com",
+        ));
+
+        assert!(storage.set_default_alias(Some("missing")).is_err());
+        assert_eq!(storage.default_alias, None);
+        storage.set_default_alias(Some("test1")).unwrap();
+
+        storage.rename_configuration("test1", "work").unwrap();
+        assert_eq!(storage.default_alias.as_deref(), Some("work"));
+        storage.remove_configuration("work");
+        assert_eq!(storage.default_alias, None);
+
+        storage.add_configuration(create_test_config("home", "sk-ant", "https://h.test"));
+        storage.set_default_alias(Some("home")).unwrap();
+        storage.set_default_alias(None).unwrap();
+        assert_eq!(storage.default_alias, None);
+    }
+
+    #[test]
+    fn test_config_storage_last_used_follows_rename_and_remove() {
+        let mut storage = ConfigStorage::default();
+        storage.add_configuration(create_test_config(
+            "test1",
+            "sk-ant-test",
+            "https://api.test.com",
+        ));
+        assert!(storage.last_used_alias().is_err());
+
+        let at = chrono::Utc::now();
+        storage.record_use("test1", at);
+        assert_eq!(storage.last_used_alias().unwrap(), "test1");
+
+        storage.rename_configuration("test1", "work").unwrap();
+        assert_eq!(storage.last_used_alias().unwrap(), "work");
+        assert_eq!(storage.last_used_at.get("work"), Some(&at));
+        assert!(!storage.last_used_at.contains_key("test1"));
+
+        storage.remove_configuration("work");
+        assert!(storage.last_used_alias().is_err());
+        assert!(storage.last_used_at.is_empty());
+    }
+
     #[test]
     fn test_config_storage_save_and_load() {
         let temp_dir = create_test_temp_dir();
@@ -330,7 +408,7 @@ mod tests {
             ..
         }) = cli.command
         {
-            assert_eq!(alias_name, "my-config");
+            assert_eq!(alias_name.as_deref(), Some("my-config"));
             assert_eq!(token, None);
             assert_eq!(url, None);
             assert!(!force);
@@ -371,7 +449,7 @@ mod tests {
             ..
         }) = cli.command
         {
-            assert_eq!(alias_name, "my-config");
+            assert_eq!(alias_name.as_deref(), Some("my-config"));
             assert_eq!(token, Some("sk-ant-test".to_string()));
             assert_eq!(url, Some("https://api.test.com".to_string()));
             assert!(force);
@@ -404,7 +482,7 @@ mod tests {
         let args = vec!["cc-switch", "remove", "config1", "config2"];
         let cli = Cli::try_parse_from(args).unwrap();
 
-        if let Some(Commands::Remove { alias_names }) = cli.command {
+        if let Some(Commands::Remove { alias_names, .. }) = cli.command {
             assert_eq!(alias_names, vec!["config1", "config2"]);
         } else {
             panic!("Expected Remove command");
@@ -418,7 +496,7 @@ mod tests {
         let args = vec!["cc-switch", "completion", "fish"];
         let cli = Cli::try_parse_from(args).unwrap();
 
-        if let Some(Commands::Completion { shell }) = cli.command {
+        if let Some(Commands::Completion { shell, .. }) = cli.command {
             assert_eq!(shell, "fish");
         } else {
             panic!("Expected Completion command");
@@ -428,18 +506,17 @@ mod tests {
     // Additional config.rs tests to improve coverage
     #[test]
     fn test_get_config_storage_path() {
-        let result = get_config_storage_path();
+        // Without the developer's overrides, which would pick another file
+        let home = create_test_temp_dir();
+        let result =
+            cc_switch::utils::resolve_config_storage_path(None, |_| None, Some(home.path()));
         assert!(
             result.is_ok(),
             "Should be able to determine config storage path"
         );
 
-        let path = result.unwrap();
-        assert!(path.to_string_lossy().contains(".claude"));
-        assert!(
-            path.to_string_lossy()
-                .contains("cc_auto_switch_setting.json")
-        );
+        let (path, _) = result.unwrap();
+        assert!(path.ends_with(".config/cc-switch/configurations.json"));
     }
 
     #[test]
@@ -554,6 +631,7 @@ mod tests {
             alias_name: "edge-case".to_string(),
             token: "".to_string(),
             api_key: None,
+            token_command: None,
             url: "".to_string(),
             model: None,
             small_fast_model: None,
@@ -571,6 +649,14 @@ mod tests {
             disable_prompt_caching: None,
             claude_code_disable_experimental_betas: None,
             disable_autoupdater: None,
+            tags: Vec::new(),
+            launch_profiles: Default::default(),
+            url_variants: Default::default(),
+            quota_probe: None,
+            extra_env: Default::default(),
+            color: None,
+            provider: None,
+            launch_args: Vec::new(),
         };
 
         let env_config = EnvironmentConfig::from_config(&config);
@@ -723,6 +809,7 @@ mod tests {
             alias_name: "format-test".to_string(),
             token: "sk-ant-format-test".to_string(),
             api_key: None,
+            token_command: None,
             url: "https://format.test.com".to_string(),
             model: Some("claude-format-model".to_string()),
             small_fast_model: None,
@@ -740,6 +827,14 @@ mod tests {
             disable_prompt_caching: None,
             claude_code_disable_experimental_betas: None,
             disable_autoupdater: None,
+            tags: Vec::new(),
+            launch_profiles: Default::default(),
+            url_variants: Default::default(),
+            quota_probe: None,
+            extra_env: Default::default(),
+            color: None,
+            provider: None,
+            launch_args: Vec::new(),
         };
 
         let json = serde_json::to_string_pretty(&config).expect("Should serialize to pretty JSON");
@@ -798,12 +893,88 @@ mod tests {
         assert_eq!(config.small_fast_model, None);
     }
 
+    #[test]
+    fn test_read_legacy_storage_maps_old_shape() {
+        let temp_dir = create_test_temp_dir();
+        let legacy_path = temp_dir.path().join("configurations.json");
+        // Shape written by the releases storing in ~/.cc_auto_switch: no
+        // optional model fields, and one entry without its alias
+        fs::write(
+            &legacy_path,
+            r#"{
+                "configurations": {
+                    "work": {
+                        "alias_name": "work",
+                        "token": "sk-ant-work",
+                        "url": "https://work.example.com",
+                        "model": "claude-3-opus"
+                    },
+                    "home": {
+                        "token": "sk-ant-home",
+                        "url": "https://home.example.com"
+                    }
+                },
+                "claude_settings_dir": "/opt/claude"
+            }"#,
+        )
+        .expect("Should write legacy store");
+
+        let storage = ConfigStorage::read_legacy(&legacy_path).expect("Should read legacy store");
+        assert_eq!(storage.configurations.len(), 2);
+        assert_eq!(storage.claude.settings_dir.as_deref(), Some("/opt/claude"));
+        let work = storage.get_configuration("work").unwrap();
+        assert_eq!(work.model.as_deref(), Some("claude-3-opus"));
+        assert_eq!(work.small_fast_model, None);
+        assert_eq!(work.max_thinking_tokens, None);
+        let home = storage.get_configuration("home").unwrap();
+        assert_eq!(home.alias_name, "home");
+        assert_eq!(home.url, "https://home.example.com");
+        assert!(home.tags.is_empty());
+
+        fs::write(&legacy_path, r#"{"env": {}}"#).unwrap();
+        assert!(ConfigStorage::read_legacy(&legacy_path).is_err());
+    }
+
+    #[test]
+    fn test_with_lock_keeps_concurrent_adds() {
+        let temp_dir = create_test_temp_dir();
+        let path = temp_dir.path().join("cc_auto_switch_setting.json");
+        let barrier = std::sync::Barrier::new(2);
+
+        std::thread::scope(|scope| {
+            for alias in ["first", "second"] {
+                let (path, barrier) = (&path, &barrier);
+                scope.spawn(move || {
+                    // Both runs start from the same (empty) view of the store
+                    let mut storage = ConfigStorage::default();
+                    barrier.wait();
+                    storage
+                        .with_lock_at(path, |storage| {
+                            storage.add_configuration(create_test_config(
+                                alias,
+                                "sk-ant-test",
+                                "https://api.example.com",
+                            ));
+                            Ok(())
+                        })
+                        .expect("Should add under the lock");
+                });
+            }
+        });
+
+        let storage = ConfigStorage::load_from(&path).expect("Should load the store");
+        assert!(storage.get_configuration("first").is_some());
+        assert!(storage.get_configuration("second").is_some());
+        assert!(path.with_extension("json.lock").exists());
+    }
+
     #[test]
     fn test_environment_config_as_env_tuples_order() {
         let config = Configuration {
             alias_name: "order-test".to_string(),
             token: "sk-ant-order".to_string(),
             api_key: None,
+            token_command: None,
             url: "https://order.test.com".to_string(),
             model: Some("claude-order-model".to_string()),
             small_fast_model: Some("haiku-order-model".to_string()),
@@ -821,6 +992,14 @@ mod tests {
             disable_prompt_caching: None,
             claude_code_disable_experimental_betas: None,
             disable_autoupdater: None,
+            tags: Vec::new(),
+            launch_profiles: Default::default(),
+            url_variants: Default::default(),
+            quota_probe: None,
+            extra_env: Default::default(),
+            color: None,
+            provider: None,
+            launch_args: Vec::new(),
         };
 
         let env_config = EnvironmentConfig::from_config(&config);
@@ -961,6 +1140,7 @@ mod config_edit_tests {
             alias_name: "test-config".to_string(),
             token: "sk-test-123".to_string(),
             api_key: None,
+            token_command: None,
             url: "https://api.test.com".to_string(),
             model: Some("test-model".to_string()),
             small_fast_model: Some("test-fast-model".to_string()),
@@ -978,6 +1158,14 @@ mod config_edit_tests {
             disable_prompt_caching: None,
             claude_code_disable_experimental_betas: None,
             disable_autoupdater: None,
+            tags: Vec::new(),
+            launch_profiles: Default::default(),
+            url_variants: Default::default(),
+            quota_probe: None,
+            extra_env: Default::default(),
+            color: None,
+            provider: None,
+            launch_args: Vec::new(),
         };
         storage.add_configuration(config);
 
@@ -993,6 +1181,7 @@ mod config_edit_tests {
             alias_name: "test-config".to_string(),
             token: "sk-updated-456".to_string(),
             api_key: None,
+            token_command: None,
             url: "https://api.updated.com".to_string(),
             model: Some("updated-model".to_string()),
             small_fast_model: None,
@@ -1010,6 +1199,14 @@ mod config_edit_tests {
             disable_prompt_caching: None,
             claude_code_disable_experimental_betas: None,
             disable_autoupdater: None,
+            tags: Vec::new(),
+            launch_profiles: Default::default(),
+            url_variants: Default::default(),
+            quota_probe: None,
+            extra_env: Default::default(),
+            color: None,
+            provider: None,
+            launch_args: Vec::new(),
         };
 
         let result = storage.update_configuration("test-config", updated_config);
@@ -1032,6 +1229,7 @@ mod config_edit_tests {
             alias_name: "renamed-config".to_string(),
             token: "sk-test-123".to_string(),
             api_key: None,
+            token_command: None,
             url: "https://api.test.com".to_string(),
             model: Some("test-model".to_string()),
             small_fast_model: Some("test-fast-model".to_string()),
@@ -1049,6 +1247,14 @@ mod config_edit_tests {
             disable_prompt_caching: None,
             claude_code_disable_experimental_betas: None,
             disable_autoupdater: None,
+            tags: Vec::new(),
+            launch_profiles: Default::default(),
+            url_variants: Default::default(),
+            quota_probe: None,
+            extra_env: Default::default(),
+            color: None,
+            provider: None,
+            launch_args: Vec::new(),
         };
 
         let result = storage.update_configuration("test-config", renamed_config);
@@ -1070,6 +1276,7 @@ mod config_edit_tests {
             alias_name: "new-config".to_string(),
             token: "sk-new-789".to_string(),
             api_key: None,
+            token_command: None,
             url: "https://api.new.com".to_string(),
             model: None,
             small_fast_model: None,
@@ -1087,6 +1294,14 @@ mod config_edit_tests {
             disable_prompt_caching: None,
             claude_code_disable_experimental_betas: None,
             disable_autoupdater: None,
+            tags: Vec::new(),
+            launch_profiles: Default::default(),
+            url_variants: Default::default(),
+            quota_probe: None,
+            extra_env: Default::default(),
+            color: None,
+            provider: None,
+            launch_args: Vec::new(),
         };
 
         let result = storage.update_configuration("nonexistent", new_config);
@@ -1103,6 +1318,7 @@ mod config_edit_tests {
             alias_name: "config2".to_string(),
             token: "sk-config2-456".to_string(),
             api_key: None,
+            token_command: None,
             url: "https://api.config2.com".to_string(),
             model: None,
             small_fast_model: None,
@@ -1120,6 +1336,14 @@ mod config_edit_tests {
             disable_prompt_caching: None,
             claude_code_disable_experimental_betas: None,
             disable_autoupdater: None,
+            tags: Vec::new(),
+            launch_profiles: Default::default(),
+            url_variants: Default::default(),
+            quota_probe: None,
+            extra_env: Default::default(),
+            color: None,
+            provider: None,
+            launch_args: Vec::new(),
         };
         storage.add_configuration(config2);
 
@@ -1128,6 +1352,7 @@ mod config_edit_tests {
             alias_name: "config2".to_string(),
             token: "sk-overwritten".to_string(),
             api_key: None,
+            token_command: None,
             url: "https://api.overwritten.com".to_string(),
             model: None,
             small_fast_model: None,
@@ -1145,6 +1370,14 @@ mod config_edit_tests {
             disable_prompt_caching: None,
             claude_code_disable_experimental_betas: None,
             disable_autoupdater: None,
+            tags: Vec::new(),
+            launch_profiles: Default::default(),
+            url_variants: Default::default(),
+            quota_probe: None,
+            extra_env: Default::default(),
+            color: None,
+            provider: None,
+            launch_args: Vec::new(),
         };
 
         let result = storage.update_configuration("test-config", renamed_config);
@@ -1166,6 +1399,7 @@ mod config_edit_tests {
             alias_name: "test-config".to_string(),
             token: "sk-test-123".to_string(),
             api_key: None,
+            token_command: None,
             url: "https://api.test.com".to_string(),
             model: None,
             small_fast_model: None,
@@ -1183,6 +1417,14 @@ mod config_edit_tests {
             disable_prompt_caching: None,
             claude_code_disable_experimental_betas: None,
             disable_autoupdater: None,
+            tags: Vec::new(),
+            launch_profiles: Default::default(),
+            url_variants: Default::default(),
+            quota_probe: None,
+            extra_env: Default::default(),
+            color: None,
+            provider: None,
+            launch_args: Vec::new(),
         };
 
         let result = storage.update_configuration("test-config", updated_config);
@@ -1199,6 +1441,7 @@ mod config_edit_tests {
             alias_name: "test".to_string(),
             token: "sk-ant-test".to_string(),
             api_key: None,
+            token_command: None,
             url: "https://api.test.com".to_string(),
             model: None,
             small_fast_model: None,
@@ -1216,6 +1459,14 @@ mod config_edit_tests {
             disable_prompt_caching: None,
             claude_code_disable_experimental_betas: None,
             disable_autoupdater: None,
+            tags: Vec::new(),
+            launch_profiles: Default::default(),
+            url_variants: Default::default(),
+            quota_probe: None,
+            extra_env: Default::default(),
+            color: None,
+            provider: None,
+            launch_args: Vec::new(),
         };
 
         assert_eq!(config.api_timeout_ms, Some(3000000));
@@ -1240,6 +1491,7 @@ mod config_edit_tests {
             alias_name: "test".to_string(),
             token: "sk-ant-test".to_string(),
             api_key: None,
+            token_command: None,
             url: "https://api.test.com".to_string(),
             model: None,
             small_fast_model: None,
@@ -1257,6 +1509,14 @@ mod config_edit_tests {
             disable_prompt_caching: None,
             claude_code_disable_experimental_betas: None,
             disable_autoupdater: None,
+            tags: Vec::new(),
+            launch_profiles: Default::default(),
+            url_variants: Default::default(),
+            quota_probe: None,
+            extra_env: Default::default(),
+            color: None,
+            provider: None,
+            launch_args: Vec::new(),
         };
 
         let env_config = EnvironmentConfig::from_config(&config);
@@ -1310,6 +1570,7 @@ mod claude_settings_tests {
             alias_name: alias.to_string(),
             token: token.to_string(),
             api_key: None,
+            token_command: None,
             url: url.to_string(),
             model: None,
             small_fast_model: None,
@@ -1327,6 +1588,14 @@ mod claude_settings_tests {
             disable_prompt_caching: None,
             claude_code_disable_experimental_betas: None,
             disable_autoupdater: None,
+            tags: Vec::new(),
+            launch_profiles: Default::default(),
+            url_variants: Default::default(),
+            quota_probe: None,
+            extra_env: Default::default(),
+            color: None,
+            provider: None,
+            launch_args: Vec::new(),
         }
     }
 
@@ -1349,6 +1618,7 @@ mod claude_settings_tests {
         let mut settings = ClaudeSettings {
             env,
             other: BTreeMap::new(),
+            ..Default::default()
         };
 
         // Try to switch to Env mode - should succeed and auto-clean conflicts
@@ -1408,6 +1678,7 @@ mod claude_settings_tests {
         let mut settings = ClaudeSettings {
             env: BTreeMap::new(),
             other,
+            ..Default::default()
         };
 
         // Try to switch to Env mode - should succeed when no conflicts exist
@@ -1456,11 +1727,12 @@ mod claude_settings_tests {
         let mut settings = ClaudeSettings {
             env: BTreeMap::new(),
             other,
+            ..Default::default()
         };
 
         // Temporarily unset all Anthropic-related environment variables for this test
         let env_fields = Configuration::get_env_field_names();
-        let mut saved_env: Vec<(String, Option<String>)> = Vec::new();
+        let mut saved_env = Vec::new();
         for field in &env_fields {
             saved_env.push((field.to_string(), std::env::var(field).ok()));
             unsafe {
@@ -1554,6 +1826,7 @@ mod claude_settings_tests {
         let mut settings = ClaudeSettings {
             env,
             other: BTreeMap::new(),
+            ..Default::default()
         };
 
         // Switch to Env mode